@@ -301,6 +301,54 @@ pub unsafe extern "C" fn AMlistPutCounter(
     })
 }
 
+/// \memberof AMdoc
+/// \brief Puts a decimal number value into an item within a list object.
+///
+/// \param[in] doc A pointer to an `AMdoc` struct.
+/// \param[in] obj_id A pointer to an `AMobjId` struct or `AM_ROOT`.
+/// \param[in] pos The position of an item within the list object identified by
+///                \p obj_id or `SIZE_MAX` to indicate its last item if
+///                \p insert `== false` or one past its last item if
+///                \p insert `== true`.
+/// \param[in] insert A flag for inserting a new item for \p value before
+///                   \p pos instead of putting \p value into the item at
+///                   \p pos.
+/// \param[in] value A UTF-8 string view of a decimal number, e.g. "-12.30",
+///                  as an `AMbyteSpan` struct.
+/// \return A pointer to an `AMresult` struct with an `AM_VAL_TYPE_VOID` item.
+/// \pre \p doc `!= NULL`
+/// \pre `0 <=` \p pos `<= AMobjSize(`\p obj_id `)` or \p pos `== SIZE_MAX`
+/// \pre \p value.src `!= NULL`
+/// \warning The returned `AMresult` struct pointer must be passed to
+///          `AMresultFree()` in order to avoid a memory leak.
+/// \internal
+///
+/// # Safety
+/// doc must be a valid pointer to an AMdoc
+/// obj_id must be a valid pointer to an AMobjId or std::ptr::null()
+/// value.src must be a byte array of length >= value.count
+#[no_mangle]
+pub unsafe extern "C" fn AMlistPutDecimal(
+    doc: *mut AMdoc,
+    obj_id: *const AMobjId,
+    pos: usize,
+    insert: bool,
+    value: AMbyteSpan,
+) -> *mut AMresult {
+    let doc = to_doc_mut!(doc);
+    let obj_id = to_obj_id!(obj_id);
+    let (pos, insert) = adjust!(pos, insert, doc.length(obj_id));
+    let value = match to_str!(value).parse::<am::Decimal>() {
+        Ok(value) => value,
+        Err(e) => return AMresult::error(&e.to_string()).into(),
+    };
+    to_result(if insert {
+        doc.insert(obj_id, pos, value)
+    } else {
+        doc.put(obj_id, pos, value)
+    })
+}
+
 /// \memberof AMdoc
 /// \brief Puts a float value into an item within a list object.
 ///
@@ -251,6 +251,44 @@ pub unsafe extern "C" fn AMmapPutCounter(
     ))
 }
 
+/// \memberof AMdoc
+/// \brief Puts a decimal number as the value of a key in a map object.
+///
+/// \param[in] doc A pointer to an `AMdoc` struct.
+/// \param[in] obj_id A pointer to an `AMobjId` struct or `AM_ROOT`.
+/// \param[in] key A UTF-8 string view key for the map object identified by
+///                \p obj_id as an `AMbyteSpan` struct.
+/// \param[in] value A UTF-8 string view of a decimal number, e.g. "-12.30",
+///                  as an `AMbyteSpan` struct.
+/// \return A pointer to an `AMresult` struct with an `AM_VAL_TYPE_VOID` item.
+/// \pre \p doc `!= NULL`
+/// \pre \p key.src `!= NULL`
+/// \pre \p value.src `!= NULL`
+/// \warning The returned `AMresult` struct pointer must be passed to
+///          `AMresultFree()` in order to avoid a memory leak.
+/// \internal
+///
+/// # Safety
+/// doc must be a valid pointer to an AMdoc
+/// obj_id must be a valid pointer to an AMobjId or std::ptr::null()
+/// key.src must be a byte array of length >= key.count
+/// value.src must be a byte array of length >= value.count
+#[no_mangle]
+pub unsafe extern "C" fn AMmapPutDecimal(
+    doc: *mut AMdoc,
+    obj_id: *const AMobjId,
+    key: AMbyteSpan,
+    value: AMbyteSpan,
+) -> *mut AMresult {
+    let doc = to_doc_mut!(doc);
+    let key = to_str!(key);
+    let value = match to_str!(value).parse::<am::Decimal>() {
+        Ok(value) => value,
+        Err(e) => return AMresult::error(&e.to_string()).into(),
+    };
+    to_result(doc.put(to_obj_id!(obj_id), key, value))
+}
+
 /// \memberof AMdoc
 /// \brief Puts null as the value of a key in a map object.
 ///
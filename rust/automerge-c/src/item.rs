@@ -466,11 +466,20 @@ impl TryFrom<&Value> for AMunknownValue {
         use am::Value::*;
 
         if let Value(Scalar(scalar)) = value {
-            if let Unknown { bytes, type_code } = scalar.as_ref() {
-                return Ok(Self {
-                    bytes: bytes.as_slice().into(),
-                    type_code: *type_code,
-                });
+            match scalar.as_ref() {
+                Unknown { bytes, type_code } => {
+                    return Ok(Self {
+                        bytes: bytes.as_slice().into(),
+                        type_code: *type_code,
+                    });
+                }
+                Extension(e) => {
+                    return Ok(Self {
+                        bytes: e.bytes().into(),
+                        type_code: e.type_code(),
+                    });
+                }
+                _ => {}
             }
         }
         Err(InvalidValueType {
@@ -1336,6 +1345,8 @@ pub enum AMvalType {
     Uint = 1 << 19,
     /// An unknown type of value.
     Unknown = 1 << 20,
+    /// A decimal number value.
+    Decimal = 1 << 21,
     /// A void.
     Void = 1 << 0,
 }
@@ -1357,13 +1368,14 @@ impl From<&am::Value<'static>> for AMvalType {
                 Boolean(_) => Self::Bool,
                 Bytes(_) => Self::Bytes,
                 Counter(_) => Self::Counter,
+                Decimal(_) => Self::Decimal,
                 F64(_) => Self::F64,
                 Int(_) => Self::Int,
                 Null => Self::Null,
                 Str(_) => Self::Str,
                 Timestamp(_) => Self::Timestamp,
                 Uint(_) => Self::Uint,
-                Unknown { .. } => Self::Unknown,
+                Unknown { .. } | Extension(_) => Self::Unknown,
             },
         }
     }
@@ -1483,6 +1495,28 @@ pub unsafe extern "C" fn AMitemFromCounter(value: i64) -> *mut AMresult {
     AMresult::item(am::Value::counter(value).into()).into()
 }
 
+/// \memberof AMitem
+/// \brief Allocates a new item and initializes it from a decimal number value.
+///
+/// \param[in] value A UTF-8 string view of a decimal number, e.g. "-12.30",
+///                  as an `AMbyteSpan` struct.
+/// \return A pointer to an `AMresult` struct with an `AM_VAL_TYPE_DECIMAL` item.
+/// \pre \p value.src `!= NULL`
+/// \pre `0 <` \p value.count `<= sizeof(`\p value.src `)`
+/// \warning The returned `AMresult` struct pointer must be passed to
+///          `AMresultFree()` in order to avoid a memory leak.
+/// \internal
+///
+/// # Safety
+/// value.src must be a byte array of length >= value.count
+#[no_mangle]
+pub unsafe extern "C" fn AMitemFromDecimal(value: AMbyteSpan) -> *mut AMresult {
+    match to_str!(value).parse::<am::Decimal>() {
+        Ok(decimal) => AMresult::item(am::Value::decimal(decimal).into()).into(),
+        Err(e) => AMresult::error(&e.to_string()).into(),
+    }
+}
+
 /// \memberof AMitem
 /// \brief Allocates a new item and initializes it from a float value.
 ///
@@ -1823,6 +1857,31 @@ pub unsafe extern "C" fn AMitemToChangeHash(item: *const AMitem, value: *mut AMb
     false
 }
 
+/// \memberof AMitem
+/// \brief Gets the decimal number value of an item as a string.
+///
+/// \param[in] item A pointer to an `AMitem` struct.
+/// \return A pointer to an `AMresult` struct with an `AM_VAL_TYPE_STR` item
+///         if `AMitemValType(`\p item `) == AM_VAL_TYPE_DECIMAL`.
+/// \pre \p item `!= NULL`
+/// \warning The returned `AMresult` struct pointer must be passed to
+///          `AMresultFree()` in order to avoid a memory leak.
+/// \internal
+///
+/// # Safety
+/// item must be a valid pointer to an AMitem
+#[no_mangle]
+pub unsafe extern "C" fn AMitemDecimalStr(item: *const AMitem) -> *mut AMresult {
+    if let Some(item) = item.as_ref() {
+        if let Some(Value::Value(am::Value::Scalar(scalar))) = &item.as_ref().value {
+            if let am::ScalarValue::Decimal(decimal) = scalar.as_ref() {
+                return AMresult::item(decimal.to_string().into()).into();
+            }
+        }
+    }
+    AMresult::error("Invalid `AMitem*`, expected a decimal").into()
+}
+
 /// \memberof AMitem
 /// \brief Gets the CRDT counter value of an item.
 ///
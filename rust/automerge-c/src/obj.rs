@@ -176,6 +176,10 @@ pub enum AMobjType {
     Map,
     /// A list of Unicode graphemes.
     Text,
+    /// An add-wins set of scalar values.
+    Set,
+    /// A key-value map whose keys are kept in an order.
+    OrderedMap,
 }
 
 impl Default for AMobjType {
@@ -192,6 +196,8 @@ impl From<&am::ObjType> for AMobjType {
             List => Self::List,
             Map | Table => Self::Map,
             Text => Self::Text,
+            Set => Self::Set,
+            OrderedMap => Self::OrderedMap,
         }
     }
 }
@@ -207,6 +213,8 @@ impl TryFrom<&AMobjType> for am::ObjType {
             List => Ok(Self::List),
             Map => Ok(Self::Map),
             Text => Ok(Self::Text),
+            Set => Ok(Self::Set),
+            OrderedMap => Ok(Self::OrderedMap),
             _ => Err(InvalidValueType {
                 expected: type_name::<Self>().to_string(),
                 unexpected: type_name::<u8>().to_string(),
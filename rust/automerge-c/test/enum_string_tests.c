@@ -45,6 +45,8 @@ static void test_AMobjTypeToString(void** state) {
     assert_to_string(AMobjTypeToString, AM_OBJ_TYPE_LIST);
     assert_to_string(AMobjTypeToString, AM_OBJ_TYPE_MAP);
     assert_to_string(AMobjTypeToString, AM_OBJ_TYPE_TEXT);
+    assert_to_string(AMobjTypeToString, AM_OBJ_TYPE_SET);
+    assert_to_string(AMobjTypeToString, AM_OBJ_TYPE_ORDERED_MAP);
     /* Zero tag */
     assert_string_equal(AMobjTypeToString(0), "AM_OBJ_TYPE_DEFAULT");
     /* Invalid tag */
@@ -56,6 +58,8 @@ static void test_AMobjTypeFromString(void** state) {
     assert_from_string(AMobjTypeFromString, AMobjType, AM_OBJ_TYPE_LIST);
     assert_from_string(AMobjTypeFromString, AMobjType, AM_OBJ_TYPE_MAP);
     assert_from_string(AMobjTypeFromString, AMobjType, AM_OBJ_TYPE_TEXT);
+    assert_from_string(AMobjTypeFromString, AMobjType, AM_OBJ_TYPE_SET);
+    assert_from_string(AMobjTypeFromString, AMobjType, AM_OBJ_TYPE_ORDERED_MAP);
     /* Invalid tag */
     AMobjType out = -1;
     assert_false(AMobjTypeFromString(&out, "???"));
@@ -89,6 +93,7 @@ static void test_AMvalTypeToString(void** state) {
     assert_to_string(AMvalTypeToString, AM_VAL_TYPE_CHANGE);
     assert_to_string(AMvalTypeToString, AM_VAL_TYPE_CHANGE_HASH);
     assert_to_string(AMvalTypeToString, AM_VAL_TYPE_COUNTER);
+    assert_to_string(AMvalTypeToString, AM_VAL_TYPE_DECIMAL);
     assert_to_string(AMvalTypeToString, AM_VAL_TYPE_DEFAULT);
     assert_to_string(AMvalTypeToString, AM_VAL_TYPE_DOC);
     assert_to_string(AMvalTypeToString, AM_VAL_TYPE_F64);
@@ -116,6 +121,7 @@ static void test_AMvalTypeFromString(void** state) {
     assert_from_string(AMvalTypeFromString, AMvalType, AM_VAL_TYPE_CHANGE);
     assert_from_string(AMvalTypeFromString, AMvalType, AM_VAL_TYPE_CHANGE_HASH);
     assert_from_string(AMvalTypeFromString, AMvalType, AM_VAL_TYPE_COUNTER);
+    assert_from_string(AMvalTypeFromString, AMvalType, AM_VAL_TYPE_DECIMAL);
     assert_from_string(AMvalTypeFromString, AMvalType, AM_VAL_TYPE_DEFAULT);
     assert_from_string(AMvalTypeFromString, AMvalType, AM_VAL_TYPE_DOC);
     assert_from_string(AMvalTypeFromString, AMvalType, AM_VAL_TYPE_F64);
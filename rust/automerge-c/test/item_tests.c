@@ -5,6 +5,7 @@
 #include <stdint.h>
 #include <stdio.h>
 #include <stdlib.h>
+#include <string.h>
 
 /* third-party */
 #include <cmocka.h>
@@ -15,6 +16,25 @@
 #include "cmocka_utils.h"
 #include "doc_state.h"
 
+static void test_AMitemDecimalStr(void** state) {
+    DocState* doc_state = *state;
+    AMstack** stack_ptr = &doc_state->base_state->stack;
+
+    AMstackItem(NULL, AMmapPutDecimal(doc_state->doc, AM_ROOT, AMstr("decimal"), AMstr("-12.30")), cmocka_cb,
+                AMexpect(AM_VAL_TYPE_VOID));
+    AMitem* const item = AMstackItem(stack_ptr, AMmapGet(doc_state->doc, AM_ROOT, AMstr("decimal"), NULL), cmocka_cb,
+                                     AMexpect(AM_VAL_TYPE_DECIMAL));
+    AMbyteSpan str;
+    assert_true(AMitemToStr(AMstackItem(stack_ptr, AMitemDecimalStr(item), cmocka_cb, AMexpect(AM_VAL_TYPE_STR)),
+                            &str));
+    assert_int_equal(str.count, strlen("-12.30"));
+    assert_memory_equal(str.src, "-12.30", str.count);
+    /* A string which isn't a decimal number is rejected. */
+    AMresult* result = AMmapPutDecimal(doc_state->doc, AM_ROOT, AMstr("decimal"), AMstr("1e5"));
+    assert_int_equal(AMresultStatus(result), AM_STATUS_ERROR);
+    AMresultFree(result);
+}
+
 static void test_AMitemResult(void** state) {
     enum { ITEM_COUNT = 1000 };
 
@@ -290,6 +310,7 @@ static void test_AMitemToUint_null_out_arg(void** state) {
 
 int run_item_tests(void) {
     const struct CMUnitTest tests[] = {
+        cmocka_unit_test(test_AMitemDecimalStr),
         cmocka_unit_test(test_AMitemResult),
         cmocka_unit_test(test_AMitemToActorId_null_out_arg),
         cmocka_unit_test(test_AMitemToBool_null_out_arg),
@@ -229,8 +229,13 @@ pub enum OrdScalarValue {
     Counter(i64),
     Timestamp(i64),
     Boolean(bool),
+    /// A decimal, as its string form
+    Decimal(String),
     Null,
-    Unknown { type_code: u8, bytes: Vec<u8> },
+    Unknown {
+        type_code: u8,
+        bytes: Vec<u8>,
+    },
 }
 
 impl From<automerge::ScalarValue> for OrdScalarValue {
@@ -244,10 +249,15 @@ impl From<automerge::ScalarValue> for OrdScalarValue {
             automerge::ScalarValue::Counter(c) => OrdScalarValue::Counter(c.into()),
             automerge::ScalarValue::Timestamp(v) => OrdScalarValue::Timestamp(v),
             automerge::ScalarValue::Boolean(v) => OrdScalarValue::Boolean(v),
+            automerge::ScalarValue::Decimal(v) => OrdScalarValue::Decimal(v.to_string()),
             automerge::ScalarValue::Null => OrdScalarValue::Null,
             automerge::ScalarValue::Unknown { type_code, bytes } => {
                 OrdScalarValue::Unknown { type_code, bytes }
             }
+            automerge::ScalarValue::Extension(e) => OrdScalarValue::Unknown {
+                type_code: e.type_code(),
+                bytes: e.bytes().to_vec(),
+            },
         }
     }
 }
@@ -263,6 +273,9 @@ impl From<&OrdScalarValue> for automerge::ScalarValue {
             OrdScalarValue::Counter(v) => automerge::ScalarValue::counter(*v),
             OrdScalarValue::Timestamp(v) => automerge::ScalarValue::Timestamp(*v),
             OrdScalarValue::Boolean(v) => automerge::ScalarValue::Boolean(*v),
+            OrdScalarValue::Decimal(v) => {
+                automerge::ScalarValue::Decimal(v.parse().expect("a valid decimal"))
+            }
             OrdScalarValue::Null => automerge::ScalarValue::Null,
             OrdScalarValue::Unknown { type_code, bytes } => automerge::ScalarValue::Unknown {
                 type_code: *type_code,
@@ -290,6 +303,9 @@ impl serde::Serialize for OrdScalarValue {
                 serializer.serialize_str(format!("Timestamp({})", v).as_str())
             }
             OrdScalarValue::Boolean(v) => serializer.serialize_bool(*v),
+            OrdScalarValue::Decimal(v) => {
+                serializer.serialize_str(format!("Decimal({})", v).as_str())
+            }
             OrdScalarValue::Null => serializer.serialize_none(),
             OrdScalarValue::Unknown { type_code, .. } => serializer
                 .serialize_str(format!("An unknown type with code {}", type_code).as_str()),
@@ -346,7 +362,7 @@ pub fn realize_obj<R: ReadDoc>(
     objtype: automerge::ObjType,
 ) -> RealizedObject {
     match objtype {
-        automerge::ObjType::Map | automerge::ObjType::Table => {
+        automerge::ObjType::Map | automerge::ObjType::Table | automerge::ObjType::OrderedMap => {
             let mut result = BTreeMap::new();
             for key in doc.keys(obj_id) {
                 result.insert(key.clone(), realize_values(doc, obj_id, key));
@@ -361,6 +377,11 @@ pub fn realize_obj<R: ReadDoc>(
             }
             RealizedObject::Sequence(result)
         }
+        automerge::ObjType::Set => RealizedObject::Sequence(
+            doc.set_values(obj_id)
+                .map(|v| BTreeSet::from([RealizedObject::Value(OrdScalarValue::from(v))]))
+                .collect(),
+        ),
     }
 }
 
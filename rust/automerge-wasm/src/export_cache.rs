@@ -1,5 +1,5 @@
 use crate::interop::error;
-use crate::interop::{ExternalTypeConstructor, SAFE_INT, SAFE_UINT};
+use crate::interop::{move_key_to_end, ExternalTypeConstructor, SAFE_INT, SAFE_UINT};
 use crate::value::Datatype;
 use crate::Automerge;
 use automerge as am;
@@ -27,6 +27,8 @@ pub(crate) struct ExportCache<'a> {
     pub(crate) objs: HashMap<ObjId, CachedObject, FxBuildHasher>,
     obj_cache: HashMap<ObjId, (Object, JsValue)>,
     to_freeze: Vec<Object>,
+    // the ordered maps which have been made, whose keys are put in order once they are filled in
+    ordered_maps: Vec<(ObjId, Object)>,
     datatypes: HashMap<Datatype, JsString, FxBuildHasher>,
     keys: HashMap<Cow<'a, str>, JsString>,
     definition: Object,
@@ -36,6 +38,8 @@ pub(crate) struct ExportCache<'a> {
     raw_obj_sym: Symbol,
     raw_data_sym: Symbol,
     doc: &'a Automerge,
+    /// Objects which have been detached by a patch, until they are attached again
+    pub(crate) detached: HashMap<ObjId, JsValue>,
 }
 
 impl<'a> ExportCache<'a> {
@@ -57,6 +61,7 @@ impl<'a> ExportCache<'a> {
             objs: HashMap::default(),
             obj_cache: HashMap::default(),
             to_freeze: Vec::new(),
+            ordered_maps: Vec::new(),
             datatypes: HashMap::default(),
             keys: HashMap::default(),
             definition,
@@ -66,6 +71,7 @@ impl<'a> ExportCache<'a> {
             value_key,
             raw_data_sym,
             doc,
+            detached: HashMap::new(),
         })
     }
 
@@ -78,7 +84,10 @@ impl<'a> ExportCache<'a> {
         meta: &JsValue,
     ) -> Result<JsValue, error::Export> {
         Ok(match value {
-            am::ValueRef::Object(ObjType::Map) => self.make_map(obj, meta)?.into(),
+            am::ValueRef::Object(ObjType::Map) => self.make_map(obj, Datatype::Map, meta)?.into(),
+            am::ValueRef::Object(ObjType::OrderedMap) => {
+                self.make_map(obj, Datatype::OrderedMap, meta)?.into()
+            }
             am::ValueRef::Object(ObjType::Text) => {
                 self.obj_cache
                     .insert(obj.clone(), (parent.clone(), prop.clone()));
@@ -96,8 +105,10 @@ impl<'a> ExportCache<'a> {
         meta: &JsValue,
     ) -> Result<Option<Object>, error::Export> {
         match d {
-            Datatype::Map => Ok(Some(self.make_map(obj.clone(), meta)?)),
-            Datatype::List => Ok(Some(self.make_list(obj.clone(), d, meta)?)),
+            Datatype::Map | Datatype::OrderedMap => {
+                Ok(Some(self.make_map(obj.clone(), d, meta)?))
+            }
+            Datatype::List | Datatype::Set => Ok(Some(self.make_list(obj.clone(), d, meta)?)),
             _ => Ok(None),
         }
     }
@@ -114,11 +125,19 @@ impl<'a> ExportCache<'a> {
         self.wrap_object(child.into(), &obj, datatype, meta)
     }
 
-    fn make_map(&mut self, obj: ObjId, meta: &JsValue) -> Result<Object, error::Export> {
+    fn make_map(
+        &mut self,
+        obj: ObjId,
+        datatype: Datatype,
+        meta: &JsValue,
+    ) -> Result<Object, error::Export> {
         let child = Object::new();
         self.obj_cache
             .insert(obj.clone(), (child.clone(), JsValue::null()));
-        self.wrap_object(child, &obj, Datatype::Map, meta)
+        if datatype == Datatype::OrderedMap {
+            self.ordered_maps.push((obj.clone(), child.clone()));
+        }
+        self.wrap_object(child, &obj, datatype, meta)
     }
 
     #[inline(never)]
@@ -160,6 +179,13 @@ impl<'a> ExportCache<'a> {
                 DocItem::Text(span) => {
                     buffer.push_str(span.as_str());
                 }
+                DocItem::Map(map) if Array::is_array(&o) => {
+                    // a set, which is exported as an array of its values
+                    let prop = JsValue::from_f64(index as f64);
+                    let value = self.make_value_ref(&o, &prop, map.id(), map.value, meta)?;
+                    _set(&o, &prop, &value)?;
+                    index += 1;
+                }
                 DocItem::Map(map) => {
                     let prop = self.ensure_key(map.key.clone());
                     let value = self.make_value_ref(&o, &prop, map.id(), map.value, meta)?;
@@ -176,6 +202,16 @@ impl<'a> ExportCache<'a> {
         if !buffer.is_empty() {
             _set(&o, &parent_prop, &JsValue::from_str(&buffer))?;
         }
+        // the keys were filled in the order they are stored in, not the order of the map
+        for (obj, map) in std::mem::take(&mut self.ordered_maps) {
+            let keys = match heads {
+                Some(heads) => self.doc.doc.keys_at(&obj, heads),
+                None => self.doc.doc.keys(&obj),
+            };
+            for key in keys {
+                move_key_to_end(&map, &key)?;
+            }
+        }
         for o in &self.to_freeze {
             Object::freeze(o);
         }
@@ -205,6 +241,7 @@ impl<'a> ExportCache<'a> {
                 js_sys::Date::new(&(*v as f64).into()).into(),
             ),
             am::ScalarValueRef::Boolean(v) => (Datatype::Boolean, (*v).into()),
+            am::ScalarValueRef::Decimal(v) => (Datatype::Decimal, v.to_string().into()),
             am::ScalarValueRef::Null => (Datatype::Null, JsValue::null()),
             am::ScalarValueRef::Unknown { bytes, type_code } => (
                 Datatype::Unknown(*type_code),
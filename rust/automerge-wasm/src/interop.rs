@@ -860,6 +860,18 @@ pub(crate) fn js_set<V: Into<JsValue>, S: std::fmt::Debug + Into<JsValue>>(
     Reflect::set(obj, &property, &val).map_err(|error| error::SetProp { property, error })
 }
 
+/// Move `key` to the end of the properties of `map`, which is how the keys of an ordered map are
+/// put in order
+pub(crate) fn move_key_to_end(map: &Object, key: &str) -> Result<(), error::Export> {
+    let value = js_get(map, key)?.0;
+    Reflect::delete_property(map, &key.into()).map_err(|e| error::Export::Delete {
+        prop: key.to_string(),
+        err: e,
+    })?;
+    js_set(map, key, &value)?;
+    Ok(())
+}
+
 pub(crate) fn to_prop(p: JsValue) -> Result<Prop, error::InvalidProp> {
     if let Some(s) = p.as_string() {
         Ok(Prop::Map(s))
@@ -1097,6 +1109,23 @@ fn jsvalue_to_bytes(value: &JsValue) -> Result<Vec<u8>, error::ImportValue> {
     }
 }
 
+fn jsvalue_to_decimal(value: &JsValue) -> Result<am::Decimal, error::ImportValue> {
+    if let Some(s) = value.as_string() {
+        s.parse()
+            .map_err(|_| error::ImportValue::Invalid(value.clone()))
+    } else if BigInt::is_type_of(value) {
+        let s = String::from(BigInt::from(value.clone()).to_string(10)?);
+        s.parse()
+            .map_err(|_| error::ImportValue::Invalid(value.clone()))
+    } else if let Some(n) = value.as_f64().filter(|n| n.is_finite()) {
+        n.to_string()
+            .parse()
+            .map_err(|_| error::ImportValue::Invalid(value.clone()))
+    } else {
+        Err(error::ImportValue::Invalid(value.clone()))
+    }
+}
+
 fn bigint_to_u64(value: BigInt) -> Result<u64, error::ImportValue> {
     let max = std::sync::LazyLock::new(|| BigInt::from(u64::MAX));
     let min = std::sync::LazyLock::new(|| BigInt::from(u64::MIN));
@@ -1124,6 +1153,7 @@ pub(crate) fn import_scalar(
         Some(Datatype::Bytes) => Some(am::ScalarValue::Bytes(jsvalue_to_bytes(value)?)),
         Some(Datatype::Counter) => Some(am::ScalarValue::counter(jsvalue_to_i64(value)?)),
         Some(Datatype::Timestamp) => Some(am::ScalarValue::Timestamp(jsvalue_to_i64(value)?)),
+        Some(Datatype::Decimal) => Some(am::ScalarValue::Decimal(jsvalue_to_decimal(value)?)),
         Some(Datatype::Null) => Some(am::ScalarValue::Null),
         Some(_) => return Err(error::ImportValue::ValueNotPrimitive), // Map, Text, List ...
         None => {
@@ -1292,7 +1322,10 @@ impl Automerge {
         } else {
             value.clone()
         };
-        if matches!(datatype, Datatype::Map | Datatype::List) {
+        if matches!(
+            datatype,
+            Datatype::Map | Datatype::List | Datatype::Set | Datatype::OrderedMap
+        ) {
             cache.set_raw_object(&value, &JsValue::from(&id.to_string()))?;
         }
         cache.set_datatype(&value, &datatype.into())?;
@@ -1342,9 +1375,68 @@ impl Automerge {
             PatchAction::DeleteMap { .. } => Err(error::ApplyPatch::DeleteKeyFromSeq),
             PatchAction::PutMap { .. } => Err(error::ApplyPatch::PutKeyInSeq),
             PatchAction::SpliceText { .. } => Err(error::ApplyPatch::SpliceTextInSeq),
+            PatchAction::Move { from, to } => {
+                let value = js_get(array, *from as f64)?.0;
+                let splice = js_get(array, "splice")?
+                    .0
+                    .dyn_into::<Function>()
+                    .map_err(error::Export::GetSplice)?;
+                let remove = Array::of2(&(*from as u32).into(), &1.into());
+                Reflect::apply(&splice, array, &remove).map_err(error::Export::CallSplice)?;
+                let insert = Array::of3(&(*to as u32).into(), &0.into(), &value);
+                Reflect::apply(&splice, array, &insert).map_err(error::Export::CallSplice)?;
+                Ok(())
+            }
             PatchAction::Mark { .. } => Ok(()),
             PatchAction::Conflict { .. } => Ok(()),
+            // moved objects are stashed in the cache, see `apply_tree_move`
+            PatchAction::Detach { .. } | PatchAction::Attach { .. } => Ok(()),
+            PatchAction::MoveKey { .. } => Err(error::ApplyPatch::MoveKeyInSeq),
+            PatchAction::SetAdd { value } => {
+                if self.set_position(array, value, cache)?.is_none() {
+                    array.push(&self.export_value(alloc_scalar(value), cache)?);
+                }
+                Ok(())
+            }
+            PatchAction::SetRemove { value } => {
+                if let Some(index) = self.set_position(array, value, cache)? {
+                    let splice = js_get(array, "splice")?
+                        .0
+                        .dyn_into::<Function>()
+                        .map_err(error::Export::GetSplice)?;
+                    let remove = Array::of2(&index.into(), &1.into());
+                    Reflect::apply(&splice, array, &remove).map_err(error::Export::CallSplice)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// The index of `value` in `array`, which is a set exported as an array of its values
+    fn set_position(
+        &self,
+        array: &Array,
+        value: &am::ScalarValue,
+        cache: &ExportCache<'_>,
+    ) -> Result<Option<u32>, error::Export> {
+        let (_, raw) = alloc_scalar(value);
+        for (index, elem) in array.iter().enumerate() {
+            let elem = self.unwrap_scalar(elem, cache)?;
+            let same = match (
+                elem.dyn_ref::<js_sys::Date>(),
+                raw.dyn_ref::<js_sys::Date>(),
+            ) {
+                (Some(a), Some(b)) => a.get_time() == b.get_time(),
+                _ => match (elem.dyn_ref::<Uint8Array>(), raw.dyn_ref::<Uint8Array>()) {
+                    (Some(a), Some(b)) => a.to_vec() == b.to_vec(),
+                    _ => Object::is(&elem, &raw),
+                },
+            };
+            if same {
+                return Ok(Some(index as u32));
+            }
         }
+        Ok(None)
     }
 
     pub(crate) fn apply_patch_to_map(
@@ -1389,9 +1481,73 @@ impl Automerge {
             PatchAction::SpliceText { .. } => Err(error::ApplyPatch::SpliceTextInMap),
             PatchAction::PutSeq { .. } => Err(error::ApplyPatch::PutIdxInMap),
             PatchAction::Mark { .. } => Err(error::ApplyPatch::MarkInMap),
+            PatchAction::Move { .. } => Err(error::ApplyPatch::MoveInMap),
+            PatchAction::Detach { .. } | PatchAction::Attach { .. } => Ok(()),
+            PatchAction::SetAdd { .. } | PatchAction::SetRemove { .. } => {
+                Err(error::ApplyPatch::SetInMap)
+            }
+            // every key of the map is moved, in order, so moving each one to the end leaves
+            // them all in the right place
+            PatchAction::MoveKey { key, .. } => Ok(move_key_to_end(map, key)?),
         }
     }
 
+    /// Detach or attach the object moved by `patch`, returning `false` for any other patch
+    ///
+    /// A detached object is kept in `cache` until the patch which attaches it somewhere else.
+    fn apply_tree_move(
+        &self,
+        obj: &Object,
+        patch: &Patch,
+        cache: &mut ExportCache<'_>,
+    ) -> Result<bool, error::ApplyPatch> {
+        let splice = || {
+            js_get(obj, "splice")?
+                .0
+                .dyn_into::<Function>()
+                .map_err(error::Export::GetSplice)
+        };
+        match &patch.action {
+            PatchAction::Detach { prop, moved } => {
+                let value = js_get(obj, prop_to_js(prop))?.0;
+                cache.detached.insert(moved.clone(), value);
+                match prop {
+                    Prop::Seq(index) => {
+                        let remove = Array::of2(&(*index as u32).into(), &1.into());
+                        Reflect::apply(&splice()?, obj, &remove)
+                            .map_err(error::Export::CallSplice)?;
+                    }
+                    Prop::Map(key) => {
+                        Reflect::delete_property(obj, &key.into()).map_err(|e| {
+                            error::Export::Delete {
+                                prop: key.to_string(),
+                                err: e,
+                            }
+                        })?;
+                    }
+                }
+            }
+            PatchAction::Attach { prop, moved, .. } => {
+                let value = cache
+                    .detached
+                    .remove(moved)
+                    .ok_or_else(|| error::ApplyPatch::NotDetached(moved.to_string()))?;
+                match prop {
+                    Prop::Seq(index) => {
+                        let insert = Array::of3(&(*index as u32).into(), &0.into(), &value);
+                        Reflect::apply(&splice()?, obj, &insert)
+                            .map_err(error::Export::CallSplice)?;
+                    }
+                    Prop::Map(key) => {
+                        js_set(obj, key, &value)?;
+                    }
+                }
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
     pub(crate) fn apply_patch(
         &self,
         root: Object,
@@ -1424,6 +1580,9 @@ impl Automerge {
         if current.id != patch.obj {
             return Ok(root);
         }
+        if self.apply_tree_move(&current.inner, patch, cache)? {
+            return Ok(root_cache.outer);
+        }
         if current.inner.is_array() {
             let inner_array = current
                 .inner
@@ -1604,6 +1763,8 @@ pub(crate) fn alloc(value: &Value<'_>) -> (Datatype, JsValue) {
             ObjType::Table => (Datatype::Table, Object::new().into()),
             ObjType::List => (Datatype::List, Array::new().into()),
             ObjType::Text => (Datatype::Text, "".into()),
+            ObjType::Set => (Datatype::Set, Array::new().into()),
+            ObjType::OrderedMap => (Datatype::OrderedMap, Object::new().into()),
         },
         am::Value::Scalar(s) => alloc_scalar(s.as_ref()),
     }
@@ -1635,11 +1796,16 @@ pub(crate) fn alloc_scalar(value: &am::ScalarValue) -> (Datatype, JsValue) {
             js_sys::Date::new(&(*v as f64).into()).into(),
         ),
         am::ScalarValue::Boolean(v) => (Datatype::Boolean, (*v).into()),
+        am::ScalarValue::Decimal(v) => (Datatype::Decimal, v.to_string().into()),
         am::ScalarValue::Null => (Datatype::Null, JsValue::null()),
         am::ScalarValue::Unknown { bytes, type_code } => (
             Datatype::Unknown(*type_code),
             Uint8Array::from(bytes.as_slice()).into(),
         ),
+        am::ScalarValue::Extension(e) => (
+            Datatype::Unknown(e.type_code()),
+            Uint8Array::from(e.bytes()).into(),
+        ),
     }
 }
 
@@ -1726,6 +1892,22 @@ pub(super) fn export_hydrate(
             list.into()
         }
         am::hydrate::Value::Text(text) => text.to_string().into(),
+        am::hydrate::Value::OrderedMap(h_map) => {
+            let map = Object::new();
+            for (k, v) in h_map.iter() {
+                let val = export_hydrate(doc, cache, v.value.clone());
+                Reflect::set(&map, &k.into(), &val).unwrap();
+            }
+            map.into()
+        }
+        am::hydrate::Value::Set(h_set) => {
+            let set = Array::new();
+            for v in h_set.iter() {
+                let (datatype, val) = alloc_scalar(v);
+                set.push(&doc.export_value((datatype, val), cache).unwrap());
+            }
+            set.into()
+        }
     }
 }
 
@@ -1880,6 +2062,61 @@ fn export_patch(
             js_set(&result, "path", export_path(path, &prop))?;
             Ok(result.into())
         }
+        PatchAction::Move { from, to } => {
+            js_set(&result, "action", "move")?;
+            js_set(&result, "path", export_path(path, &Prop::Seq(from)))?;
+            js_set(&result, "to", to)?;
+            Ok(result.into())
+        }
+        PatchAction::MoveKey { key, index } => {
+            js_set(&result, "action", "moveKey")?;
+            js_set(&result, "path", export_path(path, &Prop::Map(key)))?;
+            js_set(&result, "index", index)?;
+            Ok(result.into())
+        }
+        PatchAction::Detach { prop, moved } => {
+            js_set(&result, "action", "detach")?;
+            js_set(&result, "path", export_path(path, &prop))?;
+            js_set(&result, "moved", moved.to_string())?;
+            Ok(result.into())
+        }
+        PatchAction::Attach {
+            prop,
+            moved,
+            conflict,
+        } => {
+            js_set(&result, "action", "attach")?;
+            js_set(&result, "path", export_path(path, &prop))?;
+            js_set(&result, "moved", moved.to_string())?;
+            if conflict {
+                js_set(&result, "conflict", true)?;
+            }
+            Ok(result.into())
+        }
+        PatchAction::SetAdd { value } => {
+            js_set(&result, "action", "setAdd")?;
+            js_set(&result, "path", export_just_path(path))?;
+            js_set(&result, "value", export_set_value(externals, &value)?)?;
+            Ok(result.into())
+        }
+        PatchAction::SetRemove { value } => {
+            js_set(&result, "action", "setRemove")?;
+            js_set(&result, "path", export_just_path(path))?;
+            js_set(&result, "value", export_set_value(externals, &value)?)?;
+            Ok(result.into())
+        }
+    }
+}
+
+fn export_set_value(
+    externals: &HashMap<Datatype, ExternalTypeConstructor>,
+    value: &am::ScalarValue,
+) -> Result<JsValue, error::Export> {
+    let (datatype, value) = alloc_scalar(value);
+    if let Some(external_type) = externals.get(&datatype) {
+        external_type.construct(&value, datatype)
+    } else {
+        Ok(value)
     }
 }
 
@@ -1939,6 +2176,17 @@ pub(super) fn js_val_to_hydrate(
                     .collect::<Result<_, _>>()?;
                 Ok(am::hydrate::Value::Map(obj.into()))
             }
+            am::ObjType::OrderedMap => {
+                let obj = js_obj
+                    .subvals()
+                    .filter_map(|(p, v)| match p.as_ref() {
+                        Prop::Map(key) => Some((key.to_string(), v)),
+                        _ => None,
+                    })
+                    .map(|(k, v)| js_val_to_hydrate(doc, v).map(|v| (k, v)))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(am::hydrate::Value::OrderedMap(obj.into_iter().collect()))
+            }
             am::ObjType::List => {
                 let obj: Vec<am::hydrate::Value> = js_obj
                     .subvals()
@@ -1946,6 +2194,13 @@ pub(super) fn js_val_to_hydrate(
                     .collect::<Result<_, _>>()?;
                 Ok(am::hydrate::Value::List(obj.into()))
             }
+            am::ObjType::Set => {
+                let set = js_obj
+                    .subvals()
+                    .map(|(_, v)| import_scalar(&v, None))
+                    .collect::<Result<_, _>>()?;
+                Ok(am::hydrate::Value::Set(set))
+            }
             am::ObjType::Text => {
                 let Some(obj) = js_obj.text() else {
                     return Err(error::JsValToHydrate::InvalidText);
@@ -2163,6 +2418,14 @@ pub(crate) mod error {
         PutIdxInMap,
         #[error("cannot mark a span in a map")]
         MarkInMap,
+        #[error("cannot move an element in a map")]
+        MoveInMap,
+        #[error("cannot add to or remove from a set in a map")]
+        SetInMap,
+        #[error("cannot move a key in a sequence")]
+        MoveKeyInSeq,
+        #[error("cannot attach object {0}, which has not been detached")]
+        NotDetached(String),
         #[error("cannot have blocks in a map")]
         BlockInMap,
         #[error("array patch applied to non array")]
@@ -86,13 +86,16 @@ export type FullValue =
   | ["f64", number]
   | ["boolean", boolean]
   | ["timestamp", Date]
+  | ["decimal", string]
   | ["counter", number]
   | ["bytes", Uint8Array]
   | ["null", null]
   | ["map", ObjID]
   | ["list", ObjID]
   | ["text", ObjID]
-  | ["table", ObjID];
+  | ["table", ObjID]
+  | ["set", ObjID]
+  | ["orderedMap", ObjID];
 
 export type Cursor = string;
 export type CursorPosition = number | "start" | "end";
@@ -105,19 +108,24 @@ export type FullValueWithId =
   | ["f64", number, ObjID]
   | ["boolean", boolean, ObjID]
   | ["timestamp", Date, ObjID]
+  | ["decimal", string, ObjID]
   | ["counter", number, ObjID]
   | ["bytes", Uint8Array, ObjID]
   | ["null", null, ObjID]
   | ["map", ObjID]
   | ["list", ObjID]
   | ["text", ObjID]
-  | ["table", ObjID];
+  | ["table", ObjID]
+  | ["set", ObjID]
+  | ["orderedMap", ObjID];
 
 export enum ObjTypeName {
   list = "list",
   map = "map",
   table = "table",
   text = "text",
+  set = "set",
+  orderedMap = "orderedMap",
 }
 
 export type Datatype =
@@ -128,11 +136,14 @@ export type Datatype =
   | "f64"
   | "null"
   | "timestamp"
+  | "decimal"
   | "counter"
   | "bytes"
   | "map"
   | "text"
-  | "list";
+  | "list"
+  | "set"
+  | "orderedMap";
 
 export type SyncHave = {
   lastSync: Heads;
@@ -215,7 +226,13 @@ export type Patch =
   | InsertPatch
   | MarkPatch
   | UnmarkPatch
-  | ConflictPatch;
+  | ConflictPatch
+  | MovePatch
+  | DetachPatch
+  | AttachPatch
+  | SetAddPatch
+  | SetRemovePatch
+  | MoveKeyPatch;
 
 export type PutPatch = {
   action: "put";
@@ -280,6 +297,43 @@ export type ConflictPatch = {
   path: Prop[];
 };
 
+export type MovePatch = {
+  action: "move";
+  path: Prop[];
+  to: number;
+};
+
+export type DetachPatch = {
+  action: "detach";
+  path: Prop[];
+  moved: ObjID;
+};
+
+export type AttachPatch = {
+  action: "attach";
+  path: Prop[];
+  moved: ObjID;
+  conflict?: boolean;
+};
+
+export type SetAddPatch = {
+  action: "setAdd";
+  path: Prop[];
+  value: ScalarValue;
+};
+
+export type SetRemovePatch = {
+  action: "setRemove";
+  path: Prop[];
+  value: ScalarValue;
+};
+
+export type MoveKeyPatch = {
+  action: "moveKey";
+  path: Prop[];
+  index: number;
+};
+
 export type Mark = {
   name: string;
   value: ScalarValue;
@@ -1299,6 +1353,32 @@ impl Automerge {
         Ok(())
     }
 
+    #[wasm_bindgen(js_name = moveElement)]
+    pub fn move_element(
+        &mut self,
+        #[wasm_bindgen(unchecked_param_type = "ObjID")] obj: JsValue,
+        from: f64,
+        to: f64,
+    ) -> Result<(), error::Get> {
+        let (obj, _) = self.import(obj)?;
+        self.doc.move_element(&obj, from as usize, to as usize)?;
+        Ok(())
+    }
+
+    #[wasm_bindgen(js_name = moveObject)]
+    pub fn move_object(
+        &mut self,
+        #[wasm_bindgen(unchecked_param_type = "ObjID")] obj: JsValue,
+        #[wasm_bindgen(unchecked_param_type = "ObjID")] parent: JsValue,
+        #[wasm_bindgen(unchecked_param_type = "Prop")] prop: JsValue,
+    ) -> Result<(), error::Get> {
+        let (obj, _) = self.import(obj)?;
+        let (parent, _) = self.import(parent)?;
+        let prop = to_prop(prop)?;
+        self.doc.move_object(&obj, &parent, prop)?;
+        Ok(())
+    }
+
     pub fn save(&mut self) -> Uint8Array {
         Uint8Array::from(self.doc.save().as_slice())
     }
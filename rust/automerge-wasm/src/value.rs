@@ -7,6 +7,8 @@ pub(crate) enum Datatype {
     Table,
     List,
     Text,
+    Set,
+    OrderedMap,
     Bytes,
     Str,
     Int,
@@ -15,13 +17,17 @@ pub(crate) enum Datatype {
     Counter,
     Timestamp,
     Boolean,
+    Decimal,
     Null,
     Unknown(u8),
 }
 
 impl Datatype {
     pub(crate) fn is_scalar(&self) -> bool {
-        !matches!(self, Self::Map | Self::Table | Self::List | Self::Text)
+        !matches!(
+            self,
+            Self::Map | Self::Table | Self::List | Self::Text | Self::Set | Self::OrderedMap
+        )
     }
 }
 
@@ -38,6 +44,8 @@ impl From<ObjType> for Datatype {
             ObjType::List => Self::List,
             ObjType::Table => Self::Table,
             ObjType::Text => Self::Text,
+            ObjType::Set => Self::Set,
+            ObjType::OrderedMap => Self::OrderedMap,
         }
     }
 }
@@ -59,8 +67,10 @@ impl From<&ScalarValue> for Datatype {
             ScalarValue::Counter(_) => Self::Counter,
             ScalarValue::Timestamp(_) => Self::Timestamp,
             ScalarValue::Boolean(_) => Self::Boolean,
+            ScalarValue::Decimal(_) => Self::Decimal,
             ScalarValue::Null => Self::Null,
             ScalarValue::Unknown { type_code, .. } => Self::Unknown(*type_code),
+            ScalarValue::Extension(e) => Self::Unknown(e.type_code()),
         }
     }
 }
@@ -76,6 +86,7 @@ impl From<&ScalarValueRef<'_>> for Datatype {
             ScalarValueRef::Counter(_) => Self::Counter,
             ScalarValueRef::Timestamp(_) => Self::Timestamp,
             ScalarValueRef::Boolean(_) => Self::Boolean,
+            ScalarValueRef::Decimal(_) => Self::Decimal,
             ScalarValueRef::Null => Self::Null,
             ScalarValueRef::Unknown { type_code, .. } => Self::Unknown(*type_code),
         }
@@ -107,6 +118,8 @@ impl From<Datatype> for String {
             Datatype::Table => "table".into(),
             Datatype::List => "list".into(),
             Datatype::Text => "text".into(),
+            Datatype::Set => "set".into(),
+            Datatype::OrderedMap => "orderedMap".into(),
             Datatype::Bytes => "bytes".into(),
             Datatype::Str => "str".into(),
             Datatype::Int => "int".into(),
@@ -115,6 +128,7 @@ impl From<Datatype> for String {
             Datatype::Counter => "counter".into(),
             Datatype::Timestamp => "timestamp".into(),
             Datatype::Boolean => "boolean".into(),
+            Datatype::Decimal => "decimal".into(),
             Datatype::Null => "null".into(),
             Datatype::Unknown(type_code) => format!("unknown{}", type_code),
         }
@@ -131,6 +145,8 @@ impl TryFrom<JsValue> for Datatype {
             "table" => Ok(Datatype::Table),
             "list" => Ok(Datatype::List),
             "text" => Ok(Datatype::Text),
+            "set" => Ok(Datatype::Set),
+            "orderedMap" => Ok(Datatype::OrderedMap),
             "bytes" => Ok(Datatype::Bytes),
             "str" => Ok(Datatype::Str),
             "int" => Ok(Datatype::Int),
@@ -139,6 +155,7 @@ impl TryFrom<JsValue> for Datatype {
             "counter" => Ok(Datatype::Counter),
             "timestamp" => Ok(Datatype::Timestamp),
             "boolean" => Ok(Datatype::Boolean),
+            "decimal" => Ok(Datatype::Decimal),
             "null" => Ok(Datatype::Null),
             d => {
                 if d.starts_with("unknown") {
@@ -164,6 +181,8 @@ impl From<Datatype> for Option<Value<'_>> {
             Datatype::Map => Some(Value::Object(ObjType::Map)),
             Datatype::List => Some(Value::Object(ObjType::List)),
             Datatype::Text => Some(Value::Object(ObjType::Text)),
+            Datatype::Set => Some(Value::Object(ObjType::Set)),
+            Datatype::OrderedMap => Some(Value::Object(ObjType::OrderedMap)),
             _ => None,
         }
     }
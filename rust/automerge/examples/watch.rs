@@ -80,6 +80,22 @@ fn get_changes(_doc: &Automerge, patches: Vec<Patch>) {
                 "delete {:?} in obj {:?}, object path {:?}",
                 index, obj, path,
             ),
+            PatchAction::Move { from, to } => println!(
+                "move {:?} to {:?} in obj {:?}, object path {:?}",
+                from, to, obj, path,
+            ),
+            PatchAction::MoveKey { key, index } => println!(
+                "move key {:?} to {:?} in obj {:?}, object path {:?}",
+                key, index, obj, path,
+            ),
+            PatchAction::Detach { prop, moved } => println!(
+                "detach {:?} from {:?} in obj {:?}, object path {:?}",
+                moved, prop, obj, path,
+            ),
+            PatchAction::Attach { prop, moved, .. } => println!(
+                "attach {:?} at {:?} in obj {:?}, object path {:?}",
+                moved, prop, obj, path,
+            ),
             PatchAction::Mark { marks } => {
                 println!("mark {:?} in obj {:?}, object path {:?}", marks, obj, path,)
             }
@@ -89,6 +105,15 @@ fn get_changes(_doc: &Automerge, patches: Vec<Patch>) {
                     prop, obj, path,
                 )
             }
+            PatchAction::SetAdd { value } => {
+                println!("add {:?} to obj {:?}, object path {:?}", value, obj, path)
+            }
+            PatchAction::SetRemove { value } => {
+                println!(
+                    "remove {:?} from obj {:?}, object path {:?}",
+                    value, obj, path
+                )
+            }
         }
     }
 }
@@ -0,0 +1,407 @@
+//! Annotations anchored to ranges of text
+//!
+//! [Marks](crate::marks) are the right tool for formatting but they have no identity: two marks
+//! with the same name and value which touch are the same mark. Annotations such as review
+//! comments need to be told apart even when they overlap or have the same contents, so an
+//! [`Annotations`] store keeps each annotation separately under a unique id, with a range of a
+//! text object anchored by cursors and a map of arbitrary metadata.
+//!
+//! The annotations of a text object are kept in a map object chosen by the application, each
+//! annotation is a map under its id in that map:
+//!
+//! | key          | value                                                                 |
+//! |--------------|-----------------------------------------------------------------------|
+//! | `"start"`    | the [`CursorRange::start`] cursor as a string                         |
+//! | `"end"`      | the [`CursorRange::end`] cursor as a string                           |
+//! | `"expand"`   | `"before"`, `"after"`, `"both"` or `"none"`                           |
+//! | `"empty"`    | whether the annotation was created on an empty range                  |
+//! | `"metadata"` | a map with the metadata of the annotation                             |
+//!
+//! Entries of the store which do not have this shape are ignored. Because the annotations are
+//! ordinary objects, concurrently added annotations merge and the metadata of an annotation
+//! merges key by key.
+//!
+//! ## Example
+//!
+//! ```
+//! # use automerge::{AutoCommit, ObjType, ROOT, transaction::Transactable, hydrate_map};
+//! use automerge::annotations::Annotations;
+//! use automerge::marks::ExpandMark;
+//!
+//! let mut doc = AutoCommit::new();
+//! let text = doc.put_object(ROOT, "text", ObjType::Text)?;
+//! let comments = doc.put_object(ROOT, "comments", ObjType::Map)?;
+//! doc.splice_text(&text, 0, 0, "hello world")?;
+//!
+//! let annotations = Annotations::new(text.clone(), comments);
+//! let id = annotations.add(
+//!     &mut doc,
+//!     6..11,
+//!     ExpandMark::None,
+//!     hydrate_map! { "author" => "alice", "body" => "nice" },
+//! )?;
+//! doc.splice_text(&text, 0, 0, "oh, ")?;
+//!
+//! let found = annotations.intersecting(&doc, 8..12, None)?;
+//! assert_eq!(found.len(), 1);
+//! assert_eq!(found[0].id, id);
+//! assert_eq!(found[0].range, 10..15);
+//! # Ok::<(), automerge::AutomergeError>(())
+//! ```
+use std::collections::HashMap;
+use std::ops::Range;
+
+use crate::cursor::CursorRange;
+use crate::error::UpdateObjectError;
+use crate::hydrate;
+use crate::marks::ExpandMark;
+use crate::transaction::Transactable;
+use crate::{ActorId, AutomergeError, ChangeHash, Cursor, ObjId, ReadDoc, ScalarValue};
+
+const START: &str = "start";
+const END: &str = "end";
+const EXPAND: &str = "expand";
+const EMPTY: &str = "empty";
+const METADATA: &str = "metadata";
+
+/// The annotations of a text object, stored in a map object
+#[derive(Debug, Clone, PartialEq)]
+pub struct Annotations {
+    text: ObjId,
+    store: ObjId,
+}
+
+/// An annotation and the range of text it currently covers
+#[derive(Debug, Clone, PartialEq)]
+pub struct Annotation {
+    /// The unique id of the annotation
+    pub id: String,
+    /// The range of the text the annotation covers
+    pub range: Range<usize>,
+    /// Whether all the text the annotation covered has been deleted
+    pub collapsed: bool,
+    /// Whether text inserted at the start or end of the annotation is added to it
+    pub expand: ExpandMark,
+    /// The metadata of the annotation
+    pub metadata: hydrate::Map,
+}
+
+/// A change to an annotation between two versions of a document, see [`Annotations::diff`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnnotationPatch {
+    /// The id of the annotation which changed
+    pub id: String,
+    pub action: AnnotationPatchAction,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AnnotationPatchAction {
+    /// The annotation was added
+    Added { range: Range<usize> },
+    /// The annotation was removed
+    Removed,
+    /// The range of the annotation moved or changed size because of edits to the text
+    Moved {
+        from: Range<usize>,
+        to: Range<usize>,
+    },
+    /// All of the text the annotation covered was deleted, it is now an empty range at `at`
+    Collapsed { from: Range<usize>, at: usize },
+    /// The metadata of the annotation changed
+    Updated { metadata: hydrate::Map },
+}
+
+impl Annotations {
+    /// The annotations of the text object `text`, stored in the map object `store`
+    pub fn new(text: ObjId, store: ObjId) -> Self {
+        Self { text, store }
+    }
+
+    pub fn text(&self) -> &ObjId {
+        &self.text
+    }
+
+    pub fn store(&self) -> &ObjId {
+        &self.store
+    }
+
+    /// Add an annotation covering `range` of the text and return its id
+    ///
+    /// `expand` decides whether text inserted at the start or end of the range is added to the
+    /// annotation, like it does for a [`Mark`](crate::marks::Mark).
+    pub fn add<T: Transactable>(
+        &self,
+        tx: &mut T,
+        range: Range<usize>,
+        expand: ExpandMark,
+        metadata: hydrate::Map,
+    ) -> Result<String, AutomergeError> {
+        let cursors = tx.get_cursor_range(&self.text, range, expand, None)?;
+        let id = ActorId::random().to_hex_string();
+        let value = hydrate::Map::from(HashMap::from([
+            (START, cursors.start.to_string().as_str().into()),
+            (END, cursors.end.to_string().as_str().into()),
+            (EXPAND, expand_to_str(expand).into()),
+            (EMPTY, ScalarValue::Boolean(cursors.empty).into()),
+            (METADATA, hydrate::Value::Map(metadata)),
+        ]));
+        tx.batch_create_object(&self.store, id.as_str(), &value.into(), false)?;
+        Ok(id)
+    }
+
+    /// Remove the annotation `id`, returns `false` if there is no such annotation
+    pub fn remove<T: Transactable>(&self, tx: &mut T, id: &str) -> Result<bool, AutomergeError> {
+        if tx.get(&self.store, id)?.is_none() {
+            return Ok(false);
+        }
+        tx.delete(&self.store, id)?;
+        Ok(true)
+    }
+
+    /// Replace the metadata of the annotation `id`, returns `false` if there is no such
+    /// annotation
+    ///
+    /// Only the keys which differ from the current metadata are changed, so concurrent updates
+    /// to different keys merge.
+    pub fn update_metadata<T: Transactable>(
+        &self,
+        tx: &mut T,
+        id: &str,
+        metadata: hydrate::Map,
+    ) -> Result<bool, AutomergeError> {
+        let Some((_, annotation)) = tx.get(&self.store, id)? else {
+            return Ok(false);
+        };
+        let Some((_, obj)) = tx.get(&annotation, METADATA)? else {
+            return Ok(false);
+        };
+        tx.update_object(&obj, &metadata.into())
+            .map_err(|e| match e {
+                UpdateObjectError::Automerge(e) => e,
+                UpdateObjectError::ChangeType => AutomergeError::NotAnObject,
+            })?;
+        Ok(true)
+    }
+
+    /// The annotation `id` as at `heads`
+    pub fn get<R: ReadDoc>(
+        &self,
+        doc: &R,
+        id: &str,
+        heads: Option<&[ChangeHash]>,
+    ) -> Result<Option<Annotation>, AutomergeError> {
+        Ok(self
+            .all(doc, heads)?
+            .into_iter()
+            .find(|annotation| annotation.id == id))
+    }
+
+    /// All the annotations as at `heads`, ordered by their position in the text
+    pub fn all<R: ReadDoc>(
+        &self,
+        doc: &R,
+        heads: Option<&[ChangeHash]>,
+    ) -> Result<Vec<Annotation>, AutomergeError> {
+        let hydrate::Value::Map(store) = doc.hydrate(&self.store, heads)? else {
+            return Err(AutomergeError::InvalidOp(doc.object_type(&self.store)?));
+        };
+        let mut stored = store
+            .iter()
+            .filter_map(|(id, value)| Stored::parse(id, &value.value))
+            .collect::<Vec<_>>();
+        // every annotation is resolved in a single pass over the text
+        let ranges = stored
+            .iter()
+            .map(|stored| stored.cursors.clone())
+            .collect::<Vec<_>>();
+        let positions = doc.get_cursor_range_positions(&self.text, &ranges, heads)?;
+        let mut annotations = stored
+            .drain(..)
+            .zip(positions)
+            .map(|(stored, position)| Annotation {
+                id: stored.id,
+                range: position.range,
+                collapsed: position.collapsed,
+                expand: stored.cursors.expand,
+                metadata: stored.metadata,
+            })
+            .collect::<Vec<_>>();
+        annotations.sort_by(|a, b| {
+            (a.range.start, a.range.end, &a.id).cmp(&(b.range.start, b.range.end, &b.id))
+        });
+        Ok(annotations)
+    }
+
+    /// The annotations as at `heads` which overlap `range`
+    ///
+    /// Empty annotations, and all annotations when `range` is empty, are included if they touch
+    /// `range`.
+    pub fn intersecting<R: ReadDoc>(
+        &self,
+        doc: &R,
+        range: Range<usize>,
+        heads: Option<&[ChangeHash]>,
+    ) -> Result<Vec<Annotation>, AutomergeError> {
+        Ok(self
+            .all(doc, heads)?
+            .into_iter()
+            .filter(|annotation| intersects(&annotation.range, &range))
+            .collect())
+    }
+
+    /// The changes to the annotations between `before` and `after`
+    ///
+    /// Edits to the text are reported as [`AnnotationPatchAction::Moved`] for every annotation
+    /// whose range changed, or [`AnnotationPatchAction::Collapsed`] if the text it covered was
+    /// deleted. A change to the metadata of an annotation is reported separately from a change
+    /// to its range.
+    pub fn diff<R: ReadDoc>(
+        &self,
+        doc: &R,
+        before: &[ChangeHash],
+        after: &[ChangeHash],
+    ) -> Result<Vec<AnnotationPatch>, AutomergeError> {
+        let mut old = self
+            .all(doc, Some(before))?
+            .into_iter()
+            .map(|annotation| (annotation.id.clone(), annotation))
+            .collect::<HashMap<_, _>>();
+        let mut patches = Vec::new();
+        for new in self.all(doc, Some(after))? {
+            let Some(old) = old.remove(&new.id) else {
+                patches.push(AnnotationPatch {
+                    id: new.id,
+                    action: AnnotationPatchAction::Added { range: new.range },
+                });
+                continue;
+            };
+            if old.range != new.range {
+                let action = if new.collapsed && !old.collapsed {
+                    AnnotationPatchAction::Collapsed {
+                        from: old.range,
+                        at: new.range.start,
+                    }
+                } else {
+                    AnnotationPatchAction::Moved {
+                        from: old.range,
+                        to: new.range,
+                    }
+                };
+                patches.push(AnnotationPatch {
+                    id: new.id.clone(),
+                    action,
+                });
+            }
+            if old.metadata != new.metadata {
+                patches.push(AnnotationPatch {
+                    id: new.id,
+                    action: AnnotationPatchAction::Updated {
+                        metadata: new.metadata,
+                    },
+                });
+            }
+        }
+        let mut removed = old.into_keys().collect::<Vec<_>>();
+        removed.sort();
+        patches.extend(removed.into_iter().map(|id| AnnotationPatch {
+            id,
+            action: AnnotationPatchAction::Removed,
+        }));
+        Ok(patches)
+    }
+}
+
+/// An annotation as it is stored in the document
+struct Stored {
+    id: String,
+    cursors: CursorRange,
+    metadata: hydrate::Map,
+}
+
+impl Stored {
+    fn parse(id: &str, value: &hydrate::Value) -> Option<Self> {
+        let hydrate::Value::Map(map) = value else {
+            return None;
+        };
+        let cursor = |key| match map.get(key) {
+            Some(hydrate::Value::Scalar(ScalarValue::Str(s))) => Cursor::try_from(s.as_str()).ok(),
+            _ => None,
+        };
+        let expand = match map.get(EXPAND) {
+            Some(hydrate::Value::Scalar(ScalarValue::Str(s))) => expand_from_str(s)?,
+            _ => return None,
+        };
+        let empty = match map.get(EMPTY) {
+            Some(hydrate::Value::Scalar(ScalarValue::Boolean(b))) => *b,
+            _ => return None,
+        };
+        let metadata = match map.get(METADATA) {
+            Some(hydrate::Value::Map(m)) => m.clone(),
+            _ => return None,
+        };
+        Some(Self {
+            id: id.to_string(),
+            cursors: CursorRange {
+                start: cursor(START)?,
+                end: cursor(END)?,
+                expand,
+                empty,
+            },
+            metadata,
+        })
+    }
+}
+
+fn expand_to_str(expand: ExpandMark) -> &'static str {
+    match expand {
+        ExpandMark::Before => "before",
+        ExpandMark::After => "after",
+        ExpandMark::Both => "both",
+        ExpandMark::None => "none",
+    }
+}
+
+fn expand_from_str(s: &str) -> Option<ExpandMark> {
+    match s {
+        "before" => Some(ExpandMark::Before),
+        "after" => Some(ExpandMark::After),
+        "both" => Some(ExpandMark::Both),
+        "none" => Some(ExpandMark::None),
+        _ => None,
+    }
+}
+
+fn intersects(a: &Range<usize>, b: &Range<usize>) -> bool {
+    if a.is_empty() || b.is_empty() {
+        a.start <= b.end && b.start <= a.end
+    } else {
+        a.start < b.end && b.start < a.end
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ranges_intersect() {
+        assert!(intersects(&(2..5), &(4..8)));
+        assert!(!intersects(&(2..5), &(5..8)));
+        assert!(intersects(&(5..5), &(2..5)));
+        assert!(intersects(&(3..3), &(2..5)));
+        assert!(!intersects(&(6..6), &(2..5)));
+        assert!(intersects(&(2..5), &(2..2)));
+    }
+
+    #[test]
+    fn expand_round_trips() {
+        for expand in [
+            ExpandMark::Before,
+            ExpandMark::After,
+            ExpandMark::Both,
+            ExpandMark::None,
+        ] {
+            assert_eq!(expand_from_str(expand_to_str(expand)), Some(expand));
+        }
+    }
+}
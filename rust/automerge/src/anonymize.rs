@@ -1,5 +1,5 @@
 use crate::legacy::{ElementId, Key, MarkData, ObjectId, OpId, OpType};
-use crate::{ActorId, Automerge, AutomergeError, Change, ChangeHash, ScalarValue};
+use crate::{ActorId, Automerge, AutomergeError, Change, ChangeHash, Decimal, ScalarValue};
 use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
 use rand::RngExt;
@@ -153,7 +153,8 @@ impl Anonymization {
                 *name = self.anonymize_structural_string(name).into();
                 *value = self.anonymize_scalar(value);
             }
-            OpType::Make(_) | OpType::Delete | OpType::MarkEnd(_) => {}
+            OpType::MoveObject(obj) => map_op_id(obj, actors),
+            OpType::Make(_) | OpType::Delete | OpType::MarkEnd(_) | OpType::Move => {}
         }
     }
 
@@ -173,10 +174,18 @@ impl Anonymization {
                 ScalarValue::Timestamp(self.random_i64_other_than(*value))
             }
             ScalarValue::Boolean(_) => ScalarValue::Boolean(self.rng.random()),
+            ScalarValue::Decimal(value) => {
+                ScalarValue::Decimal(self.random_decimal_other_than(value))
+            }
             ScalarValue::Unknown { type_code, bytes } => ScalarValue::Unknown {
                 type_code: *type_code,
                 bytes: self.anonymize_bytes(bytes),
             },
+            // random bytes are unlikely to be a valid value of the type, so keep them unknown
+            ScalarValue::Extension(e) => ScalarValue::Unknown {
+                type_code: e.type_code(),
+                bytes: self.anonymize_bytes(e.bytes()),
+            },
             ScalarValue::Null => ScalarValue::Null,
         }
     }
@@ -193,6 +202,15 @@ impl Anonymization {
         }
     }
 
+    fn random_decimal_other_than(&mut self, original: &Decimal) -> Decimal {
+        loop {
+            let replacement = Decimal::new(self.rng.random::<i32>().into(), original.scale());
+            if &replacement != original {
+                return replacement;
+            }
+        }
+    }
+
     fn random_u64_other_than(&mut self, original: u64) -> u64 {
         loop {
             let replacement = self.rng.random();
@@ -6,7 +6,7 @@ use super::{
 };
 use crate::marks::{ExpandMark, Mark};
 use crate::transaction::{CommitOptions, Transactable};
-use crate::{ActorId, AutoCommit, Automerge, ObjId, ObjType, ReadDoc, ScalarValue, ROOT};
+use crate::{ActorId, AutoCommit, Automerge, Decimal, ObjId, ObjType, ReadDoc, ScalarValue, ROOT};
 
 const REPLICA_COUNT: usize = 3;
 const ROOT_KEYS: [&str; 4] = ["alpha", "beta", "gamma", "delta"];
@@ -178,7 +178,7 @@ fn apply_instruction(replicas: &mut [AutoCommit], list: &ObjId, text: &ObjId, in
 }
 
 fn scalar(kind: u8, a: u8, b: u8) -> ScalarValue {
-    match kind % 10 {
+    match kind % 11 {
         0 => ScalarValue::from(format!("private-{a}-{b}")),
         1 => ScalarValue::Bytes(vec![a, b, kind]),
         2 => ScalarValue::Int(i64::from(i16::from_le_bytes([a, b]))),
@@ -188,9 +188,13 @@ fn scalar(kind: u8, a: u8, b: u8) -> ScalarValue {
         6 => ScalarValue::Timestamp(i64::from(i16::from_le_bytes([a, b]))),
         7 => ScalarValue::Boolean(a.is_multiple_of(2)),
         8 => ScalarValue::Unknown {
-            type_code: 10 + a % 6,
+            type_code: 11 + a % 5,
             bytes: vec![a, b],
         },
+        9 => ScalarValue::Decimal(Decimal::new(
+            i16::from_le_bytes([a, b]).into(),
+            u32::from(kind % 4),
+        )),
         _ => ScalarValue::Null,
     }
 }
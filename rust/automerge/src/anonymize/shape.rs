@@ -71,6 +71,8 @@ enum ActionShape {
         expand: bool,
     },
     MarkEnd(bool),
+    Move,
+    MoveObject(CanonicalOpId),
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -83,6 +85,7 @@ enum ScalarShape {
     Counter,
     Timestamp,
     Boolean,
+    Decimal { scale: u32 },
     Unknown { type_code: u8, bytes: usize },
     Null,
 }
@@ -231,6 +234,8 @@ impl ShapeBuilder {
                 expand: *expand,
             },
             OpType::MarkEnd(expand) => ActionShape::MarkEnd(*expand),
+            OpType::Move => ActionShape::Move,
+            OpType::MoveObject(obj) => ActionShape::MoveObject(self.op_id(obj)),
         }
     }
 
@@ -274,10 +279,15 @@ fn scalar_shape(value: &ScalarValue) -> ScalarShape {
         ScalarValue::Counter(_) => ScalarShape::Counter,
         ScalarValue::Timestamp(_) => ScalarShape::Timestamp,
         ScalarValue::Boolean(_) => ScalarShape::Boolean,
+        ScalarValue::Decimal(d) => ScalarShape::Decimal { scale: d.scale() },
         ScalarValue::Unknown { type_code, bytes } => ScalarShape::Unknown {
             type_code: *type_code,
             bytes: bytes.len(),
         },
+        ScalarValue::Extension(e) => ScalarShape::Unknown {
+            type_code: e.type_code(),
+            bytes: e.bytes().len(),
+        },
         ScalarValue::Null => ScalarShape::Null,
     }
 }
@@ -378,7 +388,9 @@ pub(super) fn assert_private_data_changed(source: &Automerge, anonymized: &Autom
                 }
                 (OpType::Make(_), OpType::Make(_))
                 | (OpType::Delete, OpType::Delete)
-                | (OpType::MarkEnd(_), OpType::MarkEnd(_)) => {}
+                | (OpType::MarkEnd(_), OpType::MarkEnd(_))
+                | (OpType::Move, OpType::Move)
+                | (OpType::MoveObject(_), OpType::MoveObject(_)) => {}
                 _ => panic!("action shape differs in {id:?} op {index}"),
             }
         }
@@ -425,6 +437,9 @@ fn assert_scalar_changed(source: &ScalarValue, anonymized: &ScalarValue) {
         (ScalarValue::Timestamp(source), ScalarValue::Timestamp(anonymized)) => {
             assert_ne!(source, anonymized)
         }
+        (ScalarValue::Decimal(source), ScalarValue::Decimal(anonymized)) => {
+            assert_ne!(source, anonymized)
+        }
         // A random boolean can equal its source, and null has no alternative of the same type.
         (ScalarValue::Boolean(_), ScalarValue::Boolean(_))
         | (ScalarValue::Null, ScalarValue::Null) => {}
@@ -434,6 +449,12 @@ fn assert_scalar_changed(source: &ScalarValue, anonymized: &ScalarValue) {
                 bytes: anonymized, ..
             },
         ) => assert_bytes_changed(source, anonymized, "unknown scalar bytes"),
+        (
+            ScalarValue::Extension(source),
+            ScalarValue::Unknown {
+                bytes: anonymized, ..
+            },
+        ) => assert_bytes_changed(source.bytes(), anonymized, "extension scalar bytes"),
         _ => panic!("scalar shape differs"),
     }
 }
@@ -1,25 +1,29 @@
-use std::ops::RangeBounds;
+use std::ops::{Range, RangeBounds};
 
 use crate::automerge::SaveOptions;
+use crate::bounded_counter::BoundedCounterOp;
 use crate::clock::Clock;
-use crate::cursor::{CursorPosition, MoveCursor};
+use crate::cursor::{CursorAnchor, CursorPosition, CursorRange, CursorRangePosition, MoveCursor};
 use crate::exid::ExId;
-use crate::iter::{DiffIter, DocIter, Keys, ListRange, MapRange, Span, Spans, Values};
+use crate::iter::{DiffIter, DocIter, Keys, ListRange, MapRange, SetValues, Span, Spans, Values};
 use crate::marks::UpdateSpansConfig;
-use crate::marks::{ExpandMark, Mark, MarkSet};
+use crate::marks::{ExpandMark, Mark, MarkQuery, MarkSet};
 use crate::op_set2::{ChangeMetadata, Parents};
 use crate::patches::PatchLog;
 use crate::sync::SyncDoc;
-use crate::transaction::{CommitOptions, Transactable};
+use crate::transaction::{
+    CommitOptions, FailedPrecondition, PreconditionConflict, Transactable, UpdateTextConfig,
+};
 use crate::types::{ObjId, ObjMeta};
 use crate::Fragment;
-use crate::{hydrate, AnonymizeError, Bundle, OnPartialLoad, TextEncoding};
+use crate::{hydrate, AnonymizeError, Bundle, Compression, OnPartialLoad, TextEncoding};
 use crate::{sync, ObjType, Patch, ReadDoc, ScalarValue, ROOT};
 use crate::{
     transaction::TransactionInner, ActorId, Automerge, AutomergeError, Change, ChangeHash, Cursor,
     Prop, Value,
 };
 use crate::{LoadOptions, VerificationMode};
+use crate::{OrphanedEdits, RestorePolicy};
 
 /// An automerge document that automatically manages transactions.
 ///
@@ -65,6 +69,9 @@ pub struct AutoCommit {
     diff_cache: Option<(OpRange, ObjId, bool, Vec<Patch>)>,
     save_cursor: Vec<ChangeHash>,
     isolation: Option<Vec<ChangeHash>>,
+    /// The preconditions which rolled back a commit nobody could be told about, see
+    /// [`Self::take_failed_preconditions`]
+    failed_preconditions: Vec<FailedPrecondition>,
 }
 
 /// An autocommit document with an inactive [`PatchLog`]
@@ -80,6 +87,7 @@ impl Default for AutoCommit {
             diff_cache: None,
             save_cursor: Vec::new(),
             isolation: None,
+            failed_preconditions: Vec::new(),
         }
     }
 }
@@ -103,6 +111,7 @@ impl AutoCommit {
             diff_cache: None,
             save_cursor: Vec::new(),
             isolation: None,
+            failed_preconditions: Vec::new(),
         }
     }
 
@@ -117,6 +126,7 @@ impl AutoCommit {
             diff_cache: None,
             save_cursor: Vec::new(),
             isolation: None,
+            failed_preconditions: Vec::new(),
         })
     }
 
@@ -130,6 +140,7 @@ impl AutoCommit {
             diff_cache: None,
             save_cursor: Vec::new(),
             isolation: None,
+            failed_preconditions: Vec::new(),
         })
     }
 
@@ -143,6 +154,7 @@ impl AutoCommit {
             diff_cache: None,
             save_cursor: Vec::new(),
             isolation: None,
+            failed_preconditions: Vec::new(),
         })
     }
 
@@ -173,6 +185,7 @@ impl AutoCommit {
             diff_cache: None,
             save_cursor: Vec::new(),
             isolation: None,
+            failed_preconditions: Vec::new(),
         })
     }
 
@@ -359,6 +372,7 @@ impl AutoCommit {
             diff_cache: None,
             save_cursor: vec![],
             isolation: None,
+            failed_preconditions: Vec::new(),
         }
     }
 
@@ -372,9 +386,48 @@ impl AutoCommit {
             diff_cache: None,
             save_cursor: vec![],
             isolation: None,
+            failed_preconditions: Vec::new(),
+        })
+    }
+
+    /// Create a shallow copy of this document which only has the history after `base`, see
+    /// [`Automerge::shallow`]
+    pub fn shallow(&mut self, base: &[ChangeHash]) -> Result<Self, AutomergeError> {
+        self.ensure_transaction_closed();
+        Ok(Self {
+            doc: self.doc.shallow(base)?,
+            transaction: self.transaction.clone(),
+            patch_log: PatchLog::inactive(),
+            diff_cursor: vec![],
+            diff_cache: None,
+            save_cursor: vec![],
+            isolation: None,
+            failed_preconditions: Vec::new(),
+        })
+    }
+
+    /// Create a compacted copy of this document which drops the content deleted before `stable`,
+    /// see [`Automerge::compact`]
+    pub fn compact(&mut self, stable: &[ChangeHash]) -> Result<Self, AutomergeError> {
+        self.ensure_transaction_closed();
+        Ok(Self {
+            doc: self.doc.compact(stable)?,
+            transaction: self.transaction.clone(),
+            patch_log: PatchLog::inactive(),
+            diff_cursor: vec![],
+            diff_cache: None,
+            save_cursor: vec![],
+            isolation: None,
+            failed_preconditions: Vec::new(),
         })
     }
 
+    /// The heads the history of this document was truncated at, see [`Automerge::shallow_base`]
+    pub fn shallow_base(&mut self) -> Option<&[ChangeHash]> {
+        self.ensure_transaction_closed();
+        self.doc.shallow_base()
+    }
+
     /// Get the inner document.
     #[doc(hidden)]
     pub fn document(&mut self) -> &Automerge {
@@ -425,13 +478,24 @@ impl AutoCommit {
     }
 
     pub(crate) fn ensure_transaction_closed(&mut self) {
-        if let Some((patch_log, tx)) = self.transaction.take() {
-            self.patch_log.merge(patch_log);
-            let hash = tx.commit(&mut self.doc, None, None);
-            self.patch_log.finish_transaction(&self.doc.ops().actors);
-            if self.isolation.is_some() && hash.is_some() {
-                self.isolation = hash.map(|h| vec![h])
+        if self.transaction.is_some() {
+            self.commit_or_record(CommitOptions::default());
+        }
+    }
+
+    /// Commit the current operations, recording the precondition which failed if they are rolled
+    /// back instead
+    fn commit_or_record(&mut self, options: CommitOptions) -> Option<ChangeHash> {
+        match self.try_commit_with(options) {
+            Ok(hash) => hash,
+            Err(AutomergeError::PreconditionFailed(failed)) => {
+                if self.failed_preconditions.len() == Self::MAX_FAILED_PRECONDITIONS {
+                    self.failed_preconditions.remove(0);
+                }
+                self.failed_preconditions.push(*failed);
+                None
             }
+            Err(_) => None,
         }
     }
 
@@ -544,7 +608,7 @@ impl AutoCommit {
     /// Save this document, but don't run it through DEFLATE afterwards
     pub fn save_nocompress(&mut self) -> Vec<u8> {
         self.save_with_options(SaveOptions {
-            deflate: false,
+            compression: Compression::none(),
             ..Default::default()
         })
     }
@@ -585,6 +649,60 @@ impl AutoCommit {
         self.doc.get_last_local_change()
     }
 
+    /// See [`Automerge::set_restore_policy`]
+    pub fn set_restore_policy<O: AsRef<ExId>>(
+        &mut self,
+        obj: O,
+        policy: RestorePolicy,
+    ) -> Result<(), AutomergeError> {
+        self.doc.set_restore_policy(obj, policy)
+    }
+
+    /// See [`Automerge::restore_policy`]
+    pub fn restore_policy<O: AsRef<ExId>>(&self, obj: O) -> RestorePolicy {
+        self.doc.restore_policy(obj)
+    }
+
+    /// See [`Automerge::orphaned_edits`]
+    pub fn orphaned_edits(&mut self) -> Vec<OrphanedEdits> {
+        self.ensure_transaction_closed();
+        self.doc.orphaned_edits()
+    }
+
+    /// See [`Automerge::orphaned_edits_since`]
+    pub fn orphaned_edits_since(&mut self, heads: &[ChangeHash]) -> Vec<OrphanedEdits> {
+        self.ensure_transaction_closed();
+        self.doc.orphaned_edits_since(heads)
+    }
+
+    /// Put back the objects which were deleted from maps with the
+    /// [`RestorePolicy::Restore`] policy, see [`Automerge::restore_orphans`]
+    ///
+    /// The objects are moved back in the current transaction.
+    pub fn restore_orphans(&mut self) -> Result<Vec<ExId>, AutomergeError> {
+        let orphans = self.orphaned_edits();
+        self.restore(orphans)
+    }
+
+    /// See [`Automerge::restore_orphans_since`]
+    ///
+    /// The objects are moved back in the current transaction.
+    pub fn restore_orphans_since(
+        &mut self,
+        heads: &[ChangeHash],
+    ) -> Result<Vec<ExId>, AutomergeError> {
+        let orphans = self.orphaned_edits_since(heads);
+        self.restore(orphans)
+    }
+
+    fn restore(&mut self, orphans: Vec<OrphanedEdits>) -> Result<Vec<ExId>, AutomergeError> {
+        let orphans = orphans
+            .into_iter()
+            .filter(|orphan| self.restore_policy(&orphan.parent) == RestorePolicy::Restore)
+            .collect::<Vec<_>>();
+        crate::orphans::restore(self, orphans)
+    }
+
     pub fn get_changes(&mut self, have_deps: &[ChangeHash]) -> Vec<Change> {
         self.ensure_transaction_closed();
         self.doc.get_changes(have_deps)
@@ -671,11 +789,22 @@ impl AutoCommit {
 
     /// Commit any uncommitted changes
     ///
-    /// Returns [`None`] if there were no operations to commit
+    /// Returns [`None`] if there were no operations to commit, or if a precondition added with
+    /// [`Self::expect_winner`] does not hold. The precondition which failed is then kept for
+    /// [`Self::take_failed_preconditions`].
     pub fn commit(&mut self) -> Option<ChangeHash> {
         self.commit_with(CommitOptions::default())
     }
 
+    /// Commit any uncommitted changes, or return [`AutomergeError::PreconditionFailed`] if a
+    /// precondition added with [`Self::expect_winner`] does not hold, in which case the changes
+    /// are rolled back
+    ///
+    /// Returns `Ok(None)` if there were no operations to commit
+    pub fn try_commit(&mut self) -> Result<Option<ChangeHash>, AutomergeError> {
+        self.try_commit_with(CommitOptions::default())
+    }
+
     /// Commit the current operations with some options.
     ///
     /// Returns [`None`] if there were no operations to commit
@@ -694,18 +823,79 @@ impl AutoCommit {
     /// doc.commit_with(CommitOptions::default().with_message("Create todos list").with_time(now));
     /// ```
     pub fn commit_with(&mut self, options: CommitOptions) -> Option<ChangeHash> {
+        self.commit_or_record(options)
+    }
+
+    /// Commit the current operations with some options, see [`Self::try_commit`]
+    pub fn try_commit_with(
+        &mut self,
+        options: CommitOptions,
+    ) -> Result<Option<ChangeHash>, AutomergeError> {
         // ensure that even no changes triggers a change
         self.ensure_transaction_open();
         let (patch_log, tx) = self.transaction.take().unwrap();
+        if let Err(e) = tx.check_preconditions(&self.doc) {
+            // the patches of the rolled back operations are dropped with their branch
+            tx.rollback(&mut self.doc);
+            self.patch_log.finish_transaction(&self.doc.ops().actors);
+            return Err(e);
+        }
         self.patch_log.merge(patch_log);
+        let preconditions = tx.preconditions().to_vec();
         let hash = tx.commit(&mut self.doc, options.message, options.time);
         self.patch_log.finish_transaction(&self.doc.ops().actors);
         if self.isolation.is_some() && hash.is_some() {
             self.isolation = hash.map(|h| vec![h])
         }
-        hash
+        if let Some(hash) = hash {
+            self.doc.guard_writes(&preconditions, hash);
+        }
+        Ok(hash)
+    }
+
+    /// Require the winner of `prop` in `obj` to be `winner` when the current operations are
+    /// committed, see [`crate::transaction::Transaction::expect_winner`]
+    ///
+    /// If the precondition does not hold then the operations are rolled back when they are
+    /// committed, whether by [`Self::commit`] or by a method which commits them first such as
+    /// [`Self::save`]. [`Self::try_commit`] returns the failure, otherwise it is kept for
+    /// [`Self::take_failed_preconditions`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `obj` is not an object in the document.
+    pub fn expect_winner<O: AsRef<ExId>, P: Into<Prop>>(
+        &mut self,
+        obj: O,
+        prop: P,
+        winner: Option<ExId>,
+    ) -> Result<(), AutomergeError> {
+        self.ensure_transaction_open();
+        let (_, tx) = self.transaction.as_mut().unwrap();
+        tx.expect_winner(&self.doc, obj.as_ref(), prop.into(), winner)
+    }
+
+    /// See [`Automerge::take_precondition_conflicts`]
+    pub fn take_precondition_conflicts(&mut self) -> Vec<PreconditionConflict> {
+        self.ensure_transaction_closed();
+        self.doc.take_precondition_conflicts()
+    }
+
+    /// The preconditions added with [`Self::expect_winner`] which did not hold when their
+    /// operations were committed by anything other than [`Self::try_commit`], oldest first
+    ///
+    /// Each of these rolled back the operations it guarded. Each failure is returned once, and at
+    /// most the [`MAX_FAILED_PRECONDITIONS`](Self::MAX_FAILED_PRECONDITIONS) most recent are
+    /// kept.
+    pub fn take_failed_preconditions(&mut self) -> Vec<FailedPrecondition> {
+        self.ensure_transaction_closed();
+        std::mem::take(&mut self.failed_preconditions)
     }
 
+    /// The number of failed preconditions which are kept, see
+    /// [`Self::take_failed_preconditions`]
+    pub const MAX_FAILED_PRECONDITIONS: usize = 100;
+
     /// Remove any changes that have been made in the current transaction from the document
     pub fn rollback(&mut self) -> usize {
         self.transaction
@@ -871,6 +1061,34 @@ impl ReadDoc for AutoCommit {
             .values_for(obj.as_ref(), self.get_scope(Some(heads)))
     }
 
+    fn set_contains<O: AsRef<ExId>>(
+        &self,
+        obj: O,
+        value: &ScalarValue,
+    ) -> Result<bool, AutomergeError> {
+        self.doc
+            .set_contains_for(obj.as_ref(), value, self.get_scope(None))
+    }
+
+    fn set_contains_at<O: AsRef<ExId>>(
+        &self,
+        obj: O,
+        value: &ScalarValue,
+        heads: &[ChangeHash],
+    ) -> Result<bool, AutomergeError> {
+        self.doc
+            .set_contains_for(obj.as_ref(), value, self.get_scope(Some(heads)))
+    }
+
+    fn set_values<O: AsRef<ExId>>(&self, obj: O) -> SetValues<'_> {
+        self.doc.set_values_for(obj.as_ref(), self.get_scope(None))
+    }
+
+    fn set_values_at<O: AsRef<ExId>>(&self, obj: O, heads: &[ChangeHash]) -> SetValues<'_> {
+        self.doc
+            .set_values_for(obj.as_ref(), self.get_scope(Some(heads)))
+    }
+
     fn length<O: AsRef<ExId>>(&self, obj: O) -> usize {
         self.doc.length_for(obj.as_ref(), self.get_scope(None))
     }
@@ -907,6 +1125,37 @@ impl ReadDoc for AutoCommit {
             .get_marks_for(obj.as_ref(), index, self.get_scope(heads))
     }
 
+    fn query_marks<O: AsRef<ExId>>(
+        &self,
+        obj: O,
+        query: &MarkQuery,
+        heads: Option<&[ChangeHash]>,
+    ) -> Result<Vec<Mark>, AutomergeError> {
+        self.doc
+            .query_marks_for(obj.as_ref(), query, self.get_scope(heads))
+    }
+
+    fn next_mark_change<O: AsRef<ExId>>(
+        &self,
+        obj: O,
+        name: &str,
+        index: usize,
+        heads: Option<&[ChangeHash]>,
+    ) -> Result<Option<usize>, AutomergeError> {
+        self.doc
+            .next_mark_change_for(obj.as_ref(), name, index, self.get_scope(heads))
+    }
+
+    fn grapheme_range<O: AsRef<ExId>>(
+        &self,
+        obj: O,
+        range: Range<usize>,
+        heads: Option<&[ChangeHash]>,
+    ) -> Result<Range<usize>, AutomergeError> {
+        self.doc
+            .grapheme_range_for(obj.as_ref(), range, self.get_scope(heads))
+    }
+
     fn text<O: AsRef<ExId>>(&self, obj: O) -> Result<String, AutomergeError> {
         self.doc.text_for(obj.as_ref(), self.get_scope(None))
     }
@@ -971,6 +1220,41 @@ impl ReadDoc for AutoCommit {
             .get_cursor_position_for(obj.as_ref(), cursor, self.get_scope(at))
     }
 
+    fn get_cursor_positions<O: AsRef<ExId>>(
+        &self,
+        obj: O,
+        cursors: &[Cursor],
+        at: Option<&[ChangeHash]>,
+    ) -> Result<Vec<usize>, AutomergeError> {
+        let cursors = cursors
+            .iter()
+            .map(|cursor| (cursor, CursorAnchor::At))
+            .collect::<Vec<_>>();
+        self.doc
+            .get_cursor_positions_for(obj.as_ref(), &cursors, self.get_scope(at))
+    }
+
+    fn get_cursor_range<O: AsRef<ExId>>(
+        &self,
+        obj: O,
+        range: Range<usize>,
+        expand: ExpandMark,
+        at: Option<&[ChangeHash]>,
+    ) -> Result<CursorRange, AutomergeError> {
+        self.doc
+            .get_cursor_range_for(obj.as_ref(), range, expand, self.get_scope(at))
+    }
+
+    fn get_cursor_range_positions<O: AsRef<ExId>>(
+        &self,
+        obj: O,
+        ranges: &[CursorRange],
+        at: Option<&[ChangeHash]>,
+    ) -> Result<Vec<CursorRangePosition>, AutomergeError> {
+        self.doc
+            .get_cursor_range_positions_for(obj.as_ref(), ranges, self.get_scope(at))
+    }
+
     fn hydrate<O: AsRef<ExId>>(
         &self,
         obj: O,
@@ -1029,6 +1313,10 @@ impl ReadDoc for AutoCommit {
         self.doc.stats()
     }
 
+    fn detailed_stats(&self) -> crate::read::DetailedStats {
+        self.doc.detailed_stats()
+    }
+
     fn text_encoding(&self) -> crate::TextEncoding {
         self.doc.text_encoding()
     }
@@ -1107,6 +1395,118 @@ impl Transactable for AutoCommit {
         tx.delete(&mut self.doc, patch_log, obj.as_ref(), prop)
     }
 
+    fn resolve_conflict<O: AsRef<ExId>, P: Into<Prop>, V: Into<ScalarValue>>(
+        &mut self,
+        obj: O,
+        prop: P,
+        value: V,
+        superseding: &[ExId],
+    ) -> Result<(), AutomergeError> {
+        self.ensure_transaction_open();
+        let (patch_log, tx) = self.transaction.as_mut().unwrap();
+        tx.resolve_conflict(
+            &mut self.doc,
+            patch_log,
+            obj.as_ref(),
+            prop.into(),
+            value.into(),
+            superseding,
+        )
+    }
+
+    fn bounded_counter_increment<O: AsRef<ExId>>(
+        &mut self,
+        obj: O,
+        n: u64,
+    ) -> Result<(), AutomergeError> {
+        self.ensure_transaction_open();
+        let (patch_log, tx) = self.transaction.as_mut().unwrap();
+        let op = BoundedCounterOp::Increment(n);
+        tx.bounded_counter(&mut self.doc, patch_log, obj.as_ref(), op)
+    }
+
+    fn bounded_counter_decrement<O: AsRef<ExId>>(
+        &mut self,
+        obj: O,
+        n: u64,
+    ) -> Result<(), AutomergeError> {
+        self.ensure_transaction_open();
+        let (patch_log, tx) = self.transaction.as_mut().unwrap();
+        let op = BoundedCounterOp::Decrement(n);
+        tx.bounded_counter(&mut self.doc, patch_log, obj.as_ref(), op)
+    }
+
+    fn bounded_counter_transfer<O: AsRef<ExId>>(
+        &mut self,
+        obj: O,
+        to: &ActorId,
+        n: u64,
+    ) -> Result<(), AutomergeError> {
+        self.ensure_transaction_open();
+        let (patch_log, tx) = self.transaction.as_mut().unwrap();
+        let op = BoundedCounterOp::Transfer(to.clone(), n);
+        tx.bounded_counter(&mut self.doc, patch_log, obj.as_ref(), op)
+    }
+
+    fn set_add<O: AsRef<ExId>, V: Into<ScalarValue>>(
+        &mut self,
+        obj: O,
+        value: V,
+    ) -> Result<(), AutomergeError> {
+        self.ensure_transaction_open();
+        let (patch_log, tx) = self.transaction.as_mut().unwrap();
+        tx.set_add(&mut self.doc, patch_log, obj.as_ref(), value.into())
+    }
+
+    fn set_remove<O: AsRef<ExId>, V: Into<ScalarValue>>(
+        &mut self,
+        obj: O,
+        value: V,
+    ) -> Result<(), AutomergeError> {
+        self.ensure_transaction_open();
+        let (patch_log, tx) = self.transaction.as_mut().unwrap();
+        tx.set_remove(&mut self.doc, patch_log, obj.as_ref(), &value.into())
+    }
+
+    fn move_element<O: AsRef<ExId>>(
+        &mut self,
+        obj: O,
+        from: usize,
+        to: usize,
+    ) -> Result<(), AutomergeError> {
+        self.ensure_transaction_open();
+        let (patch_log, tx) = self.transaction.as_mut().unwrap();
+        tx.move_element(&mut self.doc, patch_log, obj.as_ref(), from, to)
+    }
+
+    fn move_key<O: AsRef<ExId>>(
+        &mut self,
+        obj: O,
+        key: &str,
+        index: usize,
+    ) -> Result<(), AutomergeError> {
+        self.ensure_transaction_open();
+        let (patch_log, tx) = self.transaction.as_mut().unwrap();
+        tx.move_key(&mut self.doc, patch_log, obj.as_ref(), key, index)
+    }
+
+    fn move_object<O: AsRef<ExId>, P: AsRef<ExId>, Q: Into<Prop>>(
+        &mut self,
+        obj: O,
+        parent: P,
+        prop: Q,
+    ) -> Result<(), AutomergeError> {
+        self.ensure_transaction_open();
+        let (patch_log, tx) = self.transaction.as_mut().unwrap();
+        tx.move_object(
+            &mut self.doc,
+            patch_log,
+            obj.as_ref(),
+            parent.as_ref(),
+            prop.into(),
+        )
+    }
+
     /// Splice new elements into the given sequence
     fn splice<O: AsRef<ExId>, V: Into<crate::hydrate::Value>, I: IntoIterator<Item = V>>(
         &mut self,
@@ -1201,10 +1601,19 @@ impl Transactable for AutoCommit {
         &mut self,
         obj: &ExId,
         new_text: S,
+    ) -> Result<(), AutomergeError> {
+        self.update_text_with(obj, new_text, UpdateTextConfig::default())
+    }
+
+    fn update_text_with<S: AsRef<str>>(
+        &mut self,
+        obj: &ExId,
+        new_text: S,
+        config: UpdateTextConfig,
     ) -> Result<(), AutomergeError> {
         self.ensure_transaction_open();
         let (patch_log, tx) = self.transaction.as_mut().unwrap();
-        crate::text_diff::myers_diff(&mut self.doc, tx, patch_log, obj, new_text)
+        crate::text_diff::myers_diff(&mut self.doc, tx, patch_log, obj, new_text, &config)
     }
 
     fn update_spans<O: AsRef<ExId>, I: IntoIterator<Item = Span>>(
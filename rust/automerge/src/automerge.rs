@@ -3,29 +3,34 @@ use std::collections::{BTreeSet, HashMap, HashSet};
 use std::env;
 use std::fmt::Debug;
 use std::num::NonZeroU64;
-use std::ops::RangeBounds;
+use std::ops::{Range, RangeBounds};
 
 use itertools::Itertools;
+use unicode_segmentation::UnicodeSegmentation;
 
 pub(crate) use crate::op_set2::change::ChangeCollector;
 pub(crate) use crate::op_set2::types::ScalarValue;
 pub(crate) use crate::op_set2::{
-    ChangeMetadata, KeyRef, OpQuery, OpQueryTerm, OpSet, OpType, Parents,
+    ChangeMetadata, FoundOpId, KeyRef, OpQuery, OpQueryTerm, OpSet, OpType, Parents,
 };
 pub(crate) use crate::read::ReadDoc;
 
 use crate::change_graph::ChangeGraph;
 use crate::change_queue::ChangeQueue;
-use crate::cursor::{CursorPosition, MoveCursor, OpCursor};
+use crate::cursor::{
+    CursorAnchor, CursorPosition, CursorRange, CursorRangePosition, MoveCursor, OpCursor,
+};
 use crate::exid::ExId;
-use crate::iter::{DiffIter, DocIter, Keys, ListRange, MapRange, Spans, Values};
-use crate::marks::{Mark, MarkAccumulator, MarkSet};
+use crate::iter::{DiffIter, DocIter, Keys, ListRange, MapRange, SetValues, Spans, Values};
+use crate::marks::{ExpandMark, Mark, MarkAccumulator, MarkQuery, MarkSet};
+use crate::ordered_map;
+use crate::orphans::{self, OrphanedEdits, RestorePolicy};
 use crate::patches::{Patch, PatchLog};
 use crate::storage::document::ReconstructError;
-use crate::storage::{self, change, load, Bundle, CompressConfig, Document, VerificationMode};
+use crate::storage::{self, load, Bundle, Compression, Document, VerificationMode};
 use crate::transaction::{
-    self, CommitOptions, Failure, OwnedTransaction, Success, Transactable, Transaction,
-    TransactionArgs,
+    self, CommitOptions, Failure, GuardState, GuardedWrite, OwnedTransaction, Precondition,
+    PreconditionConflict, Success, Transactable, Transaction, TransactionArgs,
 };
 
 use crate::clock::{Clock, ClockRange};
@@ -214,6 +219,10 @@ pub struct Automerge {
     pub(crate) ops: OpSet,
     /// The current actor.
     actor: Actor,
+    /// Values put by transactions with preconditions, see [`Self::take_precondition_conflicts`]
+    guarded_writes: Vec<GuardedWrite>,
+    /// The maps which use [`RestorePolicy::Restore`], see [`Self::set_restore_policy`]
+    restore_policies: HashMap<ExId, RestorePolicy>,
 }
 
 impl Automerge {
@@ -225,6 +234,8 @@ impl Automerge {
             ops: OpSet::new(TextEncoding::platform_default()),
             deps: Default::default(),
             actor: Actor::Unused(ActorId::random()),
+            guarded_writes: Vec::new(),
+            restore_policies: HashMap::new(),
         }
     }
 
@@ -263,6 +274,8 @@ impl Automerge {
             ops: OpSet::new(encoding),
             deps: Default::default(),
             actor: Actor::Unused(ActorId::random()),
+            guarded_writes: Vec::new(),
+            restore_policies: HashMap::new(),
         }
     }
 
@@ -274,6 +287,8 @@ impl Automerge {
             ops,
             deps,
             actor: Actor::Unused(ActorId::random()),
+            guarded_writes: Vec::new(),
+            restore_policies: HashMap::new(),
         };
         doc.remove_unused_actors(false);
         doc
@@ -449,7 +464,9 @@ impl Automerge {
                 seq = self.change_graph.seq_for_actor(actor_index) + 1;
                 deps = self.get_heads();
                 scope = None;
-                if seq > 1 {
+                // The previous change of a truncated actor is an ancestor of the base and so of
+                // our heads already
+                if seq > 1 + self.change_graph.truncated_seq(actor_index) {
                     let last_hash = self.get_hash(actor_index, seq - 1).unwrap();
                     if !deps.contains(&last_hash) {
                         deps.push(last_hash);
@@ -528,6 +545,154 @@ impl Automerge {
         }
     }
 
+    /// The values put by transactions with preconditions which a concurrent change has since
+    /// beaten
+    ///
+    /// A transaction which calls [`Transaction::expect_winner`] for a property and then puts a
+    /// value there only commits if nothing changed the property since it was read, but a change
+    /// made concurrently elsewhere can still put a value which wins over it when it is merged.
+    /// Call this after merging or applying changes to find those values, for example to put
+    /// them back or tell the user. Each conflict is returned once, and values which have since
+    /// been overwritten by changes which saw them are no longer tracked.
+    ///
+    /// The values are tracked in memory only: they are not saved, so a document loaded from
+    /// bytes or reached by sync doesn't know about them, although a clone or fork keeps them. At
+    /// most the
+    /// [`MAX_GUARDED_WRITES`](Self::MAX_GUARDED_WRITES) most recent values are tracked, so call
+    /// this regularly when many transactions have preconditions.
+    pub fn take_precondition_conflicts(&mut self) -> Vec<PreconditionConflict> {
+        let mut conflicts = Vec::new();
+        let writes = std::mem::take(&mut self.guarded_writes);
+        for write in writes {
+            match write.check(self) {
+                GuardState::Winning => self.guarded_writes.push(write),
+                GuardState::Replaced => {}
+                GuardState::Beaten(conflict) => conflicts.push(conflict),
+            }
+        }
+        conflicts
+    }
+
+    /// The number of values put by transactions with preconditions which are tracked, see
+    /// [`Self::take_precondition_conflicts`]
+    pub const MAX_GUARDED_WRITES: usize = 1000;
+
+    /// Track the values the change `hash` put at the properties of `preconditions`
+    ///
+    /// Once more than [`Self::MAX_GUARDED_WRITES`] values are tracked the ones which have been
+    /// overwritten are dropped, and then the oldest.
+    pub(crate) fn guard_writes(&mut self, preconditions: &[Precondition], hash: ChangeHash) {
+        for precondition in preconditions {
+            if let Some(write) = precondition.guarded_write(self, hash) {
+                self.guarded_writes.push(write);
+            }
+        }
+        if self.guarded_writes.len() > Self::MAX_GUARDED_WRITES {
+            let mut writes = std::mem::take(&mut self.guarded_writes);
+            writes.retain(|write| !matches!(write.check(self), GuardState::Replaced));
+            let excess = writes.len().saturating_sub(Self::MAX_GUARDED_WRITES);
+            writes.drain(..excess);
+            self.guarded_writes = writes;
+        }
+    }
+
+    /// Set whether [`Self::restore_orphans`] puts back the objects deleted from the map `obj`
+    /// concurrently with edits inside them
+    ///
+    /// The policy is a local setting of this document which is not saved, see
+    /// [`RestorePolicy`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AutomergeError::InvalidOp`] if `obj` is not a map.
+    pub fn set_restore_policy<O: AsRef<ExId>>(
+        &mut self,
+        obj: O,
+        policy: RestorePolicy,
+    ) -> Result<(), AutomergeError> {
+        let obj = obj.as_ref();
+        match self.exid_to_obj(obj)?.typ {
+            ObjType::Map | ObjType::Table => {}
+            typ => return Err(AutomergeError::InvalidOp(typ)),
+        }
+        if policy == RestorePolicy::Leave {
+            self.restore_policies.remove(obj);
+        } else {
+            self.restore_policies.insert(obj.clone(), policy);
+        }
+        Ok(())
+    }
+
+    /// The [`RestorePolicy`] of the map `obj`
+    pub fn restore_policy<O: AsRef<ExId>>(&self, obj: O) -> RestorePolicy {
+        self.restore_policies
+            .get(obj.as_ref())
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// The objects which have been removed from the document by changes which did not see
+    /// edits made inside them
+    ///
+    /// This is usually the result of merging a change which deleted a key with one which
+    /// edited the object at that key. The edits are still in the document but can't be read
+    /// from the root, because the object they are in is no longer reachable (its
+    /// [`Parents::visible_path`] is `None`). Objects are only reported if they were removed
+    /// from an object which is still reachable, edits in the objects inside them are included
+    /// with them.
+    ///
+    /// This visits every object in the document, after merging or applying changes use
+    /// [`Self::orphaned_edits_since`] with the heads from before.
+    pub fn orphaned_edits(&self) -> Vec<OrphanedEdits> {
+        orphans::orphaned_edits(self)
+    }
+
+    /// The [`Self::orphaned_edits`] which involve the changes not covered by `heads`, because
+    /// they removed the object or made some of the edits
+    ///
+    /// Only the ops of those changes and the ops they deleted or overwrote are visited, so this
+    /// is much cheaper than [`Self::orphaned_edits`] for a few changes merged into a large
+    /// document.
+    pub fn orphaned_edits_since(&self, heads: &[ChangeHash]) -> Vec<OrphanedEdits> {
+        orphans::orphaned_edits_since(self, heads)
+    }
+
+    /// Put back the objects with [`Self::orphaned_edits`] which were deleted from maps with
+    /// the [`RestorePolicy::Restore`] policy, returning the ids of the objects put back
+    ///
+    /// This is a helper for the application to call after merging, nothing is put back
+    /// automatically. Each object is moved back to the key it was deleted from with
+    /// [`Transactable::move_object`], in a single change, which is sent to other peers like any
+    /// other. An object whose key was overwritten rather than deleted is left where it is. If
+    /// nothing needs to be put back then no change is made.
+    pub fn restore_orphans(&mut self) -> Result<Vec<ExId>, AutomergeError> {
+        let orphans = self.orphaned_edits();
+        self.restore(orphans)
+    }
+
+    /// Like [`Self::restore_orphans`], but only for the [`Self::orphaned_edits_since`] `heads`
+    pub fn restore_orphans_since(
+        &mut self,
+        heads: &[ChangeHash],
+    ) -> Result<Vec<ExId>, AutomergeError> {
+        let orphans = self.orphaned_edits_since(heads);
+        self.restore(orphans)
+    }
+
+    fn restore(&mut self, orphans: Vec<OrphanedEdits>) -> Result<Vec<ExId>, AutomergeError> {
+        let orphans = orphans
+            .into_iter()
+            .filter(|orphan| self.restore_policy(&orphan.parent) == RestorePolicy::Restore)
+            .collect::<Vec<_>>();
+        if orphans.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut tx = self.transaction();
+        let restored = orphans::restore(&mut tx, orphans)?;
+        tx.commit();
+        Ok(restored)
+    }
+
     /// Run a transaction on this document in a closure, collecting patches, automatically handling commit or rollback
     /// afterwards.
     ///
@@ -620,6 +785,9 @@ impl Automerge {
             if !self.change_graph.has_change(&hash) {
                 return Err(AutomergeError::InvalidHash(hash));
             }
+            if self.change_graph.is_stub_hash(&hash) {
+                return Err(AutomergeError::TruncatedHistory(hash));
+            }
             for dep in self.change_graph.deps_for_hash(&hash) {
                 if seen.insert(dep) {
                     heads.push(dep);
@@ -634,6 +802,96 @@ impl Automerge {
         Ok(f)
     }
 
+    /// Create a "shallow" copy of this document which only has the history after `base`
+    ///
+    /// The returned document has the same state and actor as this one but the changes which are
+    /// ancestors of `base` (including `base` itself) are replaced by a compact snapshot: just
+    /// enough metadata about the truncated changes to check that later changes apply on top of
+    /// them. The ops of the truncated changes are kept as they make up the current state of the
+    /// document.
+    ///
+    /// A shallow document can merge and sync with any peer which has the base, whether that peer
+    /// has the full history or is shallow with the same (or an older) base. Changes the base
+    /// already covers are ignored. Operations which need the truncated history fail with
+    /// [`AutomergeError::TruncatedHistory`], for example [`Self::fork_at`] before the base, merging
+    /// a shallow document into a document which does not have its base or syncing with a peer
+    /// which does not have the base (unless the peer has no data at all and supports sending the
+    /// whole document).
+    ///
+    /// Truncating a shallow document again requires `base` to include the existing base.
+    pub fn shallow(&self, base: &[ChangeHash]) -> Result<Self, AutomergeError> {
+        let change_graph = self.change_graph.truncate(base)?;
+        Ok(Automerge {
+            queue: self.queue.clone(),
+            change_graph,
+            deps: self.deps.clone(),
+            ops: self.ops.clone(),
+            actor: self.actor.clone(),
+            guarded_writes: self.guarded_writes.clone(),
+            restore_policies: self.restore_policies.clone(),
+        })
+    }
+
+    /// Create a compacted copy of this document which drops the content deleted before `stable`
+    ///
+    /// `stable` must be a causal stability frontier: heads which every peer is known to have,
+    /// such that every change any peer makes or applies in future has all of `stable` in its
+    /// history. Compaction then removes what can never be seen again:
+    ///
+    /// * map values which were overwritten or deleted, together with their history
+    /// * the values of deleted list and text elements. The elements themselves are kept as
+    ///   tombstones so that [`Cursor`]s and ops which refer to them stay valid
+    /// * the contents of deleted objects. The deleted objects themselves are kept as empty
+    ///   objects, as a peer which has seen the delete can still make changes inside them
+    ///
+    /// As the dropped ops can't be turned back into changes the history before `stable` is
+    /// truncated as in [`Self::shallow`], and the returned document has the same restrictions as
+    /// any other shallow document. Reading or diffing at heads from before `stable` is not
+    /// supported.
+    pub fn compact(&self, stable: &[ChangeHash]) -> Result<Self, AutomergeError> {
+        let change_graph = self.change_graph.truncate(stable)?;
+        let clock = self.change_graph.clock_at(stable);
+        Ok(Automerge {
+            queue: self.queue.clone(),
+            change_graph,
+            deps: self.deps.clone(),
+            ops: self.ops.compact(&clock),
+            actor: self.actor.clone(),
+            guarded_writes: self.guarded_writes.clone(),
+            restore_policies: self.restore_policies.clone(),
+        })
+    }
+
+    /// The heads the history of this document was truncated at by [`Self::shallow`], `None` if
+    /// this document has its full history
+    pub fn shallow_base(&self) -> Option<&[ChangeHash]> {
+        self.change_graph.base_heads()
+    }
+
+    /// Whether `change` is part of the history which has been truncated from this document
+    pub(crate) fn is_truncated(&self, change: &Change) -> bool {
+        self.ops
+            .lookup_actor(change.actor_id())
+            .map(|idx| self.change_graph.truncated_seq(idx) >= change.seq())
+            .unwrap_or(false)
+    }
+
+    /// Check that all of the truncated history of `other` is in `self`
+    fn check_base(&self, other: &Self) -> Result<(), AutomergeError> {
+        for (hash, actor, seq) in other.change_graph.stubs() {
+            let covered = self.has_change(&hash)
+                || self
+                    .ops
+                    .lookup_actor(&other.ops.actors[actor])
+                    .map(|idx| self.change_graph.truncated_seq(idx) >= seq)
+                    .unwrap_or(false);
+            if !covered {
+                return Err(AutomergeError::TruncatedHistory(hash));
+            }
+        }
+        Ok(())
+    }
+
     pub(crate) fn get_changes_by_hashes<I>(&self, hashes: I) -> Result<Vec<Change>, AutomergeError>
     where
         I: IntoIterator<Item = ChangeHash>,
@@ -818,6 +1076,14 @@ impl Automerge {
         if !first_chunk.checksum_valid() {
             return Err(load::Error::BadChecksum.into());
         }
+        let (remaining, base, first_chunk) = match first_chunk {
+            storage::Chunk::Base(base) => {
+                tracing::trace!("first chunk is base chunk, loading shallow document");
+                let (remaining, chunk) = load::next_document(remaining.reset())?;
+                (remaining, Some(base), chunk)
+            }
+            chunk => (remaining, None, chunk),
+        };
 
         let mut changes = vec![];
         let mut first_chunk_was_doc = false;
@@ -825,7 +1091,11 @@ impl Automerge {
             storage::Chunk::Document(d) => {
                 tracing::trace!("first chunk is document chunk, inflating");
                 first_chunk_was_doc = true;
-                match d.reconstruct(options.verification_mode, options.text_encoding) {
+                match d.reconstruct(
+                    base.as_ref(),
+                    options.verification_mode,
+                    options.text_encoding,
+                ) {
                     Ok(doc) => doc,
                     Err(ReconstructError::InvalidMarkOrderDoc {
                         doc,
@@ -863,6 +1133,7 @@ impl Automerge {
                 );
                 Self::new_with_encoding(options.text_encoding)
             }
+            storage::Chunk::Base(_) => return Err(load::Error::OrphanedBase.into()),
         };
         tracing::trace!("loading change chunks");
         match load::load_changes(
@@ -1024,6 +1295,7 @@ impl Automerge {
         patch_log: &mut PatchLog,
     ) -> Result<Vec<ChangeHash>, AutomergeError> {
         // TODO: Make this fallible and figure out how to do this transactionally
+        self.check_base(other)?;
         let changes = self.get_changes_added(other);
         tracing::trace!(changes=?changes.iter().map(|c| c.hash()).collect::<Vec<_>>(), "merging new changes");
         self.apply_changes_log_patches(changes, patch_log)?;
@@ -1049,8 +1321,12 @@ impl Automerge {
     pub fn save_with_options(&self, options: SaveOptions) -> Vec<u8> {
         self.assert_no_unused_actors(true);
 
-        let doc = Document::new(&self.ops, &self.change_graph, options.compress());
-        let mut bytes = doc.into_bytes();
+        let doc = Document::new(&self.ops, &self.change_graph, &options.compression);
+        let mut bytes = match self.change_graph.export_base() {
+            Some(base) => base.into_bytes(),
+            None => Vec::new(),
+        };
+        bytes.extend(doc.into_bytes());
 
         if options.retain_orphans {
             for orphaned in self.queue.iter() {
@@ -1080,7 +1356,7 @@ impl Automerge {
     /// Save this document, but don't run it through `DEFLATE` afterwards
     pub fn save_nocompress(&self) -> Vec<u8> {
         self.save_with_options(SaveOptions {
-            deflate: false,
+            compression: Compression::none(),
             ..Default::default()
         })
     }
@@ -1430,7 +1706,10 @@ impl Automerge {
         let mut seen_hashes = HashSet::new();
         let mut added_change_hashes = Vec::new();
         while let Some(hash) = stack.pop() {
-            if !seen_hashes.contains(&hash) && !self.has_change(&hash) {
+            if !seen_hashes.contains(&hash)
+                && !self.has_change(&hash)
+                && !other.change_graph.is_stub_hash(&hash)
+            {
                 seen_hashes.insert(hash);
                 added_change_hashes.push(hash);
                 stack.extend(other.change_graph.deps_for_hash(&hash));
@@ -1494,33 +1773,82 @@ impl Automerge {
         clock: Option<Clock>,
         seq_type: SequenceType,
     ) -> Vec<Mark> {
-        let mut top_ops = self.ops().top_ops(&obj.id, clock).marks();
-
-        let mut index = 0;
-        let mut acc = MarkAccumulator::default();
-        let mut last_marks = None;
-        let mut mark_len = 0;
-        let mut mark_index = 0;
-        while let Some(o) = top_ops.next() {
-            let marks = top_ops.get_marks();
-            let len = o.width(seq_type, self.text_encoding());
-            if last_marks.as_ref() != marks {
-                match last_marks.as_ref() {
-                    Some(m) if mark_len > 0 => acc.add(mark_index, mark_len, m),
-                    _ => (),
-                }
-                last_marks = marks.cloned();
-                mark_index = index;
-                mark_len = 0;
+        if seq_type == SequenceType::List {
+            let elements = self.ops().list_elements(&obj.id, clock);
+            return accumulate_marks(elements, seq_type, self.text_encoding());
+        }
+        let top_ops = self.ops().top_ops(&obj.id, clock).marks();
+        accumulate_marks(top_ops, seq_type, self.text_encoding())
+    }
+
+    pub(crate) fn query_marks_for(
+        &self,
+        obj: &ExId,
+        query: &MarkQuery,
+        clock: Option<Clock>,
+    ) -> Result<Vec<Mark>, AutomergeError> {
+        let obj = self.exid_to_obj(obj)?;
+        let Some(seq_type) = obj.typ.as_sequence_type() else {
+            return Ok(Vec::new());
+        };
+        let range = query.range.clone().unwrap_or(0..usize::MAX);
+        let marks = if clock.is_none() && seq_type == SequenceType::Text {
+            let fast = self.ops().marks_in_range_fast(&obj.id, range.clone());
+            #[cfg(feature = "slow_path_assertions")]
+            {
+                let slow = clip_marks(self.calculate_marks_slow(&obj, None, seq_type), &range);
+                assert_eq!(fast, slow, "indexed marks != walked marks");
             }
-            mark_len += len;
-            index += len;
+            fast
+        } else {
+            clip_marks(self.calculate_marks_slow(&obj, clock, seq_type), &range)
+        };
+        Ok(marks
+            .into_iter()
+            .filter(|mark| query.matches(mark))
+            .collect())
+    }
+
+    pub(crate) fn next_mark_change_for(
+        &self,
+        obj: &ExId,
+        name: &str,
+        index: usize,
+        clock: Option<Clock>,
+    ) -> Result<Option<usize>, AutomergeError> {
+        let length = self.length_for(obj, clock.clone());
+        let query = MarkQuery::new().with_name(name).in_range(index..length);
+        let marks = self.query_marks_for(obj, &query, clock)?;
+        Ok(match marks.first() {
+            // the mark is set at `index` and changes where it ends
+            Some(mark) if mark.start == index => Some(mark.end).filter(|end| *end < length),
+            Some(mark) => Some(mark.start),
+            None => None,
+        })
+    }
+
+    pub(crate) fn grapheme_range_for(
+        &self,
+        obj: &ExId,
+        range: Range<usize>,
+        clock: Option<Clock>,
+    ) -> Result<Range<usize>, AutomergeError> {
+        let text = self.text_for(obj, clock)?;
+        let encoding = self.text_encoding();
+        if range.start > range.end || range.end > encoding.width(&text) {
+            return Err(AutomergeError::InvalidIndex(range.end));
         }
-        match last_marks.as_ref() {
-            Some(m) if mark_len > 0 => acc.add(mark_index, mark_len, m),
-            _ => (),
+        let (mut start, mut boundary) = (0, 0);
+        for grapheme in text.graphemes(true) {
+            if boundary >= range.end {
+                break;
+            }
+            boundary += encoding.width(grapheme);
+            if boundary <= range.start {
+                start = boundary;
+            }
         }
-        acc.into_iter_no_unmark().collect()
+        Ok(start..boundary)
     }
 
     pub fn hydrate(&self, heads: Option<&[ChangeHash]>) -> hydrate::Value {
@@ -1539,6 +1867,8 @@ impl Automerge {
             ObjType::Map | ObjType::Table => self.hydrate_map(&obj.id, clock.as_ref()),
             ObjType::List => self.hydrate_list(&obj.id, clock.as_ref()),
             ObjType::Text => self.hydrate_text(&obj.id, clock.as_ref()),
+            ObjType::Set => self.hydrate_set(&obj.id, clock.as_ref()),
+            ObjType::OrderedMap => self.hydrate_ordered_map(&obj.id, clock.as_ref()),
         })
     }
 
@@ -1591,9 +1921,43 @@ impl Automerge {
     }
 
     pub(crate) fn values_for(&self, obj: &ExId, clock: Option<Clock>) -> Values<'_> {
+        match self.exid_to_obj(obj) {
+            Ok(obj) if obj.typ == ObjType::OrderedMap => Values::ordered(
+                self.ops
+                    .map_range(&obj.id, .., clock)
+                    .map(|item| (item.value.to_value(), item.id()))
+                    .collect(),
+            ),
+            Ok(obj) => Values::new(&self.ops, self.ops.top_ops(&obj.id, clock.clone()), clock),
+            Err(_) => Values::default(),
+        }
+    }
+
+    pub(crate) fn set_contains_for(
+        &self,
+        obj: &ExId,
+        value: &crate::ScalarValue,
+        clock: Option<Clock>,
+    ) -> Result<bool, AutomergeError> {
+        let obj = self.exid_to_obj(obj)?;
+        if obj.typ != ObjType::Set {
+            return Err(AutomergeError::InvalidOp(obj.typ));
+        }
+        let Some(key) = value.set_key() else {
+            return Ok(false);
+        };
+        Ok(!self
+            .ops
+            .seek_ops_by_map_key(&obj.id, &key, clock.as_ref())
+            .ops
+            .is_empty())
+    }
+
+    pub(crate) fn set_values_for(&self, obj: &ExId, clock: Option<Clock>) -> SetValues<'_> {
         self.exid_to_obj(obj)
             .ok()
-            .map(|obj| Values::new(&self.ops, self.ops.top_ops(&obj.id, clock.clone()), clock))
+            .filter(|obj| obj.typ == ObjType::Set)
+            .map(|obj| SetValues::new(self.ops.map_range(&obj.id, .., clock)))
             .unwrap_or_default()
     }
 
@@ -1673,67 +2037,145 @@ impl Automerge {
                     .seek_list_opid(&obj_meta.id, opid, seq_type, clock.as_ref())
                     .ok_or_else(|| AutomergeError::InvalidCursor(cursor.clone()))?;
 
-                match op.move_cursor {
-                    // `MoveCursor::After` mimics the original behavior of cursors.
-                    //
-                    // The original behavior was to just return the `FoundOpId::index` found by
-                    // `OpSetInternal::seek_list_opid()`.
-                    //
-                    // This index always corresponds to the:
-                    // - index of the item itself (if it's visible at `clock`)
-                    // - next index of visible item that **was also visible at the time of cursor creation**
-                    //   (if the item is not visible at `clock`).
-                    // - or `sequence.length` if none of the next items are visible at `clock`.
-                    MoveCursor::After => Ok(found.index),
-                    MoveCursor::Before => {
-                        // `MoveCursor::Before` behaves like `MoveCursor::After` but in the opposite direction:
-                        //
-                        // - if the item is visible at `clock`, just return its index
-                        // - if the item isn't visible at `clock`, find the index of the **previous** item
-                        //   that's visible at `clock` that was also visible at the time of cursor creation.
-                        // - if none of the previous items are visible (or the index of the original item is 0),
-                        //   our index is `0`.
-                        if found.visible || found.index == 0 {
-                            Ok(found.index)
-                        } else {
-                            // FIXME: this should probably be an `OpSet` query
-                            // also this implementation is likely very inefficient
-
-                            // current implementation walks upwards through `key` of op pointed to by cursor
-                            // and checks if `key` is visible by using `seek_list_opid()`.
-
-                            let mut key = found
-                                .op.key.elemid()
-                                .expect("failed to retrieve initial cursor op key for MoveCursor::Before")
-                                .0;
-
-                            loop {
-                                let f = self.ops.seek_list_opid(
-                                    &obj_meta.id,
-                                    key,
-                                    seq_type,
-                                    clock.as_ref(),
-                                );
-
-                                match f {
-                                    Some(f) => {
-                                        if f.visible {
-                                            return Ok(f.index);
-                                        }
-
-                                        key = f
-                                            .op
-                                            .key
-                                            .elemid()
-                                            .expect(
-                                                "failed to retrieve op key in MoveCursor::Before",
-                                            )
-                                            .0;
-                                    }
-                                    // reached when we've gone before the beginning of the sequence
-                                    None => break Ok(0),
+                Ok(self.found_cursor_position(
+                    &obj_meta.id,
+                    op,
+                    found,
+                    CursorAnchor::At,
+                    seq_type,
+                    clock.as_ref(),
+                ))
+            }
+        }
+    }
+
+    /// Resolve many cursors in `obj` with a single pass over the object
+    pub(crate) fn get_cursor_positions_for(
+        &self,
+        obj: &ExId,
+        cursors: &[(&Cursor, CursorAnchor)],
+        clock: Option<Clock>,
+    ) -> Result<Vec<usize>, AutomergeError> {
+        let obj_meta = self.exid_to_obj(obj)?;
+        let mut op_cursors = Vec::new();
+        let mut opids = Vec::new();
+        for (cursor, _) in cursors {
+            if let Cursor::Op(op) = cursor {
+                if obj_meta.typ.as_sequence_type().is_none() {
+                    return Err(AutomergeError::InvalidCursor((*cursor).clone()));
+                }
+                op_cursors.push(op);
+                opids.push(self.op_cursor_to_opid(op, clock.as_ref())?);
+            }
+        }
+        let mut found = match obj_meta.typ.as_sequence_type() {
+            Some(seq_type) if !opids.is_empty() => self
+                .ops
+                .seek_list_opids(&obj_meta.id, &opids, seq_type, clock.as_ref())
+                .into_iter()
+                .zip(op_cursors)
+                .map(|(found, op)| Some((found?, op, seq_type)))
+                .collect(),
+            _ => Vec::new(),
+        }
+        .into_iter();
+
+        let mut length = None;
+        let mut positions = Vec::with_capacity(cursors.len());
+        for (cursor, anchor) in cursors {
+            let pos = match cursor {
+                Cursor::Start => 0,
+                Cursor::End => *length.get_or_insert_with(|| self.length_for(obj, clock.clone())),
+                Cursor::Op(_) => {
+                    let Some(Some((found, op, seq_type))) = found.next() else {
+                        return Err(AutomergeError::InvalidCursor((*cursor).clone()));
+                    };
+                    self.found_cursor_position(
+                        &obj_meta.id,
+                        op,
+                        found,
+                        *anchor,
+                        seq_type,
+                        clock.as_ref(),
+                    )
+                }
+            };
+            positions.push(pos);
+        }
+        Ok(positions)
+    }
+
+    fn found_cursor_position(
+        &self,
+        obj: &ObjId,
+        op: &OpCursor,
+        found: FoundOpId<'_>,
+        anchor: CursorAnchor,
+        seq_type: SequenceType,
+        clock: Option<&Clock>,
+    ) -> usize {
+        if anchor == CursorAnchor::After {
+            // the position after a deleted element is where the element was, which is the
+            // same as the `MoveCursor::After` position of the element
+            return if found.visible {
+                found.index + found.op.width(seq_type, self.text_encoding())
+            } else {
+                found.index
+            };
+        }
+        match op.move_cursor {
+            // `MoveCursor::After` mimics the original behavior of cursors.
+            //
+            // The original behavior was to just return the `FoundOpId::index` found by
+            // `OpSetInternal::seek_list_opid()`.
+            //
+            // This index always corresponds to the:
+            // - index of the item itself (if it's visible at `clock`)
+            // - next index of visible item that **was also visible at the time of cursor creation**
+            //   (if the item is not visible at `clock`).
+            // - or `sequence.length` if none of the next items are visible at `clock`.
+            MoveCursor::After => found.index,
+            MoveCursor::Before => {
+                // `MoveCursor::Before` behaves like `MoveCursor::After` but in the opposite direction:
+                //
+                // - if the item is visible at `clock`, just return its index
+                // - if the item isn't visible at `clock`, find the index of the **previous** item
+                //   that's visible at `clock` that was also visible at the time of cursor creation.
+                // - if none of the previous items are visible (or the index of the original item is 0),
+                //   our index is `0`.
+                if found.visible || found.index == 0 {
+                    found.index
+                } else {
+                    // FIXME: this should probably be an `OpSet` query
+                    // also this implementation is likely very inefficient
+
+                    // current implementation walks upwards through `key` of op pointed to by cursor
+                    // and checks if `key` is visible by using `seek_list_opid()`.
+
+                    let mut key = found
+                        .op
+                        .key
+                        .elemid()
+                        .expect("failed to retrieve initial cursor op key for MoveCursor::Before")
+                        .0;
+
+                    loop {
+                        let f = self.ops.seek_list_opid(obj, key, seq_type, clock);
+
+                        match f {
+                            Some(f) => {
+                                if f.visible {
+                                    return f.index;
                                 }
+
+                                key =
+                                    f.op.key
+                                        .elemid()
+                                        .expect("failed to retrieve op key in MoveCursor::Before")
+                                        .0;
                             }
+                            // reached when we've gone before the beginning of the sequence
+                            None => break 0,
                         }
                     }
                 }
@@ -1741,6 +2183,64 @@ impl Automerge {
         }
     }
 
+    pub(crate) fn get_cursor_range_for(
+        &self,
+        obj: &ExId,
+        range: Range<usize>,
+        expand: ExpandMark,
+        clock: Option<Clock>,
+    ) -> Result<CursorRange, AutomergeError> {
+        let length = self.length_for(obj, clock.clone());
+        if range.start > range.end || range.end > length {
+            return Err(AutomergeError::InvalidIndex(range.end));
+        }
+        // each end of the range points at the element on the side which excludes insertions
+        let start = if expand.before() {
+            match range.start.checked_sub(1) {
+                Some(index) => CursorPosition::Index(index),
+                None => CursorPosition::Start,
+            }
+        } else if range.start == length {
+            CursorPosition::End
+        } else {
+            CursorPosition::Index(range.start)
+        };
+        let end = if !expand.after() {
+            match range.end.checked_sub(1) {
+                Some(index) => CursorPosition::Index(index),
+                None => CursorPosition::Start,
+            }
+        } else if range.end == length {
+            CursorPosition::End
+        } else {
+            CursorPosition::Index(range.end)
+        };
+        Ok(CursorRange {
+            start: self.get_cursor_for(obj, start, clock.clone(), MoveCursor::After)?,
+            end: self.get_cursor_for(obj, end, clock, MoveCursor::After)?,
+            expand,
+            empty: range.is_empty(),
+        })
+    }
+
+    pub(crate) fn get_cursor_range_positions_for(
+        &self,
+        obj: &ExId,
+        ranges: &[CursorRange],
+        clock: Option<Clock>,
+    ) -> Result<Vec<CursorRangePosition>, AutomergeError> {
+        let anchors = ranges
+            .iter()
+            .flat_map(|range| range.anchors())
+            .collect::<Vec<_>>();
+        let positions = self.get_cursor_positions_for(obj, &anchors, clock)?;
+        Ok(ranges
+            .iter()
+            .zip(positions.chunks_exact(2))
+            .map(|(range, pos)| range.resolve(pos[0], pos[1]))
+            .collect())
+    }
+
     pub(crate) fn marks_for(
         &self,
         obj: &ExId,
@@ -1757,7 +2257,8 @@ impl Automerge {
     ) -> Result<Option<(Value<'_>, ExId)>, AutomergeError> {
         let obj = self.exid_to_obj(obj)?;
         let op = match (obj.typ, prop) {
-            (ObjType::Map | ObjType::Table, Prop::Map(key)) => self
+            (ObjType::OrderedMap, Prop::Map(key)) if ordered_map::is_position_key(&key) => None,
+            (ObjType::Map | ObjType::Table | ObjType::OrderedMap, Prop::Map(key)) => self
                 .ops
                 .seek_ops_by_map_key(&obj.id, &key, clock.as_ref())
                 .ops
@@ -1790,7 +2291,8 @@ impl Automerge {
         let prop = prop.into();
         let obj = self.exid_to_obj(obj.as_ref())?;
         let values = match (obj.typ, prop) {
-            (ObjType::Map | ObjType::Table, Prop::Map(key)) => self
+            (ObjType::OrderedMap, Prop::Map(key)) if ordered_map::is_position_key(&key) => vec![],
+            (ObjType::Map | ObjType::Table | ObjType::OrderedMap, Prop::Map(key)) => self
                 .ops
                 .seek_ops_by_map_key(&obj.id, &key, clock.as_ref())
                 .ops
@@ -1826,6 +2328,14 @@ impl Automerge {
         clock: Option<Clock>,
     ) -> Result<MarkSet, AutomergeError> {
         let obj = self.exid_to_obj(obj.as_ref())?;
+        if obj.typ == ObjType::List {
+            let mut iter = self.ops.list_elements(&obj.id, clock);
+            iter.nth(index);
+            return Ok(iter
+                .get_marks()
+                .map(|arc| arc.as_ref().clone().without_unmarks())
+                .unwrap_or_default());
+        }
         let mut iter = self.ops.top_ops(&obj.id, clock).marks();
         iter.nth(index);
         match iter.get_marks() {
@@ -1934,6 +2444,51 @@ impl Automerge {
     }
 }
 
+/// Collect the marks covering the ops of a sequence, as tracked by the iterator
+fn accumulate_marks<'a, I: OpQueryTerm<'a>>(
+    mut ops: I,
+    seq_type: SequenceType,
+    text_encoding: TextEncoding,
+) -> Vec<Mark> {
+    let mut index = 0;
+    let mut acc = MarkAccumulator::default();
+    let mut last_marks = None;
+    let mut mark_len = 0;
+    let mut mark_index = 0;
+    while let Some(o) = ops.next() {
+        let marks = ops.get_marks();
+        let len = o.width(seq_type, text_encoding);
+        if last_marks.as_ref() != marks {
+            match last_marks.as_ref() {
+                Some(m) if mark_len > 0 => acc.add(mark_index, mark_len, m),
+                _ => (),
+            }
+            last_marks = marks.cloned();
+            mark_index = index;
+            mark_len = 0;
+        }
+        mark_len += len;
+        index += len;
+    }
+    match last_marks.as_ref() {
+        Some(m) if mark_len > 0 => acc.add(mark_index, mark_len, m),
+        _ => (),
+    }
+    acc.into_iter_no_unmark().collect()
+}
+
+/// Cut `marks` down to the parts within `range`
+fn clip_marks(marks: Vec<Mark>, range: &Range<usize>) -> Vec<Mark> {
+    marks
+        .into_iter()
+        .filter_map(|mut mark| {
+            mark.start = mark.start.max(range.start);
+            mark.end = mark.end.min(range.end);
+            (mark.start < mark.end).then_some(mark)
+        })
+        .collect()
+}
+
 impl ReadDoc for Automerge {
     fn parents<O: AsRef<ExId>>(&self, obj: O) -> Result<Parents<'_>, AutomergeError> {
         self.parents_for(obj.as_ref(), None)
@@ -2004,6 +2559,33 @@ impl ReadDoc for Automerge {
         self.values_for(obj.as_ref(), clock)
     }
 
+    fn set_contains<O: AsRef<ExId>>(
+        &self,
+        obj: O,
+        value: &crate::ScalarValue,
+    ) -> Result<bool, AutomergeError> {
+        self.set_contains_for(obj.as_ref(), value, None)
+    }
+
+    fn set_contains_at<O: AsRef<ExId>>(
+        &self,
+        obj: O,
+        value: &crate::ScalarValue,
+        heads: &[ChangeHash],
+    ) -> Result<bool, AutomergeError> {
+        let clock = self.clock_at(heads);
+        self.set_contains_for(obj.as_ref(), value, clock)
+    }
+
+    fn set_values<O: AsRef<ExId>>(&self, obj: O) -> SetValues<'_> {
+        self.set_values_for(obj.as_ref(), None)
+    }
+
+    fn set_values_at<O: AsRef<ExId>>(&self, obj: O, heads: &[ChangeHash]) -> SetValues<'_> {
+        let clock = self.clock_at(heads);
+        self.set_values_for(obj.as_ref(), clock)
+    }
+
     fn length<O: AsRef<ExId>>(&self, obj: O) -> usize {
         self.length_for(obj.as_ref(), None)
     }
@@ -2061,6 +2643,41 @@ impl ReadDoc for Automerge {
         self.get_cursor_position_for(obj.as_ref(), cursor, clock)
     }
 
+    fn get_cursor_positions<O: AsRef<ExId>>(
+        &self,
+        obj: O,
+        cursors: &[Cursor],
+        at: Option<&[ChangeHash]>,
+    ) -> Result<Vec<usize>, AutomergeError> {
+        let clock = at.and_then(|heads| self.clock_at(heads));
+        let cursors = cursors
+            .iter()
+            .map(|cursor| (cursor, CursorAnchor::At))
+            .collect::<Vec<_>>();
+        self.get_cursor_positions_for(obj.as_ref(), &cursors, clock)
+    }
+
+    fn get_cursor_range<O: AsRef<ExId>>(
+        &self,
+        obj: O,
+        range: Range<usize>,
+        expand: ExpandMark,
+        at: Option<&[ChangeHash]>,
+    ) -> Result<CursorRange, AutomergeError> {
+        let clock = at.and_then(|heads| self.clock_at(heads));
+        self.get_cursor_range_for(obj.as_ref(), range, expand, clock)
+    }
+
+    fn get_cursor_range_positions<O: AsRef<ExId>>(
+        &self,
+        obj: O,
+        ranges: &[CursorRange],
+        at: Option<&[ChangeHash]>,
+    ) -> Result<Vec<CursorRangePosition>, AutomergeError> {
+        let clock = at.and_then(|heads| self.clock_at(heads));
+        self.get_cursor_range_positions_for(obj.as_ref(), ranges, clock)
+    }
+
     fn text_at<O: AsRef<ExId>>(
         &self,
         obj: O,
@@ -2107,6 +2724,37 @@ impl ReadDoc for Automerge {
         self.get_marks_for(obj.as_ref(), index, clock)
     }
 
+    fn query_marks<O: AsRef<ExId>>(
+        &self,
+        obj: O,
+        query: &MarkQuery,
+        heads: Option<&[ChangeHash]>,
+    ) -> Result<Vec<Mark>, AutomergeError> {
+        let clock = heads.and_then(|h| self.clock_at(h));
+        self.query_marks_for(obj.as_ref(), query, clock)
+    }
+
+    fn next_mark_change<O: AsRef<ExId>>(
+        &self,
+        obj: O,
+        name: &str,
+        index: usize,
+        heads: Option<&[ChangeHash]>,
+    ) -> Result<Option<usize>, AutomergeError> {
+        let clock = heads.and_then(|h| self.clock_at(h));
+        self.next_mark_change_for(obj.as_ref(), name, index, clock)
+    }
+
+    fn grapheme_range<O: AsRef<ExId>>(
+        &self,
+        obj: O,
+        range: Range<usize>,
+        heads: Option<&[ChangeHash]>,
+    ) -> Result<Range<usize>, AutomergeError> {
+        let clock = heads.and_then(|h| self.clock_at(h));
+        self.grapheme_range_for(obj.as_ref(), range, clock)
+    }
+
     fn get<O: AsRef<ExId>, P: Into<Prop>>(
         &self,
         obj: O,
@@ -2178,6 +2826,69 @@ impl ReadDoc for Automerge {
         }
     }
 
+    fn detailed_stats(&self) -> crate::read::DetailedStats {
+        let mut objects = self
+            .ops
+            .obj_op_counts()
+            .into_iter()
+            .map(|counts| crate::read::ObjectStats {
+                obj: self.ops.id_to_exid(counts.obj.0),
+                obj_type: counts.obj_type,
+                num_ops: counts.num_ops,
+                live_ops: counts.live_ops,
+                tombstones: counts.tombstones,
+                marks: counts.marks,
+                length: self.ops.seq_length(&counts.obj, self.text_encoding(), None),
+            })
+            .collect::<Vec<_>>();
+        objects.sort_by(|a, b| b.num_ops.cmp(&a.num_ops));
+
+        let mut actors = self
+            .ops
+            .actors
+            .iter()
+            .zip(self.ops.ops_per_actor())
+            .map(|(actor, num_ops)| crate::read::ActorStats {
+                actor: actor.clone(),
+                num_changes: 0,
+                num_ops,
+                change_bytes: 0,
+            })
+            .collect::<Vec<_>>();
+        // the sizes come from the change graph so that no change has to be rebuilt from the ops
+        let changes = self
+            .change_graph
+            .change_sizes()
+            .map(|change| {
+                let actor = &mut actors[change.actor];
+                actor.num_changes += 1;
+                actor.change_bytes += change.bytes;
+                crate::read::ChangeStats {
+                    hash: change.hash,
+                    actor: actor.actor.clone(),
+                    seq: change.seq,
+                    num_ops: change.num_ops,
+                    bytes: change.bytes,
+                }
+            })
+            .collect();
+
+        let columns = self
+            .ops
+            .column_byte_lens()
+            .into_iter()
+            .map(|(name, bytes)| crate::read::ColumnStats { name, bytes })
+            .collect();
+
+        crate::read::DetailedStats {
+            stats: self.stats(),
+            objects,
+            actors,
+            changes,
+            columns,
+        }
+    }
+
     fn text_encoding(&self) -> TextEncoding {
         self.ops.text_encoding
     }
@@ -2192,26 +2903,16 @@ impl Default for Automerge {
 /// Options to pass to [`Automerge::save_with_options()`] and [`crate::AutoCommit::save_with_options()`]
 #[derive(Debug)]
 pub struct SaveOptions {
-    /// Whether to apply DEFLATE compression to the RLE encoded columns in the document
-    pub deflate: bool,
+    /// How to compress the RLE encoded columns in the document, the default is DEFLATE
+    pub compression: Compression,
     /// Whether to save changes which we do not have the dependencies for
     pub retain_orphans: bool,
 }
 
-impl SaveOptions {
-    fn compress(&self) -> CompressConfig {
-        if self.deflate {
-            CompressConfig::Threshold(change::DEFLATE_MIN_SIZE)
-        } else {
-            CompressConfig::None
-        }
-    }
-}
-
 impl std::default::Default for SaveOptions {
     fn default() -> Self {
         Self {
-            deflate: true,
+            compression: Compression::default(),
             retain_orphans: true,
         }
     }
@@ -104,7 +104,7 @@ impl<R: crate::ReadDoc> serde::Serialize for AutoSerdeVal<'_, R> {
         S: serde::Serializer,
     {
         match &self.val {
-            Value::Object(ObjType::Map | ObjType::Table) => {
+            Value::Object(ObjType::Map | ObjType::Table | ObjType::OrderedMap) => {
                 let map = AutoSerdeMap {
                     doc: self.doc,
                     obj: self.obj.clone(),
@@ -122,6 +122,7 @@ impl<R: crate::ReadDoc> serde::Serialize for AutoSerdeVal<'_, R> {
                 };
                 seq.serialize(serializer)
             }
+            Value::Object(ObjType::Set) => serializer.collect_seq(self.doc.set_values(&self.obj)),
             Value::Scalar(v) => v.serialize(serializer),
         }
     }
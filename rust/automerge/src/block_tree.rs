@@ -0,0 +1,842 @@
+//! A tree of the blocks in a rich text object
+//!
+//! Block markers (see [`Transactable::split_block`]) are stored inline in a text object and
+//! describe where they sit in the structure of a document with a `"type"` and a list of
+//! `"parents"`, the types of the blocks enclosing them from the outermost inwards. A
+//! [`BlockTree`] interprets these attributes and nests the blocks accordingly. The text following
+//! a block marker, up to the next marker, is the content of that block.
+//!
+//! Each entry in the parents of a block is matched against the block which is open at the same
+//! depth. If that block has the same type the new block is nested inside it, otherwise a
+//! container of that type is created. Containers created this way have no marker of their own
+//! and are shared by consecutive blocks with the same parents, so two list items with the parents
+//! `["blockquote"]` end up in the same blockquote, and a list item with the parents
+//! `["unordered-list-item"]` becomes a child of the list item before it.
+//!
+//! [`BlockTree::apply_patches`] keeps a tree up to date with the [`Patch`]es to its text object
+//! and translates them into [`BlockPatch`]es which address blocks by their path in the tree. Typing
+//! into a paragraph produces a splice into the content of that paragraph rather than a splice at
+//! an offset into the whole text.
+//!
+//! ## Example
+//!
+//! ```
+//! # use automerge::{AutoCommit, ObjType, ROOT, transaction::Transactable};
+//! use automerge::block_tree::{self, BlockPatch};
+//! use automerge::markdown::{self, MarkdownConfig};
+//!
+//! let mut doc = AutoCommit::new();
+//! let text = doc.put_object(ROOT, "text", ObjType::Text)?;
+//! let source = "> - one\n>   - two\n\nend";
+//! markdown::update_markdown(&mut doc, &text, source, &MarkdownConfig::default())?;
+//!
+//! let mut tree = block_tree::get_block_tree(&doc, &text)?;
+//! let quote = &tree.blocks()[0];
+//! assert_eq!(quote.block_type, "blockquote");
+//! assert_eq!(quote.children[0].text(), "one");
+//! assert_eq!(quote.children[0].children[0].text(), "two");
+//! assert_eq!(tree.blocks()[1].text(), "end");
+//!
+//! // append to "two", the item at path [0, 0, 0]
+//! doc.update_diff_cursor();
+//! let start = tree.content_start(&[0, 0, 0]).unwrap();
+//! doc.splice_text(&text, start + 3, 0, "!")?;
+//! let patches = tree.apply_patches(&text, &doc.diff_incremental())?;
+//! assert_eq!(
+//!     patches,
+//!     vec![BlockPatch::SpliceText {
+//!         path: vec![0, 0, 0],
+//!         index: 3,
+//!         value: "!".to_string(),
+//!         marks: None,
+//!     }]
+//! );
+//! assert_eq!(tree.get(&[0, 0, 0]).unwrap().text(), "two!");
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+use std::sync::Arc;
+
+use crate::error::HydrateError;
+use crate::hydrate;
+use crate::iter::Span;
+use crate::marks::{Mark, MarkSet};
+#[cfg(doc)]
+use crate::transaction::Transactable;
+use crate::{
+    AutomergeError, ChangeHash, ObjId, Patch, PatchAction, Prop, ReadDoc, ScalarValue,
+    TextEncoding, Value,
+};
+
+/// A run of text with the same marks
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TextRun {
+    pub text: String,
+    pub marks: Option<Arc<MarkSet>>,
+}
+
+/// A block in a [`BlockTree`]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Block {
+    /// The `"type"` of the block marker, or for a container without a marker the type named in
+    /// the parents of the blocks inside it. Empty for the root and for markers without a type.
+    pub block_type: String,
+    /// The `"attrs"` of the block marker
+    pub attrs: hydrate::Map,
+    /// Whether the block has a marker in the text, containers which are only named in the
+    /// parents of other blocks don't
+    pub has_marker: bool,
+    /// The text between the marker of this block and the next marker
+    pub content: Vec<TextRun>,
+    /// The blocks nested inside this one, which follow its content in the text
+    pub children: Vec<Block>,
+}
+
+impl Block {
+    /// The content of the block without its marks
+    pub fn text(&self) -> String {
+        self.content.iter().map(|run| run.text.as_str()).collect()
+    }
+}
+
+/// A change to a [`BlockTree`]
+///
+/// Blocks are addressed by their path, the indexes of the children to follow from the root of the
+/// tree. The empty path is the root, whose content is the text before the first block marker.
+/// Indexes into the content of a block are in the text encoding of the document.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BlockPatch {
+    /// Text was inserted into the content of a block
+    SpliceText {
+        path: Vec<usize>,
+        index: usize,
+        value: String,
+        marks: Option<Arc<MarkSet>>,
+    },
+    /// Text was removed from the content of a block
+    DeleteText {
+        path: Vec<usize>,
+        index: usize,
+        length: usize,
+    },
+    /// Marks were added to or removed from the content of a block. The ranges of the marks are
+    /// relative to the start of the content and removed marks have a null value.
+    Mark { path: Vec<usize>, marks: Vec<Mark> },
+    /// The type or attributes of a block changed
+    UpdateBlock {
+        path: Vec<usize>,
+        block_type: String,
+        attrs: hydrate::Map,
+    },
+    /// `delete` children of the block at `path`, starting at `index`, were replaced by `insert`
+    SpliceBlocks {
+        path: Vec<usize>,
+        index: usize,
+        delete: usize,
+        insert: Vec<Block>,
+    },
+}
+
+impl BlockPatch {
+    fn path_mut(&mut self) -> &mut Vec<usize> {
+        match self {
+            Self::SpliceText { path, .. }
+            | Self::DeleteText { path, .. }
+            | Self::Mark { path, .. }
+            | Self::UpdateBlock { path, .. }
+            | Self::SpliceBlocks { path, .. } => path,
+        }
+    }
+}
+
+/// The blocks of a rich text object arranged as a tree
+///
+/// See the [module documentation](self) for how the tree is built.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockTree {
+    root: Block,
+    /// The text as a flat list, the root content followed by every block marker and its content
+    entries: Vec<Entry>,
+    /// The path in the tree of the block for each entry
+    paths: Vec<Vec<usize>>,
+    text_encoding: TextEncoding,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Entry {
+    marker: Option<hydrate::Value>,
+    runs: Vec<TextRun>,
+}
+
+impl Entry {
+    fn marker_width(&self) -> usize {
+        usize::from(self.marker.is_some())
+    }
+}
+
+/// What a patch changed
+enum Edit {
+    None,
+    /// The content of some entries changed, the paths of the patches are filled in later
+    Content(Vec<(usize, BlockPatch)>),
+    /// Block markers were added, removed or updated
+    Structure,
+}
+
+/// Build the tree of blocks for `text`
+pub fn get_block_tree<R: ReadDoc, O: AsRef<ObjId>>(
+    doc: &R,
+    text: O,
+) -> Result<BlockTree, AutomergeError> {
+    Ok(BlockTree::from_spans(doc.spans(text)?, doc.text_encoding()))
+}
+
+/// Build the tree of blocks for `text` as at `heads`
+pub fn get_block_tree_at<R: ReadDoc, O: AsRef<ObjId>>(
+    doc: &R,
+    text: O,
+    heads: &[ChangeHash],
+) -> Result<BlockTree, AutomergeError> {
+    Ok(BlockTree::from_spans(
+        doc.spans_at(text, heads)?,
+        doc.text_encoding(),
+    ))
+}
+
+impl BlockTree {
+    /// Build a tree from a sequence of spans, as returned by [`ReadDoc::spans`]
+    pub fn from_spans<I: IntoIterator<Item = Span>>(spans: I, text_encoding: TextEncoding) -> Self {
+        let mut entries = vec![Entry {
+            marker: None,
+            runs: Vec::new(),
+        }];
+        for span in spans {
+            match span {
+                Span::Block(block) => entries.push(Entry {
+                    marker: Some(hydrate::Value::Map(block)),
+                    runs: Vec::new(),
+                }),
+                Span::Text { text, marks } => {
+                    let runs = &mut entries.last_mut().unwrap().runs;
+                    runs.push(TextRun { text, marks });
+                    normalize(runs);
+                }
+            }
+        }
+        let mut tree = Self {
+            root: Block::default(),
+            entries,
+            paths: Vec::new(),
+            text_encoding,
+        };
+        tree.build();
+        tree
+    }
+
+    /// The root of the tree, whose content is the text before the first block marker
+    pub fn root(&self) -> &Block {
+        &self.root
+    }
+
+    /// The top level blocks
+    pub fn blocks(&self) -> &[Block] {
+        &self.root.children
+    }
+
+    /// The block at `path`
+    pub fn get(&self, path: &[usize]) -> Option<&Block> {
+        path.iter()
+            .try_fold(&self.root, |block, index| block.children.get(*index))
+    }
+
+    /// The index in the text of the start of the content of the block at `path`, or `None` if
+    /// there is no such block or it has no marker
+    pub fn content_start(&self, path: &[usize]) -> Option<usize> {
+        let mut start = 0;
+        for (entry, entry_path) in self.entries.iter().zip(&self.paths) {
+            start += entry.marker_width();
+            if entry_path == path {
+                return Some(start);
+            }
+            start += self.width(&entry.runs);
+        }
+        None
+    }
+
+    /// The path of the block whose content contains the position `index` in the text and the
+    /// offset of `index` into that content. A position just before a block marker belongs to the
+    /// end of the preceding block.
+    pub fn block_at(&self, index: usize) -> Option<(&[usize], usize)> {
+        let (entry, offset) = self.locate(index)?;
+        Some((&self.paths[entry], offset))
+    }
+
+    /// Update the tree with the patches to the text object `text`, in the order they were
+    /// produced, and return the corresponding changes to the tree
+    ///
+    /// Patches which only change the content of blocks are translated one by one. Once a block
+    /// marker is added, removed or updated the remaining patches are applied to the text and the
+    /// changes to the tree are found by comparing it before and after the patches. Patches to
+    /// other objects are ignored.
+    pub fn apply_patches<'a, I: IntoIterator<Item = &'a Patch>>(
+        &mut self,
+        text: &ObjId,
+        patches: I,
+    ) -> Result<Vec<BlockPatch>, HydrateError> {
+        let mut result = Vec::new();
+        let mut before = None;
+        for patch in patches {
+            match self.apply_patch(text, patch)? {
+                Edit::None => {}
+                Edit::Content(changes) if before.is_none() => {
+                    for (entry, mut change) in changes {
+                        let path = &self.paths[entry];
+                        *change.path_mut() = path.clone();
+                        block_mut(&mut self.root, path).content = self.entries[entry].runs.clone();
+                        result.push(change);
+                    }
+                }
+                Edit::Content(_) => {}
+                Edit::Structure => {
+                    if before.is_none() {
+                        before = Some(std::mem::take(&mut self.root));
+                    }
+                }
+            }
+        }
+        if let Some(before) = before {
+            self.build();
+            diff_block(
+                &mut Vec::new(),
+                &before,
+                &self.root,
+                self.text_encoding,
+                &mut result,
+            );
+        }
+        Ok(result)
+    }
+
+    fn apply_patch(&mut self, text: &ObjId, patch: &Patch) -> Result<Edit, HydrateError> {
+        if &patch.obj != text {
+            let mut props = patch
+                .path
+                .iter()
+                .skip_while(|(obj, _)| obj != text)
+                .map(|(_, prop)| prop);
+            let Some(Prop::Seq(index)) = props.next() else {
+                return Ok(Edit::None);
+            };
+            let marker = self
+                .marker_at(*index)
+                .and_then(|entry| self.entries[entry].marker.as_mut())
+                .ok_or(HydrateError::InvalidIndex(*index))?;
+            marker.apply(props, self.text_encoding, patch.action.clone())?;
+            return Ok(Edit::Structure);
+        }
+        match &patch.action {
+            PatchAction::SpliceText {
+                index,
+                value,
+                marks,
+            } => {
+                let (entry, offset) = self
+                    .locate(*index)
+                    .ok_or(HydrateError::InvalidIndex(*index))?;
+                let marks = marks
+                    .clone()
+                    .map(MarkSet::without_unmarks)
+                    .filter(|m| !m.is_empty())
+                    .map(Arc::new);
+                let value = value.make_string();
+                self.insert_text(entry, offset, &value, marks.clone())?;
+                Ok(Edit::Content(vec![(
+                    entry,
+                    BlockPatch::SpliceText {
+                        path: Vec::new(),
+                        index: offset,
+                        value,
+                        marks,
+                    },
+                )]))
+            }
+            PatchAction::DeleteSeq { index, length } => {
+                if let Some((entry, offset)) = self.locate(*index) {
+                    if offset + length <= self.width(&self.entries[entry].runs) {
+                        let runs = &mut self.entries[entry].runs;
+                        delete_runs(runs, offset, *length, self.text_encoding)
+                            .ok_or(HydrateError::InvalidIndex(*index))?;
+                        return Ok(Edit::Content(vec![(
+                            entry,
+                            BlockPatch::DeleteText {
+                                path: Vec::new(),
+                                index: offset,
+                                length: *length,
+                            },
+                        )]));
+                    }
+                }
+                self.delete(*index, *length)?;
+                Ok(Edit::Structure)
+            }
+            PatchAction::Insert { index, values } => {
+                let mut index = *index;
+                for (value, _, _) in values.iter() {
+                    let (entry, offset) = self
+                        .locate(index)
+                        .ok_or(HydrateError::InvalidIndex(index))?;
+                    match value {
+                        Value::Scalar(s) => {
+                            let s = s.to_str().unwrap_or("\u{fffc}");
+                            self.insert_text(entry, offset, s, None)?;
+                            index += self.text_encoding.width(s);
+                        }
+                        Value::Object(_) => {
+                            let runs = split_off(
+                                &mut self.entries[entry].runs,
+                                offset,
+                                self.text_encoding,
+                            )
+                            .ok_or(HydrateError::InvalidIndex(index))?;
+                            let marker = hydrate::Value::new(value.clone(), self.text_encoding);
+                            self.entries.insert(
+                                entry + 1,
+                                Entry {
+                                    marker: Some(marker),
+                                    runs,
+                                },
+                            );
+                            index += 1;
+                        }
+                    }
+                }
+                Ok(Edit::Structure)
+            }
+            PatchAction::PutSeq {
+                index,
+                value: (value, _),
+                ..
+            } => {
+                match self.marker_at(*index) {
+                    Some(entry) => {
+                        self.entries[entry].marker =
+                            Some(hydrate::Value::new(value.clone(), self.text_encoding));
+                    }
+                    None => {
+                        let (entry, offset) = self
+                            .locate(*index)
+                            .ok_or(HydrateError::InvalidIndex(*index))?;
+                        let s = match value {
+                            Value::Scalar(s) => s.to_str().unwrap_or("\u{fffc}"),
+                            Value::Object(_) => "\u{fffc}",
+                        };
+                        let runs = &mut self.entries[entry].runs;
+                        delete_runs(runs, offset, 1, self.text_encoding)
+                            .ok_or(HydrateError::InvalidIndex(*index))?;
+                        self.insert_text(entry, offset, s, None)?;
+                    }
+                }
+                Ok(Edit::Structure)
+            }
+            PatchAction::Mark { marks } => Ok(Edit::Content(self.mark(marks))),
+            PatchAction::PutMap { .. }
+            | PatchAction::Increment { .. }
+            | PatchAction::Conflict { .. }
+            | PatchAction::DeleteMap { .. }
+            | PatchAction::Move { .. }
+            | PatchAction::Detach { .. }
+            | PatchAction::Attach { .. }
+            | PatchAction::SetAdd { .. }
+            | PatchAction::SetRemove { .. }
+            | PatchAction::MoveKey { .. } => Ok(Edit::None),
+        }
+    }
+
+    fn width(&self, runs: &[TextRun]) -> usize {
+        runs.iter()
+            .map(|run| self.text_encoding.width(&run.text))
+            .sum()
+    }
+
+    /// The entry whose content contains `index` and the offset of `index` into the content
+    fn locate(&self, index: usize) -> Option<(usize, usize)> {
+        let mut start = 0;
+        for (i, entry) in self.entries.iter().enumerate() {
+            let content = start + entry.marker_width();
+            let end = content + self.width(&entry.runs);
+            if (content..=end).contains(&index) {
+                return Some((i, index - content));
+            }
+            start = end;
+        }
+        None
+    }
+
+    /// The entry whose marker is at `index`
+    fn marker_at(&self, index: usize) -> Option<usize> {
+        let mut start = 0;
+        for (i, entry) in self.entries.iter().enumerate() {
+            if entry.marker.is_some() && start == index {
+                return Some(i);
+            }
+            start += entry.marker_width() + self.width(&entry.runs);
+        }
+        None
+    }
+
+    fn insert_text(
+        &mut self,
+        entry: usize,
+        offset: usize,
+        text: &str,
+        marks: Option<Arc<MarkSet>>,
+    ) -> Result<(), HydrateError> {
+        let runs = &mut self.entries[entry].runs;
+        let at = split_runs(runs, offset, self.text_encoding)
+            .ok_or(HydrateError::InvalidIndex(offset))?;
+        runs.insert(
+            at,
+            TextRun {
+                text: text.to_string(),
+                marks,
+            },
+        );
+        normalize(runs);
+        Ok(())
+    }
+
+    /// Delete a range of the text which includes block markers, the content of a deleted marker
+    /// is appended to the content of the preceding entry
+    fn delete(&mut self, index: usize, length: usize) -> Result<(), HydrateError> {
+        let end = index + length;
+        // the start of each entry and the end of its content, before any content is moved
+        let mut bounds = Vec::with_capacity(self.entries.len());
+        let mut start = 0;
+        for entry in &self.entries {
+            let content_end = start + entry.marker_width() + self.width(&entry.runs);
+            bounds.push((start, content_end));
+            start = content_end;
+        }
+        if end > start {
+            return Err(HydrateError::InvalidIndex(end));
+        }
+        for (i, (start, content_end)) in bounds.into_iter().enumerate().rev() {
+            let content = start + self.entries[i].marker_width();
+            let (from, to) = (index.max(content), end.min(content_end));
+            if from < to {
+                let runs = &mut self.entries[i].runs;
+                delete_runs(runs, from - content, to - from, self.text_encoding)
+                    .ok_or(HydrateError::InvalidIndex(from))?;
+            }
+            if self.entries[i].marker.is_some() && (index..end).contains(&start) {
+                let removed = self.entries.remove(i);
+                let runs = &mut self.entries[i - 1].runs;
+                runs.extend(removed.runs);
+                normalize(runs);
+            }
+        }
+        Ok(())
+    }
+
+    fn mark(&mut self, marks: &[Mark]) -> Vec<(usize, BlockPatch)> {
+        let mut changes: Vec<(usize, BlockPatch)> = Vec::new();
+        for mark in marks {
+            let mut start = 0;
+            for i in 0..self.entries.len() {
+                let content = start + self.entries[i].marker_width();
+                let end = content + self.width(&self.entries[i].runs);
+                start = end;
+                let (from, to) = (mark.start.max(content), mark.end.min(end));
+                if from >= to {
+                    continue;
+                }
+                let (from, to) = (from - content, to - content);
+                mark_runs(
+                    &mut self.entries[i].runs,
+                    from,
+                    to,
+                    mark,
+                    self.text_encoding,
+                );
+                let relative = Mark {
+                    start: from,
+                    end: to,
+                    ..mark.clone()
+                };
+                match changes.iter_mut().find(|(entry, _)| *entry == i) {
+                    Some((_, BlockPatch::Mark { marks, .. })) => marks.push(relative),
+                    _ => changes.push((
+                        i,
+                        BlockPatch::Mark {
+                            path: Vec::new(),
+                            marks: vec![relative],
+                        },
+                    )),
+                }
+            }
+        }
+        changes
+    }
+
+    /// Rebuild the tree and the paths of the entries from the entries
+    fn build(&mut self) {
+        let mut root = Block {
+            content: self.entries[0].runs.clone(),
+            ..Default::default()
+        };
+        let mut paths = vec![Vec::new()];
+        // the path from the root to the innermost open block
+        let mut open: Vec<usize> = Vec::new();
+        for entry in &self.entries[1..] {
+            let (block_type, parents, attrs) = marker_info(entry.marker.as_ref());
+            for (depth, parent) in parents.iter().enumerate() {
+                if depth < open.len() && block_mut(&mut root, &open[..=depth]).block_type == *parent
+                {
+                    continue;
+                }
+                open.truncate(depth);
+                let container = block_mut(&mut root, &open);
+                container.children.push(Block {
+                    block_type: parent.clone(),
+                    ..Default::default()
+                });
+                open.push(container.children.len() - 1);
+            }
+            open.truncate(parents.len());
+            let container = block_mut(&mut root, &open);
+            container.children.push(Block {
+                block_type,
+                attrs,
+                has_marker: true,
+                content: entry.runs.clone(),
+                children: Vec::new(),
+            });
+            open.push(container.children.len() - 1);
+            paths.push(open.clone());
+        }
+        self.root = root;
+        self.paths = paths;
+    }
+}
+
+fn block_mut<'a>(root: &'a mut Block, path: &[usize]) -> &'a mut Block {
+    path.iter()
+        .fold(root, |block, index| &mut block.children[*index])
+}
+
+/// The type, parents and attributes of a block marker
+fn marker_info(marker: Option<&hydrate::Value>) -> (String, Vec<String>, hydrate::Map) {
+    let Some(hydrate::Value::Map(marker)) = marker else {
+        return Default::default();
+    };
+    let as_string = |value: &hydrate::Value| match value {
+        hydrate::Value::Scalar(s) => s.to_str().unwrap_or_default().to_string(),
+        _ => String::new(),
+    };
+    let block_type = marker.get("type").map(as_string).unwrap_or_default();
+    let parents = match marker.get("parents") {
+        Some(hydrate::Value::List(parents)) => {
+            parents.iter().map(|p| as_string(&p.value)).collect()
+        }
+        _ => Vec::new(),
+    };
+    let attrs = match marker.get("attrs") {
+        Some(hydrate::Value::Map(attrs)) => attrs.clone(),
+        _ => hydrate::Map::default(),
+    };
+    (block_type, parents, attrs)
+}
+
+/// Split the runs so that a run starts at `offset` and return the index of that run
+fn split_runs(runs: &mut Vec<TextRun>, offset: usize, encoding: TextEncoding) -> Option<usize> {
+    let mut start = 0;
+    for i in 0..runs.len() {
+        let width = encoding.width(&runs[i].text);
+        if offset == start {
+            return Some(i);
+        }
+        if offset < start + width {
+            let at = encoding.byte_index(&runs[i].text, offset - start)?;
+            let tail = runs[i].text.split_off(at);
+            let marks = runs[i].marks.clone();
+            runs.insert(i + 1, TextRun { text: tail, marks });
+            return Some(i + 1);
+        }
+        start += width;
+    }
+    (offset == start).then_some(runs.len())
+}
+
+fn split_off(
+    runs: &mut Vec<TextRun>,
+    offset: usize,
+    encoding: TextEncoding,
+) -> Option<Vec<TextRun>> {
+    let at = split_runs(runs, offset, encoding)?;
+    Some(runs.split_off(at))
+}
+
+fn delete_runs(
+    runs: &mut Vec<TextRun>,
+    offset: usize,
+    length: usize,
+    encoding: TextEncoding,
+) -> Option<()> {
+    let from = split_runs(runs, offset, encoding)?;
+    let to = split_runs(runs, offset + length, encoding)?;
+    runs.drain(from..to);
+    normalize(runs);
+    Some(())
+}
+
+fn mark_runs(runs: &mut Vec<TextRun>, from: usize, to: usize, mark: &Mark, encoding: TextEncoding) {
+    let (Some(from), Some(to)) = (
+        split_runs(runs, from, encoding),
+        split_runs(runs, to, encoding),
+    ) else {
+        return;
+    };
+    for run in &mut runs[from..to] {
+        let mut marks = run.marks.as_deref().cloned().unwrap_or_default();
+        match &mark.value {
+            ScalarValue::Null => marks.remove(&mark.name),
+            value => marks.insert(mark.name.clone(), value.clone()),
+        }
+        run.marks = (!marks.is_empty()).then(|| Arc::new(marks));
+    }
+    normalize(runs);
+}
+
+/// Remove empty runs and merge neighbouring runs with the same marks
+fn normalize(runs: &mut Vec<TextRun>) {
+    let mut merged: Vec<TextRun> = Vec::with_capacity(runs.len());
+    for run in runs.drain(..) {
+        match merged.last_mut() {
+            _ if run.text.is_empty() => {}
+            Some(last) if last.marks == run.marks => last.text.push_str(&run.text),
+            _ => merged.push(run),
+        }
+    }
+    *runs = merged;
+}
+
+/// Whether `after` can be described as a change to `before` rather than a replacement
+fn same_block(before: &Block, after: &Block) -> bool {
+    before.has_marker == after.has_marker
+        && (before.has_marker || before.block_type == after.block_type)
+}
+
+fn diff_block(
+    path: &mut Vec<usize>,
+    before: &Block,
+    after: &Block,
+    encoding: TextEncoding,
+    out: &mut Vec<BlockPatch>,
+) {
+    if before.block_type != after.block_type || before.attrs != after.attrs {
+        out.push(BlockPatch::UpdateBlock {
+            path: path.clone(),
+            block_type: after.block_type.clone(),
+            attrs: after.attrs.clone(),
+        });
+    }
+    diff_content(path, &before.content, &after.content, encoding, out);
+
+    let (old, new) = (&before.children, &after.children);
+    let prefix = old.iter().zip(new).take_while(|(b, a)| b == a).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(b, a)| b == a)
+        .count();
+    let (old, new) = (
+        &old[prefix..old.len() - suffix],
+        &new[prefix..new.len() - suffix],
+    );
+    if old.len() == new.len() && old.iter().zip(new).all(|(b, a)| same_block(b, a)) {
+        for (i, (b, a)) in old.iter().zip(new).enumerate() {
+            path.push(prefix + i);
+            diff_block(path, b, a, encoding, out);
+            path.pop();
+        }
+    } else {
+        out.push(BlockPatch::SpliceBlocks {
+            path: path.clone(),
+            index: prefix,
+            delete: old.len(),
+            insert: new.to_vec(),
+        });
+    }
+}
+
+/// Describe the change from `before` to `after` as a deletion followed by insertions, keeping the
+/// text and marks they have in common at either end
+fn diff_content(
+    path: &[usize],
+    before: &[TextRun],
+    after: &[TextRun],
+    encoding: TextEncoding,
+    out: &mut Vec<BlockPatch>,
+) {
+    if before == after {
+        return;
+    }
+    let units = |runs: &'_ [TextRun]| -> Vec<(String, Option<Arc<MarkSet>>)> {
+        runs.iter()
+            .flat_map(|run| {
+                let units: Vec<String> = match encoding {
+                    TextEncoding::GraphemeCluster => {
+                        unicode_segmentation::UnicodeSegmentation::graphemes(
+                            run.text.as_str(),
+                            true,
+                        )
+                        .map(str::to_string)
+                        .collect()
+                    }
+                    _ => run.text.chars().map(String::from).collect(),
+                };
+                units.into_iter().map(|u| (u, run.marks.clone()))
+            })
+            .collect()
+    };
+    let (old, new) = (units(before), units(after));
+    let prefix = old.iter().zip(&new).take_while(|(b, a)| b == a).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(b, a)| b == a)
+        .count();
+    let width = |units: &[(String, Option<Arc<MarkSet>>)]| {
+        units.iter().map(|(u, _)| encoding.width(u)).sum::<usize>()
+    };
+    let mut index = width(&old[..prefix]);
+    let deleted = width(&old[prefix..old.len() - suffix]);
+    if deleted > 0 {
+        out.push(BlockPatch::DeleteText {
+            path: path.to_vec(),
+            index,
+            length: deleted,
+        });
+    }
+    let mut inserted: Vec<TextRun> = Vec::new();
+    for (unit, marks) in &new[prefix..new.len() - suffix] {
+        match inserted.last_mut() {
+            Some(last) if last.marks == *marks => last.text.push_str(unit),
+            _ => inserted.push(TextRun {
+                text: unit.clone(),
+                marks: marks.clone(),
+            }),
+        }
+    }
+    for run in inserted {
+        let width = encoding.width(&run.text);
+        out.push(BlockPatch::SpliceText {
+            path: path.to_vec(),
+            index,
+            value: run.text,
+            marks: run.marks,
+        });
+        index += width;
+    }
+}
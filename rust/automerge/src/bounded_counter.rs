@@ -0,0 +1,183 @@
+//! Counters which never go below zero
+//!
+//! A bounded counter is a map which is only ever changed through
+//! [`Transactable::bounded_counter_increment`], [`Transactable::bounded_counter_decrement`] and
+//! [`Transactable::bounded_counter_transfer`]. It works by escrow: the value of the counter is
+//! split into rights held by each actor, and an actor can only decrement the counter by as much
+//! as the rights it holds. Incrementing the counter gives the actor which did so rights to the
+//! increment, and an actor can transfer rights it holds to another actor. As the rights of an
+//! actor can only be spent by that actor, concurrent decrements can never take the counter below
+//! zero.
+//!
+//! Each actor keeps a total of its increments, decrements and transfers to every other actor in
+//! a counter in the map which no other actor writes to, so the map never has conflicts. The keys
+//! are:
+//!
+//! * `inc:<actor>` for the increments of `actor`
+//! * `dec:<actor>` for the decrements of `actor`
+//! * `xfer:<from>:<to>` for the rights transferred from `from` to `to`
+//!
+//! Where the actors are hex encoded. Keys which are not one of these, or whose value is not a
+//! counter, are ignored.
+//!
+//! ```
+//! # use automerge::{transaction::Transactable, ActorId, AutoCommit, ObjType, ReadDoc, ROOT};
+//! let mut doc = AutoCommit::new().with_actor(ActorId::from([1]));
+//! let stock = doc.put_object(ROOT, "stock", ObjType::Map).unwrap();
+//! doc.bounded_counter_increment(&stock, 10).unwrap();
+//!
+//! let mut other = doc.fork().with_actor(ActorId::from([2]));
+//! doc.bounded_counter_transfer(&stock, &ActorId::from([2]), 4).unwrap();
+//! other.merge(&mut doc).unwrap();
+//!
+//! // each actor can only spend the rights it holds
+//! assert!(doc.bounded_counter_decrement(&stock, 7).is_err());
+//! doc.bounded_counter_decrement(&stock, 6).unwrap();
+//! other.bounded_counter_decrement(&stock, 4).unwrap();
+//!
+//! doc.merge(&mut other).unwrap();
+//! assert_eq!(doc.bounded_counter(&stock).unwrap().value(), 0);
+//! ```
+//!
+//! [`Transactable::bounded_counter_increment`]: crate::transaction::Transactable::bounded_counter_increment
+//! [`Transactable::bounded_counter_decrement`]: crate::transaction::Transactable::bounded_counter_decrement
+//! [`Transactable::bounded_counter_transfer`]: crate::transaction::Transactable::bounded_counter_transfer
+
+use std::collections::BTreeMap;
+
+use crate::iter::MapRangeItem;
+use crate::op_set2::types::ScalarValue;
+use crate::{ActorId, AutomergeError, ValueRef};
+
+/// The state of a bounded counter, see the [module docs](self)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BoundedCounter {
+    increments: BTreeMap<ActorId, i64>,
+    decrements: BTreeMap<ActorId, i64>,
+    transfers: BTreeMap<(ActorId, ActorId), i64>,
+}
+
+impl BoundedCounter {
+    pub(crate) fn from_items<'a, I: Iterator<Item = MapRangeItem<'a>>>(items: I) -> Self {
+        let mut counter = Self::default();
+        for item in items {
+            let ValueRef::Scalar(ScalarValue::Counter(n)) = item.value else {
+                continue;
+            };
+            match Key::parse(&item.key) {
+                Some(Key::Increments(actor)) => {
+                    counter.increments.insert(actor, n);
+                }
+                Some(Key::Decrements(actor)) => {
+                    counter.decrements.insert(actor, n);
+                }
+                Some(Key::Transfers(from, to)) => {
+                    counter.transfers.insert((from, to), n);
+                }
+                None => {}
+            }
+        }
+        counter
+    }
+
+    /// The value of the counter
+    pub fn value(&self) -> i64 {
+        self.increments.values().sum::<i64>() - self.decrements.values().sum::<i64>()
+    }
+
+    /// How much `actor` can decrement the counter by, or transfer to other actors
+    pub fn rights(&self, actor: &ActorId) -> i64 {
+        let mut rights = self.increments.get(actor).copied().unwrap_or(0)
+            - self.decrements.get(actor).copied().unwrap_or(0);
+        for ((from, to), n) in &self.transfers {
+            if to == actor {
+                rights += n;
+            }
+            if from == actor {
+                rights -= n;
+            }
+        }
+        rights
+    }
+
+    /// The actors which hold rights to the counter, with the rights they hold
+    pub fn holders(&self) -> impl Iterator<Item = (&ActorId, i64)> + '_ {
+        let mut actors = self.increments.keys().collect::<Vec<_>>();
+        actors.extend(self.transfers.keys().map(|(_, to)| to));
+        actors.sort();
+        actors.dedup();
+        actors
+            .into_iter()
+            .map(|actor| (actor, self.rights(actor)))
+            .filter(|(_, rights)| *rights > 0)
+    }
+
+    /// The key `actor` records `op` in and the amount to add to it, or an error if `actor` does
+    /// not hold enough rights for it
+    pub(crate) fn apply(
+        &self,
+        actor: &ActorId,
+        op: BoundedCounterOp,
+    ) -> Result<Option<(String, i64)>, AutomergeError> {
+        let (key, amount) = match op {
+            BoundedCounterOp::Increment(n) => (Key::Increments(actor.clone()), n),
+            BoundedCounterOp::Decrement(n) => (Key::Decrements(actor.clone()), n),
+            BoundedCounterOp::Transfer(to, _) if &to == actor => return Ok(None),
+            BoundedCounterOp::Transfer(to, n) => (Key::Transfers(actor.clone(), to), n),
+        };
+        if amount == 0 {
+            return Ok(None);
+        }
+        let available = self.rights(actor);
+        if !matches!(key, Key::Increments(_)) && amount > available.max(0) as u64 {
+            return Err(AutomergeError::InsufficientRights {
+                requested: amount,
+                available: available.max(0) as u64,
+            });
+        }
+        let amount = i64::try_from(amount).map_err(|_| AutomergeError::InvalidValueType {
+            expected: "an amount no greater than i64::MAX".to_string(),
+            unexpected: amount.to_string(),
+        })?;
+        Ok(Some((key.to_string(), amount)))
+    }
+}
+
+/// A change to a bounded counter
+pub(crate) enum BoundedCounterOp {
+    Increment(u64),
+    Decrement(u64),
+    Transfer(ActorId, u64),
+}
+
+enum Key {
+    Increments(ActorId),
+    Decrements(ActorId),
+    Transfers(ActorId, ActorId),
+}
+
+impl Key {
+    fn parse(key: &str) -> Option<Self> {
+        let actor = |s: &str| ActorId::try_from(s).ok();
+        let (kind, rest) = key.split_once(':')?;
+        match kind {
+            "inc" => Some(Self::Increments(actor(rest)?)),
+            "dec" => Some(Self::Decrements(actor(rest)?)),
+            "xfer" => {
+                let (from, to) = rest.split_once(':')?;
+                Some(Self::Transfers(actor(from)?, actor(to)?))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Key {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Increments(actor) => write!(f, "inc:{}", actor),
+            Self::Decrements(actor) => write!(f, "dec:{}", actor),
+            Self::Transfers(from, to) => write!(f, "xfer:{}:{}", from, to),
+        }
+    }
+}
@@ -278,6 +278,14 @@ mod convert_expanded {
                 None
             }
         }
+
+        fn target(&self) -> Option<Self::OpId> {
+            if let legacy::OpType::MoveObject(target) = &self.action {
+                Some(target)
+            } else {
+                None
+            }
+        }
     }
 
     impl<'a> convert::OpId<&'a ActorId> for &'a legacy::OpId {
@@ -306,7 +314,12 @@ impl From<&Change> for crate::ExpandedChange {
                     value: o.val,
                     expand: o.expand,
                     mark_name: o.mark_name,
-                }),
+                    target: o.target.as_ref().and_then(|t| t.id()).map(|id| {
+                        crate::legacy::OpId::new(id.counter(), actors.get(&id.actor()).unwrap())
+                    }),
+                })
+                // SAFETY: the ops of a verified change have been checked by `ChangeOpsIter`
+                .unwrap(),
                 insert: o.insert,
                 key: match o.key {
                     StoredKey::Elem(e) if e.is_head() => {
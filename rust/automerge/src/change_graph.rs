@@ -18,6 +18,9 @@ use crate::{
     Change, ChangeHash,
 };
 
+mod shallow;
+use shallow::Truncation;
+
 /// The graph of changes
 ///
 /// This is a sort of adjacency list based representation, except that instead of using linked
@@ -34,6 +37,8 @@ pub(crate) struct ChangeGraph {
     max_ops: Vec<u32>,
     max_op: u32,
     num_ops: hexane::Column<u64>,
+    /// The length of each change when encoded as a change chunk, 0 for stubs
+    change_bytes: hexane::Column<u64>,
     timestamps: hexane::DeltaColumn<i64>,
     messages: hexane::Column<Option<String>>,
     extra_bytes_meta: hexane::PrefixColumn<ValueMeta>,
@@ -44,6 +49,8 @@ pub(crate) struct ChangeGraph {
     seq_index: Vec<Vec<NodeIdx>>,
     fragment_top: SeqClock,
     fragments: Vec<FragmentNode>,
+    /// Set if the history of this graph has been truncated, see [`shallow`]
+    base: Option<Box<Truncation>>,
 }
 
 pub(crate) struct ChangeGraphCols(ChangeGraph);
@@ -91,6 +98,7 @@ impl ChangeGraph {
             max_ops: Vec::new(),
             max_op: 0,
             num_ops: hexane::Column::new(),
+            change_bytes: hexane::Column::new(),
             seq: Vec::new(),
             parents: Vec::new(),
             messages: hexane::Column::new(),
@@ -102,6 +110,7 @@ impl ChangeGraph {
             seq_index: vec![vec![]; num_actors],
             fragments: vec![],
             fragment_top: SeqClock::new(num_actors),
+            base: None,
         }
     }
 
@@ -110,17 +119,11 @@ impl ChangeGraph {
     }
 
     pub(crate) fn actor_ids(&self) -> impl Iterator<Item = usize> + '_ {
-        self.seq_index
-            .iter()
-            .enumerate()
-            .filter_map(|(i, v)| if !v.is_empty() { Some(i) } else { None })
+        self.all_actor_ids().filter(|i| self.seq_for_actor(*i) > 0)
     }
 
     pub(crate) fn unused_actors(&self) -> impl Iterator<Item = usize> + '_ {
-        self.seq_index
-            .iter()
-            .enumerate()
-            .filter_map(|(i, v)| if v.is_empty() { Some(i) } else { None })
+        self.all_actor_ids().filter(|i| self.seq_for_actor(*i) == 0)
     }
 
     pub(crate) fn heads(&self) -> impl Iterator<Item = ChangeHash> + '_ {
@@ -164,6 +167,9 @@ impl ChangeGraph {
         }
         self.fragment_top.rewrite_with_new_actor(idx);
         self.seq_index.insert(idx, vec![]);
+        if let Some(base) = &mut self.base {
+            base.insert_actor(idx);
+        }
     }
 
     pub(crate) fn remove_actor(&mut self, idx: usize) {
@@ -183,6 +189,9 @@ impl ChangeGraph {
             fragment.clock.remove_actor(idx)
         }
         self.fragment_top.remove_actor(idx);
+        if let Some(base) = &mut self.base {
+            base.remove_actor(idx);
+        }
     }
 
     pub(crate) fn len(&self) -> usize {
@@ -206,11 +215,8 @@ impl ChangeGraph {
     }
 
     pub(crate) fn max_op_for_actor(&mut self, actor_index: usize) -> u64 {
-        self.seq_index
-            .get(actor_index)
-            .and_then(|s| s.last())
-            .and_then(|index| self.max_ops.get(index.0 as usize).cloned())
-            .unwrap_or(0) as u64
+        let seq = self.seq_for_actor(actor_index) as u32;
+        self.max_op_for_seq(actor_index, seq).unwrap_or(0) as u64
     }
 
     pub(crate) fn seq_for_actor(&self, actor: usize) -> u64 {
@@ -218,6 +224,7 @@ impl ChangeGraph {
             .get(actor)
             .map(|v| v.len() as u64)
             .unwrap_or(0)
+            + self.truncated_seq(actor)
     }
 
     fn deps_iter(&self) -> impl Iterator<Item = NodeIdx> + '_ {
@@ -347,6 +354,7 @@ impl ChangeGraph {
                 .nodes_by_hash
                 .get(&hash)
                 .cloned()
+                .filter(|n| !self.is_stub(*n))
                 .ok_or(MissingDep(hash))?;
             let i = index.0 as usize;
             let actor = self.actors[i].into();
@@ -394,6 +402,7 @@ impl ChangeGraph {
                 self.nodes_by_hash
                     .get(&hash)
                     .cloned()
+                    .filter(|n| !self.is_stub(*n))
                     .ok_or(MissingDep(hash))
             })
             .collect::<Result<_, _>>()?;
@@ -401,6 +410,23 @@ impl ChangeGraph {
         Ok(self.get_build_metadata_for_indexes(indexes))
     }
 
+    /// The size of every change in the graph which is not a stub, in topological order
+    pub(crate) fn change_sizes(&self) -> impl Iterator<Item = ChangeSize> + '_ {
+        let num_stubs = self.num_stubs();
+        self.num_ops
+            .iter()
+            .zip(self.change_bytes.iter())
+            .enumerate()
+            .skip(num_stubs)
+            .map(|(i, (num_ops, bytes))| ChangeSize {
+                hash: self.hashes[i],
+                actor: self.actors[i].into(),
+                seq: self.seq[i] as u64,
+                num_ops: num_ops as usize,
+                bytes: bytes as usize,
+            })
+    }
+
     pub(crate) fn iter(&self) -> ChangeIter<'_> {
         ChangeIter {
             index: 0,
@@ -461,7 +487,9 @@ impl ChangeGraph {
             if let Some(seq) = clock.get_for_actor(&actor_index) {
                 // find the change in this actors sequence of changes that corresponds to the max_op
                 // recorded for them in the clock
-                change_indexes.extend(&actor_changes[seq.get() as usize..]);
+                let offset = self.truncated_seq(actor_index) as usize;
+                let start = (seq.get() as usize).saturating_sub(offset);
+                change_indexes.extend(&actor_changes[start..]);
             } else {
                 change_indexes.extend(&actor_changes[..]);
             }
@@ -475,7 +503,7 @@ impl ChangeGraph {
 
     pub(crate) fn get_hashes(&self, have_deps: &[ChangeHash]) -> Cow<'_, [ChangeHash]> {
         if have_deps.is_empty() {
-            Cow::Borrowed(&self.hashes)
+            Cow::Borrowed(&self.hashes[self.num_stubs()..])
         } else {
             let clock = self.seq_clock_for_heads(have_deps);
             Cow::Owned(
@@ -502,10 +530,16 @@ impl ChangeGraph {
         actor: usize,
         seq: u64,
     ) -> Result<ChangeHash, AutomergeError> {
-        self.seq_index
-            .get(actor)
-            .and_then(|v| v.get(seq as usize - 1))
-            .and_then(|i| self.hashes.get(i.0 as usize))
+        let offset = self.truncated_seq(actor);
+        let node = if seq > offset {
+            self.seq_index
+                .get(actor)
+                .and_then(|v| v.get((seq - offset) as usize - 1))
+                .copied()
+        } else {
+            self.stub_for(actor, seq)
+        };
+        node.and_then(|i| self.hashes.get(i.0 as usize))
             .ok_or(AutomergeError::InvalidSeq(seq))
             .copied()
     }
@@ -531,6 +565,8 @@ impl ChangeGraph {
             .extend(iter.clone().map(|(c, _)| c.max_op() as u32));
         self.num_ops
             .extend(iter.clone().map(|(c, _)| c.len() as u64));
+        self.change_bytes
+            .extend(iter.clone().map(|(c, _)| c.raw_bytes().len() as u64));
         self.timestamps
             .extend(iter.clone().map(|(c, _)| c.timestamp()));
         self.messages.extend(iter.clone().map(|(c, _)| c.message()));
@@ -560,7 +596,7 @@ impl ChangeGraph {
             self.update_heads(change);
 
             assert!(actor < self.seq_index.len());
-            assert_eq!(self.seq_index[actor].len() + 1, change.seq() as usize);
+            assert_eq!(self.seq_for_actor(actor) + 1, change.seq());
             self.seq_index[actor].push(node_idx);
 
             for parent_hash in change.deps().iter() {
@@ -655,7 +691,7 @@ impl ChangeGraph {
         std::iter::from_fn(move || {
             let idx = to_visit.pop_front()?;
             for p in self.parents(idx) {
-                if !seen.contains(&p) {
+                if !seen.contains(&p) && !self.is_stub(p) {
                     let actor = self.actors[p.0 as usize].into();
                     let seq = self.seq[p.0 as usize];
                     if clock.get_for_actor(&actor) < NonZeroU32::new(seq) {
@@ -669,11 +705,19 @@ impl ChangeGraph {
     }
 
     fn cache_fragments(&mut self) {
-        for n in 0..self.hashes.len() {
+        for n in self.num_stubs()..self.hashes.len() {
             self.cache_fragment(NodeIdx(n as u32))
         }
     }
 
+    fn cache_clocks(&mut self) {
+        for n in 0..(self.len() as u32) {
+            if (n + 1) % CACHE_STEP == 0 {
+                self.cache_clock(NodeIdx(n));
+            }
+        }
+    }
+
     fn cache_fragment(&mut self, head: NodeIdx) {
         let hash = &self.hashes[head.0 as usize];
         let level = hash.fragment_level();
@@ -782,13 +826,7 @@ impl ChangeGraph {
         let nodes = self.heads_to_nodes(heads);
         self.calculate_clock(nodes)
             .iter()
-            .map(|(actor, seq)| {
-                self.seq_index
-                    .get(actor)
-                    .and_then(|v| v.get(seq?.get() as usize - 1))
-                    .and_then(|i| self.max_ops.get(i.0 as usize))
-                    .copied()
-            })
+            .map(|(actor, seq)| self.max_op_for_seq(actor, seq?.get()))
             .collect()
     }
 
@@ -880,14 +918,20 @@ impl ChangeGraphCols {
 
     pub(crate) fn finalize(self, changes: &[Change]) -> ChangeGraph {
         let mut graph = self.0;
-        debug_assert_eq!(changes.len(), graph.len());
-        debug_assert!(graph.hashes.is_empty());
+        let num_stubs = graph.num_stubs();
+        debug_assert_eq!(num_stubs + changes.len(), graph.len());
+        debug_assert_eq!(graph.hashes.len(), num_stubs);
 
         // The encoded change columns only contain each change's maximum op.
         // `load()` estimates op counts from dependencies, but that is ambiguous
         // for an isolated actor whose first change can start above counter 1.
         // Reconstruction has the verified changes, so use their exact lengths.
-        graph.num_ops = changes.iter().map(|change| change.len() as u64).collect();
+        graph.num_ops = std::iter::repeat_n(0, num_stubs)
+            .chain(changes.iter().map(|change| change.len() as u64))
+            .collect();
+        graph.change_bytes = std::iter::repeat_n(0, num_stubs)
+            .chain(changes.iter().map(|change| change.raw_bytes().len() as u64))
+            .collect();
 
         for c in changes {
             let hash = c.hash();
@@ -896,12 +940,7 @@ impl ChangeGraphCols {
             graph.hashes.push(hash)
         }
 
-        for n in 0..(graph.len() as u32) {
-            if (n + 1) % CACHE_STEP == 0 {
-                graph.cache_clock(NodeIdx(n));
-            }
-        }
-
+        graph.cache_clocks();
         graph.cache_fragments();
 
         graph
@@ -1020,6 +1059,8 @@ impl ChangeGraphCols {
             max_ops,
             max_op,
             num_ops,
+            // filled in by `finalize` from the reconstructed changes
+            change_bytes: hexane::Column::new(),
             timestamps,
             messages,
             extra_bytes_meta,
@@ -1030,6 +1071,7 @@ impl ChangeGraphCols {
             seq_index,
             fragments,
             fragment_top,
+            base: None,
         }))
     }
 }
@@ -1620,6 +1662,15 @@ impl<'a> Iterator for ChangeIter<'a> {
     }
 }
 
+/// The size of a change, as returned by [`ChangeGraph::change_sizes`]
+pub(crate) struct ChangeSize {
+    pub(crate) hash: ChangeHash,
+    pub(crate) actor: usize,
+    pub(crate) seq: u64,
+    pub(crate) num_ops: usize,
+    pub(crate) bytes: usize,
+}
+
 #[derive(Debug, PartialEq, Clone)]
 struct FragmentNode {
     head: NodeIdx,
@@ -0,0 +1,372 @@
+//! Truncated history for shallow documents
+//!
+//! A shallow document replaces the history before some base heads with "stubs". A stub is a node
+//! in the change graph which keeps the hash, actor, seq and max op of a truncated change but has
+//! no ops and no dependencies. Its clock can't be derived from its (missing) ancestors so it is
+//! pinned in the clock cache. Stubs are only kept for the base heads and for truncated changes which
+//! are dependencies of retained changes, and always occupy the node indexes `0..num_stubs`.
+//!
+//! The ops of the truncated changes stay in the op set, only the change graph is truncated.
+
+use std::collections::BTreeSet;
+
+use super::{ChangeGraph, ChangeGraphCols, NodeIdx};
+use crate::{
+    clock::SeqClock,
+    error::AutomergeError,
+    op_set2::ValueMeta,
+    storage::{
+        base::{Stub, TruncatedActor},
+        document::ReconstructError as LoadError,
+        Base,
+    },
+    ChangeHash,
+};
+
+#[derive(Debug, Clone, Default)]
+pub(super) struct Truncation {
+    heads: Vec<ChangeHash>,
+    num_stubs: u32,
+    /// The number of truncated changes for each actor
+    seqs: Vec<u32>,
+    /// The max op of the truncated changes referenced by the clocks of the stubs for each actor,
+    /// sorted by seq
+    max_ops: Vec<Vec<(u32, u32)>>,
+}
+
+impl Truncation {
+    pub(super) fn insert_actor(&mut self, idx: usize) {
+        self.seqs.insert(idx, 0);
+        self.max_ops.insert(idx, vec![]);
+    }
+
+    pub(super) fn remove_actor(&mut self, idx: usize) {
+        if idx < self.seqs.len() {
+            assert_eq!(self.seqs[idx], 0);
+            self.seqs.remove(idx);
+            self.max_ops.remove(idx);
+        }
+    }
+}
+
+impl ChangeGraph {
+    /// The heads this graph was truncated at, if it has been truncated
+    pub(crate) fn base_heads(&self) -> Option<&[ChangeHash]> {
+        self.base.as_ref().map(|b| b.heads.as_slice())
+    }
+
+    pub(super) fn num_stubs(&self) -> usize {
+        self.base
+            .as_ref()
+            .map(|b| b.num_stubs as usize)
+            .unwrap_or(0)
+    }
+
+    pub(super) fn is_stub(&self, idx: NodeIdx) -> bool {
+        (idx.0 as usize) < self.num_stubs()
+    }
+
+    pub(crate) fn is_stub_hash(&self, hash: &ChangeHash) -> bool {
+        self.nodes_by_hash
+            .get(hash)
+            .map(|n| self.is_stub(*n))
+            .unwrap_or(false)
+    }
+
+    /// The number of changes by `actor` which have been truncated
+    pub(crate) fn truncated_seq(&self, actor: usize) -> u64 {
+        self.base
+            .as_ref()
+            .and_then(|b| b.seqs.get(actor))
+            .copied()
+            .unwrap_or(0) as u64
+    }
+
+    /// The hash, actor and seq of each stub
+    pub(crate) fn stubs(&self) -> impl Iterator<Item = (ChangeHash, usize, u64)> + '_ {
+        (0..self.num_stubs()).map(|i| {
+            (
+                self.hashes[i],
+                usize::from(self.actors[i]),
+                self.seq[i] as u64,
+            )
+        })
+    }
+
+    pub(super) fn stub_for(&self, actor: usize, seq: u64) -> Option<NodeIdx> {
+        (0..self.num_stubs())
+            .find(|i| usize::from(self.actors[*i]) == actor && self.seq[*i] as u64 == seq)
+            .map(|i| NodeIdx(i as u32))
+    }
+
+    /// The max op of the change by `actor` with `seq`
+    pub(super) fn max_op_for_seq(&self, actor: usize, seq: u32) -> Option<u32> {
+        let offset = self.truncated_seq(actor) as u32;
+        if seq > offset {
+            let idx = self
+                .seq_index
+                .get(actor)?
+                .get((seq - offset - 1) as usize)?;
+            self.max_ops.get(idx.0 as usize).copied()
+        } else {
+            let max_ops = self.base.as_ref()?.max_ops.get(actor)?;
+            let i = max_ops.binary_search_by_key(&seq, |(s, _)| *s).ok()?;
+            Some(max_ops[i].1)
+        }
+    }
+
+    /// Whether the clock of `heads` includes all of the base heads of this graph
+    ///
+    /// Returns the first base head which is not included otherwise. All of `heads` must be in the
+    /// graph.
+    pub(crate) fn covers_base(&self, heads: &[ChangeHash]) -> Result<(), ChangeHash> {
+        let Some(base) = self.base_heads() else {
+            return Ok(());
+        };
+        let clock = self.seq_clock_for_heads(heads);
+        for head in base {
+            let idx = self.nodes_by_hash[head].0 as usize;
+            let actor = usize::from(self.actors[idx]);
+            let seq = self.seq[idx];
+            if clock.get_for_actor(&actor).map(|s| s.get()).unwrap_or(0) < seq {
+                return Err(*head);
+            }
+        }
+        Ok(())
+    }
+
+    /// Create a new graph with the history before `base` replaced by stubs
+    pub(crate) fn truncate(&self, base: &[ChangeHash]) -> Result<ChangeGraph, AutomergeError> {
+        let base_nodes = base
+            .iter()
+            .map(|h| {
+                self.nodes_by_hash
+                    .get(h)
+                    .copied()
+                    .ok_or(AutomergeError::InvalidHash(*h))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if base_nodes.is_empty() {
+            return Ok(self.clone());
+        }
+
+        let len = self.len();
+        let num_actors = self.num_actors();
+        let mut truncated = vec![false; len];
+        self.traverse_ancestors(base_nodes.clone(), |n| {
+            truncated[n.0 as usize] = true;
+            true
+        });
+
+        // A stub has no parents so it can't become a retained change again
+        if let Some(stub) = (0..self.num_stubs()).find(|i| !truncated[*i]) {
+            return Err(AutomergeError::TruncatedHistory(self.hashes[stub]));
+        }
+
+        let mut is_stub = vec![false; len];
+        let mut last = vec![None; num_actors];
+        for n in &base_nodes {
+            is_stub[n.0 as usize] = true;
+        }
+        for n in self.node_ids() {
+            let i = n.0 as usize;
+            if truncated[i] {
+                last[usize::from(self.actors[i])] = Some(i);
+            } else {
+                for p in self.parents(n) {
+                    if truncated[p.0 as usize] {
+                        is_stub[p.0 as usize] = true;
+                    }
+                }
+            }
+        }
+
+        let stubs = (0..len).filter(|i| is_stub[*i]).collect::<Vec<_>>();
+        let retained = (0..len).filter(|i| !truncated[*i]).collect::<Vec<_>>();
+
+        let mut seqs = vec![0; num_actors];
+        for (actor, i) in last.iter().enumerate() {
+            if let Some(i) = i {
+                seqs[actor] = self.seq[*i];
+            }
+        }
+
+        let clocks = stubs
+            .iter()
+            .map(|i| self.calculate_clock(vec![NodeIdx(*i as u32)]))
+            .collect::<Vec<_>>();
+
+        let mut max_ops = vec![BTreeSet::new(); num_actors];
+        for clock in &clocks {
+            for (actor, seq) in clock.iter() {
+                if let Some(seq) = seq {
+                    let max_op = self.max_op_for_seq(actor, seq.get()).unwrap();
+                    max_ops[actor].insert((seq.get(), max_op));
+                }
+            }
+        }
+
+        let mut graph = ChangeGraph::new(num_actors);
+        let num_nodes = stubs.len() + retained.len();
+        let mut num_ops = Vec::with_capacity(num_nodes);
+        let mut change_bytes = Vec::with_capacity(num_nodes);
+        let mut timestamps = Vec::with_capacity(num_nodes);
+        let mut messages = Vec::with_capacity(num_nodes);
+        let mut extra_bytes_meta = Vec::with_capacity(num_nodes);
+        // stubs first, then the retained changes in their original (topological) order so parents
+        // are always added before their children
+        for (new, old) in stubs.iter().chain(retained.iter()).copied().enumerate() {
+            let node = NodeIdx(new as u32);
+            let hash = self.hashes[old];
+            graph.hashes.push(hash);
+            graph.nodes_by_hash.insert(hash, node);
+            graph.actors.push(self.actors[old]);
+            graph.seq.push(self.seq[old]);
+            graph.max_ops.push(self.max_ops[old]);
+            timestamps.push(self.timestamps.get(old).unwrap_or_default());
+            messages.push(self.messages.get(old).flatten().map(String::from));
+
+            let meta = self.extra_bytes_meta.get(old).unwrap();
+            let extra = &self.extra_bytes_raw[meta.prefix() as usize..meta.total() as usize];
+            extra_bytes_meta.push(ValueMeta::from(extra));
+            graph.extra_bytes_raw.extend_from_slice(extra);
+
+            if is_stub[old] {
+                num_ops.push(0);
+                change_bytes.push(0);
+                graph.parents.push(None);
+            } else {
+                num_ops.push(self.num_ops.get(old).unwrap_or_default());
+                change_bytes.push(self.change_bytes.get(old).unwrap_or_default());
+                graph.parents.push(None);
+                for p in self.parents(NodeIdx(old as u32)) {
+                    graph.add_parent(node, &self.hashes[p.0 as usize]);
+                }
+                let actor = usize::from(self.actors[old]);
+                graph.seq_index[actor].push(node);
+            }
+        }
+        graph.num_ops = num_ops.into_iter().collect();
+        graph.change_bytes = change_bytes.into_iter().collect();
+        graph.timestamps = timestamps.into_iter().collect();
+        graph.messages = messages.into_iter().collect();
+        graph.extra_bytes_meta = extra_bytes_meta.into_iter().collect();
+        graph.max_op = self.max_op;
+        graph.heads = self.heads.clone();
+
+        let mut heads = base.to_vec();
+        heads.sort_unstable();
+        heads.dedup();
+        graph.base = Some(Box::new(Truncation {
+            heads,
+            num_stubs: stubs.len() as u32,
+            seqs,
+            max_ops: max_ops
+                .into_iter()
+                .map(|m| m.into_iter().collect())
+                .collect(),
+        }));
+        for (i, clock) in clocks.into_iter().enumerate() {
+            graph.clock_cache.insert(NodeIdx(i as u32), clock);
+        }
+        graph.cache_clocks();
+        graph.cache_fragments();
+
+        Ok(graph)
+    }
+
+    /// The base chunk to save alongside the document chunk of this graph
+    pub(crate) fn export_base(&self) -> Option<Base> {
+        let base = self.base.as_ref()?;
+        let stubs = (0..base.num_stubs)
+            .map(|i| Stub {
+                hash: self.hashes[i as usize],
+                clock: self.clock_cache[&NodeIdx(i)]
+                    .iter()
+                    .filter_map(|(actor, seq)| Some((actor as u32, seq?.get())))
+                    .collect(),
+            })
+            .collect();
+        let actors = base
+            .seqs
+            .iter()
+            .zip(base.max_ops.iter())
+            .map(|(seq, max_ops)| TruncatedActor {
+                seq: *seq,
+                max_ops: max_ops.clone(),
+            })
+            .collect();
+        Some(Base::new(base.heads.clone(), stubs, actors))
+    }
+}
+
+impl ChangeGraphCols {
+    /// Turn the first rows of the loaded change columns into the stubs described by `base`
+    pub(crate) fn with_base(self, base: &Base) -> Result<Self, LoadError> {
+        let mut graph = self.0;
+        let num_actors = graph.num_actors();
+        let num_stubs = base.stubs.len();
+        if num_stubs > graph.len() || base.actors.len() != num_actors {
+            return Err(LoadError::InvalidBase);
+        }
+
+        for (i, stub) in base.stubs.iter().enumerate() {
+            if graph.parents[i].is_some() {
+                return Err(LoadError::InvalidBase);
+            }
+            let mut clock = SeqClock::new(num_actors);
+            for (actor, seq) in &stub.clock {
+                if *actor as usize >= num_actors {
+                    return Err(LoadError::InvalidActorId(*actor as usize));
+                }
+                clock.include(*actor as usize, Some(*seq));
+            }
+            graph.clock_cache.insert(NodeIdx(i as u32), clock);
+            graph.nodes_by_hash.insert(stub.hash, NodeIdx(i as u32));
+            graph.hashes.push(stub.hash);
+        }
+
+        graph.num_ops = (0..graph.len())
+            .map(|i| {
+                if i < num_stubs {
+                    0
+                } else {
+                    graph.num_ops.get(i).unwrap_or_default()
+                }
+            })
+            .collect();
+        for (actor, nodes) in graph.seq_index.iter_mut().enumerate() {
+            nodes.retain(|n| n.0 as usize >= num_stubs);
+            let seq = base.actors[actor].seq;
+            if let Some(first) = nodes.first() {
+                if graph.seq[first.0 as usize] != seq + 1 {
+                    return Err(LoadError::InvalidBase);
+                }
+            }
+        }
+
+        let stubs = &graph.hashes[..num_stubs];
+        if !base.heads.iter().all(|h| stubs.contains(h)) {
+            return Err(LoadError::InvalidBase);
+        }
+        let mut heads = base.heads.clone();
+        heads.sort_unstable();
+        heads.dedup();
+        graph.base = Some(Box::new(Truncation {
+            heads,
+            num_stubs: num_stubs as u32,
+            seqs: base.actors.iter().map(|a| a.seq).collect(),
+            max_ops: base.actors.iter().map(|a| a.max_ops.clone()).collect(),
+        }));
+
+        Ok(ChangeGraphCols(graph))
+    }
+
+    pub(crate) fn stub_hashes(&self) -> &[ChangeHash] {
+        &self.0.hashes[..self.0.num_stubs()]
+    }
+
+    pub(crate) fn base_heads(&self) -> &[ChangeHash] {
+        self.0.base_heads().unwrap_or(&[])
+    }
+}
@@ -37,6 +37,10 @@ impl ChangeBatch {
         self.changes.push(change);
         Ok(())
     }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &Change> {
+        self.changes.iter()
+    }
 }
 
 /// An indexed queue of unapplied changes that are not yet causally ready.
@@ -13,7 +13,7 @@ use crate::{
         leb128::{leb128_i64, leb128_u64},
         Input, ParseResult,
     },
-    ScalarValue,
+    Decimal, ScalarValue,
 };
 
 use super::{RawRange, RleRange};
@@ -251,12 +251,15 @@ impl Iterator for ValueIter<'_> {
                     ValueType::Timestamp => self.parse_input(val_meta, |input| {
                         leb128_i64(input).map(|(i, n)| (i, ScalarValue::Timestamp(n)))
                     }),
-                    ValueType::Unknown(code) => self.parse_raw(val_meta, |bytes| {
-                        Ok(ScalarValue::Unknown {
-                            type_code: code,
-                            bytes: bytes.to_vec(),
-                        })
+                    ValueType::Decimal => self.parse_raw(val_meta, |bytes| {
+                        let val = Decimal::from_bytes(bytes).ok_or_else(|| {
+                            DecodeColumnError::invalid_value("value", "invalid decimal")
+                        })?;
+                        Ok(ScalarValue::Decimal(val))
                     }),
+                    ValueType::Unknown(code) => {
+                        self.parse_raw(val_meta, |bytes| Ok(ScalarValue::from_unknown(code, bytes)))
+                    }
                     ValueType::Bytes => match self.raw.read_bytes(val_meta.length()) {
                         Err(e) => Some(Err(DecodeColumnError::invalid_value(
                             "value",
@@ -364,7 +367,9 @@ fn encode_val<S: Sink>(out: &mut RawEncoder<S>, val: &ScalarValue) -> usize {
         ScalarValue::Counter(i) => out.append(i.start),
         ScalarValue::Str(s) => out.append(RawBytes::from(s.as_bytes())),
         ScalarValue::Bytes(b) => out.append(RawBytes::from(&b[..])),
+        ScalarValue::Decimal(d) => out.append(RawBytes::from(&d.to_bytes()[..])),
         ScalarValue::Unknown { bytes, .. } => out.append(RawBytes::from(&bytes[..])),
+        ScalarValue::Extension(e) => out.append(RawBytes::from(e.bytes())),
     }
 }
 
@@ -380,6 +385,7 @@ enum ValueType {
     Bytes,
     Counter,
     Timestamp,
+    Decimal,
     Unknown(u8),
 }
 
@@ -400,6 +406,7 @@ impl ValueMeta {
             7 => ValueType::Bytes,
             8 => ValueType::Counter,
             9 => ValueType::Timestamp,
+            10 => ValueType::Decimal,
             other => ValueType::Unknown(other),
         }
     }
@@ -424,9 +431,13 @@ impl From<&ScalarValue> for ValueMeta {
             ScalarValue::Counter(i) => Self((lebsize(i.start) << 4) | 8),
             ScalarValue::Str(s) => Self(((s.len() as u64) << 4) | 6),
             ScalarValue::Bytes(b) => Self(((b.len() as u64) << 4) | 7),
+            ScalarValue::Decimal(d) => Self(((d.to_bytes().len() as u64) << 4) | 10),
             ScalarValue::Unknown { type_code, bytes } => {
                 Self(((bytes.len() as u64) << 4) | (*type_code as u64))
             }
+            ScalarValue::Extension(e) => {
+                Self(((e.bytes().len() as u64) << 4) | (e.type_code() as u64))
+            }
         }
     }
 }
@@ -458,7 +469,9 @@ impl From<&ScalarValue> for ValueType {
             ScalarValue::Counter(_) => ValueType::Counter,
             ScalarValue::Str(_) => ValueType::String,
             ScalarValue::Bytes(_) => ValueType::Bytes,
+            ScalarValue::Decimal(_) => ValueType::Decimal,
             ScalarValue::Unknown { type_code, .. } => ValueType::Unknown(*type_code),
+            ScalarValue::Extension(e) => ValueType::Unknown(e.type_code()),
         }
     }
 }
@@ -476,6 +489,7 @@ impl From<ValueType> for u64 {
             ValueType::Bytes => 7,
             ValueType::Counter => 8,
             ValueType::Timestamp => 9,
+            ValueType::Decimal => 10,
             ValueType::Unknown(other) => other as u64,
         }
     }
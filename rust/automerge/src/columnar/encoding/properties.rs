@@ -8,6 +8,7 @@ use smol_str::SmolStr;
 use crate::{
     columnar::Key,
     types::{ElemId, OpId, ScalarValue},
+    Decimal,
 };
 
 #[derive(Clone, Debug)]
@@ -169,7 +170,8 @@ pub(crate) fn scalar_value() -> impl Strategy<Value = ScalarValue> + Clone {
         any::<Vec<u8>>().prop_map(ScalarValue::Bytes),
         encodable_int().prop_map(|i| ScalarValue::Counter(i.into())),
         encodable_int().prop_map(ScalarValue::Timestamp),
-        (10..15_u8, any::<Vec<u8>>()).prop_map(|(c, b)| ScalarValue::Unknown { type_code: c, bytes: b }),
+        (any::<i64>(), 0..20_u32).prop_map(|(c, s)| ScalarValue::Decimal(Decimal::new(c.into(), s))),
+        (11..16_u8, any::<Vec<u8>>()).prop_map(|(c, b)| ScalarValue::Unknown { type_code: c, bytes: b }),
     }
 }
 
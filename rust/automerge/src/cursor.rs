@@ -1,3 +1,4 @@
+use crate::marks::ExpandMark;
 use crate::op_set2::OpSet;
 use crate::storage::parse;
 use crate::types::OpId;
@@ -5,6 +6,7 @@ use crate::types::OpId;
 use crate::ReadDoc;
 use crate::{ActorId, AutomergeError};
 use std::fmt;
+use std::ops::Range;
 
 /// An identifier of a position in a Sequence (either Self::List or Self::Text).
 ///
@@ -79,6 +81,88 @@ impl Default for MoveCursor {
     }
 }
 
+/// A range in a Sequence (either [`ObjType::List`](crate::ObjType::List) or
+/// [`ObjType::Text`](crate::ObjType::Text)) which follows its contents as the sequence is edited,
+/// for example the extent of a comment or a selection.
+///
+/// The range is held by two cursors. `expand` decides whether content inserted exactly at the
+/// start or end of the range becomes part of it, in the same way as it does for a
+/// [`Mark`](crate::marks::Mark).
+///
+/// A cursor range is obtained from [`ReadDoc::get_cursor_range()`] and is dereferenced to a
+/// position using [`ReadDoc::get_cursor_range_position()`] or
+/// [`ReadDoc::get_cursor_range_positions()`].
+#[derive(Clone, PartialEq, Debug)]
+pub struct CursorRange {
+    pub(crate) start: Cursor,
+    pub(crate) end: Cursor,
+    pub(crate) expand: ExpandMark,
+    pub(crate) empty: bool,
+}
+
+impl CursorRange {
+    /// The cursor which follows the start of the range. If the range expands before, this is the
+    /// cursor of the element before the range.
+    pub fn start(&self) -> &Cursor {
+        &self.start
+    }
+
+    /// The cursor which follows the end of the range. If the range does not expand after, this is
+    /// the cursor of the last element in the range.
+    pub fn end(&self) -> &Cursor {
+        &self.end
+    }
+
+    pub fn expand(&self) -> ExpandMark {
+        self.expand
+    }
+
+    /// The cursors of the range, with whether each one is resolved to the position after the
+    /// element it points at rather than the position of the element itself
+    pub(crate) fn anchors(&self) -> [(&Cursor, CursorAnchor); 2] {
+        let start = if self.expand.before() {
+            CursorAnchor::After
+        } else {
+            CursorAnchor::At
+        };
+        let end = if self.expand.after() {
+            CursorAnchor::At
+        } else {
+            CursorAnchor::After
+        };
+        [(&self.start, start), (&self.end, end)]
+    }
+
+    pub(crate) fn resolve(&self, start: usize, end: usize) -> CursorRangePosition {
+        // content inserted at an empty range which doesn't expand lands between the cursors
+        let end = end.max(start);
+        CursorRangePosition {
+            range: start..end,
+            collapsed: !self.empty && start == end,
+        }
+    }
+}
+
+/// The position of a [`CursorRange`] in a sequence
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct CursorRangePosition {
+    /// The range of positions between the start and end of the range
+    pub range: Range<usize>,
+    /// Whether the range has become empty because everything in it was deleted. This is always
+    /// `false` for a range which was created empty.
+    pub collapsed: bool,
+}
+
+/// How a cursor is dereferenced to a position
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub(crate) enum CursorAnchor {
+    /// The position of the element the cursor points at
+    At,
+    /// The position immediately after the element the cursor points at, or where the element was
+    /// if it has been deleted
+    After,
+}
+
 const VERSION_TAG: u8 = 1;
 
 const START_TAG: u8 = 1;
@@ -0,0 +1,328 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Serialize, Serializer};
+
+use crate::columnar::encoding::leb128::ulebsize;
+
+/// An exact decimal number, of up to [`Decimal::MAX_DIGITS`] digits
+///
+/// A decimal is a whole number coefficient and a scale, the number of digits of the coefficient
+/// which come after the decimal point, so `12.30` has a coefficient of `1230` and a scale of `2`.
+/// The scale is part of the value, so `12.30` and `12.3` are different values, in the same way
+/// that they would be different strings. This means that a value always round trips exactly,
+/// including how many decimal places it was written with.
+///
+/// Decimals are parsed from and displayed as strings of digits with an optional sign and decimal
+/// point, and serialize as those strings.
+///
+/// ```
+/// # use automerge::Decimal;
+/// let price: Decimal = "-12.30".parse().unwrap();
+/// assert_eq!(price, Decimal::new(-1230, 2));
+/// assert_eq!(price.to_string(), "-12.30");
+/// assert_eq!(price.scale(), 2);
+/// ```
+///
+/// # Encoding
+///
+/// A decimal is stored under value type code 10. Its bytes are a ULEB128 encoding of the scale
+/// shifted left by one bit, with the lowest bit set if the value is negative, followed by a
+/// ULEB128 encoding of the coefficient without its sign, which may be any length. Both must be
+/// the shortest encoding of their value, and zero is never negative. The scale is at most
+/// [`Decimal::MAX_SCALE`] and the coefficient at most [`Decimal::MAX_DIGITS`] digits long, so that
+/// neither decoding nor displaying a decimal can take an unbounded amount of work.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Default)]
+pub struct Decimal {
+    negative: bool,
+    /// The decimal digits of the coefficient, most significant first, with no leading zeros
+    digits: Box<[u8]>,
+    scale: u32,
+}
+
+/// The error returned when a string is not a valid [`Decimal`]
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+#[error("invalid decimal: {0:?}")]
+pub struct ParseDecimalError(String);
+
+impl Decimal {
+    /// The most digits the coefficient of a decimal can have
+    pub const MAX_DIGITS: usize = 1000;
+    /// The largest scale a decimal can have
+    pub const MAX_SCALE: u32 = 1000;
+
+    /// The value `coefficient * 10^-scale`
+    ///
+    /// # Panics
+    ///
+    /// If `scale` is greater than [`Decimal::MAX_SCALE`]
+    pub fn new(coefficient: i128, scale: u32) -> Self {
+        assert!(
+            scale <= Self::MAX_SCALE,
+            "decimal scale {} is greater than {}",
+            scale,
+            Self::MAX_SCALE
+        );
+        let digits = coefficient
+            .unsigned_abs()
+            .to_string()
+            .bytes()
+            .map(|b| b - b'0')
+            .collect();
+        Self::from_parts(coefficient < 0, digits, scale)
+    }
+
+    fn from_parts(negative: bool, mut digits: Vec<u8>, scale: u32) -> Self {
+        let zeros = digits.iter().take_while(|d| **d == 0).count();
+        digits.drain(..zeros);
+        Self {
+            negative: negative && !digits.is_empty(),
+            digits: digits.into_boxed_slice(),
+            scale,
+        }
+    }
+
+    /// The number of digits after the decimal point
+    pub fn scale(&self) -> u32 {
+        self.scale
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.digits.is_empty()
+    }
+
+    /// The nearest `f64` to this value
+    pub fn to_f64(&self) -> f64 {
+        self.to_string().parse().unwrap_or(f64::NAN)
+    }
+
+    pub(crate) fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        let head = (u64::from(self.scale) << 1) | u64::from(self.negative);
+        leb128::write::unsigned(&mut out, head).unwrap();
+        // the coefficient in base 128, least significant first
+        let mut digits = self.digits.to_vec();
+        let mut groups = Vec::new();
+        while !digits.is_empty() {
+            let mut rem = 0_u32;
+            for d in digits.iter_mut() {
+                let n = rem * 10 + u32::from(*d);
+                *d = (n / 128) as u8;
+                rem = n % 128;
+            }
+            groups.push(rem as u8);
+            let zeros = digits.iter().take_while(|d| **d == 0).count();
+            digits.drain(..zeros);
+        }
+        if groups.is_empty() {
+            groups.push(0);
+        }
+        let last = groups.len() - 1;
+        out.extend(
+            groups
+                .iter()
+                .enumerate()
+                .map(|(i, g)| if i == last { *g } else { g | 0x80 }),
+        );
+        out
+    }
+
+    /// Decode the bytes of a decimal, or `None` if they are not the canonical encoding of one
+    pub(crate) fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut input = bytes;
+        let head = leb128::read::unsigned(&mut input).ok()?;
+        if bytes.len() - input.len() != ulebsize(head) as usize {
+            return None;
+        }
+        let scale = u32::try_from(head >> 1)
+            .ok()
+            .filter(|scale| *scale <= Self::MAX_SCALE)?;
+        let negative = head & 1 == 1;
+        let (last, rest) = input.split_last()?;
+        if last & 0x80 != 0 || rest.iter().any(|b| b & 0x80 == 0) {
+            return None;
+        }
+        if *last == 0 && (!rest.is_empty() || negative) {
+            return None;
+        }
+        // every group after the first adds at least two digits, so this bounds the work of
+        // converting them before we know how many digits there are
+        if input.len() > Self::MAX_DIGITS / 2 + 1 {
+            return None;
+        }
+        let mut digits: Vec<u8> = Vec::new();
+        for group in input.iter().rev() {
+            // digits = digits * 128 + group, working from the least significant digit
+            let mut carry = u32::from(group & 0x7f);
+            for d in digits.iter_mut().rev() {
+                let n = u32::from(*d) * 128 + carry;
+                *d = (n % 10) as u8;
+                carry = n / 10;
+            }
+            while carry > 0 {
+                digits.insert(0, (carry % 10) as u8);
+                carry /= 10;
+            }
+        }
+        if digits.len() > Self::MAX_DIGITS {
+            return None;
+        }
+        Some(Self::from_parts(negative, digits, scale))
+    }
+}
+
+impl FromStr for Decimal {
+    type Err = ParseDecimalError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ParseDecimalError(s.to_string());
+        let (negative, unsigned) = match s.as_bytes().first() {
+            Some(b'-') => (true, &s[1..]),
+            Some(b'+') => (false, &s[1..]),
+            _ => (false, s),
+        };
+        let (whole, fraction) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+        if whole.is_empty() && fraction.is_empty() {
+            return Err(err());
+        }
+        if unsigned.ends_with('.') || unsigned.starts_with('.') {
+            return Err(err());
+        }
+        let digits = whole
+            .bytes()
+            .chain(fraction.bytes())
+            .map(|b| b.is_ascii_digit().then(|| b - b'0'))
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(err)?;
+        let scale = u32::try_from(fraction.len())
+            .ok()
+            .filter(|scale| *scale <= Self::MAX_SCALE)
+            .ok_or_else(err)?;
+        let decimal = Self::from_parts(negative, digits, scale);
+        if decimal.digits.len() > Self::MAX_DIGITS {
+            return Err(err());
+        }
+        Ok(decimal)
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let scale = self.scale as usize;
+        let mut digits = self
+            .digits
+            .iter()
+            .map(|d| char::from(b'0' + d))
+            .collect::<String>();
+        if digits.len() <= scale {
+            digits.insert_str(0, &"0".repeat(scale + 1 - digits.len()));
+        }
+        if self.negative {
+            write!(f, "-")?;
+        }
+        let (whole, fraction) = digits.split_at(digits.len() - scale);
+        if fraction.is_empty() {
+            write!(f, "{}", whole)
+        } else {
+            write!(f, "{}.{}", whole, fraction)
+        }
+    }
+}
+
+impl From<i64> for Decimal {
+    fn from(n: i64) -> Self {
+        Self::new(n.into(), 0)
+    }
+}
+
+impl From<u64> for Decimal {
+    fn from(n: u64) -> Self {
+        Self::new(n.into(), 0)
+    }
+}
+
+impl Serialize for Decimal {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_and_display() {
+        for (s, display) in [
+            ("0", "0"),
+            ("-0", "0"),
+            ("007", "7"),
+            ("0.001", "0.001"),
+            ("-12.30", "-12.30"),
+            ("+5", "5"),
+            (
+                "123456789012345678901234567890.5",
+                "123456789012345678901234567890.5",
+            ),
+        ] {
+            assert_eq!(s.parse::<Decimal>().unwrap().to_string(), display);
+        }
+        for s in ["", "-", ".", "1.", ".5", "1.2.3", "1e5", "--1", " 1"] {
+            assert!(s.parse::<Decimal>().is_err(), "{:?} parsed", s);
+        }
+    }
+
+    #[test]
+    fn encoding_round_trips() {
+        for s in [
+            "0",
+            "0.00",
+            "-1",
+            "127",
+            "128",
+            "-16383.99",
+            "340282366920938463463374607431768211456.123456789",
+        ] {
+            let d = s.parse::<Decimal>().unwrap();
+            assert_eq!(Decimal::from_bytes(&d.to_bytes()), Some(d), "{}", s);
+        }
+        assert_eq!(Decimal::new(-1230, 2).to_bytes(), vec![5, 0xce, 0x09]);
+        // non canonical encodings are rejected
+        assert_eq!(Decimal::from_bytes(&[0, 0x80, 0]), None);
+        assert_eq!(Decimal::from_bytes(&[1, 0]), None);
+        assert_eq!(Decimal::from_bytes(&[0]), None);
+        assert_eq!(Decimal::from_bytes(&[0x80, 0, 1]), None);
+    }
+
+    #[test]
+    fn digits_and_scale_are_limited() {
+        let most_digits = "9".repeat(Decimal::MAX_DIGITS);
+        let d = most_digits.parse::<Decimal>().unwrap();
+        assert_eq!(Decimal::from_bytes(&d.to_bytes()), Some(d));
+        assert!(format!("{}9", most_digits).parse::<Decimal>().is_err());
+        // leading zeros don't count
+        assert!(format!("0{}", most_digits).parse::<Decimal>().is_ok());
+
+        let max_scale = Decimal::MAX_SCALE as usize;
+        let d = format!("0.{}1", "0".repeat(max_scale - 1))
+            .parse::<Decimal>()
+            .unwrap();
+        assert_eq!(d.scale(), Decimal::MAX_SCALE);
+        assert_eq!(Decimal::from_bytes(&d.to_bytes()), Some(d));
+        assert!(format!("0.{}1", "0".repeat(max_scale))
+            .parse::<Decimal>()
+            .is_err());
+
+        let mut too_large = Decimal::new(1, Decimal::MAX_SCALE).to_bytes();
+        too_large[0] += 2;
+        assert_eq!(Decimal::from_bytes(&too_large), None);
+        let mut too_long = vec![0];
+        too_long.extend(std::iter::repeat_n(0xff, Decimal::MAX_DIGITS));
+        too_long.push(1);
+        assert_eq!(Decimal::from_bytes(&too_long), None);
+    }
+}
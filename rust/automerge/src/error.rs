@@ -1,4 +1,6 @@
+use crate::exid::ExId;
 use crate::storage::load::Error as LoadError;
+use crate::transaction::FailedPrecondition;
 use crate::types::{ActorId, ScalarValue};
 use crate::value::DataType;
 use crate::{ChangeHash, Cursor, LoadChangeError, ObjType, PatchAction};
@@ -61,10 +63,26 @@ pub enum AutomergeError {
     MissingHash(ChangeHash),
     #[error("change's deps should already be in the document")]
     MissingDeps,
+    #[error("change {0} is not available because the history of this document has been truncated")]
+    TruncatedHistory(ChangeHash),
     #[error("compressed chunk was not a change")]
     NonChangeCompressed,
     #[error("id was not an object id")]
     NotAnObject,
+    #[error("an object cannot be moved into itself or one of its descendants")]
+    MoveIntoDescendant,
+    #[error("`{0}` is not one of the current values of the property")]
+    NotACurrentValue(ExId),
+    #[error("{0}")]
+    PreconditionFailed(Box<FailedPrecondition>),
+    #[error("there is no key {0:?} in the map")]
+    MissingKey(String),
+    #[error("keys of an ordered map cannot start with a null character, {0:?} does")]
+    ReservedKey(String),
+    #[error(
+        "the actor holds {available} rights to the bounded counter but {requested} are needed"
+    )]
+    InsufficientRights { requested: u64, available: u64 },
     #[error(transparent)]
     HydrateError(#[from] HydrateError),
     #[error(transparent)]
@@ -129,6 +147,8 @@ pub enum InvalidOpType {
     UnknownAction(u64),
     #[error("non numeric argument for inc op")]
     NonNumericInc,
+    #[error("move op with a value or without the object it moves")]
+    InvalidMoveTarget,
 }
 
 #[derive(Error, Debug)]
@@ -147,12 +167,16 @@ pub enum HydrateError {
     InvalidMapOp,
     #[error("invalid op appied to list")]
     InvalidListOp,
+    #[error("invalid op applied to set")]
+    InvalidSetOp,
     #[error("invalid op applied to map: {0}")]
     InvalidTextOp(PatchAction),
     #[error("invalid prop in patch: {0}")]
     ApplyInvalidProp(PatchAction),
     #[error("invalid encoding for text value")]
     InvalidEncoding,
+    #[error("attach of object {0} which was not detached")]
+    NotDetached(ExId),
 }
 
 #[derive(Error, Debug)]
@@ -0,0 +1,261 @@
+//! Application defined scalar types
+//!
+//! Values with a type code automerge does not know are kept as [`ScalarValue::Unknown`], which
+//! preserves their bytes but not what they mean. An application can give its own scalar types a
+//! type code in [`TYPE_CODES`] by implementing [`ExtensionType`] and calling [`register`], after
+//! which values with that type code are read as a [`ScalarValue::Extension`], so they are
+//! displayed, serialized and decoded as the type they are wherever values are read: in
+//! [`ReadDoc::get`], [`ReadDoc::hydrate`] and [`AutoSerde`] output.
+//!
+//! The registry is shared by every document in the process, so types should be registered once,
+//! before any documents are loaded. Values are stored as the bytes [`ExtensionType::encode`]
+//! returns, so documents containing them can still be read by peers which have not registered
+//! the type, they just see a [`ScalarValue::Unknown`]. Likewise, values whose bytes the
+//! registered type cannot [`ExtensionType::decode`] are read as [`ScalarValue::Unknown`].
+//!
+//! Types cannot be unregistered, but whether a type is registered never changes what a document
+//! contains: an extension value is equal to the [`ScalarValue::Unknown`] with the same type code
+//! and bytes, so comparing values read before and after registering a type gives the same answer.
+//!
+//! ```
+//! # use std::fmt;
+//! # use automerge::{transaction::Transactable, AutoCommit, ReadDoc, ScalarValue, ROOT};
+//! use automerge::extension::{self, DecodeError, ExtensionType, ExtensionValue};
+//!
+//! #[derive(Debug, PartialEq)]
+//! struct Point {
+//!     lat: f32,
+//!     lng: f32,
+//! }
+//!
+//! impl fmt::Display for Point {
+//!     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+//!         write!(f, "{},{}", self.lat, self.lng)
+//!     }
+//! }
+//!
+//! impl ExtensionType for Point {
+//!     const TYPE_CODE: u8 = 12;
+//!     const NAME: &'static str = "point";
+//!
+//!     fn encode(&self) -> Vec<u8> {
+//!         [self.lat.to_le_bytes(), self.lng.to_le_bytes()].concat()
+//!     }
+//!
+//!     fn decode(bytes: &[u8]) -> Result<Self, DecodeError> {
+//!         let bytes: [u8; 8] = bytes.try_into().map_err(|_| DecodeError::new("not 8 bytes"))?;
+//!         Ok(Point {
+//!             lat: f32::from_le_bytes(bytes[..4].try_into().unwrap()),
+//!             lng: f32::from_le_bytes(bytes[4..].try_into().unwrap()),
+//!         })
+//!     }
+//! }
+//!
+//! extension::register::<Point>().unwrap();
+//!
+//! let mut doc = AutoCommit::new();
+//! let home = Point { lat: 51.5, lng: -0.25 };
+//! doc.put(ROOT, "home", ExtensionValue::new(&home).unwrap()).unwrap();
+//!
+//! let (value, _) = doc.get(ROOT, "home").unwrap().unwrap();
+//! let ScalarValue::Extension(ext) = value.to_scalar().unwrap() else { panic!() };
+//! assert_eq!(ext.name(), "point");
+//! assert_eq!(ext.get::<Point>(), Some(home));
+//! assert_eq!(value.to_string(), "51.5,-0.25");
+//! ```
+//!
+//! [`ScalarValue::Unknown`]: crate::ScalarValue::Unknown
+//! [`ScalarValue::Extension`]: crate::ScalarValue::Extension
+//! [`ReadDoc::get`]: crate::ReadDoc::get
+//! [`ReadDoc::hydrate`]: crate::ReadDoc::hydrate
+//! [`AutoSerde`]: crate::AutoSerde
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::sync::{LazyLock, RwLock};
+
+use serde::{Serialize, Serializer};
+
+use crate::ScalarValue;
+
+/// The type codes which are reserved for application defined types
+pub const TYPE_CODES: RangeInclusive<u8> = 12..=15;
+
+/// A scalar type defined by an application, see the [module docs](self)
+pub trait ExtensionType: fmt::Display + Sized + 'static {
+    /// The type code values of this type are stored under, which must be in [`TYPE_CODES`]
+    const TYPE_CODE: u8;
+    /// The name of this type, which must be unique among the registered types
+    const NAME: &'static str;
+
+    /// The bytes this value is stored as
+    fn encode(&self) -> Vec<u8>;
+
+    /// The value stored as `bytes`
+    fn decode(bytes: &[u8]) -> Result<Self, DecodeError>;
+
+    /// The value this is serialized as, by default its [`Display`](fmt::Display) form
+    fn to_serde(&self) -> ScalarValue {
+        ScalarValue::Str(self.to_string().into())
+    }
+}
+
+/// The error returned by [`ExtensionType::decode`] when the bytes are not a valid value
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+#[error("{0}")]
+pub struct DecodeError(String);
+
+impl DecodeError {
+    pub fn new<S: Into<String>>(reason: S) -> Self {
+        Self(reason.into())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum ExtensionError {
+    #[error("type code {0} is not reserved for extensions")]
+    TypeCodeOutOfRange(u8),
+    #[error("type code {type_code} is already registered to {name}")]
+    TypeCodeInUse { type_code: u8, name: &'static str },
+    #[error("no extension named {name} is registered to type code {type_code}")]
+    NotRegistered { type_code: u8, name: &'static str },
+}
+
+/// The hooks for a registered type, with the type erased
+struct Registration {
+    type_code: u8,
+    name: &'static str,
+    is_valid: fn(&[u8]) -> bool,
+    display: fn(&[u8], &mut fmt::Formatter<'_>) -> fmt::Result,
+    to_serde: fn(&[u8]) -> ScalarValue,
+}
+
+// Registrations are leaked so that values can refer to them without reference counting, there
+// can only ever be as many of them as there are type codes
+static REGISTRY: LazyLock<RwLock<BTreeMap<u8, &'static Registration>>> =
+    LazyLock::new(Default::default);
+
+/// Register `T`, so that values with its type code are read as a [`ScalarValue::Extension`]
+///
+/// Registering a type again is allowed and does nothing, registering a different type with the
+/// same type code is an error.
+pub fn register<T: ExtensionType>() -> Result<(), ExtensionError> {
+    if !TYPE_CODES.contains(&T::TYPE_CODE) {
+        return Err(ExtensionError::TypeCodeOutOfRange(T::TYPE_CODE));
+    }
+    let mut registry = REGISTRY.write().unwrap_or_else(|e| e.into_inner());
+    match registry.get(&T::TYPE_CODE) {
+        Some(existing) if existing.name == T::NAME => Ok(()),
+        Some(existing) => Err(ExtensionError::TypeCodeInUse {
+            type_code: T::TYPE_CODE,
+            name: existing.name,
+        }),
+        None => {
+            let registration = Registration {
+                type_code: T::TYPE_CODE,
+                name: T::NAME,
+                is_valid: |bytes| T::decode(bytes).is_ok(),
+                display: |bytes, f| match T::decode(bytes) {
+                    Ok(value) => value.fmt(f),
+                    Err(_) => write!(f, "invalid {}", T::NAME),
+                },
+                to_serde: |bytes| match T::decode(bytes) {
+                    Ok(value) => value.to_serde(),
+                    Err(_) => ScalarValue::Null,
+                },
+            };
+            registry.insert(T::TYPE_CODE, Box::leak(Box::new(registration)));
+            Ok(())
+        }
+    }
+}
+
+fn registration(type_code: u8) -> Option<&'static Registration> {
+    if !TYPE_CODES.contains(&type_code) {
+        return None;
+    }
+    let registry = REGISTRY.read().unwrap_or_else(|e| e.into_inner());
+    registry.get(&type_code).copied()
+}
+
+/// A value of a registered [`ExtensionType`]
+#[derive(Clone)]
+pub struct ExtensionValue {
+    ty: &'static Registration,
+    bytes: Box<[u8]>,
+}
+
+impl ExtensionValue {
+    /// The value of `value`, or an error if `T` has not been [`register`]ed
+    pub fn new<T: ExtensionType>(value: &T) -> Result<Self, ExtensionError> {
+        match registration(T::TYPE_CODE) {
+            Some(ty) if ty.name == T::NAME => Ok(Self {
+                ty,
+                bytes: value.encode().into(),
+            }),
+            _ => Err(ExtensionError::NotRegistered {
+                type_code: T::TYPE_CODE,
+                name: T::NAME,
+            }),
+        }
+    }
+
+    /// The value stored as `bytes` under `type_code` if its type is registered and it is valid
+    pub(crate) fn from_bytes(type_code: u8, bytes: &[u8]) -> Option<Self> {
+        let ty = registration(type_code)?;
+        (ty.is_valid)(bytes).then(|| Self {
+            ty,
+            bytes: bytes.into(),
+        })
+    }
+
+    pub fn type_code(&self) -> u8 {
+        self.ty.type_code
+    }
+
+    /// The [`ExtensionType::NAME`] of the type of this value
+    pub fn name(&self) -> &'static str {
+        self.ty.name
+    }
+
+    /// The bytes this value is stored as
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// This value as a `T`, or `None` if it is a value of another type
+    pub fn get<T: ExtensionType>(&self) -> Option<T> {
+        if self.type_code() != T::TYPE_CODE || self.name() != T::NAME {
+            return None;
+        }
+        T::decode(&self.bytes).ok()
+    }
+}
+
+impl PartialEq for ExtensionValue {
+    fn eq(&self, other: &Self) -> bool {
+        self.type_code() == other.type_code() && self.bytes == other.bytes
+    }
+}
+
+impl fmt::Debug for ExtensionValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExtensionValue")
+            .field("name", &self.name())
+            .field("value", &format_args!("{}", self))
+            .finish()
+    }
+}
+
+impl fmt::Display for ExtensionValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (self.ty.display)(&self.bytes, f)
+    }
+}
+
+impl Serialize for ExtensionValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (self.ty.to_serde)(&self.bytes).serialize(serializer)
+    }
+}
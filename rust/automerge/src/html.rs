@@ -0,0 +1,1287 @@
+//! Convert rich text to and from HTML
+//!
+//! [`to_html`] renders the [`Span`]s returned by [`ReadDoc::spans`] as sanitized HTML and
+//! [`from_html`] parses HTML, for example from a clipboard paste, into spans which can be passed
+//! to [`Transactable::update_spans`]. [`get_html`] and [`update_html`] do both steps at once.
+//!
+//! The mark and block vocabulary is defined by an [`HtmlSchema`] so that every client converting
+//! the same document agrees on which tags correspond to which marks and blocks. The default
+//! schema uses the same vocabulary as the default [`MarkdownConfig`](crate::markdown::MarkdownConfig):
+//!
+//! | HTML                          | Automerge                                         |
+//! |-------------------------------|---------------------------------------------------|
+//! | `<strong>`, `<b>`             | mark `"strong"` with value `true`                 |
+//! | `<em>`, `<i>`                 | mark `"em"` with value `true`                     |
+//! | `<code>`                      | mark `"code"` with value `true`                   |
+//! | `<a href>`                    | mark `"link"` with the URL as its value           |
+//! | `<p>`                         | block of type `"paragraph"`                       |
+//! | `<h1>` to `<h6>`              | block of type `"heading"` with a `"level"` attr   |
+//! | `<ol><li>`                    | block of type `"ordered-list-item"`               |
+//! | `<ul><li>`                    | block of type `"unordered-list-item"`             |
+//! | `<blockquote>`                | a `"blockquote"` in the parents of a block        |
+//! | `<pre>`                       | block of type `"code-block"`                      |
+//!
+//! ## Sanitization
+//!
+//! The contents of elements which can run code or load resources (`<script>`, `<style>`,
+//! `<iframe>` and so on) are always discarded, as are event handler and `style` attributes and
+//! URLs with a scheme other than `http`, `https`, `mailto` and `tel`. Other tags which are not
+//! part of the schema are handled according to [`UnknownTags`]: either the tag is dropped and its
+//! content kept, or block level tags are recorded in the attributes of the blocks inside them so
+//! that they can be written out again.
+//!
+//! ## Example
+//!
+//! ```
+//! # use automerge::{AutoCommit, ObjType, ROOT, transaction::Transactable};
+//! use automerge::html::{self, HtmlSchema};
+//!
+//! let mut doc = AutoCommit::new();
+//! let text = doc.put_object(ROOT, "text", ObjType::Text)?;
+//! let schema = HtmlSchema::default();
+//! let pasted = r#"<h1 class="title">Hello</h1><p onclick="evil()">some <b>bold</b> text<script>evil()</script></p>"#;
+//! html::update_html(&mut doc, &text, pasted, &schema)?;
+//! assert_eq!(
+//!     html::get_html(&doc, &text, &schema)?,
+//!     "<h1>Hello</h1><p>some <strong>bold</strong> text</p>"
+//! );
+//! # Ok::<(), automerge::AutomergeError>(())
+//! ```
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::hydrate;
+use crate::iter::Span;
+use crate::marks::{ExpandMark, MarkSet, UpdateSpansConfig};
+use crate::transaction::Transactable;
+use crate::{AutomergeError, ObjId, ReadDoc, ScalarValue};
+
+/// What to do with tags which are not part of an [`HtmlSchema`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UnknownTags {
+    /// Remove the tag but keep its content
+    #[default]
+    Drop,
+    /// Keep block level tags, such as `<section class="note">`, as a list of
+    /// `{"tag": .., "attrs": {..}}` maps in the attributes of every block inside them. The key
+    /// used is set by [`HtmlSchema::with_opaque_attr`]. Unknown inline tags are dropped.
+    Preserve,
+}
+
+/// How a mark is written in HTML
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarkSpec {
+    name: String,
+    tag: String,
+    aliases: Vec<String>,
+    value_attr: Option<String>,
+}
+
+impl MarkSpec {
+    /// A mark called `name` written as `<tag>`, with the value `true`
+    pub fn new<N: Into<String>, T: Into<String>>(name: N, tag: T) -> Self {
+        Self {
+            name: name.into(),
+            tag: tag.into(),
+            aliases: Vec::new(),
+            value_attr: None,
+        }
+    }
+
+    /// Also create this mark from `<tag>` when importing
+    pub fn with_alias<T: Into<String>>(mut self, tag: T) -> Self {
+        self.aliases.push(tag.into());
+        self
+    }
+
+    /// Store the value of the mark in the attribute `attr` of the tag, e.g. `href` for links.
+    /// Tags without the attribute do not create the mark.
+    pub fn with_value_attr<A: Into<String>>(mut self, attr: A) -> Self {
+        self.value_attr = Some(attr.into());
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn tag(&self) -> &str {
+        &self.tag
+    }
+
+    fn matches(&self, tag: &str) -> bool {
+        self.tag == tag || self.aliases.iter().any(|a| a == tag)
+    }
+}
+
+/// How a block is written in HTML
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockSpec {
+    block_type: String,
+    tag: String,
+    aliases: Vec<String>,
+    container: Option<String>,
+    attrs: Vec<(String, ScalarValue)>,
+    parent_only: bool,
+    preformatted: bool,
+}
+
+impl BlockSpec {
+    /// A block of type `block_type` written as `<tag>`
+    pub fn new<B: Into<String>, T: Into<String>>(block_type: B, tag: T) -> Self {
+        Self {
+            block_type: block_type.into(),
+            tag: tag.into(),
+            aliases: Vec::new(),
+            container: None,
+            attrs: Vec::new(),
+            parent_only: false,
+            preformatted: false,
+        }
+    }
+
+    /// Also create this block from `<tag>` when importing
+    pub fn with_alias<T: Into<String>>(mut self, tag: T) -> Self {
+        self.aliases.push(tag.into());
+        self
+    }
+
+    /// The tag of this block is wrapped in `<container>`, as `<li>` is wrapped in `<ul>`. When
+    /// importing this spec is only used for tags inside the container.
+    pub fn in_container<T: Into<String>>(mut self, container: T) -> Self {
+        self.container = Some(container.into());
+        self
+    }
+
+    /// Blocks created from this spec have the attribute `key` set to `value` and blocks are only
+    /// written with this spec when they have that attribute. This is how `<h2>` maps to a
+    /// heading with `{"level": 2}`.
+    pub fn with_attr<K: Into<String>, V: Into<ScalarValue>>(mut self, key: K, value: V) -> Self {
+        self.attrs.push((key.into(), value.into()));
+        self
+    }
+
+    /// The tag groups other blocks rather than containing text itself, as `<blockquote>` does.
+    /// The type of the block is added to the parents of the blocks inside it.
+    pub fn parent_only(mut self) -> Self {
+        self.parent_only = true;
+        self
+    }
+
+    /// Whitespace and line breaks in the block are kept as they are and no marks are applied
+    pub fn preformatted(mut self) -> Self {
+        self.preformatted = true;
+        self
+    }
+
+    pub fn block_type(&self) -> &str {
+        &self.block_type
+    }
+
+    pub fn tag(&self) -> &str {
+        &self.tag
+    }
+
+    fn matches(&self, tag: &str, container: Option<&str>) -> bool {
+        (self.tag == tag || self.aliases.iter().any(|a| a == tag))
+            && (self.container.is_none() || self.container.as_deref() == container)
+    }
+
+    /// Whether blocks inside this one are nested in it, as lists are in list items, rather than
+    /// following it, as a list inside a heading does
+    fn nests(&self) -> bool {
+        self.parent_only || self.container.is_some()
+    }
+
+    fn matches_attrs(&self, attrs: Option<&hydrate::Map>) -> bool {
+        self.attrs
+            .iter()
+            .all(|(key, value)| match attrs.and_then(|a| a.get(key)) {
+                Some(hydrate::Value::Scalar(s)) => match (s.to_f64(), value.to_f64()) {
+                    (Some(a), Some(b)) => a == b,
+                    _ => s == value,
+                },
+                _ => false,
+            })
+    }
+}
+
+/// The mark and block vocabulary used to convert between rich text and HTML
+///
+/// Marks are nested in the order they were added, the first mark is the outermost.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HtmlSchema {
+    default_block: BlockSpec,
+    marks: Vec<MarkSpec>,
+    blocks: Vec<BlockSpec>,
+    unknown_tags: UnknownTags,
+    opaque_attr: String,
+}
+
+impl Default for HtmlSchema {
+    fn default() -> Self {
+        let mut schema = HtmlSchema::new(BlockSpec::new("paragraph", "p"))
+            .with_mark(MarkSpec::new("link", "a").with_value_attr("href"))
+            .with_mark(MarkSpec::new("strong", "strong").with_alias("b"))
+            .with_mark(MarkSpec::new("em", "em").with_alias("i"))
+            .with_mark(MarkSpec::new("code", "code"));
+        for level in 1..=6 {
+            schema = schema.with_block(
+                BlockSpec::new("heading", format!("h{}", level)).with_attr("level", level as i64),
+            );
+        }
+        schema
+            .with_block(BlockSpec::new("ordered-list-item", "li").in_container("ol"))
+            .with_block(BlockSpec::new("unordered-list-item", "li").in_container("ul"))
+            .with_block(BlockSpec::new("blockquote", "blockquote").parent_only())
+            .with_block(BlockSpec::new("code-block", "pre").preformatted())
+    }
+}
+
+impl HtmlSchema {
+    /// A schema with no marks and one block, `default_block`, which is used for text outside
+    /// of any block and for blocks of a type the schema does not know about
+    pub fn new(default_block: BlockSpec) -> Self {
+        Self {
+            default_block,
+            marks: Vec::new(),
+            blocks: Vec::new(),
+            unknown_tags: UnknownTags::default(),
+            opaque_attr: "html".to_string(),
+        }
+    }
+
+    pub fn with_mark(mut self, mark: MarkSpec) -> Self {
+        self.marks.push(mark);
+        self
+    }
+
+    pub fn with_block(mut self, block: BlockSpec) -> Self {
+        self.blocks.push(block);
+        self
+    }
+
+    pub fn with_unknown_tags(mut self, unknown_tags: UnknownTags) -> Self {
+        self.unknown_tags = unknown_tags;
+        self
+    }
+
+    /// The block attribute which holds unknown tags when using [`UnknownTags::Preserve`],
+    /// `"html"` by default
+    pub fn with_opaque_attr<S: Into<String>>(mut self, attr: S) -> Self {
+        self.opaque_attr = attr.into();
+        self
+    }
+
+    fn blocks(&self) -> impl Iterator<Item = &BlockSpec> {
+        std::iter::once(&self.default_block).chain(&self.blocks)
+    }
+
+    fn block_for_tag(&self, tag: &str, container: Option<&str>) -> Option<&BlockSpec> {
+        // prefer a spec which is specific to the container
+        self.blocks()
+            .filter(|b| b.matches(tag, container))
+            .max_by_key(|b| b.container.is_some())
+    }
+
+    fn block_for_type(&self, block_type: &str, attrs: Option<&hydrate::Map>) -> Option<&BlockSpec> {
+        self.blocks()
+            .find(|b| b.block_type == block_type && b.matches_attrs(attrs))
+            .or_else(|| self.blocks().find(|b| b.block_type == block_type))
+    }
+
+    fn is_container(&self, tag: &str) -> bool {
+        self.blocks().any(|b| b.container.as_deref() == Some(tag))
+    }
+}
+
+/// Render the rich text in `text` as HTML
+pub fn get_html<R: ReadDoc, O: AsRef<ObjId>>(
+    doc: &R,
+    text: O,
+    schema: &HtmlSchema,
+) -> Result<String, AutomergeError> {
+    Ok(to_html(doc.spans(text)?, schema))
+}
+
+/// Replace the content of `text` with `html`, making the smallest change possible
+///
+/// Marks which store their value in an attribute, such as links, are created with
+/// [`ExpandMark::None`] and all other marks with the default expand flag, use [`from_html`] and
+/// [`Transactable::update_spans`] for more control.
+pub fn update_html<T: Transactable, O: AsRef<ObjId>>(
+    tx: &mut T,
+    text: O,
+    html: &str,
+    schema: &HtmlSchema,
+) -> Result<(), AutomergeError> {
+    let config = schema
+        .marks
+        .iter()
+        .filter(|m| m.value_attr.is_some())
+        .fold(UpdateSpansConfig::default(), |config, m| {
+            config.with_mark_expand(&m.name, ExpandMark::None)
+        });
+    tx.update_spans(text, config, from_html(html, schema))
+}
+
+/// Render a sequence of spans, as returned by [`ReadDoc::spans`], as sanitized HTML
+pub fn to_html<I: IntoIterator<Item = Span>>(spans: I, schema: &HtmlSchema) -> String {
+    let mut writer = Writer {
+        schema,
+        out: String::new(),
+        elements: Vec::new(),
+        marks: Vec::new(),
+        preformatted: false,
+        empty: false,
+    };
+    for span in spans {
+        match span {
+            Span::Block(block) => writer.block(&block),
+            Span::Text { text, marks } => {
+                if writer.elements.is_empty() {
+                    writer.block(&hydrate::Map::default());
+                }
+                writer.text(&text, marks.as_deref());
+            }
+        }
+    }
+    writer.close_marks(0);
+    writer.close_elements(0);
+    writer.out
+}
+
+/// Parse `html` into a sequence of spans suitable for [`Transactable::update_spans`]
+///
+/// Every block, including text outside of any block tag, starts with a [`Span::Block`]. Blocks
+/// are only nested in list items and [`BlockSpec::parent_only`] blocks, blocks inside any other
+/// block, such as a list inside a heading, come after it.
+pub fn from_html(html: &str, schema: &HtmlSchema) -> Vec<Span> {
+    let nodes = build_tree(tokenize(html));
+    let mut importer = Importer {
+        schema,
+        spans: Vec::new(),
+        inline: None,
+    };
+    importer.blocks(&nodes, &Context::default());
+    importer.finish_block();
+    importer.spans
+}
+
+/// Elements which are removed together with everything inside them
+const DISCARDED: &[&str] = &[
+    "script", "style", "template", "iframe", "frame", "frameset", "object", "embed", "applet",
+    "noscript", "noembed", "noframes", "head", "title", "textarea", "select", "svg", "math",
+    "meta", "link", "base", "xmp",
+];
+
+/// Elements whose content is not parsed as HTML
+const RAW_TEXT: &[&str] = &[
+    "script", "style", "textarea", "title", "xmp", "iframe", "noembed", "noframes", "noscript",
+];
+
+const VOID: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source",
+    "track", "wbr",
+];
+
+/// Elements which are part of the text of a block rather than blocks themselves
+const INLINE: &[&str] = &[
+    "a", "abbr", "b", "bdi", "bdo", "br", "cite", "code", "data", "del", "dfn", "em", "font", "i",
+    "img", "ins", "kbd", "label", "mark", "q", "s", "samp", "small", "span", "strike", "strong",
+    "sub", "sup", "time", "tt", "u", "var", "wbr",
+];
+
+const URL_ATTRS: &[&str] = &[
+    "href",
+    "src",
+    "action",
+    "formaction",
+    "cite",
+    "background",
+    "poster",
+    "xlink:href",
+];
+
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | ':'))
+}
+
+fn is_safe_url(url: &str) -> bool {
+    let url = url
+        .chars()
+        .filter(|c| !c.is_whitespace() && !c.is_control())
+        .collect::<String>()
+        .to_ascii_lowercase();
+    match url.find(':') {
+        Some(colon) if !url[..colon].contains(['/', '?', '#']) => {
+            matches!(&url[..colon], "http" | "https" | "mailto" | "tel")
+        }
+        _ => true,
+    }
+}
+
+fn is_safe_attr(name: &str, value: &str) -> bool {
+    is_valid_name(name)
+        && !name.starts_with("on")
+        && name != "style"
+        && (!URL_ATTRS.contains(&name) || is_safe_url(value))
+}
+
+fn is_safe_tag(name: &str) -> bool {
+    is_valid_name(name) && !DISCARDED.contains(&name)
+}
+
+fn escape(s: &str, out: &mut String) {
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            c => out.push(c),
+        }
+    }
+}
+
+/// An element as it is written out, used to find which elements can stay open between blocks
+#[derive(Debug, Clone, PartialEq)]
+struct Tag {
+    name: String,
+    attrs: Vec<(String, String)>,
+}
+
+impl Tag {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            attrs: Vec::new(),
+        }
+    }
+}
+
+struct Writer<'a> {
+    schema: &'a HtmlSchema,
+    out: String,
+    /// The open block elements, from the outermost
+    elements: Vec<Tag>,
+    /// The open marks, from the outermost
+    marks: Vec<(&'a MarkSpec, Option<String>)>,
+    preformatted: bool,
+    /// Whether no text has been written in the current block
+    empty: bool,
+}
+
+impl<'a> Writer<'a> {
+    fn block(&mut self, block: &hydrate::Map) {
+        let schema = self.schema;
+        let str_value = |value: &hydrate::Value| match value {
+            hydrate::Value::Scalar(s) => s.to_str().map(String::from),
+            _ => None,
+        };
+        let attrs = match block.get("attrs") {
+            Some(hydrate::Value::Map(attrs)) => Some(attrs),
+            _ => None,
+        };
+
+        let mut elements = Vec::new();
+        // preserved unknown tags, from the outermost
+        if let Some(hydrate::Value::List(opaque)) =
+            attrs.and_then(|a| a.get(&self.schema.opaque_attr))
+        {
+            for item in opaque.iter() {
+                let hydrate::Value::Map(item) = &item.value else {
+                    continue;
+                };
+                let Some(name) = item.get("tag").and_then(str_value) else {
+                    continue;
+                };
+                if !is_safe_tag(&name) {
+                    continue;
+                }
+                let mut tag = Tag::new(&name);
+                if let Some(hydrate::Value::Map(attrs)) = item.get("attrs") {
+                    let mut attrs = attrs
+                        .iter()
+                        .filter_map(|(k, v)| Some((k.clone(), str_value(&v.value)?)))
+                        .filter(|(k, v)| is_safe_attr(k, v))
+                        .collect::<Vec<_>>();
+                    attrs.sort();
+                    tag.attrs = attrs;
+                }
+                elements.push(tag);
+            }
+        }
+        if let Some(hydrate::Value::List(parents)) = block.get("parents") {
+            for parent in parents.iter() {
+                let spec = str_value(&parent.value)
+                    .and_then(|p| schema.block_for_type(&p, None))
+                    .filter(|spec| spec.nests());
+                if let Some(spec) = spec {
+                    elements.extend(spec.container.as_deref().map(Tag::new));
+                    elements.push(Tag::new(&spec.tag));
+                }
+            }
+        }
+        let spec = block
+            .get("type")
+            .and_then(str_value)
+            .and_then(|t| self.schema.block_for_type(&t, attrs))
+            .unwrap_or(&self.schema.default_block);
+        elements.extend(spec.container.as_deref().map(Tag::new));
+
+        self.close_marks(0);
+        let common = self
+            .elements
+            .iter()
+            .zip(&elements)
+            .take_while(|(a, b)| a == b)
+            .count();
+        // a default block at the start of an empty list item would be read back as its text
+        let default = &schema.default_block;
+        let next = elements
+            .get(common)
+            .map_or(spec.tag.as_str(), |t| t.name.as_str());
+        if self.empty
+            && common == self.elements.len()
+            && next == default.tag
+            && default.container.is_none()
+        {
+            self.out.push_str(&format!("<{0}></{0}>", default.tag));
+        }
+        self.close_elements(common);
+        elements.push(Tag::new(&spec.tag));
+        for tag in elements.into_iter().skip(common) {
+            self.out.push('<');
+            self.out.push_str(&tag.name);
+            for (name, value) in &tag.attrs {
+                self.out.push(' ');
+                self.out.push_str(name);
+                self.out.push_str("=\"");
+                escape(value, &mut self.out);
+                self.out.push('"');
+            }
+            self.out.push('>');
+            self.elements.push(tag);
+        }
+        self.preformatted = spec.preformatted;
+        self.empty = true;
+    }
+
+    fn text(&mut self, text: &str, marks: Option<&MarkSet>) {
+        let active = if self.preformatted {
+            Vec::new()
+        } else {
+            let schema = self.schema;
+            schema
+                .marks
+                .iter()
+                .filter_map(|spec| {
+                    let (_, value) = marks?.iter().find(|(name, _)| *name == spec.name)?;
+                    match (&spec.value_attr, value) {
+                        (Some(attr), value) => {
+                            let value = value.to_str()?;
+                            is_safe_attr(attr, value).then(|| (spec, Some(value.to_string())))
+                        }
+                        (None, ScalarValue::Null | ScalarValue::Boolean(false)) => None,
+                        (None, _) => Some((spec, None)),
+                    }
+                })
+                .collect::<Vec<_>>()
+        };
+        let common = self
+            .marks
+            .iter()
+            .zip(&active)
+            .take_while(|(a, b)| std::ptr::eq(a.0, b.0) && a.1 == b.1)
+            .count();
+        self.close_marks(common);
+        for (spec, value) in active.into_iter().skip(common) {
+            self.out.push('<');
+            self.out.push_str(&spec.tag);
+            if let (Some(attr), Some(value)) = (&spec.value_attr, &value) {
+                self.out.push(' ');
+                self.out.push_str(attr);
+                self.out.push_str("=\"");
+                escape(value, &mut self.out);
+                self.out.push('"');
+            }
+            self.out.push('>');
+            self.marks.push((spec, value));
+        }
+        if self.preformatted {
+            // a newline straight after <pre> is dropped when reading it back
+            if self.empty && text.starts_with('\n') {
+                self.out.push('\n');
+            }
+            self.empty &= text.is_empty();
+            escape(text, &mut self.out);
+        } else {
+            self.empty &= text.is_empty();
+            for (i, line) in text.split('\n').enumerate() {
+                if i > 0 {
+                    self.out.push_str("<br>");
+                }
+                escape(line, &mut self.out);
+            }
+        }
+    }
+
+    fn close_marks(&mut self, keep: usize) {
+        for (spec, _) in self.marks.drain(keep..).rev() {
+            self.out.push_str("</");
+            self.out.push_str(&spec.tag);
+            self.out.push('>');
+        }
+    }
+
+    fn close_elements(&mut self, keep: usize) {
+        for tag in self.elements.drain(keep..).rev() {
+            self.out.push_str("</");
+            self.out.push_str(&tag.name);
+            self.out.push('>');
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Start {
+        name: String,
+        attrs: Vec<(String, String)>,
+        self_closing: bool,
+    },
+    End(String),
+    Text(String),
+}
+
+fn decode_entities(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let decoded = rest.find(';').filter(|end| *end <= 10).and_then(|end| {
+            let entity = &rest[1..end];
+            let c = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some('\u{a0}'),
+                _ => match entity.strip_prefix('#') {
+                    Some(hex) if hex.starts_with(['x', 'X']) => u32::from_str_radix(&hex[1..], 16)
+                        .ok()
+                        .and_then(char::from_u32),
+                    Some(dec) => dec.parse().ok().and_then(char::from_u32),
+                    None => None,
+                },
+            };
+            c.map(|c| (c, end + 1))
+        });
+        match decoded {
+            Some((c, len)) => {
+                out.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn tokenize(html: &str) -> Vec<Token> {
+    // lowercasing ASCII keeps byte offsets the same
+    let lower = html.to_ascii_lowercase();
+    let bytes = html.as_bytes();
+    let mut tokens = Vec::new();
+    let mut text_start = 0;
+    let mut i = 0;
+    let end_of = |from: usize, pat: &str| lower[from..].find(pat).map(|p| from + p);
+
+    while i < bytes.len() {
+        if bytes[i] != b'<' {
+            i += 1;
+            continue;
+        }
+        let next = bytes.get(i + 1).copied().unwrap_or_default();
+        let is_end = next == b'/' && bytes.get(i + 2).is_some_and(u8::is_ascii_alphabetic);
+        let skip_to = if lower[i..].starts_with("<!--") {
+            Some(end_of(i + 4, "-->").map_or(bytes.len(), |e| e + 3))
+        } else if next == b'!' || next == b'?' {
+            Some(end_of(i, ">").map_or(bytes.len(), |e| e + 1))
+        } else {
+            None
+        };
+        if !(is_end || next.is_ascii_alphabetic() || skip_to.is_some()) {
+            i += 1;
+            continue;
+        }
+        if text_start < i {
+            tokens.push(Token::Text(decode_entities(&html[text_start..i])));
+        }
+        if let Some(skip_to) = skip_to {
+            i = skip_to;
+            text_start = i;
+            continue;
+        }
+
+        // the name of the tag, then attributes until the closing `>`
+        let name_start = if is_end { i + 2 } else { i + 1 };
+        let mut j = name_start;
+        while j < bytes.len() && !bytes[j].is_ascii_whitespace() && !matches!(bytes[j], b'>' | b'/')
+        {
+            j += 1;
+        }
+        let name = lower[name_start..j].to_string();
+        let mut attrs = Vec::new();
+        let mut self_closing = false;
+        loop {
+            while j < bytes.len() && bytes[j].is_ascii_whitespace() {
+                j += 1;
+            }
+            match bytes.get(j) {
+                None => break,
+                Some(b'>') => {
+                    j += 1;
+                    break;
+                }
+                Some(b'/') => {
+                    self_closing = true;
+                    j += 1;
+                    continue;
+                }
+                _ => {}
+            }
+            let attr_start = j;
+            while j < bytes.len()
+                && !bytes[j].is_ascii_whitespace()
+                && !matches!(bytes[j], b'>' | b'/' | b'=')
+            {
+                j += 1;
+            }
+            let attr = lower[attr_start..j].to_string();
+            while j < bytes.len() && bytes[j].is_ascii_whitespace() {
+                j += 1;
+            }
+            let mut value = String::new();
+            if bytes.get(j) == Some(&b'=') {
+                j += 1;
+                while j < bytes.len() && bytes[j].is_ascii_whitespace() {
+                    j += 1;
+                }
+                let value_end = match bytes.get(j) {
+                    Some(q @ (b'"' | b'\'')) => {
+                        j += 1;
+                        let end = html[j..].find(*q as char).map_or(bytes.len(), |e| j + e);
+                        value = decode_entities(&html[j..end]);
+                        (end + 1).min(bytes.len())
+                    }
+                    _ => {
+                        let start = j;
+                        while j < bytes.len() && !bytes[j].is_ascii_whitespace() && bytes[j] != b'>'
+                        {
+                            j += 1;
+                        }
+                        value = decode_entities(&html[start..j]);
+                        j
+                    }
+                };
+                j = value_end;
+            }
+            if !attr.is_empty() {
+                attrs.push((attr, value));
+            } else if j == attr_start {
+                j += 1;
+            }
+        }
+        i = j;
+
+        if is_end {
+            tokens.push(Token::End(name));
+        } else if RAW_TEXT.contains(&name.as_str()) {
+            // the content of raw text elements is never kept
+            let close = format!("</{}", name);
+            i = end_of(i, &close)
+                .and_then(|e| end_of(e, ">"))
+                .map_or(bytes.len(), |e| e + 1);
+        } else {
+            tokens.push(Token::Start {
+                name,
+                attrs,
+                self_closing,
+            });
+        }
+        text_start = i;
+    }
+    if text_start < bytes.len() {
+        tokens.push(Token::Text(decode_entities(&html[text_start..])));
+    }
+    tokens
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Element(Element),
+    Text(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Element {
+    name: String,
+    attrs: Vec<(String, String)>,
+    children: Vec<Node>,
+}
+
+/// Build a tree from the tokens, closing elements the way browsers do for the common cases of
+/// missing end tags
+fn build_tree(tokens: Vec<Token>) -> Vec<Node> {
+    fn close(stack: &mut Vec<Element>) {
+        let element = stack.pop().expect("the root is never closed");
+        if let Some(parent) = stack.last_mut() {
+            parent.children.push(Node::Element(element));
+        }
+    }
+
+    let mut stack = vec![Element {
+        name: String::new(),
+        attrs: Vec::new(),
+        children: Vec::new(),
+    }];
+    for token in tokens {
+        match token {
+            Token::Text(text) => {
+                let parent = stack.last_mut().expect("the root is never closed");
+                parent.children.push(Node::Text(text));
+            }
+            Token::Start {
+                name,
+                attrs,
+                self_closing,
+            } => {
+                let open = |stack: &Vec<Element>, name: &str, scope: &[&str]| {
+                    stack[1..]
+                        .iter()
+                        .rev()
+                        .take_while(|e| !scope.contains(&e.name.as_str()))
+                        .position(|e| e.name == name)
+                        .map(|p| stack.len() - 1 - p)
+                };
+                let closes_p = !INLINE.contains(&name.as_str());
+                if name == "li" {
+                    if let Some(li) = open(&stack, "li", &["ul", "ol"]) {
+                        while stack.len() > li {
+                            close(&mut stack);
+                        }
+                    }
+                } else if closes_p {
+                    if let Some(p) = open(&stack, "p", &["blockquote", "li", "div"]) {
+                        while stack.len() > p {
+                            close(&mut stack);
+                        }
+                    }
+                }
+                let element = Element {
+                    name,
+                    attrs,
+                    children: Vec::new(),
+                };
+                if self_closing || VOID.contains(&element.name.as_str()) {
+                    let parent = stack.last_mut().expect("the root is never closed");
+                    parent.children.push(Node::Element(element));
+                } else {
+                    stack.push(element);
+                }
+            }
+            Token::End(name) => {
+                if let Some(pos) = stack[1..].iter().rposition(|e| e.name == name) {
+                    while stack.len() > pos + 1 {
+                        close(&mut stack);
+                    }
+                }
+            }
+        }
+    }
+    while stack.len() > 1 {
+        close(&mut stack);
+    }
+    stack.pop().map(|root| root.children).unwrap_or_default()
+}
+
+/// Where in the block structure we are while importing
+#[derive(Debug, Clone, Default)]
+struct Context {
+    parents: Vec<String>,
+    /// The innermost container tag, such as `ul`
+    container: Option<String>,
+    /// Unknown tags being preserved, from the outermost
+    opaque: Vec<(String, Vec<(String, String)>)>,
+}
+
+/// The text of the block being imported
+struct Inline {
+    runs: Vec<(String, Vec<(String, ScalarValue)>)>,
+    /// The active marks, sorted by name
+    marks: Vec<(String, ScalarValue)>,
+    preformatted: bool,
+    /// Whether the next whitespace character should be dropped
+    skip_space: bool,
+}
+
+impl Inline {
+    fn push_text(&mut self, text: &str) {
+        let mut collapsed = String::new();
+        if self.preformatted {
+            collapsed.push_str(text);
+        } else {
+            for c in text.chars() {
+                if c.is_ascii_whitespace() {
+                    if !self.skip_space {
+                        collapsed.push(' ');
+                        self.skip_space = true;
+                    }
+                } else {
+                    collapsed.push(c);
+                    self.skip_space = false;
+                }
+            }
+        }
+        if collapsed.is_empty() {
+            return;
+        }
+        match self.runs.last_mut() {
+            Some((last, marks)) if *marks == self.marks => last.push_str(&collapsed),
+            _ => self.runs.push((collapsed, self.marks.clone())),
+        }
+    }
+
+    fn line_break(&mut self) {
+        self.trim_end();
+        match self.runs.last_mut() {
+            Some((last, marks)) if *marks == self.marks => last.push('\n'),
+            _ => self.runs.push(("\n".to_string(), self.marks.clone())),
+        }
+        self.skip_space = true;
+    }
+
+    fn trim_end(&mut self) {
+        if self.preformatted {
+            return;
+        }
+        while let Some((last, _)) = self.runs.last_mut() {
+            let trimmed = last.trim_end_matches(' ').len();
+            last.truncate(trimmed);
+            if last.is_empty() {
+                self.runs.pop();
+            } else {
+                break;
+            }
+        }
+    }
+}
+
+struct Importer<'a> {
+    schema: &'a HtmlSchema,
+    spans: Vec<Span>,
+    inline: Option<Inline>,
+}
+
+impl Importer<'_> {
+    fn start_block(&mut self, spec: &BlockSpec, ctx: &Context) {
+        self.finish_block();
+        let mut attrs = spec
+            .attrs
+            .iter()
+            .map(|(k, v)| (k.clone(), hydrate::Value::from(v.clone())))
+            .collect::<HashMap<String, hydrate::Value>>();
+        if !ctx.opaque.is_empty() {
+            let opaque = ctx
+                .opaque
+                .iter()
+                .map(|(tag, tag_attrs)| {
+                    let tag_attrs = tag_attrs
+                        .iter()
+                        .map(|(k, v)| (k.clone(), hydrate::Value::from(v.as_str())))
+                        .collect::<HashMap<String, hydrate::Value>>();
+                    hydrate::Value::from(hydrate::Map::from(HashMap::from([
+                        ("tag", hydrate::Value::from(tag.as_str())),
+                        ("attrs", hydrate::Map::from(tag_attrs).into()),
+                    ])))
+                })
+                .collect::<Vec<_>>();
+            attrs.insert(self.schema.opaque_attr.clone(), opaque.into());
+        }
+        let parents = ctx
+            .parents
+            .iter()
+            .map(|p| hydrate::Value::from(p.as_str()))
+            .collect::<Vec<_>>();
+        self.spans
+            .push(Span::Block(hydrate::Map::from(HashMap::from([
+                ("type", spec.block_type.as_str().into()),
+                ("parents", parents.into()),
+                ("attrs", hydrate::Map::from(attrs).into()),
+            ]))));
+        self.inline = Some(Inline {
+            runs: Vec::new(),
+            marks: Vec::new(),
+            preformatted: spec.preformatted,
+            skip_space: true,
+        });
+    }
+
+    fn finish_block(&mut self) {
+        let Some(mut inline) = self.inline.take() else {
+            return;
+        };
+        inline.trim_end();
+        if inline.preformatted {
+            // a newline straight after <pre> is not part of the content
+            if let Some((first, _)) = inline.runs.first_mut() {
+                if first.starts_with('\n') {
+                    first.remove(0);
+                }
+            }
+        }
+        for (text, marks) in inline.runs {
+            if text.is_empty() {
+                continue;
+            }
+            let marks =
+                (!marks.is_empty()).then(|| Arc::new(marks.into_iter().collect::<MarkSet>()));
+            self.spans.push(Span::Text { text, marks });
+        }
+    }
+
+    fn inline(&mut self, ctx: &Context) -> &mut Inline {
+        if self.inline.is_none() {
+            self.start_block(&self.schema.default_block, ctx);
+        }
+        self.inline.as_mut().expect("a block was just started")
+    }
+
+    fn is_block_level(&self, element: &Element) -> bool {
+        !INLINE.contains(&element.name.as_str())
+            || self.schema.blocks().any(|b| {
+                b.matches(&element.name, None) || b.container.as_deref() == Some(&element.name)
+            })
+    }
+
+    fn blocks(&mut self, nodes: &[Node], ctx: &Context) {
+        for node in nodes {
+            match node {
+                Node::Text(text) => {
+                    if self.inline.is_some() || !text.trim().is_empty() {
+                        self.inline(ctx).push_text(text);
+                    }
+                }
+                Node::Element(element) => self.block_element(element, ctx),
+            }
+        }
+    }
+
+    fn block_element(&mut self, element: &Element, ctx: &Context) {
+        let name = element.name.as_str();
+        if DISCARDED.contains(&name) {
+            return;
+        }
+        if !self.is_block_level(element) {
+            self.inline_element(element, ctx);
+            return;
+        }
+        self.finish_block();
+        if self.schema.is_container(name) {
+            let mut inner = ctx.clone();
+            inner.container = Some(name.to_string());
+            self.blocks(&element.children, &inner);
+        } else if let Some(spec) = self.schema.block_for_tag(name, ctx.container.as_deref()) {
+            let spec = spec.clone();
+            let mut inner = ctx.clone();
+            if spec.nests() {
+                inner.parents.push(spec.block_type.clone());
+            }
+            inner.container = None;
+            if spec.parent_only {
+                self.blocks(&element.children, &inner);
+            } else {
+                self.start_block(&spec, ctx);
+                self.leaf(&element.children, ctx, &inner);
+            }
+        } else {
+            let mut inner = ctx.clone();
+            // preserved tags are written outside the parents of a block, so they can only be
+            // kept when they are outside them too. Block tags used outside their container,
+            // such as an <li> outside a list, are never kept.
+            if self.schema.unknown_tags == UnknownTags::Preserve
+                && is_safe_tag(name)
+                && ctx.parents.is_empty()
+                && !self
+                    .schema
+                    .blocks()
+                    .any(|b| b.matches(name, b.container.as_deref()))
+            {
+                let attrs = element
+                    .attrs
+                    .iter()
+                    .filter(|(k, v)| is_safe_attr(k, v))
+                    .cloned()
+                    .collect();
+                inner.opaque.push((name.to_string(), attrs));
+            }
+            self.blocks(&element.children, &inner);
+        }
+        self.finish_block();
+    }
+
+    /// The content of a block which contains text, `nested` is the context for any blocks
+    /// inside it, such as a list inside a list item
+    fn leaf(&mut self, nodes: &[Node], ctx: &Context, nested: &Context) {
+        // text after a nested block is inside the block rather than part of it
+        let mut text_ctx = ctx;
+        let mut first = true;
+        for node in nodes {
+            match node {
+                Node::Text(text) => {
+                    if self.inline.is_some() || !text.trim().is_empty() {
+                        self.inline(text_ctx).push_text(text);
+                    }
+                }
+                Node::Element(element) if DISCARDED.contains(&element.name.as_str()) => {}
+                Node::Element(element) if self.is_block_level(element) => {
+                    // e.g. a <p> at the start of an <li>, the text belongs to the list item
+                    let merges = std::mem::take(&mut first)
+                        && self.schema.default_block.matches(&element.name, None)
+                        && self.inline.as_ref().is_some_and(|i| i.runs.is_empty());
+                    if merges {
+                        self.leaf(&element.children, ctx, nested);
+                    } else {
+                        self.block_element(element, nested);
+                        text_ctx = nested;
+                    }
+                }
+                Node::Element(element) => {
+                    first = false;
+                    self.inline_element(element, text_ctx);
+                }
+            }
+        }
+    }
+
+    fn inline_element(&mut self, element: &Element, ctx: &Context) {
+        let name = element.name.as_str();
+        if DISCARDED.contains(&name) {
+            return;
+        }
+        if name == "br" {
+            self.inline(ctx).line_break();
+            return;
+        }
+        let inline = self.inline(ctx);
+        let saved = inline.marks.clone();
+        if !inline.preformatted {
+            if let Some(spec) = self.schema.marks.iter().find(|m| m.matches(name)) {
+                let value = match &spec.value_attr {
+                    Some(attr) => element
+                        .attrs
+                        .iter()
+                        .find(|(k, v)| k == attr && is_safe_attr(k, v))
+                        .map(|(_, v)| ScalarValue::Str(v.into())),
+                    None => Some(ScalarValue::Boolean(true)),
+                };
+                if let Some(value) = value {
+                    let inline = self.inline.as_mut().expect("a block was just started");
+                    match inline
+                        .marks
+                        .binary_search_by(|(n, _)| n.as_str().cmp(spec.name.as_str()))
+                    {
+                        Ok(i) => inline.marks[i].1 = value,
+                        Err(i) => inline.marks.insert(i, (spec.name.clone(), value)),
+                    }
+                }
+            }
+        }
+        for child in &element.children {
+            match child {
+                Node::Text(text) => self.inline(ctx).push_text(text),
+                Node::Element(child) => self.inline_element(child, ctx),
+            }
+        }
+        if let Some(inline) = self.inline.as_mut() {
+            inline.marks = saved;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokenize_tags_attributes_and_entities() {
+        let tokens = tokenize(
+            "<P class=a id='b' hidden>x &amp; y &#x41;&#66;<br/></p><!-- gone --><script>1 < 2</script>z",
+        );
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Start {
+                    name: "p".to_string(),
+                    attrs: vec![
+                        ("class".to_string(), "a".to_string()),
+                        ("id".to_string(), "b".to_string()),
+                        ("hidden".to_string(), String::new()),
+                    ],
+                    self_closing: false,
+                },
+                Token::Text("x & y AB".to_string()),
+                Token::Start {
+                    name: "br".to_string(),
+                    attrs: vec![],
+                    self_closing: true,
+                },
+                Token::End("p".to_string()),
+                Token::Text("z".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn tree_closes_implied_end_tags() {
+        let nodes = build_tree(tokenize("<ul><li>a<li>b</ul><p>c<p>d"));
+        let names = |nodes: &[Node]| {
+            nodes
+                .iter()
+                .map(|n| match n {
+                    Node::Element(e) => e.name.clone(),
+                    Node::Text(t) => t.clone(),
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(&nodes), vec!["ul", "p", "p"]);
+        let Node::Element(ul) = &nodes[0] else {
+            panic!("expected an element")
+        };
+        assert_eq!(names(&ul.children), vec!["li", "li"]);
+    }
+
+    #[test]
+    fn unsafe_urls_and_attributes() {
+        assert!(is_safe_url("https://example.com"));
+        assert!(is_safe_url("/relative/path?a=b:c"));
+        assert!(is_safe_url("mailto:someone@example.com"));
+        assert!(!is_safe_url("javascript:alert(1)"));
+        assert!(!is_safe_url(" JaVa\tScRiPt:alert(1)"));
+        assert!(!is_safe_url("data:text/html,hello"));
+        assert!(!is_safe_attr("onclick", "x"));
+        assert!(!is_safe_attr("style", "color: red"));
+        assert!(is_safe_attr("class", "note"));
+    }
+}
@@ -1,12 +1,15 @@
+use crate::extension::ExtensionValue;
 use crate::op_set2::{Op, OpSet, OpType};
 use crate::types::{Clock, ObjId, ScalarValue, SequenceType};
-use crate::TextEncoding;
 use crate::{error::HydrateError, value, ObjType, Patch, PatchAction, Prop};
+use crate::{Decimal, TextEncoding};
 use std::borrow::Cow;
 use std::collections::HashMap;
 
 mod list;
 mod map;
+mod ordered_map;
+mod set;
 mod text;
 
 #[cfg(test)]
@@ -16,6 +19,8 @@ use crate::Automerge;
 
 pub use list::{List, ListValue};
 pub use map::{Map, MapValue};
+pub use ordered_map::OrderedMap;
+pub use set::Set;
 pub use text::Text;
 
 #[derive(Clone, Debug, PartialEq)]
@@ -24,6 +29,8 @@ pub enum Value {
     Map(Map),
     List(List),
     Text(Text),
+    Set(Set),
+    OrderedMap(OrderedMap),
 }
 
 impl Value {
@@ -33,6 +40,8 @@ impl Value {
             value::Value::Object(ObjType::List) => Value::List(List::default()),
             value::Value::Object(ObjType::Text) => Value::Text(Text::new(text_encoding, "")),
             value::Value::Object(ObjType::Table) => Value::Map(Map::default()),
+            value::Value::Object(ObjType::Set) => Value::Set(Set::default()),
+            value::Value::Object(ObjType::OrderedMap) => Value::OrderedMap(OrderedMap::default()),
             value::Value::Scalar(s) => Value::Scalar(s.into_owned()),
         }
     }
@@ -45,6 +54,14 @@ impl Value {
         Value::List(List::default())
     }
 
+    pub fn set() -> Self {
+        Value::Set(Set::default())
+    }
+
+    pub fn ordered_map() -> Self {
+        Value::OrderedMap(OrderedMap::default())
+    }
+
     pub fn text(text_encoding: TextEncoding, s: &str) -> Self {
         Value::Text(Text::new(text_encoding, s))
     }
@@ -80,12 +97,77 @@ impl Value {
         text_encoding: TextEncoding,
         patches: P,
     ) -> Result<(), HydrateError> {
+        // objects which have been detached and not yet attached again
+        let mut detached = HashMap::new();
         for p in patches {
-            self.apply(p.path.iter().map(|(_, prop)| prop), text_encoding, p.action)?;
+            let path = p.path.iter().map(|(_, prop)| prop);
+            match p.action {
+                PatchAction::Detach { prop, moved } => {
+                    let value = self.get_path_mut(path)?.detach(&prop)?;
+                    detached.insert(moved, value);
+                }
+                PatchAction::Attach {
+                    prop,
+                    moved,
+                    conflict,
+                } => {
+                    let value = detached
+                        .remove(&moved)
+                        .ok_or(HydrateError::NotDetached(moved))?;
+                    self.get_path_mut(path)?.attach(prop, value, conflict)?;
+                }
+                action => self.apply(path, text_encoding, action)?,
+            }
         }
         Ok(())
     }
 
+    fn get_path_mut<'a, P: Iterator<Item = &'a Prop>>(
+        &mut self,
+        path: P,
+    ) -> Result<&mut Value, HydrateError> {
+        let mut value = self;
+        for prop in path {
+            value = match (prop, value) {
+                (Prop::Seq(n), Value::List(list)) => {
+                    list.get_mut(*n).ok_or(HydrateError::InvalidIndex(*n))?
+                }
+                (Prop::Map(s), Value::Map(map)) => map
+                    .get_mut(s)
+                    .ok_or_else(|| HydrateError::InvalidKey(s.clone()))?,
+                (Prop::Map(s), Value::OrderedMap(map)) => map
+                    .get_mut(s)
+                    .ok_or_else(|| HydrateError::InvalidKey(s.clone()))?,
+                _ => return Err(HydrateError::Fail),
+            };
+        }
+        Ok(value)
+    }
+
+    fn detach(&mut self, prop: &Prop) -> Result<Value, HydrateError> {
+        match (prop, self) {
+            (Prop::Seq(n), Value::List(list)) => list.remove_value(*n),
+            (Prop::Map(s), Value::Map(map)) => map.remove_value(s),
+            (Prop::Map(s), Value::OrderedMap(map)) => map.remove_value(s),
+            _ => Err(HydrateError::Fail),
+        }
+    }
+
+    fn attach(&mut self, prop: Prop, value: Value, conflict: bool) -> Result<(), HydrateError> {
+        match (prop, self) {
+            (Prop::Seq(n), Value::List(list)) => list.insert_value(n, value, conflict),
+            (Prop::Map(s), Value::Map(map)) => {
+                map.insert_value(s, value, conflict);
+                Ok(())
+            }
+            (Prop::Map(s), Value::OrderedMap(map)) => {
+                map.insert_value(s, value, conflict);
+                Ok(())
+            }
+            _ => Err(HydrateError::Fail),
+        }
+    }
+
     pub(crate) fn apply<'a, P: Iterator<Item = &'a Prop>>(
         &mut self,
         mut path: P,
@@ -101,6 +183,10 @@ impl Value {
                 .get_mut(s)
                 .ok_or_else(|| HydrateError::ApplyInvalidProp(patch.clone()))?
                 .apply(path, text_encoding, patch),
+            (Some(Prop::Map(s)), Value::OrderedMap(map)) => map
+                .get_mut(s)
+                .ok_or_else(|| HydrateError::ApplyInvalidProp(patch.clone()))?
+                .apply(path, text_encoding, patch),
             // Hydrated text represents embedded block objects only by their
             // replacement characters, so patches to a block's contents do
             // not affect the hydrated text value.
@@ -108,6 +194,8 @@ impl Value {
             (None, Value::Map(map)) => map.apply(text_encoding, patch),
             (None, Value::List(list)) => list.apply(text_encoding, patch),
             (None, Value::Text(text)) => text.apply(text_encoding, patch),
+            (None, Value::Set(set)) => set.apply(patch),
+            (None, Value::OrderedMap(map)) => map.apply(text_encoding, patch),
             _ => Err(HydrateError::Fail),
         }
     }
@@ -140,6 +228,8 @@ impl From<Value> for value::Value<'_> {
             Value::Map(_) => value::Value::Object(ObjType::Map),
             Value::List(_) => value::Value::Object(ObjType::List),
             Value::Text(_) => value::Value::Object(ObjType::Text),
+            Value::Set(_) => value::Value::Object(ObjType::Set),
+            Value::OrderedMap(_) => value::Value::Object(ObjType::OrderedMap),
             Value::Scalar(s) => value::Value::Scalar(Cow::Owned(s)),
         }
     }
@@ -163,12 +253,26 @@ impl From<Text> for Value {
     }
 }
 
+impl From<Set> for Value {
+    fn from(value: Set) -> Self {
+        Value::Set(value)
+    }
+}
+
+impl From<OrderedMap> for Value {
+    fn from(value: OrderedMap) -> Self {
+        Value::OrderedMap(value)
+    }
+}
+
 impl From<&Value> for value::Value<'_> {
     fn from(value: &Value) -> Self {
         match value {
             Value::Map(_) => value::Value::Object(ObjType::Map),
             Value::List(_) => value::Value::Object(ObjType::List),
             Value::Text(_) => value::Value::Object(ObjType::Text),
+            Value::Set(_) => value::Value::Object(ObjType::Set),
+            Value::OrderedMap(_) => value::Value::Object(ObjType::OrderedMap),
             Value::Scalar(s) => value::Value::Scalar(Cow::Owned(s.clone())),
         }
     }
@@ -222,6 +326,18 @@ impl From<f32> for Value {
     }
 }
 
+impl From<Decimal> for Value {
+    fn from(value: Decimal) -> Self {
+        Value::Scalar(ScalarValue::Decimal(value))
+    }
+}
+
+impl From<ExtensionValue> for Value {
+    fn from(value: ExtensionValue) -> Self {
+        Value::Scalar(ScalarValue::Extension(value))
+    }
+}
+
 impl From<ScalarValue> for Value {
     fn from(value: ScalarValue) -> Self {
         Value::Scalar(value)
@@ -238,6 +354,13 @@ impl Automerge {
     pub(crate) fn hydrate_text(&self, obj: &ObjId, clock: Option<&Clock>) -> Value {
         self.ops().hydrate_text(obj, clock, self.text_encoding())
     }
+    pub(crate) fn hydrate_set(&self, obj: &ObjId, clock: Option<&Clock>) -> Value {
+        self.ops().hydrate_set(obj, clock)
+    }
+    pub(crate) fn hydrate_ordered_map(&self, obj: &ObjId, clock: Option<&Clock>) -> Value {
+        self.ops()
+            .hydrate_ordered_map(obj, clock, self.text_encoding())
+    }
 }
 
 impl OpSet {
@@ -258,6 +381,29 @@ impl OpSet {
         Value::Map(map)
     }
 
+    pub(crate) fn hydrate_ordered_map(
+        &self,
+        obj: &ObjId,
+        clock: Option<&Clock>,
+        encoding: TextEncoding,
+    ) -> Value {
+        let mut map = OrderedMap::default();
+        let tops = self
+            .top_ops(obj, clock.cloned())
+            .map(|top| (self.to_string(top.elemid_or_key()), top));
+        let sorted = crate::ordered_map::sort(
+            tops,
+            |(key, _)| key,
+            |(_, top)| crate::ordered_map::position(&top.value),
+        );
+        for ((key, top), _) in sorted {
+            let conflict = top.conflict;
+            let value = self.hydrate_op(top, clock, encoding);
+            map.insert_value(key, value, conflict);
+        }
+        Value::OrderedMap(map)
+    }
+
     pub(crate) fn hydrate_list(
         &self,
         obj: &ObjId,
@@ -285,6 +431,16 @@ impl OpSet {
         Value::Text(Text::new(encoding, text))
     }
 
+    pub(crate) fn hydrate_set(&self, obj: &ObjId, clock: Option<&Clock>) -> Value {
+        let mut set = Set::default();
+        for top in self.top_ops(obj, clock.cloned()) {
+            if let OpType::Put(value) = top.action() {
+                set.insert(value.into());
+            }
+        }
+        Value::Set(set)
+    }
+
     pub(crate) fn hydrate_op(
         &self,
         op: Op<'_>,
@@ -296,6 +452,10 @@ impl OpSet {
             OpType::Make(ObjType::Table) => self.hydrate_map(&op.id.into(), clock, encoding),
             OpType::Make(ObjType::List) => self.hydrate_list(&op.id.into(), clock, encoding),
             OpType::Make(ObjType::Text) => self.hydrate_text(&op.id.into(), clock, encoding),
+            OpType::Make(ObjType::Set) => self.hydrate_set(&op.id.into(), clock),
+            OpType::Make(ObjType::OrderedMap) => {
+                self.hydrate_ordered_map(&op.id.into(), clock, encoding)
+            }
             OpType::Put(scalar) => Value::Scalar(scalar.into()),
             _ => panic!("invalid op to hydrate"),
         }
@@ -312,6 +472,13 @@ macro_rules! hydrate_map {
     };
 }
 
+#[macro_export]
+macro_rules! hydrate_ordered_map {
+    {$($k: expr => $v: expr),* $(,)?} => {
+        $crate::hydrate::OrderedMap::from_iter([$(($k, $crate::hydrate::Value::from($v)),)*])
+    };
+}
+
 #[macro_export]
 macro_rules! hydrate_list {
     {$($v: expr),* $(,)?} => {
@@ -319,6 +486,13 @@ macro_rules! hydrate_list {
     };
 }
 
+#[macro_export]
+macro_rules! hydrate_set {
+    {$($v: expr),* $(,)?} => {
+        $crate::hydrate::Set::from_iter([$($crate::ScalarValue::from($v),)*])
+    };
+}
+
 #[macro_export]
 macro_rules! hydrate_text {
     {$t: expr} => {
@@ -348,11 +522,13 @@ impl From<&Value> for wasm_bindgen::JsValue {
                 ScalarValue::Counter(v) => (f64::from(v)).into(),
                 ScalarValue::Timestamp(v) => Date::new(&(*v as f64).into()).into(),
                 ScalarValue::Boolean(v) => (*v).into(),
+                ScalarValue::Decimal(v) => v.to_string().into(),
                 ScalarValue::Null => JsValue::null(),
                 ScalarValue::Unknown {
                     bytes,
                     type_code: _,
                 } => Uint8Array::from(bytes.as_slice()).into(),
+                ScalarValue::Extension(e) => Uint8Array::from(e.bytes()).into(),
             },
             Value::Map(m) => {
                 let result = Object::new();
@@ -367,6 +543,18 @@ impl From<&Value> for wasm_bindgen::JsValue {
                 .collect::<Array>()
                 .into(),
             Value::Text(t) => String::from(t).into(),
+            Value::Set(s) => s
+                .iter()
+                .map(|v| JsValue::from(&Value::Scalar(v.clone())))
+                .collect::<Array>()
+                .into(),
+            Value::OrderedMap(m) => {
+                let result = Object::new();
+                for (key, val) in m.iter() {
+                    Reflect::set(&result, &key.into(), &JsValue::from(&val.value)).unwrap();
+                }
+                result.into()
+            }
         }
     }
 }
@@ -85,9 +85,19 @@ impl List {
                     .conflict = true;
                 Ok(())
             }
-            PatchAction::Mark { marks: _ } => {
-                todo!()
+            PatchAction::Move { from, to } => {
+                if from >= self.0.len() {
+                    return Err(HydrateError::InvalidIndex(from));
+                }
+                if to >= self.0.len() {
+                    return Err(HydrateError::InvalidIndex(to));
+                }
+                let value = self.0.remove(from);
+                self.0.insert(to, value);
+                Ok(())
             }
+            // hydrated lists don't carry marks
+            PatchAction::Mark { marks: _ } => Ok(()),
             _ => Err(HydrateError::InvalidListOp),
         }
     }
@@ -100,6 +110,26 @@ impl List {
         self.0.get(index).map(|lv| &lv.value)
     }
 
+    pub(crate) fn remove_value(&mut self, index: usize) -> Result<Value, HydrateError> {
+        if index >= self.0.len() {
+            return Err(HydrateError::InvalidIndex(index));
+        }
+        Ok(self.0.remove(index).value)
+    }
+
+    pub(crate) fn insert_value(
+        &mut self,
+        index: usize,
+        value: Value,
+        conflict: bool,
+    ) -> Result<(), HydrateError> {
+        if index > self.0.len() {
+            return Err(HydrateError::InvalidIndex(index));
+        }
+        self.0.insert(index, ListValue::new(value, conflict));
+        Ok(())
+    }
+
     pub(crate) fn push<V: Into<Value>>(&mut self, value: V, _id: ExId, conflict: bool) {
         self.0.push(ListValue::new(value.into(), conflict))
     }
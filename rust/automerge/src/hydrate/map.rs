@@ -75,6 +75,17 @@ impl Map {
     pub(crate) fn new() -> Self {
         Self(Default::default())
     }
+
+    pub(crate) fn remove_value(&mut self, key: &str) -> Result<Value, HydrateError> {
+        self.0
+            .remove(key)
+            .map(|mv| mv.value)
+            .ok_or_else(|| HydrateError::InvalidKey(key.to_string()))
+    }
+
+    pub(crate) fn insert_value(&mut self, key: String, value: Value, conflict: bool) {
+        self.0.insert(key, MapValue { value, conflict });
+    }
 }
 
 impl MapValue {
@@ -0,0 +1,129 @@
+use crate::types::Prop;
+use crate::{PatchAction, TextEncoding};
+
+use super::{HydrateError, MapValue, Value};
+
+/// A map whose keys are in the order [`crate::ReadDoc::keys`] returns them
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OrderedMap(Vec<(String, MapValue)>);
+
+impl OrderedMap {
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &MapValue)> {
+        self.0.iter().map(|(k, v)| (k, v))
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.0.iter().map(|(k, _)| k.as_str())
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.entry(key).map(|mv| &mv.value)
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Value> {
+        self.entry_mut(key).map(|mv| &mut mv.value)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Put `value` at `key`, which keeps its place if it is already in the map and is added to
+    /// the end otherwise
+    pub fn insert<K: Into<String>, V: Into<Value>>(&mut self, key: K, value: V) {
+        self.insert_value(key.into(), value.into(), false);
+    }
+
+    /// Remove `key` from the map, returning its value if it was there
+    pub fn remove(&mut self, key: &str) -> Option<Value> {
+        let index = self.index_of(key)?;
+        Some(self.0.remove(index).1.value)
+    }
+
+    pub(crate) fn apply(
+        &mut self,
+        text_encoding: TextEncoding,
+        patch: PatchAction,
+    ) -> Result<(), HydrateError> {
+        match patch {
+            PatchAction::DeleteMap { key } => {
+                self.remove(&key);
+                Ok(())
+            }
+            PatchAction::PutMap {
+                key,
+                value,
+                conflict,
+            } => {
+                let h_value = Value::new(value.0, text_encoding);
+                self.insert_value(key, h_value, conflict);
+                Ok(())
+            }
+            PatchAction::MoveKey { key, index } => {
+                let from = self
+                    .index_of(&key)
+                    .ok_or_else(|| HydrateError::InvalidKey(key.clone()))?;
+                let entry = self.0.remove(from);
+                self.0.insert(index.min(self.0.len()), entry);
+                Ok(())
+            }
+            PatchAction::Increment {
+                prop: Prop::Map(key),
+                value,
+            } => {
+                self.entry_mut(&key)
+                    .ok_or(HydrateError::InvalidKey(key))?
+                    .increment(value)?;
+                Ok(())
+            }
+            PatchAction::Conflict {
+                prop: Prop::Map(key),
+            } => {
+                self.entry_mut(&key)
+                    .ok_or(HydrateError::InvalidKey(key))?
+                    .conflict = true;
+                Ok(())
+            }
+            _ => Err(HydrateError::InvalidMapOp),
+        }
+    }
+
+    pub(crate) fn remove_value(&mut self, key: &str) -> Result<Value, HydrateError> {
+        self.remove(key)
+            .ok_or_else(|| HydrateError::InvalidKey(key.to_string()))
+    }
+
+    pub(crate) fn insert_value(&mut self, key: String, value: Value, conflict: bool) {
+        let value = MapValue { value, conflict };
+        match self.entry_mut(&key) {
+            Some(entry) => *entry = value,
+            None => self.0.push((key, value)),
+        }
+    }
+
+    fn index_of(&self, key: &str) -> Option<usize> {
+        self.0.iter().position(|(k, _)| k == key)
+    }
+
+    fn entry(&self, key: &str) -> Option<&MapValue> {
+        self.0.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+
+    fn entry_mut(&mut self, key: &str) -> Option<&mut MapValue> {
+        self.0.iter_mut().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+}
+
+impl<K: Into<String>, V: Into<Value>> FromIterator<(K, V)> for OrderedMap {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = OrderedMap::default();
+        for (key, value) in iter {
+            map.insert(key, value);
+        }
+        map
+    }
+}
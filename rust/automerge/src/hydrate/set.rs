@@ -0,0 +1,67 @@
+use std::collections::BTreeMap;
+
+use crate::{PatchAction, ScalarValue};
+
+use super::HydrateError;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Set(BTreeMap<String, ScalarValue>);
+
+impl Set {
+    /// The values in the set, in the order [`crate::ReadDoc::set_values`] returns them
+    pub fn iter(&self) -> impl Iterator<Item = &ScalarValue> {
+        self.0.values()
+    }
+
+    pub fn contains(&self, value: &ScalarValue) -> bool {
+        value.set_key().is_some_and(|key| self.0.contains_key(&key))
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Add `value` to the set, returning whether it was not already there. Counters cannot be
+    /// members of a set and are ignored.
+    pub fn insert(&mut self, value: ScalarValue) -> bool {
+        match value.set_key() {
+            Some(key) => self.0.insert(key, value).is_none(),
+            None => false,
+        }
+    }
+
+    /// Remove `value` from the set, returning whether it was there
+    pub fn remove(&mut self, value: &ScalarValue) -> bool {
+        value
+            .set_key()
+            .is_some_and(|key| self.0.remove(&key).is_some())
+    }
+
+    pub(crate) fn apply(&mut self, patch: PatchAction) -> Result<(), HydrateError> {
+        match patch {
+            PatchAction::SetAdd { value } => {
+                self.insert(value);
+                Ok(())
+            }
+            PatchAction::SetRemove { value } => {
+                self.remove(&value);
+                Ok(())
+            }
+            _ => Err(HydrateError::InvalidSetOp),
+        }
+    }
+}
+
+impl<V: Into<ScalarValue>> FromIterator<V> for Set {
+    fn from_iter<I: IntoIterator<Item = V>>(iter: I) -> Self {
+        let mut set = Set::default();
+        for value in iter {
+            set.insert(value.into());
+        }
+        set
+    }
+}
@@ -0,0 +1,471 @@
+//! Inspect the structure of saved automerge data without loading it into a document
+//!
+//! [`inspect()`] parses each chunk in a byte array produced by [`crate::Automerge::save()`],
+//! [`crate::Change::raw_bytes()`], [`crate::Bundle::bytes()`] etc. and reports how the data is laid
+//! out: the type and size of each chunk, the size of every column before and after compression,
+//! the number of ops in each type of object, the actor table and statistics about the change
+//! graph. This is intended for tooling which tracks how the size of saved documents changes
+//! over time.
+//!
+//! ```
+//! # use automerge::{AutoCommit, ObjType, transaction::Transactable, ROOT};
+//! # use automerge::inspect::{inspect, ChunkContents};
+//! let mut doc = AutoCommit::new();
+//! let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+//! doc.splice_text(&text, 0, 0, "hello").unwrap();
+//!
+//! let inspection = inspect(&doc.save()).unwrap();
+//! let ChunkContents::Document(info) = &inspection.chunks[0].contents else {
+//!     panic!("expected a document chunk");
+//! };
+//! assert_eq!(info.ops.by_obj_type[&ObjType::Text], 5);
+//! let biggest = info.op_columns.iter().max_by_key(|c| c.size).unwrap();
+//! println!("{} is {} bytes", biggest.name.unwrap_or("unknown"), biggest.size);
+//! ```
+use std::collections::HashMap;
+
+use crate::change_graph::ChangeGraphCols;
+use crate::op_set2::OpSet;
+use crate::storage::{self, load::Error as LoadError, parse, ColumnSpec, StoredColumn};
+use crate::types::ObjId;
+use crate::{ActorId, AutomergeError, Bundle, ChangeHash, ObjType, TextEncoding};
+
+/// The result of [`inspect()`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Inspection {
+    /// The chunks in the order they appear in the data
+    pub chunks: Vec<ChunkInfo>,
+}
+
+impl Inspection {
+    /// The total size in bytes of all the chunks
+    pub fn size(&self) -> usize {
+        self.chunks.iter().map(|c| c.size).sum()
+    }
+}
+
+/// A single chunk
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChunkInfo {
+    /// The offset of the chunk in the inspected data
+    pub offset: usize,
+    /// The size of the chunk in bytes, including the header
+    pub size: usize,
+    /// Whether the checksum in the header of the chunk matches its contents
+    pub checksum_valid: bool,
+    pub contents: ChunkContents,
+}
+
+/// What a chunk contains
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChunkContents {
+    Document(DocumentInfo),
+    Change(ChangeInfo),
+    /// A change chunk which was compressed as a whole
+    CompressedChange {
+        change: ChangeInfo,
+        /// The size of the change chunk once decompressed
+        uncompressed_size: usize,
+    },
+    Bundle(BundleInfo),
+    /// The base of a shallow document, this is always followed by a document chunk
+    Base(BaseInfo),
+}
+
+/// A document chunk
+#[derive(Debug, Clone, PartialEq)]
+pub struct DocumentInfo {
+    /// The actor table, the actors of all the changes and ops are indexes into this table
+    pub actors: Vec<ActorId>,
+    pub heads: Vec<ChangeHash>,
+    pub change_columns: Vec<ColumnInfo>,
+    pub op_columns: Vec<ColumnInfo>,
+    pub ops: OpCounts,
+    pub changes: ChangeGraphStats,
+}
+
+/// A change chunk
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChangeInfo {
+    pub hash: ChangeHash,
+    pub actor: ActorId,
+    /// Actors other than the author which are referenced by the ops in the change
+    pub other_actors: Vec<ActorId>,
+    pub seq: u64,
+    pub start_op: u64,
+    pub num_ops: usize,
+    pub deps: Vec<ChangeHash>,
+    pub op_columns: Vec<ColumnInfo>,
+}
+
+/// A bundle chunk
+#[derive(Debug, Clone, PartialEq)]
+pub struct BundleInfo {
+    pub actors: Vec<ActorId>,
+    /// The dependencies of the bundle which are not in the bundle
+    pub deps: Vec<ChangeHash>,
+    pub num_changes: usize,
+    pub num_ops: usize,
+    pub change_columns: Vec<ColumnInfo>,
+    pub op_columns: Vec<ColumnInfo>,
+}
+
+/// The base chunk of a shallow document
+#[derive(Debug, Clone, PartialEq)]
+pub struct BaseInfo {
+    /// The heads the history of the document was truncated at
+    pub heads: Vec<ChangeHash>,
+    /// The number of truncated changes which are still referenced by the retained history
+    pub num_stubs: usize,
+    /// The number of changes which were truncated
+    pub num_truncated: usize,
+}
+
+/// A column in a document, change or bundle chunk
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnInfo {
+    /// The id of the column from the column specification
+    pub id: u32,
+    pub column_type: ColumnType,
+    /// The name given to the column by the storage format specification, if this is a column
+    /// this version of automerge knows about
+    pub name: Option<&'static str>,
+    /// The codec the column was compressed with
+    pub codec: crate::Codec,
+    /// The number of bytes the column takes up in the chunk
+    pub size: usize,
+    /// The number of bytes in the column once decompressed
+    pub uncompressed_size: usize,
+}
+
+/// The encoding of a column, as given by its column specification
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ColumnType {
+    Group,
+    Actor,
+    Integer,
+    DeltaInteger,
+    Boolean,
+    String,
+    ValueMetadata,
+    Value,
+}
+
+/// The number of ops in a document chunk
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OpCounts {
+    pub total: usize,
+    /// The number of ops in objects of each type, including the ops which have been deleted
+    pub by_obj_type: HashMap<ObjType, usize>,
+    /// The number of objects of each type, including the root and deleted objects
+    pub objects: HashMap<ObjType, usize>,
+}
+
+/// Statistics about the changes in a document chunk
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ChangeGraphStats {
+    /// The number of changes, not including the stubs of a shallow document
+    pub num_changes: usize,
+    /// The number of changes which were truncated from a shallow document but which are still
+    /// referenced by the retained changes
+    pub num_stubs: usize,
+    /// The total number of dependencies of all the changes
+    pub num_deps: usize,
+    /// The length of the longest path through the change graph
+    pub max_depth: usize,
+    /// The largest op counter in the document
+    pub max_op: u64,
+    /// The number of changes by each actor, indexed in the same way as [`DocumentInfo::actors`]
+    pub changes_per_actor: Vec<usize>,
+}
+
+/// Parse `data` and describe each of the chunks in it
+///
+/// Chunks with an invalid checksum are reported with [`ChunkInfo::checksum_valid`] set to
+/// `false` rather than as an error so that corrupted data can be investigated.
+///
+/// # Errors
+///
+/// If any of the chunks in `data` cannot be parsed
+pub fn inspect(data: &[u8]) -> Result<Inspection, AutomergeError> {
+    let mut chunks = Vec::new();
+    let mut offset = 0;
+    let mut num_stubs = 0;
+    while offset < data.len() {
+        let chunk_bytes = &data[offset..];
+        let (remaining, chunk) = storage::Chunk::parse(parse::Input::new(chunk_bytes))
+            .map_err(|e| LoadError::Parse(Box::new(e)))?;
+        let size = chunk_bytes.len() - remaining.unconsumed_bytes().len();
+        let chunk_bytes = &chunk_bytes[..size];
+        let checksum_valid = chunk.checksum_valid();
+        let contents = match chunk {
+            storage::Chunk::Document(doc) => {
+                ChunkContents::Document(document_info(&doc, chunk_bytes, num_stubs)?)
+            }
+            storage::Chunk::Change(change) => ChunkContents::Change(change_info(change)?),
+            storage::Chunk::CompressedChange(change, _) => ChunkContents::CompressedChange {
+                uncompressed_size: change.bytes.len(),
+                change: change_info(change)?,
+            },
+            storage::Chunk::Bundle(bundle) => {
+                ChunkContents::Bundle(bundle_info(bundle.into_owned(), chunk_bytes)?)
+            }
+            storage::Chunk::Base(base) => ChunkContents::Base(BaseInfo {
+                heads: base.heads.clone(),
+                num_stubs: base.stubs.len(),
+                num_truncated: base.actors.iter().map(|a| a.seq as usize).sum(),
+            }),
+        };
+        num_stubs = match &contents {
+            ChunkContents::Base(base) => base.num_stubs,
+            _ => 0,
+        };
+        chunks.push(ChunkInfo {
+            offset,
+            size,
+            checksum_valid,
+            contents,
+        });
+        offset += size;
+    }
+    Ok(Inspection { chunks })
+}
+
+fn document_info(
+    doc: &storage::Document<'_>,
+    chunk_bytes: &[u8],
+    num_stubs: usize,
+) -> Result<DocumentInfo, AutomergeError> {
+    let layout = storage::column_layout(chunk_bytes).map_err(|e| LoadError::Parse(Box::new(e)))?;
+    let inflate_err = |e| LoadError::InflateDocument(Box::new(e));
+    let op_set = OpSet::load(doc, TextEncoding::platform_default())
+        .map_err(|e| inflate_err(storage::document::ReconstructError::from(e)))?;
+    let change_cols = ChangeGraphCols::load(doc).map_err(inflate_err)?;
+    Ok(DocumentInfo {
+        actors: doc.actors().to_vec(),
+        heads: doc.heads().to_vec(),
+        change_columns: columns(&layout.changes, change_column_name),
+        op_columns: columns(&layout.ops, op_column_name),
+        ops: op_counts(&op_set),
+        changes: change_graph_stats(&change_cols, doc.actors().len(), num_stubs),
+    })
+}
+
+fn op_counts(op_set: &OpSet) -> OpCounts {
+    let mut counts = OpCounts::default();
+    let mut obj_types = HashMap::new();
+    obj_types.insert(ObjId::root(), ObjType::Map);
+    counts.objects.insert(ObjType::Map, 1);
+    for op in op_set.iter() {
+        counts.total += 1;
+        // objects are created before any of the ops in them so the creating op is always seen
+        // before the ops in the object
+        if let Some(obj_type) = obj_types.get(&op.obj) {
+            *counts.by_obj_type.entry(*obj_type).or_default() += 1;
+        }
+        if let Some(info) = op.obj_info() {
+            obj_types.insert(ObjId(op.id), info.obj_type);
+            *counts.objects.entry(info.obj_type).or_default() += 1;
+        }
+    }
+    counts
+}
+
+fn change_graph_stats(
+    change_cols: &ChangeGraphCols,
+    num_actors: usize,
+    num_stubs: usize,
+) -> ChangeGraphStats {
+    let mut stats = ChangeGraphStats {
+        num_stubs,
+        changes_per_actor: vec![0; num_actors],
+        ..Default::default()
+    };
+    // changes are stored in topological order so the depth of each dependency is known by the
+    // time we get to the change. Stubs have a depth of zero.
+    let mut depths = Vec::with_capacity(change_cols.len());
+    for (i, change) in change_cols.iter().enumerate() {
+        if i < num_stubs {
+            depths.push(0);
+            continue;
+        }
+        let depth = 1 + change
+            .deps
+            .iter()
+            .filter_map(|d| depths.get(*d as usize))
+            .max()
+            .unwrap_or(&0);
+        depths.push(depth);
+        stats.num_changes += 1;
+        stats.num_deps += change.deps.len();
+        stats.max_depth = stats.max_depth.max(depth);
+        stats.max_op = stats.max_op.max(change.max_op);
+        if let Some(count) = stats.changes_per_actor.get_mut(change.actor) {
+            *count += 1;
+        }
+    }
+    stats
+}
+
+fn change_info(
+    change: storage::Change<'_, storage::change::Unverified>,
+) -> Result<ChangeInfo, AutomergeError> {
+    let op_columns = change
+        .ops_meta
+        .raw_columns()
+        .iter()
+        .map(|col| StoredColumn {
+            spec: col.spec(),
+            len: col.data().len(),
+            uncompressed_len: col.data().len(),
+        })
+        .collect::<Vec<_>>();
+    let change = change
+        .verify_ops(|_| ())
+        .map_err(|e| LoadError::InvalidChangeColumns(Box::new(e)))?;
+    Ok(ChangeInfo {
+        hash: change.hash(),
+        actor: change.actor().clone(),
+        other_actors: change.other_actors().to_vec(),
+        seq: change.seq(),
+        start_op: change.start_op().get(),
+        num_ops: change.len(),
+        deps: change.dependencies().to_vec(),
+        op_columns: columns(&op_columns, op_column_name),
+    })
+}
+
+fn bundle_info(
+    bundle: storage::BundleStorage<'static, storage::change::Unverified>,
+    chunk_bytes: &[u8],
+) -> Result<BundleInfo, AutomergeError> {
+    let layout = storage::column_layout(chunk_bytes).map_err(|e| LoadError::Parse(Box::new(e)))?;
+    let bundle = Bundle::new_from_unverified(bundle)
+        .map_err(|e| LoadError::InvalidBundleColumn(Box::new(e)))?;
+    let (num_changes, num_ops) = bundle.iter_changes().fold((0, 0), |(changes, ops), c| {
+        (changes + 1, ops + (1 + c.max_op - c.start_op) as usize)
+    });
+    Ok(BundleInfo {
+        actors: bundle.actors().to_vec(),
+        deps: bundle.deps().to_vec(),
+        num_changes,
+        num_ops,
+        change_columns: columns(&layout.changes, bundle_change_column_name),
+        op_columns: columns(&layout.ops, bundle_op_column_name),
+    })
+}
+
+fn columns(
+    stored: &[StoredColumn],
+    name: fn(u32, ColumnType) -> Option<&'static str>,
+) -> Vec<ColumnInfo> {
+    stored
+        .iter()
+        .map(|col| {
+            let id = u32::from(col.spec.id());
+            let column_type = ColumnType::from(col.spec);
+            ColumnInfo {
+                id,
+                column_type,
+                name: name(id, column_type),
+                codec: col.spec.codec(),
+                size: col.len,
+                uncompressed_size: col.uncompressed_len,
+            }
+        })
+        .collect()
+}
+
+impl From<ColumnSpec> for ColumnType {
+    fn from(spec: ColumnSpec) -> Self {
+        use storage::columns::ColumnType as Stored;
+        match spec.col_type() {
+            Stored::Group => Self::Group,
+            Stored::Actor => Self::Actor,
+            Stored::Integer => Self::Integer,
+            Stored::DeltaInteger => Self::DeltaInteger,
+            Stored::Boolean => Self::Boolean,
+            Stored::String => Self::String,
+            Stored::ValueMetadata => Self::ValueMetadata,
+            Stored::Value => Self::Value,
+        }
+    }
+}
+
+/// The names of the columns in the change columns of document and bundle chunks
+fn change_column_name(id: u32, column_type: ColumnType) -> Option<&'static str> {
+    use ColumnType::*;
+    match (id, column_type) {
+        (0, Actor) => Some("actor"),
+        (0, DeltaInteger) => Some("seq"),
+        (1, DeltaInteger) => Some("maxOp"),
+        (2, DeltaInteger) => Some("time"),
+        (3, String) => Some("message"),
+        (4, Group) => Some("depsNum"),
+        (4, DeltaInteger) => Some("depsIndex"),
+        (5, ValueMetadata) => Some("extraLen"),
+        (5, Value) => Some("extraRaw"),
+        _ => None,
+    }
+}
+
+/// The names of the op columns in document and change chunks
+fn op_column_name(id: u32, column_type: ColumnType) -> Option<&'static str> {
+    use ColumnType::*;
+    match (id, column_type) {
+        (0, Actor) => Some("objActor"),
+        (0, Integer) => Some("objCtr"),
+        (1, Actor) => Some("keyActor"),
+        (1, DeltaInteger) => Some("keyCtr"),
+        (1, String) => Some("keyStr"),
+        (2, Actor) => Some("idActor"),
+        (2, DeltaInteger) => Some("idCtr"),
+        (3, Boolean) => Some("insert"),
+        (4, Integer) => Some("action"),
+        (5, ValueMetadata) => Some("valLen"),
+        (5, Value) => Some("valRaw"),
+        (7, Group) => Some("predNum"),
+        (7, Actor) => Some("predActor"),
+        (7, DeltaInteger) => Some("predCtr"),
+        (8, Group) => Some("succNum"),
+        (8, Actor) => Some("succActor"),
+        (8, DeltaInteger) => Some("succCtr"),
+        (9, Boolean) => Some("expand"),
+        (10, String) => Some("markName"),
+        (11, Actor) => Some("targetActor"),
+        (11, Integer) => Some("targetCtr"),
+        _ => None,
+    }
+}
+
+/// The names of the change columns in bundle chunks
+fn bundle_change_column_name(id: u32, column_type: ColumnType) -> Option<&'static str> {
+    use ColumnType::*;
+    match (id, column_type) {
+        (0, Actor) => Some("actor"),
+        (0, DeltaInteger) => Some("seq"),
+        (1, DeltaInteger) => Some("startOp"),
+        (2, DeltaInteger) => Some("maxOp"),
+        (3, DeltaInteger) => Some("time"),
+        (4, String) => Some("message"),
+        (5, Group) => Some("depsNum"),
+        (5, DeltaInteger) => Some("depsIndex"),
+        (6, Group) => Some("extraNum"),
+        (6, Value) => Some("extraRaw"),
+        _ => None,
+    }
+}
+
+/// The names of the op columns in bundle chunks, these are the same as the op columns of change
+/// chunks except that the op counters are stored relative to the end of the change, and that the
+/// target of a move comes after them
+fn bundle_op_column_name(id: u32, column_type: ColumnType) -> Option<&'static str> {
+    match (id, column_type) {
+        (0, ColumnType::DeltaInteger) => Some("objCtr"),
+        (11, ColumnType::DeltaInteger) => Some("idCtrInverse"),
+        (11, _) => None,
+        (12, ColumnType::Actor) => Some("targetActor"),
+        (12, ColumnType::DeltaInteger) => Some("targetCtr"),
+        _ => op_column_name(id, column_type),
+    }
+}
@@ -2,6 +2,7 @@ mod doc;
 mod keys;
 mod list_range;
 mod map_range;
+mod set_values;
 mod spans;
 mod values;
 
@@ -11,6 +12,7 @@ pub use doc::{DocItem, DocIter, DocObjItem};
 pub use keys::Keys;
 pub use list_range::{ListRange, ListRangeItem};
 pub use map_range::{MapRange, MapRangeItem};
+pub use set_values::SetValues;
 pub use spans::{Span, Spans};
 pub use values::Values;
 
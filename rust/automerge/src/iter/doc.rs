@@ -1,17 +1,18 @@
 use super::{
-    ListDiff, ListDiffItem, ListRange, ListRangeItem, MapDiff, MapDiffItem, MapRange, MapRangeItem,
-    Span, SpanDiff, SpanInternal, SpansDiff, SpansInternal,
+    Diff, ListDiff, ListDiffItem, ListRange, ListRangeItem, MapDiff, MapDiffItem, MapRange,
+    MapRangeItem, Span, SpanDiff, SpanInternal, SpansDiff, SpansInternal,
 };
 use crate::clock::{Clock, ClockRange};
 use crate::exid::ExId;
 use crate::op_set2::op_set::{ObjIdIter, OpSet};
 use crate::op_set2::types::ValueRef;
+use crate::ordered_map;
 use crate::patches::PatchLog;
 use crate::types::{ObjId, ObjMeta, ObjType, Prop};
 use crate::Automerge;
 use crate::TextEncoding;
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
 use std::ops::Range;
 use std::sync::Arc;
 
@@ -60,6 +61,9 @@ pub(crate) struct DocIterInternal<'a> {
     span_iter: SpansInternal<'a>,
     iter_type: IterType,
     obj: ObjId,
+    // the last object seeked to, see `rewind`
+    seeked: ObjId,
+    op_set: Option<&'a OpSet>,
 }
 
 #[derive(Debug, Clone)]
@@ -73,6 +77,11 @@ pub(crate) struct DiffIter<'a> {
     iter_type: IterType,
     recursive: bool,
     obj: ObjId,
+    // the last object seeked to, see `rewind`
+    seeked: ObjId,
+    op_set: &'a OpSet,
+    clock: ClockRange,
+    encoding: TextEncoding,
 }
 
 impl<'a> DiffIter<'a> {
@@ -84,13 +93,73 @@ impl<'a> DiffIter<'a> {
         recursive: bool,
     ) -> BTreeMap<ObjId, (Prop, ObjId)> {
         let encoding = doc.text_encoding();
+        let moved = match &clock {
+            ClockRange::Diff(before, after) if doc.ops().has_tree_moves() => {
+                Self::log_detached(doc, obj.id, before, after, log, recursive)
+            }
+            _ => HashMap::new(),
+        };
         let mut iter = DiffIter::new(doc, obj, clock, recursive);
         for item in iter.by_ref() {
-            item.log(log, encoding);
+            item.log_moved(log, encoding, &moved);
         }
         iter.path_map
     }
 
+    /// Detach the objects which are somewhere else after `after` than they were at `before`
+    ///
+    /// The diff does not see a moved object at the location it had before at all (see
+    /// [`OpSet::tree_diff_locations`]), so this removes it from there first, deepest first so
+    /// that the paths stay valid. Returns the moved objects which the diff should attach again
+    /// (`true`), or expose in full because they were not visible before (`false`). Objects
+    /// which are not visible afterwards are deleted instead.
+    ///
+    /// The detach patches are completed straight away, so `log` must not have pending events.
+    fn log_detached(
+        doc: &Automerge,
+        obj: ObjId,
+        before: &Clock,
+        after: &Clock,
+        log: &mut PatchLog,
+        recursive: bool,
+    ) -> HashMap<ObjId, bool> {
+        let op_set = doc.ops();
+        let exid = op_set.id_to_exid(obj.0);
+        let in_scope = |path: &Vec<(ExId, Prop)>| match recursive {
+            true => path.iter().any(|(o, _)| o == &exid),
+            false => path.last().is_some_and(|(o, _)| o == &exid),
+        };
+        let old = op_set.tree_locations(Some(before));
+        let new = op_set.tree_locations(Some(after));
+        let mut moved = HashMap::new();
+        let mut detached = vec![];
+        for target in op_set.moved_between(&old, &new) {
+            let old_path = op_set.visible_path(target, Some(before)).filter(&in_scope);
+            let visible_after = op_set
+                .visible_path(target, Some(after))
+                .is_some_and(|path| in_scope(&path));
+            if let Some(path) = old_path {
+                detached.push((path, target, visible_after));
+            }
+            if visible_after {
+                moved.insert(target, false);
+            }
+        }
+        detached.sort_by(|(a, _, _), (b, _, _)| {
+            let props = |path: &Vec<(ExId, Prop)>| path.iter().map(|(_, p)| p.clone()).collect();
+            Vec::<Prop>::cmp(&props(b), &props(a))
+        });
+        for (path, target, visible_after) in detached {
+            if visible_after {
+                log.detach_at(path, op_set.id_to_exid(target.0));
+                moved.insert(target, true);
+            } else {
+                log.delete_at(path);
+            }
+        }
+        moved
+    }
+
     pub(crate) fn new(
         doc: &'a Automerge,
         obj: ObjMeta,
@@ -104,7 +173,7 @@ impl<'a> DiffIter<'a> {
         let scope = obj_id_iter.seek_to_value(obj);
         let map_iter = MapDiff::new(op_set, scope.clone(), clock.clone());
         let list_iter = ListDiff::new(op_set, scope.clone(), clock.clone());
-        let span_iter = SpansDiff::new(op_set, scope, clock, doc.text_encoding());
+        let span_iter = SpansDiff::new(op_set, scope, clock.clone(), doc.text_encoding());
         let path_map = BTreeMap::new();
         let next_objs = BTreeMap::new();
         DiffIter {
@@ -117,9 +186,25 @@ impl<'a> DiffIter<'a> {
             next_objs,
             path_map,
             recursive,
+            seeked: obj,
+            op_set,
+            clock,
+            encoding: doc.text_encoding(),
         }
     }
 
+    /// Start the iterators again from the beginning of the op set
+    ///
+    /// The iterators can only move forwards, but an object which has been moved can come before
+    /// the object it was moved into.
+    fn rewind(&mut self) {
+        let (op_set, clock) = (self.op_set, self.clock.clone());
+        self.obj_id_iter = op_set.obj_id_iter();
+        self.map_iter = MapDiff::new(op_set, 0..0, clock.clone());
+        self.list_iter = ListDiff::new(op_set, 0..0, clock.clone());
+        self.span_iter = SpansDiff::new(op_set, 0..0, clock, self.encoding);
+    }
+
     fn process_item(&mut self, item: DocDiffItem<'a>) -> Option<DocObjDiffItem<'a>> {
         if let Some((next_obj, next_typ)) = item.make_obj() {
             let prop = item.prop();
@@ -144,6 +229,10 @@ impl<'a> DiffIter<'a> {
 
     fn next_object(&mut self) -> Option<Option<DocObjDiffItem<'a>>> {
         let (next, next_type) = self.next_objs.pop_first()?;
+        if next < self.seeked {
+            self.rewind();
+        }
+        self.seeked = next;
         let next_range = self.obj_id_iter.seek_to_value(next);
         if next_range.is_empty() {
             Some(None)
@@ -195,9 +284,24 @@ impl<'a> DocIterInternal<'a> {
             obj_id_iter,
             next_objs,
             path_map,
+            seeked: obj,
+            op_set: Some(op_set),
         }
     }
 
+    /// Start the iterators again from the beginning of the op set, see [`DiffIter::rewind`]
+    fn rewind(&mut self) {
+        let Some(op_set) = self.op_set else {
+            return;
+        };
+        let clock = self.span_iter.clock().cloned();
+        let encoding = self.span_iter.encoding();
+        self.obj_id_iter = op_set.obj_id_iter();
+        self.map_iter = MapRange::new(op_set, 0..0, clock.clone());
+        self.list_iter = ListRange::new(op_set, 0..0, clock.clone(), ..);
+        self.span_iter = SpansInternal::new(op_set, 0..0, clock, encoding);
+    }
+
     fn empty(encoding: TextEncoding) -> Self {
         Self {
             next_objs: BTreeMap::default(),
@@ -208,6 +312,8 @@ impl<'a> DocIterInternal<'a> {
             span_iter: SpansInternal::empty(encoding),
             iter_type: IterType::Map,
             obj: ObjId::root(),
+            seeked: ObjId::root(),
+            op_set: None,
         }
     }
 
@@ -241,6 +347,10 @@ impl<'a> DocIterInternal<'a> {
 
     fn next_object(&mut self) -> Option<Option<DocObjItemInternal<'a>>> {
         let (next, next_type) = self.next_objs.pop_first()?;
+        if next < self.seeked {
+            self.rewind();
+        }
+        self.seeked = next;
         let next_range = self.obj_id_iter.seek_to_value(next);
         if next_range.is_empty() {
             Some(None)
@@ -282,7 +392,7 @@ impl IterType {
     fn new(obj_type: ObjType) -> Self {
         match obj_type {
             ObjType::Text => IterType::Text,
-            ObjType::Map => IterType::Map,
+            ObjType::Map | ObjType::Table | ObjType::Set | ObjType::OrderedMap => IterType::Map,
             _ => IterType::List,
         }
     }
@@ -292,7 +402,13 @@ impl<'a> Iterator for DocIter<'a> {
     type Item = DocObjItem<'a>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let DocObjItemInternal { obj, item } = self.inner.next()?;
+        let op_set = self.op_set?;
+        let DocObjItemInternal { obj, item } = loop {
+            let next = self.inner.next()?;
+            if !next.is_position(op_set) {
+                break next;
+            }
+        };
         if *self.obj_export != obj {
             self.obj_export = Arc::new(self.op_set?.id_to_exid(self.inner.obj.0));
         }
@@ -345,6 +461,14 @@ pub(crate) struct DocObjItemInternal<'a> {
     pub(crate) item: DocItemInternal<'a>,
 }
 
+impl DocObjItemInternal<'_> {
+    /// Whether this is the position of a key of an ordered map, rather than one of its keys
+    fn is_position(&self, op_set: &OpSet) -> bool {
+        matches!(&self.item, DocItemInternal::Map(m) if ordered_map::is_position_key(&m.key))
+            && op_set.object_type(&self.obj) == Some(ObjType::OrderedMap)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub(crate) struct DocObjDiffItem<'a> {
     pub(crate) obj: ObjId,
@@ -359,6 +483,36 @@ impl DocObjDiffItem<'_> {
             DocDiffItem::Text(t) => t.log(self.obj, log, encoding),
         }
     }
+
+    /// Log this item, attaching or exposing the objects in `moved` (see
+    /// [`DiffIter::log_detached`]) where they are added
+    fn log_moved(self, log: &mut PatchLog, encoding: TextEncoding, moved: &HashMap<ObjId, bool>) {
+        match self.item {
+            DocDiffItem::Map(mut m) if m.diff == Diff::Add => match moved.get(&ObjId(m.id)) {
+                Some(true) => log.attach(self.obj, Prop::Map(m.key.into()), m.id, m.conflict),
+                Some(false) => {
+                    m.expose = true;
+                    m.log(self.obj, log, encoding)
+                }
+                None => m.log(self.obj, log, encoding),
+            },
+            DocDiffItem::List(mut l) if l.diff == Diff::Add && !l.update => {
+                match moved.get(&ObjId(l.id)) {
+                    Some(true) => log.attach(self.obj, Prop::Seq(l.index), l.id, l.conflict),
+                    Some(false) => {
+                        l.expose = true;
+                        l.log(self.obj, log, encoding)
+                    }
+                    None => l.log(self.obj, log, encoding),
+                }
+            }
+            item => DocObjDiffItem {
+                obj: self.obj,
+                item,
+            }
+            .log(log, encoding),
+        }
+    }
 }
 
 impl<'a> DocObjItem<'a> {
@@ -7,12 +7,17 @@ use std::fmt::Debug;
 #[derive(Clone, Debug, Default)]
 pub struct Keys<'a> {
     pub(crate) iter: Option<(&'a OpSet, TopOps<'a>)>,
+    // the keys of an ordered map, which are sorted up front
+    ordered: Option<std::vec::IntoIter<String>>,
 }
 
 impl Iterator for Keys<'_> {
     type Item = String;
 
     fn next(&mut self) -> Option<Self::Item> {
+        if let Some(ordered) = &mut self.ordered {
+            return ordered.next();
+        }
         let (op_set, iter) = self.iter.as_mut()?;
         let op = iter.next()?;
         Some(op_set.to_string(op.elemid_or_key()))
@@ -23,6 +28,14 @@ impl<'a> Keys<'a> {
     pub(crate) fn new(op_set: &'a OpSet, iter: TopOps<'a>) -> Self {
         Self {
             iter: Some((op_set, iter)),
+            ordered: None,
+        }
+    }
+
+    pub(crate) fn ordered(keys: Vec<String>) -> Self {
+        Self {
+            iter: None,
+            ordered: Some(keys.into_iter()),
         }
     }
 }
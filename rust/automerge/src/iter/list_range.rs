@@ -1,15 +1,18 @@
 use crate::clock::{Clock, ClockRange};
 use crate::exid::ExId;
 use crate::iter::tools::{DiffIter, ExIdPromise, Shiftable, Unshift};
-use crate::iter::Diff;
-use crate::op_set2::op_set::{ActionIter, InsertAcc, OpIdIter, ValueIter};
-use crate::op_set2::types::{Action, ScalarValue, ValueRef};
+use crate::iter::{Diff, RichTextDiff};
+use crate::marks::MarkSet;
+use crate::op_set2::op_set::{ActionIter, InsertAcc, MarkInfoIter, OpIdIter, ValueIter};
+use crate::op_set2::types::{Action, MarkData, ScalarValue, ValueRef};
 use crate::op_set2::OpSet;
 use crate::patches::PatchLog;
 use crate::types::{ObjId, OpId, TextEncoding};
 
+use std::borrow::Cow;
 use std::fmt::Debug;
 use std::ops::{Bound, Range, RangeBounds};
+use std::sync::Arc;
 
 #[derive(PartialEq, Clone, Debug)]
 pub struct ListRangeItem<'a> {
@@ -41,8 +44,12 @@ impl ListRangeItem<'_> {
 pub(crate) struct ListDiff<'a> {
     op_set: Option<&'a OpSet>,
     iter: Unshift<DiffIter<'a, ListIter<'a>>>,
+    mark_info: MarkInfoIter<'a>,
+    marks: RichTextDiff<'a>,
     index: usize,
     clock: ClockRange,
+    // the items of a list with moves, which are worked out up front
+    moved: Option<std::vec::IntoIter<ListDiffItem<'a>>>,
 }
 
 impl<'a> ListDiff<'a> {
@@ -59,22 +66,58 @@ impl<'a> ListDiff<'a> {
             id,
         };
 
+        let mark_info = op_set.mark_info_iter_range(&range);
+        let moved = op_set
+            .moved_list_diff(&range, &clock)
+            .map(|items| items.into_iter());
         let skip = DiffIter::new(op_set, list_iter, clock.clone(), range);
         let iter = Unshift::new(skip);
 
         Self {
             op_set: Some(op_set),
             iter,
+            mark_info,
+            marks: Default::default(),
             clock,
             index: 0,
+            moved,
         }
     }
 
     pub(crate) fn shift_next(&mut self, range: Range<usize>) -> Option<<Self as Iterator>::Item> {
+        self.mark_info.set_max(range.end);
+        self.moved = self
+            .op_set
+            .and_then(|op_set| op_set.moved_list_diff(&range, &self.clock))
+            .map(|items| items.into_iter());
         self.iter.shift(range);
+        self.marks = Default::default();
         self.index = 0;
         self.next()
     }
+
+    fn process_mark(&mut self, diff: Diff, list: List<'a>) {
+        let name = self
+            .mark_info
+            .nth(list.pos - self.mark_info.pos())
+            .and_then(|(name, _)| name);
+        if let Some(name) = name {
+            let name = Cow::Borrowed(name);
+            let value = list.value;
+            self.marks
+                .mark_begin_diff(diff, list.id, MarkData { name, value });
+        } else {
+            self.marks.mark_end_diff(diff, list.id);
+        }
+    }
+
+    /// The marks which changed on an element that is in both the old and new list
+    fn changed_marks(&self, mut item: ListDiffItem<'a>) -> ListDiffItem<'a> {
+        if item.diff == Diff::Same {
+            item.marks = self.marks.current().export();
+        }
+        item
+    }
 }
 
 #[derive(Debug, Clone, Default)]
@@ -106,6 +149,8 @@ impl ListState {
             conflict: self.conflict,
             expose: self.expose,
             id,
+            marks: None,
+            moved_from: None,
         }
     }
 }
@@ -114,7 +159,10 @@ impl<'a> Iterator for ListDiff<'a> {
     type Item = ListDiffItem<'a>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let op_set = self.op_set.as_mut()?;
+        let op_set = self.op_set?;
+        if let Some(moved) = &mut self.moved {
+            return moved.next();
+        }
         let mut last_is_same = false;
         //let mut expose;
         let mut last_visible: Option<Self::Item> = None;
@@ -123,6 +171,11 @@ impl<'a> Iterator for ListDiff<'a> {
         let mut state = ListState::default();
 
         while let Some((diff, list)) = self.iter.next() {
+            if list.action == Action::Mark {
+                // marks are anchors between elements, not elements
+                self.process_mark(diff, list);
+                continue;
+            }
             match diff {
                 Diff::Del => {
                     state.num_old += 1;
@@ -171,7 +224,7 @@ impl<'a> Iterator for ListDiff<'a> {
                 if last.diff.is_visible() {
                     self.index += 1;
                 }
-                return Some(last);
+                return Some(self.changed_marks(last));
             } else {
                 let mut item = state.diff_item(list.id, value, self.index, diff);
                 if diff == Diff::Same && state.num_old > 1 && state.num_new == 1 {
@@ -183,7 +236,7 @@ impl<'a> Iterator for ListDiff<'a> {
                 if item.diff.is_visible() {
                     self.index += 1;
                 }
-                return Some(item);
+                return Some(self.changed_marks(item));
             }
         }
         None
@@ -200,6 +253,9 @@ pub(crate) struct ListDiffItem<'a> {
     pub(crate) update: bool,
     pub(crate) expose: bool,
     pub(crate) id: OpId,
+    pub(crate) marks: Option<Arc<MarkSet>>,
+    /// Set when the item moves an element, from this index to `index`
+    pub(crate) moved_from: Option<usize>,
 }
 
 impl<'a> ListDiffItem<'a> {
@@ -222,7 +278,13 @@ impl<'a> ListDiffItem<'a> {
             id,
             conflict,
             expose,
+            marks,
+            moved_from,
         } = self;
+        if let Some(from) = moved_from {
+            log.move_seq(obj, from, index);
+            return;
+        }
         match diff {
             Diff::Add => {
                 let value = value.hydrate(encoding);
@@ -238,6 +300,9 @@ impl<'a> ListDiffItem<'a> {
                 } else if conflict {
                     log.flag_conflict_seq(obj, index);
                 }
+                if let Some(marks) = marks {
+                    log.mark(obj, index, 1, &marks);
+                }
             }
             Diff::Del => log.delete_seq(obj, index, 1),
         }
@@ -336,6 +401,11 @@ impl<'a> ListRange<'a> {
     }
 
     pub(crate) fn shift_next(&mut self, range: Range<usize>) -> Option<<Self as Iterator>::Item> {
+        self.iter.moved = self
+            .iter
+            .op_set
+            .and_then(|op_set| op_set.moved_list_diff(&range, &self.iter.clock))
+            .map(|items| items.into_iter());
         self.iter.iter.shift(range);
         self.iter.index = 0;
         self.next()
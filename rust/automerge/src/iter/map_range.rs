@@ -42,7 +42,7 @@ impl MapRangeItem<'_> {
 #[derive(Debug, Clone, PartialEq)]
 pub(crate) struct MapDiffItem<'a> {
     pub(crate) diff: Diff,
-    pub(crate) key: &'a str,
+    pub(crate) key: Cow<'a, str>,
     pub(crate) value: ValueRef<'a>,
     pub(crate) inc: i64,
     pub(crate) conflict: bool,
@@ -55,7 +55,7 @@ impl<'a> MapDiffItem<'a> {
     pub(crate) fn export(self, op_set: &'a OpSet) -> MapRangeItem<'a> {
         let maybe_exid = ExIdPromise::new(op_set, self.id);
         MapRangeItem {
-            key: Cow::Borrowed(self.key),
+            key: self.key,
             value: self.value,
             conflict: self.conflict,
             pos: self.pos,
@@ -73,7 +73,7 @@ impl<'a> MapDiffItem<'a> {
         match self.diff {
             Diff::Add => log.put_map(
                 obj,
-                self.key,
+                &self.key,
                 self.value.hydrate(encoding),
                 self.id,
                 self.conflict,
@@ -81,12 +81,12 @@ impl<'a> MapDiffItem<'a> {
             ),
             Diff::Same => {
                 if self.inc != 0 {
-                    log.increment_map(obj, self.key, self.inc, self.id);
+                    log.increment_map(obj, &self.key, self.inc, self.id);
                 } else if self.conflict {
-                    log.flag_conflict_map(obj, self.key);
+                    log.flag_conflict_map(obj, &self.key);
                 }
             }
-            Diff::Del => log.delete_map(obj, self.key),
+            Diff::Del => log.delete_map(obj, &self.key),
         }
     }
 }
@@ -94,12 +94,17 @@ impl<'a> MapDiffItem<'a> {
 #[derive(Debug, Clone, Default)]
 pub struct MapRange<'a> {
     iter: MapDiff<'a>,
+    // the items of an ordered map, which are sorted up front
+    ordered: Option<std::vec::IntoIter<MapRangeItem<'a>>>,
 }
 
 impl<'a> Iterator for MapRange<'a> {
     type Item = MapRangeItem<'a>;
 
     fn next(&mut self) -> Option<Self::Item> {
+        if let Some(ordered) = &mut self.ordered {
+            return ordered.next();
+        }
         Some(self.iter.next()?.export(self.iter.op_set?))
     }
 }
@@ -147,7 +152,7 @@ impl<'a> MapEntry<'a> {
         conflict: bool,
         expose: bool,
     ) -> MapDiffItem<'a> {
-        let key = self.key;
+        let key = Cow::Borrowed(self.key);
         let pos = self.pos;
         let id = self.id;
         if expose && diff == Diff::Same {
@@ -203,7 +208,17 @@ impl<'a> Iterator for MapIter<'a> {
 impl<'a> MapRange<'a> {
     pub(crate) fn new(op_set: &'a OpSet, range: Range<usize>, clock: Option<Clock>) -> Self {
         let iter = MapDiff::new(op_set, range, ClockRange::current(clock));
-        Self { iter }
+        Self {
+            iter,
+            ordered: None,
+        }
+    }
+
+    pub(crate) fn ordered(items: Vec<MapRangeItem<'a>>) -> Self {
+        Self {
+            iter: MapDiff::default(),
+            ordered: Some(items.into_iter()),
+        }
     }
 
     pub(crate) fn shift_next(&mut self, range: Range<usize>) -> Option<<Self as Iterator>::Item> {
@@ -217,6 +232,8 @@ pub(crate) struct MapDiff<'a> {
     op_set: Option<&'a OpSet>,
     iter: Unshift<DiffIter<'a, MapIter<'a>>>,
     clock: ClockRange,
+    // the items of a map with moves, which are worked out up front
+    moved: Option<std::vec::IntoIter<MapDiffItem<'a>>>,
 }
 
 impl<'a> Iterator for MapDiff<'a> {
@@ -224,6 +241,9 @@ impl<'a> Iterator for MapDiff<'a> {
 
     fn next(&mut self) -> Option<Self::Item> {
         let op_set = self.op_set.as_mut()?;
+        if let Some(moved) = &mut self.moved {
+            return moved.next();
+        }
         let mut last_is_same = false;
         let mut num_new = 0;
         let mut num_old = 0;
@@ -311,6 +331,9 @@ impl<'a> MapDiff<'a> {
             action,
         };
 
+        let moved = op_set
+            .moved_map_diff(&range, &clock)
+            .map(|items| items.into_iter());
         let skip = DiffIter::new(op_set, map_iter, clock.clone(), range);
         let iter = Unshift::new(skip);
 
@@ -318,10 +341,15 @@ impl<'a> MapDiff<'a> {
             op_set: Some(op_set),
             iter,
             clock,
+            moved,
         }
     }
 
     pub(crate) fn shift_next(&mut self, range: Range<usize>) -> Option<<Self as Iterator>::Item> {
+        self.moved = self
+            .op_set
+            .and_then(|op_set| op_set.moved_map_diff(&range, &self.clock))
+            .map(|items| items.into_iter());
         self.iter.shift(range);
         self.next()
     }
@@ -0,0 +1,24 @@
+use super::MapRange;
+use crate::ScalarValue;
+
+/// The values in a set, see [`crate::ReadDoc::set_values`]
+#[derive(Clone, Debug, Default)]
+pub struct SetValues<'a> {
+    iter: MapRange<'a>,
+}
+
+impl<'a> SetValues<'a> {
+    pub(crate) fn new(iter: MapRange<'a>) -> Self {
+        Self { iter }
+    }
+}
+
+impl Iterator for SetValues<'_> {
+    type Item = ScalarValue;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter
+            .by_ref()
+            .find_map(|item| item.value.to_value().into_scalar().ok())
+    }
+}
@@ -660,7 +660,7 @@ impl<'a> RichTextDiff<'a> {
         }
     }
 
-    fn mark_begin_diff(&mut self, diff: Diff, id: OpId, data: MarkData<'a>) -> bool {
+    pub(crate) fn mark_begin_diff(&mut self, diff: Diff, id: OpId, data: MarkData<'a>) -> bool {
         match diff {
             Diff::Add => self.after.mark_begin(id, data),
             Diff::Del => self.before.mark_begin(id, data),
@@ -671,7 +671,7 @@ impl<'a> RichTextDiff<'a> {
         }
     }
 
-    fn mark_end_diff(&mut self, diff: Diff, id: OpId) -> bool {
+    pub(crate) fn mark_end_diff(&mut self, diff: Diff, id: OpId) -> bool {
         match diff {
             Diff::Add => self.after.mark_end(id),
             Diff::Del => self.before.mark_end(id),
@@ -8,6 +8,8 @@ use std::fmt::Debug;
 #[derive(Default, Debug)]
 pub struct Values<'a> {
     iter: Option<(&'a OpSet, Box<dyn OpQueryTerm<'a> + 'a>)>,
+    // the values of an ordered map, which are sorted up front
+    ordered: Option<std::vec::IntoIter<(types::Value<'a>, ExId)>>,
 }
 
 impl<'a> Values<'a> {
@@ -19,6 +21,14 @@ impl<'a> Values<'a> {
     ) -> Self {
         Self {
             iter: Some((op_set, Box::new(iter))),
+            ordered: None,
+        }
+    }
+
+    pub(crate) fn ordered(values: Vec<(types::Value<'a>, ExId)>) -> Self {
+        Self {
+            iter: None,
+            ordered: Some(values.into_iter()),
         }
     }
 }
@@ -27,6 +37,9 @@ impl<'a> Iterator for Values<'a> {
     type Item = (types::Value<'a>, ExId);
 
     fn next(&mut self) -> Option<Self::Item> {
+        if let Some(ordered) = &mut self.ordered {
+            return ordered.next();
+        }
         let (op_set, iter) = self.iter.as_mut()?;
         let op = iter.next()?;
         let value = op.value().to_value();
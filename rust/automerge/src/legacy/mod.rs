@@ -3,6 +3,7 @@ mod utility_impls;
 
 use std::num::NonZeroU64;
 
+use crate::error::InvalidOpType;
 pub(crate) use crate::types::{ActorId, ChangeHash, ObjType, ScalarValue};
 pub(crate) use crate::value::DataType;
 
@@ -206,6 +207,7 @@ pub(crate) struct OpTypeParts {
     pub(crate) value: ScalarValue,
     pub(crate) expand: bool,
     pub(crate) mark_name: Option<smol_str::SmolStr>,
+    pub(crate) target: Option<OpId>,
 }
 
 // Like `types::OpType` except using a String for mark names
@@ -217,29 +219,32 @@ pub enum OpType {
     Put(ScalarValue),
     MarkBegin(MarkData),
     MarkEnd(bool),
+    Move,
+    MoveObject(OpId),
 }
 
 impl OpType {
     /// Create a new legacy OpType
     ///
     /// This is really only meant to be used to convert from a crate::Change to a
-    /// crate::legacy::Change, so the arguments should all have been validated. Consequently it
-    /// does not return an error and instead panics on the following conditions
+    /// crate::legacy::Change, so the arguments should usually have been validated already.
     ///
-    /// # Panics
+    /// # Errors
     ///
     /// * If The action index is unrecognized
     /// * If the action index indicates that the value should be numeric but the value is not a
     ///   number
+    /// * If an object move has no target
     pub(crate) fn from_parts(
         OpTypeParts {
             action,
             value,
             expand,
             mark_name,
+            target,
         }: OpTypeParts,
-    ) -> Self {
-        match action {
+    ) -> Result<Self, InvalidOpType> {
+        Ok(match action {
             0 => Self::Make(ObjType::Map),
             1 => Self::Put(value),
             2 => Self::Make(ObjType::List),
@@ -248,7 +253,7 @@ impl OpType {
             5 => match value {
                 ScalarValue::Int(i) => Self::Increment(i),
                 ScalarValue::Uint(i) => Self::Increment(i as i64),
-                _ => panic!("non numeric value for integer action"),
+                _ => return Err(InvalidOpType::NonNumericInc),
             },
             6 => Self::Make(ObjType::Table),
             7 => match mark_name {
@@ -259,8 +264,12 @@ impl OpType {
                 }),
                 None => Self::MarkEnd(expand),
             },
-            other => panic!("unknown action type {}", other),
-        }
+            8 => Self::Move,
+            9 => Self::Make(ObjType::Set),
+            10 => Self::Make(ObjType::OrderedMap),
+            11 => Self::MoveObject(target.ok_or(InvalidOpType::InvalidMoveTarget)?),
+            other => return Err(InvalidOpType::UnknownAction(other)),
+        })
     }
 
     pub(crate) fn action_index(&self) -> u64 {
@@ -273,6 +282,10 @@ impl OpType {
             Self::Increment(_) => 5,
             Self::Make(ObjType::Table) => 6,
             Self::MarkBegin(_) | Self::MarkEnd(_) => 7,
+            Self::Move => 8,
+            Self::Make(ObjType::Set) => 9,
+            Self::Make(ObjType::OrderedMap) => 10,
+            Self::MoveObject(_) => 11,
         }
     }
 
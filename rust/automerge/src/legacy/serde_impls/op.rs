@@ -27,7 +27,7 @@ impl Serialize for Op {
 
         if numerical_datatype.is_some() {
             fields += 2
-        } else if !matches!(&self.action, OpType::Make(..)) {
+        } else if !matches!(&self.action, OpType::Make(..) | OpType::Move) {
             fields += 1
         };
 
@@ -62,6 +62,7 @@ impl Serialize for Op {
                 op.serialize_field("expand", &expand)?
             }
             OpType::MarkEnd(expand) => op.serialize_field("expand", &expand)?,
+            OpType::MoveObject(target) => op.serialize_field("target", &target)?,
             _ => {}
         }
         op.serialize_field("pred", &self.pred)?;
@@ -80,11 +81,15 @@ pub(crate) enum RawOpType {
     MakeTable,
     MakeList,
     MakeText,
+    MakeSet,
+    MakeOrderedMap,
     Del,
     Inc,
     Set,
     MarkBegin,
     MarkEnd,
+    Move,
+    MoveObject,
 }
 
 impl Serialize for RawOpType {
@@ -97,11 +102,15 @@ impl Serialize for RawOpType {
             RawOpType::MakeTable => "makeTable",
             RawOpType::MakeList => "makeList",
             RawOpType::MakeText => "makeText",
+            RawOpType::MakeSet => "makeSet",
+            RawOpType::MakeOrderedMap => "makeOrderedMap",
             RawOpType::Del => "del",
             RawOpType::Inc => "inc",
             RawOpType::Set => "set",
             RawOpType::MarkBegin => "markBegin",
             RawOpType::MarkEnd => "markEnd",
+            RawOpType::Move => "move",
+            RawOpType::MoveObject => "moveObject",
         };
         serializer.serialize_str(s)
     }
@@ -117,11 +126,15 @@ impl<'de> Deserialize<'de> for RawOpType {
             "makeTable",
             "makeList",
             "makeText",
+            "makeSet",
+            "makeOrderedMap",
             "del",
             "inc",
             "set",
             "markBegin",
             "markEnd",
+            "move",
+            "moveObject",
         ];
         // TODO: Probably more efficient to deserialize to a `&str`
         let raw_type = String::deserialize(deserializer)?;
@@ -130,11 +143,15 @@ impl<'de> Deserialize<'de> for RawOpType {
             "makeTable" => Ok(RawOpType::MakeTable),
             "makeList" => Ok(RawOpType::MakeList),
             "makeText" => Ok(RawOpType::MakeText),
+            "makeSet" => Ok(RawOpType::MakeSet),
+            "makeOrderedMap" => Ok(RawOpType::MakeOrderedMap),
             "del" => Ok(RawOpType::Del),
             "inc" => Ok(RawOpType::Inc),
             "set" => Ok(RawOpType::Set),
             "markBegin" => Ok(RawOpType::MarkBegin),
             "markEnd" => Ok(RawOpType::MarkEnd),
+            "move" => Ok(RawOpType::Move),
+            "moveObject" => Ok(RawOpType::MoveObject),
             other => Err(Error::unknown_variant(other, VARIANTS)),
         }
     }
@@ -168,6 +185,7 @@ impl<'de> Deserialize<'de> for Op {
                 let mut name: Option<String> = None;
                 let mut expand: Option<bool> = None;
                 let mut ref_id: Option<OpId> = None;
+                let mut target: Option<OpId> = None;
                 while let Some(field) = map.next_key::<String>()? {
                     match field.as_ref() {
                         "action" => read_field("action", &mut action, &mut map)?,
@@ -193,6 +211,7 @@ impl<'de> Deserialize<'de> for Op {
                         "name" => read_field("name", &mut name, &mut map)?,
                         "expand" => read_field("expand", &mut expand, &mut map)?,
                         "ref" => read_field("ref", &mut ref_id, &mut map)?,
+                        "target" => read_field("target", &mut target, &mut map)?,
                         _ => return Err(Error::unknown_field(&field, FIELDS)),
                     }
                 }
@@ -206,6 +225,8 @@ impl<'de> Deserialize<'de> for Op {
                     RawOpType::MakeTable => OpType::Make(ObjType::Table),
                     RawOpType::MakeList => OpType::Make(ObjType::List),
                     RawOpType::MakeText => OpType::Make(ObjType::Text),
+                    RawOpType::MakeSet => OpType::Make(ObjType::Set),
+                    RawOpType::MakeOrderedMap => OpType::Make(ObjType::OrderedMap),
                     RawOpType::Del => OpType::Delete,
                     RawOpType::Set => OpType::Put(unwrap_value(value, datatype)?),
                     RawOpType::Inc => match value.flatten() {
@@ -220,12 +241,20 @@ impl<'de> Deserialize<'de> for Op {
                         Some(ScalarValue::Unknown { bytes, .. }) => {
                             Err(Error::invalid_value(Unexpected::Bytes(&bytes), &"a number"))
                         }
+                        Some(ScalarValue::Extension(e)) => Err(Error::invalid_value(
+                            Unexpected::Bytes(e.bytes()),
+                            &"a number",
+                        )),
                         Some(ScalarValue::Str(s)) => {
                             Err(Error::invalid_value(Unexpected::Str(&s), &"a number"))
                         }
                         Some(ScalarValue::Boolean(b)) => {
                             Err(Error::invalid_value(Unexpected::Bool(b), &"a number"))
                         }
+                        Some(ScalarValue::Decimal(d)) => Err(Error::invalid_value(
+                            Unexpected::Other(&d.to_string()),
+                            &"an integer",
+                        )),
                         Some(ScalarValue::Null) => {
                             Err(Error::invalid_value(Unexpected::Other("null"), &"a number"))
                         }
@@ -243,6 +272,10 @@ impl<'de> Deserialize<'de> for Op {
                         })
                     }
                     RawOpType::MarkEnd => OpType::MarkEnd(expand.unwrap_or(false)),
+                    RawOpType::Move => OpType::Move,
+                    RawOpType::MoveObject => {
+                        OpType::MoveObject(target.ok_or_else(|| Error::missing_field("target"))?)
+                    }
                 };
                 Ok(Op {
                     action,
@@ -284,6 +317,7 @@ mod tests {
 
     use super::*;
     use crate::legacy as amp;
+    use crate::Decimal;
 
     #[test]
     fn test_deserialize_action() {
@@ -457,6 +491,39 @@ mod tests {
                     &"an integer",
                 )),
             },
+            Scenario {
+                name: "Set with decimal datatype",
+                json: serde_json::json!({
+                    "action": "set",
+                    "obj": "_root",
+                    "key": "somekey",
+                    "value": "-12.30",
+                    "datatype": "decimal",
+                    "pred": []
+                }),
+                expected: Ok(Op {
+                    action: OpType::Put(ScalarValue::Decimal(Decimal::new(-1230, 2))),
+                    obj: ObjectId::Root,
+                    key: "somekey".into(),
+                    insert: false,
+                    pred: SortedVec::new(),
+                }),
+            },
+            Scenario {
+                name: "Set with decimal datatype and invalid value",
+                json: serde_json::json!({
+                    "action": "set",
+                    "obj": "_root",
+                    "key": "somekey",
+                    "value": "1e5",
+                    "datatype": "decimal",
+                    "pred": []
+                }),
+                expected: Err(serde_json::Error::invalid_value(
+                    Unexpected::Other("\"1e5\""),
+                    &"a decimal string",
+                )),
+            },
             Scenario {
                 name: "Inc with counter",
                 json: serde_json::json!({
@@ -654,6 +721,13 @@ mod tests {
                 insert: false,
                 pred: vec![OpId::from_str("1@7ef48769b04d47e9a88e98a134d62716").unwrap()].into(),
             },
+            Op {
+                action: OpType::Put(ScalarValue::Decimal("0.000".parse().unwrap())),
+                obj: ObjectId::Root,
+                key: "somekey".into(),
+                insert: false,
+                pred: SortedVec::new(),
+            },
         ];
         for (testcase_num, testcase) in testcases.iter().enumerate() {
             #[allow(clippy::expect_fun_call)]
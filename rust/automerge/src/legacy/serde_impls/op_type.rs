@@ -15,11 +15,15 @@ impl Serialize for OpType {
             OpType::Make(ObjType::Table) => RawOpType::MakeTable,
             OpType::Make(ObjType::List) => RawOpType::MakeList,
             OpType::Make(ObjType::Text) => RawOpType::MakeText,
+            OpType::Make(ObjType::Set) => RawOpType::MakeSet,
+            OpType::Make(ObjType::OrderedMap) => RawOpType::MakeOrderedMap,
             OpType::Delete => RawOpType::Del,
             OpType::Increment(_) => RawOpType::Inc,
             OpType::Put(_) => RawOpType::Set,
             OpType::MarkBegin(_) => RawOpType::MarkBegin,
             OpType::MarkEnd(_) => RawOpType::MarkEnd,
+            OpType::Move => RawOpType::Move,
+            OpType::MoveObject(_) => RawOpType::MoveObject,
         };
         raw_type.serialize(serializer)
     }
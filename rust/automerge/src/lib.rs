@@ -259,10 +259,13 @@ macro_rules! __log {
      }
  }
 
+pub mod annotations;
 pub mod anonymize;
 mod autocommit;
 mod automerge;
 mod autoserde;
+pub mod block_tree;
+pub mod bounded_counter;
 mod change;
 mod change_graph;
 mod change_queue;
@@ -270,16 +273,23 @@ mod clock;
 mod columnar;
 mod convert;
 mod cursor;
+mod decimal;
 pub mod error;
 mod exid;
+pub mod extension;
+pub mod html;
 pub mod hydrate;
 mod indexed_cache;
+pub mod inspect;
 pub mod iter;
 pub use iter::Span;
 #[doc(hidden)]
 pub mod legacy;
+pub mod markdown;
 pub mod marks;
 pub mod op_set2;
+mod ordered_map;
+mod orphans;
 pub mod patches;
 mod read;
 mod sequence_tree;
@@ -298,17 +308,21 @@ pub use autoserde::AutoSerde;
 pub use change::{Change, LoadError as LoadChangeError};
 #[doc(hidden)]
 pub use change_graph::Fragment;
-pub use cursor::{Cursor, CursorPosition, MoveCursor, OpCursor};
+pub use cursor::{Cursor, CursorPosition, CursorRange, CursorRangePosition, MoveCursor, OpCursor};
+pub use decimal::{Decimal, ParseDecimalError};
 pub use error::InvalidActorId;
 pub use error::InvalidChangeHashSlice;
 pub use error::{AutomergeError, PatchLogMismatch};
 pub use exid::{ExId as ObjId, ObjIdFromBytesError};
 pub use legacy::Change as ExpandedChange;
 pub use op_set2::{ChangeMetadata, Parent, Parents, ScalarValue as ScalarValueRef, ValueRef};
-pub use patches::{Patch, PatchAction, PatchLog};
-pub use read::{ReadDoc, Stats};
+pub use orphans::{OrphanedEdits, RestorePolicy};
+pub use patches::{MappedPosition, Patch, PatchAction, PatchLog, PositionMap};
+pub use read::{ActorStats, ChangeStats, ColumnStats, DetailedStats, ObjectStats, ReadDoc, Stats};
 pub use sequence_tree::SequenceTree;
-pub use storage::{Bundle, BundleChange, BundleChangeIter, VerificationMode};
+pub use storage::{
+    Bundle, BundleChange, BundleChangeIter, Codec, ColumnGroup, Compression, VerificationMode,
+};
 pub use text_value::ConcreteTextValue;
 pub use transaction::BlockOrText;
 pub use types::{ActorId, ChangeHash, ObjType, OpType, ParseChangeHashError, Prop, TextEncoding};
@@ -0,0 +1,1124 @@
+//! Convert rich text to and from Markdown
+//!
+//! Rich text is stored as an [`ObjType::Text`](crate::ObjType::Text) containing marks and block
+//! markers (see [`Transactable::split_block`]). [`to_markdown`] renders the [`Span`]s returned by
+//! [`ReadDoc::spans`] as Markdown and [`from_markdown`] parses Markdown into spans which can be
+//! passed to [`Transactable::update_spans`]. [`get_markdown`] and [`update_markdown`] do both
+//! steps at once.
+//!
+//! Which mark names and block types correspond to which Markdown constructs is controlled by a
+//! [`MarkdownConfig`]. The default configuration uses the following vocabulary:
+//!
+//! | Markdown            | Automerge                                        |
+//! |---------------------|--------------------------------------------------|
+//! | `**strong**`        | mark `"strong"` with value `true`                |
+//! | `*emphasis*`        | mark `"em"` with value `true`                    |
+//! | `` `code` ``        | mark `"code"` with value `true`                  |
+//! | `[text](url)`       | mark `"link"` with the URL as its value          |
+//! | paragraph           | block of type `"paragraph"`                      |
+//! | `# heading`         | block of type `"heading"` with a `"level"` attr  |
+//! | `1. item`           | block of type `"ordered-list-item"`              |
+//! | `- item`            | block of type `"unordered-list-item"`            |
+//! | `> quote`           | a `"blockquote"` in the parents of a block       |
+//! | ```` ``` ```` fence | block of type `"code-block"` with a `"language"` attr |
+//!
+//! Nested list items have the enclosing list items in their `"parents"`. Marks and blocks which
+//! are not part of the configuration are dropped when exporting, blocks of an unknown type are
+//! rendered as paragraphs.
+//!
+//! Only the subset of Markdown above is understood by the parser, anything else (images, tables,
+//! HTML and so on) is imported as plain text.
+//!
+//! ## Example
+//!
+//! ```
+//! # use automerge::{AutoCommit, ObjType, ROOT, transaction::Transactable};
+//! use automerge::markdown::{self, MarkdownConfig};
+//!
+//! let mut doc = AutoCommit::new();
+//! let text = doc.put_object(ROOT, "text", ObjType::Text)?;
+//! let config = MarkdownConfig::default();
+//! let source = "# Shopping\n\n- some **fresh** bread\n- [milk](https://example.com)";
+//! markdown::update_markdown(&mut doc, &text, source, &config)?;
+//! assert_eq!(markdown::get_markdown(&doc, &text, &config)?, source);
+//! # Ok::<(), automerge::AutomergeError>(())
+//! ```
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::hydrate;
+use crate::iter::Span;
+use crate::marks::{ExpandMark, MarkSet, UpdateSpansConfig};
+use crate::transaction::Transactable;
+use crate::{AutomergeError, ObjId, ReadDoc, ScalarValue};
+
+/// The inline Markdown constructs which are represented as marks
+///
+/// The variants are ordered from the outermost to the innermost when several marks are nested.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum MarkdownMark {
+    /// `[text](url)`, the value of the mark is the URL
+    Link,
+    /// `**strong**`
+    Strong,
+    /// `*emphasis*`
+    Emphasis,
+    /// `` `code` ``
+    Code,
+}
+
+/// The Markdown constructs which are represented as blocks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MarkdownBlock {
+    Paragraph,
+    /// `# heading`, the level is stored in the attributes of the block
+    Heading,
+    /// `1. item`
+    OrderedListItem,
+    /// `- item`
+    UnorderedListItem,
+    /// `> quote`, usually found in the parents of other blocks
+    Blockquote,
+    /// A fenced code block, the language is stored in the attributes of the block
+    CodeBlock,
+}
+
+/// The mapping between mark names, block types and block attributes and Markdown
+///
+/// See the [module documentation](self) for the defaults.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarkdownConfig {
+    marks: HashMap<MarkdownMark, String>,
+    blocks: HashMap<MarkdownBlock, String>,
+    heading_level: String,
+    code_language: String,
+}
+
+impl Default for MarkdownConfig {
+    fn default() -> Self {
+        Self {
+            marks: HashMap::from([
+                (MarkdownMark::Link, "link".to_string()),
+                (MarkdownMark::Strong, "strong".to_string()),
+                (MarkdownMark::Emphasis, "em".to_string()),
+                (MarkdownMark::Code, "code".to_string()),
+            ]),
+            blocks: HashMap::from([
+                (MarkdownBlock::Paragraph, "paragraph".to_string()),
+                (MarkdownBlock::Heading, "heading".to_string()),
+                (
+                    MarkdownBlock::OrderedListItem,
+                    "ordered-list-item".to_string(),
+                ),
+                (
+                    MarkdownBlock::UnorderedListItem,
+                    "unordered-list-item".to_string(),
+                ),
+                (MarkdownBlock::Blockquote, "blockquote".to_string()),
+                (MarkdownBlock::CodeBlock, "code-block".to_string()),
+            ]),
+            heading_level: "level".to_string(),
+            code_language: "language".to_string(),
+        }
+    }
+}
+
+impl MarkdownConfig {
+    /// Use `name` as the name of the mark for `mark`
+    pub fn with_mark<S: Into<String>>(mut self, mark: MarkdownMark, name: S) -> Self {
+        self.marks.insert(mark, name.into());
+        self
+    }
+
+    /// Use `block_type` as the `"type"` of blocks for `block`
+    pub fn with_block<S: Into<String>>(mut self, block: MarkdownBlock, block_type: S) -> Self {
+        self.blocks.insert(block, block_type.into());
+        self
+    }
+
+    /// Use `attr` as the key in the block attributes which holds the level of a heading
+    pub fn with_heading_level_attr<S: Into<String>>(mut self, attr: S) -> Self {
+        self.heading_level = attr.into();
+        self
+    }
+
+    /// Use `attr` as the key in the block attributes which holds the language of a code block
+    pub fn with_code_language_attr<S: Into<String>>(mut self, attr: S) -> Self {
+        self.code_language = attr.into();
+        self
+    }
+
+    /// The name of the mark used for `mark`
+    pub fn mark_name(&self, mark: MarkdownMark) -> &str {
+        &self.marks[&mark]
+    }
+
+    /// The `"type"` of the blocks used for `block`
+    pub fn block_type(&self, block: MarkdownBlock) -> &str {
+        &self.blocks[&block]
+    }
+
+    fn mark_for(&self, name: &str) -> Option<MarkdownMark> {
+        self.marks
+            .iter()
+            .find(|(_, n)| n.as_str() == name)
+            .map(|(m, _)| *m)
+    }
+
+    fn block_for(&self, block_type: &str) -> Option<MarkdownBlock> {
+        self.blocks
+            .iter()
+            .find(|(_, t)| t.as_str() == block_type)
+            .map(|(b, _)| *b)
+    }
+}
+
+/// Render the rich text in `text` as Markdown
+pub fn get_markdown<R: ReadDoc, O: AsRef<ObjId>>(
+    doc: &R,
+    text: O,
+    config: &MarkdownConfig,
+) -> Result<String, AutomergeError> {
+    Ok(to_markdown(doc.spans(text)?, config))
+}
+
+/// Replace the content of `text` with `markdown`, making the smallest change possible
+///
+/// Links are created with [`ExpandMark::None`] and all other marks with the default expand
+/// flag, use [`from_markdown`] and [`Transactable::update_spans`] for more control.
+pub fn update_markdown<T: Transactable, O: AsRef<ObjId>>(
+    tx: &mut T,
+    text: O,
+    markdown: &str,
+    config: &MarkdownConfig,
+) -> Result<(), AutomergeError> {
+    let update_config = UpdateSpansConfig::default()
+        .with_mark_expand(config.mark_name(MarkdownMark::Link), ExpandMark::None);
+    tx.update_spans(text, update_config, from_markdown(markdown, config))
+}
+
+/// Render a sequence of spans, as returned by [`ReadDoc::spans`], as Markdown
+pub fn to_markdown<I: IntoIterator<Item = Span>>(spans: I, config: &MarkdownConfig) -> String {
+    let mut writer = Writer::new(config);
+    for span in spans {
+        match span {
+            Span::Block(block) => {
+                writer.finish_block();
+                writer.block = Some(BlockInfo::new(&block, config));
+            }
+            Span::Text { text, marks } => writer.text(text, marks.as_deref()),
+        }
+    }
+    writer.finish_block();
+    writer.out
+}
+
+/// Parse `markdown` into a sequence of spans suitable for [`Transactable::update_spans`]
+///
+/// Every block in the Markdown, including the first paragraph, starts with a
+/// [`Span::Block`].
+pub fn from_markdown(markdown: &str, config: &MarkdownConfig) -> Vec<Span> {
+    let mut spans = Vec::new();
+    for block in parse_blocks(markdown) {
+        spans.push(Span::Block(block.to_map(config)));
+        if block.kind == MarkdownBlock::CodeBlock {
+            let text = block.lines.join("\n");
+            if !text.is_empty() {
+                spans.push(Span::Text { text, marks: None });
+            }
+        } else {
+            spans.extend(InlineParser::new(&block.lines.join("\n")).parse(config));
+        }
+    }
+    spans
+}
+
+/// The active marks of a run of text, sorted from the outermost to the innermost. Links carry
+/// their URL.
+type ActiveMarks = Vec<(MarkdownMark, Option<String>)>;
+
+fn active_marks(marks: Option<&MarkSet>, config: &MarkdownConfig) -> ActiveMarks {
+    let mut active = marks
+        .into_iter()
+        .flat_map(|m| m.iter())
+        .filter_map(|(name, value)| {
+            let mark = config.mark_for(name)?;
+            match (mark, value) {
+                (MarkdownMark::Link, value) => Some((mark, Some(value.to_str()?.to_string()))),
+                (_, ScalarValue::Null | ScalarValue::Boolean(false)) => None,
+                _ => Some((mark, None)),
+            }
+        })
+        .collect::<ActiveMarks>();
+    active.sort();
+    active
+}
+
+fn mark_set(active: &ActiveMarks, config: &MarkdownConfig) -> Option<Arc<MarkSet>> {
+    if active.is_empty() {
+        return None;
+    }
+    Some(Arc::new(
+        active
+            .iter()
+            .map(|(mark, url)| {
+                let value = match url {
+                    Some(url) => ScalarValue::Str(url.into()),
+                    None => ScalarValue::Boolean(true),
+                };
+                (config.mark_name(*mark).to_string(), value)
+            })
+            .collect(),
+    ))
+}
+
+/// The parts of a block marker which matter for Markdown
+#[derive(Debug, Clone, Default)]
+struct BlockInfo {
+    kind: Option<MarkdownBlock>,
+    parents: Vec<Option<MarkdownBlock>>,
+    level: usize,
+    language: Option<String>,
+}
+
+impl BlockInfo {
+    fn new(block: &hydrate::Map, config: &MarkdownConfig) -> Self {
+        let block_kind = |value: &hydrate::Value| match value {
+            hydrate::Value::Scalar(s) => config.block_for(s.to_str()?),
+            _ => None,
+        };
+        let parents = match block.get("parents") {
+            Some(hydrate::Value::List(parents)) => {
+                parents.iter().map(|p| block_kind(&p.value)).collect()
+            }
+            _ => Vec::new(),
+        };
+        let attr = |name: &str| match block.get("attrs") {
+            Some(hydrate::Value::Map(attrs)) => match attrs.get(name) {
+                Some(hydrate::Value::Scalar(s)) => Some(s.clone()),
+                _ => None,
+            },
+            _ => None,
+        };
+        Self {
+            kind: block.get("type").and_then(block_kind),
+            parents,
+            level: attr(&config.heading_level)
+                .and_then(|l| l.to_i64())
+                .unwrap_or(1)
+                .clamp(1, 6) as usize,
+            language: attr(&config.code_language).and_then(|l| l.into_string().ok()),
+        }
+    }
+
+    fn is_list(&self) -> bool {
+        let is_item = |b: &Option<MarkdownBlock>| {
+            matches!(
+                b,
+                Some(MarkdownBlock::OrderedListItem | MarkdownBlock::UnorderedListItem)
+            )
+        };
+        is_item(&self.kind) || self.parents.iter().any(is_item)
+    }
+
+    fn is_quote(&self) -> bool {
+        self.kind == Some(MarkdownBlock::Blockquote)
+            || self.parents.first() == Some(&Some(MarkdownBlock::Blockquote))
+    }
+}
+
+struct Writer<'a> {
+    config: &'a MarkdownConfig,
+    out: String,
+    /// The block the runs belong to, `None` for text before the first block marker
+    block: Option<BlockInfo>,
+    runs: Vec<(String, ActiveMarks)>,
+    previous: Option<BlockInfo>,
+    /// The number of the last ordered list item at each depth of nesting
+    numbers: Vec<Option<usize>>,
+}
+
+impl<'a> Writer<'a> {
+    fn new(config: &'a MarkdownConfig) -> Self {
+        Self {
+            config,
+            out: String::new(),
+            block: None,
+            runs: Vec::new(),
+            previous: None,
+            numbers: Vec::new(),
+        }
+    }
+
+    fn text(&mut self, text: String, marks: Option<&MarkSet>) {
+        let active = active_marks(marks, self.config);
+        match self.runs.last_mut() {
+            Some((last, last_marks)) if *last_marks == active => last.push_str(&text),
+            _ => self.runs.push((text, active)),
+        }
+    }
+
+    fn finish_block(&mut self) {
+        let runs = std::mem::take(&mut self.runs);
+        let block = match self.block.take() {
+            Some(block) => block,
+            None if runs.is_empty() => return,
+            None => BlockInfo::default(),
+        };
+
+        let depth = block.parents.len();
+        self.numbers.resize(depth + 1, None);
+        let number = if block.kind == Some(MarkdownBlock::OrderedListItem) {
+            let n = self.numbers[depth].unwrap_or(0) + 1;
+            self.numbers[depth] = Some(n);
+            n
+        } else {
+            self.numbers[depth] = None;
+            0
+        };
+
+        if let Some(previous) = &self.previous {
+            if previous.is_list() && block.is_list() {
+                self.out.push('\n');
+            } else if previous.is_quote() && block.is_quote() {
+                self.out.push_str("\n>\n");
+            } else {
+                self.out.push_str("\n\n");
+            }
+        }
+
+        let mut prefix = String::new();
+        for (depth, parent) in block.parents.iter().enumerate() {
+            match parent {
+                Some(MarkdownBlock::Blockquote) => prefix.push_str("> "),
+                Some(MarkdownBlock::UnorderedListItem) => prefix.push_str("  "),
+                Some(MarkdownBlock::OrderedListItem) => {
+                    let n = self.numbers.get(depth).copied().flatten().unwrap_or(1);
+                    prefix.push_str(&" ".repeat(n.to_string().len() + 2));
+                }
+                _ => {}
+            }
+        }
+
+        if block.kind == Some(MarkdownBlock::CodeBlock) {
+            let code = runs.into_iter().map(|(text, _)| text).collect::<String>();
+            let fence = "`".repeat(longest_run(&code, '`').max(2) + 1);
+            self.out.push_str(&prefix);
+            self.out.push_str(&fence);
+            self.out
+                .push_str(block.language.as_deref().unwrap_or_default());
+            for line in code.split('\n') {
+                self.out.push('\n');
+                self.out.push_str(&prefix);
+                self.out.push_str(line);
+            }
+            self.out.push('\n');
+            self.out.push_str(&prefix);
+            self.out.push_str(&fence);
+        } else {
+            let (marker, continuation) = match block.kind {
+                Some(MarkdownBlock::Heading) => (format!("{} ", "#".repeat(block.level)), None),
+                Some(MarkdownBlock::UnorderedListItem) => {
+                    ("- ".to_string(), Some("  ".to_string()))
+                }
+                Some(MarkdownBlock::OrderedListItem) => {
+                    let marker = format!("{}. ", number);
+                    let indent = " ".repeat(marker.len());
+                    (marker, Some(indent))
+                }
+                Some(MarkdownBlock::Blockquote) => ("> ".to_string(), Some("> ".to_string())),
+                _ => (String::new(), Some(String::new())),
+            };
+            let body = render_inline(&runs);
+            for (i, line) in body.split('\n').enumerate() {
+                if i == 0 {
+                    self.out.push_str(&prefix);
+                    self.out.push_str(&marker);
+                } else if let Some(continuation) = &continuation {
+                    self.out.push('\n');
+                    self.out.push_str(&prefix);
+                    self.out.push_str(continuation);
+                } else {
+                    // headings cannot span several lines
+                    self.out.push(' ');
+                }
+                push_line(&mut self.out, line);
+            }
+            if body.is_empty() && marker.is_empty() {
+                // keep the prefix of an empty paragraph in a quote or list
+                let trimmed = self.out.trim_end_matches(' ').len();
+                self.out.truncate(trimmed);
+            }
+        }
+        self.previous = Some(block);
+    }
+}
+
+/// Append a line of rendered inline content, escaping anything at the start of the line which
+/// would otherwise start a new block
+fn push_line(out: &mut String, line: &str) {
+    let content = line.trim_start_matches(' ');
+    out.push_str(&line[..line.len() - content.len()]);
+    let digits = content.chars().take_while(char::is_ascii_digit).count();
+    match content.chars().next() {
+        Some('#' | '>' | '-' | '+') => {
+            out.push('\\');
+            out.push_str(content);
+        }
+        Some(_) if digits > 0 && matches!(content[digits..].chars().next(), Some('.' | ')')) => {
+            out.push_str(&content[..digits]);
+            out.push('\\');
+            out.push_str(&content[digits..]);
+        }
+        _ => out.push_str(content),
+    }
+}
+
+fn render_inline(runs: &[(String, ActiveMarks)]) -> String {
+    let mut out = String::new();
+    let mut open: ActiveMarks = Vec::new();
+    for (text, active) in runs {
+        if text.is_empty() {
+            continue;
+        }
+        // a code span always covers the whole of its run so it is never left open
+        let (outer, code) = match active.last() {
+            Some((MarkdownMark::Code, _)) => (&active[..active.len() - 1], true),
+            _ => (&active[..], false),
+        };
+        if !code && text.chars().all(char::is_whitespace) {
+            // delimiters cannot be placed around whitespace, leave the marks as they are
+            out.push_str(text);
+            continue;
+        }
+        let common = open.iter().zip(outer).take_while(|(a, b)| a == b).count();
+        close_marks(&mut out, &mut open, common);
+
+        let content = if code {
+            text.as_str()
+        } else {
+            // leading whitespace goes outside of any delimiters we are about to open
+            let content = text.trim_start();
+            out.push_str(&text[..text.len() - content.len()]);
+            content
+        };
+        for mark in &outer[common..] {
+            out.push_str(match mark.0 {
+                MarkdownMark::Link => "[",
+                MarkdownMark::Strong => "**",
+                _ => "*",
+            });
+            open.push(mark.clone());
+        }
+
+        if code {
+            let delimiter = "`".repeat(longest_run(content, '`') + 1);
+            let pad = content.starts_with('`')
+                || content.ends_with('`')
+                || (content.starts_with(' ')
+                    && content.ends_with(' ')
+                    && !content.chars().all(|c| c == ' '));
+            out.push_str(&delimiter);
+            if pad {
+                out.push(' ');
+            }
+            out.push_str(content);
+            if pad {
+                out.push(' ');
+            }
+            out.push_str(&delimiter);
+        } else {
+            let mut previous = None;
+            let mut chars = content.chars().peekable();
+            while let Some(c) = chars.next() {
+                // an underscore inside a word cannot start or end emphasis
+                let in_word = c == '_'
+                    && previous.is_some_and(char::is_alphanumeric)
+                    && chars.peek().is_some_and(|c| c.is_alphanumeric());
+                if matches!(c, '\\' | '`' | '*' | '_' | '[' | ']' | '<') && !in_word {
+                    out.push('\\');
+                }
+                out.push(c);
+                previous = Some(c);
+            }
+        }
+    }
+    close_marks(&mut out, &mut open, 0);
+    out
+}
+
+/// Close all but the first `keep` open marks, moving trailing whitespace after the delimiters
+fn close_marks(out: &mut String, open: &mut ActiveMarks, keep: usize) {
+    if open.len() <= keep {
+        return;
+    }
+    let trailing = out.split_off(out.trim_end().len());
+    for (mark, url) in open.drain(keep..).rev() {
+        match mark {
+            MarkdownMark::Link => {
+                let url = url.unwrap_or_default();
+                if url.contains(|c: char| c.is_whitespace() || matches!(c, '(' | ')' | '<' | '>')) {
+                    out.push_str(&format!("](<{}>)", url));
+                } else {
+                    out.push_str(&format!("]({})", url));
+                }
+            }
+            MarkdownMark::Strong => out.push_str("**"),
+            _ => out.push('*'),
+        }
+    }
+    out.push_str(&trailing);
+}
+
+fn longest_run(s: &str, c: char) -> usize {
+    let mut longest = 0;
+    let mut current = 0;
+    for ch in s.chars() {
+        if ch == c {
+            current += 1;
+            longest = longest.max(current);
+        } else {
+            current = 0;
+        }
+    }
+    longest
+}
+
+/// A block found while parsing Markdown, with the raw text of its lines
+#[derive(Debug)]
+struct ParsedBlock {
+    kind: MarkdownBlock,
+    parents: Vec<MarkdownBlock>,
+    level: usize,
+    language: Option<String>,
+    lines: Vec<String>,
+    quote_depth: usize,
+    /// Whether the next line of text continues this block
+    open: bool,
+}
+
+impl ParsedBlock {
+    fn new(kind: MarkdownBlock, quote_depth: usize, lists: &[(MarkdownBlock, usize)]) -> Self {
+        let mut parents = vec![MarkdownBlock::Blockquote; quote_depth];
+        parents.extend(lists.iter().map(|(kind, _)| *kind));
+        Self {
+            kind,
+            parents,
+            level: 1,
+            language: None,
+            lines: Vec::new(),
+            quote_depth,
+            open: false,
+        }
+    }
+
+    fn to_map(&self, config: &MarkdownConfig) -> hydrate::Map {
+        let mut attrs = HashMap::<&str, hydrate::Value>::new();
+        if self.kind == MarkdownBlock::Heading {
+            attrs.insert(&config.heading_level, (self.level as i64).into());
+        }
+        if let Some(language) = &self.language {
+            attrs.insert(&config.code_language, language.as_str().into());
+        }
+        let parents = self
+            .parents
+            .iter()
+            .map(|p| hydrate::Value::from(config.block_type(*p)))
+            .collect::<Vec<_>>();
+        hydrate::Map::from(HashMap::from([
+            ("type", config.block_type(self.kind).into()),
+            ("parents", parents.into()),
+            ("attrs", hydrate::Map::from(attrs).into()),
+        ]))
+    }
+}
+
+/// How a line of Markdown starts, once any blockquote markers and indentation are removed
+enum LineStart<'a> {
+    Heading(usize, &'a str),
+    Fence(char, usize, &'a str),
+    /// The kind of list, the width of the marker including the space after it and the content
+    ListItem(MarkdownBlock, usize, &'a str),
+    Text,
+}
+
+impl<'a> LineStart<'a> {
+    fn new(line: &'a str) -> Self {
+        let marker_end = |rest: &'a str| rest.is_empty() || rest.starts_with(' ');
+        let hashes = line.chars().take_while(|c| *c == '#').count();
+        if (1..=6).contains(&hashes) && marker_end(&line[hashes..]) {
+            let content = line[hashes..].trim();
+            // remove an optional closing sequence of hashes
+            let closed = content.trim_end_matches('#');
+            let content = if closed.is_empty() || closed.ends_with(' ') {
+                closed.trim_end()
+            } else {
+                content
+            };
+            return LineStart::Heading(hashes, content);
+        }
+        for fence in ['`', '~'] {
+            let len = line.chars().take_while(|c| *c == fence).count();
+            if len >= 3 && !(fence == '`' && line[len..].contains('`')) {
+                return LineStart::Fence(fence, len, line[len..].trim());
+            }
+        }
+        if line.starts_with(['-', '+', '*']) && marker_end(&line[1..]) {
+            let content = line[1..].strip_prefix(' ').unwrap_or_default();
+            return LineStart::ListItem(MarkdownBlock::UnorderedListItem, 2, content);
+        }
+        let digits = line.chars().take_while(char::is_ascii_digit).count();
+        if (1..=9).contains(&digits)
+            && line[digits..].starts_with(['.', ')'])
+            && marker_end(&line[digits + 1..])
+        {
+            let content = line[digits + 1..].strip_prefix(' ').unwrap_or_default();
+            return LineStart::ListItem(MarkdownBlock::OrderedListItem, digits + 2, content);
+        }
+        LineStart::Text
+    }
+}
+
+/// Remove up to `max` blockquote markers from the start of `line`, returning how many were
+/// removed and the rest of the line
+fn strip_quotes(mut line: &str, max: usize) -> (usize, &str) {
+    let mut depth = 0;
+    while depth < max {
+        match line.trim_start_matches(' ').strip_prefix('>') {
+            Some(rest) => {
+                line = rest.strip_prefix(' ').unwrap_or(rest);
+                depth += 1;
+            }
+            None => break,
+        }
+    }
+    (depth, line)
+}
+
+fn parse_blocks(markdown: &str) -> Vec<ParsedBlock> {
+    let mut blocks: Vec<ParsedBlock> = Vec::new();
+    // the open list items, with the indentation of their content
+    let mut lists: Vec<(MarkdownBlock, usize)> = Vec::new();
+    // the character, length and indentation of the fence of an open code block
+    let mut fence: Option<(char, usize, usize)> = None;
+    let mut quote_depth = 0;
+
+    for raw in markdown.lines() {
+        if let Some((fence_char, fence_len, indent)) = fence {
+            let block = blocks.last_mut().expect("a fence belongs to a block");
+            let (depth, line) = strip_quotes(raw, block.quote_depth);
+            if depth == block.quote_depth {
+                let content = line.trim_start_matches(' ');
+                let closing = content.chars().take_while(|c| *c == fence_char).count();
+                if closing >= fence_len && content[closing..].trim().is_empty() {
+                    fence = None;
+                } else {
+                    let strip = (line.len() - content.len()).min(indent);
+                    block.lines.push(line[strip..].to_string());
+                }
+                continue;
+            }
+            fence = None;
+        }
+
+        let (depth, line) = strip_quotes(raw, usize::MAX);
+        if line.trim().is_empty() {
+            if let Some(block) = blocks.last_mut() {
+                block.open = false;
+            }
+            if depth != quote_depth {
+                lists.clear();
+                quote_depth = depth;
+            }
+            continue;
+        }
+        let content = line.trim_start_matches(' ');
+        let indent = line.len() - content.len();
+        let start = LineStart::new(content);
+
+        if let (LineStart::Text, Some(block)) = (&start, blocks.last_mut()) {
+            if block.open && depth <= block.quote_depth {
+                block.lines.push(content.to_string());
+                continue;
+            }
+        }
+        if depth != quote_depth {
+            lists.clear();
+            quote_depth = depth;
+        }
+        while lists.last().is_some_and(|(_, content)| *content > indent) {
+            lists.pop();
+        }
+
+        match start {
+            LineStart::Heading(level, content) => {
+                let mut block = ParsedBlock::new(MarkdownBlock::Heading, depth, &lists);
+                block.level = level;
+                block.lines.push(content.to_string());
+                blocks.push(block);
+            }
+            LineStart::Fence(fence_char, len, info) => {
+                let mut block = ParsedBlock::new(MarkdownBlock::CodeBlock, depth, &lists);
+                if !info.is_empty() {
+                    block.language = Some(info.to_string());
+                }
+                blocks.push(block);
+                fence = Some((fence_char, len, indent));
+            }
+            LineStart::ListItem(kind, width, content) => {
+                let mut block = ParsedBlock::new(kind, depth, &lists);
+                if !content.is_empty() {
+                    block.lines.push(content.to_string());
+                }
+                block.open = true;
+                blocks.push(block);
+                lists.push((kind, indent + width));
+            }
+            LineStart::Text => {
+                let mut block = ParsedBlock::new(MarkdownBlock::Paragraph, depth, &lists);
+                block.lines.push(content.to_string());
+                block.open = true;
+                blocks.push(block);
+            }
+        }
+    }
+    blocks
+}
+
+/// Parses the inline content of a block into runs of text and the marks active on them
+struct InlineParser {
+    chars: Vec<char>,
+    runs: Vec<(String, ActiveMarks)>,
+    text: String,
+    strong: Option<char>,
+    emphasis: Option<char>,
+    /// The URL, the index of the closing `]` and the index after the closing `)` of the link
+    /// we are in
+    link: Option<(String, usize, usize)>,
+}
+
+impl InlineParser {
+    fn new(source: &str) -> Self {
+        Self {
+            chars: source.chars().collect(),
+            runs: Vec::new(),
+            text: String::new(),
+            strong: None,
+            emphasis: None,
+            link: None,
+        }
+    }
+
+    fn parse(mut self, config: &MarkdownConfig) -> impl Iterator<Item = Span> + '_ {
+        let mut i = 0;
+        while i < self.chars.len() {
+            if let Some((_, end, after)) = &self.link {
+                if *end == i {
+                    let after = *after;
+                    self.flush();
+                    self.link = None;
+                    i = after;
+                    continue;
+                }
+            }
+            let next = self.chars.get(i + 1).copied();
+            match self.chars[i] {
+                '\\' if next.is_some_and(|c| c.is_ascii_punctuation()) => {
+                    self.text.push(next.unwrap_or_default());
+                    i += 2;
+                }
+                '`' => i = self.code_span(i),
+                c @ ('*' | '_') => i = self.delimiter_run(c, i),
+                '[' if self.link.is_none() => match self.find_link(i) {
+                    Some(link) => {
+                        self.flush();
+                        self.link = Some(link);
+                        i += 1;
+                    }
+                    None => {
+                        self.text.push('[');
+                        i += 1;
+                    }
+                },
+                '!' if next == Some('[') => {
+                    // images are not supported, keep them as text
+                    self.text.push_str("![");
+                    i += 2;
+                }
+                '<' => i = self.autolink(i),
+                c => {
+                    self.text.push(c);
+                    i += 1;
+                }
+            }
+        }
+        self.flush();
+        self.runs
+            .into_iter()
+            .fold(
+                Vec::<(String, ActiveMarks)>::new(),
+                |mut runs, (text, marks)| {
+                    match runs.last_mut() {
+                        Some((last, last_marks)) if *last_marks == marks => last.push_str(&text),
+                        _ => runs.push((text, marks)),
+                    }
+                    runs
+                },
+            )
+            .into_iter()
+            .map(move |(text, marks)| Span::Text {
+                text,
+                marks: mark_set(&marks, config),
+            })
+    }
+
+    fn active(&self) -> ActiveMarks {
+        let mut active = Vec::new();
+        if let Some((url, _, _)) = &self.link {
+            active.push((MarkdownMark::Link, Some(url.clone())));
+        }
+        if self.strong.is_some() {
+            active.push((MarkdownMark::Strong, None));
+        }
+        if self.emphasis.is_some() {
+            active.push((MarkdownMark::Emphasis, None));
+        }
+        active
+    }
+
+    fn flush(&mut self) {
+        if !self.text.is_empty() {
+            let text = std::mem::take(&mut self.text);
+            self.runs.push((text, self.active()));
+        }
+    }
+
+    fn run_len(&self, i: usize) -> usize {
+        self.chars[i..]
+            .iter()
+            .take_while(|c| **c == self.chars[i])
+            .count()
+    }
+
+    fn code_span(&mut self, i: usize) -> usize {
+        let len = self.run_len(i);
+        let mut j = i + len;
+        while j < self.chars.len() {
+            if self.chars[j] != '`' {
+                j += 1;
+                continue;
+            }
+            let closing = self.run_len(j);
+            if closing == len {
+                let mut content = self.chars[i + len..j].iter().collect::<String>();
+                if content.len() > 2
+                    && content.starts_with(' ')
+                    && content.ends_with(' ')
+                    && !content.chars().all(|c| c == ' ')
+                {
+                    content = content[1..content.len() - 1].to_string();
+                }
+                self.flush();
+                let mut active = self.active();
+                active.push((MarkdownMark::Code, None));
+                self.runs.push((content, active));
+                return j + len;
+            }
+            j += closing;
+        }
+        self.text.extend(&self.chars[i..i + len]);
+        i + len
+    }
+
+    /// Handle a run of `*` or `_` starting at `i`, returning the index after the run
+    fn delimiter_run(&mut self, c: char, i: usize) -> usize {
+        let len = self.run_len(i);
+        let before = i.checked_sub(1).map(|j| self.chars[j]);
+        let after = self.chars.get(i + len).copied();
+        let space = |c: Option<char>| c.is_none_or(char::is_whitespace);
+        let alnum = |c: Option<char>| c.is_some_and(char::is_alphanumeric);
+        // underscores do not create emphasis inside words
+        let intraword = |c: char, outside: Option<char>| c == '_' && alnum(outside);
+        let can_open = !space(after) && !intraword(c, before);
+        let can_close = !space(before) && !intraword(c, after);
+
+        let mut remaining = len;
+        if can_close {
+            if remaining >= 2 && self.strong == Some(c) {
+                if remaining >= 3 && self.emphasis == Some(c) {
+                    self.flush();
+                    self.emphasis = None;
+                    remaining -= 1;
+                }
+                self.flush();
+                self.strong = None;
+                remaining -= 2;
+            }
+            if (remaining == 1 || remaining >= 3) && self.emphasis == Some(c) {
+                self.flush();
+                self.emphasis = None;
+                remaining -= 1;
+            }
+        }
+        if can_open && remaining > 0 && remaining <= 3 {
+            let both = remaining == 3 && self.strong.is_none() && self.emphasis.is_none();
+            if both && self.has_closer(c, i + len, 1..=3) {
+                self.flush();
+                self.strong = Some(c);
+                self.emphasis = Some(c);
+                remaining = 0;
+            } else if remaining == 2 && self.strong.is_none() && self.has_closer(c, i + len, 2..=3)
+            {
+                self.flush();
+                self.strong = Some(c);
+                remaining = 0;
+            } else if remaining == 1
+                && self.emphasis.is_none()
+                && self.has_closer(c, i + len, 1..=3)
+            {
+                self.flush();
+                self.emphasis = Some(c);
+                remaining = 0;
+            }
+        }
+        self.text.extend(std::iter::repeat_n(c, remaining));
+        i + len
+    }
+
+    /// Whether there is a run of `c` after `from` which could close a delimiter
+    fn has_closer(&self, c: char, from: usize, lengths: std::ops::RangeInclusive<usize>) -> bool {
+        let mut j = from;
+        while j < self.chars.len() {
+            if self.chars[j] == '\\' {
+                j += 2;
+                continue;
+            }
+            if self.chars[j] != c {
+                j += 1;
+                continue;
+            }
+            let len = self.run_len(j);
+            if lengths.contains(&len) && !self.chars[j - 1].is_whitespace() {
+                return true;
+            }
+            j += len;
+        }
+        false
+    }
+
+    /// Find the end of a link whose text starts at `i`
+    fn find_link(&self, i: usize) -> Option<(String, usize, usize)> {
+        let mut j = i + 1;
+        while j < self.chars.len() {
+            match self.chars[j] {
+                '\\' => j += 2,
+                '[' => return None,
+                ']' => break,
+                _ => j += 1,
+            }
+        }
+        let end = j;
+        if self.chars.get(end + 1) != Some(&'(') {
+            return None;
+        }
+        let rest = &self.chars[end + 2..];
+        let (url, consumed) = if rest.first() == Some(&'<') {
+            let close = rest.iter().position(|c| *c == '>')?;
+            if rest.get(close + 1) != Some(&')') {
+                return None;
+            }
+            (rest[1..close].iter().collect::<String>(), close + 2)
+        } else {
+            let close = rest.iter().position(|c| *c == ')')?;
+            let target = rest[..close].iter().collect::<String>();
+            // ignore a link title
+            let url = target.split_whitespace().next().unwrap_or_default();
+            (url.to_string(), close + 1)
+        };
+        Some((url, end, end + 2 + consumed))
+    }
+
+    /// Handle an autolink such as `<https://example.com>` starting at `i`
+    fn autolink(&mut self, i: usize) -> usize {
+        let close = self.chars[i..].iter().position(|c| *c == '>');
+        if let Some(close) = close {
+            let url = self.chars[i + 1..i + close].iter().collect::<String>();
+            let scheme = url.split(':').next().unwrap_or_default();
+            if url.contains(':')
+                && !scheme.is_empty()
+                && scheme.chars().all(|c| c.is_ascii_alphanumeric())
+                && !url.contains(char::is_whitespace)
+            {
+                self.flush();
+                let mut active = self.active();
+                active.retain(|(m, _)| *m != MarkdownMark::Link);
+                active.insert(0, (MarkdownMark::Link, Some(url.clone())));
+                self.runs.push((url, active));
+                return i + close + 1;
+            }
+        }
+        self.text.push('<');
+        i + 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(markdown: &str) -> String {
+        let config = MarkdownConfig::default();
+        to_markdown(from_markdown(markdown, &config), &config)
+    }
+
+    #[test]
+    fn inline_marks_round_trip() {
+        for source in [
+            "plain text",
+            "some **strong** and *emphasis* and `code`",
+            "a [link](https://example.com) with **strong** [**nested**](x) text",
+            "***both*** and **strong *with emphasis***",
+            "``code with ` backtick``",
+            "escaped \\*stars\\* and \\[brackets\\]",
+            "snake_case_words",
+        ] {
+            assert_eq!(round_trip(source), source);
+        }
+    }
+
+    #[test]
+    fn blocks_round_trip() {
+        let source = "# Title\n\nA paragraph\nover two lines\n\n1. one\n2. two\n   - nested\n3. three\n\n> quoted\n>\n> - quoted item\n\n```rust\nfn main() {}\n```";
+        assert_eq!(round_trip(source), source);
+    }
+
+    #[test]
+    fn text_which_looks_like_markdown_is_escaped() {
+        let config = MarkdownConfig::default();
+        let spans = vec![Span::Text {
+            text: "# not a heading\n1. not a list\n- nor this".to_string(),
+            marks: None,
+        }];
+        let markdown = to_markdown(spans, &config);
+        assert_eq!(markdown, "\\# not a heading\n1\\. not a list\n\\- nor this");
+        let parsed = from_markdown(&markdown, &config);
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(
+            parsed[1].as_str(),
+            "# not a heading\n1. not a list\n- nor this"
+        );
+    }
+
+    #[test]
+    fn unmatched_delimiters_are_text() {
+        let config = MarkdownConfig::default();
+        let parsed = from_markdown("2 * 3 = 6 and a [bracket", &config);
+        assert_eq!(
+            parsed[1],
+            Span::Text {
+                text: "2 * 3 = 6 and a [bracket".to_string(),
+                marks: None
+            }
+        );
+    }
+}
@@ -4,9 +4,11 @@ use std::borrow::Cow;
 use std::collections::{BTreeMap, HashMap};
 use std::fmt;
 use std::fmt::Display;
+use std::ops::Range;
 use std::sync::Arc;
 
 use crate::op_set2::{MarkData, Op, OpType};
+use crate::transaction::UpdateTextConfig;
 use crate::types::{Clock, ObjType, OpId, SmallHashMap};
 use crate::value::ScalarValue;
 
@@ -154,7 +156,7 @@ impl MarkSet {
         self.marks.insert(name, value);
     }
 
-    fn remove(&mut self, name: &SmolStr) {
+    pub(crate) fn remove(&mut self, name: &SmolStr) {
         self.marks.remove(name);
     }
 
@@ -464,12 +466,15 @@ impl NonDeletedMarks<'_> {
 }
 
 /// Configure the expand flag used when creating marks in [`update_spans`](crate::transaction::Transactable::update_spans)
+/// and how the old and new text are compared
 #[derive(Default, Debug, Clone)]
 pub struct UpdateSpansConfig {
     /// The expand flag to use when the mark does not have a flag set in Self::per_mark_expands.
     pub default_expand: ExpandMark,
     /// A map of mark names to the expand flag to use for that mark
     pub per_mark_expands: HashMap<String, ExpandMark>,
+    /// How the old and new text are compared
+    pub text_diff: UpdateTextConfig,
 }
 
 impl UpdateSpansConfig {
@@ -483,6 +488,55 @@ impl UpdateSpansConfig {
             .insert(mark_name.as_ref().to_string(), expand);
         self
     }
+
+    /// How the text of the spans is diffed against the current text, see [`UpdateTextConfig`]
+    pub fn with_text_diff(mut self, text_diff: UpdateTextConfig) -> Self {
+        self.text_diff = text_diff;
+        self
+    }
+}
+
+/// Which marks to return from [`ReadDoc::query_marks`](crate::ReadDoc::query_marks)
+///
+/// An empty query returns the same marks as [`ReadDoc::marks`](crate::ReadDoc::marks).
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct MarkQuery {
+    /// Only return marks with this name
+    pub name: Option<String>,
+    /// Only return marks with this value
+    pub value: Option<ScalarValue>,
+    /// Only return the parts of marks within this range of the sequence
+    pub range: Option<Range<usize>>,
+}
+
+impl MarkQuery {
+    /// A query which matches every mark
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only return marks named `name`
+    pub fn with_name<S: AsRef<str>>(mut self, name: S) -> Self {
+        self.name = Some(name.as_ref().to_string());
+        self
+    }
+
+    /// Only return marks whose value is `value`
+    pub fn with_value<V: Into<ScalarValue>>(mut self, value: V) -> Self {
+        self.value = Some(value.into());
+        self
+    }
+
+    /// Only return the parts of marks which are within `range`
+    pub fn in_range(mut self, range: Range<usize>) -> Self {
+        self.range = Some(range);
+        self
+    }
+
+    pub(crate) fn matches(&self, mark: &Mark) -> bool {
+        self.name.as_ref().is_none_or(|name| mark.name == name)
+            && self.value.as_ref().is_none_or(|value| &mark.value == value)
+    }
 }
 
 #[cfg(test)]
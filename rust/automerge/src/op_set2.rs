@@ -13,4 +13,4 @@ pub(crate) use types::{ActorIdx, KeyRef, MarkData, OpType, PropRef};
 pub use types::{ChangeMetadata, ScalarValue, ValueRef};
 
 pub(crate) use meta::ValueMeta;
-pub(crate) use op_set::{OpQuery, OpQueryTerm, OpSet, ReadOpError, TopOps};
+pub(crate) use op_set::{FoundOpId, OpQuery, OpQueryTerm, OpSet, ReadOpError, TopOps};
@@ -162,6 +162,8 @@ pub(crate) struct ChangeOpsColumns {
     pub(crate) pred_ctr: Range<usize>,
     pub(crate) expand: Range<usize>,
     pub(crate) mark_name: Range<usize>,
+    pub(crate) target_actor: Range<usize>,
+    pub(crate) target_ctr: Range<usize>,
 }
 
 pub(crate) fn shift_range(range: Range<usize>, by: usize) -> Range<usize> {
@@ -243,6 +245,13 @@ where
         ops.iter().map(T::mark_name),
         None,
     );
+    let target_actor = hexane::Encoder::<Option<ActorIdx>>::encode_to_unless(
+        data,
+        ops.iter().map(T::target_actor).map(&remap_opt_actor),
+        None,
+    );
+    let target_ctr =
+        hexane::Encoder::<Option<u64>>::encode_to_unless(data, ops.iter().map(T::target_ctr), None);
 
     let cols = ChangeOpsColumns {
         obj_actor,
@@ -259,6 +268,8 @@ where
         pred_ctr,
         expand,
         mark_name,
+        target_actor,
+        target_ctr,
     };
 
     cols.into()
@@ -319,6 +330,9 @@ impl<'a> ActorMapper<'a> {
         for id in C::pred(op) {
             self.process_actor(id.actor());
         }
+        if let Some(actor) = C::target_actor(op) {
+            self.process_actor(usize::from(actor));
+        }
     }
 
     pub(crate) fn build_mapping(&mut self, default_actor: Option<usize>) {
@@ -1,10 +1,11 @@
 use crate::change_queue::ChangeBatch;
+use crate::clock::ClockRange;
 use crate::hydrate::Value;
-use crate::iter::RichTextDiff;
+use crate::iter::{DiffIter, ListDiff, MapDiff, RichTextDiff};
 use crate::op_set2::types::{Action, KeyRef, MarkData, PropRef, ScalarValue as OpScalarValue};
 use crate::op_set2::SuccInsert;
 use crate::types::{
-    ActorId, ElemId, ObjId, ObjType, OpId, Prop, ScalarValue, SequenceType, SmallHashMap,
+    ActorId, ElemId, ObjId, ObjMeta, ObjType, OpId, Prop, ScalarValue, SequenceType, SmallHashMap,
 };
 use crate::{Automerge, Change, ChangeHash, PatchLog, PatchLogMismatch};
 use crate::{AutomergeError, TextEncoding};
@@ -85,7 +86,18 @@ impl<'a> Untangler<'a> {
             self.width = doc_op.width(self.seq_type, self.text_encoding);
         }
         self.value.process_doc_op(doc_op, deleted);
-        self.top.process_doc_op(self.change_ops, doc_op, deleted);
+        if !self.is_anchor(doc_op.action) {
+            self.top.process_doc_op(self.change_ops, doc_op, deleted);
+        }
+    }
+
+    // anchors in a list are never top (see `ObjIndex::is_list_anchor`)
+    fn is_anchor(&self, action: Action) -> bool {
+        match action {
+            Action::Move | Action::MoveObject => true,
+            Action::Mark => self.seq_type == SequenceType::List,
+            _ => false,
+        }
     }
 
     fn element_update(&mut self, doc_op: &Op<'_>) {
@@ -99,8 +111,10 @@ impl<'a> Untangler<'a> {
                 }
                 self.value.process_change_op(&self.change_ops[last]);
                 self.count += 1;
-                self.top
-                    .process_change_op(self.conflicts, self.change_ops, last);
+                if !self.is_anchor(self.change_ops[last].action()) {
+                    self.top
+                        .process_change_op(self.conflicts, self.change_ops, last);
+                }
             } else {
                 break;
             }
@@ -117,8 +131,10 @@ impl<'a> Untangler<'a> {
             }
             self.value.process_change_op(&self.change_ops[*i]);
 
-            self.top
-                .process_change_op(self.conflicts, self.change_ops, *i);
+            if !self.is_anchor(self.change_ops[*i].action()) {
+                self.top
+                    .process_change_op(self.conflicts, self.change_ops, *i);
+            }
 
             self.count += 1;
         }
@@ -613,7 +629,7 @@ impl<'a> ValueState<'a> {
 
     fn process_doc_op(&mut self, doc_op: &Op<'a>, deleted: bool) {
         match doc_op.action {
-            Action::Increment => {}
+            Action::Increment | Action::Move | Action::MoveObject => {}
             Action::Mark => {
                 self.marks.before.process(doc_op.id, doc_op.action());
                 self.marks.after.process(doc_op.id, doc_op.action());
@@ -652,7 +668,7 @@ impl<'a> ValueState<'a> {
 
     fn process_change_op(&mut self, op: &ChangeOp) {
         match op.action() {
-            Action::Delete => {}
+            Action::Delete | Action::Move | Action::MoveObject => {}
             Action::Increment => self.do_increment(op),
             Action::Mark => self.process_mark(op.id(), op.mark_data()),
             _ => {
@@ -700,6 +716,8 @@ impl<'a> ValueState<'a> {
                         log.put_seq(obj, index, d.value, d.id, d.conflict, true);
                     } else if d.deleted {
                         log.delete_seq(obj, index, 1);
+                    } else if let Some(m) = self.marks.current().export() {
+                        log.mark(obj, index, 1, &m);
                     }
                 }
                 _ => {}
@@ -890,8 +908,17 @@ impl BatchApply {
 
         log.migrate_actors(&doc.ops().actors)?;
 
+        let before = doc.get_heads();
+
         self.import_ops(doc);
 
+        // moving an object changes the paths of everything in it, so when objects are moved the
+        // whole document is diffed once all the ops are in place, from a completed view
+        let tree_moves = log.is_active() && self.ops.iter().any(|op| op.is_tree_move());
+        if tree_moves {
+            log.finish_current_view(doc, &before);
+        }
+
         let mut obj_info = doc.ops().obj_info.clone();
 
         self.order_ops_for_doc(&mut obj_info);
@@ -902,18 +929,34 @@ impl BatchApply {
 
         let mut conflicts = vec![];
 
+        // lists with moves are logged once all the ops are in place, as the index of an
+        // element depends on ops which may come later in the list
+        let mut moved = vec![];
+
         for os in &self.obj_spans {
             let obj_range = walker.seek_to_obj(os.obj);
             let doc_ops = doc.ops().iter_range(&obj_range);
             match obj_info.object_type(&os.obj) {
-                Some(ObjType::Map) => {
+                Some(ObjType::Map | ObjType::Table | ObjType::Set | ObjType::OrderedMap) => {
+                    // as with lists below, maps with moves are logged once the ops are in place
+                    let has_moves = obj_info.has_moves(&os.obj)
+                        || self.ops[os.span.clone()].iter().any(|op| op.is_tree_move());
+                    let mut null = PatchLog::null();
+                    let map_log = if tree_moves || has_moves {
+                        if has_moves {
+                            moved.push(os.obj);
+                        }
+                        &mut null
+                    } else {
+                        &mut *log
+                    };
                     let mut walker = MapWalker::new(
                         os.obj,
                         doc_ops,
                         doc.text_encoding(),
                         &mut self.pred,
                         &mut succ,
-                        log,
+                        map_log,
                         &mut conflicts,
                     );
                     let change_ops = &mut self.ops[os.span.clone()];
@@ -925,6 +968,10 @@ impl BatchApply {
                         ObjType::List => SequenceType::List,
                         _ => unreachable!(),
                     };
+                    let has_moves = obj_info.has_moves(&os.obj)
+                        || self.ops[os.span.clone()]
+                            .iter()
+                            .any(|op| matches!(op.action(), Action::Move | Action::MoveObject));
                     let ut = Untangler::new(
                         os.obj,
                         sequence_type,
@@ -934,7 +981,14 @@ impl BatchApply {
                         &mut self.pred,
                         doc_ops.end_pos(),
                     );
-                    walk_list(ut, doc_ops, &mut succ, log);
+                    if has_moves || tree_moves {
+                        if has_moves {
+                            moved.push(os.obj);
+                        }
+                        walk_list(ut, doc_ops, &mut succ, &mut PatchLog::null());
+                    } else {
+                        walk_list(ut, doc_ops, &mut succ, log);
+                    }
                 }
                 _ => panic!("Obj {:?} Missing from Index", os.obj),
             }
@@ -965,6 +1019,29 @@ impl BatchApply {
 
         self.insert_runs_of_ops(doc);
 
+        if tree_moves {
+            let before = doc.change_graph.clock_at(&before);
+            let after = doc.change_graph.clock_at(&doc.get_heads());
+            let clock = ClockRange::Diff(before, after);
+            DiffIter::log(doc, ObjMeta::root(), clock, log, true);
+        } else if log.is_active() && !moved.is_empty() {
+            let before = doc.change_graph.clock_at(&before);
+            let after = doc.change_graph.clock_at(&doc.get_heads());
+            let clock = ClockRange::Diff(before, after);
+            for obj in moved {
+                let range = doc.ops().scope_to_obj(&obj);
+                if doc.ops().object_type(&obj) == Some(ObjType::List) {
+                    for item in ListDiff::new(doc.ops(), range, clock.clone()) {
+                        item.log(obj, log, doc.text_encoding());
+                    }
+                } else {
+                    for item in MapDiff::new(doc.ops(), range, clock.clone()) {
+                        item.log(obj, log, doc.text_encoding());
+                    }
+                }
+            }
+        }
+
         debug_assert!(doc.ops.validate_op_order());
         Ok(())
     }
@@ -1076,7 +1153,13 @@ impl Automerge {
             let hash = c.hash();
             !(self.change_graph.has_change(&hash) || self.queue.has_hash(&hash))
         });
+        // Changes in the truncated history of a shallow document are already covered by its base
+        let mut truncated = HashSet::new();
         for c in changes {
+            if self.is_truncated(&c) {
+                truncated.insert(c.hash());
+                continue;
+            }
             if self.has_actor_seq(&c) {
                 self.queue
                     .remove_actor_branch_from(c.actor_id(), c.seq().saturating_add(1));
@@ -1094,6 +1177,15 @@ impl Automerge {
             batch.push(c)?;
         }
 
+        // A change which depends on truncated history we don't have can never be applied
+        if !truncated.is_empty() {
+            for c in batch.iter().chain(self.queue.iter()) {
+                if let Some(dep) = c.deps().iter().find(|d| truncated.contains(*d)) {
+                    return Err(AutomergeError::TruncatedHistory(*dep));
+                }
+            }
+        }
+
         self.queue.extend(batch);
 
         if self.queue.is_empty() {
@@ -1131,6 +1223,7 @@ impl Automerge {
                 let id = OpId::new(change.start_op().get() + i as u64, 0).map(&actors)?;
                 let key = c.key.map(&actors)?;
                 let obj = c.obj.map(&actors)?;
+                let target = c.target.map(|t| t.map(&actors)).transpose()?;
                 let pred = c
                     .pred
                     .into_iter()
@@ -1146,6 +1239,7 @@ impl Automerge {
                     expand: c.expand,
                     insert: c.insert,
                     pred,
+                    target,
                 };
                 let change = ChangeOp {
                     pos: None,
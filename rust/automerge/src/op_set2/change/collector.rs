@@ -92,6 +92,9 @@ pub(crate) struct ChangeCollector<'a> {
     pub(crate) builders: Vec<ChangeBuilder<'a>>,
     last: Option<(ObjId, KeyRef<'a>)>,
     preds: HashMap<OpId, Vec<OpId>>,
+    /// The hashes of the stubs of a shallow document, these are the first entries in `changes`
+    stubs: &'a [ChangeHash],
+    base_heads: &'a [ChangeHash],
 }
 
 #[derive(Clone, PartialEq, Debug)]
@@ -354,6 +357,8 @@ pub(crate) struct ProgressiveEncoder<'a> {
     pred_ctr: hexane::DeltaEncoder<'a, i64>,
     expand: hexane::Encoder<'a, bool>,
     mark_name: hexane::Encoder<'a, Option<String>>,
+    target_actor: hexane::Encoder<'a, Option<ActorIdx>>,
+    target_ctr: hexane::Encoder<'a, Option<u64>>,
 }
 
 impl<'a> ProgressiveEncoder<'a> {
@@ -380,6 +385,9 @@ impl<'a> ProgressiveEncoder<'a> {
         for id in &op.pred {
             self.process_actor(id.actor());
         }
+        if let Some(actor) = op.target.and_then(|t| t.actor()) {
+            self.process_actor(usize::from(actor));
+        }
     }
 
     fn add(&mut self, index: usize, op: OpBuilder<'a>) {
@@ -420,6 +428,8 @@ impl<'a> ProgressiveEncoder<'a> {
         self.expand.append(op.expand);
         self.mark_name
             .append_owned(op.mark_name.map(|s| s.into_owned()));
+        self.target_actor.append(op.target.and_then(|t| t.actor()));
+        self.target_ctr.append(op.target.and_then(|t| t.counter()));
     }
 
     fn flush(&mut self) {
@@ -489,6 +499,10 @@ impl<'a> ProgressiveEncoder<'a> {
         let pred_ctr = self.pred_ctr.save_to(data);
         let expand = self.expand.save_to_unless(data, false);
         let mark_name = self.mark_name.save_to_unless(data, None);
+        let target_actor = self
+            .target_actor
+            .save_to_unless_and_remap(data, None, &remap_opt);
+        let target_ctr = self.target_ctr.save_to_unless(data, None);
 
         ChangeOpsColumns {
             obj_actor,
@@ -505,6 +519,8 @@ impl<'a> ProgressiveEncoder<'a> {
             pred_ctr,
             expand,
             mark_name,
+            target_actor,
+            target_ctr,
         }
     }
 
@@ -577,7 +593,10 @@ impl<'a> ChangeCollector<'a> {
         for c in change_cols.iter() {
             meta.push(c);
         }
-        ChangeCollector::try_from_change_meta(meta, &op_set.actors)
+        let mut collector = ChangeCollector::try_from_change_meta(meta, &op_set.actors)?;
+        collector.stubs = change_cols.stub_hashes();
+        collector.base_heads = change_cols.base_heads();
+        Ok(collector)
     }
 
     pub(crate) fn process_ops(
@@ -642,6 +661,8 @@ impl<'a> ChangeCollector<'a> {
             builders,
             last: None,
             preds: HashMap::default(),
+            stubs: &[],
+            base_heads: &[],
         })
     }
 
@@ -908,14 +929,27 @@ impl<'a> ChangeCollector<'a> {
         let mut seq = vec![0; num_actors];
         let mut changes = Vec::with_capacity(self.changes.len());
         let mut heads = BTreeSet::new();
+        let num_stubs = self.stubs.len();
 
-        for change in self.changes.into_iter() {
+        for (index, change) in self.changes.into_iter().enumerate() {
             let actor = change.actor;
 
             if actor >= num_actors {
                 return Err(Error::MissingActor);
             }
 
+            if index < num_stubs {
+                // stubs stand in for truncated history, they have no ops and are only heads if
+                // they are part of the base
+                let hash = self.stubs[index];
+                seq[actor] = seq[actor].max(change.seq);
+                max_ops[actor] = max_ops[actor].max(change.max_op);
+                if self.base_heads.contains(&hash) {
+                    heads.insert(hash);
+                }
+                continue;
+            }
+
             if seq[actor] + 1 != change.seq {
                 return Err(Error::ChangesOutOfOrder);
             }
@@ -931,7 +965,11 @@ impl<'a> ChangeCollector<'a> {
 
             max_ops[actor] = max_op;
 
-            let change = self.builders[builder].finish(&change, &changes, &mut self.mapper)?;
+            let deps = StubDeps {
+                stubs: self.stubs,
+                changes: &changes,
+            };
+            let change = self.builders[builder].finish(&change, &deps, &mut self.mapper)?;
 
             let hash = change.hash();
 
@@ -953,6 +991,21 @@ pub(crate) struct CollectedChanges {
     pub(crate) heads: BTreeSet<ChangeHash>,
 }
 
+/// Resolves dependencies on the stubs of a shallow document, which precede the changes
+struct StubDeps<'a> {
+    stubs: &'a [ChangeHash],
+    changes: &'a Vec<Change>,
+}
+
+impl GetHash for StubDeps<'_> {
+    fn get_hash(&self, index: usize) -> Option<ChangeHash> {
+        match index.checked_sub(self.stubs.len()) {
+            None => Some(self.stubs[index]),
+            Some(index) => Some(self.changes.get(index)?.hash()),
+        }
+    }
+}
+
 struct BundleDeps<'a> {
     num_changes: usize,
     changes: &'a Vec<Change>,
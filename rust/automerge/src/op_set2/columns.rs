@@ -1,6 +1,6 @@
 use super::meta::ValueMeta;
 use super::op::OpLike;
-use super::op_set::MarkIndexColumn;
+use super::op_set::{MarkIndexColumn, ObjIndex};
 use super::types::{Action, ActorIdx, ScalarValue};
 use crate::storage::columns::compression::Uncompressed;
 use crate::storage::columns::{BadColumnLayout, Columns as ColumnFormat};
@@ -31,6 +31,8 @@ pub(super) struct Columns {
     pub(super) value: hexane::RawColumn,
     pub(super) mark_name: hexane::Column<Option<String>>,
     pub(super) expand: hexane::Column<bool>,
+    pub(super) target_actor: hexane::Column<Option<ActorIdx>>,
+    pub(super) target_ctr: hexane::Column<Option<u32>>,
     pub(super) index: Indexes,
 }
 
@@ -74,6 +76,8 @@ impl Default for Columns {
             value: hexane::RawColumn::new(),
             mark_name: hexane::Column::new(),
             expand: hexane::Column::new(),
+            target_actor: hexane::Column::new(),
+            target_ctr: hexane::Column::new(),
             index: Indexes::default(),
         }
     }
@@ -93,6 +97,35 @@ fn debug_cmp<I: Debug + PartialEq>(tag: &str, a: Vec<I>, b: Vec<I>) -> bool {
 }
 
 impl Columns {
+    /// The number of bytes of encoded data held by each column, including the index columns
+    pub(super) fn byte_lens(&self) -> Vec<(&'static str, usize)> {
+        vec![
+            ("id_actor", self.id_actor.byte_len()),
+            ("id_ctr", self.id_ctr.byte_len()),
+            ("obj_actor", self.obj_actor.byte_len()),
+            ("obj_ctr", self.obj_ctr.byte_len()),
+            ("key_actor", self.key_actor.byte_len()),
+            ("key_ctr", self.key_ctr.byte_len()),
+            ("key_str", self.key_str.byte_len()),
+            ("succ_count", self.succ_count.byte_len()),
+            ("succ_actor", self.succ_actor.byte_len()),
+            ("succ_ctr", self.succ_ctr.byte_len()),
+            ("insert", self.insert.byte_len()),
+            ("action", self.action.byte_len()),
+            ("value_meta", self.value_meta.byte_len()),
+            ("value", self.value.len()),
+            ("mark_name", self.mark_name.byte_len()),
+            ("expand", self.expand.byte_len()),
+            ("target_actor", self.target_actor.byte_len()),
+            ("target_ctr", self.target_ctr.byte_len()),
+            ("index_text", self.index.text.byte_len()),
+            ("index_top", self.index.top.byte_len()),
+            ("index_visible", self.index.visible.byte_len()),
+            ("index_inc", self.index.inc.byte_len()),
+            ("index_mark", self.index.mark.byte_len()),
+        ]
+    }
+
     #[cfg(test)]
     pub(super) fn debug_cmp(&self, other: &Self) {
         let mut ok = true;
@@ -120,6 +153,10 @@ impl Columns {
             self.expand.iter().collect::<Vec<_>>(),
             other.expand.iter().collect::<Vec<_>>()
         );
+        log!("TARGET_ACTOR");
+        assert_eq!(self.target_actor.to_vec(), other.target_actor.to_vec());
+        log!("TARGET_CTR");
+        assert_eq!(self.target_ctr.to_vec(), other.target_ctr.to_vec());
         log!("SUCC_COUNT");
         assert_eq!(self.succ_count.to_vec(), other.succ_count.to_vec());
         log!("SUCC_ACTOR");
@@ -160,6 +197,12 @@ impl Columns {
                 RawColumn::try_new(*spec, self.mark_name.save_to_unless(data, None))
             }
             EXPAND_COL_SPEC => RawColumn::try_new(*spec, self.expand.save_to_unless(data, false)),
+            TARGET_ACTOR_COL_SPEC => {
+                RawColumn::try_new(*spec, self.target_actor.save_to_unless(data, None))
+            }
+            TARGET_COUNTER_COL_SPEC => {
+                RawColumn::try_new(*spec, self.target_ctr.save_to_unless(data, None))
+            }
             SUCC_COUNT_COL_SPEC => RawColumn::try_new(*spec, self.succ_count.save_to(data)),
             SUCC_ACTOR_COL_SPEC => RawColumn::try_new(*spec, self.succ_actor.save_to(data)),
             SUCC_COUNTER_COL_SPEC => RawColumn::try_new(*spec, self.succ_ctr.save_to(data)),
@@ -200,6 +243,8 @@ impl Columns {
             ("value", self.value.save()),
             ("mark_name", self.mark_name.save()),
             ("expand", self.expand.save()),
+            ("target_actor", self.target_actor.save()),
+            ("target_ctr", self.target_ctr.save()),
             // succ
             ("succ_count", self.succ_count.save()),
             ("succ_actor", self.succ_actor.save()),
@@ -243,6 +288,8 @@ impl Columns {
                         | VALUE_COL_SPEC
                         | MARK_NAME_COL_SPEC
                         | EXPAND_COL_SPEC
+                        | TARGET_ACTOR_COL_SPEC
+                        | TARGET_COUNTER_COL_SPEC
                 )
             })
             .cloned()
@@ -291,6 +338,15 @@ impl Columns {
 
         let expand = hexane::Column::load_with(data_for(EXPAND_COL_SPEC), opts.with_fill(false))?;
 
+        let target_actor = hexane::Column::<Option<ActorIdx>>::load_with(
+            data_for(TARGET_ACTOR_COL_SPEC),
+            opts.with_fill(None),
+        )?;
+        let target_ctr = hexane::Column::<Option<u32>>::load_with(
+            data_for(TARGET_COUNTER_COL_SPEC),
+            opts.with_fill(None),
+        )?;
+
         let succ_count =
             hexane::PrefixColumn::<u32>::load_with(data_for(SUCC_COUNT_COL_SPEC), opts)?;
 
@@ -325,6 +381,8 @@ impl Columns {
             value,
             mark_name,
             expand,
+            target_actor,
+            target_ctr,
             index,
         })
     }
@@ -337,6 +395,7 @@ impl Columns {
         self.succ_actor.remap(f);
         self.obj_actor.remap(&|a: Option<ActorIdx>| a.map(f));
         self.key_actor.remap(&|a: Option<ActorIdx>| a.map(f));
+        self.target_actor.remap(&|a: Option<ActorIdx>| a.map(f));
     }
 
     pub(crate) fn rewrite_with_new_actor(&mut self, idx: usize) {
@@ -384,6 +443,10 @@ impl Columns {
         self.action.splice(pos, del, std::iter::empty::<Action>());
         self.expand.splice(pos, del, std::iter::empty::<bool>());
         self.mark_name.splice(pos, del, [] as [Option<&str>; 0]);
+        self.target_actor
+            .splice(pos, del, std::iter::empty::<Option<ActorIdx>>());
+        self.target_ctr
+            .splice(pos, del, std::iter::empty::<Option<u32>>());
 
         self.value_meta
             .splice(pos, del, std::iter::empty::<ValueMeta>());
@@ -416,7 +479,13 @@ impl Columns {
         ops.count()
     }
 
-    pub(crate) fn splice<O>(&mut self, pos: usize, ops: &[O], text_encoding: TextEncoding) -> usize
+    pub(crate) fn splice<O>(
+        &mut self,
+        pos: usize,
+        ops: &[O],
+        text_encoding: TextEncoding,
+        obj_info: &ObjIndex,
+    ) -> usize
     where
         O: OpLike,
     {
@@ -439,6 +508,13 @@ impl Columns {
         self.action.splice(pos, 0, ops.clone().map(O::action));
         self.expand.splice(pos, 0, ops.clone().map(O::expand));
         self.mark_name.splice(pos, 0, ops.clone().map(O::mark_name));
+        self.target_actor
+            .splice(pos, 0, ops.clone().map(O::target_actor));
+        self.target_ctr.splice(
+            pos,
+            0,
+            ops.clone().map(|o| O::target_ctr(o).map(|v| v as u32)),
+        );
 
         self.value_meta
             .splice(pos, 0, ops.clone().map(|o| o.meta_value()));
@@ -472,18 +548,19 @@ impl Columns {
         self.index
             .mark
             .extend(pos, ops.clone().map(O::mark_index).collect());
+        let top = |o: &O| O::top(o) && !obj_info.is_list_anchor(o);
         self.index.text.splice(
             pos,
             0,
             ops.clone().map(|s| {
-                if O::top(s) {
+                if top(s) {
                     Some(O::width(s, SequenceType::Text, text_encoding) as u32)
                 } else {
                     None
                 }
             }),
         );
-        self.index.top.splice(pos, 0, ops.clone().map(O::top));
+        self.index.top.splice(pos, 0, ops.clone().map(top));
         self.index
             .visible
             .splice(pos, 0, ops.clone().map(O::visible));
@@ -495,10 +572,23 @@ impl Columns {
     pub(crate) fn new<'a, I: Iterator<Item = super::op::Op<'a>> + ExactSizeIterator + Clone>(
         ops: I,
     ) -> Self {
-        let mut op_set = Self::default();
-        let ops: Vec<_> = ops.collect();
-        op_set.splice(0, &ops, TextEncoding::platform_default());
-        op_set
+        Self::from_sorted_ops(ops.collect(), TextEncoding::platform_default())
+    }
+
+    /// Build the columns for `ops`, which must already be in the order of an op set: grouped by
+    /// object, then by key (or element), then by op id, with the succ of each op filled in.
+    ///
+    /// The index columns are computed without any knowledge of the objects, so moved list
+    /// elements and the anchors of list marks are indexed as ordinary ops. Call
+    /// [`OpSet::rebuild_indexes`](super::OpSet::rebuild_indexes) on the op set which owns these
+    /// columns before reading from it.
+    pub(crate) fn from_sorted_ops(
+        ops: Vec<super::op::Op<'_>>,
+        text_encoding: TextEncoding,
+    ) -> Self {
+        let mut columns = Self::default();
+        columns.splice(0, &ops, text_encoding, &ObjIndex::default());
+        columns
     }
 
     pub(crate) fn len(&self) -> usize {
@@ -623,6 +713,7 @@ pub(crate) mod ids {
     pub(crate) const SUCC_COL_ID:               ColumnId = ColumnId::new(8);
     pub(crate) const EXPAND_COL_ID:             ColumnId = ColumnId::new(9);
     pub(crate) const MARK_NAME_COL_ID:          ColumnId = ColumnId::new(10);
+    pub(crate) const TARGET_COL_ID:             ColumnId = ColumnId::new(11);
 
     pub(crate) const ID_ACTOR_COL_SPEC:       ColumnSpec = ColumnSpec::new_actor(ID_COL_ID);
     pub(crate) const ID_COUNTER_COL_SPEC:     ColumnSpec = ColumnSpec::new_delta(ID_COL_ID);
@@ -640,8 +731,10 @@ pub(crate) mod ids {
     pub(crate) const VALUE_COL_SPEC:          ColumnSpec = ColumnSpec::new_value(VAL_COL_ID);
     pub(crate) const MARK_NAME_COL_SPEC:      ColumnSpec = ColumnSpec::new_string(MARK_NAME_COL_ID);
     pub(crate) const EXPAND_COL_SPEC:         ColumnSpec = ColumnSpec::new_boolean(EXPAND_COL_ID);
+    pub(crate) const TARGET_ACTOR_COL_SPEC:   ColumnSpec = ColumnSpec::new_actor(TARGET_COL_ID);
+    pub(crate) const TARGET_COUNTER_COL_SPEC: ColumnSpec = ColumnSpec::new_integer(TARGET_COL_ID);
 
-    pub(crate) const ALL_COLUMN_SPECS: [ColumnSpec; 16] = [
+    pub(crate) const ALL_COLUMN_SPECS: [ColumnSpec; 18] = [
         ID_ACTOR_COL_SPEC,
         ID_COUNTER_COL_SPEC,
         OBJ_ID_ACTOR_COL_SPEC,
@@ -658,6 +751,8 @@ pub(crate) mod ids {
         VALUE_COL_SPEC,
         MARK_NAME_COL_SPEC,
         EXPAND_COL_SPEC,
+        TARGET_ACTOR_COL_SPEC,
+        TARGET_COUNTER_COL_SPEC,
     ];
 }
 
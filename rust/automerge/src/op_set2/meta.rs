@@ -13,6 +13,7 @@ pub(crate) enum ValueType {
     Bytes,
     Counter,
     Timestamp,
+    Decimal,
     Unknown(u8),
 }
 
@@ -33,6 +34,7 @@ impl ValueMeta {
             7 => ValueType::Bytes,
             8 => ValueType::Counter,
             9 => ValueType::Timestamp,
+            10 => ValueType::Decimal,
             other => ValueType::Unknown(other),
         }
     }
@@ -63,9 +65,13 @@ impl From<&crate::ScalarValue> for ValueMeta {
             crate::ScalarValue::Counter(i) => Self((lebsize(i.start) << 4) | 8),
             crate::ScalarValue::Str(s) => Self(((s.len() as u64) << 4) | 6),
             crate::ScalarValue::Bytes(b) => Self(((b.len() as u64) << 4) | 7),
+            crate::ScalarValue::Decimal(d) => Self(((d.to_bytes().len() as u64) << 4) | 10),
             crate::ScalarValue::Unknown { type_code, bytes } => {
                 Self(((bytes.len() as u64) << 4) | (*type_code as u64))
             }
+            crate::ScalarValue::Extension(e) => {
+                Self(((e.bytes().len() as u64) << 4) | (e.type_code() as u64))
+            }
         }
     }
 }
@@ -85,6 +91,7 @@ impl<'a> From<&'a ScalarValue<'a>> for ValueMeta {
             ScalarValue::Counter(i) => Self((lebsize(*i) << 4) | 8),
             ScalarValue::Str(s) => Self(((s.len() as u64) << 4) | 6),
             ScalarValue::Bytes(b) => Self(((b.len() as u64) << 4) | 7),
+            ScalarValue::Decimal(d) => Self(((d.to_bytes().len() as u64) << 4) | 10),
             ScalarValue::Unknown { type_code, bytes } => {
                 Self(((bytes.len() as u64) << 4) | (*type_code as u64))
             }
@@ -57,6 +57,11 @@ pub(crate) struct ChangeOp {
 }
 
 impl ChangeOp {
+    /// Whether this op moves an object, see [`OpSet::tree_view`](super::op_set::OpSet::tree_view)
+    pub(crate) fn is_tree_move(&self) -> bool {
+        self.bld.action == Action::MoveObject
+    }
+
     pub(crate) fn prop_static(&self) -> Option<PropRef<'static>> {
         match &self.bld.key {
             KeyRef::Map(s) => Some(PropRef::Map(Cow::Owned(String::from(s.as_ref())))),
@@ -117,7 +122,13 @@ impl ChangeOp {
     pub(crate) fn is_set_or_make(&self) -> bool {
         matches!(
             self.bld.action,
-            Action::Set | Action::MakeMap | Action::MakeList | Action::MakeText | Action::MakeTable
+            Action::Set
+                | Action::MakeMap
+                | Action::MakeList
+                | Action::MakeText
+                | Action::MakeTable
+                | Action::MakeSet
+                | Action::MakeOrderedMap
         )
     }
 
@@ -184,6 +195,8 @@ pub(crate) struct OpBuilder<'a> {
     pub(crate) insert: bool,
     pub(crate) expand: bool,
     pub(crate) mark_name: Option<Cow<'a, str>>,
+    /// The object moved by an [`Action::MoveObject`]
+    pub(crate) target: Option<ObjId>,
     pub(crate) pred: Vec<OpId>,
 }
 
@@ -203,8 +216,8 @@ impl OpBuilder<'_> {
 
     pub(crate) fn width(&self, seq_type: SequenceType, text_encoding: TextEncoding) -> usize {
         match seq_type {
+            _ if self.is_mark() || self.is_move() => 0,
             SequenceType::List => 1,
-            SequenceType::Text if self.is_mark() => 0,
             SequenceType::Text => text_encoding.width(self.as_str()),
         }
     }
@@ -217,10 +230,14 @@ impl OpBuilder<'_> {
         self.action == Action::Mark
     }
 
+    pub(crate) fn is_move(&self) -> bool {
+        matches!(self.action, Action::Move | Action::MoveObject)
+    }
+
     pub(crate) fn as_str(&self) -> &str {
         match (self.action, &self.value) {
             (Action::Set, ScalarValue::Str(s)) => s,
-            (Action::Mark, _) => "",
+            (Action::Mark | Action::Move | Action::MoveObject, _) => "",
             _ => "\u{fffc}",
         }
     }
@@ -245,6 +262,9 @@ impl OpBuilder<'_> {
             Action::MakeList => hydrate::Value::list(),
             Action::MakeText => hydrate::Value::new(ObjType::Text, text_encoding),
             Action::MakeTable => hydrate::Value::new(ObjType::Table, text_encoding),
+            Action::MakeSet => hydrate::Value::set(),
+            Action::MakeOrderedMap => hydrate::Value::ordered_map(),
+            Action::Move | Action::MoveObject => hydrate::Value::Scalar(crate::ScalarValue::Null),
             //Action::Mark if self.mark_name.is_some() => hydrate::Value::new(&self.value, text_rep),
             //Action::Mark => hydrate::Value::Scalar("markEnd".into()),
             _ => panic!("cant convert op into a value"),
@@ -271,6 +291,7 @@ impl TxOp {
             ResolvedAction::ConflictResolution(action) => (action, true),
             ResolvedAction::VisibleUpdate(action) => (action, false),
         };
+        let target = op_type.target();
         let (action, value, expand, mark_name) = op_type.decompose();
         TxOp {
             obj_type: obj.typ,
@@ -286,6 +307,7 @@ impl TxOp {
                 value,
                 expand,
                 mark_name,
+                target,
                 key: KeyRef::Seq(elemid),
                 insert: false,
                 pred,
@@ -305,6 +327,7 @@ impl TxOp {
             ResolvedAction::ConflictResolution(action) => (action, true),
             ResolvedAction::VisibleUpdate(action) => (action, false),
         };
+        let target = action.target();
         let (action, value, expand, mark_name) = action.clone().decompose();
         TxOp {
             obj_type: obj.typ,
@@ -320,6 +343,7 @@ impl TxOp {
                 action,
                 expand,
                 mark_name,
+                target,
                 key: KeyRef::Map(Cow::Owned(prop)),
                 insert: false,
                 pred,
@@ -335,6 +359,7 @@ impl TxOp {
         _action: types::OpType,
         elemid: ElemId,
     ) -> Self {
+        let target = _action.target();
         let (action, value, expand, mark_name) = _action.clone().decompose();
         TxOp {
             obj_type: obj.typ,
@@ -350,6 +375,7 @@ impl TxOp {
                 value,
                 expand,
                 mark_name,
+                target,
                 key: KeyRef::Seq(elemid),
                 insert: true,
                 pred: vec![],
@@ -380,6 +406,7 @@ impl TxOp {
                 value,
                 expand,
                 mark_name,
+                target: None,
                 key: KeyRef::Seq(elemid),
                 insert: true,
                 pred: vec![],
@@ -411,6 +438,7 @@ impl TxOp {
                 value,
                 expand,
                 mark_name,
+                target: None,
                 key: elemid.into(),
                 insert: true,
                 pred: vec![],
@@ -441,6 +469,7 @@ impl TxOp {
                 value,
                 expand,
                 mark_name,
+                target: None,
                 key: elemid.into(),
                 insert: false,
                 pred: pred.into_iter().collect(),
@@ -498,7 +527,11 @@ impl OpLike for &TxOp {
     fn obj_info(&self) -> Option<ObjInfo> {
         let obj_type = ObjType::try_from(self.bld.action).ok()?;
         let parent = self.bld.obj;
-        Some(ObjInfo { parent, obj_type })
+        Some(ObjInfo {
+            parent,
+            obj_type,
+            moves: false,
+        })
     }
 
     fn id_actor(op: &Self) -> ActorIdx {
@@ -523,6 +556,9 @@ impl OpLike for &TxOp {
     fn obj(&self) -> ObjId {
         self.as_builder().obj
     }
+    fn target(&self) -> Option<ObjId> {
+        self.as_builder().target
+    }
     fn action(o: &Self) -> Action {
         o.as_builder().action
     }
@@ -577,7 +613,11 @@ impl OpLike for TxOp {
     fn obj_info(&self) -> Option<ObjInfo> {
         let obj_type = ObjType::try_from(self.bld.action).ok()?;
         let parent = self.bld.obj;
-        Some(ObjInfo { parent, obj_type })
+        Some(ObjInfo {
+            parent,
+            obj_type,
+            moves: false,
+        })
     }
 
     fn id_actor(op: &Self) -> ActorIdx {
@@ -602,6 +642,9 @@ impl OpLike for TxOp {
     fn obj(&self) -> ObjId {
         self.as_builder().obj
     }
+    fn target(&self) -> Option<ObjId> {
+        self.as_builder().target
+    }
     fn action(o: &Self) -> Action {
         o.as_builder().action
     }
@@ -664,7 +707,11 @@ impl OpLike for ChangeOp {
     fn obj_info(&self) -> Option<ObjInfo> {
         let obj_type = ObjType::try_from(self.bld.action).ok()?;
         let parent = self.bld.obj;
-        Some(ObjInfo { parent, obj_type })
+        Some(ObjInfo {
+            parent,
+            obj_type,
+            moves: false,
+        })
     }
 
     fn id_actor(op: &Self) -> ActorIdx {
@@ -688,6 +735,9 @@ impl OpLike for ChangeOp {
     fn obj(&self) -> ObjId {
         self.as_builder().obj
     }
+    fn target(&self) -> Option<ObjId> {
+        self.as_builder().target
+    }
     fn action(o: &Self) -> Action {
         o.as_builder().action
     }
@@ -765,7 +815,11 @@ impl<'a> OpLike for Op<'a> {
     fn obj_info(&self) -> Option<ObjInfo> {
         let obj_type = ObjType::try_from(self.action).ok()?;
         let parent = self.obj;
-        Some(ObjInfo { parent, obj_type })
+        Some(ObjInfo {
+            parent,
+            obj_type,
+            moves: false,
+        })
     }
 
     fn id_actor(op: &Self) -> ActorIdx {
@@ -783,6 +837,10 @@ impl<'a> OpLike for Op<'a> {
         self.obj
     }
 
+    fn target(&self) -> Option<ObjId> {
+        self.target
+    }
+
     fn action(o: &Self) -> Action {
         o.action
     }
@@ -840,7 +898,12 @@ pub(crate) struct Op<'a> {
     pub(crate) value: ScalarValue<'a>,
     pub(crate) expand: bool,
     pub(crate) mark_name: Option<&'a str>,
+    /// The object moved by an [`Action::MoveObject`]
+    pub(crate) target: Option<ObjId>,
     pub(super) succ_cursors: SuccCursors<'a>,
+    /// When this is the op a moved object is displayed at, the id of that op, `id` having been
+    /// replaced with the id of the object (see [`OpSet::tree_view`])
+    pub(crate) slot: Option<OpId>,
 }
 
 #[derive(Clone, Default)]
@@ -995,7 +1058,10 @@ impl<'a> Op<'a> {
     }
 
     pub(crate) fn as_str(&self) -> &str {
-        if self.action == Action::Mark {
+        if matches!(
+            self.action,
+            Action::Mark | Action::Move | Action::MoveObject
+        ) {
             ""
         } else if let ScalarValue::Str(s) = &self.value {
             s.as_ref()
@@ -1006,8 +1072,14 @@ impl<'a> Op<'a> {
 
     pub(crate) fn width(&self, seq_type: SequenceType, text_encoding: TextEncoding) -> usize {
         match seq_type {
+            _ if matches!(
+                self.action,
+                Action::Mark | Action::Move | Action::MoveObject
+            ) =>
+            {
+                0
+            }
             SequenceType::List => 1,
-            SequenceType::Text if self.action == Action::Mark => 0,
             SequenceType::Text => text_encoding.width(self.as_str()),
         }
     }
@@ -1024,6 +1096,11 @@ impl<'a> Op<'a> {
         self.succ_cursors.clone().with_inc()
     }
 
+    /// The id this op is stored under, which differs from `id` for the slot of a moved object
+    pub(crate) fn stored_id(&self) -> OpId {
+        self.slot.unwrap_or(self.id)
+    }
+
     pub(crate) fn exid(&self, op_set: &OpSet) -> ExId {
         let id = self.id;
         if id == types::ROOT {
@@ -1039,7 +1116,7 @@ impl<'a> Op<'a> {
 
     pub(crate) fn elemid_or_key(&self) -> KeyRef<'a> {
         if self.insert {
-            KeyRef::Seq(ElemId(self.id))
+            KeyRef::Seq(ElemId(self.stored_id()))
         } else {
             self.key.clone()
         }
@@ -1072,7 +1149,7 @@ impl<'a> Op<'a> {
 
     pub(crate) fn cursor(&self) -> Result<ElemId, AutomergeError> {
         if self.insert {
-            Ok(ElemId(self.id))
+            Ok(ElemId(self.stored_id()))
         } else {
             match self.key {
                 KeyRef::Seq(e) => Ok(e),
@@ -1101,6 +1178,7 @@ impl<'a> Op<'a> {
                 ValueRef::Scalar(ScalarValue::Str(Cow::Borrowed("markBegin")))
             }
             OpType::MarkEnd(_) => ValueRef::Scalar(ScalarValue::Str(Cow::Borrowed("markEnd"))),
+            OpType::Move | OpType::MoveObject => ValueRef::Scalar(ScalarValue::Null),
             _ => panic!("cant convert op into a value - {:?}", self),
         }
     }
@@ -1111,6 +1189,7 @@ impl<'a> Op<'a> {
             OpType::Put(scalar) => hydrate::Value::Scalar(scalar.to_owned()),
             OpType::MarkBegin(_, mark) => hydrate::Value::new(&mark.value, text_encoding),
             OpType::MarkEnd(_) => hydrate::Value::Scalar("markEnd".into()),
+            OpType::Move | OpType::MoveObject => hydrate::Value::Scalar(crate::ScalarValue::Null),
             _ => panic!("cant convert op into a value"),
         }
     }
@@ -1141,6 +1220,7 @@ impl<'a> Op<'a> {
             insert: self.insert,
             expand: self.expand,
             mark_name: self.mark_name.map(Cow::Borrowed),
+            target: self.target,
             pred,
         }
     }
@@ -1167,7 +1247,9 @@ impl<'a> Op<'a> {
             value: ScalarValue::Null,
             expand: false,
             mark_name: None,
+            target: None,
             succ_cursors: SuccCursors::default(),
+            slot: None,
         }
     }
 
@@ -1218,6 +1300,8 @@ pub(crate) trait AsChangeOp {
     fn pred_count(op: &Self) -> u32;
     fn expand(op: &Self) -> bool;
     fn mark_name(op: &Self) -> Option<&str>;
+    fn target_actor(op: &Self) -> Option<ActorIdx>;
+    fn target_ctr(op: &Self) -> Option<u64>;
     fn op_id_ctr(op: &Self) -> u64;
     fn pred(op: &Self) -> &[OpId];
 
@@ -1263,6 +1347,12 @@ impl<T: AsChangeOp> AsChangeOp for Option<T> {
     fn mark_name(op: &Self) -> Option<&str> {
         T::mark_name(op.as_ref()?)
     }
+    fn target_actor(op: &Self) -> Option<ActorIdx> {
+        T::target_actor(op.as_ref()?)
+    }
+    fn target_ctr(op: &Self) -> Option<u64> {
+        T::target_ctr(op.as_ref()?)
+    }
     fn op_id_ctr(op: &Self) -> u64 {
         op.as_ref().map(|o| T::op_id_ctr(o)).unwrap_or(0)
     }
@@ -1313,6 +1403,12 @@ impl<B: AsBuilder> AsChangeOp for B {
     fn mark_name(op: &Self) -> Option<&str> {
         op.as_builder().mark_name.as_deref()
     }
+    fn target_actor(op: &Self) -> Option<ActorIdx> {
+        op.as_builder().target?.actor()
+    }
+    fn target_ctr(op: &Self) -> Option<u64> {
+        op.as_builder().target?.counter()
+    }
     fn op_id_ctr(op: &Self) -> u64 {
         op.as_builder().id.counter()
     }
@@ -1344,6 +1440,13 @@ pub(crate) trait OpLike: Debug {
         op.key().icounter()
     }
     fn key(&self) -> KeyRef<'_>;
+    fn target(&self) -> Option<ObjId>;
+    fn target_actor(op: &Self) -> Option<ActorIdx> {
+        op.target()?.actor()
+    }
+    fn target_ctr(op: &Self) -> Option<u64> {
+        op.target()?.counter()
+    }
     fn raw_value(&self) -> Option<Cow<'_, [u8]>>; // allocation
     fn meta_value(&self) -> ValueMeta;
     fn insert(op: &Self) -> bool;
@@ -2,8 +2,10 @@ use super::parents::Parents;
 use crate::clock::{Clock, ClockRange};
 use crate::exid::ExId;
 use crate::iter::tools::{MergeIter, SkipIter, SkipWrap};
+use crate::iter::MapRangeItem;
 use crate::marks::{MarkSet, RichTextQueryState};
 use crate::op_set2::op_set::index::Indexes;
+use crate::ordered_map;
 use crate::storage::columns::BadColumnLayout;
 use crate::storage::{columns::compression::Uncompressed, Document, RawColumns};
 use crate::types;
@@ -17,7 +19,7 @@ use super::op::{Op, OpLike, SuccCursors, SuccInsert, TxOp};
 
 use super::columns::Columns;
 
-use super::types::{Action, ActorIdx, KeyRef, MarkData, OpType, ScalarValue};
+use super::types::{Action, ActorIdx, KeyRef, MarkData, OpType, ScalarValue, ValueRef};
 
 use hexane::PackError;
 use itertools::Itertools;
@@ -28,14 +30,18 @@ use std::num::NonZeroUsize;
 use std::ops::{Range, RangeBounds};
 use std::sync::Arc;
 
+mod compact;
 mod found_op;
 mod index;
 mod insert;
 mod mark_index;
 mod marks;
+mod moves;
 mod op_iter;
 mod op_query;
+mod stats;
 mod top_op;
+mod tree;
 mod visible;
 
 pub(crate) use index::{IndexBuilder, MarkOrderValidator, ObjIndex, ObjInfo};
@@ -45,13 +51,14 @@ pub(crate) use crate::iter::{Keys, ListRange, MapRange, SpansInternal};
 pub(crate) use found_op::OpsFoundIter;
 pub(crate) use insert::InsertQuery;
 pub(crate) use mark_index::{MarkIdx, MarkIndexBuilder, MarkIndexColumn};
-pub(crate) use marks::{MarkIter, NoMarkIter};
+pub(crate) use marks::{ListMarkIter, MarkIter, NoMarkIter};
 pub(crate) use op_iter::{
     ActionIter, ActionValueIter, CtrWalker, InsertIter, KeyIter, MarkInfoIter, ObjIdIter, OpIdIter,
-    OpIter, ReadOpError, SuccIterIter, SuccWalker, ValueIter,
+    OpIter, ReadOpError, SuccIterIter, SuccWalker, TargetIter, ValueIter,
 };
 pub(crate) use op_query::{FixCounters, OpQuery, OpQueryTerm};
 pub(crate) use top_op::{TopIter, TopOps};
+pub(crate) use tree::TreeLocations;
 pub(crate) use visible::{VisIter, VisibleOpIter};
 
 pub(crate) type InsertAcc<'a> = hexane::PrefixIter<'a, bool>;
@@ -107,11 +114,15 @@ impl OpSet {
     pub(crate) fn reset_top(&mut self, range: Range<usize>) {
         let top = self.cols.index.top.values().iter_range(range.clone());
         let vis = self.cols.index.visible.iter_range(range.clone());
+        let list = self.is_list_range(&range);
 
         let mut conflicts = vec![];
         let mut expose = None;
         let mut last_t = None;
         for (i, (v, t)) in vis.zip(top).enumerate() {
+            if v && !t && self.is_anchor_at(range.start + i, list) {
+                continue;
+            }
             if t {
                 assert!(v);
                 if let Some(n) = last_t {
@@ -145,6 +156,12 @@ impl OpSet {
     }
 
     pub(crate) fn expose(&mut self, pos: usize) {
+        if self
+            .get(pos)
+            .is_some_and(|op| self.obj_info.is_list_anchor(&op))
+        {
+            return;
+        }
         self.cols.index.top.splice(pos, 1, [true]);
         // Note we alwasy set the exposed widht using the text type, as the text
         // index is only used for text width. Non-text ops are never measured
@@ -205,7 +222,7 @@ impl OpSet {
                     first_top = Some(op.pos);
                 }
             }
-            if vis {
+            if vis && !self.obj_info.is_list_anchor(&op) {
                 last_vis = Some(op.pos);
             }
         }
@@ -234,13 +251,27 @@ impl OpSet {
             if let Some(obj_info) = op.obj_info() {
                 self.obj_info.insert(op.id(), obj_info);
             }
+            match (O::action(op), op.target()) {
+                (Action::MoveObject, Some(target)) => {
+                    self.obj_info.set_moves(&op.obj());
+                    self.obj_info.insert_tree_move(op.id(), target, op.obj());
+                }
+                (Action::Move, _) => {
+                    self.obj_info.set_moves(&op.obj());
+                    if !O::insert(op) {
+                        self.obj_info.insert_elem_move(op.id(), op.obj());
+                    }
+                }
+                _ => {}
+            }
         }
     }
 
     pub(crate) fn splice<O: OpLike>(&mut self, pos: usize, ops: &[O]) -> usize {
-        let added = self.cols.splice(pos, ops, self.text_encoding);
+        // objects first, so marks in a list created by these same ops are recognised
         self.splice_objects(ops);
-        added
+        self.cols
+            .splice(pos, ops, self.text_encoding, &self.obj_info)
     }
 
     pub(crate) fn undo_op(&mut self, op: &TxOp) {
@@ -251,6 +282,11 @@ impl OpSet {
         if op.obj_info().is_some() {
             self.obj_info.remove(op.id());
         }
+        match <TxOp as OpLike>::action(op) {
+            Action::MoveObject => self.obj_info.remove_tree_move(op.id()),
+            Action::Move => self.obj_info.remove_elem_move(op.id(), op.obj()),
+            _ => {}
+        }
         if let Some(range) = &op.reset_range {
             self.reset_top(range.clone());
         }
@@ -351,7 +387,17 @@ impl OpSet {
     }
 
     pub(crate) fn parent_object(&self, child: &ObjId, clock: Option<&Clock>) -> Option<Parent> {
-        let (op, visible) = self.find_op_by_id_and_vis(child.id()?, clock)?;
+        // a moved object is wherever the op which moved it last is
+        let slot = match self.obj_info.is_moved(child) {
+            true => self.tree_locations(clock).get(child).map(|l| l.slot),
+            false => None,
+        };
+        // the op which moved an object is never flagged as the top op of its key in the index
+        // (see `ObjIndex::is_list_anchor`), so its visibility is found the slow way
+        let (op, visible) = match &slot {
+            Some(slot) => self.find_op_by_id_and_vis_slow(slot, clock)?,
+            None => self.find_op_by_id_and_vis(child.id()?, clock)?,
+        };
         let obj = op.obj;
         let typ = self.object_type(&obj)?;
         let prop = match op.key {
@@ -371,13 +417,47 @@ impl OpSet {
             obj: op.obj,
             prop,
             visible,
+            op: op.id,
         })
     }
 
     pub(crate) fn keys<'a>(&'a self, obj: &ObjId, clock: Option<Clock>) -> Keys<'a> {
+        if self.object_type(obj) == Some(ObjType::OrderedMap) {
+            let keys = self
+                .ordered_map_entries(obj, clock)
+                .into_iter()
+                .map(|(item, _)| item.key.into_owned())
+                .collect();
+            return Keys::ordered(keys);
+        }
         Keys::new(self, self.top_ops(obj, clock))
     }
 
+    /// The entries of the ordered map `obj` in order, with their positions (see
+    /// [`crate::ordered_map`])
+    pub(crate) fn ordered_map_entries(
+        &self,
+        obj: &ObjId,
+        clock: Option<Clock>,
+    ) -> Vec<(MapRangeItem<'_>, Option<String>)> {
+        self.sort_ordered_map(MapRange::new(self, self.scope_to_obj(obj), clock))
+    }
+
+    /// Sort `items`, which are the entries of an ordered map along with their position keys
+    fn sort_ordered_map<'a>(
+        &self,
+        items: impl Iterator<Item = MapRangeItem<'a>>,
+    ) -> Vec<(MapRangeItem<'a>, Option<String>)> {
+        ordered_map::sort(
+            items,
+            |item| &item.key,
+            |item| match &item.value {
+                ValueRef::Scalar(value) => ordered_map::position(value),
+                ValueRef::Object(_) => None,
+            },
+        )
+    }
+
     pub(crate) fn spans(&self, obj: &ObjId, clock: Option<Clock>) -> SpansInternal<'_> {
         let range = self.scope_to_obj(obj);
         SpansInternal::new(self, range, clock, self.text_encoding)
@@ -415,6 +495,21 @@ impl OpSet {
             std::ops::Bound::Excluded(s) => scope(s.as_str()).start,
         };
 
+        if self.object_type(obj) == Some(ObjType::OrderedMap) {
+            // only the entries in the range are sorted, along with the position keys, which are
+            // the keys from the prefix up to the next character
+            let positions = scope("\u{0}").start..scope("\u{1}").start;
+            let items = MapRange::new(self, positions, clock.clone()).chain(
+                MapRange::new(self, start..end, clock)
+                    .filter(|item| !ordered_map::is_position_key(&item.key)),
+            );
+            let items = self
+                .sort_ordered_map(items)
+                .into_iter()
+                .map(|(item, _)| item)
+                .collect();
+            return MapRange::ordered(items);
+        }
         MapRange::new(self, start..end, clock)
     }
 
@@ -442,18 +537,28 @@ impl OpSet {
                 self.action_value_iter(range.clone(), clock.as_ref())
                     .map(|(action, value, _)| match (action, &value) {
                         (Action::Set, ScalarValue::Str(s)) => text_encoding.width(s),
-                        (Action::Mark, _) => 0,
+                        (Action::Mark | Action::Move | Action::MoveObject, _) => 0,
                         _ => text_encoding.width("\u{fffc}"),
                     })
                     .sum()
             }
         } else if typ == ObjType::List {
-            let insert = self
-                .cols
-                .insert
-                .iter_range(range.clone())
-                .map(|pv| pv.total());
-            SkipIter::new(insert, vis).dedup().count()
+            if self.has_moves(obj) {
+                return self.moved_list_len(obj, clock.as_ref());
+            }
+            // one top op per element; marks in a list are never top
+            let insert = self.cols.insert.iter_range(range.clone());
+            SkipIter::new(insert, TopIter::new(self, clock, range.clone())).count()
+        } else if typ == ObjType::OrderedMap {
+            self.top_ops(obj, clock)
+                .filter(|op| {
+                    !op.key
+                        .key_str()
+                        .is_some_and(|key| ordered_map::is_position_key(&key))
+                })
+                .count()
+        } else if let Some(map) = self.moved_map(&range, clock.as_ref()) {
+            map.len()
         } else {
             let key = self.cols.key_str.iter_range(range.clone());
             SkipIter::new(key, vis).dedup().count()
@@ -573,6 +678,9 @@ impl OpSet {
         seq_type: SequenceType,
         clock: Option<Clock>,
     ) -> Result<QueryNth, AutomergeError> {
+        if seq_type == SequenceType::List && self.has_moves(obj) {
+            return self.query_moved_insert_at(obj, index, clock.as_ref());
+        }
         if clock.is_none() && index > 0 {
             let index = NonZeroUsize::new(index).unwrap();
             let query = if seq_type == SequenceType::List {
@@ -617,7 +725,11 @@ impl OpSet {
         let range = self.prop_range(obj, key);
         let iter = self.iter_range(&range);
         let end_pos = iter.end_pos();
-        let ops = iter.visible(self, clock).collect::<Vec<_>>();
+        let ops = if self.has_moves(obj) {
+            self.seek_moved_ops_by_map_key(obj, key, clock)
+        } else {
+            iter.visible(self, clock).collect::<Vec<_>>()
+        };
         assert_eq!(end_pos, range.end);
         OpsFound {
             index: 0,
@@ -636,6 +748,9 @@ impl OpSet {
         seq_type: SequenceType,
         clock: Option<&Clock>,
     ) -> OpsFound<'a> {
+        if seq_type == SequenceType::List && self.has_moves(obj) {
+            return self.seek_moved_ops_by_index(obj, index, clock);
+        }
         if clock.is_none() {
             let found = if seq_type == SequenceType::List {
                 self.seek_list_ops_by_index_fast(obj, index)
@@ -764,7 +879,9 @@ impl OpSet {
                 }
                 end_pos = op.pos + 1;
                 range.end = op.pos + 1;
-                if op.succ().len() == 0 && op.action != Action::Mark {
+                if op.succ().len() == 0
+                    && !matches!(op.action, Action::Mark | Action::Move | Action::MoveObject)
+                {
                     ops.push(op);
                 }
             }
@@ -841,6 +958,9 @@ impl OpSet {
         seq_type: SequenceType,
         clock: Option<&Clock>,
     ) -> Option<FoundOpId<'_>> {
+        if seq_type == SequenceType::List && self.has_moves(obj) {
+            return self.seek_moved_list_opid(obj, opid, clock);
+        }
         if clock.is_none() {
             let found = self.seek_list_opid_fast(obj, opid, seq_type);
             debug_assert_eq!(found, self.seek_list_opid_slow(obj, opid, seq_type, clock));
@@ -892,7 +1012,132 @@ impl OpSet {
             }
             index += ops.width(seq_type, self.text_encoding);
         }
-        None
+        // the op is after the last visible element
+        Some(FoundOpId {
+            op,
+            index,
+            visible: false,
+        })
+    }
+
+    /// Like [`Self::seek_list_opid`] for many ids at once, in a single pass over the object.
+    /// The results are in the same order as `ids`.
+    pub(crate) fn seek_list_opids(
+        &self,
+        obj: &ObjId,
+        ids: &[OpId],
+        seq_type: SequenceType,
+        clock: Option<&Clock>,
+    ) -> Vec<Option<FoundOpId<'_>>> {
+        if seq_type == SequenceType::List && self.has_moves(obj) {
+            return self.seek_moved_list_opids(obj, ids, clock);
+        }
+        let obj_range = self.scope_to_obj(obj);
+        let mut targets = ids
+            .iter()
+            .enumerate()
+            .filter_map(|(i, id)| {
+                let pos = self.get_op_id_pos(*id)?;
+                obj_range.contains(&pos).then_some((pos, i))
+            })
+            .collect::<Vec<_>>();
+        targets.sort_unstable();
+        let mut found = ids.iter().map(|_| None).collect::<Vec<_>>();
+        if clock.is_none() {
+            self.seek_list_opids_fast(obj_range.start, &targets, seq_type, &mut found);
+        } else {
+            self.seek_list_opids_slow(obj, &targets, seq_type, clock, &mut found);
+        }
+        #[cfg(feature = "slow_path_assertions")]
+        {
+            let slow = ids
+                .iter()
+                .map(|id| self.seek_list_opid_slow(obj, *id, seq_type, clock))
+                .collect::<Vec<_>>();
+            assert_eq!(found, slow, "fast != slow");
+        }
+        found
+    }
+
+    fn seek_list_opids_fast<'a>(
+        &'a self,
+        obj_start: usize,
+        targets: &[(usize, usize)],
+        seq_type: SequenceType,
+        found: &mut [Option<FoundOpId<'a>>],
+    ) {
+        // the targets are sorted so the index columns are only walked forwards once
+        let mut last: Option<(usize, usize, bool)> = None;
+        match seq_type {
+            SequenceType::List => {
+                let mut iter = self.cols.index.top.iter();
+                iter.advance_to(obj_start);
+                let base = iter.prefix();
+                for &(pos, i) in targets {
+                    let (index, visible) = match last {
+                        Some((last_pos, index, visible)) if last_pos == pos => (index, visible),
+                        _ => {
+                            let Some(seek) = iter.delta_nth(pos - iter.pos()) else {
+                                continue;
+                            };
+                            (seek.pv.prefix() - base, seek.pv.value)
+                        }
+                    };
+                    last = Some((pos, index, visible));
+                    found[i] = self.get(pos).map(|op| FoundOpId { op, index, visible });
+                }
+            }
+            SequenceType::Text => {
+                let mut iter = self.cols.index.text.iter();
+                iter.advance_to(obj_start);
+                let base = iter.prefix();
+                for &(pos, i) in targets {
+                    let (index, visible) = match last {
+                        Some((last_pos, index, visible)) if last_pos == pos => (index, visible),
+                        _ => {
+                            let Some(seek) = iter.delta_nth(pos - iter.pos()) else {
+                                continue;
+                            };
+                            ((seek.pv.prefix() - base) as usize, seek.pv.value.is_some())
+                        }
+                    };
+                    last = Some((pos, index, visible));
+                    found[i] = self.get(pos).map(|op| FoundOpId { op, index, visible });
+                }
+            }
+        }
+    }
+
+    fn seek_list_opids_slow<'a>(
+        &'a self,
+        obj: &ObjId,
+        targets: &[(usize, usize)],
+        seq_type: SequenceType,
+        clock: Option<&Clock>,
+        found: &mut [Option<FoundOpId<'a>>],
+    ) {
+        let mut targets = targets.iter().peekable();
+        let iter = OpsFoundIter::new(self.iter_obj(obj).no_marks(), clock.cloned());
+        let mut index = 0;
+        for ops in iter {
+            while let Some(&(pos, i)) = targets.next_if(|(pos, _)| *pos < ops.end_pos) {
+                found[i] = self.get(pos).map(|op| {
+                    let visible = ops.ops.contains(&op);
+                    FoundOpId { op, index, visible }
+                });
+            }
+            if targets.peek().is_none() {
+                break;
+            }
+            index += ops.width(seq_type, self.text_encoding);
+        }
+        for &(pos, i) in targets {
+            found[i] = self.get(pos).map(|op| FoundOpId {
+                op,
+                index,
+                visible: false,
+            });
+        }
     }
 
     pub(crate) fn action_iter_range(&self, range: &Range<usize>) -> ActionIter<'_> {
@@ -959,6 +1204,18 @@ impl OpSet {
     }
 
     pub(crate) fn calculate_marks_fast(&self, obj: &ObjId) -> Vec<crate::marks::Mark> {
+        self.marks_in_range_fast(obj, 0..usize::MAX)
+    }
+
+    /// The marks on the text `obj` clipped to the sequence positions in `range`
+    ///
+    /// Only the mark boundaries inside `range` are visited: the marks which are already active
+    /// at its start come from the prefix sums of the mark index.
+    pub(crate) fn marks_in_range_fast(
+        &self,
+        obj: &ObjId,
+        range: Range<usize>,
+    ) -> Vec<crate::marks::Mark> {
         use super::op_set::mark_index::MarkIdx;
         use crate::marks::MarkAccumulator;
 
@@ -966,21 +1223,39 @@ impl OpSet {
         if !self.cols.index.mark.has_any_marks() {
             return vec![];
         }
-        let range = self.scope_to_obj(obj);
+        let obj_range = self.scope_to_obj(obj);
         let text = &self.cols.index.text;
+        let base = text.get_prefix(obj_range.start);
+        let end = range
+            .end
+            .min((text.get_prefix(obj_range.end) - base) as usize);
+        if range.start >= end {
+            return vec![];
+        }
+        // the first op at or after `range.start`
+        let start_pos = if range.start == 0 {
+            obj_range.start
+        } else {
+            text.get_index_for_prefix(base + range.start as u64)
+                .clamp(obj_range.start, obj_range.end)
+        };
+        let mut state = if start_pos > obj_range.start {
+            self.cols.index.mark.rich_text_at(start_pos - 1, None)
+        } else {
+            RichTextQueryState::default()
+        };
+        let mut seg: Option<(usize, std::sync::Arc<MarkSet>)> =
+            MarkSet::from_query_state(&state).map(|set| (range.start, set));
+
         // Sequence positions are exclusive prefix sums of the text index (which
         // tracks text widths). Boundaries arrive in ascending position order,
         // so one forward width iterator serves them all in O(1) amortized per
         // boundary.
-        let mut widths = text.iter_range(range.clone());
-        let base = widths.total();
-        let mut widths_at = range.start;
-
-        let mut state = RichTextQueryState::default();
-        let mut seg: Option<(usize, std::sync::Arc<MarkSet>)> = None;
-        let mut iter = self.cols.index.mark.iter_range(range.clone());
-        let mut pos = range.start;
-        while let Some(run) = iter.next_run() {
+        let mut widths = text.iter_range(start_pos..obj_range.end);
+        let mut widths_at = start_pos;
+        let mut iter = self.cols.index.mark.iter_range(start_pos..obj_range.end);
+        let mut pos = start_pos;
+        'walk: while let Some(run) = iter.next_run() {
             let Some(idx) = run.value else {
                 pos += run.count;
                 continue;
@@ -993,6 +1268,9 @@ impl OpSet {
                     .expect("mark boundary lies within the text index");
                 widths_at = pos + 1;
                 let seq = (seek.pv.prefix() - base) as usize;
+                if seq >= end {
+                    break 'walk;
+                }
                 if let Some((start, set)) = seg.take() {
                     if seq > start {
                         acc.add(start, seq - start, &set);
@@ -1015,7 +1293,6 @@ impl OpSet {
             }
         }
         if let Some((start, set)) = seg {
-            let end = (text.get_prefix(range.end) - base) as usize;
             if end > start {
                 acc.add(start, end - start, &set);
             }
@@ -1071,10 +1348,21 @@ impl OpSet {
         let range = self.scope_to_obj(obj);
         let fast = TopOps::new(self, clock.clone(), range);
         #[cfg(feature = "slow_path_assertions")]
-        top_op::assert_matches_slow(self, obj, clock, fast.clone());
+        if !self.has_moves(obj) {
+            top_op::assert_matches_slow(self, obj, clock, fast.clone());
+        }
         fast
     }
 
+    /// The elements of the list `obj` along with the marks which cover them
+    pub(crate) fn list_elements<'a>(
+        &'a self,
+        obj: &ObjId,
+        clock: Option<Clock>,
+    ) -> ListMarkIter<'a, FixCounters<'a, SkipIter<OpIter<'a>, VisIter<'a>>>> {
+        ListMarkIter::new(self.iter_obj(obj).visible(self, clock.as_ref()))
+    }
+
     pub(crate) fn to_string<E: Exportable>(&self, id: E) -> String {
         match id.export() {
             Export::Id(id) => format!("{}@{}", id.counter(), self.actors[id.actor()]),
@@ -1157,6 +1445,24 @@ impl OpSet {
         self.obj_info.object_type(obj)
     }
 
+    /// Whether the ops in `range`, which is scoped to a single object, belong to a list
+    fn is_list_range(&self, range: &Range<usize>) -> bool {
+        !range.is_empty()
+            && self
+                .get(range.start)
+                .is_some_and(|op| self.object_type(&op.obj) == Some(ObjType::List))
+    }
+
+    /// Whether the op at `pos` is an anchor rather than an element, `list` being whether
+    /// it belongs to a list (see [`ObjIndex::is_list_anchor`])
+    fn is_anchor_at(&self, pos: usize, list: bool) -> bool {
+        match self.cols.action.get(pos) {
+            Some(Action::Move | Action::MoveObject) => true,
+            Some(Action::Mark) => list,
+            _ => false,
+        }
+    }
+
     pub(crate) fn object_parent(&self, obj: &ObjId) -> Option<ObjId> {
         self.obj_info.object_parent(obj)
     }
@@ -1321,6 +1627,10 @@ impl OpSet {
             action: ActionIter::new(self.cols.action.iter_range(range.clone())),
             value,
             marks: self.mark_info_iter_range(range),
+            target: TargetIter::new(
+                self.cols.target_actor.iter_range(range.clone()),
+                self.cols.target_ctr.iter_range(range.clone()),
+            ),
             range: range.clone(),
             op_set: self,
         }
@@ -1350,6 +1660,7 @@ impl OpSet {
             action: ActionIter::new(self.cols.action.iter()),
             value: ValueIter::new(self.cols.value_meta.iter(), self.cols.value.iter()),
             marks: MarkInfoIter::new(self.cols.mark_name.iter(), self.cols.expand.iter()),
+            target: TargetIter::new(self.cols.target_actor.iter(), self.cols.target_ctr.iter()),
             range: 0..self.len(),
             op_set: self,
         }
@@ -1392,25 +1703,13 @@ impl OpSet {
     pub(crate) fn rewrite_with_new_actor(&mut self, idx: usize) {
         self.cols.rewrite_with_new_actor(idx);
         self.cols.index.mark.rewrite_with_new_actor(idx);
-        self.obj_info = ObjIndex(
-            self.obj_info
-                .0
-                .iter()
-                .map(|(id, make)| (id.with_new_actor(idx), make.with_new_actor(idx)))
-                .collect(),
-        );
+        self.obj_info = self.obj_info.with_new_actor(idx);
     }
 
     pub(crate) fn remove_actor(&mut self, idx: usize) {
         self.actors.remove(idx);
         self.cols.rewrite_without_actor(idx);
-        self.obj_info = ObjIndex(
-            self.obj_info
-                .0
-                .iter()
-                .filter_map(|(id, make)| Some((id.without_actor(idx)?, make.without_actor(idx)?)))
-                .collect(),
-        );
+        self.obj_info = self.obj_info.without_actor(idx);
     }
 }
 
@@ -1420,6 +1719,8 @@ pub(crate) struct Parent {
     pub(crate) typ: ObjType,
     pub(crate) prop: Prop,
     pub(crate) visible: bool,
+    /// The op which puts the child in `obj`
+    pub(crate) op: OpId,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -1766,7 +2067,9 @@ mod tests {
                 insert: test_op.insert,
                 expand: test_op.expand,
                 mark_name: test_op.mark_name,
+                target: None,
                 conflict: false,
+                slot: None,
                 succ_cursors: SuccCursors {
                     len: group_count as usize,
                     succ_counter: counter_iter.clone(),
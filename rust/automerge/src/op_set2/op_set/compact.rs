@@ -0,0 +1,197 @@
+use std::collections::{HashMap, HashSet};
+
+use super::{IndexBuilder, OpSet, TreeLocations};
+use crate::clock::Clock;
+use crate::op_set2::columns::Columns;
+use crate::op_set2::op::Op;
+use crate::op_set2::types::{Action, ScalarValue};
+use crate::types::{ObjId, OpId};
+
+/// What compaction does with a single op
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Compacted {
+    Keep,
+    /// Keep the op but drop its value, used for deleted list elements whose id must stay around
+    /// for cursors and for ops which reference the element
+    Collapse,
+    Drop,
+}
+
+impl OpSet {
+    /// Remove the content which was deleted before `stable` from the op set
+    ///
+    /// `stable` must be the clock of a causal stability frontier: every op which is added to the
+    /// document in the future must have seen all of the ops it covers. An op which was deleted
+    /// or overwritten by ops in `stable` (and only by ops in `stable`) can then never be seen
+    /// again:
+    ///
+    /// * Map values, list overwrites and the increments of such counters are dropped
+    /// * Deleted list and text elements keep their id and position, so cursors and later
+    ///   insertions which refer to them still work, but lose their value
+    /// * The content of deleted objects is dropped, unless the object contains ops which are not
+    ///   covered by `stable`. The ops which make objects are always kept, as future changes can
+    ///   still write into a deleted object. An object which has been moved is only deleted if it
+    ///   is deleted where it was moved to, and the ops which move objects are always kept
+    ///
+    /// Reading the document at heads which are not a descendant of the frontier is not supported
+    /// after compaction.
+    pub(crate) fn compact(&self, stable: &Clock) -> OpSet {
+        let unstable = self.unstable_objects(stable);
+        let dead_objs = self.dead_objects(stable, &unstable);
+
+        let mut dropped_incs = HashSet::new();
+        let mut ops = Vec::with_capacity(self.len());
+
+        for (op, visible) in self.iter().zip(self.cols.index.visible.iter()) {
+            let deleted = !op.is_inc() && !op.is_mark() && !visible && is_stable(stable, &op);
+            let is_obj = op.obj_info().is_some();
+
+            let compacted = if is_obj || op.action == Action::MoveObject {
+                // a change made by a peer which has seen the delete can still write into the
+                // object, so the ops which make objects are kept even though their content isn't.
+                // Dropping a replaced move would put the object back where it was before it
+                Compacted::Keep
+            } else if dead_objs.contains(&op.obj) {
+                Compacted::Drop
+            } else if op.is_inc() {
+                if dropped_incs.contains(&op.id) {
+                    Compacted::Drop
+                } else {
+                    Compacted::Keep
+                }
+            } else if !deleted {
+                Compacted::Keep
+            } else if op.insert {
+                match op.value {
+                    // the increments of a counter element refer to the element
+                    ScalarValue::Counter(_) | ScalarValue::Null => Compacted::Keep,
+                    _ if op.action == Action::Set => Compacted::Collapse,
+                    _ => Compacted::Keep,
+                }
+            } else {
+                Compacted::Drop
+            };
+
+            if compacted == Compacted::Drop && op.is_counter() {
+                dropped_incs.extend(op.succ());
+            }
+
+            match compacted {
+                Compacted::Keep => ops.push(op),
+                Compacted::Collapse => ops.push(Op {
+                    value: ScalarValue::Null,
+                    ..op
+                }),
+                Compacted::Drop => {}
+            }
+        }
+
+        let mut op_set = OpSet {
+            actors: self.actors.clone(),
+            obj_info: Default::default(),
+            cols: Columns::from_sorted_ops(ops, self.text_encoding),
+            text_encoding: self.text_encoding,
+        };
+        op_set.rebuild_indexes();
+        op_set
+    }
+
+    /// The objects which contain, or have descendants which contain, ops which are not covered
+    /// by `stable` or were deleted by ops which are not covered by `stable`, and the objects
+    /// moved by such ops
+    fn unstable_objects(&self, stable: &Clock) -> HashSet<ObjId> {
+        let mut unstable = HashSet::new();
+        for op in self.iter() {
+            if is_stable(stable, &op) {
+                continue;
+            }
+            for mut obj in [Some(op.obj), op.target] {
+                while let Some(o) = obj {
+                    if !unstable.insert(o) {
+                        break;
+                    }
+                    obj = self.object_parent(&o);
+                }
+            }
+        }
+        unstable
+    }
+
+    /// The objects which are not displayed anywhere and are not in `unstable`
+    ///
+    /// An object is displayed if the op which put it where it is, the op which made it or the
+    /// winning op which moved it, has not been deleted by ops in `stable` and the object it is in
+    /// is displayed.
+    fn dead_objects(&self, stable: &Clock, unstable: &HashSet<ObjId>) -> HashSet<ObjId> {
+        let mut deleted = HashMap::new();
+        for (op, visible) in self.iter().zip(self.cols.index.visible.iter()) {
+            if op.obj_info().is_some() || op.action == Action::MoveObject {
+                deleted.insert(op.id, !visible && is_stable(stable, &op));
+            }
+        }
+        let locations = self.tree_locations(None);
+        let mut live = HashMap::new();
+        deleted
+            .keys()
+            .map(|id| ObjId(*id))
+            .filter(|obj| self.object_type(obj).is_some())
+            .filter(|obj| !self.is_live(*obj, &locations, &deleted, unstable, &mut live))
+            .collect()
+    }
+
+    fn is_live(
+        &self,
+        obj: ObjId,
+        locations: &TreeLocations,
+        deleted: &HashMap<OpId, bool>,
+        unstable: &HashSet<ObjId>,
+        live: &mut HashMap<ObjId, bool>,
+    ) -> bool {
+        let mut path = vec![];
+        let mut next = obj;
+        let result = loop {
+            if next.is_root() || unstable.contains(&next) {
+                break true;
+            }
+            if let Some(result) = live.get(&next) {
+                break *result;
+            }
+            path.push(next);
+            let (placed_by, parent) = match locations.get(&next) {
+                Some(location) => (location.slot, Some(location.dest)),
+                None => (next.0, self.object_parent(&next)),
+            };
+            match parent {
+                Some(parent) if deleted.get(&placed_by) == Some(&false) => next = parent,
+                _ => break false,
+            }
+        };
+        for obj in path {
+            live.insert(obj, result);
+        }
+        result
+    }
+
+    fn rebuild_indexes(&mut self) {
+        let mut index = IndexBuilder::new(self, self.text_encoding);
+        let mut last = None;
+        for op in self.iter() {
+            let next = Some((op.obj, op.elemid_or_key()));
+            if last != next {
+                index.flush();
+                last = next;
+            }
+            index.process_op(&op);
+            let is_counter = op.is_counter();
+            for id in op.succ() {
+                index.process_succ(is_counter, id);
+            }
+        }
+        let (indexes, _) = index.finish();
+        self.set_indexes(indexes);
+    }
+}
+
+fn is_stable(stable: &Clock, op: &Op<'_>) -> bool {
+    stable.covers(&op.id) && op.succ().all(|id| stable.covers(&id))
+}
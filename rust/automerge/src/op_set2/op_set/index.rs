@@ -1,7 +1,9 @@
+use crate::op_set2::op::OpLike;
 use crate::op_set2::op_set::{MarkIndexBuilder, MarkIndexColumn};
+use crate::op_set2::types::Action;
 use crate::op_set2::{ChangeOp, Op, OpBuilder, OpSet};
 use crate::types::{ObjId, ObjType, OpId, SequenceType, TextEncoding};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 
 // TODO : this could be faster and use less memory if
 // hexane::Encoder was used here instead of Vec<>
@@ -15,6 +17,8 @@ pub(crate) struct IndexBuilder {
     marks: Vec<Option<MarkIndexBuilder>>,
     obj_info: ObjIndex,
     last_flush: usize,
+    // for each op of the current group, whether it is a list anchor
+    anchors: Vec<bool>,
     text_encoding: TextEncoding,
     mark_order: MarkOrderValidator,
 }
@@ -75,14 +79,49 @@ impl MarkOrderValidator {
 }
 
 #[derive(Debug, Default, Clone)]
-pub(crate) struct ObjIndex(pub(crate) HashMap<OpId, ObjInfo>);
+pub(crate) struct ObjIndex {
+    objs: HashMap<OpId, ObjInfo>,
+    /// The ops which move an object, see [`OpSet::tree_locations`]
+    tree_moves: BTreeMap<OpId, TreeMove>,
+    /// The number of ops in `tree_moves` which move each object
+    moved_objs: HashMap<ObjId, usize>,
+    /// The number of ops in `tree_moves` which move an object into each object
+    move_dests: HashMap<ObjId, usize>,
+    /// The number of ops in `tree_moves` which move an object made in each object
+    move_sources: HashMap<ObjId, usize>,
+    /// The claims which move an element of a list, by list, see [`OpSet::moved_list`]
+    elem_moves: HashMap<ObjId, BTreeSet<OpId>>,
+}
+
+/// An op which moves the object `target` into `dest`
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(crate) struct TreeMove {
+    pub(crate) target: ObjId,
+    pub(crate) dest: ObjId,
+}
+
+impl TreeMove {
+    fn with_new_actor(self, idx: usize) -> Self {
+        Self {
+            target: self.target.with_new_actor(idx),
+            dest: self.dest.with_new_actor(idx),
+        }
+    }
+
+    fn without_actor(self, idx: usize) -> Option<Self> {
+        Some(Self {
+            target: self.target.without_actor(idx)?,
+            dest: self.dest.without_actor(idx)?,
+        })
+    }
+}
 
 impl ObjIndex {
     pub(crate) fn object_type(&self, obj: &ObjId) -> Option<ObjType> {
         if obj.is_root() {
             Some(ObjType::Map)
         } else {
-            self.0.get(&obj.0).map(|p| p.obj_type)
+            self.objs.get(&obj.0).map(|p| p.obj_type)
         }
     }
 
@@ -90,23 +129,210 @@ impl ObjIndex {
         if obj.is_root() {
             None
         } else {
-            self.0.get(&obj.0).map(|p| p.parent)
+            self.objs.get(&obj.0).map(|p| p.parent)
         }
     }
 
-    pub(crate) fn insert(&mut self, id: OpId, obj_info: ObjInfo) {
-        self.0.insert(id, obj_info);
+    /// Whether `op` is an anchor in a list: a mark, or either half of a move
+    ///
+    /// Anchors in a list are zero width positions between elements rather than elements
+    /// themselves, so they are never the top op of their key and do not count towards the
+    /// length of the list.
+    pub(crate) fn is_list_anchor<O: OpLike>(&self, op: &O) -> bool {
+        match O::action(op) {
+            Action::Move | Action::MoveObject => true,
+            Action::Mark => self.object_type(&op.obj()) == Some(ObjType::List),
+            _ => false,
+        }
+    }
+
+    /// Whether any element of the list `obj` has ever been moved, or any object has ever been
+    /// moved into or out of `obj`
+    pub(crate) fn has_moves(&self, obj: &ObjId) -> bool {
+        if obj.is_root() {
+            // the root is not in `objs`, so it can't be flagged
+            self.has_tree_moves(obj)
+        } else {
+            self.objs.get(&obj.0).is_some_and(|p| p.moves)
+        }
+    }
+
+    pub(crate) fn set_moves(&mut self, obj: &ObjId) {
+        if let Some(info) = self.objs.get_mut(&obj.0) {
+            info.moves = true;
+        }
+    }
+
+    pub(crate) fn insert(&mut self, id: OpId, mut obj_info: ObjInfo) {
+        // the op which moves an object may be seen before the op which makes it
+        if let Some(moves) = self.moved_objs.get(&ObjId(id)) {
+            *self.move_sources.entry(obj_info.parent).or_default() += moves;
+            self.set_moves(&obj_info.parent);
+        }
+        if self.move_dests.contains_key(&ObjId(id)) {
+            obj_info.moves = true;
+        }
+        self.objs.insert(id, obj_info);
     }
 
     pub(crate) fn remove(&mut self, id: OpId) {
-        self.0.remove(&id);
+        if let Some(removed) = self.objs.remove(&id) {
+            if let Some(moves) = self.moved_objs.get(&ObjId(id)) {
+                uncount(&mut self.move_sources, removed.parent, *moves);
+            }
+        }
+    }
+
+    /// Record the op `id`, which moves `target` into `dest`, flagging both `dest` and the object
+    /// `target` was made in as having moves
+    pub(crate) fn insert_tree_move(&mut self, id: OpId, target: ObjId, dest: ObjId) {
+        if self
+            .tree_moves
+            .insert(id, TreeMove { target, dest })
+            .is_some()
+        {
+            return;
+        }
+        *self.moved_objs.entry(target).or_default() += 1;
+        *self.move_dests.entry(dest).or_default() += 1;
+        self.set_moves(&dest);
+        if let Some(parent) = self.object_parent(&target) {
+            *self.move_sources.entry(parent).or_default() += 1;
+            self.set_moves(&parent);
+        }
     }
+
+    pub(crate) fn remove_tree_move(&mut self, id: OpId) {
+        if let Some(removed) = self.tree_moves.remove(&id) {
+            uncount(&mut self.moved_objs, removed.target, 1);
+            uncount(&mut self.move_dests, removed.dest, 1);
+            if let Some(parent) = self.object_parent(&removed.target) {
+                uncount(&mut self.move_sources, parent, 1);
+            }
+        }
+    }
+
+    /// Whether an object has ever been moved into or out of `obj`
+    pub(crate) fn has_tree_moves(&self, obj: &ObjId) -> bool {
+        self.move_dests.contains_key(obj) || self.move_sources.contains_key(obj)
+    }
+
+    /// Record the claim `id`, which moves an element of the list `obj`
+    pub(crate) fn insert_elem_move(&mut self, id: OpId, obj: ObjId) {
+        self.elem_moves.entry(obj).or_default().insert(id);
+        self.set_moves(&obj);
+    }
+
+    pub(crate) fn remove_elem_move(&mut self, id: OpId, obj: ObjId) {
+        if let Some(claims) = self.elem_moves.get_mut(&obj) {
+            claims.remove(&id);
+        }
+    }
+
+    /// The claims which move an element of the list `obj`, including the ones which have since
+    /// been replaced
+    pub(crate) fn elem_moves(&self, obj: &ObjId) -> impl Iterator<Item = &OpId> {
+        self.elem_moves.get(obj).into_iter().flatten()
+    }
+
+    /// The ops which move an object, in the order they are applied in
+    pub(crate) fn tree_moves(&self) -> impl Iterator<Item = (&OpId, &TreeMove)> {
+        self.tree_moves.iter()
+    }
+
+    pub(crate) fn tree_move(&self, id: &OpId) -> Option<&TreeMove> {
+        self.tree_moves.get(id)
+    }
+
+    pub(crate) fn is_moved(&self, obj: &ObjId) -> bool {
+        self.moved_objs.contains_key(obj)
+    }
+
+    pub(crate) fn with_new_actor(&self, idx: usize) -> Self {
+        Self {
+            objs: self
+                .objs
+                .iter()
+                .map(|(id, make)| (id.with_new_actor(idx), make.with_new_actor(idx)))
+                .collect(),
+            tree_moves: self
+                .tree_moves
+                .iter()
+                .map(|(id, m)| (id.with_new_actor(idx), m.with_new_actor(idx)))
+                .collect(),
+            moved_objs: counts_with_new_actor(&self.moved_objs, idx),
+            move_dests: counts_with_new_actor(&self.move_dests, idx),
+            move_sources: counts_with_new_actor(&self.move_sources, idx),
+            elem_moves: self
+                .elem_moves
+                .iter()
+                .map(|(obj, claims)| {
+                    let claims = claims.iter().map(|id| id.with_new_actor(idx)).collect();
+                    (obj.with_new_actor(idx), claims)
+                })
+                .collect(),
+        }
+    }
+
+    pub(crate) fn without_actor(&self, idx: usize) -> Self {
+        Self {
+            objs: self
+                .objs
+                .iter()
+                .filter_map(|(id, make)| Some((id.without_actor(idx)?, make.without_actor(idx)?)))
+                .collect(),
+            tree_moves: self
+                .tree_moves
+                .iter()
+                .filter_map(|(id, m)| Some((id.without_actor(idx)?, m.without_actor(idx)?)))
+                .collect(),
+            moved_objs: counts_without_actor(&self.moved_objs, idx),
+            move_dests: counts_without_actor(&self.move_dests, idx),
+            move_sources: counts_without_actor(&self.move_sources, idx),
+            elem_moves: self
+                .elem_moves
+                .iter()
+                .filter_map(|(obj, claims)| {
+                    let claims = claims
+                        .iter()
+                        .filter_map(|id| id.without_actor(idx))
+                        .collect();
+                    Some((obj.without_actor(idx)?, claims))
+                })
+                .collect(),
+        }
+    }
+}
+
+fn uncount(counts: &mut HashMap<ObjId, usize>, obj: ObjId, n: usize) {
+    if let Some(count) = counts.get_mut(&obj) {
+        *count = count.saturating_sub(n);
+        if *count == 0 {
+            counts.remove(&obj);
+        }
+    }
+}
+
+fn counts_with_new_actor(counts: &HashMap<ObjId, usize>, idx: usize) -> HashMap<ObjId, usize> {
+    counts
+        .iter()
+        .map(|(obj, n)| (obj.with_new_actor(idx), *n))
+        .collect()
+}
+
+fn counts_without_actor(counts: &HashMap<ObjId, usize>, idx: usize) -> HashMap<ObjId, usize> {
+    counts
+        .iter()
+        .filter_map(|(obj, n)| Some((obj.without_actor(idx)?, *n)))
+        .collect()
 }
 
 #[derive(Copy, Clone, Debug)]
 pub(crate) struct ObjInfo {
     pub(crate) parent: ObjId,
     pub(crate) obj_type: ObjType,
+    /// Set once a move op has been seen in this (list) object
+    pub(crate) moves: bool,
 }
 
 impl ObjInfo {
@@ -114,6 +340,7 @@ impl ObjInfo {
         Self {
             parent: self.parent.with_new_actor(idx),
             obj_type: self.obj_type,
+            moves: self.moves,
         }
     }
 
@@ -121,6 +348,7 @@ impl ObjInfo {
         Some(Self {
             parent: self.parent.without_actor(idx)?,
             obj_type: self.obj_type,
+            moves: self.moves,
         })
     }
 }
@@ -129,7 +357,11 @@ impl Op<'_> {
     pub(crate) fn obj_info(&self) -> Option<ObjInfo> {
         let obj_type = ObjType::try_from(self.action).ok()?;
         let parent = self.obj;
-        Some(ObjInfo { parent, obj_type })
+        Some(ObjInfo {
+            parent,
+            obj_type,
+            moves: false,
+        })
     }
 }
 
@@ -143,7 +375,11 @@ impl OpBuilder<'_> {
     pub(crate) fn obj_info(&self) -> Option<ObjInfo> {
         let obj_type = ObjType::try_from(self.action).ok()?;
         let parent = self.obj;
-        Some(ObjInfo { parent, obj_type })
+        Some(ObjInfo {
+            parent,
+            obj_type,
+            moves: false,
+        })
     }
 }
 
@@ -167,6 +403,7 @@ impl IndexBuilder {
             marks: Vec::with_capacity(op_set.len()),
             obj_info: ObjIndex::default(),
             last_flush: 0,
+            anchors: vec![],
             text_encoding: encoding,
             mark_order: MarkOrderValidator::default(),
         }
@@ -174,12 +411,14 @@ impl IndexBuilder {
 
     pub(crate) fn flush(&mut self) {
         let len = self.succ.len();
-        for (delta, succ) in self.succ[self.last_flush..].iter().rev().enumerate() {
-            if *succ == 0 {
+        let group = self.succ[self.last_flush..].iter().zip(&self.anchors);
+        for (delta, (succ, anchor)) in group.rev().enumerate() {
+            if *succ == 0 && !anchor {
                 self.top[len - delta - 1] = true;
                 break;
             }
         }
+        self.anchors.clear();
         self.last_flush = len;
     }
     pub(crate) fn process_op(&mut self, op: &Op<'_>) {
@@ -189,6 +428,20 @@ impl IndexBuilder {
 
         self.succ.push(vis_num(op));
         self.top.push(false);
+        self.anchors.push(self.obj_info.is_list_anchor(op));
+        match (op.action, op.target) {
+            (Action::MoveObject, Some(target)) => {
+                self.obj_info.set_moves(&op.obj);
+                self.obj_info.insert_tree_move(op.id, target, op.obj);
+            }
+            (Action::Move, _) => {
+                self.obj_info.set_moves(&op.obj);
+                if !op.insert {
+                    self.obj_info.insert_elem_move(op.id, op.obj);
+                }
+            }
+            _ => {}
+        }
 
         self.widths
             .push(op.width(SequenceType::Text, self.text_encoding) as u64);
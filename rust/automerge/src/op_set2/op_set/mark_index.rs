@@ -228,6 +228,10 @@ impl MarkIndexColumn {
         self.data.len()
     }
 
+    pub(crate) fn byte_len(&self) -> usize {
+        self.data.byte_len()
+    }
+
     pub(crate) fn iter(&self) -> hexane::Iter<'_, Option<MarkIdx>> {
         self.data.values().iter()
     }
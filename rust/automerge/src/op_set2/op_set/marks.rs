@@ -1,4 +1,5 @@
 use crate::marks::{MarkSet, MarkStateMachine};
+use crate::types::ElemId;
 
 use super::{Action, MarkData, Op, OpQueryTerm};
 
@@ -86,3 +87,48 @@ impl<'a, I: OpQueryTerm<'a> + Clone> OpQueryTerm<'a> for NoMarkIter<'a, I> {
         self.iter.range()
     }
 }
+
+/// One visible op for each element of a list, tracking the marks which cover it
+///
+/// Marks in a list are never top ops so [`TopOps`](super::TopOps) doesn't see them. This walks
+/// every visible op instead, which includes the marks, and skips the conflicting values of an
+/// element after the first.
+#[derive(Clone, Debug)]
+pub(crate) struct ListMarkIter<'a, I: Iterator<Item = Op<'a>> + Clone> {
+    iter: MarkIter<'a, I>,
+    last: Option<ElemId>,
+}
+
+impl<'a, I: Iterator<Item = Op<'a>> + Clone> ListMarkIter<'a, I> {
+    pub(crate) fn new(iter: I) -> Self {
+        Self {
+            iter: MarkIter::new(iter),
+            last: None,
+        }
+    }
+}
+
+impl<'a, I: Iterator<Item = Op<'a>> + Clone> Iterator for ListMarkIter<'a, I> {
+    type Item = Op<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for op in self.iter.by_ref() {
+            let elem = op.cursor().ok();
+            if elem != self.last {
+                self.last = elem;
+                return Some(op);
+            }
+        }
+        None
+    }
+}
+
+impl<'a, I: OpQueryTerm<'a> + Clone> OpQueryTerm<'a> for ListMarkIter<'a, I> {
+    fn get_marks(&self) -> Option<&Arc<MarkSet>> {
+        self.iter.get_marks()
+    }
+
+    fn range(&self) -> Range<usize> {
+        self.iter.range()
+    }
+}
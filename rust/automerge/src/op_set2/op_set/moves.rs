@@ -0,0 +1,733 @@
+//! The view of a list whose elements have been moved
+//!
+//! Moving an element is done with two ops in the same change. The first, the "claim", is a
+//! non-insert [`Action::Move`] keyed by the element's original id, whose preds are the claims it
+//! replaces. The second, the "slot", is an insert [`Action::Move`] made straight after it at the
+//! position the element moves to. The values of an element are always keyed by its original id,
+//! so moving it doesn't change its identity and concurrent puts and increments are preserved.
+//!
+//! The index columns treat both halves of a move as anchors (see
+//! [`ObjIndex::is_list_anchor`](super::ObjIndex::is_list_anchor)) and so describe the list as if
+//! nothing had been moved. Lists which contain moves are instead read through [`OpSet::moved_list`],
+//! which displays an element at the slot of its winning claim, or at its original position if it
+//! has never been moved. The slots of objects moved into a list (see [`super::tree`]) are not part
+//! of this, they are elements like any other.
+//!
+//! Building the whole of [`OpSet::moved_list`] for every read would make each edit of a long list
+//! cost as much as reading all of it, so the current state of a list which no object has been
+//! moved into or out of is read through the index columns instead, with an [`Overlay`] of the
+//! few elements which are displayed somewhere other than where they were inserted.
+
+use super::{FoundOpId, OpSet, OpsFound, QueryNth, TreeLocations};
+use crate::clock::{Clock, ClockRange};
+use crate::iter::{Diff, ListDiffItem};
+use crate::op_set2::op::Op;
+use crate::op_set2::types::{Action, ScalarValue, ValueRef};
+use crate::types::{ElemId, ObjId, ObjType, OpId, HEAD};
+use crate::AutomergeError;
+
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// An element of a list with moves, at the position it is displayed at
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct MovedElem<'a> {
+    /// The op the element is displayed at: its insert op, or the slot of its winning claim
+    pub(crate) slot: OpId,
+    /// The position of `slot`
+    pub(crate) slot_pos: usize,
+    /// The end of the ops keyed by `slot`, which is where inserts after the element go
+    pub(crate) slot_end: usize,
+    /// The original id of the element, which all of its values are keyed by
+    pub(crate) elem: ElemId,
+    /// The visible values of the element, the last one being the winner
+    pub(crate) ops: Vec<Op<'a>>,
+    /// The visible claims on the element
+    pub(crate) claims: Vec<Op<'a>>,
+    /// The range of the ops keyed by `elem`
+    pub(crate) range: Range<usize>,
+}
+
+impl<'a> MovedElem<'a> {
+    pub(crate) fn winner(&self) -> &Op<'a> {
+        self.ops
+            .last()
+            .expect("displayed elements always have a value")
+    }
+
+    pub(crate) fn conflict(&self) -> bool {
+        self.ops.len() > 1
+    }
+
+    fn diff_item(&self, diff: Diff, index: usize) -> ListDiffItem<'a> {
+        let winner = self.winner();
+        ListDiffItem {
+            diff,
+            value: ValueRef::from_action_value(winner.action, winner.value.clone()),
+            inc: 0,
+            index,
+            conflict: self.conflict(),
+            update: false,
+            expose: false,
+            id: winner.id,
+            marks: None,
+            moved_from: None,
+        }
+    }
+}
+
+/// The ops of a list keyed by a single element
+struct Group<'a> {
+    insert: Op<'a>,
+    range: Range<usize>,
+    values: Vec<Op<'a>>,
+    claims: Vec<Op<'a>>,
+}
+
+impl Group<'_> {
+    fn claim(&self) -> Option<OpId> {
+        self.claims.iter().map(|op| op.id).max()
+    }
+}
+
+/// An element which has been moved, at its original position and at the slot it is displayed at
+#[derive(Debug, Clone)]
+struct Relocated {
+    /// The range of the ops keyed by the element
+    range: Range<usize>,
+    slot: OpId,
+    slot_pos: usize,
+    slot_end: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Shift {
+    /// The element inserted here is displayed somewhere else
+    Removed,
+    /// The element at this index of [`Overlay::relocated`] is displayed here
+    Slot(usize),
+}
+
+/// The elements of a list which are displayed somewhere other than where they were inserted
+///
+/// The index columns describe the list as if nothing had been moved, in the current state of a
+/// list which no object has been moved into or out of every element with a top op is displayed
+/// exactly once, so the displayed list is the elements with top ops with each of `relocated`
+/// taken out and put back at its slot.
+struct Overlay {
+    range: Range<usize>,
+    /// The number of top ops before the list
+    base: usize,
+    relocated: Vec<Relocated>,
+    /// Where the displayed list differs from the top ops, in order
+    shifts: Vec<(usize, Shift)>,
+}
+
+/// What is needed to move an element, see [`OpSet::query_move`]
+pub(crate) struct MoveQuery<'a> {
+    pub(crate) elem: ElemId,
+    /// The claims the new claim replaces
+    pub(crate) claims: Vec<Op<'a>>,
+    /// Where the new claim goes
+    pub(crate) claim_pos: usize,
+    /// The element the new slot is inserted after
+    pub(crate) anchor: ElemId,
+    /// Where the new slot goes, before the claim is inserted
+    pub(crate) anchor_pos: usize,
+}
+
+impl OpSet {
+    /// Whether `obj` is a list which has had an element moved
+    pub(crate) fn has_moves(&self, obj: &ObjId) -> bool {
+        self.obj_info.has_moves(obj)
+    }
+
+    /// The object with moves the ops in `range` belong to, if any
+    pub(crate) fn moved_obj(&self, range: &Range<usize>) -> Option<ObjId> {
+        if range.is_empty() {
+            return None;
+        }
+        let obj = self.get(range.start)?.obj;
+        self.has_moves(&obj).then_some(obj)
+    }
+
+    /// The list with moves the ops in `range` belong to, if any
+    pub(crate) fn moved_list_obj(&self, range: &Range<usize>) -> Option<ObjId> {
+        let obj = self.moved_obj(range)?;
+        (self.object_type(&obj) == Some(ObjType::List)).then_some(obj)
+    }
+
+    /// The elements of the list `obj`, in the order they are displayed in at `clock`
+    pub(crate) fn moved_list(&self, obj: &ObjId, clock: Option<&Clock>) -> Vec<MovedElem<'_>> {
+        self.moved_list_at(obj, clock, &self.tree_locations(clock))
+    }
+
+    fn moved_list_at(
+        &self,
+        obj: &ObjId,
+        clock: Option<&Clock>,
+        locations: &TreeLocations,
+    ) -> Vec<MovedElem<'_>> {
+        let mut groups: Vec<Group<'_>> = vec![];
+        let mut claimed = HashMap::new();
+        for mut op in self.iter_obj(obj) {
+            if op.insert {
+                groups.push(Group {
+                    insert: op.clone(),
+                    range: op.pos..op.pos,
+                    values: vec![],
+                    claims: vec![],
+                });
+            }
+            let index = groups.len().saturating_sub(1);
+            let Some(group) = groups.last_mut() else {
+                continue;
+            };
+            group.range.end = op.pos + 1;
+            match op.action {
+                Action::Increment | Action::Mark => {}
+                Action::Move if op.insert => {}
+                Action::Move => {
+                    if op.scope_to_clock(clock) {
+                        claimed.insert(op.id, index);
+                        group.claims.push(op);
+                    }
+                }
+                _ => {
+                    if op.scope_to_clock(clock) {
+                        group.values.extend(self.tree_view(op, locations));
+                    }
+                }
+            }
+        }
+
+        let covered = |id: &OpId| clock.is_none_or(|c| c.covers(id));
+        let mut list = vec![];
+        for group in &groups {
+            let elem = match group.insert.action {
+                Action::Mark => continue,
+                Action::Move => {
+                    if !covered(&group.insert.id) {
+                        continue;
+                    }
+                    // the claim is always the op just before its slot
+                    let claim = group.insert.id.prev();
+                    match claimed.get(&claim).map(|i| &groups[*i]) {
+                        Some(elem) if elem.claim() == Some(claim) => elem,
+                        _ => continue,
+                    }
+                }
+                _ if !group.claims.is_empty() => continue,
+                _ => group,
+            };
+            if elem.values.is_empty() {
+                continue;
+            }
+            list.push(MovedElem {
+                slot: group.insert.id,
+                slot_pos: group.insert.pos,
+                slot_end: group.range.end,
+                elem: ElemId(elem.insert.id),
+                ops: elem.values.clone(),
+                claims: elem.claims.clone(),
+                range: elem.range.clone(),
+            });
+        }
+        list
+    }
+
+    /// The number of elements displayed in the list `obj` at `clock`
+    pub(crate) fn moved_list_len(&self, obj: &ObjId, clock: Option<&Clock>) -> usize {
+        let len = match self.overlay(obj, clock) {
+            Some(overlay) => self.cols.index.top.sum_range(overlay.range),
+            None => self.moved_list(obj, clock).len(),
+        };
+        #[cfg(feature = "slow_path_assertions")]
+        assert_eq!(len, self.moved_list(obj, clock).len(), "fast != slow");
+        len
+    }
+
+    /// The element displayed at `index` of the list `obj` at `clock`
+    fn moved_nth(&self, obj: &ObjId, index: usize, clock: Option<&Clock>) -> Option<MovedElem<'_>> {
+        let elem = match self.overlay(obj, clock) {
+            Some(overlay) => self.overlay_nth(&overlay, index),
+            None => self.moved_list(obj, clock).into_iter().nth(index),
+        };
+        #[cfg(feature = "slow_path_assertions")]
+        assert_eq!(
+            elem,
+            self.moved_list(obj, clock).into_iter().nth(index),
+            "fast != slow"
+        );
+        elem
+    }
+
+    pub(crate) fn seek_moved_ops_by_index(
+        &self,
+        obj: &ObjId,
+        index: usize,
+        clock: Option<&Clock>,
+    ) -> OpsFound<'_> {
+        match self.moved_nth(obj, index, clock) {
+            Some(elem) => OpsFound {
+                index,
+                ops: elem.ops,
+                end_pos: elem.range.end,
+                range: elem.range,
+            },
+            None => {
+                let end = self.scope_to_obj(obj).end;
+                OpsFound {
+                    index,
+                    ops: vec![],
+                    end_pos: end,
+                    range: end..end,
+                }
+            }
+        }
+    }
+
+    pub(crate) fn query_moved_insert_at(
+        &self,
+        obj: &ObjId,
+        index: usize,
+        clock: Option<&Clock>,
+    ) -> Result<QueryNth, AutomergeError> {
+        let (elemid, pos) = match index.checked_sub(1) {
+            None => (HEAD, self.scope_to_obj(obj).start),
+            Some(prev) => {
+                let prev = self
+                    .moved_nth(obj, prev, clock)
+                    .ok_or(AutomergeError::InvalidIndex(index))?;
+                (ElemId(prev.slot), prev.slot_end)
+            }
+        };
+        Ok(QueryNth {
+            marks: None,
+            pos,
+            index,
+            elemid,
+        })
+    }
+
+    /// Everything needed to move the element at `from` to `to`, where `to` is its index after
+    /// the move
+    pub(crate) fn query_move(
+        &self,
+        obj: &ObjId,
+        from: usize,
+        to: usize,
+        clock: Option<&Clock>,
+    ) -> Result<MoveQuery<'_>, AutomergeError> {
+        let elem = self
+            .moved_nth(obj, from, clock)
+            .ok_or(AutomergeError::InvalidIndex(from))?;
+        if to >= self.moved_list_len(obj, clock) {
+            return Err(AutomergeError::InvalidIndex(to));
+        }
+        // the element goes after the one before `to` once it has been taken out of the list
+        let (anchor, anchor_pos) = match to.checked_sub(1) {
+            None => (HEAD, self.scope_to_obj(obj).start),
+            Some(prev) => {
+                let prev = if prev < from { prev } else { prev + 1 };
+                let prev = self
+                    .moved_nth(obj, prev, clock)
+                    .ok_or(AutomergeError::InvalidIndex(to))?;
+                (ElemId(prev.slot), prev.slot_end)
+            }
+        };
+        Ok(MoveQuery {
+            elem: elem.elem,
+            claims: elem.claims,
+            claim_pos: elem.range.end,
+            anchor,
+            anchor_pos,
+        })
+    }
+
+    /// Like [`OpSet::seek_list_opid`] for a list with moves
+    pub(crate) fn seek_moved_list_opid(
+        &self,
+        obj: &ObjId,
+        id: OpId,
+        clock: Option<&Clock>,
+    ) -> Option<FoundOpId<'_>> {
+        self.seek_moved_list_opids(obj, &[id], clock)
+            .pop()
+            .flatten()
+    }
+
+    pub(crate) fn seek_moved_list_opids(
+        &self,
+        obj: &ObjId,
+        ids: &[OpId],
+        clock: Option<&Clock>,
+    ) -> Vec<Option<FoundOpId<'_>>> {
+        let found = match self.overlay(obj, clock) {
+            Some(overlay) => ids
+                .iter()
+                .map(|id| self.find_in_overlay(&overlay, *id))
+                .collect(),
+            None => {
+                let list = self.moved_list(obj, clock);
+                ids.iter()
+                    .map(|id| self.find_in_moved_list(obj, &list, *id))
+                    .collect::<Vec<_>>()
+            }
+        };
+        #[cfg(feature = "slow_path_assertions")]
+        {
+            let list = self.moved_list(obj, clock);
+            let slow = ids
+                .iter()
+                .map(|id| self.find_in_moved_list(obj, &list, *id))
+                .collect::<Vec<_>>();
+            assert_eq!(found, slow, "fast != slow");
+        }
+        found
+    }
+
+    fn find_in_moved_list<'a>(
+        &'a self,
+        obj: &ObjId,
+        list: &[MovedElem<'a>],
+        id: OpId,
+    ) -> Option<FoundOpId<'a>> {
+        let pos = self.get_op_id_pos(id)?;
+        if !self.scope_to_obj(obj).contains(&pos) {
+            return None;
+        }
+        let op = self.get(pos)?;
+        if let Some(index) = list.iter().position(|e| e.range.contains(&pos)) {
+            let visible = list[index].ops.iter().any(|o| o.stored_id() == id);
+            return Some(FoundOpId { op, index, visible });
+        }
+        // not displayed, so it is at the position of the first element after it
+        let index = list.iter().filter(|e| e.slot_pos < pos).count();
+        Some(FoundOpId {
+            op,
+            index,
+            visible: false,
+        })
+    }
+
+    /// The [`Overlay`] of `obj`, if it can be read through one at `clock`
+    fn overlay(&self, obj: &ObjId, clock: Option<&Clock>) -> Option<Overlay> {
+        if clock.is_some() || self.obj_info.has_tree_moves(obj) {
+            return None;
+        }
+        let range = self.scope_to_obj(obj);
+        // the winning claim of each element, replaced claims are not visible
+        let mut winners = HashMap::new();
+        for id in self.obj_info.elem_moves(obj) {
+            let Some(claim) = self.get_op_id_pos(*id).and_then(|pos| self.get(pos)) else {
+                continue;
+            };
+            if !claim.visible() {
+                continue;
+            }
+            let Ok(elem) = claim.cursor() else {
+                continue;
+            };
+            let winner = winners.entry(elem).or_insert(*id);
+            *winner = (*winner).max(*id);
+        }
+        let mut relocated = Vec::with_capacity(winners.len());
+        for (elem, claim) in winners {
+            let elem_range = self.list_register_at_pos(self.get_op_id_pos(elem.0)?, range.clone());
+            // an element without a value is not displayed anywhere
+            if self.cols.index.top.sum_range(elem_range.clone()) == 0 {
+                continue;
+            }
+            // the slot is always the op just after its claim
+            let slot = claim.next();
+            let slot_pos = self.get_op_id_pos(slot)?;
+            let slot_end = self.list_register_at_pos(slot_pos, range.clone()).end;
+            relocated.push(Relocated {
+                range: elem_range,
+                slot,
+                slot_pos,
+                slot_end,
+            });
+        }
+        let mut shifts = relocated
+            .iter()
+            .enumerate()
+            .flat_map(|(i, r)| {
+                [
+                    (r.range.start, Shift::Removed),
+                    (r.slot_pos, Shift::Slot(i)),
+                ]
+            })
+            .collect::<Vec<_>>();
+        shifts.sort_unstable_by_key(|(pos, _)| *pos);
+        Some(Overlay {
+            base: self.cols.index.top.get_prefix(range.start),
+            range,
+            relocated,
+            shifts,
+        })
+    }
+
+    /// The number of elements displayed before `pos`, which is not inside a displayed element
+    fn overlay_index(&self, overlay: &Overlay, pos: usize) -> usize {
+        let (added, removed) = overlay.shifts.iter().take_while(|(p, _)| *p < pos).fold(
+            (0, 0),
+            |(added, removed), (_, shift)| match shift {
+                Shift::Slot(_) => (added + 1, removed),
+                Shift::Removed => (added, removed + 1),
+            },
+        );
+        self.cols.index.top.get_prefix(pos) - overlay.base + added - removed
+    }
+
+    fn overlay_nth(&self, overlay: &Overlay, index: usize) -> Option<MovedElem<'_>> {
+        let (mut added, mut removed) = (0, 0);
+        for (pos, shift) in &overlay.shifts {
+            let before = self.cols.index.top.get_prefix(*pos) - overlay.base + added - removed;
+            if index < before {
+                break;
+            }
+            match shift {
+                Shift::Slot(i) if index == before => {
+                    let r = &overlay.relocated[*i];
+                    return self.moved_elem(r.slot, r.slot_pos, r.slot_end, r.range.clone());
+                }
+                Shift::Slot(_) => added += 1,
+                Shift::Removed => removed += 1,
+            }
+        }
+        // an element displayed where it was inserted
+        let mut top = self.cols.index.top.iter_range(overlay.range.clone());
+        let found = top.advance_prefix(index + removed - added)?;
+        let range = self.list_register_at_pos(found.pos, overlay.range.clone());
+        let insert = self.get(range.start)?;
+        self.moved_elem(insert.id, range.start, range.end, range)
+    }
+
+    /// The element whose ops are in `range`, displayed at `slot`
+    fn moved_elem(
+        &self,
+        slot: OpId,
+        slot_pos: usize,
+        slot_end: usize,
+        range: Range<usize>,
+    ) -> Option<MovedElem<'_>> {
+        let mut elem = MovedElem {
+            slot,
+            slot_pos,
+            slot_end,
+            elem: ElemId(self.get(range.start)?.id),
+            ops: vec![],
+            claims: vec![],
+            range: range.clone(),
+        };
+        for mut op in self.iter_range(&range) {
+            match op.action {
+                Action::Increment | Action::Mark => {}
+                Action::Move if op.insert => {}
+                Action::Move => {
+                    if op.scope_to_clock(None) {
+                        elem.claims.push(op);
+                    }
+                }
+                _ => {
+                    if op.scope_to_clock(None) {
+                        elem.ops.push(op);
+                    }
+                }
+            }
+        }
+        Some(elem)
+    }
+
+    fn find_in_overlay(&self, overlay: &Overlay, id: OpId) -> Option<FoundOpId<'_>> {
+        let pos = self.get_op_id_pos(id)?;
+        if !overlay.range.contains(&pos) {
+            return None;
+        }
+        let op = self.get(pos)?;
+        let range = self.list_register_at_pos(pos, overlay.range.clone());
+        if self.cols.index.top.sum_range(range.clone()) == 0 {
+            // not displayed, so it is at the position of the first element after it
+            let index = self.overlay_index(overlay, pos);
+            return Some(FoundOpId {
+                op,
+                index,
+                visible: false,
+            });
+        }
+        let slot_pos = overlay
+            .relocated
+            .iter()
+            .find(|r| r.range == range)
+            .map_or(range.start, |r| r.slot_pos);
+        let index = self.overlay_index(overlay, slot_pos);
+        let visible = !matches!(
+            op.action,
+            Action::Increment | Action::Mark | Action::Move | Action::MoveObject
+        ) && op.clone().scope_to_clock(None);
+        Some(FoundOpId { op, index, visible })
+    }
+
+    /// The [`ListDiffItem`]s for the list with moves the ops in `range` belong to, if there is one
+    pub(crate) fn moved_list_diff(
+        &self,
+        range: &Range<usize>,
+        clock: &ClockRange,
+    ) -> Option<Vec<ListDiffItem<'_>>> {
+        let obj = self.moved_list_obj(range)?;
+        match clock {
+            ClockRange::Current(clock) => Some(
+                self.moved_list(&obj, clock.as_ref())
+                    .iter()
+                    .enumerate()
+                    .map(|(index, elem)| elem.diff_item(Diff::Add, index))
+                    .collect(),
+            ),
+            ClockRange::Diff(before, after) => {
+                let (old_locations, new_locations) = self.tree_diff_locations(clock);
+                let old = self.moved_list_at(&obj, Some(before), &old_locations);
+                let new = self.moved_list_at(&obj, Some(after), &new_locations);
+                Some(diff_moved_lists(&old, &new, clock))
+            }
+        }
+    }
+}
+
+/// Turn `old` into `new`: first delete the elements which are gone, then move the elements which
+/// are not on the longest run of elements that kept their order, and finally insert the new
+/// elements and update the ones whose value changed
+fn diff_moved_lists<'a>(
+    old: &[MovedElem<'a>],
+    new: &[MovedElem<'a>],
+    clock: &ClockRange,
+) -> Vec<ListDiffItem<'a>> {
+    let old_at: HashMap<_, _> = old.iter().enumerate().map(|(i, e)| (e.elem, i)).collect();
+    let new_at: HashMap<_, _> = new.iter().enumerate().map(|(i, e)| (e.elem, i)).collect();
+    let mut items = vec![];
+
+    // from the back, so the index of every deleted element is its index in `old`
+    for (index, elem) in old.iter().enumerate().rev() {
+        if !new_at.contains_key(&elem.elem) {
+            items.push(elem.diff_item(Diff::Del, index));
+        }
+    }
+
+    let mut order = old
+        .iter()
+        .map(|e| e.elem)
+        .filter(|e| new_at.contains_key(e))
+        .collect::<Vec<_>>();
+    let common = new
+        .iter()
+        .map(|e| e.elem)
+        .filter(|e| old_at.contains_key(e))
+        .collect::<Vec<_>>();
+    let rank: HashMap<_, _> = order.iter().enumerate().map(|(i, e)| (*e, i)).collect();
+    let stay = longest_increasing(&common.iter().map(|e| rank[e]).collect::<Vec<_>>());
+    // each element is moved to just after the element before it in `new`, which has either not
+    // moved or already been moved
+    for (n, elem) in common.iter().enumerate() {
+        if stay[n] {
+            continue;
+        }
+        let from = order.iter().position(|e| e == elem).unwrap();
+        order.remove(from);
+        let to = match n.checked_sub(1) {
+            Some(prev) => order.iter().position(|e| *e == common[prev]).unwrap() + 1,
+            None => 0,
+        };
+        order.insert(to, *elem);
+        let mut item = new[new_at[elem]].diff_item(Diff::Same, to);
+        item.moved_from = Some(from);
+        item.conflict = false;
+        items.push(item);
+    }
+
+    for (index, elem) in new.iter().enumerate() {
+        let Some(before) = old_at.get(&elem.elem).map(|i| &old[*i]) else {
+            let mut item = elem.diff_item(Diff::Add, index);
+            item.expose = clock.predates(&item.id);
+            items.push(item);
+            continue;
+        };
+        let (winner, old_winner) = (elem.winner(), before.winner());
+        let mut item = elem.diff_item(Diff::Same, index);
+        if winner.id != old_winner.id {
+            item.diff = Diff::Add;
+            item.update = true;
+            item.expose = clock.predates(&winner.id);
+        } else if before.conflict() && !elem.conflict() {
+            // the value is the same, but a put is needed to clear its conflict flag
+            item.diff = Diff::Add;
+            item.update = true;
+            item.expose = true;
+        } else {
+            if let (ScalarValue::Counter(old), ScalarValue::Counter(new)) =
+                (&old_winner.value, &winner.value)
+            {
+                item.inc = new - old;
+            }
+            item.conflict = elem.conflict() && !before.conflict();
+        }
+        items.push(item);
+    }
+    items
+}
+
+/// Which of `seq` are on a longest strictly increasing subsequence of it
+fn longest_increasing(seq: &[usize]) -> Vec<bool> {
+    // tails[k] is the index in `seq` of the smallest tail of an increasing run of length k + 1
+    let mut tails: Vec<usize> = vec![];
+    let mut prev = vec![None; seq.len()];
+    for (i, n) in seq.iter().enumerate() {
+        let k = tails.partition_point(|t| seq[*t] < *n);
+        if k > 0 {
+            prev[i] = Some(tails[k - 1]);
+        }
+        if k == tails.len() {
+            tails.push(i);
+        } else {
+            tails[k] = i;
+        }
+    }
+    let mut on_run = vec![false; seq.len()];
+    let mut next = tails.last().copied();
+    while let Some(i) = next {
+        on_run[i] = true;
+        next = prev[i];
+    }
+    on_run
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{transaction::Transactable, AutoCommit, ObjType, ReadDoc, ROOT};
+
+    #[test]
+    fn current_reads_of_a_list_with_moves_go_through_the_index() {
+        // building the whole moved list for every read made each append as slow as reading the
+        // list, so a few thousand appends took minutes
+        let mut doc = AutoCommit::new();
+        let list = doc.put_object(ROOT, "list", ObjType::List).unwrap();
+        for i in 0..10 {
+            doc.insert(&list, i, i as i64).unwrap();
+        }
+        doc.move_element(&list, 0, 5).unwrap();
+        let heads = doc.get_heads();
+        let obj = doc.doc.exid_to_obj(&list).unwrap().id;
+        for i in 0..10 {
+            assert!(doc.doc.ops().overlay(&obj, None).is_some());
+            let len = doc.length(&list);
+            doc.insert(&list, len, i).unwrap();
+            assert_eq!(doc.get(&list, len).unwrap().unwrap().0.to_i64(), Some(i));
+        }
+
+        doc.commit();
+
+        // only reads at older heads build the whole list
+        let clock = doc.doc.clock_at(&heads);
+        assert!(doc.doc.ops().overlay(&obj, clock.as_ref()).is_none());
+    }
+}
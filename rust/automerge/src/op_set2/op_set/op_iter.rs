@@ -27,6 +27,7 @@ pub(crate) struct OpIter<'a> {
     pub(super) action: ActionIter<'a>,
     pub(super) value: ValueIter<'a>,
     pub(super) marks: MarkInfoIter<'a>,
+    pub(super) target: TargetIter<'a>,
     pub(super) range: Range<usize>,
     pub(super) op_set: &'a OpSet,
 }
@@ -41,6 +42,7 @@ pub(crate) struct OpIterState {
     action: ActionIterState,
     value: ValueIterState,
     marks: MarkInfoIterState,
+    target: TargetIterState,
     range: Range<usize>,
 }
 
@@ -57,6 +59,7 @@ impl OpIterState {
             action: self.action.try_resume(op_set)?,
             value: self.value.try_resume(op_set)?,
             marks: self.marks.try_resume(op_set)?,
+            target: self.target.try_resume(op_set)?,
             range: self.range.clone(),
             op_set,
         })
@@ -106,6 +109,7 @@ impl<'a> OpIter<'a> {
         let obj = self.obj.try_next()?;
         let value = self.value.try_next()?;
         let (mark_name, expand) = self.marks.try_next()?;
+        let target = self.target.try_next()?;
         let succ_cursors = self.succ.try_next()?;
         let pos = self.pos;
         let conflict = false;
@@ -121,7 +125,9 @@ impl<'a> OpIter<'a> {
             value,
             expand,
             mark_name,
+            target,
             succ_cursors,
+            slot: None,
         }))
     }
 
@@ -135,6 +141,7 @@ impl<'a> OpIter<'a> {
         let obj = self.obj.try_nth(n)?;
         let value = self.value.try_nth(n)?;
         let (mark_name, expand) = self.marks.try_nth(n)?;
+        let target = self.target.try_nth(n)?;
         let succ_cursors = self.succ.try_nth(n)?;
         let pos = self.pos + n;
         let conflict = false;
@@ -150,7 +157,9 @@ impl<'a> OpIter<'a> {
             value,
             expand,
             mark_name,
+            target,
             succ_cursors,
+            slot: None,
         }))
     }
 
@@ -166,6 +175,7 @@ impl<'a> OpIter<'a> {
             action: self.action.suspend(),
             value: self.value.suspend(),
             marks: self.marks.suspend(),
+            target: self.target.suspend(),
             range: self.range.clone(),
         }
     }
@@ -763,6 +773,86 @@ impl<'a> Iterator for MarkInfoIter<'a> {
     }
 }
 
+#[derive(Clone, Debug, Default)]
+pub(crate) struct TargetIter<'a> {
+    target_actor: hexane::Iter<'a, Option<ActorIdx>>,
+    target_ctr: hexane::Iter<'a, Option<u32>>,
+}
+
+pub(crate) struct TargetIterState {
+    target_actor: hexane::column::IterState,
+    target_ctr: hexane::column::IterState,
+}
+
+impl TargetIterState {
+    fn try_resume<'a>(&self, op_set: &'a OpSet) -> Result<TargetIter<'a>, AutomergeError> {
+        Ok(TargetIter {
+            target_actor: self
+                .target_actor
+                .try_resume(&op_set.cols.target_actor)
+                .map_err(AutomergeError::encoding)?,
+            target_ctr: self
+                .target_ctr
+                .try_resume(&op_set.cols.target_ctr)
+                .map_err(AutomergeError::encoding)?,
+        })
+    }
+}
+
+impl<'a> TargetIter<'a> {
+    pub(crate) fn new(
+        target_actor: hexane::Iter<'a, Option<ActorIdx>>,
+        target_ctr: hexane::Iter<'a, Option<u32>>,
+    ) -> Self {
+        Self {
+            target_actor,
+            target_ctr,
+        }
+    }
+
+    fn load(actor: Option<ActorIdx>, ctr: Option<u32>) -> Result<Option<ObjId>, ReadOpError> {
+        match (actor, ctr) {
+            (None, None) => Ok(None),
+            (Some(actor), Some(ctr)) if ctr > 0 => Ok(Some(ObjId(OpId::new(
+                ctr as u64,
+                u64::from(actor) as usize,
+            )))),
+            _ => Err(ReadOpError::InvalidOpId("invalid move target".to_string())),
+        }
+    }
+
+    pub(crate) fn try_next(&mut self) -> Result<Option<ObjId>, ReadOpError> {
+        let actor = self
+            .target_actor
+            .next()
+            .ok_or(ReadOpError::MissingValue("target_actor"))?;
+        let ctr = self
+            .target_ctr
+            .next()
+            .ok_or(ReadOpError::MissingValue("target_ctr"))?;
+        Self::load(actor, ctr)
+    }
+
+    pub(crate) fn try_nth(&mut self, n: usize) -> Result<Option<ObjId>, ReadOpError> {
+        let actor = self
+            .target_actor
+            .nth(n)
+            .ok_or(ReadOpError::MissingValue("target_actor"))?;
+        let ctr = self
+            .target_ctr
+            .nth(n)
+            .ok_or(ReadOpError::MissingValue("target_ctr"))?;
+        Self::load(actor, ctr)
+    }
+
+    fn suspend(&self) -> TargetIterState {
+        TargetIterState {
+            target_actor: self.target_actor.suspend(),
+            target_ctr: self.target_ctr.suspend(),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Default)]
 pub(crate) struct ActionValueIter<'a> {
     action: ActionIter<'a>,
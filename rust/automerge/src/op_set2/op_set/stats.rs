@@ -0,0 +1,75 @@
+use std::collections::HashMap;
+
+use super::OpSet;
+use crate::types::{ObjId, ObjType};
+
+/// The ops stored in a single object
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ObjOpCounts {
+    pub(crate) obj: ObjId,
+    pub(crate) obj_type: ObjType,
+    pub(crate) num_ops: usize,
+    pub(crate) live_ops: usize,
+    pub(crate) tombstones: usize,
+    pub(crate) marks: usize,
+}
+
+impl ObjOpCounts {
+    fn new(obj: ObjId, obj_type: ObjType) -> Self {
+        Self {
+            obj,
+            obj_type,
+            num_ops: 0,
+            live_ops: 0,
+            tombstones: 0,
+            marks: 0,
+        }
+    }
+}
+
+impl OpSet {
+    /// Count the ops in every object in the document, in the order the objects are stored
+    pub(crate) fn obj_op_counts(&self) -> Vec<ObjOpCounts> {
+        let mut counts = vec![ObjOpCounts::new(ObjId::root(), ObjType::Map)];
+        let mut index = HashMap::new();
+        index.insert(ObjId::root(), 0);
+        for (op, visible) in self.iter().zip(self.cols.index.visible.iter()) {
+            if let Some(info) = op.obj_info() {
+                index.insert(ObjId(op.id), counts.len());
+                counts.push(ObjOpCounts::new(ObjId(op.id), info.obj_type));
+            }
+            // objects are created before any of the ops in them so the object is always known
+            let Some(obj) = index.get(&op.obj).map(|i| &mut counts[*i]) else {
+                continue;
+            };
+            obj.num_ops += 1;
+            if op.is_mark() {
+                // a mark is a begin op with the name of the mark and an end op
+                if op.mark_name.is_some() {
+                    obj.marks += 1;
+                }
+            } else if op.is_inc() {
+                // increments are neither live nor deleted, they are folded into their counter
+            } else if visible {
+                obj.live_ops += 1;
+            } else {
+                obj.tombstones += 1;
+            }
+        }
+        counts
+    }
+
+    /// The number of ops created by each actor, indexed by actor
+    pub(crate) fn ops_per_actor(&self) -> Vec<usize> {
+        let mut counts = vec![0; self.actors.len()];
+        for op in self.iter() {
+            counts[op.id.actor()] += 1;
+        }
+        counts
+    }
+
+    /// The number of bytes of encoded data held in memory by each column of the op set
+    pub(crate) fn column_byte_lens(&self) -> Vec<(&'static str, usize)> {
+        self.cols.byte_lens()
+    }
+}
@@ -20,12 +20,31 @@ pub(crate) struct TopOps<'a> {
     inner: FixCounters<'a, SkipIter<OpIter<'a>, TopIter<'a>>>,
     visible: VisIter<'a>,
     visible_pos: usize,
+    // set for a list, whose marks are visible but never top and so are not conflicts
+    list: Option<&'a OpSet>,
+    // the winners of an object with moves, whose top ops are not in the order they are displayed
+    moved: Option<std::vec::IntoIter<super::Op<'a>>>,
 }
 
 impl<'a> TopOps<'a> {
     pub(crate) fn new(op_set: &'a OpSet, clock: Option<Clock>, range: Range<usize>) -> Self {
         let visible_pos = range.start;
         let visible = VisIter::new(op_set, clock.as_ref(), range.clone());
+        let list = op_set.is_list_range(&range).then_some(op_set);
+        let moved = match op_set.moved_map(&range, clock.as_ref()) {
+            Some(map) => Some(map.into_iter()),
+            None => op_set.moved_list_obj(&range).map(|obj| {
+                let list = op_set.moved_list(&obj, clock.as_ref());
+                list.into_iter()
+                    .map(|elem| {
+                        let mut op = elem.winner().clone();
+                        op.conflict = elem.conflict();
+                        op
+                    })
+                    .collect::<Vec<_>>()
+                    .into_iter()
+            }),
+        };
         let iter = SkipIter::new(
             op_set.iter_range(&range),
             TopIter::new(op_set, clock.clone(), range),
@@ -35,6 +54,8 @@ impl<'a> TopOps<'a> {
             inner,
             visible,
             visible_pos,
+            list,
+            moved,
         }
     }
 }
@@ -43,6 +64,9 @@ impl<'a> Iterator for TopOps<'a> {
     type Item = super::Op<'a>;
 
     fn next(&mut self) -> Option<Self::Item> {
+        if let Some(moved) = &mut self.moved {
+            return moved.next();
+        }
         let mut op = self.inner.next()?;
         let mut conflict = false;
         loop {
@@ -57,7 +81,12 @@ impl<'a> Iterator for TopOps<'a> {
             if pos == op.pos {
                 break;
             }
-            conflict = true;
+            if !self
+                .list
+                .is_some_and(|op_set| op_set.is_anchor_at(pos, true))
+            {
+                conflict = true;
+            }
         }
         op.conflict = conflict;
         Some(op)
@@ -83,7 +112,10 @@ pub(super) fn assert_matches_slow(
 ) {
     use super::OpQuery;
 
-    let mut slow = SlowTopOpIter::new(op_set.iter_obj(obj).visible_slow(clock));
+    let list = op_set.object_type(obj) == Some(crate::types::ObjType::List);
+    let mut slow = SlowTopOpIter::new(op_set.iter_obj(obj).visible_slow(clock).filter(|op| {
+        !(list && matches!(op.action, Action::Mark | Action::Move | Action::MoveObject))
+    }));
     let mut index = 0;
     loop {
         match (fast.next(), slow.next()) {
@@ -270,6 +302,8 @@ impl Iterator for TopIter<'_> {
 struct ScanTopIter<'a> {
     pos: usize,
     clock: Clock,
+    // anchors in a list are never top (see `ObjIndex::is_list_anchor`)
+    list: bool,
     key_str: hexane::Iter<'a, Option<String>>,
     id: OpIdIter<'a>,
     insert: InsertIter<'a>,
@@ -279,9 +313,11 @@ struct ScanTopIter<'a> {
 
 impl<'a> ScanTopIter<'a> {
     fn new(op_set: &'a OpSet, clock: Clock, range: &Range<usize>) -> Self {
+        let list = op_set.is_list_range(range);
         Self {
             pos: range.start,
             clock,
+            list,
             key_str: op_set.key_str_iter_range(range),
             id: op_set.id_iter_range(range),
             insert: op_set.insert_iter_range(range),
@@ -299,7 +335,8 @@ impl<'a> ScanTopIter<'a> {
         action: Action,
         succ: SuccCursors<'a>,
     ) -> ScanTopRow<'a> {
-        let visible = is_visible(id, action, succ, &self.clock);
+        let visible = is_visible(id, action, succ, &self.clock)
+            && !(self.list && matches!(action, Action::Mark | Action::Move | Action::MoveObject));
         ScanTopRow {
             pos,
             insert,
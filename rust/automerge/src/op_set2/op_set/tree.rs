@@ -0,0 +1,360 @@
+//! The view of a document whose objects have been moved
+//!
+//! Moving an object is done with a single [`Action::MoveObject`] op in the object it is moved into,
+//! whose target columns hold the id of the moved object. In a map it is keyed by the key the object
+//! is moved to and its preds are the values it replaces, like a put. In a list it is an insert at the
+//! index the object is moved to. This op is the "slot" of the move.
+//!
+//! The moves of all objects are applied in the order of their ids, each one replacing the location
+//! of the object it moves. A move into the object itself or into one of its descendants would make
+//! a cycle and is skipped, which is the algorithm of Kleppmann et al. "A highly-available move
+//! operation for replicated trees". As the moves are always replayed from the start, the result
+//! does not depend on the order they are received in.
+//!
+//! Every object which has been moved into, and every object a moved object was made in, is
+//! flagged as having moves (see [`ObjIndex::has_moves`](super::ObjIndex::has_moves)) and is read
+//! through [`OpSet::tree_view`]. This displays the winning slot of a moved object as if it were
+//! the op which made the object, and hides the op which made it.
+
+use super::{OpQuery, OpSet};
+use crate::clock::{Clock, ClockRange};
+use crate::exid::ExId;
+use crate::iter::{Diff, MapDiffItem};
+use crate::op_set2::op::Op;
+use crate::op_set2::parents::Parents;
+use crate::op_set2::types::{Action, ScalarValue, ValueRef};
+use crate::types::{ObjId, ObjType, OpId, Prop};
+
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::ops::Range;
+
+/// Where a moved object is displayed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Location {
+    /// The op which moved the object here
+    pub(crate) slot: OpId,
+    /// The object it was moved into
+    pub(crate) dest: ObjId,
+}
+
+/// The locations of the objects which have been moved, at some point in history
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TreeLocations {
+    locations: HashMap<ObjId, Location>,
+    /// Objects which are not displayed anywhere, see [`OpSet::tree_diff_locations`]
+    detached: HashSet<ObjId>,
+}
+
+impl TreeLocations {
+    pub(crate) fn get(&self, obj: &ObjId) -> Option<&Location> {
+        self.locations.get(obj)
+    }
+
+    fn is_moved(&self, obj: &ObjId) -> bool {
+        self.locations.contains_key(obj) || self.detached.contains(obj)
+    }
+
+    /// The slot `obj` is displayed at, or `None` if it is where it was made
+    fn slot(&self, obj: &ObjId) -> Option<OpId> {
+        self.locations.get(obj).map(|l| l.slot)
+    }
+}
+
+fn make_action(obj_type: ObjType) -> Action {
+    match obj_type {
+        ObjType::Map => Action::MakeMap,
+        ObjType::Table => Action::MakeTable,
+        ObjType::List => Action::MakeList,
+        ObjType::Text => Action::MakeText,
+        ObjType::Set => Action::MakeSet,
+        ObjType::OrderedMap => Action::MakeOrderedMap,
+    }
+}
+
+impl OpSet {
+    /// Whether any object in the document has ever been moved
+    pub(crate) fn has_tree_moves(&self) -> bool {
+        self.obj_info.tree_moves().next().is_some()
+    }
+
+    /// Where each moved object is at `clock`
+    pub(crate) fn tree_locations(&self, clock: Option<&Clock>) -> TreeLocations {
+        let mut result = TreeLocations::default();
+        for (id, mv) in self.obj_info.tree_moves() {
+            if !clock.is_none_or(|c| c.covers(id)) || !self.is_valid_move(mv.target, mv.dest) {
+                continue;
+            }
+            if self.is_ancestor(&mv.target, mv.dest, &result) {
+                continue;
+            }
+            let location = Location {
+                slot: *id,
+                dest: mv.dest,
+            };
+            result.locations.insert(mv.target, location);
+        }
+        result
+    }
+
+    /// The locations of the moved objects at the start of the diff `clock`, with the objects
+    /// which are somewhere else at the end of it not displayed at all.
+    ///
+    /// Diffing from this to the end of `clock` describes everything except the objects which
+    /// have been moved, which appear to be added at their new location. The patches for them
+    /// detach them from their old location first (see [`crate::iter::DiffIter::log`]).
+    pub(crate) fn tree_diff_locations(&self, clock: &ClockRange) -> (TreeLocations, TreeLocations) {
+        match clock {
+            ClockRange::Current(clock) => {
+                let locations = self.tree_locations(clock.as_ref());
+                (locations.clone(), locations)
+            }
+            ClockRange::Diff(before, after) => {
+                let mut old = self.tree_locations(Some(before));
+                let new = self.tree_locations(Some(after));
+                for moved in self.moved_between(&old, &new) {
+                    old.locations.remove(&moved);
+                    old.detached.insert(moved);
+                }
+                (old, new)
+            }
+        }
+    }
+
+    /// The objects which are in a different place in `new` than in `old`
+    pub(crate) fn moved_between(&self, old: &TreeLocations, new: &TreeLocations) -> Vec<ObjId> {
+        let mut moved = old
+            .locations
+            .keys()
+            .chain(new.locations.keys())
+            .filter(|obj| old.slot(obj) != new.slot(obj))
+            .copied()
+            .collect::<Vec<_>>();
+        moved.sort();
+        moved.dedup();
+        moved
+    }
+
+    /// Whether `target` may be moved into `dest` at all, regardless of where they are
+    pub(crate) fn is_valid_move(&self, target: ObjId, dest: ObjId) -> bool {
+        let Some(origin) = self.object_parent(&target) else {
+            return false;
+        };
+        self.object_type(&origin) != Some(ObjType::Text)
+            && matches!(
+                self.object_type(&dest),
+                Some(ObjType::Map | ObjType::Table | ObjType::List)
+            )
+    }
+
+    /// The path to `obj` at `clock`, if it is visible
+    pub(crate) fn visible_path(
+        &self,
+        obj: ObjId,
+        clock: Option<&Clock>,
+    ) -> Option<Vec<(ExId, Prop)>> {
+        let clock = clock.cloned();
+        Parents {
+            obj,
+            ops: self,
+            clock,
+        }
+        .visible_path()
+    }
+
+    /// The object `obj` is in according to `locations`
+    pub(crate) fn tree_parent(&self, obj: &ObjId, locations: &TreeLocations) -> Option<ObjId> {
+        match locations.get(obj) {
+            Some(location) => Some(location.dest),
+            None => self.object_parent(obj),
+        }
+    }
+
+    /// Whether `obj` is `ancestor` or one of its descendants according to `locations`
+    pub(crate) fn is_ancestor(
+        &self,
+        ancestor: &ObjId,
+        obj: ObjId,
+        locations: &TreeLocations,
+    ) -> bool {
+        let mut next = Some(obj);
+        while let Some(obj) = next {
+            if obj == *ancestor {
+                return true;
+            }
+            next = self.tree_parent(&obj, locations);
+        }
+        false
+    }
+
+    /// How `op` is displayed when the moved objects are at `locations`
+    ///
+    /// The winning slot of a moved object is displayed as the op which made the object, with
+    /// the id of the slot kept in [`Op::slot`]. Every other slot, and the op which made a moved
+    /// object, is not displayed at all.
+    pub(crate) fn tree_view<'a>(
+        &self,
+        mut op: Op<'a>,
+        locations: &TreeLocations,
+    ) -> Option<Op<'a>> {
+        match op.action {
+            Action::MoveObject => {
+                let mv = self.obj_info.tree_move(&op.id)?;
+                if locations.slot(&mv.target) != Some(op.id) {
+                    return None;
+                }
+                let obj_type = self.object_type(&mv.target)?;
+                op.slot = Some(op.id);
+                op.id = mv.target.0;
+                op.action = make_action(obj_type);
+                op.value = ScalarValue::Null;
+                Some(op)
+            }
+            Action::MakeMap
+            | Action::MakeList
+            | Action::MakeText
+            | Action::MakeTable
+            | Action::MakeSet
+            | Action::MakeOrderedMap
+                if locations.is_moved(&ObjId(op.id)) =>
+            {
+                None
+            }
+            _ => Some(op),
+        }
+    }
+
+    /// The map with moves the ops in `range` belong to, if any
+    fn moved_map_obj(&self, range: &Range<usize>) -> Option<ObjId> {
+        let obj = self.moved_obj(range)?;
+        matches!(self.object_type(&obj)?, ObjType::Map | ObjType::Table).then_some(obj)
+    }
+
+    /// The visible ops of the keys of a map with moves in `range`, the last of each being the
+    /// winner
+    fn moved_map_at(
+        &self,
+        range: &Range<usize>,
+        clock: Option<&Clock>,
+        locations: &TreeLocations,
+    ) -> BTreeMap<Cow<'_, str>, Vec<Op<'_>>> {
+        let mut keys: BTreeMap<_, Vec<_>> = BTreeMap::new();
+        for op in self.iter_range(range).visible(self, clock) {
+            if let Some(op) = self.tree_view(op, locations) {
+                if let Some(key) = op.key.key_str() {
+                    keys.entry(key).or_default().push(op);
+                }
+            }
+        }
+        keys
+    }
+
+    /// The winning ops of the keys of the map with moves the ops in `range` belong to, if there
+    /// is one
+    pub(crate) fn moved_map(
+        &self,
+        range: &Range<usize>,
+        clock: Option<&Clock>,
+    ) -> Option<Vec<Op<'_>>> {
+        self.moved_map_obj(range)?;
+        let locations = self.tree_locations(clock);
+        let winners = self
+            .moved_map_at(range, clock, &locations)
+            .into_values()
+            .filter_map(|mut ops| {
+                let conflict = ops.len() > 1;
+                let mut winner = ops.pop()?;
+                winner.conflict = conflict;
+                Some(winner)
+            })
+            .collect();
+        Some(winners)
+    }
+
+    /// The visible ops of `key` in the map `obj`, which has moves
+    pub(crate) fn seek_moved_ops_by_map_key(
+        &self,
+        obj: &ObjId,
+        key: &str,
+        clock: Option<&Clock>,
+    ) -> Vec<Op<'_>> {
+        let locations = self.tree_locations(clock);
+        self.iter_range(&self.prop_range(obj, key))
+            .visible(self, clock)
+            .filter_map(|op| self.tree_view(op, &locations))
+            .collect()
+    }
+
+    /// The [`MapDiffItem`]s for the map with moves the ops in `range` belong to, if there is one
+    pub(crate) fn moved_map_diff(
+        &self,
+        range: &Range<usize>,
+        clock: &ClockRange,
+    ) -> Option<Vec<MapDiffItem<'_>>> {
+        self.moved_map_obj(range)?;
+        let (old_locations, new_locations) = self.tree_diff_locations(clock);
+        let (mut old, new) = match clock {
+            ClockRange::Current(clock) => (
+                BTreeMap::new(),
+                self.moved_map_at(range, clock.as_ref(), &new_locations),
+            ),
+            ClockRange::Diff(before, after) => (
+                self.moved_map_at(range, Some(before), &old_locations),
+                self.moved_map_at(range, Some(after), &new_locations),
+            ),
+        };
+        let mut items = vec![];
+        for (key, ops) in new {
+            let winner = ops.last().expect("keys always have a value");
+            let conflict = ops.len() > 1;
+            let (value, inc) = match &winner.value {
+                ScalarValue::Counter(c) => {
+                    let (before, after) = self.get_increment_diff_at_pos(winner.pos, clock);
+                    (ScalarValue::Counter(*c + after), after - before)
+                }
+                value => (value.clone(), 0),
+            };
+            let item = |diff, expose| MapDiffItem {
+                diff,
+                key: key.clone(),
+                value: ValueRef::from_action_value(winner.action, value.clone()),
+                inc: 0,
+                conflict,
+                expose,
+                pos: winner.pos,
+                id: winner.id,
+            };
+            let Some(before) = old.remove(&key) else {
+                items.push(item(Diff::Add, clock.predates(&winner.stored_id())));
+                continue;
+            };
+            let old_winner = before.last().expect("keys always have a value");
+            if old_winner.stored_id() != winner.stored_id() {
+                items.push(item(Diff::Add, clock.predates(&winner.stored_id())));
+            } else if before.len() > 1 && !conflict {
+                // the value is the same, but a put is needed to clear its conflict flag
+                items.push(item(Diff::Add, true));
+            } else {
+                let mut same = item(Diff::Same, false);
+                same.inc = inc;
+                same.conflict = conflict && before.len() == 1;
+                items.push(same);
+            }
+        }
+        for (key, ops) in old {
+            let winner = ops.last().expect("keys always have a value");
+            items.push(MapDiffItem {
+                diff: Diff::Del,
+                key,
+                value: ValueRef::from_action_value(winner.action, winner.value.clone()),
+                inc: 0,
+                conflict: false,
+                expose: false,
+                pos: winner.pos,
+                id: winner.id,
+            });
+        }
+        items.sort_by(|a, b| a.key.cmp(&b.key));
+        Some(items)
+    }
+}
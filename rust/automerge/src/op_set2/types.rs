@@ -4,7 +4,7 @@ use crate::error::AutomergeError;
 use crate::types;
 use crate::types::{ActorId, ChangeHash, ElemId, ObjType};
 use crate::value;
-use crate::{hydrate, TextEncoding};
+use crate::{hydrate, Decimal, TextEncoding};
 
 use std::cmp::Ordering;
 use std::fmt;
@@ -78,6 +78,14 @@ pub(crate) enum Action {
     MakeTable,
     /// Mark formatting spans in rich-text contexts.
     Mark,
+    /// Move an element of a list. See [`OpType::Move`].
+    Move,
+    /// Create a Set value.
+    MakeSet,
+    /// Create an ordered map value.
+    MakeOrderedMap,
+    /// Move an object. See [`OpType::MoveObject`].
+    MoveObject,
 }
 
 impl fmt::Display for Action {
@@ -91,6 +99,10 @@ impl fmt::Display for Action {
             Self::Increment => write!(f, "INC"),
             Self::MakeTable => write!(f, "TBL"),
             Self::Mark => write!(f, "MRK"),
+            Self::Move => write!(f, "MOV"),
+            Self::MakeSet => write!(f, "SET+"),
+            Self::MakeOrderedMap => write!(f, "OMAP"),
+            Self::MoveObject => write!(f, "MVO"),
         }
     }
 }
@@ -106,6 +118,10 @@ impl From<Action> for u64 {
             Action::Increment => 5,
             Action::MakeTable => 6,
             Action::Mark => 7,
+            Action::Move => 8,
+            Action::MakeSet => 9,
+            Action::MakeOrderedMap => 10,
+            Action::MoveObject => 11,
         }
     }
 }
@@ -123,8 +139,12 @@ impl TryFrom<u64> for Action {
             5 => Ok(Action::Increment),
             6 => Ok(Action::MakeTable),
             7 => Ok(Action::Mark),
+            8 => Ok(Action::Move),
+            9 => Ok(Action::MakeSet),
+            10 => Ok(Action::MakeOrderedMap),
+            11 => Ok(Action::MoveObject),
             other => Err(PackError::InvalidValue(format!(
-                "valid action (integer between 0 and 7), unexpected integer: {}",
+                "valid action (integer between 0 and 11), unexpected integer: {}",
                 other
             ))),
         }
@@ -139,6 +159,9 @@ impl TryFrom<Action> for ObjType {
             Action::MakeMap => Ok(ObjType::Map),
             Action::MakeList => Ok(ObjType::List),
             Action::MakeText => Ok(ObjType::Text),
+            Action::MakeTable => Ok(ObjType::Table),
+            Action::MakeSet => Ok(ObjType::Set),
+            Action::MakeOrderedMap => Ok(ObjType::OrderedMap),
             _ => Err(AutomergeError::Fail),
         }
     }
@@ -152,6 +175,13 @@ pub(crate) enum OpType<'a> {
     Put(ScalarValue<'a>),
     MarkBegin(bool, MarkData<'a>),
     MarkEnd(bool),
+    /// One half of moving a list element. Without `insert` it claims the element
+    /// it is keyed by, with `insert` it is the position the claim just before it
+    /// moves that element to.
+    Move,
+    /// Moves the object in the target columns of the op to the key or index of the op, see
+    /// [`OpSet::tree_view`](super::op_set::OpSet::tree_view)
+    MoveObject,
 }
 
 impl<'a> OpType<'a> {
@@ -166,6 +196,8 @@ impl<'a> OpType<'a> {
             Action::MakeList => Self::Make(ObjType::List),
             Action::MakeText => Self::Make(ObjType::Text),
             Action::MakeTable => Self::Make(ObjType::Table),
+            Action::MakeSet => Self::Make(ObjType::Set),
+            Action::MakeOrderedMap => Self::Make(ObjType::OrderedMap),
             Action::Set => Self::Put(value.clone()),
             Action::Delete => Self::Delete,
             Action::Increment => match value {
@@ -183,6 +215,8 @@ impl<'a> OpType<'a> {
                 ),
                 None => Self::MarkEnd(expand),
             },
+            Action::Move => Self::Move,
+            Action::MoveObject => Self::MoveObject,
         }
     }
 }
@@ -197,6 +231,7 @@ pub enum ScalarValue<'a> {
     Counter(i64),
     Timestamp(i64),
     Boolean(bool),
+    Decimal(Decimal),
     Unknown { type_code: u8, bytes: Cow<'a, [u8]> },
     Null,
 }
@@ -212,6 +247,7 @@ impl fmt::Display for ScalarValue<'_> {
             ScalarValue::Counter(c) => write!(f, "Counter: {}", c),
             ScalarValue::Timestamp(i) => write!(f, "Timestamp: {}", i),
             ScalarValue::Boolean(b) => write!(f, "{}", b),
+            ScalarValue::Decimal(d) => write!(f, "Decimal: {}", d),
             ScalarValue::Null => write!(f, "null"),
             ScalarValue::Unknown { type_code, .. } => write!(f, "unknown type {}", type_code),
         }
@@ -266,10 +302,10 @@ impl<'a> ScalarValue<'a> {
             Self::Counter(n) => types::ScalarValue::Counter(n.into()),
             Self::Timestamp(n) => types::ScalarValue::Timestamp(*n),
             Self::Boolean(b) => types::ScalarValue::Boolean(*b),
-            Self::Unknown { type_code, bytes } => types::ScalarValue::Unknown {
-                type_code: *type_code,
-                bytes: bytes.to_vec(),
-            },
+            Self::Decimal(d) => types::ScalarValue::Decimal(d.clone()),
+            Self::Unknown { type_code, bytes } => {
+                types::ScalarValue::from_unknown(*type_code, bytes)
+            }
             Self::Null => types::ScalarValue::Null,
         }
     }
@@ -284,10 +320,10 @@ impl<'a> ScalarValue<'a> {
             Self::Counter(n) => types::ScalarValue::Counter(n.into()),
             Self::Timestamp(n) => types::ScalarValue::Timestamp(n),
             Self::Boolean(b) => types::ScalarValue::Boolean(b),
-            Self::Unknown { type_code, bytes } => types::ScalarValue::Unknown {
-                type_code,
-                bytes: bytes.to_vec(),
-            },
+            Self::Decimal(d) => types::ScalarValue::Decimal(d),
+            Self::Unknown { type_code, bytes } => {
+                types::ScalarValue::from_unknown(type_code, &bytes)
+            }
             Self::Null => types::ScalarValue::Null,
         }
     }
@@ -302,6 +338,7 @@ impl<'a> ScalarValue<'a> {
             Self::Counter(n) => ScalarValue::Counter(n),
             Self::Timestamp(n) => ScalarValue::Timestamp(n),
             Self::Boolean(b) => ScalarValue::Boolean(b),
+            Self::Decimal(d) => ScalarValue::Decimal(d),
             Self::Unknown { type_code, bytes } => ScalarValue::Unknown {
                 type_code,
                 bytes: Cow::Owned(bytes.into_owned()),
@@ -332,6 +369,9 @@ impl<'a> ScalarValue<'a> {
             ValueType::Bytes => Ok(ScalarValue::Bytes(Cow::Borrowed(raw))),
             ValueType::Counter => Ok(ScalarValue::Counter(parse_leb128(raw)?)),
             ValueType::Timestamp => Ok(ScalarValue::Timestamp(parse_leb128(raw)?)),
+            ValueType::Decimal => Ok(ScalarValue::Decimal(
+                Decimal::from_bytes(raw).ok_or(ReadScalarError::Decimal)?,
+            )),
             ValueType::Unknown(u8) => Ok(ScalarValue::Unknown {
                 type_code: u8,
                 bytes: Cow::Borrowed(raw),
@@ -361,6 +401,7 @@ impl<'a> ScalarValue<'a> {
                 out.extend_from_slice(&f.to_le_bytes());
                 Some(Cow::Owned(out))
             }
+            Self::Decimal(d) => Some(Cow::Owned(d.to_bytes())),
             Self::Unknown { bytes, .. } => Some(bytes.clone()),
         }
     }
@@ -388,6 +429,7 @@ impl<'a> ScalarValue<'a> {
                 out.extend_from_slice(&f.to_le_bytes());
                 Some(Cow::Owned(out))
             }
+            Self::Decimal(d) => Some(Cow::Owned(d.to_bytes())),
             Self::Unknown { bytes, .. } => Some(bytes.clone()),
         }
     }
@@ -415,6 +457,10 @@ impl crate::types::OpType {
             Self::Make(ObjType::List) => (Action::MakeList, ScalarValue::Null, false, None),
             Self::Make(ObjType::Text) => (Action::MakeText, ScalarValue::Null, false, None),
             Self::Make(ObjType::Table) => (Action::MakeTable, ScalarValue::Null, false, None),
+            Self::Make(ObjType::Set) => (Action::MakeSet, ScalarValue::Null, false, None),
+            Self::Make(ObjType::OrderedMap) => {
+                (Action::MakeOrderedMap, ScalarValue::Null, false, None)
+            }
             Self::Delete => (Action::Delete, ScalarValue::Null, false, None),
             Self::Increment(i) => (Action::Increment, ScalarValue::Int(i), false, None),
             Self::Put(val) => (Action::Set, val.into_ref(), false, None),
@@ -425,6 +471,8 @@ impl crate::types::OpType {
                 Some(Cow::Owned(String::from(md.name))),
             ),
             Self::MarkEnd(expand) => (Action::Mark, ScalarValue::Null, expand, None),
+            Self::Move => (Action::Move, ScalarValue::Null, false, None),
+            Self::MoveObject(_) => (Action::MoveObject, ScalarValue::Null, false, None),
         }
     }
 }
@@ -440,11 +488,16 @@ impl crate::types::ScalarValue {
             Self::Counter(c) => ScalarValue::Counter(c.into()),
             Self::Timestamp(i) => ScalarValue::Timestamp(i),
             Self::Boolean(b) => ScalarValue::Boolean(b),
+            Self::Decimal(d) => ScalarValue::Decimal(d),
             Self::Null => ScalarValue::Null,
             Self::Unknown { type_code, bytes } => ScalarValue::Unknown {
                 type_code,
                 bytes: Cow::Owned(bytes),
             },
+            Self::Extension(e) => ScalarValue::Unknown {
+                type_code: e.type_code(),
+                bytes: Cow::Owned(e.bytes().to_vec()),
+            },
         }
     }
 }
@@ -459,6 +512,8 @@ pub(crate) enum ReadScalarError {
     Float,
     #[error("invalid string")]
     Str,
+    #[error("invalid decimal")]
+    Decimal,
 }
 
 impl From<crate::storage::parse::leb128::Error> for ReadScalarError {
@@ -496,6 +551,7 @@ impl PartialEq<types::ScalarValue> for ScalarValue<'_> {
             (ScalarValue::Counter(a), types::ScalarValue::Counter(b)) => *a == i64::from(b),
             (ScalarValue::Timestamp(a), types::ScalarValue::Timestamp(b)) => a == b,
             (ScalarValue::Boolean(a), types::ScalarValue::Boolean(b)) => a == b,
+            (ScalarValue::Decimal(a), types::ScalarValue::Decimal(b)) => a == b,
             (ScalarValue::Null, types::ScalarValue::Null) => true,
             (
                 ScalarValue::Unknown {
@@ -507,6 +563,9 @@ impl PartialEq<types::ScalarValue> for ScalarValue<'_> {
                     bytes: b2,
                 },
             ) => a1 == b1 && a2 == b2,
+            (ScalarValue::Unknown { type_code, bytes }, types::ScalarValue::Extension(e)) => {
+                *type_code == e.type_code() && **bytes == *e.bytes()
+            }
             _ => false,
         }
     }
@@ -669,6 +728,8 @@ impl<'a> ValueRef<'a> {
             Action::MakeList => ValueRef::Object(ObjType::List),
             Action::MakeText => ValueRef::Object(ObjType::Text),
             Action::MakeTable => ValueRef::Object(ObjType::Table),
+            Action::MakeSet => ValueRef::Object(ObjType::Set),
+            Action::MakeOrderedMap => ValueRef::Object(ObjType::OrderedMap),
             _ => ValueRef::Scalar(value),
         }
     }
@@ -679,6 +740,8 @@ impl<'a> ValueRef<'a> {
             Self::Object(ObjType::Table) => hydrate::Value::map(),
             Self::Object(ObjType::List) => hydrate::Value::list(),
             Self::Object(ObjType::Text) => hydrate::Value::text(encoding, ""),
+            Self::Object(ObjType::Set) => hydrate::Value::set(),
+            Self::Object(ObjType::OrderedMap) => hydrate::Value::ordered_map(),
             Self::Scalar(s) => hydrate::Value::Scalar(s.into()),
         }
     }
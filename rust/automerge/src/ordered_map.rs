@@ -0,0 +1,280 @@
+//! The ordering of the keys of an [`ObjType::OrderedMap`](crate::ObjType::OrderedMap)
+//!
+//! An ordered map is stored as a map which has two keys for each of its entries: the key itself,
+//! whose value is the value of the entry, and its position key, the key prefixed with
+//! [`POSITION_PREFIX`], whose value is a string which the entries are sorted by. Moving an entry
+//! puts a new position which sorts between the positions of its new neighbours, so concurrent
+//! moves of the same key are resolved like any other concurrent puts and moves of different keys
+//! never conflict.
+//!
+//! Entries with the same position are ordered by their keys. An entry can lose its position if it
+//! is put concurrently with being deleted, these come after all the other entries, in the order
+//! of their keys.
+
+use std::collections::HashMap;
+
+use crate::op_set2::types::ScalarValue;
+
+/// The prefix of position keys, which the keys of an ordered map cannot start with
+pub(crate) const POSITION_PREFIX: char = '\u{0}';
+
+/// The digits of a position, in ascending order
+const DIGITS: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+const BASE: usize = DIGITS.len();
+
+pub(crate) fn position_key(key: &str) -> String {
+    format!("{}{}", POSITION_PREFIX, key)
+}
+
+pub(crate) fn is_position_key(key: &str) -> bool {
+    key.starts_with(POSITION_PREFIX)
+}
+
+/// The position stored in `value`, if it is one
+pub(crate) fn position(value: &ScalarValue<'_>) -> Option<String> {
+    match value {
+        ScalarValue::Str(s) => Some(s.to_string()),
+        _ => None,
+    }
+}
+
+/// Sort the entries of an ordered map, where `items` contains the position keys as well as the
+/// entries and `key` and `position` return the key and the string value of an item. The position
+/// of each entry is returned alongside it.
+pub(crate) fn sort<T, K, P>(
+    items: impl IntoIterator<Item = T>,
+    key: K,
+    position: P,
+) -> Vec<(T, Option<String>)>
+where
+    K: Fn(&T) -> &str,
+    P: Fn(&T) -> Option<String>,
+{
+    let mut positions = HashMap::new();
+    let mut entries = Vec::new();
+    for item in items {
+        match key(&item).strip_prefix(POSITION_PREFIX) {
+            Some(k) => {
+                if let Some(p) = position(&item) {
+                    positions.insert(k.to_string(), p);
+                }
+            }
+            None => entries.push(item),
+        }
+    }
+    let mut entries = entries
+        .into_iter()
+        .map(|item| {
+            let p = positions.remove(key(&item));
+            (item, p)
+        })
+        .collect::<Vec<_>>();
+    entries.sort_by(|(a, pa), (b, pb)| (pa.is_none(), pa, key(a)).cmp(&(pb.is_none(), pb, key(b))));
+    entries
+}
+
+/// The keys of an ordered map in order, with their positions
+///
+/// A transaction sorts the entries of a map once, the first time it writes to it, and then keeps
+/// this up to date with its own writes, so that writing to a map doesn't sort all of its entries
+/// every time.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SortedKeys(Vec<(String, Option<String>)>);
+
+impl SortedKeys {
+    /// The keys in `entries`, which must be in the order [`sort`] returns
+    pub(crate) fn new(entries: Vec<(String, Option<String>)>) -> Self {
+        Self(entries)
+    }
+
+    pub(crate) fn entries(&self) -> &[(String, Option<String>)] {
+        &self.0
+    }
+
+    /// The greatest position of any key
+    pub(crate) fn last_position(&self) -> Option<&str> {
+        let with_position = self.0.partition_point(|(_, p)| p.is_some());
+        self.0[..with_position]
+            .last()
+            .and_then(|(_, p)| p.as_deref())
+    }
+
+    /// Put `key` where `position` sorts, adding it if it isn't already a key
+    pub(crate) fn set_position(&mut self, key: &str, position: String) {
+        self.remove(key);
+        let index = self.0.partition_point(|(k, p)| match p {
+            Some(p) => (p.as_str(), k.as_str()) < (position.as_str(), key),
+            None => false,
+        });
+        self.0.insert(index, (key.to_string(), Some(position)));
+    }
+
+    pub(crate) fn remove(&mut self, key: &str) {
+        if let Some(index) = self.0.iter().position(|(k, _)| k == key) {
+            self.0.remove(index);
+        }
+    }
+}
+
+/// A position which sorts after `before` and before `after`, where `None` is the start or end of
+/// the map
+pub(crate) fn between(before: Option<&str>, after: Option<&str>) -> String {
+    let before = digits(before.unwrap_or(""));
+    let result = match after {
+        Some(after) if before.is_empty() => decrement(&digits(after)),
+        Some(after) => midpoint(&before, &digits(after)),
+        None => increment(&before),
+    };
+    result.into_iter().map(|d| char::from(DIGITS[d])).collect()
+}
+
+/// The value of each character of a position. Positions written by other implementations may
+/// contain characters which are not digits, these are clamped to the nearest digit.
+fn digits(position: &str) -> Vec<usize> {
+    position
+        .bytes()
+        .map(|b| match DIGITS.binary_search(&b) {
+            Ok(d) => d,
+            Err(0) => 0,
+            Err(d) => d - 1,
+        })
+        .collect()
+}
+
+/// The shortest position after `before`, which leaves room after it for more positions
+fn increment(before: &[usize]) -> Vec<usize> {
+    match before.iter().position(|d| *d < BASE - 1) {
+        Some(i) => {
+            let mut result = before[..i].to_vec();
+            result.push(before[i] + 1);
+            result
+        }
+        None => {
+            let mut result = before.to_vec();
+            result.push(BASE / 2);
+            result
+        }
+    }
+}
+
+/// The shortest position before `after`, which leaves room before it for more positions
+fn decrement(after: &[usize]) -> Vec<usize> {
+    match after.iter().position(|d| *d > 1) {
+        Some(i) => {
+            let mut result = after[..i].to_vec();
+            result.push(after[i] - 1);
+            result
+        }
+        None => midpoint(&[], after),
+    }
+}
+
+fn midpoint(before: &[usize], after: &[usize]) -> Vec<usize> {
+    let mut result = Vec::new();
+    let mut bounded = true;
+    for i in 0.. {
+        let lo = before.get(i).copied().unwrap_or(0);
+        let hi = match after.get(i) {
+            Some(hi) if bounded && *hi >= lo => *hi,
+            // the upper bound is used up or below the lower bound, which can only happen with
+            // positions this module didn't write, so give up on it
+            _ => {
+                bounded = false;
+                BASE
+            }
+        };
+        let mid = (lo + hi) / 2;
+        if mid > lo {
+            result.push(mid);
+            break;
+        }
+        // there is no digit between the bounds, so this digit is `lo` and everything which
+        // follows it is below the upper bound
+        if hi > lo {
+            bounded = false;
+        }
+        result.push(lo);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn positions_sort_between_their_bounds() {
+        let mut positions = vec![between(None, None)];
+        for _ in 0..100 {
+            let last = positions.last().unwrap().clone();
+            positions.push(between(Some(&last), None));
+        }
+        for _ in 0..100 {
+            let first = positions[0].clone();
+            positions.insert(0, between(None, Some(&first)));
+        }
+        for i in 0..100 {
+            let (a, b) = (positions[i * 2].clone(), positions[i * 2 + 1].clone());
+            positions.insert(i * 2 + 1, between(Some(&a), Some(&b)));
+        }
+        // repeatedly inserting at the same place
+        for _ in 0..100 {
+            let (a, b) = (positions[10].clone(), positions[11].clone());
+            positions.insert(11, between(Some(&a), Some(&b)));
+        }
+        for pair in positions.windows(2) {
+            assert!(pair[0] < pair[1], "{:?} >= {:?}", pair[0], pair[1]);
+        }
+        assert!(positions.iter().all(|p| !p.ends_with('0')));
+        let longest = positions.iter().max_by_key(|p| p.len()).unwrap();
+        assert!(longest.len() < 30, "{longest}");
+    }
+
+    #[test]
+    fn sorted_keys_stay_sorted() {
+        let mut keys = SortedKeys::new(vec![
+            ("a".to_string(), Some("B".to_string())),
+            ("c".to_string(), Some("D".to_string())),
+            ("b".to_string(), None),
+        ]);
+        assert_eq!(keys.last_position(), Some("D"));
+        keys.set_position("d", between(keys.last_position(), None));
+        keys.set_position("b", "B".to_string());
+        keys.set_position("c", "A".to_string());
+        keys.remove("a");
+        let order = keys
+            .entries()
+            .iter()
+            .map(|(k, _)| k.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(order, vec!["c", "b", "d"]);
+        assert_eq!(keys.last_position(), Some("E"));
+    }
+
+    #[test]
+    fn entries_sort_by_position_then_key() {
+        let items = vec![
+            ("b", "V"),
+            ("\u{0}b", "W"),
+            ("a", "V"),
+            ("\u{0}a", "X"),
+            ("c", "V"),
+            ("d", "V"),
+            ("\u{0}d", "W"),
+            ("\u{0}e", "A"),
+        ];
+        let sorted = sort(items, |(k, _)| k, |(_, p)| Some(p.to_string()))
+            .into_iter()
+            .map(|((k, _), p)| (k, p))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            sorted,
+            vec![
+                ("b", Some("W".to_string())),
+                ("d", Some("W".to_string())),
+                ("a", Some("X".to_string())),
+                ("c", None),
+            ]
+        );
+    }
+}
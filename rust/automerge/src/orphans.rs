@@ -0,0 +1,212 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use crate::clock::Clock;
+use crate::exid::ExId;
+use crate::op_set2::OpSet;
+use crate::transaction::Transactable;
+use crate::types::{ObjId, OpId};
+use crate::{Automerge, AutomergeError, ChangeHash, Prop};
+
+/// Whether [`Automerge::restore_orphans`] puts back the objects deleted from a map
+/// concurrently with edits made inside them
+///
+/// This is not part of the data model: merging always removes such objects, in every document.
+/// The policy is a setting of the local document, set for a map with
+/// [`Automerge::set_restore_policy`], which is not saved or sent to other peers and is only
+/// acted on when the application calls [`Automerge::restore_orphans`]. The objects that call
+/// puts back are moved back with an ordinary change, which is what other peers see.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RestorePolicy {
+    /// The objects are left out of the document, their edits are only reported by
+    /// [`Automerge::orphaned_edits`]
+    #[default]
+    Leave,
+    /// [`Automerge::restore_orphans`] puts the objects back at their keys
+    Restore,
+}
+
+/// An object which is no longer reachable from the root of the document and the edits made in
+/// it which the changes that removed it did not see
+///
+/// This is returned by [`Automerge::orphaned_edits`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrphanedEdits {
+    /// The object which was removed
+    pub obj: ExId,
+    /// The object it was removed from
+    pub parent: ExId,
+    /// The property of `parent` it was at
+    pub prop: Prop,
+    /// The ops which removed it, deleting or overwriting the property
+    pub removed_by: Vec<ExId>,
+    /// The ops in the object, or in the objects in it, which none of `removed_by` saw
+    pub edits: Vec<ExId>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Reach {
+    Reachable,
+    /// Unreachable because the object at the top of its subtree was removed from a reachable
+    /// parent
+    Orphaned(ObjId),
+    /// Unreachable because something inside an orphaned object was removed
+    Lost,
+}
+
+/// Find the objects which were removed from the document concurrently with edits inside them
+pub(crate) fn orphaned_edits(doc: &Automerge) -> Vec<OrphanedEdits> {
+    let ops = doc.ops();
+    let mut reach = HashMap::new();
+    let tops = ops.iter_obj_ids().map(|(obj, _)| obj).collect::<Vec<_>>();
+    collect(doc, tops, &mut reach)
+}
+
+/// Like [`orphaned_edits`], but only the objects removed by the changes since `heads` or with
+/// edits made by them
+///
+/// Those are the objects with ops from the changes, which may be inside a removed object, and
+/// the objects whose ops were deleted or overwritten by them. All of these ops are found with
+/// the counter index, so this only visits the ops in the range of counters of the changes.
+pub(crate) fn orphaned_edits_since(doc: &Automerge, heads: &[ChangeHash]) -> Vec<OrphanedEdits> {
+    let ops = doc.ops();
+    let changes = doc.get_changes_meta(heads);
+    let (Some(min), Some(max)) = (
+        changes.iter().map(|c| c.start_op).min(),
+        changes.iter().map(|c| c.max_op).max(),
+    ) else {
+        return Vec::new();
+    };
+    let clock = doc.change_graph.clock_at(heads);
+    let mut candidates = Vec::new();
+    for op in ops.iter_ctr_range(min as usize..max as usize + 1) {
+        if !clock.covers(&op.id) {
+            candidates.push(op.obj);
+        }
+        if op.succ().any(|id| !clock.covers(&id)) {
+            candidates.extend(child(ops, op.id, op.target));
+        }
+    }
+    let mut reach = HashMap::new();
+    collect(doc, candidates, &mut reach)
+}
+
+/// The orphans with the objects in `objs` in them and their edits, in the order of their ids
+fn collect(
+    doc: &Automerge,
+    objs: Vec<ObjId>,
+    reach: &mut HashMap<ObjId, Reach>,
+) -> Vec<OrphanedEdits> {
+    let ops = doc.ops();
+    let tops = objs
+        .into_iter()
+        .filter_map(|obj| match reach_of(ops, obj, reach) {
+            Reach::Orphaned(top) => Some(top),
+            _ => None,
+        })
+        .collect::<BTreeSet<_>>();
+    let mut orphans = Vec::new();
+    for top in tops {
+        let (mut orphan, clocks) = orphan(doc, top);
+        // the edits of each object in the orphan, which is the object at the top and the
+        // objects in it which were not removed from it or moved out of it
+        let mut edits = BTreeMap::new();
+        let mut seen = BTreeSet::from([top]);
+        let mut queue = vec![top];
+        while let Some(obj) = queue.pop() {
+            let mut obj_edits = Vec::new();
+            for op in ops.iter_range(&ops.scope_to_obj(&obj)) {
+                if !clocks.iter().any(|clock| clock.covers(&op.id)) {
+                    obj_edits.push(ops.id_to_exid(op.id));
+                }
+                if let Some(child) = child(ops, op.id, op.target) {
+                    if reach_of(ops, child, reach) == Reach::Orphaned(top) && seen.insert(child) {
+                        queue.push(child);
+                    }
+                }
+            }
+            edits.insert(obj, obj_edits);
+        }
+        orphan.edits = edits.into_values().flatten().collect();
+        if !orphan.edits.is_empty() {
+            orphans.push(orphan);
+        }
+    }
+    orphans
+}
+
+/// The object made or moved by the op `id`, if it makes or moves one
+fn child(ops: &OpSet, id: OpId, target: Option<ObjId>) -> Option<ObjId> {
+    let made = ObjId(id);
+    match ops.object_type(&made) {
+        Some(_) => Some(made),
+        None => target,
+    }
+}
+
+fn reach_of(ops: &OpSet, obj: ObjId, reach: &mut HashMap<ObjId, Reach>) -> Reach {
+    if obj.is_root() {
+        return Reach::Reachable;
+    }
+    if let Some(r) = reach.get(&obj) {
+        return *r;
+    }
+    let r = match ops.parent_object(&obj, None) {
+        None => Reach::Lost,
+        // an object which lost a conflict can still be read with `get_all`
+        Some(parent) if parent.visible || removed_by(ops, parent.op).is_empty() => {
+            reach_of(ops, parent.obj, reach)
+        }
+        Some(parent) => match reach_of(ops, parent.obj, reach) {
+            Reach::Reachable => Reach::Orphaned(obj),
+            _ => Reach::Lost,
+        },
+    };
+    reach.insert(obj, r);
+    r
+}
+
+/// The ops which deleted or overwrote `op`
+fn removed_by(ops: &OpSet, op: OpId) -> Vec<OpId> {
+    ops.find_op_by_id_and_vis(&op, None)
+        .map(|(op, _)| op.succ().collect())
+        .unwrap_or_default()
+}
+
+/// The orphan at `obj` with no edits yet, and the clocks of the changes which removed it
+fn orphan(doc: &Automerge, obj: ObjId) -> (OrphanedEdits, Vec<Clock>) {
+    let ops = doc.ops();
+    // `obj` was orphaned because it has a parent
+    let parent = ops.parent_object(&obj, None).unwrap();
+    let removed_by = removed_by(ops, parent.op);
+    let clocks = removed_by
+        .iter()
+        .filter_map(|id| doc.change_graph.opid_to_hash(*id))
+        .map(|hash| doc.change_graph.clock_at(&[hash]))
+        .collect();
+    let orphan = OrphanedEdits {
+        obj: ops.id_to_exid(obj.0),
+        parent: ops.id_to_exid(parent.obj.0),
+        prop: parent.prop,
+        removed_by: removed_by
+            .into_iter()
+            .map(|id| ops.id_to_exid(id))
+            .collect(),
+        edits: Vec::new(),
+    };
+    (orphan, clocks)
+}
+
+/// Move each of `orphans` back to where it was removed from, unless something else is there now
+pub(crate) fn restore<T: Transactable>(
+    tx: &mut T,
+    orphans: Vec<OrphanedEdits>,
+) -> Result<Vec<ExId>, AutomergeError> {
+    let mut restored = Vec::new();
+    for orphan in orphans {
+        if tx.get(&orphan.parent, orphan.prop.clone())?.is_none() {
+            tx.move_object(&orphan.obj, &orphan.parent, orphan.prop)?;
+            restored.push(orphan.obj);
+        }
+    }
+    Ok(restored)
+}
@@ -1,7 +1,9 @@
 mod patch;
 mod patch_builder;
 mod patch_log;
+mod position_map;
 pub use patch::{Patch, PatchAction};
 pub(crate) use patch_builder::PatchBuilder;
 pub(crate) use patch_log::Event;
 pub use patch_log::PatchLog;
+pub use position_map::{MappedPosition, PositionMap};
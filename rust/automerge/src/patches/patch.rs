@@ -1,7 +1,7 @@
 use crate::{
     marks::{Mark, MarkSet},
     text_value::ConcreteTextValue,
-    ObjId, Prop, Value,
+    ObjId, Prop, ScalarValue, Value,
 };
 use core::fmt::Debug;
 use std::fmt;
@@ -86,6 +86,29 @@ pub enum PatchAction {
     DeleteSeq { index: usize, length: usize },
     /// Some marks within a text object were added or removed
     Mark { marks: Vec<Mark> },
+    /// An element of a list was moved from index `from` to index `to`, which is its index after
+    /// it has been removed from `from`
+    Move { from: usize, to: usize },
+    /// The object `moved` was removed from `prop` and will be put somewhere else by a later
+    /// [`Self::Attach`] patch. Its contents are unchanged while it is detached.
+    Detach { prop: Prop, moved: ObjId },
+    /// The object `moved`, which was removed by an earlier [`Self::Detach`] patch, was put at
+    /// `prop`. In a sequence it is inserted at that index.
+    Attach {
+        prop: Prop,
+        moved: ObjId,
+        /// Whether there is a conflict at this property, see [`Self::PutMap`]
+        conflict: bool,
+    },
+    /// A value was added to a set
+    SetAdd { value: ScalarValue },
+    /// A value was removed from a set
+    SetRemove { value: ScalarValue },
+    /// The key `key` of an ordered map was moved to `index`, which is its index after it has
+    /// been removed from where it was. New keys are put at the end of an ordered map, and when
+    /// the order of a map changes there is a [`Self::MoveKey`] for each of its keys, in order,
+    /// after all of the other patches.
+    MoveKey { key: String, index: usize },
 }
 
 impl fmt::Display for PatchAction {
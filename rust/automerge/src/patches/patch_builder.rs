@@ -5,9 +5,11 @@ use std::sync::Arc;
 use crate::exid::ExId;
 use crate::iter::SpanInternal;
 use crate::marks::MarkSet;
+use crate::op_set2::op_set::TreeLocations;
+use crate::ordered_map;
 use crate::text_value::ConcreteTextValue;
 use crate::types::{Clock, ObjId, ObjType};
-use crate::{Automerge, Prop, TextEncoding, Value};
+use crate::{Automerge, Prop, ScalarValue, TextEncoding, Value};
 
 use super::{Event, Patch, PatchAction};
 use crate::{marks::Mark, sequence_tree::SequenceTree};
@@ -18,9 +20,13 @@ pub(crate) struct PatchBuilder<'a> {
     last_mark_set: Option<Arc<MarkSet>>, // keep this around for a quick pointer equality test
     path_map: BTreeMap<ObjId, (Prop, ObjId)>,
     seen: HashSet<ObjId>,
+    // the ordered maps whose positions changed, in the order they were first changed
+    reordered: Vec<ExId>,
     text_encoding: TextEncoding,
     clock: Option<Clock>,
     doc: &'a Automerge,
+    // where the moved objects are at `clock`, worked out the first time it is needed
+    locations: Option<TreeLocations>,
 }
 
 impl<'a> PatchBuilder<'a> {
@@ -38,9 +44,11 @@ impl<'a> PatchBuilder<'a> {
             last_mark_set: None,
             path_map,
             seen: HashSet::new(),
+            reordered: Vec::new(),
             doc,
             clock,
             text_encoding,
+            locations: None,
         }
     }
 }
@@ -106,9 +114,48 @@ impl PatchBuilder<'_> {
                 self.splice_text(exid, *index, text, marks.clone());
             }
             Event::Mark { marks } => self.mark(exid, marks.clone().into_iter()),
+            Event::Move { from, to } => self.move_element(exid, *from, *to),
+            Event::Attach {
+                prop,
+                moved,
+                conflict,
+            } => {
+                let moved = doc.id_to_exid(*moved);
+                self.attach(exid, prop.clone(), moved, *conflict);
+            }
         }
     }
 
+    /// The object `obj` is in at `self.clock`
+    fn parent(&mut self, obj: &ObjId) -> Option<ObjId> {
+        let ops = self.doc.ops();
+        if !ops.has_tree_moves() {
+            return ops.object_parent(obj);
+        }
+        let clock = self.clock.as_ref();
+        let locations = self
+            .locations
+            .get_or_insert_with(|| ops.tree_locations(clock));
+        ops.tree_parent(obj, locations)
+    }
+
+    /// How deep in the document `obj` is
+    ///
+    /// This is zero for every object if nothing has been moved, as then an object always has a
+    /// greater id than its parent.
+    pub(crate) fn depth(&mut self, obj: &ObjId) -> usize {
+        if !self.doc.ops().has_tree_moves() {
+            return 0;
+        }
+        let mut depth = 0;
+        let mut obj = *obj;
+        while let Some(parent) = self.parent(&obj) {
+            depth += 1;
+            obj = parent;
+        }
+        depth
+    }
+
     fn update_path_map(&mut self, parent_id: ObjId, parent_type: ObjType) {
         match parent_type {
             ObjType::List => {
@@ -147,7 +194,7 @@ impl PatchBuilder<'_> {
             let (p, o) = if let Some(r) = self.path_map.get(&obj).cloned() {
                 r
             } else {
-                let parent_id = self.doc.ops().object_parent(&obj)?;
+                let parent_id = self.parent(&obj)?;
                 let parent_type = self.doc.ops().object_type(&parent_id)?;
                 if self.seen.contains(&obj) {
                     return None;
@@ -165,6 +212,22 @@ impl PatchBuilder<'_> {
     }
 
     pub(crate) fn take_patches(&mut self) -> Vec<Patch> {
+        for obj in std::mem::take(&mut self.reordered) {
+            if let Some(path) = self.get_path(&obj) {
+                let keys = self
+                    .doc
+                    .ops()
+                    .keys(&obj.to_internal_obj(), self.clock.clone());
+                for (index, key) in keys.enumerate() {
+                    let action = PatchAction::MoveKey { key, index };
+                    self.patches.push(Patch {
+                        obj: obj.clone(),
+                        path: path.clone(),
+                        action,
+                    });
+                }
+            }
+        }
         std::mem::take(&mut self.patches)
     }
 
@@ -285,14 +348,44 @@ impl PatchBuilder<'_> {
     }
 
     pub(crate) fn delete_map(&mut self, obj: ExId, key: &str) {
-        if let Some(path) = self.get_path(&obj) {
-            let action = PatchAction::DeleteMap {
-                key: key.to_owned(),
+        if self.reorder(&obj, key) {
+            return;
+        }
+        let action = if self.is_set(&obj) {
+            let Some(value) = ScalarValue::from_set_key(key) else {
+                return;
             };
+            PatchAction::SetRemove { value }
+        } else {
+            PatchAction::DeleteMap {
+                key: key.to_owned(),
+            }
+        };
+        if let Some(path) = self.get_path(&obj) {
             self.push(Patch { obj, path, action })
         }
     }
 
+    /// Whether `obj` is a set, whose keys are reported as [`PatchAction::SetAdd`] and
+    /// [`PatchAction::SetRemove`] of the values they encode
+    fn is_set(&self, obj: &ExId) -> bool {
+        self.doc.ops().object_type(&obj.to_internal_obj()) == Some(ObjType::Set)
+    }
+
+    /// Whether `key` is a position key of the ordered map `obj`, which are not reported
+    /// themselves, instead the order of the map is reported by [`Self::take_patches`]
+    fn reorder(&mut self, obj: &ExId, key: &str) -> bool {
+        if !ordered_map::is_position_key(key)
+            || self.doc.ops().object_type(&obj.to_internal_obj()) != Some(ObjType::OrderedMap)
+        {
+            return false;
+        }
+        if !self.reordered.contains(obj) {
+            self.reordered.push(obj.clone());
+        }
+        true
+    }
+
     pub(crate) fn put(
         &mut self,
         obj: ExId,
@@ -300,9 +393,18 @@ impl PatchBuilder<'_> {
         tagged_value: (Value<'_>, ExId),
         conflict: bool,
     ) {
+        if prop.as_str().is_some_and(|key| self.reorder(&obj, key)) {
+            return;
+        }
         if let Some(path) = self.get_path(&obj) {
             let value = (tagged_value.0.to_owned(), tagged_value.1);
             let action = match prop {
+                Prop::Map(_) if self.is_set(&obj) => match value.0 {
+                    Value::Scalar(value) => PatchAction::SetAdd {
+                        value: value.into_owned(),
+                    },
+                    Value::Object(_) => return,
+                },
                 Prop::Map(key) => PatchAction::PutMap {
                     key,
                     value,
@@ -318,7 +420,28 @@ impl PatchBuilder<'_> {
         }
     }
 
+    pub(crate) fn move_element(&mut self, obj: ExId, from: usize, to: usize) {
+        if let Some(path) = self.get_path(&obj) {
+            let action = PatchAction::Move { from, to };
+            self.push(Patch { obj, path, action })
+        }
+    }
+
+    pub(crate) fn attach(&mut self, obj: ExId, prop: Prop, moved: ExId, conflict: bool) {
+        if let Some(path) = self.get_path(&obj) {
+            let action = PatchAction::Attach {
+                prop,
+                moved,
+                conflict,
+            };
+            self.push(Patch { obj, path, action })
+        }
+    }
+
     pub(crate) fn increment(&mut self, obj: ExId, prop: Prop, tagged_value: (i64, ExId)) {
+        if prop.as_str().is_some_and(|key| self.reorder(&obj, key)) {
+            return;
+        }
         if let Some(path) = self.get_path(&obj) {
             let value = tagged_value.0;
             let action = PatchAction::Increment { prop, value };
@@ -343,6 +466,13 @@ impl PatchBuilder<'_> {
     }
 
     pub(crate) fn flag_conflict(&mut self, obj: ExId, prop: Prop) {
+        if self.is_set(&obj) {
+            // concurrent adds of the same value are not a conflict
+            return;
+        }
+        if prop.as_str().is_some_and(|key| self.reorder(&obj, key)) {
+            return;
+        }
         let conflict = match maybe_append(&mut self.patches, &obj) {
             Some(PatchAction::PutMap { key, conflict, .. })
                 if Some(key.as_str()) == prop.as_str() =>
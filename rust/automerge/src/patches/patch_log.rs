@@ -5,7 +5,7 @@ use crate::marks::{MarkAccumulator, MarkSet};
 use crate::op_set2::PropRef;
 use crate::transaction::TransactionArgs;
 use crate::types::{ActorId, Clock, ObjId, ObjType, OpId, Prop, SequenceType, TextEncoding};
-use crate::{ChangeHash, Patch};
+use crate::{ChangeHash, Patch, PatchAction};
 use std::collections::{BTreeMap, BTreeSet, HashSet};
 use std::sync::Arc;
 
@@ -106,6 +106,15 @@ pub(crate) enum Event {
     Mark {
         marks: MarkAccumulator,
     },
+    Move {
+        from: usize,
+        to: usize,
+    },
+    Attach {
+        prop: Prop,
+        moved: OpId,
+        conflict: bool,
+    },
 }
 
 impl Event {
@@ -154,6 +163,15 @@ impl Event {
                 n,
                 id: id.with_new_actor(idx),
             },
+            Self::Attach {
+                prop,
+                moved,
+                conflict,
+            } => Self::Attach {
+                prop,
+                moved: moved.with_new_actor(idx),
+                conflict,
+            },
             event => event,
         }
     }
@@ -208,6 +226,15 @@ impl Event {
                 n,
                 id: id.without_actor(idx)?,
             },
+            Self::Attach {
+                prop,
+                moved,
+                conflict,
+            } => Self::Attach {
+                prop,
+                moved: moved.without_actor(idx)?,
+                conflict,
+            },
             event => event,
         })
     }
@@ -287,12 +314,22 @@ impl PatchLog {
     /// concrete patches here preserves both their ordering and their paths, and
     /// lets them be safely concatenated with patches from subsequent views.
     pub(crate) fn finish_current_view(&mut self, doc: &Automerge, heads: &[ChangeHash]) {
+        if !self.events.is_empty() || !self.expose.is_empty() {
+            let previous_heads = self.heads.replace(heads.to_vec());
+            self.finish_events(doc);
+            self.heads = previous_heads;
+        }
+    }
+
+    /// Turns the events recorded so far into patches, with paths at the heads of the log.
+    ///
+    /// Moving an object changes the paths of everything in it, so this is done before an object
+    /// is moved to give the patches which precede the move the paths they had at the time.
+    pub(crate) fn finish_events(&mut self, doc: &Automerge) {
         if !self.events.is_empty() || !self.expose.is_empty() {
             self.migrate_actors(&doc.ops.actors)
                 .expect("AutoCommit's patch log always belongs to its document");
-            let previous_heads = self.heads.replace(heads.to_vec());
             let patches = self.make_current_patches(doc);
-            self.heads = previous_heads;
             self.completed_patches.extend(patches);
             self.events.clear();
             self.expose.clear();
@@ -301,6 +338,36 @@ impl PatchLog {
         }
     }
 
+    /// Detaches the object at `path` (see [`PatchAction::Detach`]), after the patches made so
+    /// far. Call [`Self::finish_events`] first if the log has pending events.
+    pub(crate) fn detach_at(&mut self, path: Vec<(ExId, Prop)>, moved: ExId) {
+        self.push_at(path, |prop| PatchAction::Detach { prop, moved });
+    }
+
+    /// Attaches a detached object at `path`, see [`Self::detach_at`]
+    pub(crate) fn attach_at(&mut self, path: Vec<(ExId, Prop)>, moved: ExId, conflict: bool) {
+        self.push_at(path, |prop| PatchAction::Attach {
+            prop,
+            moved,
+            conflict,
+        });
+    }
+
+    /// Deletes the value at `path`, see [`Self::detach_at`]
+    pub(crate) fn delete_at(&mut self, path: Vec<(ExId, Prop)>) {
+        self.push_at(path, |prop| match prop {
+            Prop::Map(key) => PatchAction::DeleteMap { key },
+            Prop::Seq(index) => PatchAction::DeleteSeq { index, length: 1 },
+        });
+    }
+
+    fn push_at<F: FnOnce(Prop) -> PatchAction>(&mut self, mut path: Vec<(ExId, Prop)>, action: F) {
+        if let Some((obj, prop)) = path.pop() {
+            let action = action(prop);
+            self.completed_patches.push(Patch { obj, path, action });
+        }
+    }
+
     pub(crate) fn delete_seq(&mut self, obj: ObjId, index: usize, num: usize) {
         self.push_event(obj, Event::DeleteSeq { index, num })
     }
@@ -331,6 +398,21 @@ impl PatchLog {
         self.push_event(obj, Event::IncrementSeq { index, n, id })
     }
 
+    pub(crate) fn move_seq(&mut self, obj: ObjId, from: usize, to: usize) {
+        self.push_event(obj, Event::Move { from, to })
+    }
+
+    pub(crate) fn attach(&mut self, obj: ObjId, prop: Prop, moved: OpId, conflict: bool) {
+        self.push_event(
+            obj,
+            Event::Attach {
+                prop,
+                moved,
+                conflict,
+            },
+        )
+    }
+
     pub(crate) fn flag_conflict(&mut self, obj: ObjId, prop: &Prop) {
         match prop {
             Prop::Map(key) => self.flag_conflict_map(obj, key),
@@ -511,17 +593,23 @@ impl PatchLog {
         let clock = self.heads.as_ref().map(|h| doc.change_graph.clock_at(h));
         let path_map = self.get_path_map();
         let text_encoding = doc.text_encoding();
-        self.events
-            .sort_by(|(obj_a, _), (obj_b, _)| obj_a.cmp(obj_b));
-        let mut expose = ExposeQueue(self.expose.iter().map(|id| doc.id_to_exid(*id)).collect());
         let mut patch_builder = PatchBuilder::new(doc, path_map, clock.clone(), text_encoding);
+        // parents must come before their children, which for moved objects is not id order
+        self.events
+            .sort_by_cached_key(|(obj, _)| (patch_builder.depth(obj), *obj));
+        let mut expose = ExposeQueue(
+            self.expose
+                .iter()
+                .map(|id| (patch_builder.depth(&ObjId(*id)), doc.id_to_exid(*id)))
+                .collect(),
+        );
         for (obj, event) in &self.events {
-            let key = doc.id_to_exid(obj.0);
+            let key = (patch_builder.depth(obj), doc.id_to_exid(obj.0));
             expose.pump_queue(&key, &mut patch_builder, doc, clock.as_ref());
             if expose.should_skip(&key) {
                 continue;
             }
-            patch_builder.log_event(doc, key, event);
+            patch_builder.log_event(doc, key.1, event);
         }
         expose.flush_queue(&mut patch_builder, doc, clock.as_ref());
         patch_builder.take_patches()
@@ -675,11 +763,12 @@ impl AsRef<OpId> for &(ObjId, Event) {
     }
 }
 
+/// The objects to expose, ordered by their depth (see [`PatchBuilder::depth`]) and id
 #[derive(Clone, Default, PartialEq, Debug)]
-struct ExposeQueue(BTreeSet<ExId>);
+struct ExposeQueue(BTreeSet<(usize, ExId)>);
 
 impl ExposeQueue {
-    fn should_skip(&self, obj: &ExId) -> bool {
+    fn should_skip(&self, obj: &(usize, ExId)) -> bool {
         if let Some(exposed) = self.0.first() {
             exposed == obj
         } else {
@@ -689,7 +778,7 @@ impl ExposeQueue {
 
     fn pump_queue(
         &mut self,
-        obj: &ExId,
+        obj: &(usize, ExId),
         patch_builder: &mut PatchBuilder<'_>,
         doc: &Automerge,
         clock: Option<&Clock>,
@@ -713,23 +802,24 @@ impl ExposeQueue {
         }
     }
 
-    fn insert(&mut self, obj: ExId) -> bool {
-        self.0.insert(obj)
+    fn insert(&mut self, obj: ExId, patch_builder: &mut PatchBuilder<'_>) -> bool {
+        let depth = patch_builder.depth(&obj.to_internal_obj());
+        self.0.insert((depth, obj))
     }
 
-    fn remove(&mut self, obj: &ExId) -> bool {
+    fn remove(&mut self, obj: &(usize, ExId)) -> bool {
         self.0.remove(obj)
     }
 
     fn flush_obj(
         &mut self,
-        exid: ExId,
+        (depth, exid): (usize, ExId),
         patch_builder: &mut PatchBuilder<'_>,
         doc: &Automerge,
         clock: Option<&Clock>,
     ) -> Option<()> {
         let id = exid.to_internal_obj();
-        self.remove(&exid);
+        self.remove(&(depth, exid.clone()));
         match doc.ops().object_type(&id)? {
             ObjType::Text => {
                 let text = doc.text_for(&exid, clock.cloned()).ok()?;
@@ -743,17 +833,17 @@ impl ExposeQueue {
                     let conflict = item.conflict;
                     let index = item.index;
                     if value.is_object() {
-                        self.insert(id.clone());
+                        self.insert(id.clone(), patch_builder);
                     }
                     patch_builder.insert(exid.clone(), index, (value, id), conflict);
                 }
             }
-            ObjType::Map | ObjType::Table => {
+            ObjType::Map | ObjType::Table | ObjType::Set | ObjType::OrderedMap => {
                 for m in doc.map_range_for(&exid, .., clock.cloned()) {
                     let value = m.value.to_value();
                     let id = m.id();
                     if value.is_object() {
-                        self.insert(id.clone());
+                        self.insert(id.clone(), patch_builder);
                     }
                     patch_builder.put(exid.clone(), m.key.into(), (value, id), m.conflict);
                 }
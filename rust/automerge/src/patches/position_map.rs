@@ -0,0 +1,209 @@
+use crate::{MoveCursor, ObjId};
+
+use super::{Patch, PatchAction};
+
+/// Maps positions in a sequence through a series of edits
+///
+/// A position is a gap between two elements of a text or list, from `0` before the first element
+/// to `length` after the last one, as used for the selection in an editor. A [`PositionMap`] is
+/// built from the [`Patch`]es to a text or list object, for example those returned by
+/// [`AutoCommit::diff_incremental`](crate::AutoCommit::diff_incremental), and maps positions from
+/// before the patches to positions after them without creating a [`Cursor`](crate::Cursor) for
+/// every position.
+///
+/// Where content is inserted at a position the bias decides which side of the new content the
+/// position ends up on, [`MoveCursor::Before`] keeps it before the insertion and
+/// [`MoveCursor::After`] moves it after. Positions inside deleted content are moved to where the
+/// content was, and then placed before or after anything inserted in its place according to the
+/// bias.
+///
+/// ```
+/// # use automerge::{AutoCommit, MoveCursor, ObjType, PositionMap, ROOT, transaction::Transactable};
+/// let mut doc = AutoCommit::new();
+/// let text = doc.put_object(ROOT, "text", ObjType::Text)?;
+/// doc.splice_text(&text, 0, 0, "hello world")?;
+/// doc.update_diff_cursor();
+///
+/// doc.splice_text(&text, 0, 5, "goodbye")?;
+/// doc.splice_text(&text, 13, 0, "!")?;
+/// let map = PositionMap::from_patches(&text, &doc.diff_incremental());
+/// // the end of "world" was at 11 and is now at 13
+/// assert_eq!(map.map(11, MoveCursor::Before), 13);
+/// assert_eq!(map.map(11, MoveCursor::After), 14);
+/// # Ok::<(), automerge::AutomergeError>(())
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PositionMap {
+    steps: Vec<Step>,
+}
+
+/// A single edit: `deleted` elements at `index` were replaced by `inserted` elements
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Step {
+    index: usize,
+    deleted: usize,
+    inserted: usize,
+}
+
+/// The result of mapping a position with [`PositionMap::map_result`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MappedPosition {
+    /// The mapped position
+    pub pos: usize,
+    /// Whether the content on both sides of the original position was deleted by some edit, in
+    /// which case the position no longer points at anything which existed before
+    pub deleted: bool,
+}
+
+impl PositionMap {
+    /// An empty map which maps every position to itself
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build a map from the patches to the sequence `obj`, in the order they were produced.
+    /// Patches to other objects are ignored.
+    pub fn from_patches<'a, I: IntoIterator<Item = &'a Patch>>(obj: &ObjId, patches: I) -> Self {
+        let mut map = Self::new();
+        for patch in patches {
+            if &patch.obj == obj {
+                map.add_patch(patch);
+            }
+        }
+        map
+    }
+
+    /// Add the edit made by `patch` to the end of the map. Patches which do not change the
+    /// length of a sequence, such as marks or updates to existing elements, are ignored.
+    pub fn add_patch(&mut self, patch: &Patch) {
+        match &patch.action {
+            PatchAction::SpliceText { index, value, .. } => self.splice(*index, 0, value.len()),
+            PatchAction::Insert { index, values } => self.splice(*index, 0, values.len()),
+            PatchAction::DeleteSeq { index, length } => self.splice(*index, *length, 0),
+            _ => {}
+        }
+    }
+
+    /// Add an edit which replaces `deleted` elements at `index` with `inserted` elements
+    pub fn splice(&mut self, index: usize, deleted: usize, inserted: usize) {
+        if deleted > 0 || inserted > 0 {
+            self.steps.push(Step {
+                index,
+                deleted,
+                inserted,
+            });
+        }
+    }
+
+    /// Add the edits in `other`, which happened after the edits in this map
+    pub fn append(&mut self, other: PositionMap) {
+        self.steps.extend(other.steps);
+    }
+
+    /// Whether this map has no edits in it
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Map `pos` through all the edits in this map
+    pub fn map(&self, pos: usize, bias: MoveCursor) -> usize {
+        self.map_result(pos, bias).pos
+    }
+
+    /// Map `pos` through all the edits in this map and report whether the content around it was
+    /// deleted
+    pub fn map_result(&self, pos: usize, bias: MoveCursor) -> MappedPosition {
+        let mut result = MappedPosition {
+            pos,
+            deleted: false,
+        };
+        for step in &self.steps {
+            let end = step.index + step.deleted;
+            if result.pos < step.index {
+                continue;
+            }
+            if result.pos > end {
+                result.pos = result.pos - step.deleted + step.inserted;
+                continue;
+            }
+            // the position is at the edit, or inside the deleted content
+            let after = if step.deleted == 0 {
+                bias == MoveCursor::After
+            } else if result.pos == step.index {
+                false
+            } else if result.pos == end {
+                true
+            } else {
+                result.deleted = true;
+                bias == MoveCursor::After
+            };
+            result.pos = if after {
+                step.index + step.inserted
+            } else {
+                step.index
+            };
+        }
+        result
+    }
+}
+
+impl Extend<PositionMap> for PositionMap {
+    fn extend<T: IntoIterator<Item = PositionMap>>(&mut self, iter: T) {
+        for map in iter {
+            self.append(map);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insertion_respects_bias() {
+        let mut map = PositionMap::new();
+        map.splice(3, 0, 2);
+        assert_eq!(map.map(2, MoveCursor::After), 2);
+        assert_eq!(map.map(3, MoveCursor::Before), 3);
+        assert_eq!(map.map(3, MoveCursor::After), 5);
+        assert_eq!(map.map(4, MoveCursor::Before), 6);
+    }
+
+    #[test]
+    fn deletion_collapses_positions() {
+        let mut map = PositionMap::new();
+        // "abcdef" -> "aXf"
+        map.splice(1, 4, 1);
+        for bias in [MoveCursor::Before, MoveCursor::After] {
+            let start = map.map_result(1, bias.clone());
+            assert_eq!(start.pos, 1);
+            assert!(!start.deleted);
+            let end = map.map_result(5, bias.clone());
+            assert_eq!(end.pos, 2);
+            assert!(!end.deleted);
+            assert_eq!(map.map(6, bias.clone()), 3);
+        }
+        assert_eq!(
+            map.map_result(3, MoveCursor::Before),
+            MappedPosition {
+                pos: 1,
+                deleted: true
+            }
+        );
+        assert_eq!(map.map(3, MoveCursor::After), 2);
+    }
+
+    #[test]
+    fn maps_compose() {
+        let mut first = PositionMap::new();
+        first.splice(0, 0, 3);
+        let mut second = PositionMap::new();
+        second.splice(5, 2, 0);
+        first.append(second);
+        // 4 -> 7 -> 5 (at the end of the deleted range)
+        assert_eq!(first.map(4, MoveCursor::Before), 5);
+        assert_eq!(first.map(1, MoveCursor::Before), 4);
+        assert_eq!(first.map(3, MoveCursor::After), 5);
+        assert!(first.map_result(3, MoveCursor::After).deleted);
+    }
+}
@@ -1,16 +1,19 @@
 use crate::{
-    cursor::{CursorPosition, MoveCursor},
+    bounded_counter::BoundedCounter,
+    cursor::{CursorPosition, CursorRange, CursorRangePosition, MoveCursor},
     error::AutomergeError,
     exid::ExId,
     hydrate,
-    marks::{Mark, MarkSet},
+    marks::{ExpandMark, Mark, MarkQuery, MarkSet},
     op_set2::Parents,
-    Change, ChangeHash, Cursor, ObjType, Prop, TextEncoding, Value, ROOT,
+    Change, ChangeHash, Cursor, ObjType, Prop, ScalarValue, TextEncoding, Value, ROOT,
 };
 
-use crate::iter::{DocIter, Keys, ListRange, MapRange, Spans, Values};
+use crate::iter::{
+    DocItem, DocIter, DocObjItem, Keys, ListRange, MapRange, SetValues, Spans, Values,
+};
 
-use std::ops::RangeBounds;
+use std::ops::{Range, RangeBounds};
 
 /// Methods for reading values from an automerge document
 ///
@@ -136,6 +139,37 @@ pub trait ReadDoc {
     /// See [`Self::values()`]
     fn values_at<O: AsRef<ExId>>(&self, obj: O, heads: &[ChangeHash]) -> Values<'_>;
 
+    /// Whether `value` is a member of the set `obj`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AutomergeError::InvalidOp`] if `obj` is not a set
+    fn set_contains<O: AsRef<ExId>>(
+        &self,
+        obj: O,
+        value: &ScalarValue,
+    ) -> Result<bool, AutomergeError>;
+
+    /// Whether `value` is a member of the set `obj` as at `heads`
+    ///
+    /// See [`Self::set_contains()`]
+    fn set_contains_at<O: AsRef<ExId>>(
+        &self,
+        obj: O,
+        value: &ScalarValue,
+        heads: &[ChangeHash],
+    ) -> Result<bool, AutomergeError>;
+
+    /// Iterate over the values in the set `obj`
+    ///
+    /// If `obj` is not a set this returns an empty iterator
+    fn set_values<O: AsRef<ExId>>(&self, obj: O) -> SetValues<'_>;
+
+    /// Iterate over the values in the set `obj` as at `heads`
+    ///
+    /// See [`Self::set_values()`]
+    fn set_values_at<O: AsRef<ExId>>(&self, obj: O, heads: &[ChangeHash]) -> SetValues<'_>;
+
     /// Get the length of the given object.
     ///
     /// If the given object is not in this document this method will return `0`
@@ -168,6 +202,45 @@ pub trait ReadDoc {
         heads: Option<&[ChangeHash]>,
     ) -> Result<MarkSet, AutomergeError>;
 
+    /// Get the marks on a sequence which match `query` as at `heads`
+    ///
+    /// With a range in the query only the parts of marks within the range are returned. For the
+    /// current state of a text object this is served from the mark index and only visits the
+    /// mark boundaries inside the range.
+    fn query_marks<O: AsRef<ExId>>(
+        &self,
+        obj: O,
+        query: &MarkQuery,
+        heads: Option<&[ChangeHash]>,
+    ) -> Result<Vec<Mark>, AutomergeError>;
+
+    /// The first position after `index` where the value of the mark `name` differs from its
+    /// value at `index`, as at `heads`
+    ///
+    /// Returns `None` if the mark does not change before the end of the sequence.
+    fn next_mark_change<O: AsRef<ExId>>(
+        &self,
+        obj: O,
+        name: &str,
+        index: usize,
+        heads: Option<&[ChangeHash]>,
+    ) -> Result<Option<usize>, AutomergeError>;
+
+    /// The smallest range containing `range` whose ends are on grapheme cluster boundaries of
+    /// the text object `obj`, as at `heads`
+    ///
+    /// Indexes are in the text encoding of the document. Editors can use this to keep a selection
+    /// or an edit from splitting a user perceived character, such as an emoji made of several
+    /// code points or a surrogate pair in UTF-16. See also [`Transactable::splice_graphemes`].
+    ///
+    /// [`Transactable::splice_graphemes`]: crate::transaction::Transactable::splice_graphemes
+    fn grapheme_range<O: AsRef<ExId>>(
+        &self,
+        obj: O,
+        range: Range<usize>,
+        heads: Option<&[ChangeHash]>,
+    ) -> Result<Range<usize>, AutomergeError>;
+
     /// Get the string represented by the given text object.
     fn text<O: AsRef<ExId>>(&self, obj: O) -> Result<String, AutomergeError>;
 
@@ -237,6 +310,55 @@ pub trait ReadDoc {
         at: Option<&[ChangeHash]>,
     ) -> Result<usize, AutomergeError>;
 
+    /// Translate many cursors in a Sequence into absolute positions, in a single pass over the
+    /// sequence.
+    ///
+    /// This returns the same positions as calling [`Self::get_cursor_position()`] for each cursor
+    /// but is much faster when there are many cursors, such as the anchors of comments in a
+    /// document. The positions are returned in the same order as `cursors`.
+    fn get_cursor_positions<O: AsRef<ExId>>(
+        &self,
+        obj: O,
+        cursors: &[Cursor],
+        at: Option<&[ChangeHash]>,
+    ) -> Result<Vec<usize>, AutomergeError>;
+
+    /// Obtain a [`CursorRange`] which follows `range` in a Sequence as it is edited.
+    ///
+    /// `expand` decides whether content inserted at the start or end of the range is included in
+    /// it, like it does for a [`Mark`].
+    fn get_cursor_range<O: AsRef<ExId>>(
+        &self,
+        obj: O,
+        range: Range<usize>,
+        expand: ExpandMark,
+        at: Option<&[ChangeHash]>,
+    ) -> Result<CursorRange, AutomergeError>;
+
+    /// Translate a [`CursorRange`] into the range of positions it covers.
+    ///
+    /// If everything in the range has been deleted the range is empty and
+    /// [`CursorRangePosition::collapsed`] is `true`.
+    fn get_cursor_range_position<O: AsRef<ExId>>(
+        &self,
+        obj: O,
+        range: &CursorRange,
+        at: Option<&[ChangeHash]>,
+    ) -> Result<CursorRangePosition, AutomergeError> {
+        let mut positions =
+            self.get_cursor_range_positions(obj, std::slice::from_ref(range), at)?;
+        Ok(positions.remove(0))
+    }
+
+    /// Translate many [`CursorRange`]s into the ranges of positions they cover, in a single pass
+    /// over the sequence. The positions are returned in the same order as `ranges`.
+    fn get_cursor_range_positions<O: AsRef<ExId>>(
+        &self,
+        obj: O,
+        ranges: &[CursorRange],
+        at: Option<&[ChangeHash]>,
+    ) -> Result<Vec<CursorRangePosition>, AutomergeError>;
+
     /// Get a value out of the document.
     ///
     /// This returns a tuple of `(value, object ID)`. This is for two reasons:
@@ -293,6 +415,66 @@ pub trait ReadDoc {
         heads: &[ChangeHash],
     ) -> Result<Vec<(Value<'_>, ExId)>, AutomergeError>;
 
+    /// The props of the map or list `obj` which have conflicting values
+    ///
+    /// The values of each prop can be read with [`Self::get_all`]. Text and sets never have
+    /// conflicting values, so this is always empty for them.
+    fn conflicts<O: AsRef<ExId>>(&self, obj: O) -> Result<Vec<Prop>, AutomergeError> {
+        conflicts_in(self, obj.as_ref(), None)
+    }
+
+    /// The props of the map or list `obj` which had conflicting values as at `heads`
+    ///
+    /// See [`Self::conflicts()`]
+    fn conflicts_at<O: AsRef<ExId>>(
+        &self,
+        obj: O,
+        heads: &[ChangeHash],
+    ) -> Result<Vec<Prop>, AutomergeError> {
+        conflicts_in(self, obj.as_ref(), Some(heads))
+    }
+
+    /// Every prop in the document which has conflicting values, with the object it is in
+    ///
+    /// Objects are visited in the order of [`Self::iter()`], so parents come before their
+    /// children. See [`Self::conflicts()`]
+    fn all_conflicts(&self) -> Vec<(ExId, Prop)> {
+        all_conflicts_in(self, None)
+    }
+
+    /// Every prop in the document which had conflicting values as at `heads`
+    ///
+    /// See [`Self::all_conflicts()`]
+    fn all_conflicts_at(&self, heads: &[ChangeHash]) -> Vec<(ExId, Prop)> {
+        all_conflicts_in(self, Some(heads))
+    }
+
+    /// Read the map `obj` as a bounded counter, see [`crate::bounded_counter`]
+    fn bounded_counter<O: AsRef<ExId>>(&self, obj: O) -> Result<BoundedCounter, AutomergeError> {
+        match self.object_type(obj.as_ref())? {
+            ObjType::Map => Ok(BoundedCounter::from_items(self.map_range(obj, ..))),
+            typ => Err(AutomergeError::InvalidOp(typ)),
+        }
+    }
+
+    /// Read the map `obj` as a bounded counter as at `heads`
+    ///
+    /// See [`Self::bounded_counter()`]
+    fn bounded_counter_at<O: AsRef<ExId>>(
+        &self,
+        obj: O,
+        heads: &[ChangeHash],
+    ) -> Result<BoundedCounter, AutomergeError> {
+        match self.object_type(obj.as_ref())? {
+            ObjType::Map => Ok(BoundedCounter::from_items(self.map_range_at(
+                obj,
+                ..,
+                heads,
+            ))),
+            typ => Err(AutomergeError::InvalidOp(typ)),
+        }
+    }
+
     /// Get the hashes of the changes in this document that aren't transitive dependencies of the
     /// given `heads`.
     fn get_missing_deps(&self, heads: &[ChangeHash]) -> Vec<ChangeHash>;
@@ -303,9 +485,64 @@ pub trait ReadDoc {
     /// Return some statistics about the document
     fn stats(&self) -> Stats;
 
+    /// Return a breakdown of what the document is made of
+    ///
+    /// This walks every op in the document so it is much more expensive than [`Self::stats()`].
+    /// It is intended for finding out which part of a document is taking up space.
+    fn detailed_stats(&self) -> DetailedStats;
+
     fn text_encoding(&self) -> TextEncoding;
 }
 
+fn conflicts_in<R: ReadDoc + ?Sized>(
+    doc: &R,
+    obj: &ExId,
+    heads: Option<&[ChangeHash]>,
+) -> Result<Vec<Prop>, AutomergeError> {
+    let conflicts = match doc.object_type(obj)? {
+        ObjType::Map | ObjType::Table | ObjType::OrderedMap => {
+            let range = match heads {
+                Some(heads) => doc.map_range_at(obj, .., heads),
+                None => doc.map_range(obj, ..),
+            };
+            range
+                .filter(|item| item.conflict)
+                .map(|item| Prop::Map(item.key.into_owned()))
+                .collect()
+        }
+        ObjType::List => {
+            let range = match heads {
+                Some(heads) => doc.list_range_at(obj, .., heads),
+                None => doc.list_range(obj, ..),
+            };
+            range
+                .filter(|item| item.conflict)
+                .map(|item| Prop::Seq(item.index))
+                .collect()
+        }
+        ObjType::Text | ObjType::Set => vec![],
+    };
+    Ok(conflicts)
+}
+
+fn all_conflicts_in<R: ReadDoc + ?Sized>(
+    doc: &R,
+    heads: Option<&[ChangeHash]>,
+) -> Vec<(ExId, Prop)> {
+    doc.iter_at(ROOT, heads)
+        .filter_map(|DocObjItem { obj, item }| {
+            let prop = match item {
+                DocItem::Map(item) if item.conflict => Prop::Map(item.key.into_owned()),
+                DocItem::List(item) if item.conflict => Prop::Seq(item.index),
+                _ => return None,
+            };
+            let obj = ExId::clone(&obj);
+            // the members of a set are stored as map keys, which are never in conflict
+            (doc.object_type(&obj).ok() != Some(ObjType::Set)).then_some((obj, prop))
+        })
+        .collect()
+}
+
 /// Statistics about the document
 ///
 /// This is returned by [`ReadDoc::stats()`]
@@ -324,3 +561,86 @@ pub struct Stats {
     /// version of rustc used to compile this
     pub rustc_version: &'static str,
 }
+
+/// A breakdown of the contents of a document
+///
+/// This is returned by [`ReadDoc::detailed_stats()`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct DetailedStats {
+    pub stats: Stats,
+    /// Every object in the document, ordered from the object with the most ops to the object
+    /// with the fewest
+    pub objects: Vec<ObjectStats>,
+    /// Every actor in the document, in the order of the actor table of the document
+    pub actors: Vec<ActorStats>,
+    /// Every change in the document in causal order
+    pub changes: Vec<ChangeStats>,
+    /// The in-memory columns the ops are stored in
+    pub columns: Vec<ColumnStats>,
+}
+
+impl DetailedStats {
+    /// The `n` objects with the most ops
+    pub fn largest_objects(&self, n: usize) -> &[ObjectStats] {
+        &self.objects[..n.min(self.objects.len())]
+    }
+
+    /// The total number of bytes of all the changes in the document
+    pub fn change_bytes(&self) -> usize {
+        self.changes.iter().map(|c| c.bytes).sum()
+    }
+
+    /// The total number of bytes held in memory by the op columns
+    pub fn column_bytes(&self) -> usize {
+        self.columns.iter().map(|c| c.bytes).sum()
+    }
+}
+
+/// The ops in a single object
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjectStats {
+    pub obj: ExId,
+    pub obj_type: ObjType,
+    /// All the ops stored in the object, including deleted ops, increments and marks
+    pub num_ops: usize,
+    /// The ops which are currently visible, including conflicting values
+    pub live_ops: usize,
+    /// The ops which have been deleted or overwritten but are kept around as part of the history
+    pub tombstones: usize,
+    /// The number of marks in a text object or list
+    pub marks: usize,
+    /// The length of the object as returned by [`ReadDoc::length()`], for text this is in the
+    /// text encoding of the document
+    pub length: usize,
+}
+
+/// The contribution of a single actor to a document
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActorStats {
+    pub actor: crate::ActorId,
+    pub num_changes: usize,
+    /// The number of ops stored in the document which were created by this actor
+    pub num_ops: usize,
+    /// The total size of the changes created by this actor when encoded as individual changes
+    pub change_bytes: usize,
+}
+
+/// The size of a single change
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChangeStats {
+    pub hash: ChangeHash,
+    pub actor: crate::ActorId,
+    pub seq: u64,
+    /// The number of ops in the change, including deletions
+    pub num_ops: usize,
+    /// The size of the change when encoded as an individual change chunk
+    pub bytes: usize,
+}
+
+/// The in-memory size of a column of ops
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnStats {
+    pub name: &'static str,
+    /// The number of bytes of encoded data in the column
+    pub bytes: usize,
+}
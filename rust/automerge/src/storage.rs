@@ -1,22 +1,28 @@
 use std::ops::Range;
 
+pub(crate) mod base;
 pub(crate) mod bundle;
 pub(crate) mod change;
 mod chunk;
+mod codec;
 pub(crate) mod columns;
 pub(crate) mod document;
+mod layout;
 pub(crate) mod load;
 pub(crate) mod parse;
 
 pub use bundle::{Bundle, BundleChange, BundleChangeIter};
+pub use codec::{Codec, ColumnGroup, Compression};
 pub use load::VerificationMode;
 
 pub(crate) use {
+    base::Base,
     bundle::{BundleMetadata, BundleStorage},
     change::{AsChangeOp, Change, ChangeOp, Compressed, ReadChangeOpError},
     chunk::{CheckSum, Chunk, ChunkType, Header},
     columns::{ColumnSpec, Columns, RawColumn, RawColumns},
-    document::{CompressConfig, Document},
+    document::Document,
+    layout::{column_layout, StoredColumn},
 };
 
 fn shift_range(range: Range<usize>, by: usize) -> Range<usize> {
@@ -0,0 +1,178 @@
+use super::{parse, ChunkType, Header};
+use crate::ChangeHash;
+
+/// The base of a shallow document
+///
+/// A shallow document is saved as a base chunk followed by a document chunk. The first
+/// `stubs.len()` rows of the change columns in the document chunk are stubs which stand in for the
+/// truncated history, they have no ops and no dependencies and their hashes cannot be derived from
+/// the document so they are stored here. All actor indices refer to the actor table of the
+/// following document chunk.
+///
+/// ```text
+/// .-----------------------------.
+/// | base heads                  |
+/// | stubs: hash, clock          |
+/// | per actor: truncated seq,   |
+/// |   (seq, max_op) pairs       |
+/// '-----------------------------'
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Base {
+    header: Header,
+    /// The heads the history was truncated at
+    pub(crate) heads: Vec<ChangeHash>,
+    pub(crate) stubs: Vec<Stub>,
+    /// Indexed by actor
+    pub(crate) actors: Vec<TruncatedActor>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Stub {
+    pub(crate) hash: ChangeHash,
+    /// The `(actor, seq)` pairs of the clock of this stub
+    pub(crate) clock: Vec<(u32, u32)>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct TruncatedActor {
+    /// The number of changes by this actor which were truncated
+    pub(crate) seq: u32,
+    /// The max op of each truncated change by this actor which is referenced by a stub clock,
+    /// sorted by seq
+    pub(crate) max_ops: Vec<(u32, u32)>,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub(crate) enum ParseError {
+    #[error(transparent)]
+    Leb128(#[from] parse::leb128::Error),
+}
+
+impl Base {
+    pub(crate) fn new(
+        heads: Vec<ChangeHash>,
+        stubs: Vec<Stub>,
+        actors: Vec<TruncatedActor>,
+    ) -> Self {
+        let mut base = Base {
+            header: Header::new(ChunkType::Base, &[]),
+            heads,
+            stubs,
+            actors,
+        };
+        base.header = Header::new(ChunkType::Base, &base.data());
+        base
+    }
+
+    /// Parse a base chunk, the header must already have been parsed
+    pub(crate) fn parse(
+        input: parse::Input<'_>,
+        header: Header,
+    ) -> parse::ParseResult<'_, Base, ParseError> {
+        let (i, heads) = parse::length_prefixed(parse::change_hash)(input)?;
+        let (i, stubs) = parse::length_prefixed(|i| {
+            let (i, hash) = parse::change_hash(i)?;
+            let (i, clock) = parse::length_prefixed(pair)(i)?;
+            Ok((i, Stub { hash, clock }))
+        })(i)?;
+        let (i, actors) = parse::length_prefixed(|i| {
+            let (i, seq) = parse::leb128_u32(i)?;
+            let (i, max_ops) = parse::length_prefixed(pair)(i)?;
+            Ok((i, TruncatedActor { seq, max_ops }))
+        })(i)?;
+        Ok((
+            i,
+            Base {
+                header,
+                heads,
+                stubs,
+                actors,
+            },
+        ))
+    }
+
+    pub(crate) fn checksum_valid(&self) -> bool {
+        self.header.checksum_valid()
+    }
+
+    fn data(&self) -> Vec<u8> {
+        let mut data = Vec::new();
+        write_len(&mut data, self.heads.len());
+        for head in &self.heads {
+            data.extend(head.as_bytes());
+        }
+        write_len(&mut data, self.stubs.len());
+        for stub in &self.stubs {
+            data.extend(stub.hash.as_bytes());
+            write_pairs(&mut data, &stub.clock);
+        }
+        write_len(&mut data, self.actors.len());
+        for actor in &self.actors {
+            leb128::write::unsigned(&mut data, actor.seq as u64).unwrap();
+            write_pairs(&mut data, &actor.max_ops);
+        }
+        data
+    }
+
+    pub(crate) fn into_bytes(self) -> Vec<u8> {
+        let data = self.data();
+        let mut bytes = Vec::with_capacity(data.len() + self.header.len());
+        self.header.write(&mut bytes);
+        bytes.extend(data);
+        bytes
+    }
+}
+
+fn pair(i: parse::Input<'_>) -> parse::ParseResult<'_, (u32, u32), ParseError> {
+    let (i, a) = parse::leb128_u32(i)?;
+    let (i, b) = parse::leb128_u32(i)?;
+    Ok((i, (a, b)))
+}
+
+fn write_len(out: &mut Vec<u8>, len: usize) {
+    leb128::write::unsigned(out, len as u64).unwrap();
+}
+
+fn write_pairs(out: &mut Vec<u8>, pairs: &[(u32, u32)]) {
+    write_len(out, pairs.len());
+    for (a, b) in pairs {
+        leb128::write::unsigned(out, *a as u64).unwrap();
+        leb128::write::unsigned(out, *b as u64).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::Chunk;
+
+    #[test]
+    fn base_chunk_roundtrips() {
+        let base = Base::new(
+            vec![ChangeHash([1; 32])],
+            vec![Stub {
+                hash: ChangeHash([1; 32]),
+                clock: vec![(0, 3), (1, 7)],
+            }],
+            vec![
+                TruncatedActor {
+                    seq: 3,
+                    max_ops: vec![(3, 10)],
+                },
+                TruncatedActor {
+                    seq: 7,
+                    max_ops: vec![(7, 12)],
+                },
+            ],
+        );
+        let bytes = base.clone().into_bytes();
+        let (i, chunk) = Chunk::parse(parse::Input::new(&bytes)).unwrap();
+        assert!(i.is_empty());
+        let Chunk::Base(parsed) = chunk else {
+            panic!("expected a base chunk");
+        };
+        assert!(parsed.checksum_valid());
+        assert_eq!(parsed, base);
+    }
+}
@@ -9,9 +9,8 @@ use crate::op_set2::meta::ValueMeta;
 use crate::op_set2::op::{Op, OpBuilder};
 use crate::op_set2::types::{Action, ActorIdx, KeyRef};
 use crate::op_set2::{ReadOpError, ScalarValue};
-use crate::storage::change::DEFLATE_MIN_SIZE;
 use crate::storage::columns::{compression, ColumnType};
-use crate::storage::{ChunkType, Header, RawColumn, RawColumns};
+use crate::storage::{ChunkType, ColumnGroup, Compression, Header, RawColumn, RawColumns};
 use crate::types::{ChangeHash, ObjId, OpId};
 
 use super::{Bundle, BundleChange, BundleMetadata, BundleStorage, ParseError};
@@ -191,19 +190,25 @@ impl<'a> BundleBuilder<'a> {
         let ops_data_u_range = shift_range(ops_data_start_u..ops_data_end_u, header_u.len());
 
         // ---- Compressed assembly (used as the on-disk/wire form) ----
-        // Per-column DEFLATE above DEFLATE_MIN_SIZE, mirroring Document.
+        // Per-column compression with the default settings, mirroring Document.
+        let compression = Compression::default();
         let mut data_c = prefix;
         let mut compressed_change_data = Vec::new();
         let changes_meta_c = changes_meta.compress(
             &change_data_buf,
             &mut compressed_change_data,
-            DEFLATE_MIN_SIZE,
+            ColumnGroup::Changes,
+            &compression,
         );
         changes_meta_c.write(&mut data_c);
         data_c.extend_from_slice(&compressed_change_data);
         let mut compressed_ops_data = Vec::new();
-        let ops_meta_c =
-            ops_meta.compress(&ops_data_buf, &mut compressed_ops_data, DEFLATE_MIN_SIZE);
+        let ops_meta_c = ops_meta.compress(
+            &ops_data_buf,
+            &mut compressed_ops_data,
+            ColumnGroup::Ops,
+            &compression,
+        );
         ops_meta_c.write(&mut data_c);
         data_c.extend_from_slice(&compressed_ops_data);
 
@@ -344,6 +349,8 @@ pub(crate) struct BundleOpWriter<'a> {
     pred_ctr: hexane::DeltaEncoder<'a, i64>,
     expand: hexane::Encoder<'a, bool>,
     mark_name: hexane::Encoder<'a, Option<String>>,
+    target_actor: hexane::Encoder<'a, Option<ActorIdx>>,
+    target_ctr: hexane::DeltaEncoder<'a, Option<i64>>,
     /// `(actor, counter, doc_pos)` for each op as we process it. At
     /// `finish` time these are sorted by `(actor, counter)` and the
     /// `doc_pos` values are emitted as a delta-int column.
@@ -375,6 +382,9 @@ impl<'a> BundleOpWriter<'a> {
         self.expand.append(op.expand);
         self.mark_name
             .append_owned(op.mark_name.map(|s| s.into_owned()));
+        self.target_actor.append(op.target.and_then(|t| t.actor()));
+        self.target_ctr
+            .append(op.target.and_then(|t| t.counter()).map(|c| c as i64));
         self.inverse_positions
             .push((op.id.actor(), op.id.counter(), doc_pos));
     }
@@ -401,6 +411,8 @@ impl<'a> BundleOpWriter<'a> {
         let pred_ctr = self.pred_ctr.save_to(data);
         let expand = self.expand.save_to_unless(data, false);
         let mark_name = self.mark_name.save_to_unless(data, None);
+        let target_actor = save_opt_actor_unless_empty(self.target_actor, &mapper.mapping, data);
+        let target_ctr = self.target_ctr.save_to_unless(data, None);
 
         // Capture doc-order counters before sorting `inverse_positions`.
         // `add()` populates this Vec in doc order, so element k is the
@@ -445,6 +457,8 @@ impl<'a> BundleOpWriter<'a> {
                 pred_ctr,
                 expand,
                 mark_name,
+                target_actor,
+                target_ctr,
             },
             id_ctr_values,
         )
@@ -469,6 +483,8 @@ pub(crate) struct BundleOpsColumns {
     pub(crate) pred_ctr: Range<usize>,
     pub(crate) expand: Range<usize>,
     pub(crate) mark_name: Range<usize>,
+    pub(crate) target_actor: Range<usize>,
+    pub(crate) target_ctr: Range<usize>,
 }
 
 #[derive(Default)]
@@ -525,6 +541,8 @@ impl BundleOpsColumns {
             (ops::EXPAND, &self.expand),
             (ops::MARK_NAME, &self.mark_name),
             (ops::ID_CTR_INVERSE, &self.id_ctr_inverse),
+            (ops::TARGET_ACTOR, &self.target_actor),
+            (ops::TARGET_CTR, &self.target_ctr),
         ]
         .into_iter()
         .filter(|(_, range)| !range.is_empty())
@@ -753,6 +771,8 @@ struct OpIterInner<'a> {
     pred_ctr: hexane::DeltaDecoder<'a, Option<i64>>,
     expand: hexane::Decoder<'a, bool>,
     mark_name: hexane::Decoder<'a, Option<String>>,
+    target_actor: hexane::Decoder<'a, Option<ActorIdx>>,
+    target_ctr: hexane::DeltaDecoder<'a, Option<i64>>,
     value: &'a [u8],
 }
 
@@ -818,6 +838,12 @@ impl<'a> OpIterInner<'a> {
         let insert = self.insert.next().unwrap_or_default();
         let expand = self.expand.next().unwrap_or_default();
         let mark_name = self.mark_name.next().flatten().map(Cow::Borrowed);
+        let target_actor = self.target_actor.next().flatten();
+        let target_ctr = self.target_ctr.next().flatten();
+        let target = match (target_actor, target_ctr) {
+            (None, None) => None,
+            (actor, ctr) => Some(ObjId::try_load(actor, ctr)?),
+        };
 
         let value_meta = self
             .meta
@@ -847,6 +873,7 @@ impl<'a> OpIterInner<'a> {
             expand,
             mark_name,
             pred,
+            target,
         }))
     }
 
@@ -870,6 +897,8 @@ impl<'a> OpIterInner<'a> {
         let mut pred_ctr = hexane::DeltaDecoder::<Option<i64>>::new(&[]);
         let mut expand = hexane::decoder::<bool>(&[]);
         let mut mark_name = hexane::decoder::<Option<String>>(&[]);
+        let mut target_actor = hexane::decoder::<Option<ActorIdx>>(&[]);
+        let mut target_ctr = hexane::DeltaDecoder::<Option<i64>>::new(&[]);
         let mut value: &[u8] = &[];
 
         for col in columns.iter() {
@@ -904,6 +933,12 @@ impl<'a> OpIterInner<'a> {
                 (ops::MARK_NAME_COL_ID, C::String) => {
                     mark_name = hexane::decoder::<Option<String>>(d)
                 }
+                (ops::TARGET_COL_ID, C::Actor) => {
+                    target_actor = hexane::decoder::<Option<ActorIdx>>(d)
+                }
+                (ops::TARGET_COL_ID, C::DeltaInteger) => {
+                    target_ctr = hexane::DeltaDecoder::<Option<i64>>::new(d)
+                }
                 _ => return Err(ParseError::InvalidOpColumn(u32::from(col.spec()))),
             }
         }
@@ -924,6 +959,8 @@ impl<'a> OpIterInner<'a> {
             pred_ctr,
             expand,
             mark_name,
+            target_actor,
+            target_ctr,
         })
     }
 }
@@ -947,6 +984,7 @@ pub(crate) mod ops {
     /// column plus the change metadata — no separate `ID_CTR` column on
     /// the wire.
     pub(super) const ID_CTR_INVERSE_COL_ID: ColumnId = ColumnId::new(11);
+    pub(super) const TARGET_COL_ID:         ColumnId = ColumnId::new(12);
 
     pub(super) const ID_ACTOR:   ColumnSpec = ColumnSpec::new_actor(ID_COL_ID);
     pub(super) const ID_CTR_INVERSE: ColumnSpec = ColumnSpec::new_delta(ID_CTR_INVERSE_COL_ID);
@@ -964,6 +1002,8 @@ pub(crate) mod ops {
     pub(super) const VALUE:      ColumnSpec = ColumnSpec::new_value(VAL_COL_ID);
     pub(super) const MARK_NAME:  ColumnSpec = ColumnSpec::new_string(MARK_NAME_COL_ID);
     pub(super) const EXPAND:     ColumnSpec = ColumnSpec::new_boolean(EXPAND_COL_ID);
+    pub(super) const TARGET_ACTOR: ColumnSpec = ColumnSpec::new_actor(TARGET_COL_ID);
+    pub(super) const TARGET_CTR: ColumnSpec = ColumnSpec::new_delta(TARGET_COL_ID);
 }
 
 #[rustfmt::skip]
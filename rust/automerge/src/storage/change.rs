@@ -447,6 +447,8 @@ pub(crate) trait AsChangeOp<'a> {
     fn pred(&self) -> Self::PredIter;
     fn expand(&self) -> bool;
     fn mark_name(&self) -> Option<Cow<'a, smol_str::SmolStr>>;
+    /// The object moved by a `MoveObject` op
+    fn target(&self) -> Option<Self::OpId>;
 }
 
 impl ChangeBuilder<Set<NonZeroU64>, Set<ActorId>, Set<u64>, Set<i64>> {
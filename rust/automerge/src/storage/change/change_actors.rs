@@ -89,6 +89,11 @@ where
                             acc.insert(o.actor());
                         }
                     }
+                    if let Some(o) = op.target() {
+                        if o.actor() != &actor {
+                            acc.insert(o.actor());
+                        }
+                    }
                     Ok((count + 1, acc))
                 })?;
         // This shouldn't be necessary but just in case
@@ -251,6 +256,10 @@ where
     fn mark_name(&self) -> Option<Cow<'aschangeop, smol_str::SmolStr>> {
         self.op.mark_name()
     }
+
+    fn target(&self) -> Option<Self::OpId> {
+        self.op.target().map(|o| self.actors.translate_opid(&o))
+    }
 }
 
 pub(crate) struct WithChangeActorsPredIter<'actors, 'aschangeop, A, I, O, C, P> {
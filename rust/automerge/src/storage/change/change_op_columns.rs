@@ -34,6 +34,7 @@ const VAL_COL_ID: ColumnId = ColumnId::new(5);
 const PRED_COL_ID: ColumnId = ColumnId::new(7);
 const EXPAND_COL_ID: ColumnId = ColumnId::new(9);
 const MARK_NAME_COL_ID: ColumnId = ColumnId::new(10);
+const TARGET_COL_ID: ColumnId = ColumnId::new(11);
 
 #[derive(Clone, Debug, PartialEq)]
 pub(crate) struct ChangeOp {
@@ -45,6 +46,7 @@ pub(crate) struct ChangeOp {
     pub(crate) obj: ObjId,
     pub(crate) expand: bool,
     pub(crate) mark_name: Option<smol_str::SmolStr>,
+    pub(crate) target: Option<ObjId>,
 }
 
 impl<'a, A: AsChangeOp<'a, ActorId = usize, OpId = OpId>> From<A> for ChangeOp {
@@ -65,6 +67,7 @@ impl<'a, A: AsChangeOp<'a, ActorId = usize, OpId = OpId>> From<A> for ChangeOp {
             action: a.action(),
             expand: a.expand(),
             mark_name: a.mark_name().map(|n| n.into_owned()),
+            target: a.target().map(ObjId),
         }
     }
 }
@@ -113,6 +116,10 @@ impl<'a> AsChangeOp<'a> for &'a ChangeOp {
     fn mark_name(&self) -> Option<Cow<'a, smol_str::SmolStr>> {
         self.mark_name.as_ref().map(Cow::Borrowed)
     }
+
+    fn target(&self) -> Option<Self::OpId> {
+        self.target.as_ref().and_then(|t| t.id())
+    }
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -125,6 +132,7 @@ pub(crate) struct ChangeOpsColumns {
     pred: OpIdListRange,
     expand: MaybeBooleanRange,
     mark_name: RleRange<smol_str::SmolStr>,
+    target: Option<ObjIdRange>,
 }
 
 use crate::op_set2::change;
@@ -152,6 +160,10 @@ impl From<change::ChangeOpsColumns> for ChangeOpsColumns {
         );
         let expand = MaybeBooleanRange::from(other.expand);
         let mark_name = RleRange::from(other.mark_name);
+        let target = ObjIdRange::new(
+            RleRange::from(other.target_actor),
+            RleRange::from(other.target_ctr),
+        );
         Self {
             obj,
             key,
@@ -161,6 +173,7 @@ impl From<change::ChangeOpsColumns> for ChangeOpsColumns {
             pred,
             expand,
             mark_name,
+            target,
         }
     }
 }
@@ -177,6 +190,7 @@ impl ChangeOpsColumns {
             pred: self.pred.iter(data),
             expand: self.expand.decoder(data),
             mark_name: self.mark_name.decoder(data),
+            target: self.target.as_ref().map(|t| t.iter(data)),
         }
     }
 
@@ -211,8 +225,17 @@ impl ChangeOpsColumns {
         let val = ValueRange::encode(ops.clone().map(|o| o.val()), out);
         let pred = OpIdListRange::encode(ops.clone().map(|o| o.pred()), out);
         let expand = MaybeBooleanRange::encode(ops.clone().map(|o| o.expand()), out);
-        let mark_name =
-            RleRange::encode::<Cow<'_, smol_str::SmolStr>, _>(ops.map(|o| o.mark_name()), out);
+        let mark_name = RleRange::encode::<Cow<'_, smol_str::SmolStr>, _>(
+            ops.clone().map(|o| o.mark_name()),
+            out,
+        );
+        // changes without object moves don't have the target columns, so that they encode the
+        // same way as they did before those columns existed
+        let target = if ops.clone().any(|o| o.target().is_some()) {
+            ObjIdRange::encode(ops.map(|o| target_obj(o.target())), out)
+        } else {
+            None
+        };
         Self {
             obj,
             key,
@@ -222,6 +245,7 @@ impl ChangeOpsColumns {
             pred,
             expand,
             mark_name,
+            target,
         }
     }
 
@@ -239,6 +263,8 @@ impl ChangeOpsColumns {
         let mut pred = OpIdListEncoder::new();
         let mut expand = MaybeBooleanEncoder::new();
         let mut mark_name = RleEncoder::<_, smol_str::SmolStr>::new(Vec::new());
+        let mut target = ObjIdEncoder::new();
+        let mut has_target = false;
         for op in ops {
             tracing::trace!(expand=?op.expand(), "expand");
             obj.append(op.obj());
@@ -249,6 +275,8 @@ impl ChangeOpsColumns {
             pred.append(op.pred());
             expand.append(op.expand());
             mark_name.append(op.mark_name());
+            has_target |= op.target().is_some();
+            target.append(target_obj(op.target()));
         }
         let obj = obj.finish(out);
         let key = key.finish(out);
@@ -276,6 +304,8 @@ impl ChangeOpsColumns {
         out.extend(mark_name);
         let mark_name = RleRange::from(mark_name_start..out.len());
 
+        let target = if has_target { target.finish(out) } else { None };
+
         Self {
             obj,
             key,
@@ -285,6 +315,7 @@ impl ChangeOpsColumns {
             pred,
             expand,
             mark_name,
+            target,
         }
     }
 
@@ -363,6 +394,18 @@ impl ChangeOpsColumns {
                 self.mark_name.clone().into(),
             ));
         }
+        if let Some(target) = &self.target {
+            cols.extend([
+                RawColumn::new(
+                    ColumnSpec::new(TARGET_COL_ID, ColumnType::Actor, false),
+                    target.actor_range().clone().into(),
+                ),
+                RawColumn::new(
+                    ColumnSpec::new(TARGET_COL_ID, ColumnType::Integer, false),
+                    target.counter_range().clone().into(),
+                ),
+            ]);
+        }
         cols.into_iter().collect()
     }
 }
@@ -389,6 +432,7 @@ pub(crate) struct ChangeOpsIter<'a> {
     pred: OpIdListIter<'a>,
     expand: MaybeBooleanDecoder<'a>,
     mark_name: RleDecoder<'a, smol_str::SmolStr>,
+    target: Option<ObjIdIter<'a>>,
 }
 
 impl ChangeOpsIter<'_> {
@@ -412,10 +456,18 @@ impl ChangeOpsIter<'_> {
             let pred = self.pred.next_in_col("pred")?;
             let expand = self.expand.maybe_next_in_col("expand")?.unwrap_or(false);
             let mark_name = self.mark_name.maybe_next_in_col("mark_name")?;
+            let target = if let Some(ref mut targets) = self.target {
+                Some(targets.next_in_col("target")?).filter(|t| !t.is_root())
+            } else {
+                None
+            };
 
             // This check is necessary to ensure that OpType::from_action_and_value
             // cannot panic later in the process.
             OpType::validate_action_and_value(action, &val)?;
+            if (action == 11) != target.is_some() {
+                return Err(InvalidOpType::InvalidMoveTarget.into());
+            }
 
             Ok(Some(ChangeOp {
                 obj,
@@ -426,6 +478,7 @@ impl ChangeOpsIter<'_> {
                 pred,
                 expand,
                 mark_name,
+                target,
             }))
         }
     }
@@ -489,6 +542,8 @@ impl TryFrom<Columns> for ChangeOpsColumns {
         let mut pred_ctr: Option<DeltaRange> = None;
         let mut expand: Option<MaybeBooleanRange> = None;
         let mut mark_name: Option<RleRange<smol_str::SmolStr>> = None;
+        let mut target_actor: Option<RleRange<u64>> = None;
+        let mut target_ctr: Option<RleRange<u64>> = None;
         let mut other = Columns::empty();
 
         for (index, col) in columns.into_iter().enumerate() {
@@ -545,6 +600,8 @@ impl TryFrom<Columns> for ChangeOpsColumns {
                 },
                 (EXPAND_COL_ID, ColumnType::Boolean) => expand = Some(col.range().into()),
                 (MARK_NAME_COL_ID, ColumnType::String) => mark_name = Some(col.range().into()),
+                (TARGET_COL_ID, ColumnType::Actor) => target_actor = Some(col.range().into()),
+                (TARGET_COL_ID, ColumnType::Integer) => target_ctr = Some(col.range().into()),
                 (other_type, other_col) => {
                     tracing::warn!(typ=?other_type, id=?other_col, "unknown column");
                     other.append(col);
@@ -572,10 +629,21 @@ impl TryFrom<Columns> for ChangeOpsColumns {
             pred,
             expand: expand.unwrap_or_else(|| (0..0).into()),
             mark_name: mark_name.unwrap_or_else(|| (0..0).into()),
+            target: ObjIdRange::new(
+                target_actor.unwrap_or_else(|| (0..0).into()),
+                target_ctr.unwrap_or_else(|| (0..0).into()),
+            ),
         })
     }
 }
 
+fn target_obj<O>(target: Option<O>) -> convert::ObjId<O> {
+    match target {
+        Some(o) => convert::ObjId::Op(o),
+        None => convert::ObjId::Root,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -587,14 +655,17 @@ mod tests {
                     (key in key(),
                      value in scalar_value(),
                      pred in proptest::collection::vec(opid(), 0..20),
-                     action in 0_u64..6,
+                     action in prop_oneof![0_u64..6, Just(11)],
                      obj in opid(),
+                     target in opid().prop_filter("the root is never moved", |o| o.counter() > 0),
                      mark_name in proptest::option::of(any::<String>().prop_map(|s| s.into())),
                      expand in any::<bool>(),
                      insert in any::<bool>()) -> ChangeOp {
 
                     let val = if action == 5 && !(value.is_int() || value.is_uint()) {
                         ScalarValue::Uint(0)
+                    } else if action == 11 {
+                        ScalarValue::Null
                     } else { value };
             ChangeOp {
                 obj: obj.into(),
@@ -605,6 +676,7 @@ mod tests {
                 insert,
                 expand,
                 mark_name,
+                target: (action == 11).then(|| target.into()),
             }
         }
     }
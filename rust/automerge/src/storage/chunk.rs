@@ -7,7 +7,9 @@ use std::{
 
 use sha2::{Digest, Sha256};
 
-use super::{change::Unverified, parse, BundleStorage, Change, Compressed, Document, MAGIC_BYTES};
+use super::{
+    change::Unverified, parse, Base, BundleStorage, Change, Compressed, Document, MAGIC_BYTES,
+};
 use crate::{columnar::encoding::leb128::ulebsize, ChangeHash};
 
 pub(crate) enum Chunk<'a> {
@@ -15,11 +17,12 @@ pub(crate) enum Chunk<'a> {
     Change(Change<'a, Unverified>),
     Bundle(BundleStorage<'a, Unverified>),
     CompressedChange(Change<'static, Unverified>, Compressed<'a>),
+    Base(Base),
 }
 
 pub(crate) mod error {
     use super::parse;
-    use crate::storage::{bundle, change, document};
+    use crate::storage::{base, bundle, change, document};
 
     #[derive(thiserror::Error, Debug)]
     pub(crate) enum Chunk {
@@ -35,6 +38,8 @@ pub(crate) mod error {
         Change(#[from] change::ParseError),
         #[error("bad document chunk: {0}")]
         Document(#[from] document::ParseError),
+        #[error("bad base chunk: {0}")]
+        Base(#[from] base::ParseError),
         #[error("unable to decompresse compressed chunk")]
         Deflate,
     }
@@ -107,6 +112,13 @@ impl<'a> Chunk<'a> {
                 }
                 Chunk::Bundle(bundle)
             }
+            ChunkType::Base => {
+                let (remaining, base) = Base::parse(chunk_input, header).map_err(|e| e.lift())?;
+                if !remaining.is_empty() {
+                    return Err(parse::ParseError::Error(error::Chunk::LeftoverData));
+                }
+                Chunk::Base(base)
+            }
         };
         Ok((remaining, chunk))
     }
@@ -119,6 +131,7 @@ impl<'a> Chunk<'a> {
                 compressed.checksum() == change.checksum() && change.checksum_valid()
             }
             Self::Bundle(b) => b.checksum_valid(),
+            Self::Base(b) => b.checksum_valid(),
         }
     }
 }
@@ -129,6 +142,7 @@ pub(crate) enum ChunkType {
     Change,
     Compressed,
     Bundle,
+    Base,
 }
 
 impl TryFrom<u8> for ChunkType {
@@ -140,6 +154,7 @@ impl TryFrom<u8> for ChunkType {
             1 => Ok(Self::Change),
             2 => Ok(Self::Compressed),
             3 => Ok(Self::Bundle),
+            4 => Ok(Self::Base),
             other => Err(other),
         }
     }
@@ -152,6 +167,7 @@ impl From<ChunkType> for u8 {
             ChunkType::Change => 1,
             ChunkType::Compressed => 2,
             ChunkType::Bundle => 3,
+            ChunkType::Base => 4,
         }
     }
 }
@@ -229,6 +245,10 @@ impl Header {
         self.header_size
     }
 
+    pub(crate) fn chunk_type(&self) -> ChunkType {
+        self.chunk_type
+    }
+
     pub(crate) fn write(&self, out: &mut Vec<u8>) {
         out.extend(MAGIC_BYTES);
         out.extend(self.checksum.bytes());
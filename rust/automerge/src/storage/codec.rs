@@ -0,0 +1,193 @@
+use std::collections::BTreeMap;
+use std::io::Read;
+
+use super::change::DEFLATE_MIN_SIZE;
+use super::ColumnSpec;
+
+/// The algorithm used to compress the columns of a saved document
+///
+/// The codec is recorded in the column specification of each compressed column so loaders detect
+/// it without any additional configuration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Codec {
+    /// Store columns uncompressed
+    None,
+    /// DEFLATE, as specified in the storage format. Readable by every version of automerge.
+    #[default]
+    Deflate,
+    /// Zstandard. Typically compresses slightly better than DEFLATE and is considerably faster to
+    /// decompress. Documents saved with this codec cannot be loaded by versions of automerge which
+    /// predate it.
+    Zstd,
+}
+
+impl Codec {
+    /// Compress `input` into `out` returning the number of bytes written
+    pub(crate) fn encode(&self, input: &[u8], level: Option<u32>, out: &mut Vec<u8>) -> usize {
+        match self {
+            Self::None => {
+                out.extend(input);
+                input.len()
+            }
+            Self::Deflate => {
+                let level = level
+                    .map(|l| flate2::Compression::new(l.min(9)))
+                    .unwrap_or_default();
+                let mut deflater = flate2::bufread::DeflateEncoder::new(input, level);
+                //This unwrap should be okay as we're reading and writing to in memory buffers
+                deflater.read_to_end(out).unwrap()
+            }
+            Self::Zstd => {
+                // ruzstd only implements the "fastest" level (roughly zstd level 1) so the level
+                // is ignored for now
+                let start = out.len();
+                ruzstd::encoding::compress(
+                    input,
+                    &mut *out,
+                    ruzstd::encoding::CompressionLevel::Fastest,
+                );
+                out.len() - start
+            }
+        }
+    }
+
+    /// Decompress `input` into `out` returning the number of bytes written
+    pub(crate) fn decode(&self, input: &[u8], out: &mut Vec<u8>) -> Result<usize, std::io::Error> {
+        match self {
+            Self::None => {
+                out.extend(input);
+                Ok(input.len())
+            }
+            Self::Deflate => flate2::bufread::DeflateDecoder::new(input).read_to_end(out),
+            Self::Zstd => ruzstd::decoding::StreamingDecoder::new(input)
+                .map_err(std::io::Error::other)?
+                .read_to_end(out),
+        }
+    }
+}
+
+/// The two sets of columns in a document chunk
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ColumnGroup {
+    /// The columns containing change metadata (actor, sequence number, dependencies etc.)
+    Changes,
+    /// The columns containing the operations in the document
+    Ops,
+}
+
+/// How to compress the columns of a saved document
+///
+/// Each column is compressed separately and only if it is at least [`Self::min_size`] bytes long,
+/// as compressing small columns typically makes them larger. The threshold can be overridden for
+/// individual columns with [`Self::column_min_size`], for example to leave columns which are read
+/// on every load uncompressed.
+///
+/// ```
+/// # use automerge::{AutoCommit, Compression, ColumnGroup, SaveOptions};
+/// let mut doc = AutoCommit::new();
+/// let bytes = doc.save_with_options(SaveOptions {
+///     compression: Compression::zstd().min_size(1024),
+///     ..Default::default()
+/// });
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Compression {
+    codec: Codec,
+    level: Option<u32>,
+    min_size: usize,
+    column_min_sizes: BTreeMap<(ColumnGroup, u32), usize>,
+}
+
+impl Compression {
+    /// Do not compress any columns
+    pub fn none() -> Self {
+        Self::new(Codec::None)
+    }
+
+    /// Compress columns with DEFLATE, this is the default
+    pub fn deflate() -> Self {
+        Self::new(Codec::Deflate)
+    }
+
+    /// Compress columns with zstd, see [`Codec::Zstd`]
+    pub fn zstd() -> Self {
+        Self::new(Codec::Zstd)
+    }
+
+    /// Compress columns with `codec` using the default level and thresholds
+    pub fn new(codec: Codec) -> Self {
+        Self {
+            codec,
+            level: None,
+            min_size: DEFLATE_MIN_SIZE,
+            column_min_sizes: BTreeMap::new(),
+        }
+    }
+
+    /// The compression level to use
+    ///
+    /// For [`Codec::Deflate`] this is a value from 0 (fastest) to 9 (smallest), larger values are
+    /// treated as 9. The zstd encoder currently only implements a single level (roughly equivalent
+    /// to zstd level 1) and so ignores this setting. The default is the codec's default level.
+    pub fn level(self, level: u32) -> Self {
+        Self {
+            level: Some(level),
+            ..self
+        }
+    }
+
+    /// The size in bytes below which columns are stored uncompressed
+    ///
+    /// The default is 256
+    pub fn min_size(self, min_size: usize) -> Self {
+        Self { min_size, ..self }
+    }
+
+    /// Override [`Self::min_size`] for a single column
+    ///
+    /// `column_id` is the column ID from the [storage format
+    /// specification](https://alexjg.github.io/automerge-storage-docs/#column-specifications)
+    /// (not including the type and compression bits). Pass `usize::MAX` to never compress the
+    /// column.
+    pub fn column_min_size(mut self, group: ColumnGroup, column_id: u32, min_size: usize) -> Self {
+        self.column_min_sizes.insert((group, column_id), min_size);
+        self
+    }
+
+    /// The codec used to compress columns
+    pub fn codec(&self) -> Codec {
+        self.codec
+    }
+
+    /// Write the data in `input` for the column `spec` to `out`, compressing it if it is larger
+    /// than the threshold for the column
+    ///
+    /// # Returns
+    /// The specification of the written column and the number of bytes written
+    pub(crate) fn compress_column(
+        &self,
+        group: ColumnGroup,
+        spec: ColumnSpec,
+        input: &[u8],
+        out: &mut Vec<u8>,
+    ) -> (ColumnSpec, usize) {
+        let threshold = self
+            .column_min_sizes
+            .get(&(group, u32::from(spec.id())))
+            .copied()
+            .unwrap_or(self.min_size);
+        if self.codec == Codec::None || spec.compressed() || input.len() < threshold {
+            out.extend(input);
+            (spec, input.len())
+        } else {
+            let len = self.codec.encode(input, self.level, out);
+            (spec.compressed_with(self.codec), len)
+        }
+    }
+}
+
+impl Default for Compression {
+    fn default() -> Self {
+        Self::deflate()
+    }
+}
@@ -1,3 +1,15 @@
+use crate::storage::Codec;
+
+/// Set on compressed columns.
+const COMPRESSED_BIT: u32 = 0b00001000;
+/// Set in addition to `COMPRESSED_BIT` when the column was compressed with zstd rather than
+/// DEFLATE. This bit is not part of [1] and so older implementations will see a column with an
+/// unknown ID which is out of order and fail to load the document, which is what we want as they
+/// have no way of decompressing the column.
+///
+/// [1]: https://alexjg.github.io/automerge-storage-docs/#column-specifications
+const ZSTD_BIT: u32 = 1 << 31;
+
 /// An implementation of column specifications as specified in [1]
 ///
 /// [1]: https://alexjg.github.io/automerge-storage-docs/#column-specifications
@@ -22,9 +34,9 @@ impl ColumnSpec {
         let mut raw = id.0 << 4;
         raw |= col_type.as_u8() as u32;
         if deflate {
-            raw |= 0b00001000;
+            raw |= COMPRESSED_BIT;
         } else {
-            raw &= 0b11110111;
+            raw &= !COMPRESSED_BIT;
         }
         ColumnSpec(raw)
     }
@@ -66,15 +78,36 @@ impl ColumnSpec {
     }
 
     pub(crate) fn id(&self) -> ColumnId {
-        ColumnId(self.0 >> 4)
+        ColumnId((self.0 & !ZSTD_BIT) >> 4)
+    }
+
+    /// Whether the column is compressed with any codec
+    pub(crate) fn compressed(&self) -> bool {
+        self.0 & COMPRESSED_BIT > 0
     }
 
+    #[cfg(test)]
     pub(crate) fn deflate(&self) -> bool {
-        self.0 & 0b00001000 > 0
+        self.codec() == Codec::Deflate
     }
 
-    pub(crate) fn deflated(&self) -> Self {
-        Self::new(self.id(), self.col_type(), true)
+    pub(crate) fn codec(&self) -> Codec {
+        if !self.compressed() {
+            Codec::None
+        } else if self.0 & ZSTD_BIT > 0 {
+            Codec::Zstd
+        } else {
+            Codec::Deflate
+        }
+    }
+
+    pub(crate) fn compressed_with(&self, codec: Codec) -> Self {
+        let spec = Self::new(self.id(), self.col_type(), codec != Codec::None);
+        if codec == Codec::Zstd {
+            ColumnSpec(spec.0 | ZSTD_BIT)
+        } else {
+            spec
+        }
     }
 
     pub(crate) fn inflated(&self) -> Self {
@@ -82,7 +115,7 @@ impl ColumnSpec {
     }
 
     pub(crate) fn normalize(&self) -> Normalized {
-        Normalized(self.0 & 0b11110111)
+        Normalized(self.0 & !(COMPRESSED_BIT | ZSTD_BIT))
     }
 }
 
@@ -93,10 +126,10 @@ impl std::fmt::Debug for ColumnSpec {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "ColumnSpec(id: {:?}, type: {}, deflate: {})",
+            "ColumnSpec(id: {:?}, type: {}, codec: {:?})",
             self.id(),
             self.col_type(),
-            self.deflate()
+            self.codec()
         )
     }
 }
@@ -116,6 +149,12 @@ impl From<u32> for ColumnId {
     }
 }
 
+impl From<ColumnId> for u32 {
+    fn from(id: ColumnId) -> Self {
+        id.0
+    }
+}
+
 impl std::fmt::Debug for ColumnId {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         self.0.fmt(f)
@@ -337,4 +376,19 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn zstd_columns_keep_their_id_and_type() {
+        let spec = ColumnSpec::new(ColumnId(10), ColumnType::String, false);
+        let zstd = spec.compressed_with(Codec::Zstd);
+        assert_eq!(zstd.codec(), Codec::Zstd);
+        assert!(zstd.compressed());
+        assert_eq!(zstd.id(), spec.id());
+        assert_eq!(zstd.col_type(), spec.col_type());
+        assert!(zstd.normalize() == spec.normalize());
+        assert_eq!(ColumnSpec::from(u32::from(zstd)), zstd);
+        assert_eq!(zstd.inflated(), spec);
+        assert_eq!(spec.compressed_with(Codec::Deflate).codec(), Codec::Deflate);
+        assert_eq!(spec.compressed_with(Codec::None), spec);
+    }
 }
@@ -1,7 +1,7 @@
 use std::collections::BTreeMap;
-use std::{io::Read, marker::PhantomData, ops::Range};
+use std::{marker::PhantomData, ops::Range};
 
-use crate::storage::parse;
+use crate::storage::{parse, Codec, ColumnGroup, Compression};
 
 use super::{compression, ColumnSpec};
 
@@ -59,23 +59,18 @@ impl<T: compression::ColumnCompression> RawColumn<T> {
         self.data.clone()
     }
 
-    fn compress(&self, input: &[u8], out: &mut Vec<u8>, threshold: usize) -> (ColumnSpec, usize) {
-        let (spec, len) = if self.data.len() < threshold || self.spec.deflate() {
-            out.extend(&input[self.data.clone()]);
-            (self.spec, self.data.len())
-        } else {
-            let mut deflater = flate2::bufread::DeflateEncoder::new(
-                &input[self.data.clone()],
-                flate2::Compression::default(),
-            );
-            //This unwrap should be okay as we're reading and writing to in memory buffers
-            (self.spec.deflated(), deflater.read_to_end(out).unwrap())
-        };
-        (spec, len)
+    fn compress(
+        &self,
+        input: &[u8],
+        out: &mut Vec<u8>,
+        group: ColumnGroup,
+        compression: &Compression,
+    ) -> (ColumnSpec, usize) {
+        compression.compress_column(group, self.spec, &input[self.data.clone()], out)
     }
 
     pub(crate) fn uncompressed(&self) -> Option<RawColumn<compression::Uncompressed>> {
-        if self.spec.deflate() {
+        if self.spec.compressed() {
             None
         } else {
             Some(RawColumn {
@@ -91,13 +86,13 @@ impl<T: compression::ColumnCompression> RawColumn<T> {
         input: &[u8],
         out: &mut Vec<u8>,
     ) -> Result<(ColumnSpec, usize), ParseError> {
-        let len = if self.spec.deflate() {
-            let mut inflater = flate2::bufread::DeflateDecoder::new(&input[self.data.clone()]);
-            inflater.read_to_end(out).map_err(ParseError::Deflate)?
-        } else {
-            out.extend(&input[self.data.clone()]);
-            self.data.len()
-        };
+        let codec = self.spec.codec();
+        let len = codec
+            .decode(&input[self.data.clone()], out)
+            .map_err(|e| match codec {
+                Codec::Zstd => ParseError::Zstd(e),
+                _ => ParseError::Deflate(e),
+            })?;
         Ok((self.spec.inflated(), len))
     }
 }
@@ -119,7 +114,8 @@ impl<T: compression::ColumnCompression> RawColumns<T> {
         Some(RawColumns(result))
     }
 
-    /// Write each column in `input` represented by `self` into `out`, possibly compressing.
+    /// Write each column in `input` represented by `self` into `out`, possibly compressing
+    /// according to `compression`. `group` is the set of columns `self` represents in the chunk.
     ///
     /// # Returns
     /// The `RawColumns` corresponding to the data written to `out`
@@ -130,12 +126,13 @@ impl<T: compression::ColumnCompression> RawColumns<T> {
         &self,
         input: &[u8],
         out: &mut Vec<u8>,
-        threshold: usize,
+        group: ColumnGroup,
+        compression: &Compression,
     ) -> RawColumns<compression::Unknown> {
         let mut result = Vec::with_capacity(self.0.len());
         let mut start = 0;
         for col in &self.0 {
-            let (spec, len) = col.compress(input, out, threshold);
+            let (spec, len) = col.compress(input, out, group, compression);
             result.push(RawColumn {
                 spec,
                 data: start..(start + len),
@@ -212,6 +209,8 @@ pub(crate) enum ParseError {
     Leb128(#[from] parse::leb128::Error),
     #[error(transparent)]
     Deflate(#[from] std::io::Error),
+    #[error("invalid zstd data: {0}")]
+    Zstd(std::io::Error),
 }
 
 impl RawColumns<compression::Unknown> {
@@ -2,24 +2,18 @@ use hexane::PackError;
 use std::collections::BTreeSet;
 use std::{borrow::Cow, ops::Range};
 
-use super::{parse, shift_range, ChunkType, Header, RawColumns};
+use super::{parse, shift_range, Base, ChunkType, Header, RawColumns};
 
 use crate::change_graph::{ChangeGraph, ChangeGraphCols};
 use crate::op_set2::change::{ChangeCollector, CollectedChanges, OutOfMemory};
 use crate::op_set2::op_set::MarkOrderValidator;
 use crate::op_set2::{OpSet, ReadOpError};
 use crate::storage::columns::compression::Uncompressed;
-use crate::storage::ColumnSpec;
+use crate::storage::{Codec, ColumnSpec, Compression};
 use crate::{ActorId, Automerge, Change, ChangeHash, TextEncoding};
 
 mod compression;
 
-#[allow(dead_code)]
-pub(crate) enum CompressConfig {
-    None,
-    Threshold(usize),
-}
-
 #[derive(Debug, Clone)]
 pub(crate) struct Document<'a> {
     bytes: Cow<'a, [u8]>,
@@ -193,7 +187,7 @@ impl<'a> Document<'a> {
     pub(crate) fn new(
         op_set: &OpSet,
         change_graph: &ChangeGraph,
-        compress: CompressConfig,
+        config: &Compression,
     ) -> Document<'static> {
         let (op_metadata, ops_out_b) = op_set.export();
 
@@ -242,7 +236,7 @@ impl<'a> Document<'a> {
         let op_bytes = shift_range(ops_start..ops_end, header.len());
         let change_bytes = shift_range(change_start..change_end, header.len());
 
-        let compressed_bytes = if let CompressConfig::Threshold(threshold) = compress {
+        let compressed_bytes = if config.codec() != Codec::None {
             let compressed = Cow::Owned(compression::compress(compression::Args {
                 prefix: prefix_len + header.len(),
                 suffix: suffix_start + header.len(),
@@ -256,7 +250,7 @@ impl<'a> Document<'a> {
                 },
                 original: Cow::Borrowed(&bytes),
                 extra_args: compression::CompressArgs {
-                    threshold,
+                    compression: config.clone(),
                     original_header_len: header_len,
                 },
             }));
@@ -320,13 +314,22 @@ impl<'a> Document<'a> {
         }
     }
 
+    fn load_change_cols(&self, base: Option<&Base>) -> Result<ChangeGraphCols, ReconstructError> {
+        let change_cols = ChangeGraphCols::load(self)?;
+        match base {
+            Some(base) => change_cols.with_base(base),
+            None => Ok(change_cols),
+        }
+    }
+
     pub(crate) fn reconstruct(
         &self,
+        base: Option<&Base>,
         mode: VerificationMode,
         text_encoding: TextEncoding,
     ) -> Result<Automerge, ReconstructError> {
         let mut op_set = OpSet::load(self, text_encoding)?;
-        let change_cols = ChangeGraphCols::load(self)?;
+        let change_cols = self.load_change_cols(base)?;
 
         let mut index = op_set.index_builder();
 
@@ -344,7 +347,10 @@ impl<'a> Document<'a> {
 
         let change_graph = change_cols.finalize(&changes.changes);
 
-        debug_assert_eq!(changes.changes.len(), change_graph.len());
+        debug_assert_eq!(
+            changes.changes.len() + base.map(|b| b.stubs.len()).unwrap_or(0),
+            change_graph.len()
+        );
 
         debug_assert!(op_set.validate_top_index());
 
@@ -360,12 +366,15 @@ impl<'a> Document<'a> {
         }
     }
 
+    /// Reconstruct the changes in this document, if the document is shallow `base` is the base
+    /// chunk which preceded it and the stubs it describes are not included in the output
     pub(crate) fn reconstruct_changes(
         &self,
+        base: Option<&Base>,
         text_encoding: TextEncoding,
     ) -> Result<Vec<Change>, ReconstructError> {
         let op_set = OpSet::load(self, text_encoding)?;
-        let change_cols = ChangeGraphCols::load(self)?;
+        let change_cols = self.load_change_cols(base)?;
 
         let mut mark_order = MarkOrderValidator::default();
         let mut change_collector = ChangeCollector::try_new(&change_cols, &op_set)?;
@@ -405,6 +414,8 @@ pub(crate) enum ReconstructError {
     InvalidColumnLength(ColumnSpec),
     #[error("max_op is lower than start_op")]
     InvalidMaxOp,
+    #[error("the base chunk does not match the document")]
+    InvalidBase,
     #[error("invalid mark operation order: {error_message}")]
     InvalidMarkOrderDoc {
         doc: Box<Automerge>,
@@ -2,7 +2,7 @@ use std::{borrow::Cow, convert::Infallible, ops::Range};
 
 use crate::storage::{
     columns::{compression, raw_column},
-    shift_range, ChunkType, Header, RawColumns,
+    shift_range, ChunkType, ColumnGroup, Compression as CompressionOptions, Header, RawColumns,
 };
 
 pub(super) struct Args<'a, T: compression::ColumnCompression, DirArgs> {
@@ -21,21 +21,21 @@ pub(super) struct Args<'a, T: compression::ColumnCompression, DirArgs> {
 }
 
 pub(super) struct CompressArgs {
-    pub(super) threshold: usize,
+    pub(super) compression: CompressionOptions,
     pub(super) original_header_len: usize,
 }
 
 /// Compress a document chunk returning the compressed bytes
 pub(super) fn compress(args: Args<'_, compression::Uncompressed, CompressArgs>) -> Vec<u8> {
     let header_len = args.extra_args.original_header_len;
-    let threshold = args.extra_args.threshold;
+    let compression = args.extra_args.compression.clone();
     // Wrap in a closure so we can use `?` in the construction but still force the compiler
     // to check that the error type is `Infallible`
     let result: Result<_, Infallible> = (|| {
         Ok(Compression::<Compressing, _>::new(
             args,
             Compressing {
-                threshold,
+                compression,
                 header_len,
             },
         )
@@ -119,12 +119,14 @@ trait Direction: std::fmt::Debug {
     /// This method represents the (de)compression process for a direction. The arguments are:
     ///
     /// * cols - The columns we are processing
+    /// * group - Which set of columns in the document `cols` is
     /// * input - the entire document chunk
     /// * out - the vector to place the processed columns in
     /// * meta_out - the vector to place processed column metadata in
     fn process(
         &self,
         cols: &Cols<Self::In>,
+        group: ColumnGroup,
         input: &[u8],
         out: &mut Vec<u8>,
         meta_out: &mut Vec<u8>,
@@ -132,7 +134,7 @@ trait Direction: std::fmt::Debug {
 }
 #[derive(Debug)]
 struct Compressing {
-    threshold: usize,
+    compression: CompressionOptions,
     header_len: usize,
 }
 
@@ -145,14 +147,15 @@ impl Direction for Compressing {
     fn process(
         &self,
         cols: &Cols<Self::In>,
+        group: ColumnGroup,
         input: &[u8],
         out: &mut Vec<u8>,
         meta_out: &mut Vec<u8>,
     ) -> Result<Cols<Self::Out>, Self::Error> {
         let start = out.len();
-        let raw_columns = cols
-            .raw_columns
-            .compress(&input[cols.data.clone()], out, self.threshold);
+        let raw_columns =
+            cols.raw_columns
+                .compress(&input[cols.data.clone()], out, group, &self.compression);
         raw_columns.write(meta_out);
         Ok(Cols {
             data: start..out.len(),
@@ -173,6 +176,7 @@ impl Direction for Decompressing {
     fn process(
         &self,
         cols: &Cols<Self::In>,
+        _group: ColumnGroup,
         input: &[u8],
         out: &mut Vec<u8>,
         meta_out: &mut Vec<u8>,
@@ -264,6 +268,7 @@ impl<'a, D: Direction> Compression<'a, D, Starting> {
         } = self.state;
         let change_cols = self.direction.process(
             &self.args.changes,
+            ColumnGroup::Changes,
             &self.args.original,
             &mut data_out,
             &mut meta_out,
@@ -289,6 +294,7 @@ impl<'a, D: Direction> Compression<'a, D, Changes<D>> {
         } = self.state;
         let ops_cols = self.direction.process(
             &self.args.ops,
+            ColumnGroup::Ops,
             &self.args.original,
             &mut data_out,
             &mut meta_out,
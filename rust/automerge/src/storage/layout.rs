@@ -0,0 +1,90 @@
+use super::{
+    chunk::error::Header as HeaderError,
+    columns::{compression, raw_column},
+    parse, ChunkType, ColumnSpec, Header, RawColumns,
+};
+
+/// A column as it is stored in a document or bundle chunk
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct StoredColumn {
+    pub(crate) spec: ColumnSpec,
+    /// The number of bytes the column occupies in the chunk
+    pub(crate) len: usize,
+    /// The number of bytes in the column once it is decompressed
+    pub(crate) uncompressed_len: usize,
+}
+
+/// The columns of a document or bundle chunk in the order they are stored
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ColumnLayout {
+    pub(crate) changes: Vec<StoredColumn>,
+    pub(crate) ops: Vec<StoredColumn>,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub(crate) enum ParseError {
+    #[error(transparent)]
+    Leb128(#[from] parse::leb128::Error),
+    #[error(transparent)]
+    RawColumns(#[from] raw_column::ParseError),
+    #[error("failed to parse header: {0}")]
+    Header(#[from] HeaderError),
+    #[error("a {0:?} chunk does not have a column layout")]
+    NoLayout(ChunkType),
+}
+
+/// Parse the column metadata of a document or bundle chunk as it was stored, that is before any
+/// compressed columns are decompressed. `chunk` must be the entire chunk including the header.
+///
+/// The other parts of a chunk are parsed by [`super::Chunk::parse`], this is only needed when we
+/// want to know how much space each column takes up on disk.
+pub(crate) fn column_layout(chunk: &[u8]) -> Result<ColumnLayout, parse::ParseError<ParseError>> {
+    let (i, header) = Header::parse::<ParseError>(parse::Input::new(chunk))?;
+    let i = i.split(header.data_bytes().len()).first;
+    match header.chunk_type() {
+        ChunkType::Document => {
+            let (i, _actors) = parse::length_prefixed(parse::actor_id::<ParseError>)(i)?;
+            let (i, _heads) = parse::length_prefixed(parse::change_hash::<ParseError>)(i)?;
+            let (i, change_meta) = RawColumns::parse::<ParseError>(i)?;
+            let (i, ops_meta) = RawColumns::parse::<ParseError>(i)?;
+            let (i, change_data) = parse::take_n(change_meta.total_column_len(), i)?;
+            let (_, op_data) = parse::take_n(ops_meta.total_column_len(), i)?;
+            Ok(ColumnLayout {
+                changes: stored_columns(&change_meta, change_data)?,
+                ops: stored_columns(&ops_meta, op_data)?,
+            })
+        }
+        ChunkType::Bundle => {
+            let (i, _deps) = parse::length_prefixed(parse::change_hash::<ParseError>)(i)?;
+            let (i, _actors) = parse::length_prefixed(parse::actor_id::<ParseError>)(i)?;
+            let (i, change_meta) = RawColumns::parse::<ParseError>(i)?;
+            let (i, change_data) = parse::take_n(change_meta.total_column_len(), i)?;
+            let (i, ops_meta) = RawColumns::parse::<ParseError>(i)?;
+            let (_, op_data) = parse::take_n(ops_meta.total_column_len(), i)?;
+            Ok(ColumnLayout {
+                changes: stored_columns(&change_meta, change_data)?,
+                ops: stored_columns(&ops_meta, op_data)?,
+            })
+        }
+        other => Err(parse::ParseError::Error(ParseError::NoLayout(other))),
+    }
+}
+
+fn stored_columns(
+    cols: &RawColumns<compression::Unknown>,
+    data: &[u8],
+) -> Result<Vec<StoredColumn>, parse::ParseError<ParseError>> {
+    let mut decompressed = Vec::new();
+    let uncompressed = cols
+        .uncompress(data, &mut decompressed)
+        .map_err(|e| parse::ParseError::Error(e.into()))?;
+    Ok(cols
+        .iter()
+        .zip(uncompressed.iter())
+        .map(|(stored, uncompressed)| StoredColumn {
+            spec: stored.spec(),
+            len: stored.data().len(),
+            uncompressed_len: uncompressed.data().len(),
+        })
+        .collect())
+}
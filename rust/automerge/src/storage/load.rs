@@ -46,6 +46,8 @@ pub enum Error {
     InflateDocument(Box<dyn std::error::Error + Send + Sync + 'static>),
     #[error("bad checksum")]
     BadChecksum,
+    #[error("a base chunk must be followed by a document chunk")]
+    OrphanedBase,
 }
 
 pub(crate) enum LoadedChanges<'a> {
@@ -107,11 +109,18 @@ fn load_next_change<'a>(
     if !chunk.checksum_valid() {
         return Err(Error::BadChecksum);
     }
+    let (remaining, base, chunk) = match chunk {
+        storage::Chunk::Base(base) => {
+            let (remaining, chunk) = next_document(remaining.reset())?;
+            (remaining, Some(base), chunk)
+        }
+        chunk => (remaining, None, chunk),
+    };
     match chunk {
         storage::Chunk::Document(d) => {
             tracing::trace!("loading document chunk");
             if !d.heads().iter().all(|h| current.has_change(h)) {
-                let new_changes = match d.reconstruct_changes(text_encoding) {
+                let new_changes = match d.reconstruct_changes(base.as_ref(), text_encoding) {
                     Ok(c) => c,
                     Err(ReconstructError::InvalidMarkOrderChanges {
                         changes,
@@ -151,6 +160,21 @@ fn load_next_change<'a>(
                     .map_err(|e| Error::InvalidChangeColumns(Box::new(e)))?;
             changes.push(change);
         }
+        storage::Chunk::Base(_) => return Err(Error::OrphanedBase),
     };
     Ok(remaining)
 }
+
+/// Parse the document chunk which must follow a base chunk
+pub(crate) fn next_document(
+    data: parse::Input<'_>,
+) -> Result<(parse::Input<'_>, storage::Chunk<'_>), Error> {
+    let (remaining, chunk) = storage::Chunk::parse(data).map_err(|e| Error::Parse(Box::new(e)))?;
+    if !chunk.checksum_valid() {
+        return Err(Error::BadChecksum);
+    }
+    if !matches!(chunk, storage::Chunk::Document(_)) {
+        return Err(Error::OrphanedBase);
+    }
+    Ok((remaining, chunk))
+}
@@ -389,7 +389,6 @@ impl Automerge {
         message: Message,
         patch_log: &mut PatchLog,
     ) -> Result<(), AutomergeError> {
-        sync_state.in_flight = false;
         let before_heads = self.get_heads();
 
         let Message {
@@ -401,6 +400,32 @@ impl Automerge {
             ..
         } = message;
 
+        // A peer which doesn't have the base of a shallow document can't apply any of the changes
+        // we could send it. Peers without any data which support v2 messages are sent the whole
+        // (shallow) document instead. This is checked before the message changes anything, and
+        // the changes are applied before the sync state is updated, so a message which fails
+        // leaves the document and the sync state as they were.
+        if let Some(need) = message_need
+            .iter()
+            .find(|h| self.change_graph.is_stub_hash(h))
+        {
+            return Err(AutomergeError::TruncatedHistory(*need));
+        }
+        let send_doc = message_heads.is_empty()
+            && (message_flags.is_some() || sync_state.supports_v2_messages());
+        if !send_doc && message_heads.iter().all(|h| self.has_change(h)) {
+            if let Err(missing) = self.change_graph.covers_base(&message_heads) {
+                return Err(AutomergeError::TruncatedHistory(missing));
+            }
+        }
+
+        let changes_is_empty = message_changes.is_empty();
+        if !changes_is_empty && !sync_state.read_only {
+            self.load_incremental_log_patches(&message_changes.join(), patch_log)?;
+        }
+
+        sync_state.in_flight = false;
+
         if let Some(flags) = message_flags {
             // Any peer that sends the flags section supports V2 messages —
             // the flags section was introduced alongside V2 support.
@@ -417,9 +442,7 @@ impl Automerge {
             sync_state.peer_read_only = flags.contains(MessageFlags::READ_ONLY);
         }
 
-        let changes_is_empty = message_changes.is_empty();
         if !changes_is_empty && !sync_state.read_only {
-            self.load_incremental_log_patches(&message_changes.join(), patch_log)?;
             sync_state.shared_heads = advance_heads(
                 &before_heads.iter().collect(),
                 &self.get_heads().into_iter().collect(),
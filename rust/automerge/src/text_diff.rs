@@ -5,42 +5,68 @@ use crate::iter::Span;
 use crate::{
     clock::Clock,
     iter::{SpanInternal, SpansInternal},
-    transaction::TransactionInner,
+    transaction::{TextDiffGranularity, TransactionInner, UpdateTextConfig},
     ObjId as ExId, PatchLog, ReadDoc, TextEncoding,
 };
 mod myers;
 mod replace;
 mod utils;
 
+use myers::Deadline;
+
 pub(crate) fn myers_diff<'a, S: AsRef<str>>(
     doc: &'a mut Automerge,
     tx: &'a mut TransactionInner,
     patch_log: &mut PatchLog,
     text_obj: &ExId,
     new: S,
+    config: &UpdateTextConfig,
 ) -> Result<(), crate::AutomergeError> {
     let old = doc.text_for(text_obj, tx.get_scope().clone())?;
     let new = new.as_ref();
-    let old_graphemes = old.graphemes(true).collect::<Vec<&str>>();
-    let new_graphemes = new.graphemes(true).collect::<Vec<&str>>();
+    let old_units = split_units(&old, config.granularity);
+    let new_units = split_units(new, config.granularity);
     let text_encoding = doc.text_encoding();
-    let mut hook = TxHook {
+    let deadline = config.timeout.map(Deadline::after);
+    let hook = TxHook {
         tx,
         doc,
         patch_log,
         obj: text_obj,
         idx: 0,
-        old: &old_graphemes,
-        new: &new_graphemes,
+        old: &old_units,
+        new: &new_units,
         text_encoding,
+        refine: refines(config),
+        deadline,
     };
-    myers::diff(
-        &mut hook,
-        &old_graphemes,
-        0..old_graphemes.len(),
-        &new_graphemes,
-        0..new_graphemes.len(),
-    )
+    let (old_range, new_range) = (0..old_units.len(), 0..new_units.len());
+    if hook.refine {
+        // the replacer turns a deletion next to an insertion into a replacement we can refine
+        let mut hook = replace::Replace::new(hook);
+        myers::diff(
+            &mut hook, &old_units, old_range, &new_units, new_range, deadline,
+        )
+    } else {
+        let mut hook = hook;
+        myers::diff(
+            &mut hook, &old_units, old_range, &new_units, new_range, deadline,
+        )
+    }
+}
+
+/// Whether replaced units are compared again grapheme by grapheme
+fn refines(config: &UpdateTextConfig) -> bool {
+    config.refine && config.granularity != TextDiffGranularity::Grapheme
+}
+
+/// Split `text` into the units which are compared by the diff
+fn split_units(text: &str, granularity: TextDiffGranularity) -> Vec<&str> {
+    match granularity {
+        TextDiffGranularity::Grapheme => text.graphemes(true).collect(),
+        TextDiffGranularity::Word => text.split_word_bounds().collect(),
+        TextDiffGranularity::Line => text.split_inclusive('\n').collect(),
+    }
 }
 
 struct TxHook<'a> {
@@ -52,6 +78,8 @@ struct TxHook<'a> {
     obj: &'a ExId,
     idx: usize,
     text_encoding: TextEncoding,
+    refine: bool,
+    deadline: Option<Deadline>,
 }
 
 impl myers::DiffHook for TxHook<'_> {
@@ -77,6 +105,34 @@ impl myers::DiffHook for TxHook<'_> {
         new_index: usize,
         new_len: usize,
     ) -> Result<(), Self::Error> {
+        if self.refine {
+            let old = self.old[old_index..old_index + old_len].concat();
+            let new = self.new[new_index..new_index + new_len].concat();
+            let old_graphemes = old.graphemes(true).collect::<Vec<_>>();
+            let new_graphemes = new.graphemes(true).collect::<Vec<_>>();
+            let mut hook = TxHook {
+                doc: self.doc,
+                tx: self.tx,
+                patch_log: self.patch_log,
+                old: &old_graphemes,
+                new: &new_graphemes,
+                obj: self.obj,
+                idx: self.idx,
+                text_encoding: self.text_encoding,
+                refine: false,
+                deadline: self.deadline,
+            };
+            myers::diff(
+                &mut hook,
+                &old_graphemes,
+                0..old_graphemes.len(),
+                &new_graphemes,
+                0..new_graphemes.len(),
+                self.deadline,
+            )?;
+            self.idx = hook.idx;
+            return Ok(());
+        }
         let new_chars = self.new[new_index..new_index + new_len].concat();
         let deleted = self.old[old_index..old_index + old_len]
             .iter()
@@ -142,9 +198,11 @@ pub(crate) fn myers_block_diff<'a, I: IntoIterator<Item = Span>>(
     config: &crate::marks::UpdateSpansConfig,
 ) -> Result<(), crate::AutomergeError> {
     let text_obj_meta = doc.exid_to_obj(text_obj)?;
-    let old = spans_as_grapheme(doc, &text_obj_meta.id, None)?;
+    let granularity = config.text_diff.granularity;
+    let old = spans_as_units(doc, &text_obj_meta.id, None, granularity)?;
     let new_spans: Vec<Span> = new.into_iter().collect();
-    let new = span_as_grapheme(new_spans.iter().cloned());
+    let new = span_as_units(new_spans.iter().cloned(), granularity);
+    let deadline = config.text_diff.timeout.map(Deadline::after);
 
     // First pass: Update text and block structure
     let mut hook = replace::Replace::new(BlockDiffHook {
@@ -155,8 +213,10 @@ pub(crate) fn myers_block_diff<'a, I: IntoIterator<Item = Span>>(
         idx: 0,
         old: &old,
         new: &new,
+        refine: refines(&config.text_diff),
+        deadline,
     });
-    myers::diff(&mut hook, &old, 0..old.len(), &new, 0..new.len())?;
+    myers::diff(&mut hook, &old, 0..old.len(), &new, 0..new.len(), deadline)?;
 
     // Second pass: Apply marks
     apply_marks_diff(doc, tx, patch_log, text_obj, &new_spans, config)
@@ -251,27 +311,47 @@ struct BlockDiffHook<'a> {
     doc: &'a mut Automerge,
     tx: &'a mut TransactionInner,
     patch_log: &'a mut PatchLog,
-    old: &'a [BlockOrGrapheme],
-    new: &'a [BlockOrGrapheme],
+    old: &'a [BlockOrUnit],
+    new: &'a [BlockOrUnit],
     obj: &'a ExId,
     idx: usize,
+    refine: bool,
+    deadline: Option<Deadline>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
-enum BlockOrGrapheme {
+enum BlockOrUnit {
     Block(crate::hydrate::Map),
-    Grapheme(String),
+    /// A grapheme, word or line of text depending on the granularity of the diff
+    Unit(String),
 }
 
-impl BlockOrGrapheme {
+impl BlockOrUnit {
     fn width(&self, encoding: TextEncoding) -> usize {
         match self {
-            BlockOrGrapheme::Block(_) => 1,
-            BlockOrGrapheme::Grapheme(g) => encoding.width(g),
+            BlockOrUnit::Block(_) => 1,
+            BlockOrUnit::Unit(u) => encoding.width(u),
         }
     }
 }
 
+impl BlockDiffHook<'_> {
+    /// Replace the unit `old` at the current index with `new`
+    fn splice(&mut self, old: &str, new: &str) -> Result<(), crate::AutomergeError> {
+        let encoding = self.doc.text_encoding();
+        self.tx.splice_text(
+            self.doc,
+            self.patch_log,
+            self.obj,
+            self.idx,
+            encoding.width(old) as isize,
+            new,
+        )?;
+        self.idx += encoding.width(new);
+        Ok(())
+    }
+}
+
 impl myers::DiffHook for BlockDiffHook<'_> {
     type Error = crate::AutomergeError;
 
@@ -295,14 +375,11 @@ impl myers::DiffHook for BlockDiffHook<'_> {
     ) -> Result<(), Self::Error> {
         for i in old_index..old_index + old_len {
             match &self.old[i] {
-                BlockOrGrapheme::Block(_) => {
+                BlockOrUnit::Block(_) => {
                     self.tx
                         .join_block(self.doc, self.patch_log, self.obj, self.idx)?;
                 }
-                BlockOrGrapheme::Grapheme(_) => {
-                    self.tx
-                        .delete(self.doc, self.patch_log, self.obj, self.idx)?;
-                }
+                BlockOrUnit::Unit(u) => self.splice(u, "")?,
             }
         }
         Ok(())
@@ -318,7 +395,7 @@ impl myers::DiffHook for BlockDiffHook<'_> {
         let mut run = String::new();
         for i in new_index..new_index + new_len {
             match &self.new[i] {
-                BlockOrGrapheme::Block(b) => {
+                BlockOrUnit::Block(b) => {
                     if !run.is_empty() {
                         self.tx.splice_text(
                             self.doc,
@@ -334,7 +411,7 @@ impl myers::DiffHook for BlockDiffHook<'_> {
                     split_block(self.doc, self.tx, self.patch_log, self.obj, self.idx, b)?;
                     self.idx += 1;
                 }
-                BlockOrGrapheme::Grapheme(g) => {
+                BlockOrUnit::Unit(g) => {
                     run.push_str(g);
                 }
             }
@@ -354,6 +431,44 @@ impl myers::DiffHook for BlockDiffHook<'_> {
         new_index: usize,
         new_len: usize,
     ) -> Result<(), Self::Error> {
+        if self.refine {
+            let graphemes = |units: &[BlockOrUnit]| {
+                units
+                    .iter()
+                    .flat_map(|unit| match unit {
+                        BlockOrUnit::Block(b) => vec![BlockOrUnit::Block(b.clone())],
+                        BlockOrUnit::Unit(u) => u
+                            .graphemes(true)
+                            .map(|g| BlockOrUnit::Unit(g.to_string()))
+                            .collect(),
+                    })
+                    .collect::<Vec<_>>()
+            };
+            let old = graphemes(&self.old[old_index..old_index + old_len]);
+            let new = graphemes(&self.new[new_index..new_index + new_len]);
+            let mut hook = replace::Replace::new(BlockDiffHook {
+                doc: self.doc,
+                tx: self.tx,
+                patch_log: self.patch_log,
+                old: &old,
+                new: &new,
+                obj: self.obj,
+                idx: self.idx,
+                refine: false,
+                deadline: self.deadline,
+            });
+            myers::diff(
+                &mut hook,
+                &old,
+                0..old.len(),
+                &new,
+                0..new.len(),
+                self.deadline,
+            )?;
+            self.idx = hook.into_inner().idx;
+            return Ok(());
+        }
+
         // iterate through the old and new indices, if we're replacing a block with a block, update
         // the block. Otherwise, delete the old and insert the new
         let mut old_idx = old_index;
@@ -372,12 +487,12 @@ impl myers::DiffHook for BlockDiffHook<'_> {
             match (old, new) {
                 (None, None) => {}
                 (None, Some(val)) => match val {
-                    BlockOrGrapheme::Block(b) => {
+                    BlockOrUnit::Block(b) => {
                         split_block(self.doc, self.tx, self.patch_log, self.obj, self.idx, b)?;
                         self.idx += 1;
                         new_idx += 1;
                     }
-                    BlockOrGrapheme::Grapheme(g) => {
+                    BlockOrUnit::Unit(g) => {
                         self.tx
                             .splice_text(self.doc, self.patch_log, self.obj, self.idx, 0, g)?;
                         self.idx += self.doc.text_encoding().width(g);
@@ -385,19 +500,18 @@ impl myers::DiffHook for BlockDiffHook<'_> {
                     }
                 },
                 (Some(val), None) => match val {
-                    BlockOrGrapheme::Block(_) => {
+                    BlockOrUnit::Block(_) => {
                         self.tx
                             .join_block(self.doc, self.patch_log, self.obj, self.idx)?;
                         old_idx += 1;
                     }
-                    BlockOrGrapheme::Grapheme(_) => {
-                        self.tx
-                            .delete(self.doc, self.patch_log, self.obj, self.idx)?;
+                    BlockOrUnit::Unit(u) => {
+                        self.splice(u, "")?;
                         old_idx += 1;
                     }
                 },
                 (Some(old), Some(new)) => match (old, new) {
-                    (BlockOrGrapheme::Block(b1), BlockOrGrapheme::Block(b2)) => {
+                    (BlockOrUnit::Block(b1), BlockOrUnit::Block(b2)) => {
                         if b1 != b2 {
                             update_block(self.doc, self.tx, self.patch_log, self.obj, self.idx, b2)?
                         }
@@ -405,16 +519,12 @@ impl myers::DiffHook for BlockDiffHook<'_> {
                         old_idx += 1;
                         new_idx += 1;
                     }
-                    (BlockOrGrapheme::Grapheme(_g1), BlockOrGrapheme::Grapheme(g2)) => {
-                        self.tx
-                            .delete(self.doc, self.patch_log, self.obj, self.idx)?;
-                        self.tx
-                            .splice_text(self.doc, self.patch_log, self.obj, self.idx, 0, g2)?;
-                        self.idx += self.doc.text_encoding().width(g2);
+                    (BlockOrUnit::Unit(u1), BlockOrUnit::Unit(u2)) => {
+                        self.splice(u1, u2)?;
                         old_idx += 1;
                         new_idx += 1;
                     }
-                    (BlockOrGrapheme::Block(_), BlockOrGrapheme::Grapheme(g2)) => {
+                    (BlockOrUnit::Block(_), BlockOrUnit::Unit(g2)) => {
                         self.tx
                             .join_block(self.doc, self.patch_log, self.obj, self.idx)?;
                         self.tx
@@ -423,9 +533,8 @@ impl myers::DiffHook for BlockDiffHook<'_> {
                         old_idx += 1;
                         new_idx += 1;
                     }
-                    (BlockOrGrapheme::Grapheme(_g1), BlockOrGrapheme::Block(b2)) => {
-                        self.tx
-                            .delete(self.doc, self.patch_log, self.obj, self.idx)?;
+                    (BlockOrUnit::Unit(u1), BlockOrUnit::Block(b2)) => {
+                        self.splice(u1, "")?;
                         split_block(self.doc, self.tx, self.patch_log, self.obj, self.idx, b2)?;
                         self.idx += 1;
                         old_idx += 1;
@@ -443,11 +552,12 @@ impl myers::DiffHook for BlockDiffHook<'_> {
     }
 }
 
-fn spans_as_grapheme(
+fn spans_as_units(
     doc: &Automerge,
     text: &crate::types::ObjId,
     clock: Option<Clock>,
-) -> Result<Vec<BlockOrGrapheme>, crate::AutomergeError> {
+    granularity: TextDiffGranularity,
+) -> Result<Vec<BlockOrUnit>, crate::AutomergeError> {
     let range = doc.ops.scope_to_obj(text);
     let spans_internal = SpansInternal::new(doc.ops(), range, clock.clone(), doc.text_encoding());
     let mut result = Vec::with_capacity(spans_internal.size_hint().0);
@@ -457,14 +567,14 @@ fn spans_as_grapheme(
                 let crate::hydrate::Value::Map(map) = doc.hydrate_map(&b.into(), clock.as_ref())
                 else {
                     tracing::warn!("unexpected non map object in text");
-                    result.push(BlockOrGrapheme::Block(crate::hydrate::Map::new()));
+                    result.push(BlockOrUnit::Block(crate::hydrate::Map::new()));
                     continue;
                 };
-                result.push(BlockOrGrapheme::Block(map));
+                result.push(BlockOrUnit::Block(map));
             }
             SpanInternal::Text(t, _, _) => {
-                for g in t.graphemes(true) {
-                    result.push(BlockOrGrapheme::Grapheme(g.to_string()));
+                for u in split_units(&t, granularity) {
+                    result.push(BlockOrUnit::Unit(u.to_string()));
                 }
             }
         }
@@ -472,14 +582,17 @@ fn spans_as_grapheme(
     Ok(result)
 }
 
-fn span_as_grapheme<I: Iterator<Item = Span>>(iter: I) -> Vec<BlockOrGrapheme> {
+fn span_as_units<I: Iterator<Item = Span>>(
+    iter: I,
+    granularity: TextDiffGranularity,
+) -> Vec<BlockOrUnit> {
     let mut result = Vec::with_capacity(iter.size_hint().0);
     for b in iter {
         match b {
-            Span::Block(b) => result.push(BlockOrGrapheme::Block(b)),
+            Span::Block(b) => result.push(BlockOrUnit::Block(b)),
             Span::Text { text, .. } => {
-                for g in text.graphemes(true) {
-                    result.push(BlockOrGrapheme::Grapheme(g.to_string()));
+                for u in split_units(&text, granularity) {
+                    result.push(BlockOrUnit::Unit(u.to_string()));
                 }
             }
         }
@@ -3,7 +3,8 @@
 // The original license is in the LICENSE file in the same directory as this file
 //
 // This file was modified to use a Diff trait defined in this file rather than the DiffHook trait
-// defined in `similar` and to remote the deadline parameter to the `diff` function.
+// defined in `similar` and to use a `Deadline` which also works in the browser in place of an
+// `Instant`.
 //! Myers' diff algorithm.
 //!
 //! * time: `O((N+M)D)`
@@ -20,9 +21,12 @@
 //! At present this implementation of Myers' does not implement any more advanced
 //! heuristics that would solve some pathological cases.  For instance passing two
 //! large and completely distinct sequences to the algorithm will make it spin
-//! without making reasonable progress.
+//! without making reasonable progress.  To avoid this you can pass a deadline
+//! after which the algorithm gives up and emits a deletion and an insertion for
+//! whatever is left to compare.
 
 use std::ops::{Index, IndexMut, Range};
+use std::time::Duration;
 
 use super::utils::{common_prefix_len, common_suffix_len, is_empty_range};
 
@@ -51,6 +55,34 @@ pub(super) trait DiffHook: Sized {
     fn finish(&mut self) -> Result<(), Self::Error>;
 }
 
+/// The time after which [`diff`] stops looking for the shortest edit script
+#[derive(Debug, Clone, Copy)]
+pub(super) struct Deadline {
+    #[cfg(not(all(feature = "wasm", target_family = "wasm")))]
+    at: std::time::Instant,
+    /// Milliseconds since the epoch, `Instant` is not available in the browser
+    #[cfg(all(feature = "wasm", target_family = "wasm"))]
+    at: f64,
+}
+
+impl Deadline {
+    pub(super) fn after(timeout: Duration) -> Self {
+        #[cfg(not(all(feature = "wasm", target_family = "wasm")))]
+        let at = std::time::Instant::now() + timeout;
+        #[cfg(all(feature = "wasm", target_family = "wasm"))]
+        let at = js_sys::Date::now() + timeout.as_secs_f64() * 1000.0;
+        Self { at }
+    }
+
+    fn passed(&self) -> bool {
+        #[cfg(not(all(feature = "wasm", target_family = "wasm")))]
+        let now = std::time::Instant::now();
+        #[cfg(all(feature = "wasm", target_family = "wasm"))]
+        let now = js_sys::Date::now();
+        now > self.at
+    }
+}
+
 /// Myers' diff algorithm.
 ///
 /// Diff `old`, between indices `old_range` and `new` between indices `new_range`.
@@ -60,6 +92,7 @@ pub(super) fn diff<Old, New, D>(
     old_range: Range<usize>,
     new: &New,
     new_range: Range<usize>,
+    deadline: Option<Deadline>,
 ) -> Result<(), D::Error>
 where
     Old: Index<usize> + ?Sized,
@@ -70,7 +103,9 @@ where
     let max_d = max_d(old_range.len(), new_range.len());
     let mut vb = V::new(max_d);
     let mut vf = V::new(max_d);
-    conquer(d, old, old_range, new, new_range, &mut vf, &mut vb)?;
+    conquer(
+        d, old, old_range, new, new_range, &mut vf, &mut vb, deadline,
+    )?;
     d.finish()
 }
 
@@ -149,6 +184,7 @@ fn find_middle_snake<Old, New>(
     new_range: Range<usize>,
     vf: &mut V,
     vb: &mut V,
+    deadline: Option<Deadline>,
 ) -> Option<(usize, usize)>
 where
     Old: Index<usize> + ?Sized,
@@ -174,6 +210,11 @@ where
     assert!(vb.len() >= d_max);
 
     for d in 0..d_max as isize {
+        // are we running for too long?
+        if deadline.is_some_and(|deadline| deadline.passed()) {
+            break;
+        }
+
         // Forward path
         for k in (-d..=d).rev().step_by(2) {
             let mut x = if k == -d || (k != d && vf[k - 1] < vf[k + 1]) {
@@ -261,6 +302,7 @@ fn conquer<Old, New, D>(
     mut new_range: Range<usize>,
     vf: &mut V,
     vb: &mut V,
+    deadline: Option<Deadline>,
 ) -> Result<(), D::Error>
 where
     Old: Index<usize> + ?Sized,
@@ -291,13 +333,19 @@ where
         d.delete(old_range.start, old_range.len(), new_range.start)?;
     } else if is_empty_range(&old_range) {
         d.insert(old_range.start, new_range.start, new_range.len())?;
-    } else if let Some((x_start, y_start)) =
-        find_middle_snake(old, old_range.clone(), new, new_range.clone(), vf, vb)
-    {
+    } else if let Some((x_start, y_start)) = find_middle_snake(
+        old,
+        old_range.clone(),
+        new,
+        new_range.clone(),
+        vf,
+        vb,
+        deadline,
+    ) {
         let (old_a, old_b) = split_at(old_range, x_start);
         let (new_a, new_b) = split_at(new_range, y_start);
-        conquer(d, old, old_a, new, new_a, vf, vb)?;
-        conquer(d, old, old_b, new, new_b, vf, vb)?;
+        conquer(d, old, old_a, new, new_a, vf, vb, deadline)?;
+        conquer(d, old, old_b, new, new_b, vf, vb, deadline)?;
     } else {
         d.delete(
             old_range.start,
@@ -326,7 +374,7 @@ fn test_find_middle_snake() {
     let mut vf = V::new(max_d);
     let mut vb = V::new(max_d);
     let (x_start, y_start) =
-        find_middle_snake(a, 0..a.len(), b, 0..b.len(), &mut vf, &mut vb).unwrap();
+        find_middle_snake(a, 0..a.len(), b, 0..b.len(), &mut vf, &mut vb, None).unwrap();
     assert_eq!(x_start, 4);
     assert_eq!(y_start, 1);
 }
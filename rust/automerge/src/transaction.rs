@@ -2,32 +2,45 @@ mod commit;
 mod inner;
 mod manual_transaction;
 mod owned_transaction;
+mod precondition;
 mod result;
 mod transactable;
 
 pub use self::commit::CommitOptions;
-pub use self::transactable::{BlockOrText, Transactable};
+pub use self::transactable::{BlockOrText, TextDiffGranularity, Transactable, UpdateTextConfig};
 pub(crate) use inner::{TransactionArgs, TransactionInner};
 pub use manual_transaction::Transaction;
 pub use owned_transaction::OwnedTransaction;
+pub use precondition::{FailedPrecondition, PreconditionConflict};
+pub(crate) use precondition::{GuardState, GuardedWrite, Precondition};
 pub use result::Failure;
 pub use result::Success;
 
 pub type Result<O, E> = std::result::Result<Success<O>, Failure<E>>;
 
+/// Commit `tx`, or roll it back and return the failed precondition if one does not hold
 fn commit_transaction(
     tx: TransactionInner,
     doc: &mut crate::Automerge,
     patch_log: &mut crate::PatchLog,
     options: CommitOptions,
-) -> Option<crate::ChangeHash> {
+) -> std::result::Result<Option<crate::ChangeHash>, crate::AutomergeError> {
+    if let Err(e) = tx.check_preconditions(doc) {
+        patch_log.finish_transaction(&doc.ops().actors);
+        tx.rollback(doc);
+        return Err(e);
+    }
+    let preconditions = tx.preconditions().to_vec();
     let historical_heads = tx.get_scope().as_ref().map(|_| tx.get_deps());
     let hash = tx.commit(doc, options.message, options.time);
     if let Some(heads) = historical_heads {
         patch_log.heads = Some(hash.map_or(heads, |hash| vec![hash]));
     }
     patch_log.finish_transaction(&doc.ops().actors);
-    hash
+    if let Some(hash) = hash {
+        doc.guard_writes(&preconditions, hash);
+    }
+    Ok(hash)
 }
 
 /// Generate a `ReadDoc` impl for `Transaction` and `OwnedTransaction`, which are expected to
@@ -111,6 +124,41 @@ macro_rules! impl_read_doc_for_tx {
                     .values_for(obj.as_ref(), self.get_scope(Some(heads)))
             }
 
+            fn set_contains<O: AsRef<crate::exid::ExId>>(
+                &self,
+                obj: O,
+                value: &crate::ScalarValue,
+            ) -> Result<bool, crate::AutomergeError> {
+                self.doc
+                    .set_contains_for(obj.as_ref(), value, self.get_scope(None))
+            }
+
+            fn set_contains_at<O: AsRef<crate::exid::ExId>>(
+                &self,
+                obj: O,
+                value: &crate::ScalarValue,
+                heads: &[crate::ChangeHash],
+            ) -> Result<bool, crate::AutomergeError> {
+                self.doc
+                    .set_contains_for(obj.as_ref(), value, self.get_scope(Some(heads)))
+            }
+
+            fn set_values<O: AsRef<crate::exid::ExId>>(
+                &self,
+                obj: O,
+            ) -> crate::iter::SetValues<'_> {
+                self.doc.set_values_for(obj.as_ref(), self.get_scope(None))
+            }
+
+            fn set_values_at<O: AsRef<crate::exid::ExId>>(
+                &self,
+                obj: O,
+                heads: &[crate::ChangeHash],
+            ) -> crate::iter::SetValues<'_> {
+                self.doc
+                    .set_values_for(obj.as_ref(), self.get_scope(Some(heads)))
+            }
+
             fn length<O: AsRef<crate::exid::ExId>>(&self, obj: O) -> usize {
                 self.doc.length_for(obj.as_ref(), self.get_scope(None))
             }
@@ -204,6 +252,41 @@ macro_rules! impl_read_doc_for_tx {
                     .get_cursor_position_for(obj.as_ref(), address, self.get_scope(at))
             }
 
+            fn get_cursor_positions<O: AsRef<crate::exid::ExId>>(
+                &self,
+                obj: O,
+                cursors: &[crate::Cursor],
+                at: Option<&[crate::ChangeHash]>,
+            ) -> Result<Vec<usize>, crate::AutomergeError> {
+                let cursors = cursors
+                    .iter()
+                    .map(|cursor| (cursor, crate::cursor::CursorAnchor::At))
+                    .collect::<Vec<_>>();
+                self.doc
+                    .get_cursor_positions_for(obj.as_ref(), &cursors, self.get_scope(at))
+            }
+
+            fn get_cursor_range<O: AsRef<crate::exid::ExId>>(
+                &self,
+                obj: O,
+                range: std::ops::Range<usize>,
+                expand: crate::marks::ExpandMark,
+                at: Option<&[crate::ChangeHash]>,
+            ) -> Result<crate::CursorRange, crate::AutomergeError> {
+                self.doc
+                    .get_cursor_range_for(obj.as_ref(), range, expand, self.get_scope(at))
+            }
+
+            fn get_cursor_range_positions<O: AsRef<crate::exid::ExId>>(
+                &self,
+                obj: O,
+                ranges: &[crate::CursorRange],
+                at: Option<&[crate::ChangeHash]>,
+            ) -> Result<Vec<crate::CursorRangePosition>, crate::AutomergeError> {
+                self.doc
+                    .get_cursor_range_positions_for(obj.as_ref(), ranges, self.get_scope(at))
+            }
+
             fn marks<O: AsRef<crate::exid::ExId>>(
                 &self,
                 obj: O,
@@ -238,6 +321,37 @@ macro_rules! impl_read_doc_for_tx {
                     .get_marks_for(obj.as_ref(), index, self.get_scope(heads))
             }
 
+            fn query_marks<O: AsRef<crate::exid::ExId>>(
+                &self,
+                obj: O,
+                query: &crate::marks::MarkQuery,
+                heads: Option<&[crate::ChangeHash]>,
+            ) -> Result<Vec<crate::marks::Mark>, crate::AutomergeError> {
+                self.doc
+                    .query_marks_for(obj.as_ref(), query, self.get_scope(heads))
+            }
+
+            fn next_mark_change<O: AsRef<crate::exid::ExId>>(
+                &self,
+                obj: O,
+                name: &str,
+                index: usize,
+                heads: Option<&[crate::ChangeHash]>,
+            ) -> Result<Option<usize>, crate::AutomergeError> {
+                self.doc
+                    .next_mark_change_for(obj.as_ref(), name, index, self.get_scope(heads))
+            }
+
+            fn grapheme_range<O: AsRef<crate::exid::ExId>>(
+                &self,
+                obj: O,
+                range: std::ops::Range<usize>,
+                heads: Option<&[crate::ChangeHash]>,
+            ) -> Result<std::ops::Range<usize>, crate::AutomergeError> {
+                self.doc
+                    .grapheme_range_for(obj.as_ref(), range, self.get_scope(heads))
+            }
+
             fn get<O: AsRef<crate::exid::ExId>, P: Into<crate::Prop>>(
                 &self,
                 obj: O,
@@ -304,6 +418,10 @@ macro_rules! impl_read_doc_for_tx {
                 self.doc.stats()
             }
 
+            fn detailed_stats(&self) -> crate::read::DetailedStats {
+                self.doc.detailed_stats()
+            }
+
             fn text_encoding(&self) -> crate::TextEncoding {
                 self.doc.text_encoding()
             }
@@ -377,6 +495,106 @@ macro_rules! impl_transactable_for_tx {
                 self.do_tx(|tx, doc, hist| tx.delete(doc, hist, obj.as_ref(), prop))
             }
 
+            fn resolve_conflict<
+                O: AsRef<crate::exid::ExId>,
+                P: Into<crate::Prop>,
+                V: Into<crate::ScalarValue>,
+            >(
+                &mut self,
+                obj: O,
+                prop: P,
+                value: V,
+                superseding: &[crate::exid::ExId],
+            ) -> Result<(), crate::AutomergeError> {
+                self.do_tx(|tx, doc, hist| {
+                    tx.resolve_conflict(
+                        doc,
+                        hist,
+                        obj.as_ref(),
+                        prop.into(),
+                        value.into(),
+                        superseding,
+                    )
+                })
+            }
+
+            fn bounded_counter_increment<O: AsRef<crate::exid::ExId>>(
+                &mut self,
+                obj: O,
+                n: u64,
+            ) -> Result<(), crate::AutomergeError> {
+                let op = crate::bounded_counter::BoundedCounterOp::Increment(n);
+                self.do_tx(|tx, doc, hist| tx.bounded_counter(doc, hist, obj.as_ref(), op))
+            }
+
+            fn bounded_counter_decrement<O: AsRef<crate::exid::ExId>>(
+                &mut self,
+                obj: O,
+                n: u64,
+            ) -> Result<(), crate::AutomergeError> {
+                let op = crate::bounded_counter::BoundedCounterOp::Decrement(n);
+                self.do_tx(|tx, doc, hist| tx.bounded_counter(doc, hist, obj.as_ref(), op))
+            }
+
+            fn bounded_counter_transfer<O: AsRef<crate::exid::ExId>>(
+                &mut self,
+                obj: O,
+                to: &crate::ActorId,
+                n: u64,
+            ) -> Result<(), crate::AutomergeError> {
+                let op = crate::bounded_counter::BoundedCounterOp::Transfer(to.clone(), n);
+                self.do_tx(|tx, doc, hist| tx.bounded_counter(doc, hist, obj.as_ref(), op))
+            }
+
+            fn set_add<O: AsRef<crate::exid::ExId>, V: Into<crate::ScalarValue>>(
+                &mut self,
+                obj: O,
+                value: V,
+            ) -> Result<(), crate::AutomergeError> {
+                self.do_tx(|tx, doc, hist| tx.set_add(doc, hist, obj.as_ref(), value.into()))
+            }
+
+            fn set_remove<O: AsRef<crate::exid::ExId>, V: Into<crate::ScalarValue>>(
+                &mut self,
+                obj: O,
+                value: V,
+            ) -> Result<(), crate::AutomergeError> {
+                self.do_tx(|tx, doc, hist| tx.set_remove(doc, hist, obj.as_ref(), &value.into()))
+            }
+
+            fn move_element<O: AsRef<crate::exid::ExId>>(
+                &mut self,
+                obj: O,
+                from: usize,
+                to: usize,
+            ) -> Result<(), crate::AutomergeError> {
+                self.do_tx(|tx, doc, hist| tx.move_element(doc, hist, obj.as_ref(), from, to))
+            }
+
+            fn move_key<O: AsRef<crate::exid::ExId>>(
+                &mut self,
+                obj: O,
+                key: &str,
+                index: usize,
+            ) -> Result<(), crate::AutomergeError> {
+                self.do_tx(|tx, doc, hist| tx.move_key(doc, hist, obj.as_ref(), key, index))
+            }
+
+            fn move_object<
+                O: AsRef<crate::exid::ExId>,
+                P: AsRef<crate::exid::ExId>,
+                Q: Into<crate::Prop>,
+            >(
+                &mut self,
+                obj: O,
+                parent: P,
+                prop: Q,
+            ) -> Result<(), crate::AutomergeError> {
+                self.do_tx(|tx, doc, hist| {
+                    tx.move_object(doc, hist, obj.as_ref(), parent.as_ref(), prop.into())
+                })
+            }
+
             fn splice<O: AsRef<ExId>, V: Into<crate::hydrate::Value>, I: IntoIterator<Item = V>>(
                 &mut self,
                 obj: O,
@@ -463,9 +681,18 @@ macro_rules! impl_transactable_for_tx {
                 &mut self,
                 obj: &crate::exid::ExId,
                 new_text: S,
+            ) -> Result<(), crate::AutomergeError> {
+                self.update_text_with(obj, new_text, Default::default())
+            }
+
+            fn update_text_with<S: AsRef<str>>(
+                &mut self,
+                obj: &crate::exid::ExId,
+                new_text: S,
+                config: crate::transaction::UpdateTextConfig,
             ) -> Result<(), crate::AutomergeError> {
                 self.do_tx(|tx, doc, hist| {
-                    crate::text_diff::myers_diff(doc, tx, hist, obj, new_text)
+                    crate::text_diff::myers_diff(doc, tx, hist, obj, new_text, &config)
                 })
             }
 
@@ -1,9 +1,10 @@
 use std::borrow::Cow;
-use std::collections::{HashSet, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::num::NonZeroU64;
 use std::ops::Range;
 use std::sync::Arc;
 
+use crate::bounded_counter::{BoundedCounter, BoundedCounterOp};
 use crate::change_graph::ChangeGraph;
 use crate::op_set2::op_set::ResolvedAction;
 use unicode_segmentation::UnicodeSegmentation;
@@ -12,8 +13,12 @@ use crate::exid::ExId;
 use crate::marks::{ExpandMark, Mark, MarkSet};
 use crate::op_set2::change::build_change;
 use crate::op_set2::{Op, OpSet, PropRef, SuccInsert, TxOp};
+use crate::ordered_map;
 use crate::patches::PatchLog;
-use crate::types::{Clock, ElemId, ObjMeta, OpId, ScalarValue, SequenceType, TextEncoding, HEAD};
+use crate::transaction::Precondition;
+use crate::types::{
+    Clock, ElemId, ObjId, ObjMeta, OpId, ScalarValue, SequenceType, TextEncoding, HEAD,
+};
 use crate::Automerge;
 use crate::{hydrate, AutomergeError, ObjType, OpType, ReadDoc};
 use crate::{Change, ChangeHash, Prop};
@@ -28,6 +33,10 @@ pub(crate) struct TransactionInner {
     deps: Vec<ChangeHash>,
     scope: Option<Clock>,
     pending: Vec<TxOp>,
+    preconditions: Vec<Precondition>,
+    /// The keys of the ordered maps this transaction has written to, see
+    /// [`Self::ordered_keys`]
+    ordered_maps: HashMap<ObjId, ordered_map::SortedKeys>,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -81,6 +90,8 @@ impl TransactionInner {
             deps,
             pending: vec![],
             scope,
+            preconditions: vec![],
+            ordered_maps: HashMap::new(),
         }
     }
 
@@ -192,6 +203,28 @@ impl TransactionInner {
         Change::new(stored)
     }
 
+    /// Require the winner of `prop` in `obj` to be `winner` when this transaction commits
+    pub(crate) fn expect_winner(
+        &mut self,
+        doc: &Automerge,
+        obj: &ExId,
+        prop: Prop,
+        winner: Option<ExId>,
+    ) -> Result<(), AutomergeError> {
+        self.exid_to_obj(doc, obj)?;
+        self.preconditions
+            .push(Precondition::new(obj.clone(), prop, winner));
+        Ok(())
+    }
+
+    pub(crate) fn check_preconditions(&self, doc: &Automerge) -> Result<(), AutomergeError> {
+        self.preconditions.iter().try_for_each(|p| p.check(doc))
+    }
+
+    pub(crate) fn preconditions(&self) -> &[Precondition] {
+        &self.preconditions
+    }
+
     /// Undo the operations added in this transaction, returning the number of cancelled
     /// operations.
     pub(crate) fn rollback(self, doc: &mut Automerge) -> usize {
@@ -236,7 +269,7 @@ impl TransactionInner {
         let value = value.into();
         let prop = prop.into();
         match (&prop, obj.typ) {
-            (Prop::Map(_), ObjType::Map) => Ok(()),
+            (Prop::Map(_), ObjType::Map | ObjType::OrderedMap) => Ok(()),
             (Prop::Seq(_), ObjType::List) => Ok(()),
             (Prop::Seq(_), ObjType::Text) => Ok(()),
             _ => Err(AutomergeError::InvalidOp(obj.typ)),
@@ -269,7 +302,7 @@ impl TransactionInner {
         let obj = self.exid_to_obj(doc, ex_obj)?;
         let prop = prop.into();
         match (&prop, obj.typ) {
-            (Prop::Map(_), ObjType::Map) => Ok(()),
+            (Prop::Map(_), ObjType::Map | ObjType::OrderedMap) => Ok(()),
             (Prop::Seq(_), ObjType::List) => Ok(()),
             _ => Err(AutomergeError::InvalidOp(obj.typ)),
         }?;
@@ -287,7 +320,7 @@ impl TransactionInner {
             obj,
             index,
             elemid,
-            ops.iter().map(|op| op.id),
+            ops.iter().map(|op| op.stored_id()),
         )
     }
 
@@ -304,7 +337,8 @@ impl TransactionInner {
 
         op.undo = doc.ops_mut().add_succ_with_undo(succ);
 
-        if self.scope.is_some() {
+        // an op which doesn't supersede every visible op leaves the old top op in place
+        if self.scope.is_some() || op.reset_range.is_some() {
             doc.ops_mut().reset_top(range.start..(range.end + added));
             op.reset_range = Some(range);
         }
@@ -424,11 +458,73 @@ impl TransactionInner {
         action: OpType,
     ) -> Result<Option<OpId>, AutomergeError> {
         match prop {
+            Prop::Map(s) if obj.typ == ObjType::OrderedMap => {
+                self.local_ordered_map_op(doc, patch_log, obj, s, action)
+            }
             Prop::Map(s) => self.local_map_op(doc, patch_log, obj, s, action),
             Prop::Seq(n) => self.local_list_op(doc, patch_log, obj, n, action),
         }
     }
 
+    /// Make `action` at `key` in the ordered map `obj`, keeping the position of `key` in step:
+    /// deleting a key deletes its position and a key without a position is put at the end (see
+    /// [`crate::ordered_map`])
+    fn local_ordered_map_op(
+        &mut self,
+        doc: &mut Automerge,
+        patch_log: &mut PatchLog,
+        obj: &ObjMeta,
+        key: String,
+        action: OpType,
+    ) -> Result<Option<OpId>, AutomergeError> {
+        if ordered_map::is_position_key(&key) {
+            return Err(AutomergeError::ReservedKey(key));
+        }
+        let position_key = ordered_map::position_key(&key);
+        let delete = matches!(action, OpType::Delete);
+        let id = self.local_map_op(doc, patch_log, obj, key.clone(), action)?;
+        if delete {
+            self.local_map_op(doc, patch_log, obj, position_key, OpType::Delete)?;
+            self.ordered_keys(doc, &obj.id).remove(&key);
+        } else if id.is_some() {
+            let has_position = !doc
+                .ops()
+                .seek_ops_by_map_key(&obj.id, &position_key, self.scope.as_ref())
+                .ops
+                .is_empty();
+            if !has_position {
+                let keys = self.ordered_keys(doc, &obj.id);
+                let position = ordered_map::between(keys.last_position(), None);
+                let action = OpType::Put(position.clone().into());
+                self.local_map_op(doc, patch_log, obj, position_key, action)?;
+                self.ordered_keys(doc, &obj.id).set_position(&key, position);
+            } else if let Some(keys) = self.ordered_maps.get(&obj.id) {
+                // a key which was deleted concurrently with a move keeps the position it was
+                // moved to, so it comes back there, sort the keys again to find it
+                if !keys.entries().iter().any(|(k, _)| *k == key) {
+                    self.ordered_maps.remove(&obj.id);
+                }
+            }
+        }
+        Ok(id)
+    }
+
+    /// The keys of the ordered map `obj` in order, which are sorted the first time they are
+    /// needed and then kept up to date by the writes of this transaction, which is the only
+    /// thing that can change the document while it is open
+    fn ordered_keys(&mut self, doc: &Automerge, obj: &ObjId) -> &mut ordered_map::SortedKeys {
+        let scope = &self.scope;
+        self.ordered_maps.entry(*obj).or_insert_with(|| {
+            let entries = doc
+                .ops()
+                .ordered_map_entries(obj, scope.clone())
+                .into_iter()
+                .map(|(item, position)| (item.key.into_owned(), position))
+                .collect();
+            ordered_map::SortedKeys::new(entries)
+        })
+    }
+
     fn local_map_op(
         &mut self,
         doc: &mut Automerge,
@@ -455,7 +551,7 @@ impl TransactionInner {
 
         let increment_replacement =
             increment_replacement(&query.ops, &resolved_action, doc.text_encoding());
-        let pred = query.ops.iter().map(|op| op.id).collect();
+        let pred = query.ops.iter().map(|op| op.stored_id()).collect();
         let op = TxOp::map(id, *obj, query.end_pos, resolved_action, prop, pred);
 
         let inc_value = op.get_increment_value();
@@ -521,7 +617,7 @@ impl TransactionInner {
                     None
                 }
             });
-        let pred = query.ops.iter().map(|op| op.id).collect();
+        let pred = query.ops.iter().map(|op| op.stored_id()).collect();
         let op = TxOp::list(
             id,
             *obj,
@@ -583,6 +679,353 @@ impl TransactionInner {
         Ok(())
     }
 
+    /// Put `value` at `prop` in `ex_obj`, superseding only the values in `superseding`
+    ///
+    /// Unlike [`Self::put`] the other values at `prop` are not overwritten, so they stay in
+    /// conflict with the new value.
+    pub(crate) fn resolve_conflict(
+        &mut self,
+        doc: &mut Automerge,
+        patch_log: &mut PatchLog,
+        ex_obj: &ExId,
+        prop: Prop,
+        value: ScalarValue,
+        superseding: &[ExId],
+    ) -> Result<(), AutomergeError> {
+        let obj = self.exid_to_obj(doc, ex_obj)?;
+        let superseding = superseding
+            .iter()
+            .map(|exid| Ok((doc.exid_to_opid(exid)?, exid)))
+            .collect::<Result<Vec<_>, AutomergeError>>()?;
+        let id = self.next_id();
+        let action = ResolvedAction::VisibleUpdate(OpType::Put(value));
+        let (mut op, query, superseded) = match (&prop, obj.typ) {
+            (Prop::Map(key), ObjType::Map | ObjType::Table) => {
+                let query = doc
+                    .ops()
+                    .seek_ops_by_map_key(&obj.id, key, self.scope.as_ref());
+                let superseded = superseded_ops(&query.ops, &superseding)?;
+                let pred = superseded.iter().map(|op| op.stored_id()).collect();
+                let op = TxOp::map(id, obj, query.end_pos, action, key.clone(), pred);
+                (op, query, superseded)
+            }
+            (Prop::Seq(index), ObjType::List) => {
+                let query = doc.ops().seek_ops_by_index(
+                    &obj.id,
+                    *index,
+                    SequenceType::List,
+                    self.scope.as_ref(),
+                );
+                let eid = query
+                    .ops
+                    .first()
+                    .and_then(|op| op.cursor().ok())
+                    .ok_or(AutomergeError::InvalidIndex(*index))?;
+                let superseded = superseded_ops(&query.ops, &superseding)?;
+                let pred = superseded.iter().map(|op| op.stored_id()).collect();
+                let op = TxOp::list(id, obj, query.end_pos, query.index, action, eid, pred);
+                (op, query, superseded)
+            }
+            _ => return Err(AutomergeError::InvalidOp(obj.typ)),
+        };
+        let succ: Vec<_> = superseded.iter().map(|op| op.add_succ(id, None)).collect();
+        let conflict = superseded.len() < query.ops.len();
+        if conflict {
+            op.reset_range = Some(query.range.clone());
+        }
+        let prop = match prop {
+            Prop::Map(key) => Prop::Map(key),
+            Prop::Seq(_) => Prop::Seq(query.index),
+        };
+        self.insert_local_op(doc, patch_log, op, &succ, query.range, None);
+        if conflict && patch_log.is_active() {
+            patch_log.flag_conflict(obj.id, &prop);
+        }
+        Ok(())
+    }
+
+    /// Apply `op` to the bounded counter `ex_obj`, as the actor of this transaction
+    pub(crate) fn bounded_counter(
+        &mut self,
+        doc: &mut Automerge,
+        patch_log: &mut PatchLog,
+        ex_obj: &ExId,
+        op: BoundedCounterOp,
+    ) -> Result<(), AutomergeError> {
+        let obj = self.exid_to_obj(doc, ex_obj)?;
+        if obj.typ != ObjType::Map {
+            return Err(AutomergeError::InvalidOp(obj.typ));
+        }
+        let items = doc.ops().map_range(&obj.id, .., self.scope.clone());
+        let counter = BoundedCounter::from_items(items);
+        let actor = doc.ops().get_actor(self.actor).clone();
+        let Some((key, amount)) = counter.apply(&actor, op)? else {
+            return Ok(());
+        };
+        let exists = !doc
+            .ops()
+            .seek_ops_by_map_key(&obj.id, &key, self.scope.as_ref())
+            .ops
+            .is_empty();
+        let action = if exists {
+            OpType::Increment(amount)
+        } else {
+            OpType::Put(ScalarValue::counter(amount))
+        };
+        self.local_op(doc, patch_log, &obj, Prop::Map(key), action)?;
+        Ok(())
+    }
+
+    /// Add `value` to the set `ex_obj`
+    ///
+    /// This always makes a new op, even if `value` is already in the set, so that the value
+    /// survives a concurrent [`Self::set_remove`] which did not see this add.
+    pub(crate) fn set_add(
+        &mut self,
+        doc: &mut Automerge,
+        patch_log: &mut PatchLog,
+        ex_obj: &ExId,
+        value: ScalarValue,
+    ) -> Result<(), AutomergeError> {
+        let obj = self.exid_to_obj(doc, ex_obj)?;
+        let key = set_key(&obj, &value)?;
+        let id = self.next_id();
+        let query = doc
+            .ops()
+            .seek_ops_by_map_key(&obj.id, &key, self.scope.as_ref());
+        let pred = query.ops.iter().map(|op| op.stored_id()).collect();
+        let action = ResolvedAction::VisibleUpdate(OpType::Put(value));
+        let op = TxOp::map(id, obj, query.end_pos, action, key, pred);
+        let succ: Vec<_> = query.ops.iter().map(|op| op.add_succ(id, None)).collect();
+        self.insert_local_op(doc, patch_log, op, &succ, query.range, None);
+        Ok(())
+    }
+
+    /// Remove `value` from the set `ex_obj`, which only removes the adds of it this transaction
+    /// can see
+    pub(crate) fn set_remove(
+        &mut self,
+        doc: &mut Automerge,
+        patch_log: &mut PatchLog,
+        ex_obj: &ExId,
+        value: &ScalarValue,
+    ) -> Result<(), AutomergeError> {
+        let obj = self.exid_to_obj(doc, ex_obj)?;
+        let key = set_key(&obj, value)?;
+        self.local_op(doc, patch_log, &obj, Prop::Map(key), OpType::Delete)?;
+        Ok(())
+    }
+
+    /// Move the element at `from` in a list so that it ends up at index `to`
+    ///
+    /// A move is made of two ops: a claim on the moved element, which supersedes any previous
+    /// claims, and a new slot inserted after the element which will precede the moved one.
+    pub(crate) fn move_element(
+        &mut self,
+        doc: &mut Automerge,
+        patch_log: &mut PatchLog,
+        ex_obj: &ExId,
+        from: usize,
+        to: usize,
+    ) -> Result<(), AutomergeError> {
+        let obj = self.exid_to_obj(doc, ex_obj)?;
+        if obj.typ != ObjType::List {
+            return Err(AutomergeError::InvalidOp(obj.typ));
+        }
+        let query = doc
+            .ops()
+            .query_move(&obj.id, from, to, self.scope.as_ref())?;
+        if from == to {
+            return Ok(());
+        }
+
+        let id = self.next_id();
+        let pred = query.claims.iter().map(|op| op.id).collect();
+        let succ = query
+            .claims
+            .iter()
+            .map(|op| op.add_succ(id, None))
+            .collect::<Vec<_>>();
+        let claim_pos = query.claim_pos;
+        let anchor = query.anchor;
+        let slot_pos = query.anchor_pos + usize::from(query.anchor_pos >= claim_pos);
+        let mut claim = TxOp::list(
+            id,
+            obj,
+            claim_pos,
+            from,
+            ResolvedAction::VisibleUpdate(OpType::Move),
+            query.elem,
+            pred,
+        );
+        doc.ops_mut().splice(claim.pos, &[&claim]);
+        claim.undo = doc.ops_mut().add_succ_with_undo(&succ);
+        self.pending.push(claim);
+
+        let slot = TxOp::insert(self.next_id(), obj, slot_pos, to, OpType::Move, anchor);
+        doc.ops_mut().splice(slot.pos, &[&slot]);
+        self.pending.push(slot);
+
+        if patch_log.is_active() {
+            patch_log.move_seq(obj.id, from, to);
+        }
+        Ok(())
+    }
+
+    /// Move `key` in the ordered map `ex_obj` to `index`
+    ///
+    /// This puts a position for `key` between those of its new neighbours. Any keys after it
+    /// with the same position as the key before it, which happens when keys are moved to the
+    /// same place concurrently, are given new positions after it, and any keys before it without
+    /// a position are given one.
+    pub(crate) fn move_key(
+        &mut self,
+        doc: &mut Automerge,
+        patch_log: &mut PatchLog,
+        ex_obj: &ExId,
+        key: &str,
+        index: usize,
+    ) -> Result<(), AutomergeError> {
+        let obj = self.exid_to_obj(doc, ex_obj)?;
+        if obj.typ != ObjType::OrderedMap {
+            return Err(AutomergeError::InvalidOp(obj.typ));
+        }
+        let mut entries = self.ordered_keys(doc, &obj.id).entries().to_vec();
+        let from = entries
+            .iter()
+            .position(|(k, _)| k == key)
+            .ok_or_else(|| AutomergeError::MissingKey(key.to_string()))?;
+        if index >= entries.len() {
+            return Err(AutomergeError::InvalidIndex(index));
+        }
+        let (_, position) = entries.remove(from);
+        if from == index && position.is_some() {
+            return Ok(());
+        }
+
+        let mut positions = Vec::new();
+        let mut before = None;
+        for (k, p) in &entries[..index] {
+            match p {
+                Some(p) => before = Some(p.clone()),
+                None => {
+                    let p = ordered_map::between(before.as_deref(), None);
+                    positions.push((k.clone(), p.clone()));
+                    before = Some(p);
+                }
+            }
+        }
+        let mut moved = vec![key.to_string()];
+        let mut after = None;
+        for (k, p) in &entries[index..] {
+            match p {
+                Some(p) if before.as_ref() == Some(p) => moved.push(k.clone()),
+                Some(p) => {
+                    after = Some(p.clone());
+                    break;
+                }
+                None => break,
+            }
+        }
+        for k in moved {
+            let p = ordered_map::between(before.as_deref(), after.as_deref());
+            positions.push((k, p.clone()));
+            before = Some(p);
+        }
+
+        for (k, p) in positions {
+            let position_key = ordered_map::position_key(&k);
+            let action = OpType::Put(p.clone().into());
+            self.local_map_op(doc, patch_log, &obj, position_key, action)?;
+            self.ordered_keys(doc, &obj.id).set_position(&k, p);
+        }
+        Ok(())
+    }
+
+    /// Move the object `ex_obj` to `prop` in the map or list `ex_parent`
+    ///
+    /// This is a single op in `ex_parent`, see [`crate::op_set2::op_set::TreeLocations`]. In a map
+    /// it replaces the value at the key and in a list it is inserted at the index.
+    pub(crate) fn move_object(
+        &mut self,
+        doc: &mut Automerge,
+        patch_log: &mut PatchLog,
+        ex_obj: &ExId,
+        ex_parent: &ExId,
+        prop: Prop,
+    ) -> Result<(), AutomergeError> {
+        let obj = self.exid_to_obj(doc, ex_obj)?;
+        let parent = self.exid_to_obj(doc, ex_parent)?;
+        let ops = doc.ops();
+        let origin = ops.object_parent(&obj.id);
+        // the positions of the keys of an ordered map are kept in step with the keys, which a
+        // move can't do
+        if let Some(typ @ (ObjType::Text | ObjType::OrderedMap)) =
+            origin.and_then(|o| ops.object_type(&o))
+        {
+            return Err(AutomergeError::InvalidOp(typ));
+        }
+        match (parent.typ, &prop) {
+            (ObjType::Map | ObjType::Table, Prop::Map(_)) | (ObjType::List, Prop::Seq(_)) => {}
+            (typ, _) => return Err(AutomergeError::InvalidOp(typ)),
+        }
+        let locations = ops.tree_locations(self.scope.as_ref());
+        if ops.is_ancestor(&obj.id, parent.id, &locations) {
+            return Err(AutomergeError::MoveIntoDescendant);
+        }
+        if let Prop::Seq(index) = prop {
+            if index > ops.seq_length(&parent.id, doc.text_encoding(), self.scope.clone()) {
+                return Err(AutomergeError::InvalidIndex(index));
+            }
+        }
+
+        let moved = ops.id_to_exid(obj.id.0);
+        let before = patch_log
+            .is_active()
+            .then(|| ops.visible_path(obj.id, self.scope.as_ref()));
+        if before.is_some() {
+            // the patches so far need the paths from before the move
+            patch_log.finish_events(doc);
+        }
+
+        let action = OpType::MoveObject(moved.clone());
+        let null = &mut PatchLog::null();
+        match prop {
+            Prop::Map(key) => {
+                self.local_map_op(doc, null, &parent, key, action)?;
+            }
+            Prop::Seq(index) => {
+                self.do_insert(doc, null, &parent, SequenceType::List, index, action)?;
+            }
+        }
+
+        if let Some(before) = before {
+            let after = doc.ops().visible_path(obj.id, self.scope.as_ref());
+            match (before, after) {
+                (Some(before), Some(after)) => {
+                    patch_log.detach_at(before, moved.clone());
+                    patch_log.attach_at(after, moved, false);
+                }
+                (Some(before), None) => patch_log.delete_at(before),
+                (None, Some(mut after)) => {
+                    // the object was not visible, so it appears with all of its contents
+                    let value = hydrate::Value::new(obj.typ, doc.text_encoding());
+                    match after.pop().map(|(_, prop)| prop) {
+                        Some(Prop::Map(key)) => {
+                            patch_log.put_map(parent.id, &key, value, obj.id.0, false, true)
+                        }
+                        Some(Prop::Seq(index)) => patch_log.insert_and_maybe_expose(
+                            parent.id, index, value, obj.id.0, false, true,
+                        ),
+                        None => {}
+                    }
+                }
+                (None, None) => {}
+            }
+        }
+        Ok(())
+    }
+
     /// Splice new elements into the given sequence. Returns a vector of the OpIds used to insert
     /// the new elements.
     ///
@@ -793,9 +1236,11 @@ impl TransactionInner {
         expand: ExpandMark,
     ) -> Result<(), AutomergeError> {
         let obj = self.exid_to_obj(doc, ex_obj)?;
-        if ObjType::Text != obj.typ {
-            return Err(AutomergeError::InvalidOp(obj.typ));
-        }
+        let seq_type = match obj.typ {
+            ObjType::Text => SequenceType::Text,
+            ObjType::List => SequenceType::List,
+            _ => return Err(AutomergeError::InvalidOp(obj.typ)),
+        };
         if mark.start == mark.end && expand == ExpandMark::None {
             // In peritext terms this is the same as a mark which has a begin anchor before one
             // character and an end anchor after the character preceding that character. E.g in the
@@ -811,7 +1256,7 @@ impl TransactionInner {
         }
 
         let action = OpType::MarkBegin(expand.before(), mark.old_data());
-        let begin = self.do_insert(doc, patch_log, &obj, SequenceType::Text, mark.start, action)?;
+        let begin = self.do_insert(doc, patch_log, &obj, seq_type, mark.start, action)?;
 
         let end = if mark.start == mark.end {
             self.insert_mark_end_after(doc, patch_log, &obj, &begin, expand.after())
@@ -826,14 +1271,14 @@ impl TransactionInner {
             // above does.
             let end_pos = doc
                 .ops()
-                .query_insert_at(&obj.id, mark.end, SequenceType::Text, self.scope.clone())?
+                .query_insert_at(&obj.id, mark.end, seq_type, self.scope.clone())?
                 .pos;
             if end_pos > begin.pos {
                 self.do_insert(
                     doc,
                     patch_log,
                     &obj,
-                    SequenceType::Text,
+                    seq_type,
                     mark.end,
                     OpType::MarkEnd(expand.after()),
                 )?
@@ -1066,6 +1511,12 @@ impl TransactionInner {
             (ObjType::List, crate::hydrate::Value::List(list)) => {
                 Ok(self.update_list(doc, patch_log, obj, list)?)
             }
+            (ObjType::Set, crate::hydrate::Value::Set(set)) => {
+                Ok(self.update_set(doc, patch_log, obj, set)?)
+            }
+            (ObjType::OrderedMap, crate::hydrate::Value::OrderedMap(map)) => {
+                Ok(self.update_ordered_map(doc, patch_log, obj, map)?)
+            }
             (ObjType::Text, crate::hydrate::Value::Text(new_text)) => {
                 Ok(crate::text_diff::myers_diff(
                     doc,
@@ -1073,6 +1524,7 @@ impl TransactionInner {
                     patch_log,
                     obj,
                     new_text.to_string().as_str(),
+                    &Default::default(),
                 )?)
             }
             _ => Err(crate::error::UpdateObjectError::ChangeType),
@@ -1131,6 +1583,55 @@ impl TransactionInner {
         Ok(())
     }
 
+    pub(crate) fn update_ordered_map(
+        &mut self,
+        doc: &mut Automerge,
+        patch_log: &mut PatchLog,
+        map: &crate::ObjId,
+        new_value: &crate::hydrate::OrderedMap,
+    ) -> Result<(), AutomergeError> {
+        let obj = self.exid_to_obj(doc, map)?;
+        let current_vals = doc
+            .ops()
+            .map_range(&obj.id, .., self.scope.clone())
+            .map(|m| (m.key.to_string(), m.value.to_value(), m.id()))
+            .collect::<Vec<_>>();
+        for (key, value, id) in current_vals {
+            match new_value.get(&key) {
+                Some(new_value) => self.update_value(
+                    doc,
+                    patch_log,
+                    map,
+                    key.into(),
+                    new_value,
+                    Some((id, value)),
+                )?,
+                None => self.delete(doc, patch_log, map, key)?,
+            }
+        }
+        // new keys are put at the end, in order, and then any keys which are out of place moved
+        let mut keys = self
+            .ordered_keys(doc, &obj.id)
+            .entries()
+            .iter()
+            .map(|(k, _)| k.clone())
+            .collect::<Vec<_>>();
+        for (key, new_value) in new_value.iter() {
+            if !keys.contains(key) {
+                self.update_value(doc, patch_log, map, key.into(), &new_value.value, None)?;
+                keys.push(key.clone());
+            }
+        }
+        for (index, key) in new_value.keys().enumerate() {
+            if keys[index] != key {
+                self.move_key(doc, patch_log, map, key, index)?;
+                keys.retain(|k| k != key);
+                keys.insert(index, key.to_string());
+            }
+        }
+        Ok(())
+    }
+
     pub(crate) fn update_list(
         &mut self,
         doc: &mut Automerge,
@@ -1186,6 +1687,25 @@ impl TransactionInner {
         Ok(())
     }
 
+    pub(crate) fn update_set(
+        &mut self,
+        doc: &mut Automerge,
+        patch_log: &mut PatchLog,
+        set: &crate::ObjId,
+        new_value: &crate::hydrate::Set,
+    ) -> Result<(), AutomergeError> {
+        let current = doc
+            .set_values_for(set, self.scope.clone())
+            .collect::<crate::hydrate::Set>();
+        for value in current.iter().filter(|v| !new_value.contains(v)) {
+            self.set_remove(doc, patch_log, set, value)?;
+        }
+        for value in new_value.iter().filter(|v| !current.contains(v)) {
+            self.set_add(doc, patch_log, set, value.clone())?;
+        }
+        Ok(())
+    }
+
     fn update_value(
         &mut self,
         doc: &mut Automerge,
@@ -1202,8 +1722,22 @@ impl TransactionInner {
             (Some((id, crate::Value::Object(ObjType::List))), crate::hydrate::Value::List(new)) => {
                 self.update_list(doc, patch_log, &id, new)
             }
+            (Some((id, crate::Value::Object(ObjType::Set))), crate::hydrate::Value::Set(new)) => {
+                self.update_set(doc, patch_log, &id, new)
+            }
+            (
+                Some((id, crate::Value::Object(ObjType::OrderedMap))),
+                crate::hydrate::Value::OrderedMap(new),
+            ) => self.update_ordered_map(doc, patch_log, &id, new),
             (Some((id, crate::Value::Object(ObjType::Text))), crate::hydrate::Value::Text(new)) => {
-                crate::text_diff::myers_diff(doc, self, patch_log, &id, new.to_string().as_str())
+                crate::text_diff::myers_diff(
+                    doc,
+                    self,
+                    patch_log,
+                    &id,
+                    new.to_string().as_str(),
+                    &Default::default(),
+                )
             }
             (old, new) => {
                 // Here we are either changing the type of the existing object, or inserting an
@@ -1230,6 +1764,16 @@ impl TransactionInner {
                         self.splice_text(doc, patch_log, &text_id, 0, 0, new.to_string().as_str())
                     }
 
+                    crate::hydrate::Value::Set(new) => {
+                        let set_id = make_obj(ObjType::Set)?;
+                        self.update_set(doc, patch_log, &set_id, new)
+                    }
+
+                    crate::hydrate::Value::OrderedMap(new) => {
+                        let map_id = make_obj(ObjType::OrderedMap)?;
+                        self.update_ordered_map(doc, patch_log, &map_id, new)
+                    }
+
                     crate::hydrate::Value::Scalar(val) => match (old, &key) {
                         (None, Prop::Seq(index)) => {
                             self.insert(doc, patch_log, parent, *index, val.clone())
@@ -1264,7 +1808,7 @@ impl TransactionInner {
         let parent = self.exid_to_obj(doc, ex_parent)?;
 
         match (&prop, insert, parent.typ) {
-            (Prop::Map(_), _, ObjType::Map) => Ok(()),
+            (Prop::Map(_), _, ObjType::Map | ObjType::OrderedMap) => Ok(()),
             (Prop::Seq(_), _, ObjType::List) => Ok(()),
             (Prop::Seq(_), true, ObjType::Text) => Ok(()),
             _ => Err(AutomergeError::InvalidOp(parent.typ)),
@@ -1275,6 +1819,8 @@ impl TransactionInner {
             hydrate::Value::Map(_) => ObjType::Map,
             hydrate::Value::List(_) => ObjType::List,
             hydrate::Value::Text(_) => ObjType::Text,
+            hydrate::Value::Set(_) => ObjType::Set,
+            hydrate::Value::OrderedMap(_) => ObjType::OrderedMap,
             hydrate::Value::Scalar(_) => return Err(AutomergeError::NotAnObject),
         };
 
@@ -1514,6 +2060,10 @@ fn value_to_op_type(value: &hydrate::Value) -> (Option<ObjType>, OpType) {
         hydrate::Value::Map(_) => (Some(ObjType::Map), OpType::Make(ObjType::Map)),
         hydrate::Value::List(_) => (Some(ObjType::List), OpType::Make(ObjType::List)),
         hydrate::Value::Text(_) => (Some(ObjType::Text), OpType::Make(ObjType::Text)),
+        hydrate::Value::Set(_) => (Some(ObjType::Set), OpType::Make(ObjType::Set)),
+        hydrate::Value::OrderedMap(_) => {
+            (Some(ObjType::OrderedMap), OpType::Make(ObjType::OrderedMap))
+        }
         hydrate::Value::Scalar(s) => (None, OpType::Put(s.clone())),
     }
 }
@@ -1580,6 +2130,64 @@ fn batch_bfs(
                 let text_str = text.to_string();
                 batch.splice_text(container_meta, 0, ElemId::head(), &text_str, None);
             }
+            (ObjType::OrderedMap, hydrate::Value::OrderedMap(map)) => {
+                // each key and its position, which are put in key order like the keys of a map
+                let mut position = None;
+                let mut entries = Vec::new();
+                for (key, map_value) in map.iter() {
+                    if ordered_map::is_position_key(key) {
+                        return Err(AutomergeError::ReservedKey(key.clone()));
+                    }
+                    let next = ordered_map::between(position.as_deref(), None);
+                    let value = hydrate::Value::Scalar(ScalarValue::Str(next.as_str().into()));
+                    entries.push((ordered_map::position_key(key), Cow::Owned(value)));
+                    entries.push((key.clone(), Cow::Borrowed(&map_value.value)));
+                    position = Some(next);
+                }
+                entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+                for (key, child_value) in entries {
+                    let (child_obj_type, op_type) = value_to_op_type(&child_value);
+
+                    let id = batch.append(|pos, id| {
+                        TxOp::map(
+                            id,
+                            container_meta,
+                            pos,
+                            ResolvedAction::VisibleUpdate(op_type),
+                            key,
+                            vec![],
+                        )
+                    });
+
+                    // positions are scalars, so only borrowed values can be objects
+                    if let (Some(obj_type), Cow::Borrowed(child_value)) =
+                        (child_obj_type, child_value)
+                    {
+                        let child_obj_meta = ObjMeta {
+                            id: crate::types::ObjId(id),
+                            typ: obj_type,
+                        };
+                        queue.push_back((child_obj_meta, child_value));
+                    }
+                }
+            }
+            (ObjType::Set, hydrate::Value::Set(set)) => {
+                for value in set.iter() {
+                    let key = set_key(&container_meta, value)?;
+                    let op_type = OpType::Put(value.clone());
+                    batch.append(|pos, id| {
+                        TxOp::map(
+                            id,
+                            container_meta,
+                            pos,
+                            ResolvedAction::VisibleUpdate(op_type),
+                            key,
+                            vec![],
+                        )
+                    });
+                }
+            }
             _ => {
                 return Err(AutomergeError::InvalidOp(container_meta.typ));
             }
@@ -1588,6 +2196,38 @@ fn batch_bfs(
     Ok(())
 }
 
+/// The ops in `ops` which are named by `superseding`, or an error if any of `superseding` is not
+/// one of them
+fn superseded_ops<'a>(
+    ops: &[Op<'a>],
+    superseding: &[(OpId, &ExId)],
+) -> Result<Vec<Op<'a>>, AutomergeError> {
+    if let Some((_, exid)) = superseding
+        .iter()
+        .find(|(id, _)| !ops.iter().any(|op| op.id == *id))
+    {
+        return Err(AutomergeError::NotACurrentValue((*exid).clone()));
+    }
+    Ok(ops
+        .iter()
+        .filter(|op| superseding.iter().any(|(id, _)| op.id == *id))
+        .cloned()
+        .collect())
+}
+
+/// The key `value` is stored under in the set `obj`
+fn set_key(obj: &ObjMeta, value: &ScalarValue) -> Result<String, AutomergeError> {
+    if obj.typ != ObjType::Set {
+        return Err(AutomergeError::InvalidOp(obj.typ));
+    }
+    value
+        .set_key()
+        .ok_or_else(|| AutomergeError::InvalidValueType {
+            expected: "a value which is not a counter".to_string(),
+            unexpected: value.to_string(),
+        })
+}
+
 fn increment_replacement(
     ops: &[Op<'_>],
     action: &ResolvedAction,
@@ -1,7 +1,7 @@
 use crate::exid::ExId;
 use crate::patches::PatchLog;
-use crate::ChangeHash;
 use crate::{automerge::Automerge, AutomergeError};
+use crate::{ChangeHash, Prop};
 
 use super::{CommitOptions, TransactionArgs, TransactionInner};
 
@@ -62,14 +62,54 @@ impl Transaction<'_> {
         self.doc.get_heads()
     }
 
+    /// Require the winner of `prop` in `obj` to be `winner` when this transaction commits
+    ///
+    /// `winner` is the id of the value read from the document, as returned by
+    /// [`ReadDoc::get`](crate::ReadDoc::get), or `None` if the property was expected to be unset.
+    /// The property is checked against the document as it is when the transaction is committed,
+    /// not counting the operations of the transaction itself, so a change made since the
+    /// transaction started (for example one made after the heads passed to
+    /// [`Automerge::transaction_at`]) makes the check fail. If any precondition fails then the
+    /// commit rolls the transaction back and returns no hash.
+    ///
+    /// Values this transaction puts at the property can still be beaten by concurrent changes
+    /// which are merged later, see [`Automerge::take_precondition_conflicts`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `obj` is not an object in the document.
+    pub fn expect_winner<O: AsRef<ExId>, P: Into<Prop>>(
+        &mut self,
+        obj: O,
+        prop: P,
+        winner: Option<ExId>,
+    ) -> Result<(), AutomergeError> {
+        self.inner
+            .as_mut()
+            .unwrap()
+            .expect_winner(self.doc, obj.as_ref(), prop.into(), winner)
+    }
+
+    /// Check the preconditions added with [`Self::expect_winner`], returning
+    /// [`AutomergeError::PreconditionFailed`] for the first which does not hold
+    pub fn check_preconditions(&self) -> Result<(), AutomergeError> {
+        self.inner.as_ref().unwrap().check_preconditions(self.doc)
+    }
+
     /// Commit the operations performed in this transaction, returning the hashes corresponding to
     /// the new heads.
-    pub fn commit(mut self) -> (Option<ChangeHash>, PatchLog) {
-        let tx = self.inner.take().unwrap();
-        let hash =
-            super::commit_transaction(tx, self.doc, &mut self.patch_log, CommitOptions::default());
-        // TODO - remove this clone
-        (hash, self.patch_log.clone())
+    ///
+    /// If a precondition added with [`Self::expect_winner`] does not hold then the transaction is
+    /// rolled back and no hash is returned, use [`Self::try_commit`] to find out which.
+    pub fn commit(self) -> (Option<ChangeHash>, PatchLog) {
+        self.commit_with(CommitOptions::default())
+    }
+
+    /// Commit the operations performed in this transaction, or return
+    /// [`AutomergeError::PreconditionFailed`] if a precondition added with
+    /// [`Self::expect_winner`] does not hold, in which case the transaction is rolled back.
+    pub fn try_commit(self) -> (Result<Option<ChangeHash>, AutomergeError>, PatchLog) {
+        self.try_commit_with(CommitOptions::default())
     }
 
     /// Commit the operations in this transaction with some options.
@@ -88,7 +128,16 @@ impl Transaction<'_> {
     /// i64;
     /// tx.commit_with(CommitOptions::default().with_message("Create todos list").with_time(now));
     /// ```
-    pub fn commit_with(mut self, options: CommitOptions) -> (Option<ChangeHash>, PatchLog) {
+    pub fn commit_with(self, options: CommitOptions) -> (Option<ChangeHash>, PatchLog) {
+        let (hash, patch_log) = self.try_commit_with(options);
+        (hash.unwrap_or(None), patch_log)
+    }
+
+    /// Commit the operations in this transaction with some options, see [`Self::try_commit`]
+    pub fn try_commit_with(
+        mut self,
+        options: CommitOptions,
+    ) -> (Result<Option<ChangeHash>, AutomergeError>, PatchLog) {
         let tx = self.inner.take().unwrap();
         let hash = super::commit_transaction(tx, self.doc, &mut self.patch_log, options);
         // TODO - remove this clone
@@ -1,7 +1,7 @@
 use crate::automerge::Automerge;
 use crate::exid::ExId;
 use crate::patches::PatchLog;
-use crate::{ChangeHash, PatchLogMismatch};
+use crate::{AutomergeError, ChangeHash, PatchLogMismatch, Prop};
 
 use super::{CommitOptions, TransactionInner};
 
@@ -64,25 +64,78 @@ impl OwnedTransaction {
         self.doc.get_heads()
     }
 
+    /// Require the winner of `prop` in `obj` to be `winner` when this transaction commits
+    ///
+    /// `winner` is the id of the value read from the document, as returned by
+    /// [`ReadDoc::get`](crate::ReadDoc::get), or `None` if the property was expected to be unset.
+    /// The property is checked against the document as it is when the transaction is committed,
+    /// not counting the operations of the transaction itself, so a change made since the
+    /// transaction started (for example one made after the heads passed to
+    /// [`Automerge::transaction_at`]) makes the check fail. If any precondition fails then the
+    /// commit rolls the transaction back and returns no hash.
+    ///
+    /// Values this transaction puts at the property can still be beaten by concurrent changes
+    /// which are merged later, see [`Automerge::take_precondition_conflicts`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `obj` is not an object in the document.
+    pub fn expect_winner<O: AsRef<ExId>, P: Into<Prop>>(
+        &mut self,
+        obj: O,
+        prop: P,
+        winner: Option<ExId>,
+    ) -> Result<(), AutomergeError> {
+        self.inner
+            .as_mut()
+            .unwrap()
+            .expect_winner(&self.doc, obj.as_ref(), prop.into(), winner)
+    }
+
+    /// Check the preconditions added with [`Self::expect_winner`], returning
+    /// [`AutomergeError::PreconditionFailed`] for the first which does not hold
+    pub fn check_preconditions(&self) -> Result<(), AutomergeError> {
+        self.inner.as_ref().unwrap().check_preconditions(&self.doc)
+    }
+
     /// Commit the transaction, returning the document, commit hash, and patch log.
     ///
     /// Unlike [`super::Transaction::commit`], no `PatchLog` clone is needed — it is moved out.
-    pub fn commit(mut self) -> (Automerge, Option<ChangeHash>, PatchLog) {
-        let tx = self.inner.take().unwrap();
-        let hash = super::commit_transaction(
-            tx,
-            &mut self.doc,
-            &mut self.patch_log,
-            CommitOptions::default(),
-        );
-        (self.doc, hash, self.patch_log)
+    /// As with that method no hash is returned, and the transaction is rolled back, if a
+    /// precondition added with [`Self::expect_winner`] does not hold, use [`Self::try_commit`] to
+    /// find out which.
+    pub fn commit(self) -> (Automerge, Option<ChangeHash>, PatchLog) {
+        self.commit_with(CommitOptions::default())
     }
 
     /// Commit with options.
-    pub fn commit_with(
+    pub fn commit_with(self, options: CommitOptions) -> (Automerge, Option<ChangeHash>, PatchLog) {
+        let (doc, hash, patch_log) = self.try_commit_with(options);
+        (doc, hash.unwrap_or(None), patch_log)
+    }
+
+    /// Commit the transaction, or return [`AutomergeError::PreconditionFailed`] if a
+    /// precondition added with [`Self::expect_winner`] does not hold, in which case the
+    /// transaction is rolled back.
+    pub fn try_commit(
+        self,
+    ) -> (
+        Automerge,
+        Result<Option<ChangeHash>, AutomergeError>,
+        PatchLog,
+    ) {
+        self.try_commit_with(CommitOptions::default())
+    }
+
+    /// Commit with options, see [`Self::try_commit`]
+    pub fn try_commit_with(
         mut self,
         options: CommitOptions,
-    ) -> (Automerge, Option<ChangeHash>, PatchLog) {
+    ) -> (
+        Automerge,
+        Result<Option<ChangeHash>, AutomergeError>,
+        PatchLog,
+    ) {
         let tx = self.inner.take().unwrap();
         let hash = super::commit_transaction(tx, &mut self.doc, &mut self.patch_log, options);
         (self.doc, hash, self.patch_log)
@@ -0,0 +1,128 @@
+use crate::exid::ExId;
+use crate::{Automerge, AutomergeError, ChangeHash, Prop, ReadDoc};
+
+/// The value a transaction expects to be the winner of a property when it commits, see
+/// [`Transaction::expect_winner`](super::Transaction::expect_winner)
+#[derive(Debug, Clone)]
+pub(crate) struct Precondition {
+    obj: ExId,
+    prop: Prop,
+    expected: Option<ExId>,
+}
+
+impl Precondition {
+    pub(crate) fn new(obj: ExId, prop: Prop, expected: Option<ExId>) -> Self {
+        Self {
+            obj,
+            prop,
+            expected,
+        }
+    }
+
+    /// Check this against the current heads of `doc`, which don't include the ops of a
+    /// transaction in progress but do include any changes made since it started
+    pub(crate) fn check(&self, doc: &Automerge) -> Result<(), AutomergeError> {
+        let clock = doc.change_graph.clock_at(&doc.get_heads());
+        let actual = doc
+            .get_for(&self.obj, self.prop.clone(), Some(clock))?
+            .map(|(_, id)| id);
+        if actual == self.expected {
+            Ok(())
+        } else {
+            Err(AutomergeError::PreconditionFailed(Box::new(
+                FailedPrecondition {
+                    obj: self.obj.clone(),
+                    prop: self.prop.clone(),
+                    expected: self.expected.clone(),
+                    actual,
+                },
+            )))
+        }
+    }
+
+    /// The value of this property which was put by the change `hash`, if there is one
+    pub(crate) fn guarded_write(&self, doc: &Automerge, hash: ChangeHash) -> Option<GuardedWrite> {
+        let values = doc.get_all(&self.obj, self.prop.clone()).ok()?;
+        let (_, write) = values
+            .into_iter()
+            .rev()
+            .find(|(_, id)| doc.hash_for_opid(id) == Some(hash))?;
+        Some(GuardedWrite {
+            obj: self.obj.clone(),
+            prop: self.prop.clone(),
+            write,
+        })
+    }
+}
+
+/// A precondition which did not hold, see [`AutomergeError::PreconditionFailed`]
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+#[error("expected the winner of {prop} in {obj} to be {expected:?} but it is {actual:?}")]
+pub struct FailedPrecondition {
+    /// The object the precondition was on
+    pub obj: ExId,
+    /// The property the precondition was on
+    pub prop: Prop,
+    /// The id of the value which was expected to be the winner
+    pub expected: Option<ExId>,
+    /// The id of the value which is the winner
+    pub actual: Option<ExId>,
+}
+
+/// A value put by a transaction which had a precondition on the property it was put at
+#[derive(Debug, Clone)]
+pub(crate) struct GuardedWrite {
+    obj: ExId,
+    prop: Prop,
+    write: ExId,
+}
+
+/// The state of a [`GuardedWrite`] in a document
+pub(crate) enum GuardState {
+    /// The write is still the winner
+    Winning,
+    /// The write has been overwritten or deleted by a change which saw it
+    Replaced,
+    /// A concurrent value has beaten the write
+    Beaten(PreconditionConflict),
+}
+
+impl GuardedWrite {
+    pub(crate) fn check(&self, doc: &Automerge) -> GuardState {
+        let values = doc
+            .get_all(&self.obj, self.prop.clone())
+            .unwrap_or_default();
+        if !values.iter().any(|(_, id)| id == &self.write) {
+            return GuardState::Replaced;
+        }
+        match doc.get(&self.obj, self.prop.clone()) {
+            Ok(Some((_, winner))) if winner != self.write => {
+                GuardState::Beaten(PreconditionConflict {
+                    obj: self.obj.clone(),
+                    prop: self.prop.clone(),
+                    write: self.write.clone(),
+                    winner,
+                })
+            }
+            _ => GuardState::Winning,
+        }
+    }
+}
+
+/// A value put by a transaction with a precondition on its property, which a concurrent change
+/// has since beaten
+///
+/// The precondition held when the transaction committed, but a change it did not see (usually
+/// one merged in later) put a value which wins over it. Both values are still available from
+/// [`ReadDoc::get_all`], see [`Automerge::take_precondition_conflicts`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct PreconditionConflict {
+    /// The object the value was put in
+    pub obj: ExId,
+    /// The property the value was put at
+    pub prop: Prop,
+    /// The id of the value the transaction put
+    pub write: ExId,
+    /// The id of the value which won over it
+    pub winner: ExId,
+}
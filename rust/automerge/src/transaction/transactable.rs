@@ -1,9 +1,11 @@
 use std::borrow::Cow;
+use std::ops::Range;
+use std::time::Duration;
 
 use crate::exid::ExId;
 use crate::iter::Span;
 use crate::marks::{ExpandMark, Mark, UpdateSpansConfig};
-use crate::{AutomergeError, ChangeHash, ObjType, Prop, ReadDoc, ScalarValue};
+use crate::{ActorId, AutomergeError, ChangeHash, ObjType, Prop, ReadDoc, ScalarValue};
 
 /// A way of mutating a document within a single change.
 pub trait Transactable: ReadDoc {
@@ -75,6 +77,149 @@ pub trait Transactable: ReadDoc {
         prop: P,
     ) -> Result<(), AutomergeError>;
 
+    /// Put `value` at `prop` in `obj`, superseding only the values in `superseding`.
+    ///
+    /// [`Self::put`] overwrites every value at `prop`, including conflicting values written
+    /// concurrently. This instead overwrites just the values named in `superseding`, which are
+    /// IDs returned by [`crate::ReadDoc::get_all`], so an application can merge some of the
+    /// conflicting values and leave the rest in conflict with the merged value. If
+    /// `superseding` is empty `value` is added as another conflicting value.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AutomergeError::InvalidOp`] if `obj` is not a map or a list, and
+    /// [`AutomergeError::NotACurrentValue`] if one of `superseding` is not a value at `prop`.
+    fn resolve_conflict<O: AsRef<ExId>, P: Into<Prop>, V: Into<ScalarValue>>(
+        &mut self,
+        obj: O,
+        prop: P,
+        value: V,
+        superseding: &[ExId],
+    ) -> Result<(), AutomergeError>;
+
+    /// Increment the bounded counter `obj` by `n`, giving this actor the rights to `n`.
+    ///
+    /// See [`crate::bounded_counter`] for how bounded counters work.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AutomergeError::InvalidOp`] if `obj` is not a map.
+    fn bounded_counter_increment<O: AsRef<ExId>>(
+        &mut self,
+        obj: O,
+        n: u64,
+    ) -> Result<(), AutomergeError>;
+
+    /// Decrement the bounded counter `obj` by `n`, spending `n` of the rights this actor holds.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AutomergeError::InsufficientRights`] if this actor holds fewer than `n` rights
+    /// to the counter, and [`AutomergeError::InvalidOp`] if `obj` is not a map.
+    fn bounded_counter_decrement<O: AsRef<ExId>>(
+        &mut self,
+        obj: O,
+        n: u64,
+    ) -> Result<(), AutomergeError>;
+
+    /// Transfer `n` of the rights this actor holds to the bounded counter `obj` to `to`.
+    ///
+    /// # Errors
+    ///
+    /// See [`Self::bounded_counter_decrement`]
+    fn bounded_counter_transfer<O: AsRef<ExId>>(
+        &mut self,
+        obj: O,
+        to: &ActorId,
+        n: u64,
+    ) -> Result<(), AutomergeError>;
+
+    /// Add `value` to the set `obj`.
+    ///
+    /// Sets are add-wins: if the same value is added and removed concurrently it stays in the
+    /// set, because a remove only removes the adds it has seen.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AutomergeError::InvalidOp`] if `obj` is not a set and
+    /// [`AutomergeError::InvalidValueType`] if `value` is a counter, which cannot be a member of
+    /// a set.
+    fn set_add<O: AsRef<ExId>, V: Into<ScalarValue>>(
+        &mut self,
+        obj: O,
+        value: V,
+    ) -> Result<(), AutomergeError>;
+
+    /// Remove `value` from the set `obj`. Removing a value which is not in the set does nothing.
+    ///
+    /// # Errors
+    ///
+    /// See [`Self::set_add`]
+    fn set_remove<O: AsRef<ExId>, V: Into<ScalarValue>>(
+        &mut self,
+        obj: O,
+        value: V,
+    ) -> Result<(), AutomergeError>;
+
+    /// Move the element at index `from` in a list so that it ends up at index `to`.
+    ///
+    /// `to` is the index of the element once the move is complete. The element keeps its
+    /// identity: its object ID, cursors pointing at it and any edits made to it concurrently
+    /// with the move are preserved. If the same element is moved concurrently by several actors
+    /// one of the moves wins and the element appears only once.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AutomergeError::InvalidOp`] if `obj` is not a list and
+    /// [`AutomergeError::InvalidIndex`] if either index is out of bounds.
+    fn move_element<O: AsRef<ExId>>(
+        &mut self,
+        obj: O,
+        from: usize,
+        to: usize,
+    ) -> Result<(), AutomergeError>;
+
+    /// Move `key` in the ordered map `obj` so that it ends up at index `index`.
+    ///
+    /// `index` is the index of the key once the move is complete. Moving a key doesn't change its
+    /// value, so moves and concurrent puts of the same key are both kept. If the same key is
+    /// moved concurrently by several actors one of the moves wins.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AutomergeError::InvalidOp`] if `obj` is not an ordered map,
+    /// [`AutomergeError::MissingKey`] if `key` is not in it and [`AutomergeError::InvalidIndex`]
+    /// if `index` is out of bounds.
+    fn move_key<O: AsRef<ExId>>(
+        &mut self,
+        obj: O,
+        key: &str,
+        index: usize,
+    ) -> Result<(), AutomergeError>;
+
+    /// Move the object `obj` so that it is the value of `prop` in `parent`.
+    ///
+    /// `obj` may be a map or a list, and `parent` a map or a list. If `parent` is a map the object
+    /// replaces the current value of `prop`, if it is a list the object is inserted at the index
+    /// `prop`. The object keeps its identity and its contents, including any edits made to it
+    /// concurrently with the move.
+    ///
+    /// If the same object is moved concurrently by several actors one of the moves wins and the
+    /// object appears only once. A move which would make an object a descendant of itself,
+    /// which can happen when two objects are concurrently moved into each other, is ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AutomergeError::InvalidOp`] if `obj` is in a text object or an ordered map or
+    /// `prop` does not match the type of `parent`, [`AutomergeError::InvalidIndex`] if the index is out of bounds
+    /// and [`AutomergeError::MoveIntoDescendant`] if `parent` is `obj` or one of its descendants.
+    fn move_object<O: AsRef<ExId>, P: AsRef<ExId>, Q: Into<Prop>>(
+        &mut self,
+        obj: O,
+        parent: P,
+        prop: Q,
+    ) -> Result<(), AutomergeError>;
+
     /// replace a section of a list. If `del` is positive then N values
     /// are deleted after position `pos` and the new values inserted. If
     /// it is negative then N values are deleted before position `pos` instead.
@@ -99,6 +244,38 @@ pub trait Transactable: ReadDoc {
         text: &str,
     ) -> Result<(), AutomergeError>;
 
+    /// Like [`Self::splice_text`] but first widens the deleted range to grapheme cluster
+    /// boundaries, see [`ReadDoc::grapheme_range`], so that no user perceived character is split.
+    /// Text inserted in the middle of a grapheme cluster is inserted after it instead.
+    ///
+    /// # Returns
+    ///
+    /// The range of the text which was replaced.
+    fn splice_graphemes<O: AsRef<ExId>>(
+        &mut self,
+        obj: O,
+        pos: usize,
+        del: isize,
+        text: &str,
+    ) -> Result<Range<usize>, AutomergeError> {
+        let range = if del < 0 {
+            let start = pos
+                .checked_sub(del.unsigned_abs())
+                .ok_or(AutomergeError::InvalidIndex(pos))?;
+            start..pos
+        } else {
+            pos..pos + del as usize
+        };
+        let snapped = self.grapheme_range(obj.as_ref(), range.clone(), None)?;
+        let snapped = if range.is_empty() {
+            snapped.end..snapped.end
+        } else {
+            snapped
+        };
+        self.splice_text(obj, snapped.start, snapped.len() as isize, text)?;
+        Ok(snapped)
+    }
+
     /// Mark a sequence
     fn mark<O: AsRef<ExId>>(
         &mut self,
@@ -164,6 +341,15 @@ pub trait Transactable: ReadDoc {
     fn update_text<S: AsRef<str>>(&mut self, obj: &ExId, new_text: S)
         -> Result<(), AutomergeError>;
 
+    /// Like [`Self::update_text`] but with an [`UpdateTextConfig`] which controls how the old and
+    /// new text are compared
+    fn update_text_with<S: AsRef<str>>(
+        &mut self,
+        obj: &ExId,
+        new_text: S,
+        config: UpdateTextConfig,
+    ) -> Result<(), AutomergeError>;
+
     fn update_object<O: AsRef<ExId>>(
         &mut self,
         obj: O,
@@ -196,6 +382,57 @@ pub trait Transactable: ReadDoc {
         -> Result<(), AutomergeError>;
 }
 
+/// The units in which [`Transactable::update_text_with`] compares the old and new text
+///
+/// A unit which changed is deleted and inserted as a whole, so coarser units produce edits which
+/// don't interleave with concurrent edits to the same word or line, at the cost of replacing more
+/// text than strictly necessary.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TextDiffGranularity {
+    /// Grapheme clusters, so that no user perceived character is split
+    #[default]
+    Grapheme,
+    /// Words, runs of whitespace and punctuation, as defined by the Unicode word boundary rules
+    Word,
+    /// Lines, including the line break at their end
+    Line,
+}
+
+/// Configure how [`Transactable::update_text_with`] and [`Transactable::update_spans`] compare the
+/// old and new text
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct UpdateTextConfig {
+    /// The units in which the old and new text are compared
+    pub granularity: TextDiffGranularity,
+    /// Whether to compare the graphemes of words or lines which were replaced by other words or
+    /// lines, so that only the characters which changed are edited. Unchanged words or lines
+    /// still anchor the diff, which keeps it from matching up unrelated parts of the text.
+    pub refine: bool,
+    /// How long to spend looking for the smallest set of edits. Once the time is up the parts of
+    /// the text which have not been compared yet are replaced as a whole.
+    pub timeout: Option<Duration>,
+}
+
+impl UpdateTextConfig {
+    /// Set [`Self::granularity`]
+    pub fn with_granularity(mut self, granularity: TextDiffGranularity) -> Self {
+        self.granularity = granularity;
+        self
+    }
+
+    /// Set [`Self::refine`]
+    pub fn with_refine(mut self, refine: bool) -> Self {
+        self.refine = refine;
+        self
+    }
+
+    /// Set [`Self::timeout`]
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum BlockOrText<'a> {
     Block(crate::hydrate::Map),
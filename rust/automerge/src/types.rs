@@ -1,5 +1,6 @@
 use crate::error;
 use crate::error::AutomergeError;
+use crate::exid::ExId;
 use crate::legacy as amp;
 use crate::op_set2::ActorIdx;
 use rand::{
@@ -178,6 +179,11 @@ pub enum ObjType {
     List,
     /// A sequence of characters
     Text,
+    /// An add-wins set of scalar values, see [`crate::transaction::Transactable::set_add`]
+    Set,
+    /// A map which keeps its keys in an order, see
+    /// [`crate::transaction::Transactable::move_key`]
+    OrderedMap,
 }
 
 impl ObjType {
@@ -219,6 +225,8 @@ impl fmt::Display for ObjType {
             ObjType::Table => write!(f, "table"),
             ObjType::List => write!(f, "list"),
             ObjType::Text => write!(f, "text"),
+            ObjType::Set => write!(f, "set"),
+            ObjType::OrderedMap => write!(f, "orderedmap"),
         }
     }
 }
@@ -231,6 +239,11 @@ pub enum OpType {
     Put(ScalarValue),
     MarkBegin(bool, OldMarkData),
     MarkEnd(bool),
+    /// One half of moving a list element, see [`crate::transaction::Transactable::move_element`]
+    Move,
+    /// Move an object to the key or index of this op, see
+    /// [`crate::transaction::Transactable::move_object`]
+    MoveObject(ExId),
 }
 
 impl OpType {
@@ -246,9 +259,23 @@ impl OpType {
             },
             6 => Ok(()),
             7 => Ok(()),
+            8 | 11 => match value {
+                ScalarValue::Null => Ok(()),
+                _ => Err(error::InvalidOpType::InvalidMoveTarget),
+            },
+            9 | 10 => Ok(()),
             _ => Err(error::InvalidOpType::UnknownAction(action)),
         }
     }
+
+    /// The object a [`OpType::MoveObject`] moves, whose id must come from the document the op is
+    /// made in
+    pub(crate) fn target(&self) -> Option<ObjId> {
+        match self {
+            Self::MoveObject(ExId::Id(ctr, _, idx)) => Some(ObjId(OpId::new(*ctr, *idx))),
+            _ => None,
+        }
+    }
 }
 
 impl From<ObjType> for OpType {
@@ -498,7 +525,6 @@ impl OpId {
     }
 
     #[inline]
-    #[expect(dead_code)]
     pub(crate) fn next(&self) -> OpId {
         OpId(self.0 + 1, self.1)
     }
@@ -641,6 +667,33 @@ impl TextEncoding {
             }
         }
     }
+
+    /// The byte offset in `s` of the position `width` units from its start, or `None` if `s` is
+    /// shorter than `width` or the position is not on a character boundary
+    pub(crate) fn byte_index(&self, s: &str, width: usize) -> Option<usize> {
+        let mut units = match self {
+            Self::Utf8CodeUnit => return s.is_char_boundary(width).then_some(width),
+            Self::UnicodeCodePoint => s.char_indices().map(|(i, _)| (i, 1)).collect::<Vec<_>>(),
+            Self::Utf16CodeUnit => s
+                .char_indices()
+                .map(|(i, c)| (i, c.len_utf16()))
+                .collect::<Vec<_>>(),
+            Self::GraphemeCluster => {
+                unicode_segmentation::UnicodeSegmentation::grapheme_indices(s, true)
+                    .map(|(i, _)| (i, 1))
+                    .collect::<Vec<_>>()
+            }
+        };
+        units.push((s.len(), 0));
+        let mut seen = 0;
+        for (index, w) in units {
+            if seen == width {
+                return Some(index);
+            }
+            seen += w;
+        }
+        None
+    }
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
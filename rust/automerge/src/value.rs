@@ -1,5 +1,7 @@
 use crate::error;
+use crate::extension::ExtensionValue;
 use crate::types::ObjType;
+use crate::Decimal;
 use serde::{Deserialize, Serialize, Serializer};
 use smol_str::SmolStr;
 use std::borrow::Cow;
@@ -61,6 +63,10 @@ impl<'a> Value<'a> {
         Value::Scalar(Cow::Owned(ScalarValue::Bytes(b)))
     }
 
+    pub fn decimal(d: Decimal) -> Value<'a> {
+        Value::Scalar(Cow::Owned(ScalarValue::Decimal(d)))
+    }
+
     pub fn is_object(&self) -> bool {
         matches!(&self, Value::Object(_))
     }
@@ -133,6 +139,14 @@ impl<'a> Value<'a> {
         }
     }
 
+    pub fn is_decimal(&self) -> bool {
+        if let Self::Scalar(s) = self {
+            s.is_decimal()
+        } else {
+            false
+        }
+    }
+
     pub fn is_null(&self) -> bool {
         if let Self::Scalar(s) = self {
             s.is_null()
@@ -317,6 +331,18 @@ impl From<u64> for Value<'_> {
     }
 }
 
+impl From<Decimal> for Value<'_> {
+    fn from(d: Decimal) -> Self {
+        Value::Scalar(Cow::Owned(ScalarValue::Decimal(d)))
+    }
+}
+
+impl From<ExtensionValue> for Value<'_> {
+    fn from(e: ExtensionValue) -> Self {
+        Value::Scalar(Cow::Owned(ScalarValue::Extension(e)))
+    }
+}
+
 impl From<bool> for Value<'_> {
     fn from(v: bool) -> Self {
         Value::Scalar(Cow::Owned(ScalarValue::Boolean(v)))
@@ -367,6 +393,8 @@ pub(crate) enum DataType {
     Int,
     #[serde(rename = "float64")]
     F64,
+    #[serde(rename = "decimal")]
+    Decimal,
     #[serde(rename = "undefined")]
     Undefined,
 }
@@ -441,7 +469,7 @@ impl From<&Counter> for f64 {
 }
 
 /// A value which is not a composite value
-#[derive(Serialize, PartialEq, Debug, Clone)]
+#[derive(Serialize, Debug, Clone)]
 #[serde(untagged)]
 pub enum ScalarValue {
     Bytes(Vec<u8>),
@@ -452,6 +480,10 @@ pub enum ScalarValue {
     Counter(Counter),
     Timestamp(i64),
     Boolean(bool),
+    /// An exact decimal number, see [`Decimal`]
+    Decimal(Decimal),
+    /// A value of a type registered by the application, see [`crate::extension`]
+    Extension(ExtensionValue),
     /// A value from a future version of automerge
     Unknown {
         type_code: u8,
@@ -460,6 +492,41 @@ pub enum ScalarValue {
     Null,
 }
 
+// An extension value is equal to the unknown value it is stored as, so that comparing values does
+// not depend on which types happen to be registered in the process
+impl PartialEq for ScalarValue {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Bytes(a), Self::Bytes(b)) => a == b,
+            (Self::Str(a), Self::Str(b)) => a == b,
+            (Self::Int(a), Self::Int(b)) => a == b,
+            (Self::Uint(a), Self::Uint(b)) => a == b,
+            (Self::F64(a), Self::F64(b)) => a == b,
+            (Self::Counter(a), Self::Counter(b)) => a == b,
+            (Self::Timestamp(a), Self::Timestamp(b)) => a == b,
+            (Self::Boolean(a), Self::Boolean(b)) => a == b,
+            (Self::Decimal(a), Self::Decimal(b)) => a == b,
+            (Self::Extension(a), Self::Extension(b)) => a == b,
+            (
+                Self::Unknown {
+                    type_code: a1,
+                    bytes: a2,
+                },
+                Self::Unknown {
+                    type_code: b1,
+                    bytes: b2,
+                },
+            ) => a1 == b1 && a2 == b2,
+            (Self::Extension(e), Self::Unknown { type_code, bytes })
+            | (Self::Unknown { type_code, bytes }, Self::Extension(e)) => {
+                e.type_code() == *type_code && e.bytes() == bytes.as_slice()
+            }
+            (Self::Null, Self::Null) => true,
+            _ => false,
+        }
+    }
+}
+
 impl PartialEq for Counter {
     fn eq(&self, other: &Self) -> bool {
         self.current == other.current
@@ -535,6 +602,16 @@ impl ScalarValue {
                     datatype,
                 },
             )?)),
+            (DataType::Decimal, ScalarValue::Decimal(d)) => Ok(ScalarValue::Decimal(d.clone())),
+            (DataType::Decimal, v) => match v.to_str().map(str::parse) {
+                Some(Ok(d)) => Ok(ScalarValue::Decimal(d)),
+                _ => Err(error::InvalidScalarValue {
+                    raw_value: self.clone(),
+                    expected: "a decimal string".to_string(),
+                    unexpected: v.to_string(),
+                    datatype,
+                }),
+            },
             (DataType::Undefined, _) => Ok(self.clone()),
         }
     }
@@ -550,6 +627,7 @@ impl ScalarValue {
             ScalarValue::Int(..) => Some(DataType::Int),
             ScalarValue::Uint(..) => Some(DataType::Uint),
             ScalarValue::F64(..) => Some(DataType::F64),
+            ScalarValue::Decimal(..) => Some(DataType::Decimal),
             _ => None,
         }
     }
@@ -590,6 +668,17 @@ impl ScalarValue {
         matches!(self, Self::Null)
     }
 
+    pub fn is_decimal(&self) -> bool {
+        matches!(self, Self::Decimal(_))
+    }
+
+    pub fn to_decimal(&self) -> Option<&Decimal> {
+        match self {
+            ScalarValue::Decimal(d) => Some(d),
+            _ => None,
+        }
+    }
+
     pub fn into_bytes(self) -> Result<Vec<u8>, Self> {
         match self {
             ScalarValue::Bytes(b) => Ok(b),
@@ -648,6 +737,7 @@ impl ScalarValue {
             ScalarValue::F64(n) => Some(*n),
             ScalarValue::Counter(n) => Some(n.into()),
             ScalarValue::Timestamp(n) => Some(*n as f64),
+            ScalarValue::Decimal(d) => Some(d.to_f64()),
             _ => None,
         }
     }
@@ -671,6 +761,69 @@ impl ScalarValue {
             _ => 0,
         }
     }
+
+    /// The value stored as `bytes` under a type code automerge does not know, which is a
+    /// [`ScalarValue::Extension`] if an extension is registered for the type code
+    pub(crate) fn from_unknown(type_code: u8, bytes: &[u8]) -> ScalarValue {
+        match ExtensionValue::from_bytes(type_code, bytes) {
+            Some(e) => ScalarValue::Extension(e),
+            None => ScalarValue::Unknown {
+                type_code,
+                bytes: bytes.to_vec(),
+            },
+        }
+    }
+
+    /// The key this value is stored under in a [`crate::ObjType::Set`], or `None` if it is a
+    /// counter, which cannot be a member of a set.
+    ///
+    /// The key includes the type of the value, so `1` and `1.0` are different members.
+    pub(crate) fn set_key(&self) -> Option<String> {
+        let key = match self {
+            Self::Bytes(b) => format!("bytes:{}", hex::encode(b)),
+            Self::Str(s) => format!("str:{}", s),
+            Self::Int(i) => format!("int:{}", i),
+            Self::Uint(u) => format!("uint:{}", u),
+            Self::F64(f) => format!("f64:{:016x}", f.to_bits()),
+            Self::Counter(_) => return None,
+            Self::Timestamp(t) => format!("timestamp:{}", t),
+            Self::Boolean(b) => format!("bool:{}", b),
+            Self::Decimal(d) => format!("decimal:{}", d),
+            Self::Unknown { type_code, bytes } => {
+                format!("unknown:{}:{}", type_code, hex::encode(bytes))
+            }
+            // the same key as the unknown value it is stored as, whether or not it is registered
+            Self::Extension(e) => format!("unknown:{}:{}", e.type_code(), hex::encode(e.bytes())),
+            Self::Null => "null".to_string(),
+        };
+        Some(key)
+    }
+
+    /// The value stored under `key` in a [`crate::ObjType::Set`], see [`Self::set_key`]
+    pub(crate) fn from_set_key(key: &str) -> Option<ScalarValue> {
+        let (tag, rest) = key.split_once(':').unwrap_or((key, ""));
+        match tag {
+            "bytes" => hex::decode(rest).ok().map(Self::Bytes),
+            "str" => Some(Self::Str(rest.into())),
+            "int" => rest.parse().ok().map(Self::Int),
+            "uint" => rest.parse().ok().map(Self::Uint),
+            "f64" => u64::from_str_radix(rest, 16)
+                .ok()
+                .map(|bits| Self::F64(f64::from_bits(bits))),
+            "timestamp" => rest.parse().ok().map(Self::Timestamp),
+            "bool" => rest.parse().ok().map(Self::Boolean),
+            "decimal" => rest.parse().ok().map(Self::Decimal),
+            "unknown" => {
+                let (type_code, bytes) = rest.split_once(':')?;
+                Some(Self::from_unknown(
+                    type_code.parse().ok()?,
+                    &hex::decode(bytes).ok()?,
+                ))
+            }
+            "null" => Some(Self::Null),
+            _ => None,
+        }
+    }
 }
 
 impl From<&str> for ScalarValue {
@@ -727,6 +880,18 @@ impl From<i32> for ScalarValue {
     }
 }
 
+impl From<Decimal> for ScalarValue {
+    fn from(d: Decimal) -> Self {
+        ScalarValue::Decimal(d)
+    }
+}
+
+impl From<ExtensionValue> for ScalarValue {
+    fn from(e: ExtensionValue) -> Self {
+        ScalarValue::Extension(e)
+    }
+}
+
 impl From<bool> for ScalarValue {
     fn from(b: bool) -> Self {
         ScalarValue::Boolean(b)
@@ -756,6 +921,8 @@ impl fmt::Display for ScalarValue {
             ScalarValue::Counter(c) => write!(f, "Counter: {}", c),
             ScalarValue::Timestamp(i) => write!(f, "Timestamp: {}", i),
             ScalarValue::Boolean(b) => write!(f, "{}", b),
+            ScalarValue::Decimal(d) => write!(f, "Decimal: {}", d),
+            ScalarValue::Extension(e) => write!(f, "{}", e),
             ScalarValue::Null => write!(f, "null"),
             ScalarValue::Unknown { type_code, .. } => write!(f, "unknown type {}", type_code),
         }
@@ -0,0 +1,175 @@
+use automerge::{
+    annotations::{AnnotationPatch, AnnotationPatchAction, Annotations},
+    hydrate_map,
+    marks::ExpandMark,
+    transaction::Transactable,
+    ActorId, AutoCommit, ObjType, ReadDoc, ScalarValue, ROOT,
+};
+
+fn setup() -> (AutoCommit, Annotations) {
+    let mut doc = AutoCommit::new().with_actor(ActorId::from(b"aaaa"));
+    let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+    let store = doc.put_object(ROOT, "comments", ObjType::Map).unwrap();
+    doc.splice_text(&text, 0, 0, "the quick brown fox").unwrap();
+    doc.commit();
+    (doc, Annotations::new(text, store))
+}
+
+#[test]
+fn identical_annotations_overlap_without_merging() {
+    let (mut doc, annotations) = setup();
+    let comment = hydrate_map! { "body" => "typo?" };
+    let first = annotations
+        .add(&mut doc, 4..9, ExpandMark::None, comment.clone())
+        .unwrap();
+    let second = annotations
+        .add(&mut doc, 4..9, ExpandMark::None, comment.clone())
+        .unwrap();
+    let third = annotations
+        .add(&mut doc, 6..15, ExpandMark::After, hydrate_map! {})
+        .unwrap();
+    assert_ne!(first, second);
+
+    let all = annotations.all(&doc, None).unwrap();
+    assert_eq!(all.len(), 3);
+    assert!(all[..2]
+        .iter()
+        .all(|a| a.range == (4..9) && a.metadata == comment));
+    assert_eq!(all[2].id, third);
+
+    let ids = |doc: &AutoCommit, range| {
+        let mut ids = annotations
+            .intersecting(doc, range, None)
+            .unwrap()
+            .into_iter()
+            .map(|a| a.id)
+            .collect::<Vec<_>>();
+        ids.sort();
+        ids
+    };
+    let mut both = vec![first.clone(), second.clone()];
+    both.sort();
+    assert_eq!(ids(&doc, 0..5), both);
+    assert_eq!(ids(&doc, 9..12), vec![third.clone()]);
+    assert_eq!(ids(&doc, 15..19), Vec::<String>::new());
+
+    assert!(annotations.remove(&mut doc, &first).unwrap());
+    assert!(!annotations.remove(&mut doc, &first).unwrap());
+    assert_eq!(ids(&doc, 0..5), vec![second]);
+}
+
+#[test]
+fn concurrent_annotations_merge() {
+    let (mut doc, annotations) = setup();
+    let id = annotations
+        .add(
+            &mut doc,
+            10..15,
+            ExpandMark::None,
+            hydrate_map! { "body" => "colour", "resolved" => ScalarValue::Boolean(false) },
+        )
+        .unwrap();
+    doc.commit();
+    let mut other = doc.fork().with_actor(ActorId::from(b"bbbb"));
+
+    other.splice_text(annotations.text(), 0, 4, "").unwrap();
+    annotations
+        .update_metadata(
+            &mut other,
+            &id,
+            hydrate_map! { "body" => "colour", "resolved" => ScalarValue::Boolean(true) },
+        )
+        .unwrap();
+    let other_id = annotations
+        .add(&mut other, 0..5, ExpandMark::Both, hydrate_map! {})
+        .unwrap();
+    annotations
+        .update_metadata(
+            &mut doc,
+            &id,
+            hydrate_map! { "body" => "color", "resolved" => ScalarValue::Boolean(false) },
+        )
+        .unwrap();
+    doc.merge(&mut other).unwrap();
+
+    let all = annotations.all(&doc, None).unwrap();
+    assert_eq!(all[0].id, other_id);
+    assert_eq!(all[0].range, 0..5);
+    assert_eq!(all[1].id, id);
+    assert_eq!(all[1].range, 6..11);
+    assert_eq!(
+        all[1].metadata,
+        hydrate_map! { "body" => "color", "resolved" => ScalarValue::Boolean(true) }
+    );
+}
+
+#[test]
+fn diff_reports_moves_and_collapses() {
+    let (mut doc, annotations) = setup();
+    let quick = annotations
+        .add(&mut doc, 4..9, ExpandMark::None, hydrate_map! {})
+        .unwrap();
+    let fox = annotations
+        .add(&mut doc, 16..19, ExpandMark::None, hydrate_map! {})
+        .unwrap();
+    let gone = annotations
+        .add(&mut doc, 0..3, ExpandMark::None, hydrate_map! {})
+        .unwrap();
+    doc.commit();
+    let before = doc.get_heads();
+
+    doc.splice_text(annotations.text(), 4, 6, "").unwrap();
+    annotations.remove(&mut doc, &gone).unwrap();
+    annotations
+        .update_metadata(
+            &mut doc,
+            &fox,
+            hydrate_map! { "seen" => ScalarValue::Boolean(true) },
+        )
+        .unwrap();
+    let added = annotations
+        .add(&mut doc, 0..3, ExpandMark::None, hydrate_map! {})
+        .unwrap();
+    doc.commit();
+    let after = doc.get_heads();
+
+    let mut patches = annotations.diff(&doc, &before, &after).unwrap();
+    patches.sort_by(|a, b| a.id.cmp(&b.id));
+    let mut expected = vec![
+        AnnotationPatch {
+            id: quick.clone(),
+            action: AnnotationPatchAction::Collapsed { from: 4..9, at: 4 },
+        },
+        AnnotationPatch {
+            id: fox.clone(),
+            action: AnnotationPatchAction::Moved {
+                from: 16..19,
+                to: 10..13,
+            },
+        },
+        AnnotationPatch {
+            id: fox.clone(),
+            action: AnnotationPatchAction::Updated {
+                metadata: hydrate_map! { "seen" => ScalarValue::Boolean(true) },
+            },
+        },
+        AnnotationPatch {
+            id: gone,
+            action: AnnotationPatchAction::Removed,
+        },
+        AnnotationPatch {
+            id: added,
+            action: AnnotationPatchAction::Added { range: 0..3 },
+        },
+    ];
+    expected.sort_by(|a, b| a.id.cmp(&b.id));
+    assert_eq!(patches, expected);
+
+    let quick = annotations.get(&doc, &quick, None).unwrap().unwrap();
+    assert!(quick.collapsed);
+    assert!(annotations
+        .get(&doc, &quick.id, Some(&before))
+        .unwrap()
+        .is_some_and(|a| !a.collapsed && a.range == (4..9)));
+    assert_eq!(doc.text(annotations.text()).unwrap(), "the brown fox");
+}
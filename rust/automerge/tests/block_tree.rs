@@ -0,0 +1,391 @@
+use std::sync::Arc;
+
+use automerge::{
+    block_tree::{self, Block, BlockPatch, BlockTree, TextRun},
+    hydrate_map,
+    markdown::{self, MarkdownConfig},
+    marks::{ExpandMark, Mark, MarkSet},
+    transaction::Transactable,
+    AutoCommit, ObjId, ObjType, ReadDoc, ScalarValue, TextEncoding, ROOT,
+};
+
+fn markdown_doc(source: &str) -> (AutoCommit, ObjId) {
+    let mut doc = AutoCommit::new();
+    let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+    markdown::update_markdown(&mut doc, &text, source, &MarkdownConfig::default()).unwrap();
+    doc.update_diff_cursor();
+    (doc, text)
+}
+
+fn bold() -> Option<Arc<MarkSet>> {
+    Some(Arc::new(MarkSet::from_iter([(
+        "strong".to_string(),
+        ScalarValue::Boolean(true),
+    )])))
+}
+
+/// Apply the patches produced since the last call to `tree` and check that they describe the
+/// difference between the old and new tree
+fn sync(doc: &mut AutoCommit, text: &ObjId, tree: &mut BlockTree) -> Vec<BlockPatch> {
+    let mut replayed = tree.root().clone();
+    let patches = tree.apply_patches(text, &doc.diff_incremental()).unwrap();
+    for patch in &patches {
+        replay(&mut replayed, patch.clone(), doc.text_encoding());
+    }
+    assert_eq!(&replayed, tree.root());
+    assert_eq!(tree, &block_tree::get_block_tree(&*doc, text).unwrap());
+    patches
+}
+
+type MarkedChar = (char, Option<Arc<MarkSet>>);
+
+fn replay(root: &mut Block, patch: BlockPatch, encoding: TextEncoding) {
+    let width = |c: char| match encoding {
+        TextEncoding::Utf16CodeUnit => c.len_utf16(),
+        _ => 1,
+    };
+    // convert an index in the text encoding into an index into the chars of the content
+    let char_index = |chars: &[MarkedChar], index: usize| {
+        let mut seen = 0;
+        chars
+            .iter()
+            .position(|(c, _)| {
+                let at = seen == index;
+                seen += width(*c);
+                at
+            })
+            .unwrap_or(chars.len())
+    };
+    let edit_content = |block: &mut Block, f: &dyn Fn(&mut Vec<MarkedChar>)| {
+        let mut chars = block
+            .content
+            .iter()
+            .flat_map(|run| run.text.chars().map(|c| (c, run.marks.clone())))
+            .collect::<Vec<_>>();
+        f(&mut chars);
+        block.content = Vec::new();
+        for (c, marks) in chars {
+            match block.content.last_mut() {
+                Some(last) if last.marks == marks => last.text.push(c),
+                _ => block.content.push(TextRun {
+                    text: c.to_string(),
+                    marks,
+                }),
+            }
+        }
+    };
+    let target = patch_path(&patch)
+        .iter()
+        .fold(root, |block, i| &mut block.children[*i]);
+    match patch {
+        BlockPatch::SpliceText {
+            index,
+            value,
+            marks,
+            ..
+        } => edit_content(target, &|chars| {
+            let index = char_index(chars, index);
+            for (i, c) in value.chars().enumerate() {
+                chars.insert(index + i, (c, marks.clone()));
+            }
+        }),
+        BlockPatch::DeleteText { index, length, .. } => edit_content(target, &|chars| {
+            let (from, to) = (char_index(chars, index), char_index(chars, index + length));
+            chars.drain(from..to);
+        }),
+        BlockPatch::Mark { marks, .. } => edit_content(target, &|chars| {
+            for mark in &marks {
+                let (from, to) = (char_index(chars, mark.start), char_index(chars, mark.end));
+                for (_, set) in &mut chars[from..to] {
+                    let mut next = set
+                        .as_deref()
+                        .into_iter()
+                        .flat_map(|s| s.iter())
+                        .filter(|(name, _)| *name != mark.name.as_str())
+                        .map(|(name, value)| (name.to_string(), value.clone()))
+                        .collect::<Vec<_>>();
+                    if !mark.value.is_null() {
+                        next.push((mark.name.to_string(), mark.value.clone()));
+                    }
+                    *set = (!next.is_empty()).then(|| Arc::new(MarkSet::from_iter(next)));
+                }
+            }
+        }),
+        BlockPatch::UpdateBlock {
+            block_type, attrs, ..
+        } => {
+            target.block_type = block_type;
+            target.attrs = attrs;
+        }
+        BlockPatch::SpliceBlocks {
+            index,
+            delete,
+            insert,
+            ..
+        } => {
+            target.children.splice(index..index + delete, insert);
+        }
+    }
+}
+
+fn patch_path(patch: &BlockPatch) -> &[usize] {
+    match patch {
+        BlockPatch::SpliceText { path, .. }
+        | BlockPatch::DeleteText { path, .. }
+        | BlockPatch::Mark { path, .. }
+        | BlockPatch::UpdateBlock { path, .. }
+        | BlockPatch::SpliceBlocks { path, .. } => path,
+    }
+}
+
+#[test]
+fn blocks_are_nested_by_their_parents() {
+    let (doc, text) = markdown_doc("# Title\n\n> - one\n>   - **two**\n> - three\n\nend");
+    let tree = block_tree::get_block_tree(&doc, &text).unwrap();
+
+    let blocks = tree.blocks();
+    assert_eq!(blocks.len(), 3);
+    assert_eq!(blocks[0].block_type, "heading");
+    assert_eq!(blocks[0].attrs, hydrate_map! {"level" => 1});
+    assert_eq!(blocks[0].text(), "Title");
+
+    // the blockquote only exists in the parents of the list items
+    let quote = &blocks[1];
+    assert_eq!(quote.block_type, "blockquote");
+    assert!(!quote.has_marker);
+    assert!(quote.content.is_empty());
+    assert_eq!(
+        quote
+            .children
+            .iter()
+            .map(|b| (b.block_type.as_str(), b.text()))
+            .collect::<Vec<_>>(),
+        vec![
+            ("unordered-list-item", "one".to_string()),
+            ("unordered-list-item", "three".to_string())
+        ]
+    );
+    let two = &quote.children[0].children[0];
+    assert!(two.has_marker);
+    assert_eq!(
+        two.content,
+        vec![TextRun {
+            text: "two".to_string(),
+            marks: bold()
+        }]
+    );
+    assert_eq!(tree.get(&[1, 0, 0]), Some(two));
+    assert_eq!(tree.get(&[1, 0, 1]), None);
+    assert_eq!(blocks[2].block_type, "paragraph");
+
+    // the content of "two" starts after the markers of the heading, "one" and "two"
+    let start = tree.content_start(&[1, 0, 0]).unwrap();
+    assert_eq!(start, "Title".len() + "one".len() + 3);
+    assert_eq!(tree.block_at(start + 1), Some((&[1, 0, 0][..], 1)));
+    // the position before the marker of "three" is the end of "two"
+    assert_eq!(tree.block_at(start + 3), Some((&[1, 0, 0][..], 3)));
+    assert_eq!(tree.content_start(&[1]), None);
+}
+
+#[test]
+fn text_before_the_first_block_belongs_to_the_root() {
+    let mut doc = AutoCommit::new();
+    let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+    doc.splice_text(&text, 0, 0, "intro body").unwrap();
+    let block = doc.split_block(&text, 5).unwrap();
+    doc.put(&block, "type", "paragraph").unwrap();
+
+    let tree = block_tree::get_block_tree(&doc, &text).unwrap();
+    assert_eq!(tree.root().text(), "intro");
+    assert_eq!(tree.blocks().len(), 1);
+    assert_eq!(tree.blocks()[0].text(), " body");
+    assert_eq!(tree.block_at(2), Some((&[][..], 2)));
+
+    let before = doc.get_heads();
+    doc.splice_text(&text, 0, 0, "an ").unwrap();
+    let old = block_tree::get_block_tree_at(&doc, &text, &before).unwrap();
+    assert_eq!(old, tree);
+}
+
+#[test]
+fn edits_inside_blocks_become_block_patches() {
+    let (mut doc, text) = markdown_doc("first paragraph\n\n- item\n  - nested");
+    let mut tree = block_tree::get_block_tree(&doc, &text).unwrap();
+
+    let nested = tree.content_start(&[1, 0]).unwrap();
+    doc.splice_text(&text, nested + 6, 0, " item").unwrap();
+    doc.splice_text(&text, 1, 5, "").unwrap();
+    assert_eq!(
+        sync(&mut doc, &text, &mut tree),
+        vec![
+            BlockPatch::SpliceText {
+                path: vec![1, 0],
+                index: 6,
+                value: " item".to_string(),
+                marks: None,
+            },
+            BlockPatch::DeleteText {
+                path: vec![0],
+                index: 0,
+                length: 5,
+            },
+        ]
+    );
+    assert_eq!(tree.get(&[1, 0]).unwrap().text(), "nested item");
+    assert_eq!(tree.blocks()[0].text(), " paragraph");
+
+    // a mark spanning several blocks is split between them
+    let item = tree.content_start(&[1]).unwrap();
+    doc.mark(
+        &text,
+        Mark::new("strong".to_string(), true, 5, item + 2),
+        ExpandMark::None,
+    )
+    .unwrap();
+    assert_eq!(
+        sync(&mut doc, &text, &mut tree),
+        vec![
+            BlockPatch::Mark {
+                path: vec![0],
+                marks: vec![Mark::new("strong".to_string(), true, 4, 10)],
+            },
+            BlockPatch::Mark {
+                path: vec![1],
+                marks: vec![Mark::new("strong".to_string(), true, 0, 2)],
+            },
+        ]
+    );
+    doc.splice_text(&text, item + 1, 0, "!").unwrap();
+    assert_eq!(
+        sync(&mut doc, &text, &mut tree),
+        vec![BlockPatch::SpliceText {
+            path: vec![1],
+            index: 1,
+            value: "!".to_string(),
+            marks: bold(),
+        }]
+    );
+}
+
+#[test]
+fn changes_to_block_markers_become_tree_patches() {
+    let (mut doc, text) = markdown_doc("one two\n\n- item");
+    let mut tree = block_tree::get_block_tree(&doc, &text).unwrap();
+
+    // splitting a paragraph
+    let block = doc.split_block(&text, 5).unwrap();
+    doc.update_object(
+        &block,
+        &hydrate_map! {
+            "type" => "paragraph",
+            "parents" => automerge::hydrate_list![],
+            "attrs" => hydrate_map!{},
+        }
+        .into(),
+    )
+    .unwrap();
+    let patches = sync(&mut doc, &text, &mut tree);
+    assert_eq!(tree.blocks().len(), 3);
+    assert_eq!(tree.blocks()[0].text(), "one ");
+    assert_eq!(tree.blocks()[1].text(), "two");
+    assert!(patches.iter().all(|p| patch_path(p).is_empty()));
+
+    // changing the type of a block keeps its content
+    let item = doc
+        .get(&text, tree.content_start(&[2]).unwrap() - 1)
+        .unwrap()
+        .unwrap()
+        .1;
+    doc.put(&item, "type", "ordered-list-item").unwrap();
+    assert_eq!(
+        sync(&mut doc, &text, &mut tree),
+        vec![BlockPatch::UpdateBlock {
+            path: vec![2],
+            block_type: "ordered-list-item".to_string(),
+            attrs: hydrate_map! {},
+        }]
+    );
+
+    // nesting a paragraph in a new blockquote
+    let parents = doc.get(&block, "parents").unwrap().unwrap().1;
+    doc.insert(&parents, 0, "blockquote").unwrap();
+    let patches = sync(&mut doc, &text, &mut tree);
+    assert_eq!(tree.blocks()[1].block_type, "blockquote");
+    assert_eq!(tree.blocks()[1].children[0].text(), "two");
+    assert_eq!(
+        patches,
+        vec![BlockPatch::SpliceBlocks {
+            path: vec![],
+            index: 1,
+            delete: 1,
+            insert: vec![tree.blocks()[1].clone()],
+        }]
+    );
+
+    // joining the blocks again, along with an edit to the remaining block
+    doc.join_block(&text, 5).unwrap();
+    doc.splice_text(&text, 1, 0, "> ").unwrap();
+    sync(&mut doc, &text, &mut tree);
+    assert_eq!(tree.blocks().len(), 2);
+    assert_eq!(tree.blocks()[0].text(), "> one two");
+}
+
+#[test]
+fn concurrent_changes_are_translated() {
+    let (mut doc, text) = markdown_doc("alpha\n\nbeta\n\ngamma");
+    let mut tree = block_tree::get_block_tree(&doc, &text).unwrap();
+    let mut other = doc.fork();
+
+    other.splice_text(&text, 3, 0, "-").unwrap();
+    let gamma = tree.content_start(&[2]).unwrap();
+    // `other` has one more character before gamma
+    other.split_block(&text, gamma + 3).unwrap();
+    doc.splice_text(&text, gamma - 4, 1, "E").unwrap();
+    doc.merge(&mut other).unwrap();
+
+    sync(&mut doc, &text, &mut tree);
+    assert_eq!(
+        tree.blocks().iter().map(Block::text).collect::<Vec<_>>(),
+        vec!["al-pha", "bEta", "ga", "mma"]
+    );
+    assert_eq!(tree.blocks()[3].block_type, "");
+}
+
+#[test]
+fn content_indexes_use_the_text_encoding() {
+    let mut doc = AutoCommit::new_with_encoding(TextEncoding::Utf16CodeUnit);
+    let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+    markdown::update_markdown(
+        &mut doc,
+        &text,
+        "🐻 bear\n\n🦊 fox",
+        &MarkdownConfig::default(),
+    )
+    .unwrap();
+    doc.update_diff_cursor();
+    let mut tree = block_tree::get_block_tree(&doc, &text).unwrap();
+
+    // each emoji is two UTF-16 code units
+    let fox = tree.content_start(&[1]).unwrap();
+    assert_eq!(fox, 1 + "🐻 bear".encode_utf16().count() + 1);
+    doc.splice_text(&text, fox + 3, 0, "red ").unwrap();
+    doc.splice_text(&text, 3, 1, "").unwrap();
+    assert_eq!(
+        sync(&mut doc, &text, &mut tree),
+        vec![
+            BlockPatch::SpliceText {
+                path: vec![1],
+                index: 3,
+                value: "red ".to_string(),
+                marks: None,
+            },
+            BlockPatch::DeleteText {
+                path: vec![0],
+                index: 2,
+                length: 1,
+            },
+        ]
+    );
+    assert_eq!(tree.blocks()[0].text(), "🐻bear");
+    assert_eq!(tree.blocks()[1].text(), "🦊 red fox");
+}
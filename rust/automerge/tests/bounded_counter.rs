@@ -0,0 +1,153 @@
+use automerge::{
+    transaction::Transactable, ActorId, AutoCommit, Automerge, AutomergeError, ObjId, ObjType,
+    ReadDoc, ROOT,
+};
+
+fn actor(n: u8) -> ActorId {
+    ActorId::from([n])
+}
+
+/// A document whose actor is `1`, with an empty bounded counter
+fn doc_with_counter() -> (AutoCommit, ObjId) {
+    let mut doc = AutoCommit::new().with_actor(actor(1));
+    let counter = doc.put_object(ROOT, "counter", ObjType::Map).unwrap();
+    (doc, counter)
+}
+
+#[test]
+fn increment_and_decrement() {
+    let (mut doc, counter) = doc_with_counter();
+    doc.bounded_counter_increment(&counter, 5).unwrap();
+    doc.bounded_counter_increment(&counter, 5).unwrap();
+    doc.bounded_counter_decrement(&counter, 3).unwrap();
+
+    let state = doc.bounded_counter(&counter).unwrap();
+    assert_eq!(state.value(), 7);
+    assert_eq!(state.rights(&actor(1)), 7);
+    assert_eq!(state.rights(&actor(2)), 0);
+    assert_eq!(state.holders().collect::<Vec<_>>(), vec![(&actor(1), 7)]);
+
+    assert_eq!(
+        doc.bounded_counter_decrement(&counter, 8),
+        Err(AutomergeError::InsufficientRights {
+            requested: 8,
+            available: 7
+        })
+    );
+    doc.bounded_counter_decrement(&counter, 7).unwrap();
+    assert_eq!(doc.bounded_counter(&counter).unwrap().value(), 0);
+    assert!(doc.bounded_counter_decrement(&counter, 1).is_err());
+}
+
+#[test]
+fn actors_can_only_spend_their_own_rights() {
+    let (mut doc, counter) = doc_with_counter();
+    doc.bounded_counter_increment(&counter, 10).unwrap();
+    let mut other = doc.fork().with_actor(actor(2));
+
+    assert_eq!(
+        other.bounded_counter_decrement(&counter, 1),
+        Err(AutomergeError::InsufficientRights {
+            requested: 1,
+            available: 0
+        })
+    );
+    assert!(other
+        .bounded_counter_transfer(&counter, &actor(1), 1)
+        .is_err());
+    other.bounded_counter_increment(&counter, 2).unwrap();
+    other.bounded_counter_decrement(&counter, 2).unwrap();
+}
+
+#[test]
+fn concurrent_decrements_never_go_below_zero() {
+    let (mut doc, counter) = doc_with_counter();
+    doc.bounded_counter_increment(&counter, 10).unwrap();
+    doc.bounded_counter_transfer(&counter, &actor(2), 4)
+        .unwrap();
+    let mut other = doc.fork().with_actor(actor(2));
+
+    // each actor spends everything it holds
+    assert!(doc.bounded_counter_decrement(&counter, 7).is_err());
+    doc.bounded_counter_decrement(&counter, 6).unwrap();
+    assert!(other.bounded_counter_decrement(&counter, 5).is_err());
+    other.bounded_counter_decrement(&counter, 4).unwrap();
+
+    doc.merge(&mut other).unwrap();
+    other.merge(&mut doc).unwrap();
+    for doc in [&doc, &other] {
+        let state = doc.bounded_counter(&counter).unwrap();
+        assert_eq!(state.value(), 0);
+        assert_eq!(state.holders().count(), 0);
+    }
+    assert_eq!(doc.conflicts(&counter).unwrap(), vec![]);
+}
+
+#[test]
+fn rights_transferred_concurrently_arrive_on_merge() {
+    let (mut doc, counter) = doc_with_counter();
+    doc.bounded_counter_increment(&counter, 3).unwrap();
+    let mut other = doc.fork().with_actor(actor(2));
+
+    doc.bounded_counter_transfer(&counter, &actor(2), 2)
+        .unwrap();
+    other.bounded_counter_increment(&counter, 1).unwrap();
+    assert_eq!(
+        other.bounded_counter(&counter).unwrap().rights(&actor(2)),
+        1
+    );
+
+    other.merge(&mut doc).unwrap();
+    let state = other.bounded_counter(&counter).unwrap();
+    assert_eq!(state.value(), 4);
+    assert_eq!(state.rights(&actor(1)), 1);
+    assert_eq!(state.rights(&actor(2)), 3);
+    other.bounded_counter_decrement(&counter, 3).unwrap();
+
+    // transferring to yourself or nothing at all does nothing
+    let heads = doc.get_heads();
+    doc.bounded_counter_transfer(&counter, &actor(1), 1)
+        .unwrap();
+    doc.bounded_counter_decrement(&counter, 0).unwrap();
+    doc.commit();
+    assert_eq!(doc.get_heads(), heads);
+}
+
+#[test]
+fn bounded_counters_at_old_heads_and_after_load() {
+    let (mut doc, counter) = doc_with_counter();
+    doc.bounded_counter_increment(&counter, 5).unwrap();
+    let heads = doc.get_heads();
+    doc.bounded_counter_decrement(&counter, 2).unwrap();
+
+    assert_eq!(doc.bounded_counter_at(&counter, &heads).unwrap().value(), 5);
+
+    let mut loaded = Automerge::load(&doc.save()).unwrap();
+    loaded.set_actor(actor(1));
+    assert_eq!(loaded.bounded_counter(&counter).unwrap().value(), 3);
+    let mut tx = loaded.transaction();
+    assert!(tx.bounded_counter_decrement(&counter, 4).is_err());
+    tx.bounded_counter_decrement(&counter, 3).unwrap();
+    tx.commit();
+    assert_eq!(loaded.bounded_counter(&counter).unwrap().value(), 0);
+}
+
+#[test]
+fn bounded_counters_must_be_maps() {
+    let mut doc = AutoCommit::new();
+    let list = doc.put_object(ROOT, "list", ObjType::List).unwrap();
+    assert_eq!(
+        doc.bounded_counter_increment(&list, 1),
+        Err(AutomergeError::InvalidOp(ObjType::List))
+    );
+    assert_eq!(
+        doc.bounded_counter(&list),
+        Err(AutomergeError::InvalidOp(ObjType::List))
+    );
+
+    // keys which are not part of the counter are ignored
+    let counter = doc.put_object(ROOT, "counter", ObjType::Map).unwrap();
+    doc.put(&counter, "note", "hello").unwrap();
+    doc.put(&counter, "inc:zz", 1).unwrap();
+    assert_eq!(doc.bounded_counter(&counter).unwrap().value(), 0);
+}
@@ -0,0 +1,40 @@
+//! Helpers shared by the integration tests of moves, sets and ordered maps
+#![allow(dead_code)]
+
+use automerge::{
+    transaction::Transactable, ActorId, AutoCommit, ChangeHash, ObjId, ObjType, ReadDoc,
+    TextEncoding, ROOT,
+};
+
+/// A document with the actor `[1]` and an empty object of type `obj_type` at `prop` in the root
+pub fn doc_with(prop: &str, obj_type: ObjType) -> (AutoCommit, ObjId) {
+    let mut doc = AutoCommit::new().with_actor(ActorId::from([1]));
+    let obj = doc.put_object(ROOT, prop, obj_type).unwrap();
+    (doc, obj)
+}
+
+pub fn fork(doc: &mut AutoCommit, actor: u8) -> AutoCommit {
+    doc.fork().with_actor(ActorId::from([actor]))
+}
+
+/// Merge `other` into `doc`, checking the patches take it from one state to the next
+pub fn merge_with_patches(doc: &mut AutoCommit, other: &mut AutoCommit) {
+    doc.update_diff_cursor();
+    let mut hydrated = doc.hydrate(ROOT, None).unwrap();
+    doc.merge(other).unwrap();
+    hydrated
+        .apply_patches(TextEncoding::UnicodeCodePoint, doc.diff_incremental())
+        .unwrap();
+    assert_eq!(hydrated, doc.hydrate(ROOT, None).unwrap());
+}
+
+/// Check that the patches from `heads` to the current state of `doc` take it from one to the other
+pub fn assert_diff_matches(doc: &mut AutoCommit, heads: &[ChangeHash]) {
+    let mut hydrated = doc.hydrate(ROOT, Some(heads)).unwrap();
+    let now = doc.get_heads();
+    let patches = doc.diff(heads, &now);
+    hydrated
+        .apply_patches(TextEncoding::UnicodeCodePoint, patches)
+        .unwrap();
+    assert_eq!(hydrated, doc.hydrate(ROOT, None).unwrap());
+}
@@ -0,0 +1,312 @@
+use automerge::transaction::Transactable;
+use automerge::{ActorId, AutoCommit, ObjId, ObjType, ReadDoc, ScalarValue, Value, ROOT};
+
+/// Text with a lot of deleted content made of pseudo random characters
+fn edited_text(doc: &mut AutoCommit, text: &ObjId) {
+    let mut seed: u64 = 42;
+    let mut rand = || {
+        seed = seed
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (seed >> 33) as usize
+    };
+    for _ in 0..150 {
+        let len = doc.length(text);
+        let pos = if len == 0 { 0 } else { rand() % len };
+        let insert: String = (0..20)
+            .map(|_| (b'a' + (rand() % 26) as u8) as char)
+            .collect();
+        doc.splice_text(text, pos, 0, &insert).unwrap();
+        let len = doc.length(text);
+        let pos = rand() % len;
+        let del = (len - pos).min(16);
+        doc.splice_text(text, pos, del as isize, "").unwrap();
+        doc.commit();
+    }
+}
+
+#[test]
+fn compact_text_keeps_state_and_cursors() {
+    let mut doc = AutoCommit::new();
+    let text = doc.put_object(&ROOT, "text", ObjType::Text).unwrap();
+    edited_text(&mut doc, &text);
+    let deleted = doc.get_cursor(&text, 10, None).unwrap();
+    doc.splice_text(&text, 8, 4, "").unwrap();
+    doc.commit();
+    let kept = doc.get_cursor(&text, 20, None).unwrap();
+    let stable = doc.get_heads();
+
+    let mut compacted = doc.compact(&stable).unwrap();
+    assert_eq!(compacted.text(&text).unwrap(), doc.text(&text).unwrap());
+    assert_eq!(
+        compacted.hydrate(&ROOT, None).unwrap(),
+        doc.hydrate(&ROOT, None).unwrap()
+    );
+    assert_eq!(compacted.shallow_base(), Some(stable.as_slice()));
+    for cursor in [&deleted, &kept] {
+        assert_eq!(
+            compacted.get_cursor_position(&text, cursor, None).unwrap(),
+            doc.get_cursor_position(&text, cursor, None).unwrap()
+        );
+    }
+
+    let saved = compacted.save();
+    assert!(saved.len() < doc.shallow(&stable).unwrap().save().len());
+    assert!(saved.len() < doc.save().len());
+    let mut loaded = AutoCommit::load(&saved).unwrap();
+    assert_eq!(loaded.text(&text).unwrap(), doc.text(&text).unwrap());
+    assert_eq!(
+        loaded.get_cursor_position(&text, &deleted, None).unwrap(),
+        doc.get_cursor_position(&text, &deleted, None).unwrap()
+    );
+    assert_eq!(loaded.save(), saved);
+}
+
+#[test]
+fn compact_drops_overwritten_map_values_and_deleted_objects() {
+    let mut doc = AutoCommit::new();
+    for i in 0..100 {
+        doc.put(&ROOT, "value", format!("value number {}", i))
+            .unwrap();
+        doc.commit();
+    }
+    let nested = doc.put_object(&ROOT, "nested", ObjType::Map).unwrap();
+    let list = doc.put_object(&nested, "list", ObjType::List).unwrap();
+    for i in 0..100 {
+        doc.insert(&list, i, format!("list item {}", i)).unwrap();
+    }
+    doc.put(&ROOT, "counter", ScalarValue::counter(1)).unwrap();
+    doc.increment(&ROOT, "counter", 5).unwrap();
+    doc.commit();
+    doc.delete(&ROOT, "nested").unwrap();
+    doc.put(&ROOT, "counter", ScalarValue::counter(10)).unwrap();
+    doc.increment(&ROOT, "counter", 2).unwrap();
+    doc.commit();
+    let stable = doc.get_heads();
+
+    let mut compacted = doc.compact(&stable).unwrap();
+    assert_eq!(
+        compacted.hydrate(&ROOT, None).unwrap(),
+        doc.hydrate(&ROOT, None).unwrap()
+    );
+    assert_eq!(
+        compacted.get(&ROOT, "counter").unwrap().unwrap().0,
+        Value::counter(12)
+    );
+    assert_eq!(compacted.get_all(&ROOT, "value").unwrap().len(), 1);
+    assert_eq!(compacted.length(&list), 0);
+
+    let saved = compacted.save();
+    assert!(saved.len() * 2 < doc.save().len());
+    let loaded = AutoCommit::load(&saved).unwrap();
+    assert_eq!(
+        loaded.hydrate(&ROOT, None).unwrap(),
+        doc.hydrate(&ROOT, None).unwrap()
+    );
+}
+
+#[test]
+fn compacted_doc_merges_with_full_peer() {
+    let mut doc = AutoCommit::new().with_actor(ActorId::from(b"aaaa"));
+    let text = doc.put_object(&ROOT, "text", ObjType::Text).unwrap();
+    doc.splice_text(&text, 0, 0, "hello world").unwrap();
+    doc.put(&ROOT, "key", "one").unwrap();
+    doc.commit();
+    doc.splice_text(&text, 0, 6, "").unwrap();
+    doc.put(&ROOT, "key", "two").unwrap();
+    doc.commit();
+    let stable = doc.get_heads();
+
+    let mut compacted = doc
+        .compact(&stable)
+        .unwrap()
+        .with_actor(ActorId::from(b"bbbb"));
+    compacted.splice_text(&text, 0, 0, "big ").unwrap();
+    compacted.put(&ROOT, "key", "three").unwrap();
+    compacted.commit();
+    doc.splice_text(&text, 5, 0, "!").unwrap();
+    doc.commit();
+
+    doc.merge(&mut compacted).unwrap();
+    compacted.merge(&mut doc).unwrap();
+    assert_eq!(compacted.text(&text).unwrap(), "big world!");
+    assert_eq!(
+        compacted.hydrate(&ROOT, None).unwrap(),
+        doc.hydrate(&ROOT, None).unwrap()
+    );
+
+    // the changes after the frontier are unchanged
+    let mut loaded = AutoCommit::load(&compacted.save()).unwrap();
+    let hashes = |d: &mut AutoCommit| {
+        let mut h = d
+            .get_changes(&stable)
+            .iter()
+            .map(|c| c.hash())
+            .collect::<Vec<_>>();
+        h.sort();
+        h
+    };
+    assert_eq!(hashes(&mut loaded), hashes(&mut doc));
+}
+
+#[test]
+fn changes_into_deleted_objects_merge_after_compaction() {
+    let mut doc = AutoCommit::new().with_actor(ActorId::from(b"aaaa"));
+    let map = doc.put_object(&ROOT, "map", ObjType::Map).unwrap();
+    doc.put(&map, "x", 1).unwrap();
+    let list = doc.put_object(&map, "list", ObjType::List).unwrap();
+    doc.insert(&list, 0, "item").unwrap();
+    doc.commit();
+    doc.delete(&ROOT, "map").unwrap();
+    doc.commit();
+    let stable = doc.get_heads();
+
+    // a peer which has every stable head writes into the deleted objects
+    let mut other = doc.fork().with_actor(ActorId::from(b"bbbb"));
+    other.put(&map, "y", 2).unwrap();
+    other.insert(&list, 0, "new item").unwrap();
+    other.commit();
+
+    let mut compacted = doc.compact(&stable).unwrap();
+    assert_eq!(compacted.length(&list), 0);
+    compacted.merge(&mut other).unwrap();
+    doc.merge(&mut other).unwrap();
+    assert_eq!(
+        compacted.hydrate(&ROOT, None).unwrap(),
+        doc.hydrate(&ROOT, None).unwrap()
+    );
+    assert_eq!(compacted.get(&map, "y").unwrap().unwrap().0, Value::int(2));
+    assert_eq!(compacted.length(&list), 1);
+
+    let mut loaded = AutoCommit::load(&compacted.save()).unwrap();
+    assert_eq!(loaded.get_heads(), doc.get_heads());
+    assert_eq!(loaded.length(&list), 1);
+}
+
+#[test]
+fn compact_keeps_objects_moved_out_of_deleted_objects() {
+    let mut doc = AutoCommit::new().with_actor(ActorId::from(b"aaaa"));
+    let outer = doc.put_object(&ROOT, "outer", ObjType::Map).unwrap();
+    let inner = doc.put_object(&outer, "inner", ObjType::Map).unwrap();
+    doc.put(&inner, "x", 1).unwrap();
+    let list = doc.put_object(&outer, "list", ObjType::List).unwrap();
+    let item = doc.insert_object(&list, 0, ObjType::Map).unwrap();
+    doc.put(&item, "y", 2).unwrap();
+    doc.commit();
+    doc.move_object(&inner, ROOT, "moved").unwrap();
+    doc.move_object(&item, ROOT, "item").unwrap();
+    doc.commit();
+    // a moved object which is deleted where it was moved to is gone
+    doc.move_object(&item, &inner, "item").unwrap();
+    doc.commit();
+    doc.delete(&ROOT, "outer").unwrap();
+    doc.delete(&inner, "item").unwrap();
+    doc.commit();
+    let stable = doc.get_heads();
+
+    let mut compacted = doc.compact(&stable).unwrap();
+    assert_eq!(
+        compacted.hydrate(&ROOT, None).unwrap(),
+        doc.hydrate(&ROOT, None).unwrap()
+    );
+    assert_eq!(
+        compacted.get(&inner, "x").unwrap().unwrap().0,
+        Value::int(1)
+    );
+    assert_eq!(compacted.length(&item), 0);
+
+    let loaded = AutoCommit::load(&compacted.save()).unwrap();
+    assert_eq!(
+        loaded.hydrate(&ROOT, None).unwrap(),
+        doc.hydrate(&ROOT, None).unwrap()
+    );
+    assert_eq!(loaded.get(&inner, "x").unwrap().unwrap().0, Value::int(1));
+}
+
+#[test]
+fn compact_keeps_content_referenced_by_concurrent_changes() {
+    let mut doc = AutoCommit::new().with_actor(ActorId::from(b"aaaa"));
+    let map = doc.put_object(&ROOT, "map", ObjType::Map).unwrap();
+    doc.put(&map, "a", 1).unwrap();
+    doc.put(&ROOT, "key", "one").unwrap();
+    doc.commit();
+
+    let mut other = doc.fork().with_actor(ActorId::from(b"bbbb"));
+    other.put(&map, "b", 2).unwrap();
+    other.put(&ROOT, "key", "other").unwrap();
+    other.commit();
+
+    doc.delete(&ROOT, "map").unwrap();
+    doc.put(&ROOT, "key", "two").unwrap();
+    doc.commit();
+    let stable = doc.get_heads();
+    doc.merge(&mut other).unwrap();
+
+    let mut compacted = doc.compact(&stable).unwrap();
+    assert_eq!(
+        compacted.hydrate(&ROOT, None).unwrap(),
+        doc.hydrate(&ROOT, None).unwrap()
+    );
+    assert_eq!(compacted.get_all(&ROOT, "key").unwrap().len(), 2);
+
+    let mut loaded = AutoCommit::load(&compacted.save()).unwrap();
+    assert_eq!(loaded.get_heads(), doc.get_heads());
+    assert_eq!(
+        loaded.get_changes(&stable)[0].hash(),
+        other.get_last_local_change().unwrap().hash()
+    );
+}
+
+#[test]
+fn compact_lists_and_marks() {
+    use automerge::marks::{ExpandMark, Mark};
+
+    let mut doc = AutoCommit::new().with_actor(ActorId::from(b"aaaa"));
+    let list = doc.put_object(&ROOT, "list", ObjType::List).unwrap();
+    let text = doc.put_object(&ROOT, "text", ObjType::Text).unwrap();
+    for i in 0..10 {
+        doc.insert(&list, i, i as i64).unwrap();
+    }
+    doc.insert_object(&list, 10, ObjType::Map).unwrap();
+    doc.insert(&list, 11, ScalarValue::counter(0)).unwrap();
+    doc.splice_text(&text, 0, 0, "hello big world").unwrap();
+    doc.mark(
+        &text,
+        Mark::new("bold".to_string(), true, 0, 9),
+        ExpandMark::After,
+    )
+    .unwrap();
+    doc.commit();
+    for i in 0..5 {
+        doc.put(&list, i, "overwritten").unwrap();
+    }
+    doc.delete(&list, 10).unwrap();
+    doc.increment(&list, 10, 3).unwrap();
+    doc.delete(&list, 2).unwrap();
+    doc.splice_text(&text, 5, 4, "").unwrap();
+    doc.commit();
+    let stable = doc.get_heads();
+
+    let mut compacted = doc
+        .compact(&stable)
+        .unwrap()
+        .with_actor(ActorId::from(b"bbbb"));
+    assert_eq!(
+        compacted.hydrate(&ROOT, None).unwrap(),
+        doc.hydrate(&ROOT, None).unwrap()
+    );
+    assert_eq!(compacted.marks(&text).unwrap(), doc.marks(&text).unwrap());
+
+    compacted.insert(&list, 2, "new").unwrap();
+    compacted.increment(&list, 10, 1).unwrap();
+    compacted.splice_text(&text, 5, 0, " small").unwrap();
+    compacted.commit();
+    doc.merge(&mut compacted).unwrap();
+
+    let loaded = AutoCommit::load(&compacted.save()).unwrap();
+    assert_eq!(
+        loaded.hydrate(&ROOT, None).unwrap(),
+        doc.hydrate(&ROOT, None).unwrap()
+    );
+    assert_eq!(loaded.marks(&text).unwrap(), doc.marks(&text).unwrap());
+}
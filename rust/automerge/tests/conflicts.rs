@@ -0,0 +1,199 @@
+use automerge::{
+    transaction::Transactable, ActorId, AutoCommit, AutomergeError, ObjType, Patch, PatchAction,
+    Prop, ReadDoc, ScalarValue, TextEncoding, Value, ROOT,
+};
+
+/// Three documents which have concurrently put "a", "b" and "c" at `key` of the root
+fn three_way_conflict(key: &str) -> AutoCommit {
+    let mut doc = AutoCommit::new().with_actor(ActorId::from([1]));
+    let mut doc2 = doc.fork().with_actor(ActorId::from([2]));
+    let mut doc3 = doc.fork().with_actor(ActorId::from([3]));
+    doc.put(ROOT, key, "a").unwrap();
+    doc2.put(ROOT, key, "b").unwrap();
+    doc3.put(ROOT, key, "c").unwrap();
+    doc.merge(&mut doc2).unwrap();
+    doc.merge(&mut doc3).unwrap();
+    doc
+}
+
+fn values(doc: &AutoCommit, obj: &automerge::ObjId, prop: impl Into<Prop>) -> Vec<ScalarValue> {
+    doc.get_all(obj, prop)
+        .unwrap()
+        .into_iter()
+        .map(|(value, _)| value.into_scalar().unwrap())
+        .collect()
+}
+
+#[test]
+fn resolve_some_conflicting_values() {
+    let mut doc = three_way_conflict("key");
+    let all = doc.get_all(ROOT, "key").unwrap();
+    let ids = all.iter().map(|(_, id)| id.clone()).collect::<Vec<_>>();
+
+    doc.resolve_conflict(ROOT, "key", "a+b", &ids[..2]).unwrap();
+
+    assert_eq!(
+        values(&doc, &ROOT, "key"),
+        vec![ScalarValue::from("c"), ScalarValue::from("a+b")]
+    );
+    assert_eq!(doc.get(ROOT, "key").unwrap().unwrap().0, Value::from("a+b"));
+    assert_eq!(doc.conflicts(ROOT).unwrap(), vec![Prop::from("key")]);
+
+    let winner = doc.get(ROOT, "key").unwrap().unwrap().1;
+    doc.resolve_conflict(ROOT, "key", "a+b+c", &[winner, ids[2].clone()])
+        .unwrap();
+    assert_eq!(values(&doc, &ROOT, "key"), vec![ScalarValue::from("a+b+c")]);
+    assert!(doc.conflicts(ROOT).unwrap().is_empty());
+}
+
+#[test]
+fn resolved_conflicts_merge_with_concurrent_values() {
+    let mut doc = three_way_conflict("key");
+    let mut other = doc.fork().with_actor(ActorId::from([4]));
+    let ids = doc
+        .get_all(ROOT, "key")
+        .unwrap()
+        .into_iter()
+        .map(|(_, id)| id)
+        .collect::<Vec<_>>();
+
+    doc.resolve_conflict(ROOT, "key", "merged", &ids).unwrap();
+    other.put(ROOT, "key", "d").unwrap();
+    doc.merge(&mut other).unwrap();
+
+    let mut values = values(&doc, &ROOT, "key");
+    values.sort_by_key(|v| v.to_string());
+    assert_eq!(
+        values,
+        vec![ScalarValue::from("d"), ScalarValue::from("merged")]
+    );
+}
+
+#[test]
+fn resolve_conflicts_in_a_list() {
+    let mut doc = AutoCommit::new().with_actor(ActorId::from([1]));
+    let list = doc.put_object(ROOT, "list", ObjType::List).unwrap();
+    doc.insert(&list, 0, 1).unwrap();
+    doc.insert(&list, 1, 1).unwrap();
+    let mut other = doc.fork().with_actor(ActorId::from([2]));
+    doc.put(&list, 1, 2).unwrap();
+    other.put(&list, 1, 3).unwrap();
+    doc.merge(&mut other).unwrap();
+
+    assert_eq!(doc.conflicts(&list).unwrap(), vec![Prop::Seq(1)]);
+    assert_eq!(doc.all_conflicts(), vec![(list.clone(), Prop::Seq(1))]);
+
+    let ids = doc
+        .get_all(&list, 1)
+        .unwrap()
+        .into_iter()
+        .map(|(_, id)| id)
+        .collect::<Vec<_>>();
+    doc.resolve_conflict(&list, 1, 5, &ids).unwrap();
+    assert_eq!(values(&doc, &list, 1), vec![ScalarValue::from(5)]);
+    assert_eq!(doc.length(&list), 2);
+    assert!(doc.all_conflicts().is_empty());
+}
+
+#[test]
+fn resolve_conflict_with_values_which_are_not_at_the_prop() {
+    let mut doc = three_way_conflict("key");
+    doc.put(ROOT, "other", "x").unwrap();
+    let other = doc.get(ROOT, "other").unwrap().unwrap().1;
+
+    assert_eq!(
+        doc.resolve_conflict(ROOT, "key", "a", std::slice::from_ref(&other)),
+        Err(AutomergeError::NotACurrentValue(other))
+    );
+
+    let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+    assert_eq!(
+        doc.resolve_conflict(&text, 0, "a", &[]),
+        Err(AutomergeError::InvalidOp(ObjType::Text))
+    );
+
+    // with nothing to supersede the value is added to the conflict
+    doc.resolve_conflict(ROOT, "key", "d", &[]).unwrap();
+    assert_eq!(doc.get_all(ROOT, "key").unwrap().len(), 4);
+}
+
+#[test]
+fn resolve_conflict_patches() {
+    let mut doc = three_way_conflict("key");
+    let ids = doc
+        .get_all(ROOT, "key")
+        .unwrap()
+        .into_iter()
+        .map(|(_, id)| id)
+        .collect::<Vec<_>>();
+    let mut hydrated = doc.hydrate(ROOT, None).unwrap();
+    doc.update_diff_cursor();
+
+    doc.resolve_conflict(ROOT, "key", "partial", &ids[..1])
+        .unwrap();
+    let patches = doc.diff_incremental();
+    assert_eq!(
+        patches
+            .iter()
+            .map(|Patch { action, .. }| action.clone())
+            .collect::<Vec<_>>(),
+        vec![PatchAction::PutMap {
+            key: "key".into(),
+            value: ("partial".into(), doc.get(ROOT, "key").unwrap().unwrap().1),
+            conflict: true,
+        }]
+    );
+    hydrated
+        .apply_patches(TextEncoding::UnicodeCodePoint, patches)
+        .unwrap();
+    assert_eq!(hydrated, doc.hydrate(ROOT, None).unwrap());
+
+    let ids = doc
+        .get_all(ROOT, "key")
+        .unwrap()
+        .into_iter()
+        .map(|(_, id)| id)
+        .collect::<Vec<_>>();
+    doc.resolve_conflict(ROOT, "key", "all", &ids).unwrap();
+    let patches = doc.diff_incremental();
+    assert!(matches!(
+        patches.as_slice(),
+        [Patch {
+            action: PatchAction::PutMap {
+                conflict: false,
+                ..
+            },
+            ..
+        }]
+    ));
+    hydrated
+        .apply_patches(TextEncoding::UnicodeCodePoint, patches)
+        .unwrap();
+    assert_eq!(hydrated, doc.hydrate(ROOT, None).unwrap());
+}
+
+#[test]
+fn list_conflicts_in_the_document() {
+    let mut doc = AutoCommit::new().with_actor(ActorId::from([1]));
+    let map = doc.put_object(ROOT, "map", ObjType::Map).unwrap();
+    let set = doc.put_object(ROOT, "set", ObjType::Set).unwrap();
+    let mut other = doc.fork().with_actor(ActorId::from([2]));
+    doc.put(ROOT, "a", 1).unwrap();
+    doc.put(&map, "b", 1).unwrap();
+    doc.set_add(&set, "c").unwrap();
+    let heads = doc.get_heads();
+    other.put(ROOT, "a", 2).unwrap();
+    other.put(&map, "b", 2).unwrap();
+    other.put(&map, "c", 2).unwrap();
+    other.set_add(&set, "c").unwrap();
+    doc.merge(&mut other).unwrap();
+
+    assert_eq!(
+        doc.all_conflicts(),
+        vec![(ROOT, Prop::from("a")), (map.clone(), Prop::from("b"))]
+    );
+    assert_eq!(doc.conflicts(&map).unwrap(), vec![Prop::from("b")]);
+    assert!(doc.conflicts(&set).unwrap().is_empty());
+    assert!(doc.all_conflicts_at(&heads).is_empty());
+    assert!(doc.conflicts_at(&map, &heads).unwrap().is_empty());
+}
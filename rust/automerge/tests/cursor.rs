@@ -0,0 +1,148 @@
+use std::ops::Range;
+
+use automerge::{
+    marks::ExpandMark, transaction::Transactable, AutoCommit, Automerge, AutomergeError, Cursor,
+    CursorRangePosition, MoveCursor, ObjType, ReadDoc, ROOT,
+};
+
+#[test]
+fn batch_cursor_positions_match_single_cursors() -> Result<(), AutomergeError> {
+    let mut doc = Automerge::new();
+    let mut tx = doc.transaction();
+    let text = tx.put_object(ROOT, "text", ObjType::Text)?;
+    let list = tx.put_object(ROOT, "list", ObjType::List)?;
+    tx.splice_text(&text, 0, 0, "the quick brown fox")?;
+    for i in 0..10 {
+        tx.insert(&list, i, i as i64)?;
+    }
+    tx.commit();
+    let heads = doc.get_heads();
+
+    let mut text_cursors = vec![Cursor::End, Cursor::Start];
+    for i in 0..19 {
+        text_cursors.push(doc.get_cursor_moving(&text, i, None, MoveCursor::Before)?);
+        text_cursors.push(doc.get_cursor_moving(&text, 18 - i, None, MoveCursor::After)?);
+    }
+    let list_cursors = (0..10)
+        .map(|i| doc.get_cursor_moving(&list, i, None, MoveCursor::Before))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut tx = doc.transaction();
+    tx.splice_text(&text, 4, 6, "")?;
+    tx.splice_text(&text, 0, 1, "T")?;
+    tx.delete(&list, 0)?;
+    tx.delete(&list, 3)?;
+    tx.insert(&list, 5, "new")?;
+    tx.commit();
+
+    for at in [None, Some(heads.as_slice())] {
+        let expected = text_cursors
+            .iter()
+            .map(|cursor| doc.get_cursor_position(&text, cursor, at))
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(
+            doc.get_cursor_positions(&text, &text_cursors, at)?,
+            expected
+        );
+        let expected = list_cursors
+            .iter()
+            .map(|cursor| doc.get_cursor_position(&list, cursor, at))
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(
+            doc.get_cursor_positions(&list, &list_cursors, at)?,
+            expected
+        );
+    }
+    assert_eq!(
+        doc.get_cursor_positions(&text, &[], None)?,
+        Vec::<usize>::new()
+    );
+    assert!(doc
+        .get_cursor_positions(&text, &list_cursors[..1], None)
+        .is_err());
+    Ok(())
+}
+
+#[test]
+fn cursor_ranges_expand_and_collapse() -> Result<(), AutomergeError> {
+    let mut doc = AutoCommit::new();
+    let text = doc.put_object(ROOT, "text", ObjType::Text)?;
+    doc.splice_text(&text, 0, 0, "one two three")?;
+    let none = doc.get_cursor_range(&text, 4..7, ExpandMark::None, None)?;
+    let both = doc.get_cursor_range(&text, 4..7, ExpandMark::Both, None)?;
+    let all = doc.get_cursor_range(&text, 0..13, ExpandMark::None, None)?;
+    let empty = doc.get_cursor_range(&text, 8..8, ExpandMark::None, None)?;
+    let ranges = [none.clone(), both, all, empty];
+    let resolve = |doc: &AutoCommit| -> Result<Vec<(Range<usize>, bool)>, AutomergeError> {
+        Ok(doc
+            .get_cursor_range_positions(&text, &ranges, None)?
+            .into_iter()
+            .map(|pos| (pos.range, pos.collapsed))
+            .collect())
+    };
+    assert_eq!(
+        resolve(&doc)?,
+        vec![(4..7, false), (4..7, false), (0..13, false), (8..8, false)]
+    );
+
+    // "one two three" -> "one <two> three"
+    doc.splice_text(&text, 7, 0, ">")?;
+    doc.splice_text(&text, 4, 0, "<")?;
+    assert_eq!(
+        resolve(&doc)?,
+        vec![
+            (5..8, false),
+            (4..9, false),
+            (0..15, false),
+            (10..10, false)
+        ]
+    );
+    // "one <two> three" -> "one <tw> three"
+    doc.splice_text(&text, 7, 1, "")?;
+    assert_eq!(
+        doc.get_cursor_range_position(&text, &none, None)?,
+        CursorRangePosition {
+            range: 5..7,
+            collapsed: false
+        }
+    );
+    // "one <tw> three" -> "one <> three"
+    doc.splice_text(&text, 5, 2, "")?;
+    assert_eq!(
+        resolve(&doc)?,
+        vec![(5..5, true), (4..6, false), (0..12, false), (7..7, false)]
+    );
+    doc.splice_text(&text, 0, 12, "")?;
+    assert_eq!(
+        resolve(&doc)?,
+        vec![(0..0, true), (0..0, true), (0..0, true), (0..0, false)]
+    );
+
+    assert!(doc
+        .get_cursor_range(&text, 0..1, ExpandMark::None, None)
+        .is_err());
+    Ok(())
+}
+
+#[test]
+fn cursor_ranges_in_a_list_at_heads() -> Result<(), AutomergeError> {
+    let mut doc = AutoCommit::new();
+    let list = doc.put_object(ROOT, "list", ObjType::List)?;
+    for i in 0..5 {
+        doc.insert(&list, i, i as i64)?;
+    }
+    let heads = doc.get_heads();
+    let range = doc.get_cursor_range(&list, 1..4, ExpandMark::After, None)?;
+    doc.insert(&list, 4, "appended")?;
+    doc.delete(&list, 1)?;
+    assert_eq!(
+        doc.get_cursor_range_position(&list, &range, None)?.range,
+        1..4
+    );
+    assert_eq!(
+        doc.get_cursor_range_position(&list, &range, Some(&heads))?
+            .range,
+        1..4
+    );
+    Ok(())
+}
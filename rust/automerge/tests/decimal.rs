@@ -0,0 +1,99 @@
+use automerge::{
+    hydrate_map, hydrate_set, transaction::Transactable, ActorId, AutoCommit, AutoSerde, Automerge,
+    Change, Decimal, ExpandedChange, ObjType, ReadDoc, ScalarValue, Value, ROOT,
+};
+
+fn decimal(s: &str) -> Decimal {
+    s.parse().unwrap()
+}
+
+fn get(doc: &impl ReadDoc, key: &str) -> Value<'static> {
+    doc.get(ROOT, key).unwrap().unwrap().0.into_owned()
+}
+
+#[test]
+fn decimals_round_trip_through_save_and_load() {
+    let mut doc = AutoCommit::new();
+    let big = decimal("-123456789012345678901234567890.000000000000000001");
+    doc.put(ROOT, "price", decimal("12.30")).unwrap();
+    doc.put(ROOT, "big", big.clone()).unwrap();
+    let list = doc.put_object(ROOT, "list", ObjType::List).unwrap();
+    doc.insert(&list, 0, decimal("0.5")).unwrap();
+    doc.insert(&list, 1, decimal("0")).unwrap();
+
+    let loaded = Automerge::load(&doc.save()).unwrap();
+    assert_eq!(get(&loaded, "price"), Value::from(decimal("12.30")));
+    assert_eq!(get(&loaded, "big"), Value::from(big));
+    // the scale is kept, so the value displays as it was written
+    assert_eq!(
+        get(&loaded, "price").to_scalar().unwrap().to_decimal(),
+        Some(&Decimal::new(1230, 2))
+    );
+    assert_ne!(get(&loaded, "price"), Value::from(decimal("12.3")));
+    assert_eq!(
+        loaded.hydrate(None),
+        automerge::hydrate::Value::from(hydrate_map! {
+            "price" => decimal("12.30"),
+            "big" => decimal("-123456789012345678901234567890.000000000000000001"),
+            "list" => automerge::hydrate_list![decimal("0.5"), decimal("0")],
+        })
+    );
+}
+
+#[test]
+fn decimals_sync_through_changes() {
+    let mut doc = AutoCommit::new().with_actor(ActorId::from([1]));
+    doc.put(ROOT, "amount", decimal("1.50")).unwrap();
+    let mut other = AutoCommit::new().with_actor(ActorId::from([2]));
+    other.merge(&mut doc).unwrap();
+    assert_eq!(get(&other, "amount"), Value::from(decimal("1.50")));
+
+    // changes encode and decode the value
+    let mut change = doc.get_last_local_change().unwrap().clone();
+    let loaded = Change::from_bytes(change.bytes().to_vec()).unwrap();
+    assert_eq!(loaded.decode(), change.decode());
+
+    // and so does the JSON form of a change
+    let json = serde_json::to_value(change.decode()).unwrap();
+    let op = &json["ops"][0];
+    assert_eq!(op["value"], "1.50");
+    assert_eq!(op["datatype"], "decimal");
+    let expanded: ExpandedChange = serde_json::from_value(json).unwrap();
+    assert_eq!(expanded, change.decode());
+}
+
+#[test]
+fn decimals_serialize_as_strings() {
+    let mut doc = AutoCommit::new();
+    doc.put(ROOT, "amount", decimal("-0.001")).unwrap();
+    assert_eq!(
+        serde_json::to_value(AutoSerde::from(&doc)).unwrap(),
+        serde_json::json!({ "amount": "-0.001" })
+    );
+    assert_eq!(
+        ScalarValue::from(decimal("2.50")).to_f64(),
+        Some(2.5),
+        "decimals convert to the nearest float"
+    );
+}
+
+#[test]
+fn decimals_in_sets() {
+    let mut doc = AutoCommit::new();
+    let set = doc.put_object(ROOT, "set", ObjType::Set).unwrap();
+    doc.set_add(&set, decimal("1.0")).unwrap();
+    doc.set_add(&set, decimal("1.00")).unwrap();
+    doc.set_add(&set, decimal("1.0")).unwrap();
+    doc.set_add(&set, "1.0").unwrap();
+
+    assert_eq!(doc.length(&set), 3);
+    assert!(doc.set_contains(&set, &decimal("1.00").into()).unwrap());
+    assert!(!doc.set_contains(&set, &decimal("1").into()).unwrap());
+    let loaded = Automerge::load(&doc.save()).unwrap();
+    assert_eq!(
+        loaded.hydrate(None),
+        automerge::hydrate::Value::from(hydrate_map! {
+            "set" => hydrate_set![decimal("1.0"), decimal("1.00"), "1.0"],
+        })
+    );
+}
@@ -0,0 +1,250 @@
+use std::fmt;
+
+use automerge::extension::{self, DecodeError, ExtensionError, ExtensionType, ExtensionValue};
+use automerge::{
+    hydrate_map, hydrate_set, transaction::Transactable, AutoCommit, AutoSerde, Automerge, ObjType,
+    ReadDoc, ScalarValue, Value, ROOT,
+};
+
+// The registry is shared by every test in this file, so each test uses its own type codes
+
+#[derive(Debug, Clone, PartialEq)]
+struct Point {
+    lat: i32,
+    lng: i32,
+}
+
+impl fmt::Display for Point {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "({}, {})", self.lat, self.lng)
+    }
+}
+
+impl ExtensionType for Point {
+    const TYPE_CODE: u8 = 12;
+    const NAME: &'static str = "point";
+
+    fn encode(&self) -> Vec<u8> {
+        [self.lat.to_be_bytes(), self.lng.to_be_bytes()].concat()
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let bytes: [u8; 8] = bytes
+            .try_into()
+            .map_err(|_| DecodeError::new("expected 8 bytes"))?;
+        Ok(Point {
+            lat: i32::from_be_bytes(bytes[..4].try_into().unwrap()),
+            lng: i32::from_be_bytes(bytes[4..].try_into().unwrap()),
+        })
+    }
+}
+
+/// A short identifier of upper case letters
+#[derive(Debug, Clone, PartialEq)]
+struct Code(String);
+
+impl fmt::Display for Code {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl ExtensionType for Code {
+    const TYPE_CODE: u8 = 13;
+    const NAME: &'static str = "code";
+
+    fn encode(&self) -> Vec<u8> {
+        self.0.as_bytes().to_vec()
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Self, DecodeError> {
+        if bytes.is_empty() || !bytes.iter().all(u8::is_ascii_uppercase) {
+            return Err(DecodeError::new("not a code"));
+        }
+        Ok(Code(String::from_utf8(bytes.to_vec()).unwrap()))
+    }
+
+    fn to_serde(&self) -> ScalarValue {
+        format!("code:{}", self.0).into()
+    }
+}
+
+fn extension<T: ExtensionType>(value: &T) -> ExtensionValue {
+    extension::register::<T>().unwrap();
+    ExtensionValue::new(value).unwrap()
+}
+
+fn get(doc: &impl ReadDoc, key: &str) -> ScalarValue {
+    doc.get(ROOT, key)
+        .unwrap()
+        .unwrap()
+        .0
+        .into_scalar()
+        .unwrap()
+}
+
+#[test]
+fn extension_values_are_read_as_their_type() {
+    let home = Point { lat: 51, lng: -1 };
+    let mut doc = AutoCommit::new();
+    doc.put(ROOT, "home", extension(&home)).unwrap();
+    let list = doc.put_object(ROOT, "route", ObjType::List).unwrap();
+    doc.insert(&list, 0, extension(&Point { lat: 0, lng: 0 }))
+        .unwrap();
+
+    let loaded = Automerge::load(&doc.save()).unwrap();
+    let ScalarValue::Extension(value) = get(&loaded, "home") else {
+        panic!("expected an extension value");
+    };
+    assert_eq!(value.name(), "point");
+    assert_eq!(value.type_code(), 12);
+    assert_eq!(value.get::<Point>(), Some(home.clone()));
+    assert_eq!(value.get::<Code>(), None);
+    assert_eq!(value.to_string(), "(51, -1)");
+
+    assert_eq!(
+        loaded.hydrate(None),
+        automerge::hydrate::Value::from(hydrate_map! {
+            "home" => extension(&home),
+            "route" => automerge::hydrate_list![extension(&Point { lat: 0, lng: 0 })],
+        })
+    );
+    assert_eq!(
+        serde_json::to_value(AutoSerde::from(&loaded)).unwrap(),
+        serde_json::json!({ "home": "(51, -1)", "route": ["(0, 0)"] })
+    );
+}
+
+#[test]
+fn extension_values_can_customise_serialization() {
+    let mut doc = AutoCommit::new();
+    doc.put(ROOT, "code", extension(&Code("ABC".to_string())))
+        .unwrap();
+    let set = doc.put_object(ROOT, "codes", ObjType::Set).unwrap();
+    doc.set_add(&set, extension(&Code("X".to_string())))
+        .unwrap();
+    doc.set_add(&set, extension(&Code("X".to_string())))
+        .unwrap();
+    assert_eq!(doc.length(&set), 1);
+
+    assert_eq!(
+        serde_json::to_value(AutoSerde::from(&doc)).unwrap(),
+        serde_json::json!({ "code": "code:ABC", "codes": ["code:X"] })
+    );
+    let loaded = Automerge::load(&doc.save()).unwrap();
+    assert_eq!(
+        loaded.hydrate(None),
+        automerge::hydrate::Value::from(hydrate_map! {
+            "code" => extension(&Code("ABC".to_string())),
+            "codes" => hydrate_set![extension(&Code("X".to_string()))],
+        })
+    );
+}
+
+#[test]
+fn invalid_and_unregistered_values_are_unknown() {
+    struct Flag;
+
+    impl fmt::Display for Flag {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "flag")
+        }
+    }
+
+    impl ExtensionType for Flag {
+        const TYPE_CODE: u8 = 14;
+        const NAME: &'static str = "flag";
+
+        fn encode(&self) -> Vec<u8> {
+            vec![1]
+        }
+
+        fn decode(bytes: &[u8]) -> Result<Self, DecodeError> {
+            match bytes {
+                [1] => Ok(Flag),
+                _ => Err(DecodeError::new("not a flag")),
+            }
+        }
+    }
+
+    extension::register::<Flag>().unwrap();
+    let unknown = |type_code, bytes: &[u8]| ScalarValue::Unknown {
+        type_code,
+        bytes: bytes.to_vec(),
+    };
+    let mut doc = AutoCommit::new();
+    doc.put(ROOT, "valid", unknown(14, &[1])).unwrap();
+    doc.put(ROOT, "invalid", unknown(14, &[2])).unwrap();
+    doc.put(ROOT, "unregistered", unknown(15, &[1])).unwrap();
+
+    let loaded = Automerge::load(&doc.save()).unwrap();
+    assert!(matches!(get(&loaded, "valid"), ScalarValue::Extension(e) if e.name() == "flag"));
+    // whether or not the type is registered, the value is equal to the bytes it is stored as
+    assert_eq!(get(&loaded, "valid"), unknown(14, &[1]));
+    assert_eq!(unknown(14, &[1]), get(&loaded, "valid"));
+    assert_ne!(get(&loaded, "valid"), unknown(15, &[1]));
+    assert_eq!(get(&loaded, "invalid"), unknown(14, &[2]));
+    assert_eq!(get(&loaded, "unregistered"), unknown(15, &[1]));
+    assert_eq!(
+        loaded.get(ROOT, "valid").unwrap().unwrap().0,
+        Value::from(ExtensionValue::new(&Flag).unwrap())
+    );
+}
+
+#[test]
+fn registration_errors() {
+    struct Named<const CODE: u8>;
+
+    impl<const CODE: u8> fmt::Display for Named<CODE> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "named")
+        }
+    }
+
+    impl<const CODE: u8> ExtensionType for Named<CODE> {
+        const TYPE_CODE: u8 = CODE;
+        const NAME: &'static str = "named";
+
+        fn encode(&self) -> Vec<u8> {
+            Vec::new()
+        }
+
+        fn decode(_bytes: &[u8]) -> Result<Self, DecodeError> {
+            Ok(Named)
+        }
+    }
+
+    assert_eq!(
+        extension::register::<Named<6>>(),
+        Err(ExtensionError::TypeCodeOutOfRange(6))
+    );
+    assert_eq!(
+        extension::register::<Named<11>>(),
+        Err(ExtensionError::TypeCodeOutOfRange(11))
+    );
+    assert_eq!(
+        ExtensionValue::new(&Named::<15>),
+        Err(ExtensionError::NotRegistered {
+            type_code: 15,
+            name: "named"
+        })
+    );
+
+    // registering the same type twice is fine, but another type can't take the code
+    extension::register::<Point>().unwrap();
+    extension::register::<Point>().unwrap();
+    assert_eq!(
+        extension::register::<Named<12>>(),
+        Err(ExtensionError::TypeCodeInUse {
+            type_code: 12,
+            name: "point"
+        })
+    );
+    assert_eq!(
+        ExtensionValue::new(&Named::<12>),
+        Err(ExtensionError::NotRegistered {
+            type_code: 12,
+            name: "named"
+        })
+    );
+}
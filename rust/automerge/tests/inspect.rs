@@ -0,0 +1,269 @@
+use automerge::inspect::{inspect, ChunkContents, ColumnInfo, ColumnType, DocumentInfo};
+use automerge::transaction::Transactable;
+use automerge::{ActorId, AutoCommit, Codec, Compression, ObjType, SaveOptions, ScalarValue, ROOT};
+
+fn document(contents: &ChunkContents) -> &DocumentInfo {
+    match contents {
+        ChunkContents::Document(doc) => doc,
+        other => panic!("expected a document chunk, got {:?}", other),
+    }
+}
+
+fn example_doc() -> AutoCommit {
+    let mut doc = AutoCommit::new().with_actor(ActorId::from(b"aaaa"));
+    let text = doc.put_object(&ROOT, "text", ObjType::Text).unwrap();
+    doc.splice_text(&text, 0, 0, "hello world").unwrap();
+    let list = doc.put_object(&ROOT, "list", ObjType::List).unwrap();
+    doc.insert(&list, 0, 1).unwrap();
+    doc.insert(&list, 1, ScalarValue::counter(0)).unwrap();
+    doc.commit();
+
+    let mut other = doc.fork().with_actor(ActorId::from(b"bbbb"));
+    other.increment(&list, 1, 5).unwrap();
+    other.commit();
+    doc.splice_text(&text, 0, 5, "goodbye").unwrap();
+    doc.commit();
+    doc.merge(&mut other).unwrap();
+    doc.put(&ROOT, "key", "value").unwrap();
+    doc.commit();
+    doc
+}
+
+#[test]
+fn inspect_document_chunk() {
+    let mut doc = example_doc();
+    let saved = doc.save();
+    let inspection = inspect(&saved).unwrap();
+
+    assert_eq!(inspection.chunks.len(), 1);
+    assert_eq!(inspection.size(), saved.len());
+    let chunk = &inspection.chunks[0];
+    assert_eq!(chunk.offset, 0);
+    assert!(chunk.checksum_valid);
+    let info = document(&chunk.contents);
+
+    assert_eq!(
+        info.actors,
+        vec![ActorId::from(b"aaaa"), ActorId::from(b"bbbb")]
+    );
+    assert_eq!(info.heads, doc.get_heads());
+
+    // 11 inserted characters, 7 more inserted and 5 deleted (deletes are recorded as successors)
+    assert_eq!(info.ops.by_obj_type[&ObjType::Text], 18);
+    assert_eq!(info.ops.by_obj_type[&ObjType::List], 3);
+    assert_eq!(info.ops.by_obj_type[&ObjType::Map], 3);
+    assert_eq!(info.ops.total, 24);
+    assert_eq!(info.ops.objects[&ObjType::Map], 1);
+    assert_eq!(info.ops.objects[&ObjType::Text], 1);
+    assert_eq!(info.ops.objects[&ObjType::List], 1);
+
+    assert_eq!(info.changes.num_changes, 4);
+    assert_eq!(info.changes.num_stubs, 0);
+    assert_eq!(info.changes.changes_per_actor, vec![3, 1]);
+    assert_eq!(info.changes.num_deps, 4);
+    assert_eq!(info.changes.max_depth, 3);
+    // the deletions have op ids but are not stored as ops in the document
+    assert_eq!(info.changes.max_op, 28);
+
+    let names = info
+        .change_columns
+        .iter()
+        .map(|c| c.name.unwrap())
+        .collect::<Vec<_>>();
+    assert!(names.contains(&"actor"));
+    assert!(names.contains(&"maxOp"));
+    let action = info
+        .op_columns
+        .iter()
+        .find(|c| c.name == Some("action"))
+        .unwrap();
+    assert_eq!(action.column_type, ColumnType::Integer);
+    assert!(info.op_columns.iter().all(|c| c.name.is_some()));
+}
+
+#[test]
+fn inspect_names_the_target_columns_of_moves() {
+    let mut doc = AutoCommit::new().with_actor(ActorId::from(b"aaaa"));
+    let map = doc.put_object(&ROOT, "map", ObjType::Map).unwrap();
+    let other = doc.put_object(&ROOT, "other", ObjType::Map).unwrap();
+    doc.commit();
+    doc.move_object(&map, &other, "moved").unwrap();
+    doc.commit();
+    let change = doc.get_last_local_change().unwrap();
+
+    let names = |columns: &[ColumnInfo]| {
+        let names = columns.iter().map(|c| c.name).collect::<Vec<_>>();
+        assert!(names.iter().all(|n| n.is_some()), "{:?}", columns);
+        assert!(names.contains(&Some("targetActor")));
+        assert!(names.contains(&Some("targetCtr")));
+    };
+    let saved = inspect(&doc.save()).unwrap();
+    names(&document(&saved.chunks[0].contents).op_columns);
+    let inspection = inspect(change.raw_bytes()).unwrap();
+    let ChunkContents::Change(info) = &inspection.chunks[0].contents else {
+        panic!("expected a change chunk");
+    };
+    names(&info.op_columns);
+    let bundle = doc.bundle([change.hash()]).unwrap();
+    let inspection = inspect(bundle.bytes()).unwrap();
+    let ChunkContents::Bundle(info) = &inspection.chunks[0].contents else {
+        panic!("expected a bundle chunk");
+    };
+    names(&info.op_columns);
+}
+
+#[test]
+fn inspect_reports_compressed_column_sizes() {
+    let mut doc = AutoCommit::new();
+    let text = doc.put_object(&ROOT, "text", ObjType::Text).unwrap();
+    for i in 0..500 {
+        doc.splice_text(&text, i, 0, if i % 3 == 0 { "a" } else { "b" })
+            .unwrap();
+    }
+    doc.commit();
+
+    let uncompressed = inspect(&doc.save_nocompress()).unwrap();
+    let uncompressed = document(&uncompressed.chunks[0].contents);
+    for col in uncompressed
+        .op_columns
+        .iter()
+        .chain(&uncompressed.change_columns)
+    {
+        assert_eq!(col.codec, Codec::None);
+        assert_eq!(col.size, col.uncompressed_size);
+    }
+
+    for codec in [Codec::Deflate, Codec::Zstd] {
+        let compression = match codec {
+            Codec::Deflate => Compression::deflate(),
+            _ => Compression::zstd(),
+        };
+        let saved = doc.save_with_options(SaveOptions {
+            compression: compression.min_size(0),
+            ..Default::default()
+        });
+        let inspection = inspect(&saved).unwrap();
+        let info = document(&inspection.chunks[0].contents);
+        assert_eq!(info.ops, uncompressed.ops);
+        assert_eq!(info.changes, uncompressed.changes);
+
+        let value = info
+            .op_columns
+            .iter()
+            .find(|c| c.name == Some("valRaw"))
+            .unwrap();
+        assert_eq!(value.codec, codec);
+        assert_eq!(value.uncompressed_size, 500);
+        assert!(value.size < value.uncompressed_size);
+        for (col, expected) in info.op_columns.iter().zip(&uncompressed.op_columns) {
+            assert_eq!(col.name, expected.name);
+            assert_eq!(col.uncompressed_size, expected.size);
+        }
+    }
+}
+
+#[test]
+fn inspect_change_chunks() {
+    let mut doc = AutoCommit::new();
+    let text = doc.put_object(&ROOT, "text", ObjType::Text).unwrap();
+    doc.splice_text(&text, 0, 0, "a").unwrap();
+    doc.commit();
+    let small = doc.get_last_local_change().unwrap();
+    doc.splice_text(&text, 1, 0, &"b".repeat(1000)).unwrap();
+    doc.commit();
+    let big = doc.get_last_local_change().unwrap();
+
+    let mut data = small.raw_bytes().to_vec();
+    let mut big_bytes = big.clone();
+    data.extend(big_bytes.bytes().as_ref());
+    let inspection = inspect(&data).unwrap();
+    assert_eq!(inspection.chunks.len(), 2);
+    assert_eq!(inspection.chunks[1].offset, small.raw_bytes().len());
+
+    let ChunkContents::Change(first) = &inspection.chunks[0].contents else {
+        panic!("expected a change chunk");
+    };
+    assert_eq!(first.hash, small.hash());
+    assert_eq!(first.actor, *small.actor_id());
+    assert_eq!(first.seq, 1);
+    assert_eq!(first.num_ops, 2);
+    assert!(first.deps.is_empty());
+    assert!(first.op_columns.iter().all(|c| c.codec == Codec::None));
+
+    let ChunkContents::CompressedChange {
+        change: second,
+        uncompressed_size,
+    } = &inspection.chunks[1].contents
+    else {
+        panic!("expected a compressed change chunk");
+    };
+    assert_eq!(second.hash, big.hash());
+    assert_eq!(second.deps, vec![small.hash()]);
+    assert_eq!(second.start_op, 3);
+    assert_eq!(second.num_ops, 1000);
+    assert_eq!(*uncompressed_size, big.raw_bytes().len());
+    assert!(inspection.chunks[1].size < *uncompressed_size);
+}
+
+#[test]
+fn inspect_bundle_chunk() {
+    let mut doc = example_doc();
+    let hashes = doc
+        .get_changes(&[])
+        .iter()
+        .map(|c| c.hash())
+        .skip(1)
+        .collect::<Vec<_>>();
+    let bundle = doc.bundle(hashes).unwrap();
+    let inspection = inspect(bundle.bytes()).unwrap();
+    let ChunkContents::Bundle(info) = &inspection.chunks[0].contents else {
+        panic!("expected a bundle chunk");
+    };
+    assert_eq!(info.num_changes, 3);
+    assert_eq!(info.deps, bundle.deps());
+    assert_eq!(info.actors, bundle.actors());
+    // an increment, five deletions, seven insertions and a put
+    assert_eq!(info.num_ops, 14);
+    assert!(info
+        .change_columns
+        .iter()
+        .chain(&info.op_columns)
+        .all(|c| c.name.is_some()));
+}
+
+#[test]
+fn inspect_shallow_document() {
+    let mut doc = example_doc();
+    let base = doc.get_changes(&[])[1].hash();
+    let mut shallow = doc.shallow(&[base]).unwrap();
+    let saved = shallow.save();
+    let inspection = inspect(&saved).unwrap();
+
+    assert_eq!(inspection.chunks.len(), 2);
+    let ChunkContents::Base(base_info) = &inspection.chunks[0].contents else {
+        panic!("expected a base chunk");
+    };
+    assert_eq!(base_info.heads, vec![base]);
+    assert_eq!(base_info.num_truncated, 2);
+    let info = document(&inspection.chunks[1].contents);
+    assert_eq!(info.changes.num_stubs, base_info.num_stubs);
+    assert_eq!(info.changes.num_changes, 2);
+    assert_eq!(info.heads, doc.get_heads());
+}
+
+#[test]
+fn inspect_invalid_data() {
+    let mut saved = example_doc().save_nocompress();
+    assert!(inspect(&saved[..saved.len() - 1]).is_err());
+    assert!(inspect(b"not an automerge document").is_err());
+
+    // corrupting the actor table leaves a chunk which can be parsed but has a bad checksum
+    let actor = saved.windows(4).position(|w| w == b"aaaa").unwrap();
+    saved[actor] = b'c';
+    let inspection = inspect(&saved).unwrap();
+    assert!(!inspection.chunks[0].checksum_valid);
+    assert_eq!(
+        document(&inspection.chunks[0].contents).actors[0],
+        ActorId::from(b"caaa")
+    );
+}
@@ -0,0 +1,176 @@
+use automerge::{
+    hydrate_list,
+    marks::{ExpandMark, Mark, MarkQuery},
+    transaction::Transactable,
+    ActorId, AutoCommit, ObjType, PatchAction, ReadDoc, ScalarValue, ROOT,
+};
+
+fn mark(name: &str, value: impl Into<ScalarValue>, start: usize, end: usize) -> Mark {
+    Mark::new(name.to_string(), value, start, end)
+}
+
+fn list_with(doc: &mut AutoCommit, n: i64) -> automerge::ObjId {
+    let list = doc.put_object(ROOT, "list", ObjType::List).unwrap();
+    for i in 0..n {
+        doc.insert(&list, i as usize, i).unwrap();
+    }
+    list
+}
+
+fn values(doc: &AutoCommit, list: &automerge::ObjId) -> Vec<i64> {
+    doc.values(list).map(|(v, _)| v.to_i64().unwrap()).collect()
+}
+
+#[test]
+fn marks_do_not_change_list_contents() {
+    let mut doc = AutoCommit::new();
+    let list = list_with(&mut doc, 6);
+    doc.mark(&list, mark("selected", true, 1, 4), ExpandMark::None)
+        .unwrap();
+    doc.mark(&list, mark("locked", "alice", 3, 6), ExpandMark::After)
+        .unwrap();
+
+    assert_eq!(doc.length(&list), 6);
+    assert_eq!(values(&doc, &list), vec![0, 1, 2, 3, 4, 5]);
+    assert_eq!(
+        doc.list_range(&list, 2..4)
+            .map(|item| (item.index, item.value.into_value().to_i64().unwrap()))
+            .collect::<Vec<_>>(),
+        vec![(2, 2), (3, 3)]
+    );
+    assert_eq!(doc.get(&list, 4).unwrap().unwrap().0.to_i64(), Some(4));
+    assert_eq!(
+        doc.hydrate(&list, None).unwrap(),
+        hydrate_list![0, 1, 2, 3, 4, 5].into()
+    );
+    assert_eq!(
+        doc.marks(&list).unwrap(),
+        vec![mark("locked", "alice", 3, 6), mark("selected", true, 1, 4)]
+    );
+    let at_3 = doc.get_marks(&list, 3, None).unwrap();
+    assert_eq!(
+        at_3.iter().collect::<Vec<_>>(),
+        vec![
+            ("locked", &ScalarValue::from("alice")),
+            ("selected", &ScalarValue::Boolean(true))
+        ]
+    );
+    assert_eq!(doc.get_marks(&list, 0, None).unwrap().len(), 0);
+    assert_eq!(
+        doc.query_marks(&list, &MarkQuery::new().with_name("locked"), None)
+            .unwrap(),
+        vec![mark("locked", "alice", 3, 6)]
+    );
+
+    // only "locked" expands to cover elements inserted at its edges
+    doc.insert(&list, 6, 6).unwrap();
+    doc.insert(&list, 4, 40).unwrap();
+    doc.insert(&list, 1, 10).unwrap();
+    doc.delete(&list, 0).unwrap();
+    assert_eq!(values(&doc, &list), vec![10, 1, 2, 3, 40, 4, 5, 6]);
+    assert_eq!(
+        doc.marks(&list).unwrap(),
+        vec![mark("locked", "alice", 3, 8), mark("selected", true, 1, 4)]
+    );
+
+    // unmark works as it does for text
+    doc.unmark(&list, "selected", 0, 3, ExpandMark::None)
+        .unwrap();
+    assert_eq!(
+        doc.marks(&list).unwrap(),
+        vec![mark("locked", "alice", 3, 8), mark("selected", true, 3, 4)]
+    );
+
+    // marks only make sense on sequences
+    let map = doc.put_object(ROOT, "map", ObjType::Map).unwrap();
+    assert!(doc
+        .mark(&map, mark("selected", true, 0, 1), ExpandMark::None)
+        .is_err());
+}
+
+#[test]
+fn concurrent_list_marks_merge_like_text_marks() {
+    let mut doc1 = AutoCommit::new().with_actor(ActorId::from(b"aaaa"));
+    let list = list_with(&mut doc1, 8);
+    let text = doc1.put_object(ROOT, "text", ObjType::Text).unwrap();
+    doc1.splice_text(&text, 0, 0, "abcdefgh").unwrap();
+    doc1.commit();
+    let mut doc2 = doc1.fork().with_actor(ActorId::from(b"bbbb"));
+
+    for obj in [&list, &text] {
+        doc1.mark(obj, mark("locked", "alice", 0, 5), ExpandMark::None)
+            .unwrap();
+        doc2.mark(obj, mark("locked", "bob", 3, 8), ExpandMark::None)
+            .unwrap();
+    }
+    doc1.insert(&list, 2, 20).unwrap();
+    doc1.splice_text(&text, 2, 0, "X").unwrap();
+    doc2.delete(&list, 6).unwrap();
+    doc2.splice_text(&text, 6, 1, "").unwrap();
+
+    doc1.merge(&mut doc2).unwrap();
+    doc2.merge(&mut doc1).unwrap();
+
+    let expected = doc1.marks(&text).unwrap();
+    assert_eq!(
+        expected,
+        vec![mark("locked", "alice", 0, 4), mark("locked", "bob", 4, 8)]
+    );
+    assert_eq!(doc1.marks(&list).unwrap(), expected);
+    assert_eq!(doc2.marks(&list).unwrap(), expected);
+    assert_eq!(values(&doc1, &list), vec![0, 1, 20, 2, 3, 4, 5, 7]);
+    assert_eq!(values(&doc2, &list), values(&doc1, &list));
+
+    // the marks survive a round trip through the storage format
+    let mut loaded = AutoCommit::load(&doc1.save()).unwrap();
+    assert_eq!(loaded.length(&list), 8);
+    assert_eq!(loaded.marks(&list).unwrap(), expected);
+    assert_eq!(values(&loaded, &list), values(&doc1, &list));
+    assert_eq!(loaded.get_heads(), doc1.get_heads());
+}
+
+#[test]
+fn list_marks_at_heads_and_in_patches() {
+    let mut doc = AutoCommit::new();
+    let list = list_with(&mut doc, 4);
+    doc.commit();
+    let before = doc.get_heads();
+    let mut other = doc.fork();
+
+    doc.mark(&list, mark("selected", true, 1, 3), ExpandMark::None)
+        .unwrap();
+    doc.insert(&list, 4, 4).unwrap();
+    doc.commit();
+    let after = doc.get_heads();
+
+    assert_eq!(doc.length_at(&list, &before), 4);
+    assert_eq!(doc.marks_at(&list, &before).unwrap(), vec![]);
+    assert_eq!(
+        doc.marks_at(&list, &after).unwrap(),
+        vec![mark("selected", true, 1, 3)]
+    );
+    assert_eq!(
+        doc.list_range_at(&list, .., &after)
+            .map(|item| item.value.into_value().to_i64().unwrap())
+            .collect::<Vec<_>>(),
+        vec![0, 1, 2, 3, 4]
+    );
+
+    other.update_diff_cursor();
+    other.merge(&mut doc).unwrap();
+    let selected = vec![mark("selected", true, 1, 3)];
+    for patches in [other.diff_incremental(), doc.diff(&before, &after)] {
+        let actions = patches
+            .into_iter()
+            .map(|patch| patch.action)
+            .collect::<Vec<_>>();
+        assert!(actions
+            .iter()
+            .any(|action| matches!(action, PatchAction::Mark { marks } if *marks == selected)));
+        assert!(actions
+            .iter()
+            .all(|action| !matches!(action, PatchAction::Insert { index, .. } if *index != 4)));
+    }
+    assert_eq!(other.hydrate(&list, None), doc.hydrate(&list, None));
+    assert_eq!(other.marks(&list).unwrap(), doc.marks(&list).unwrap());
+}
@@ -0,0 +1,351 @@
+use automerge::{
+    transaction::Transactable, ActorId, AutoCommit, AutomergeError, ObjId, ObjType, PatchAction,
+    ReadDoc, TextEncoding, ROOT,
+};
+
+mod common;
+use common::{fork, merge_with_patches};
+
+fn list_with(doc: &mut AutoCommit, n: i64) -> ObjId {
+    let list = doc.put_object(ROOT, "list", ObjType::List).unwrap();
+    for i in 0..n {
+        doc.insert(&list, i as usize, i).unwrap();
+    }
+    list
+}
+
+fn values(doc: &AutoCommit, list: &ObjId) -> Vec<i64> {
+    doc.values(list).map(|(v, _)| v.to_i64().unwrap()).collect()
+}
+
+#[test]
+fn move_element_forwards_and_backwards() {
+    let mut doc = AutoCommit::new();
+    let list = list_with(&mut doc, 5);
+
+    doc.move_element(&list, 0, 3).unwrap();
+    assert_eq!(values(&doc, &list), vec![1, 2, 3, 0, 4]);
+    assert_eq!(doc.length(&list), 5);
+
+    doc.move_element(&list, 4, 0).unwrap();
+    assert_eq!(values(&doc, &list), vec![4, 1, 2, 3, 0]);
+
+    doc.move_element(&list, 3, 3).unwrap();
+    assert_eq!(values(&doc, &list), vec![4, 1, 2, 3, 0]);
+
+    doc.move_element(&list, 1, 4).unwrap();
+    assert_eq!(values(&doc, &list), vec![4, 2, 3, 0, 1]);
+
+    assert_eq!(
+        doc.list_range(&list, ..)
+            .map(|item| (item.index, item.value.into_value().to_i64().unwrap()))
+            .collect::<Vec<_>>(),
+        vec![(0, 4), (1, 2), (2, 3), (3, 0), (4, 1)]
+    );
+}
+
+#[test]
+fn moved_elements_keep_their_identity() {
+    let mut doc = AutoCommit::new();
+    let list = doc.put_object(ROOT, "list", ObjType::List).unwrap();
+    let first = doc.insert_object(&list, 0, ObjType::Map).unwrap();
+    doc.put(&first, "name", "first").unwrap();
+    doc.insert(&list, 1, "second").unwrap();
+    doc.insert(&list, 2, "third").unwrap();
+    let cursor = doc.get_cursor(&list, 0, None).unwrap();
+
+    doc.move_element(&list, 0, 2).unwrap();
+
+    let (_, moved) = doc.get(&list, 2).unwrap().unwrap();
+    assert_eq!(moved, first);
+    assert_eq!(
+        doc.get(&first, "name").unwrap().unwrap().0.to_str(),
+        Some("first")
+    );
+    assert_eq!(doc.get_cursor_position(&list, &cursor, None).unwrap(), 2);
+
+    // edits after the move land on the moved element
+    doc.put(&list, 2, "replaced").unwrap();
+    assert_eq!(doc.length(&list), 3);
+    assert_eq!(
+        doc.get(&list, 2).unwrap().unwrap().0.to_str(),
+        Some("replaced")
+    );
+    doc.delete(&list, 0).unwrap();
+    doc.insert(&list, 0, "new").unwrap();
+    assert_eq!(
+        doc.values(&list)
+            .map(|(v, _)| v.to_str().unwrap().to_string())
+            .collect::<Vec<_>>(),
+        vec!["new", "third", "replaced"]
+    );
+}
+
+#[test]
+fn invalid_moves_are_rejected() {
+    let mut doc = AutoCommit::new();
+    let list = list_with(&mut doc, 3);
+    let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+    doc.splice_text(&text, 0, 0, "abc").unwrap();
+
+    assert!(matches!(
+        doc.move_element(&list, 3, 0),
+        Err(AutomergeError::InvalidIndex(3))
+    ));
+    assert!(matches!(
+        doc.move_element(&list, 0, 3),
+        Err(AutomergeError::InvalidIndex(3))
+    ));
+    assert!(matches!(
+        doc.move_element(&text, 0, 1),
+        Err(AutomergeError::InvalidOp(ObjType::Text))
+    ));
+    assert!(matches!(
+        doc.move_element(ROOT, 0, 1),
+        Err(AutomergeError::InvalidOp(ObjType::Map))
+    ));
+    assert_eq!(values(&doc, &list), vec![0, 1, 2]);
+}
+
+#[test]
+fn concurrent_moves_of_one_element_do_not_duplicate_it() {
+    let mut doc1 = AutoCommit::new().with_actor(ActorId::from([1]));
+    let list = list_with(&mut doc1, 5);
+    let mut doc2 = fork(&mut doc1, 2);
+
+    doc1.move_element(&list, 0, 4).unwrap();
+    doc2.move_element(&list, 0, 2).unwrap();
+
+    doc1.merge(&mut doc2).unwrap();
+    doc2.merge(&mut doc1).unwrap();
+
+    assert_eq!(values(&doc1, &list), values(&doc2, &list));
+    assert_eq!(doc1.length(&list), 5);
+    let mut sorted = values(&doc1, &list);
+    sorted.sort();
+    assert_eq!(sorted, vec![0, 1, 2, 3, 4]);
+    // the move by the greater actor wins
+    assert_eq!(values(&doc1, &list), vec![1, 2, 0, 3, 4]);
+}
+
+#[test]
+fn concurrent_moves_of_different_elements_both_apply() {
+    let mut doc1 = AutoCommit::new().with_actor(ActorId::from([1]));
+    let list = list_with(&mut doc1, 5);
+    let mut doc2 = fork(&mut doc1, 2);
+
+    doc1.move_element(&list, 0, 4).unwrap();
+    doc2.move_element(&list, 3, 1).unwrap();
+
+    doc1.merge(&mut doc2).unwrap();
+    doc2.merge(&mut doc1).unwrap();
+
+    assert_eq!(values(&doc1, &list), vec![3, 1, 2, 4, 0]);
+    assert_eq!(values(&doc2, &list), vec![3, 1, 2, 4, 0]);
+}
+
+#[test]
+fn concurrent_put_on_a_moved_element_is_kept() {
+    let mut doc1 = AutoCommit::new().with_actor(ActorId::from([1]));
+    let list = list_with(&mut doc1, 3);
+    let mut doc2 = fork(&mut doc1, 2);
+
+    doc1.move_element(&list, 0, 2).unwrap();
+    doc2.put(&list, 0, 10).unwrap();
+
+    doc1.merge(&mut doc2).unwrap();
+    doc2.merge(&mut doc1).unwrap();
+
+    assert_eq!(values(&doc1, &list), vec![1, 2, 10]);
+    assert_eq!(values(&doc2, &list), vec![1, 2, 10]);
+}
+
+#[test]
+fn concurrent_delete_of_a_moved_element_removes_it() {
+    let mut doc1 = AutoCommit::new().with_actor(ActorId::from([1]));
+    let list = list_with(&mut doc1, 3);
+    let mut doc2 = fork(&mut doc1, 2);
+
+    doc1.move_element(&list, 0, 2).unwrap();
+    doc2.delete(&list, 0).unwrap();
+
+    doc1.merge(&mut doc2).unwrap();
+    doc2.merge(&mut doc1).unwrap();
+
+    assert_eq!(values(&doc1, &list), vec![1, 2]);
+    assert_eq!(values(&doc2, &list), vec![1, 2]);
+    assert_eq!(doc1.length(&list), 2);
+}
+
+#[test]
+fn moves_survive_save_and_load() {
+    let mut doc = AutoCommit::new();
+    let list = list_with(&mut doc, 4);
+    doc.move_element(&list, 3, 0).unwrap();
+    doc.move_element(&list, 1, 3).unwrap();
+    let expected = values(&doc, &list);
+
+    let loaded = AutoCommit::load(&doc.save()).unwrap();
+    assert_eq!(values(&loaded, &list), expected);
+    assert_eq!(loaded.length(&list), 4);
+
+    let mut incremental = AutoCommit::new();
+    incremental.load_incremental(&doc.save()).unwrap();
+    assert_eq!(values(&incremental, &list), expected);
+}
+
+#[test]
+fn moves_are_visible_at_historical_heads() {
+    let mut doc = AutoCommit::new();
+    let list = list_with(&mut doc, 3);
+    let before = doc.get_heads();
+    doc.move_element(&list, 0, 2).unwrap();
+    let after = doc.get_heads();
+    doc.move_element(&list, 0, 2).unwrap();
+
+    let at = |heads: &[automerge::ChangeHash]| {
+        doc.values_at(&list, heads)
+            .map(|(v, _)| v.to_i64().unwrap())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(at(&before), vec![0, 1, 2]);
+    assert_eq!(at(&after), vec![1, 2, 0]);
+    assert_eq!(values(&doc, &list), vec![2, 0, 1]);
+    assert_eq!(doc.length_at(&list, &before), 3);
+}
+
+#[test]
+fn local_moves_emit_move_patches() {
+    let mut doc = AutoCommit::new();
+    let list = list_with(&mut doc, 4);
+    doc.update_diff_cursor();
+    let mut hydrated = doc.hydrate(ROOT, None).unwrap();
+
+    doc.move_element(&list, 0, 3).unwrap();
+    let patches = doc.diff_incremental();
+    assert_eq!(patches.len(), 1);
+    assert_eq!(patches[0].action, PatchAction::Move { from: 0, to: 3 });
+
+    hydrated
+        .apply_patches(TextEncoding::UnicodeCodePoint, patches)
+        .unwrap();
+    assert_eq!(hydrated, doc.hydrate(ROOT, None).unwrap());
+}
+
+#[test]
+fn merged_moves_produce_patches_matching_the_document() {
+    let mut doc1 = AutoCommit::new().with_actor(ActorId::from([1]));
+    let list = list_with(&mut doc1, 6);
+    let mut doc2 = fork(&mut doc1, 2);
+
+    doc1.move_element(&list, 0, 5).unwrap();
+    doc2.move_element(&list, 0, 2).unwrap();
+    doc2.move_element(&list, 5, 0).unwrap();
+    doc2.put(&list, 3, 30).unwrap();
+    doc2.insert(&list, 1, 100).unwrap();
+
+    merge_with_patches(&mut doc1, &mut doc2);
+
+    doc2.merge(&mut doc1).unwrap();
+    assert_eq!(values(&doc1, &list), values(&doc2, &list));
+}
+
+#[test]
+fn rolled_back_moves_leave_the_list_untouched() {
+    let mut doc = AutoCommit::new();
+    let list = list_with(&mut doc, 4);
+    doc.commit();
+
+    doc.move_element(&list, 0, 3).unwrap();
+    doc.move_element(&list, 2, 0).unwrap();
+    assert_eq!(values(&doc, &list), vec![3, 1, 2, 0]);
+    assert_eq!(doc.rollback(), 4);
+
+    assert_eq!(values(&doc, &list), vec![0, 1, 2, 3]);
+    doc.move_element(&list, 1, 2).unwrap();
+    assert_eq!(values(&doc, &list), vec![0, 2, 1, 3]);
+}
+
+#[derive(Debug, Clone)]
+enum Edit {
+    Insert(usize),
+    Delete(usize),
+    Put(usize),
+    Move(usize, usize),
+}
+
+fn arb_edits() -> impl proptest::strategy::Strategy<Value = Vec<Edit>> {
+    use proptest::prelude::*;
+    let edit = prop_oneof![
+        any::<usize>().prop_map(Edit::Insert),
+        any::<usize>().prop_map(Edit::Delete),
+        any::<usize>().prop_map(Edit::Put),
+        (any::<usize>(), any::<usize>()).prop_map(|(from, to)| Edit::Move(from, to)),
+    ];
+    proptest::collection::vec(edit, 0..40)
+}
+
+/// Apply `edits` to `list` in `doc` and to `model`, with indexes wrapped to the list's length
+fn apply(doc: &mut AutoCommit, list: &ObjId, model: &mut Vec<i64>, edits: &[Edit], next: &mut i64) {
+    for edit in edits {
+        let len = model.len();
+        match *edit {
+            Edit::Insert(i) => {
+                doc.insert(list, i % (len + 1), *next).unwrap();
+                model.insert(i % (len + 1), *next);
+            }
+            Edit::Delete(i) if len > 0 => {
+                doc.delete(list, i % len).unwrap();
+                model.remove(i % len);
+            }
+            Edit::Put(i) if len > 0 => {
+                doc.put(list, i % len, *next).unwrap();
+                model[i % len] = *next;
+            }
+            Edit::Move(from, to) if len > 0 => {
+                doc.move_element(list, from % len, to % len).unwrap();
+                let value = model.remove(from % len);
+                model.insert(to % len, value);
+            }
+            _ => {}
+        }
+        *next += 1;
+    }
+}
+
+/// The values of `list` read one index at a time, rather than by iterating over it
+fn values_by_index(doc: &AutoCommit, list: &ObjId) -> Vec<i64> {
+    (0..doc.length(list))
+        .map(|i| doc.get(list, i).unwrap().unwrap().0.to_i64().unwrap())
+        .collect()
+}
+
+proptest::proptest! {
+    #[test]
+    fn reads_of_a_list_with_moves_agree(ours in arb_edits(), theirs in arb_edits()) {
+        let mut doc = AutoCommit::new().with_actor(ActorId::from([1]));
+        let list = list_with(&mut doc, 5);
+        doc.move_element(&list, 0, 3).unwrap();
+        let mut model = values(&doc, &list);
+        let mut other = fork(&mut doc, 2);
+        let mut other_model = model.clone();
+
+        let mut next = 100;
+        apply(&mut doc, &list, &mut model, &ours, &mut next);
+        apply(&mut other, &list, &mut other_model, &theirs, &mut next);
+        proptest::prop_assert_eq!(values(&doc, &list), model.clone());
+        proptest::prop_assert_eq!(values_by_index(&doc, &list), model);
+        proptest::prop_assert_eq!(values_by_index(&other, &list), other_model);
+
+        doc.merge(&mut other).unwrap();
+        proptest::prop_assert_eq!(values_by_index(&doc, &list), values(&doc, &list));
+        // inserting after every element finds the same element as reading it
+        let len = doc.length(&list);
+        for i in (0..len).rev() {
+            let value = doc.get(&list, i).unwrap().unwrap().0.to_i64().unwrap();
+            doc.insert(&list, i + 1, -value).unwrap();
+        }
+        let values = values(&doc, &list);
+        proptest::prop_assert!(values.chunks(2).all(|pair| pair[1] == -pair[0]));
+    }
+}
@@ -0,0 +1,158 @@
+use automerge::{
+    marks::{ExpandMark, Mark, MarkQuery},
+    transaction::Transactable,
+    AutoCommit, ObjType, ReadDoc, ScalarValue, ROOT,
+};
+
+fn mark(name: &str, value: impl Into<ScalarValue>, start: usize, end: usize) -> Mark {
+    Mark::new(name.to_string(), value, start, end)
+}
+
+fn doc_with_marks() -> (AutoCommit, automerge::ObjId) {
+    let mut doc = AutoCommit::new();
+    let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+    doc.splice_text(&text, 0, 0, "a link, some bold text and another link")
+        .unwrap();
+    doc.mark(&text, mark("link", "https://a", 2, 6), ExpandMark::None)
+        .unwrap();
+    doc.mark(&text, mark("bold", true, 13, 22), ExpandMark::After)
+        .unwrap();
+    doc.mark(&text, mark("bold", true, 3, 5), ExpandMark::After)
+        .unwrap();
+    doc.mark(&text, mark("link", "https://b", 35, 39), ExpandMark::None)
+        .unwrap();
+    doc.mark(
+        &text,
+        mark("bold", ScalarValue::Null, 17, 19),
+        ExpandMark::None,
+    )
+    .unwrap();
+    doc.commit();
+    (doc, text)
+}
+
+#[test]
+fn filter_marks_by_name_and_value() {
+    let (doc, text) = doc_with_marks();
+    let links = doc
+        .query_marks(&text, &MarkQuery::new().with_name("link"), None)
+        .unwrap();
+    assert_eq!(
+        links,
+        vec![
+            mark("link", "https://a", 2, 6),
+            mark("link", "https://b", 35, 39)
+        ]
+    );
+    let b = doc
+        .query_marks(&text, &MarkQuery::new().with_value("https://b"), None)
+        .unwrap();
+    assert_eq!(b, vec![mark("link", "https://b", 35, 39)]);
+    assert_eq!(
+        doc.query_marks(&text, &MarkQuery::new(), None).unwrap(),
+        doc.marks(&text).unwrap()
+    );
+}
+
+#[test]
+fn marks_in_range_are_clipped() {
+    let (mut doc, text) = doc_with_marks();
+    let query = MarkQuery::new().in_range(4..18);
+    assert_eq!(
+        doc.query_marks(&text, &query, None).unwrap(),
+        vec![
+            mark("bold", true, 4, 5),
+            mark("bold", true, 13, 17),
+            mark("link", "https://a", 4, 6),
+        ]
+    );
+    assert_eq!(
+        doc.query_marks(&text, &MarkQuery::new().in_range(6..13), None)
+            .unwrap(),
+        vec![]
+    );
+    assert_eq!(
+        doc.query_marks(&text, &MarkQuery::new().in_range(30..100), None)
+            .unwrap(),
+        vec![mark("link", "https://b", 35, 39)]
+    );
+
+    // every range agrees with clipping the full list of marks, and with the walk used for
+    // historical heads
+    let heads = doc.get_heads();
+    let all = doc.marks(&text).unwrap();
+    for start in 0..40 {
+        for end in start..41 {
+            let query = MarkQuery::new().in_range(start..end);
+            let expected = all
+                .iter()
+                .filter_map(|m| {
+                    let (s, e) = (m.start.max(start), m.end.min(end));
+                    (s < e).then(|| mark(&m.name, m.value.clone(), s, e))
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(doc.query_marks(&text, &query, None).unwrap(), expected);
+            assert_eq!(
+                doc.query_marks(&text, &query, Some(&heads)).unwrap(),
+                expected
+            );
+        }
+    }
+}
+
+#[test]
+fn query_marks_at_heads() {
+    let (mut doc, text) = doc_with_marks();
+    let heads = doc.get_heads();
+    doc.splice_text(&text, 0, 8, "").unwrap();
+    doc.mark(&text, mark("link", "https://c", 0, 4), ExpandMark::None)
+        .unwrap();
+    let links = MarkQuery::new().with_name("link");
+    assert_eq!(
+        doc.query_marks(&text, &links, None).unwrap(),
+        vec![
+            mark("link", "https://c", 0, 4),
+            mark("link", "https://b", 27, 31)
+        ]
+    );
+    assert_eq!(
+        doc.query_marks(&text, &links, Some(&heads)).unwrap(),
+        vec![
+            mark("link", "https://a", 2, 6),
+            mark("link", "https://b", 35, 39)
+        ]
+    );
+}
+
+#[test]
+fn next_mark_change() {
+    let (doc, text) = doc_with_marks();
+    // "bold" is set on 3..5 and 13..17, 19..22
+    assert_eq!(
+        doc.next_mark_change(&text, "bold", 0, None).unwrap(),
+        Some(3)
+    );
+    assert_eq!(
+        doc.next_mark_change(&text, "bold", 3, None).unwrap(),
+        Some(5)
+    );
+    assert_eq!(
+        doc.next_mark_change(&text, "bold", 4, None).unwrap(),
+        Some(5)
+    );
+    assert_eq!(
+        doc.next_mark_change(&text, "bold", 5, None).unwrap(),
+        Some(13)
+    );
+    assert_eq!(
+        doc.next_mark_change(&text, "bold", 17, None).unwrap(),
+        Some(19)
+    );
+    assert_eq!(doc.next_mark_change(&text, "bold", 22, None).unwrap(), None);
+    // the link at the end of the text never changes again
+    assert_eq!(doc.next_mark_change(&text, "link", 36, None).unwrap(), None);
+    assert_eq!(
+        doc.next_mark_change(&text, "italic", 0, None).unwrap(),
+        None
+    );
+}
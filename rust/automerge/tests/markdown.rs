@@ -0,0 +1,182 @@
+use std::sync::Arc;
+
+use automerge::{
+    hydrate_list, hydrate_map,
+    iter::Span,
+    markdown::{self, MarkdownBlock, MarkdownConfig, MarkdownMark},
+    marks::{ExpandMark, Mark, MarkSet},
+    transaction::Transactable,
+    AutoCommit, ObjType, ReadDoc, ScalarValue, ROOT,
+};
+
+fn markset(values: Vec<(&'static str, ScalarValue)>) -> Option<Arc<MarkSet>> {
+    Some(Arc::new(
+        values
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v))
+            .collect::<MarkSet>(),
+    ))
+}
+
+#[test]
+fn import_markdown_creates_blocks_and_marks() {
+    let mut doc = AutoCommit::new();
+    let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+    let config = MarkdownConfig::default();
+    markdown::update_markdown(
+        &mut doc,
+        &text,
+        "## Notes\n\n- a **bold** [link](https://example.com)\n  1. nested",
+        &config,
+    )
+    .unwrap();
+
+    let spans = doc.spans(&text).unwrap().collect::<Vec<_>>();
+    assert_eq!(
+        spans,
+        vec![
+            Span::Block(hydrate_map! {
+                "type" => "heading",
+                "parents" => hydrate_list![],
+                "attrs" => hydrate_map! { "level" => 2 },
+            }),
+            Span::Text {
+                text: "Notes".to_string(),
+                marks: None,
+            },
+            Span::Block(hydrate_map! {
+                "type" => "unordered-list-item",
+                "parents" => hydrate_list![],
+                "attrs" => hydrate_map! {},
+            }),
+            Span::Text {
+                text: "a ".to_string(),
+                marks: None,
+            },
+            Span::Text {
+                text: "bold".to_string(),
+                marks: markset(vec![("strong", ScalarValue::Boolean(true))]),
+            },
+            Span::Text {
+                text: " ".to_string(),
+                marks: None,
+            },
+            Span::Text {
+                text: "link".to_string(),
+                marks: markset(vec![("link", "https://example.com".into())]),
+            },
+            Span::Block(hydrate_map! {
+                "type" => "ordered-list-item",
+                "parents" => hydrate_list!["unordered-list-item"],
+                "attrs" => hydrate_map! {},
+            }),
+            Span::Text {
+                text: "nested".to_string(),
+                marks: None,
+            },
+        ]
+    );
+    assert_eq!(
+        markdown::get_markdown(&doc, &text, &config).unwrap(),
+        "## Notes\n\n- a **bold** [link](https://example.com)\n  1. nested"
+    );
+}
+
+#[test]
+fn export_document_edited_directly() {
+    let mut doc = AutoCommit::new();
+    let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+    doc.splice_text(&text, 0, 0, "intro text").unwrap();
+    let quote = doc.split_block(&text, 10).unwrap();
+    doc.update_object(
+        &quote,
+        &hydrate_map! {
+            "type" => "paragraph",
+            "parents" => hydrate_list!["blockquote"],
+            "attrs" => hydrate_map! {},
+        }
+        .into(),
+    )
+    .unwrap();
+    doc.splice_text(&text, 11, 0, "quoted words").unwrap();
+    doc.mark(
+        &text,
+        Mark::new("em".to_string(), true, 11, 17),
+        ExpandMark::After,
+    )
+    .unwrap();
+    doc.mark(
+        &text,
+        Mark::new("comment".to_string(), "ignored", 0, 5),
+        ExpandMark::None,
+    )
+    .unwrap();
+
+    let config = MarkdownConfig::default();
+    assert_eq!(
+        markdown::get_markdown(&doc, &text, &config).unwrap(),
+        "intro text\n\n> *quoted* words"
+    );
+}
+
+#[test]
+fn update_markdown_makes_minimal_changes() {
+    let mut doc = AutoCommit::new();
+    let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+    let config = MarkdownConfig::default();
+    markdown::update_markdown(&mut doc, &text, "# Title\n\nsome text", &config).unwrap();
+    let cursor = doc.get_cursor(&text, 8, None).unwrap();
+    doc.commit();
+
+    markdown::update_markdown(&mut doc, &text, "# Title\n\nsome *more* text", &config).unwrap();
+    assert_eq!(
+        markdown::get_markdown(&doc, &text, &config).unwrap(),
+        "# Title\n\nsome *more* text"
+    );
+    // the text which was not edited is still the same text
+    assert_eq!(doc.get_cursor_position(&text, &cursor, None).unwrap(), 8);
+}
+
+#[test]
+fn custom_vocabulary() {
+    let config = MarkdownConfig::default()
+        .with_mark(MarkdownMark::Strong, "bold")
+        .with_mark(MarkdownMark::Link, "href")
+        .with_block(MarkdownBlock::Heading, "h")
+        .with_heading_level_attr("depth");
+    let spans = markdown::from_markdown("### **Big** [news](x)", &config);
+    assert_eq!(
+        spans,
+        vec![
+            Span::Block(hydrate_map! {
+                "type" => "h",
+                "parents" => hydrate_list![],
+                "attrs" => hydrate_map! { "depth" => 3 },
+            }),
+            Span::Text {
+                text: "Big".to_string(),
+                marks: markset(vec![("bold", ScalarValue::Boolean(true))]),
+            },
+            Span::Text {
+                text: " ".to_string(),
+                marks: None,
+            },
+            Span::Text {
+                text: "news".to_string(),
+                marks: markset(vec![("href", "x".into())]),
+            },
+        ]
+    );
+    assert_eq!(
+        markdown::to_markdown(spans, &config),
+        "### **Big** [news](x)"
+    );
+    // the default vocabulary knows nothing about these marks and blocks
+    assert_eq!(
+        markdown::to_markdown(
+            markdown::from_markdown("### **Big** [news](x)", &config),
+            &MarkdownConfig::default()
+        ),
+        "Big news"
+    );
+}
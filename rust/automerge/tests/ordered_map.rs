@@ -0,0 +1,354 @@
+use automerge::{
+    hydrate_list, hydrate_map, hydrate_ordered_map, transaction::Transactable, AutoCommit,
+    Automerge, AutomergeError, ObjId, ObjType, PatchAction, ReadDoc, ROOT,
+};
+
+mod common;
+use common::{assert_diff_matches, doc_with, fork, merge_with_patches};
+
+/// A document with an ordered map at `columns` which has the keys `a`, `b` and `c`, in that order
+fn doc_with_ordered_map() -> (AutoCommit, ObjId) {
+    let (mut doc, columns) = doc_with("columns", ObjType::OrderedMap);
+    for key in ["a", "b", "c"] {
+        doc.put(&columns, key, key.to_uppercase()).unwrap();
+    }
+    (doc, columns)
+}
+
+fn keys(doc: &impl ReadDoc, map: &ObjId) -> Vec<String> {
+    doc.keys(map).collect()
+}
+
+fn id(doc: &AutoCommit, map: &ObjId, key: &str) -> ObjId {
+    doc.get(map, key).unwrap().unwrap().1
+}
+
+#[test]
+fn keys_are_in_the_order_they_were_put() {
+    let (mut doc, columns) = doc_with_ordered_map();
+    doc.put(&columns, "0", 0).unwrap();
+    // putting a key which is already there keeps its place
+    doc.put(&columns, "a", "A2").unwrap();
+
+    assert_eq!(keys(&doc, &columns), vec!["a", "b", "c", "0"]);
+    assert_eq!(doc.length(&columns), 4);
+    assert_eq!(
+        doc.values(&columns)
+            .map(|(v, _)| v.to_string())
+            .collect::<Vec<_>>(),
+        vec!["\"A2\"", "\"B\"", "\"C\"", "0"]
+    );
+    assert_eq!(
+        doc.map_range(&columns, "b".to_string()..)
+            .map(|item| item.key.into_owned())
+            .collect::<Vec<_>>(),
+        vec!["b", "c"]
+    );
+    assert_eq!(
+        doc.get(&columns, "b").unwrap().unwrap().0,
+        automerge::Value::from("B")
+    );
+
+    doc.delete(&columns, "b").unwrap();
+    doc.put(&columns, "b", "B2").unwrap();
+    assert_eq!(keys(&doc, &columns), vec!["a", "c", "0", "b"]);
+    assert_eq!(
+        doc.hydrate(ROOT, None).unwrap(),
+        automerge::hydrate::Value::from(hydrate_map! {
+            "columns" => hydrate_ordered_map! { "a" => "A2", "c" => "C", "0" => 0, "b" => "B2" },
+        })
+    );
+}
+
+#[test]
+fn move_keys() {
+    let (mut doc, columns) = doc_with_ordered_map();
+
+    doc.move_key(&columns, "c", 0).unwrap();
+    assert_eq!(keys(&doc, &columns), vec!["c", "a", "b"]);
+    doc.move_key(&columns, "c", 2).unwrap();
+    assert_eq!(keys(&doc, &columns), vec!["a", "b", "c"]);
+    doc.move_key(&columns, "a", 1).unwrap();
+    assert_eq!(keys(&doc, &columns), vec!["b", "a", "c"]);
+    // moving a key to where it already is does nothing
+    doc.move_key(&columns, "a", 1).unwrap();
+    assert_eq!(keys(&doc, &columns), vec!["b", "a", "c"]);
+
+    // lots of moves to the same place
+    for _ in 0..50 {
+        doc.move_key(&columns, "c", 1).unwrap();
+        doc.move_key(&columns, "a", 1).unwrap();
+    }
+    assert_eq!(keys(&doc, &columns), vec!["b", "a", "c"]);
+    assert_eq!(doc.get(&columns, "a").unwrap().unwrap().0, "A".into());
+}
+
+#[test]
+fn writes_in_one_transaction_keep_the_keys_in_order() {
+    let (mut doc, columns) = doc_with_ordered_map();
+    let mut expected = vec!["a", "b", "c"]
+        .into_iter()
+        .map(String::from)
+        .collect::<Vec<_>>();
+    let mut rng = 7_u64;
+    let mut next = |n: usize| {
+        rng = rng
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (rng >> 33) as usize % n
+    };
+    for i in 0..200 {
+        match next(3) {
+            0 => {
+                let key = format!("k{}", next(40));
+                if !expected.contains(&key) {
+                    expected.push(key.clone());
+                }
+                doc.put(&columns, key, i).unwrap();
+            }
+            1 if !expected.is_empty() => {
+                let key = expected.remove(next(expected.len()));
+                doc.delete(&columns, key).unwrap();
+            }
+            _ if !expected.is_empty() => {
+                let key = expected.remove(next(expected.len()));
+                let index = next(expected.len() + 1);
+                doc.move_key(&columns, &key, index).unwrap();
+                expected.insert(index, key);
+            }
+            _ => {}
+        }
+    }
+    assert!(doc.pending_ops() > 0);
+    assert_eq!(keys(&doc, &columns), expected);
+    doc.commit();
+    let loaded = Automerge::load(&doc.save()).unwrap();
+    assert_eq!(keys(&loaded, &columns), expected);
+
+    let mut in_range = expected.clone();
+    in_range.retain(|k| ("k1".to_string().."k3".to_string()).contains(k));
+    assert_eq!(
+        loaded
+            .map_range(&columns, "k1".to_string().."k3".to_string())
+            .map(|item| item.key.into_owned())
+            .collect::<Vec<_>>(),
+        in_range
+    );
+}
+
+#[test]
+fn ordered_map_errors() {
+    let (mut doc, columns) = doc_with_ordered_map();
+    let map = doc.put_object(ROOT, "map", ObjType::Map).unwrap();
+
+    assert_eq!(
+        doc.move_key(&columns, "d", 0),
+        Err(AutomergeError::MissingKey("d".to_string()))
+    );
+    assert_eq!(
+        doc.move_key(&columns, "a", 3),
+        Err(AutomergeError::InvalidIndex(3))
+    );
+    assert_eq!(
+        doc.move_key(&map, "a", 0),
+        Err(AutomergeError::InvalidOp(ObjType::Map))
+    );
+    assert_eq!(
+        doc.put(&columns, "\u{0}a", 1),
+        Err(AutomergeError::ReservedKey("\u{0}a".to_string()))
+    );
+    assert_eq!(doc.get(&columns, "\u{0}a").unwrap(), None);
+    // an ordered map can be moved, but objects in it can't
+    doc.move_object(&columns, &map, "moved").unwrap();
+    let list = doc.put_object(&map, "list", ObjType::List).unwrap();
+    let child = doc.put_object(&columns, "d", ObjType::Map).unwrap();
+    assert_eq!(
+        doc.move_object(&child, &list, 0),
+        Err(AutomergeError::InvalidOp(ObjType::OrderedMap))
+    );
+}
+
+#[test]
+fn concurrent_moves_of_different_keys_both_apply() {
+    let (mut doc, columns) = doc_with_ordered_map();
+    let mut other = fork(&mut doc, 2);
+
+    doc.move_key(&columns, "c", 0).unwrap();
+    other.move_key(&columns, "a", 2).unwrap();
+    other.put(&columns, "d", "D").unwrap();
+
+    let mut merged = doc.clone();
+    merge_with_patches(&mut merged, &mut other);
+    assert_eq!(keys(&merged, &columns), vec!["c", "b", "a", "d"]);
+
+    merge_with_patches(&mut other, &mut doc);
+    assert_eq!(keys(&other, &columns), vec!["c", "b", "a", "d"]);
+}
+
+#[test]
+fn concurrent_moves_of_the_same_key_pick_one() {
+    let (mut doc, columns) = doc_with_ordered_map();
+    let mut other = fork(&mut doc, 2);
+
+    doc.move_key(&columns, "c", 0).unwrap();
+    other.move_key(&columns, "c", 1).unwrap();
+
+    merge_with_patches(&mut doc, &mut other);
+    merge_with_patches(&mut other, &mut doc);
+    // the later actor wins, like any other conflicting put
+    assert_eq!(keys(&doc, &columns), vec!["a", "c", "b"]);
+    assert_eq!(keys(&other, &columns), keys(&doc, &columns));
+}
+
+#[test]
+fn a_key_put_concurrently_with_its_delete_comes_last() {
+    let (mut doc, columns) = doc_with_ordered_map();
+    let mut other = fork(&mut doc, 2);
+
+    doc.delete(&columns, "a").unwrap();
+    other.put(&columns, "a", "A2").unwrap();
+
+    merge_with_patches(&mut doc, &mut other);
+    assert_eq!(keys(&doc, &columns), vec!["b", "c", "a"]);
+
+    // moving it gives it a position again
+    doc.move_key(&columns, "a", 0).unwrap();
+    assert_eq!(keys(&doc, &columns), vec!["a", "b", "c"]);
+}
+
+#[test]
+fn a_key_deleted_while_it_is_moved_comes_back_where_it_was_moved() {
+    let (mut doc, columns) = doc_with_ordered_map();
+    let mut other = fork(&mut doc, 2);
+
+    doc.delete(&columns, "a").unwrap();
+    other.move_key(&columns, "a", 2).unwrap();
+    merge_with_patches(&mut doc, &mut other);
+    assert_eq!(keys(&doc, &columns), vec!["b", "c"]);
+
+    // in one transaction, so the key comes back after the transaction has sorted the keys
+    doc.move_key(&columns, "c", 0).unwrap();
+    doc.put(&columns, "a", "A2").unwrap();
+    doc.move_key(&columns, "a", 1).unwrap();
+    assert_eq!(keys(&doc, &columns), vec!["c", "a", "b"]);
+}
+
+#[test]
+fn ordered_maps_round_trip_through_save_and_load() {
+    let (mut doc, columns) = doc_with_ordered_map();
+    doc.move_key(&columns, "b", 0).unwrap();
+    let nested = doc.put_object(&columns, "d", ObjType::OrderedMap).unwrap();
+    doc.put(&nested, "z", 1).unwrap();
+    doc.put(&nested, "y", 2).unwrap();
+
+    let loaded = Automerge::load(&doc.save()).unwrap();
+    let (value, id) = loaded.get(ROOT, "columns").unwrap().unwrap();
+    assert_eq!(value, automerge::Value::Object(ObjType::OrderedMap));
+    assert_eq!(id, columns);
+    assert_eq!(keys(&loaded, &columns), vec!["b", "a", "c", "d"]);
+    assert_eq!(keys(&loaded, &nested), vec!["z", "y"]);
+    assert_eq!(loaded.hydrate(None), doc.hydrate(ROOT, None).unwrap());
+
+    let mut other = Automerge::new();
+    other.apply_changes(doc.get_changes(&[])).unwrap();
+    assert_eq!(keys(&other, &columns), vec!["b", "a", "c", "d"]);
+}
+
+#[test]
+fn read_an_ordered_map_at_old_heads() {
+    let (mut doc, columns) = doc_with_ordered_map();
+    let heads = doc.get_heads();
+    doc.move_key(&columns, "c", 0).unwrap();
+    doc.delete(&columns, "a").unwrap();
+    doc.put(&columns, "d", "D").unwrap();
+
+    assert_eq!(
+        doc.keys_at(&columns, &heads).collect::<Vec<_>>(),
+        vec!["a", "b", "c"]
+    );
+    assert_eq!(doc.length_at(&columns, &heads), 3);
+
+    assert_diff_matches(&mut doc, &heads);
+}
+
+#[test]
+fn ordered_map_patches() {
+    let (mut doc, columns) = doc_with_ordered_map();
+    doc.update_diff_cursor();
+
+    doc.put(&columns, "a", "A2").unwrap();
+    doc.move_key(&columns, "c", 0).unwrap();
+
+    let actions = doc
+        .diff_incremental()
+        .into_iter()
+        .map(|p| {
+            assert_eq!(p.obj, columns);
+            p.action
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        actions,
+        vec![
+            PatchAction::PutMap {
+                key: "a".to_string(),
+                value: ("A2".into(), id(&doc, &columns, "a")),
+                conflict: false,
+            },
+            PatchAction::MoveKey {
+                key: "c".to_string(),
+                index: 0,
+            },
+            PatchAction::MoveKey {
+                key: "a".to_string(),
+                index: 1,
+            },
+            PatchAction::MoveKey {
+                key: "b".to_string(),
+                index: 2,
+            },
+        ]
+    );
+}
+
+#[test]
+fn update_object_with_a_hydrated_ordered_map() {
+    let (mut doc, columns) = doc_with_ordered_map();
+
+    let new_value = hydrate_ordered_map! {
+        "c" => "C",
+        "d" => hydrate_list![1, 2],
+        "a" => "A2",
+    };
+    doc.update_object(&columns, &new_value.clone().into())
+        .unwrap();
+    assert_eq!(keys(&doc, &columns), vec!["c", "d", "a"]);
+    assert_eq!(
+        doc.hydrate(&columns, None).unwrap(),
+        automerge::hydrate::Value::from(new_value)
+    );
+
+    let new = doc.put_object(ROOT, "new", ObjType::Map).unwrap();
+    let value = hydrate_map! { "order" => hydrate_ordered_map! { "z" => 1, "y" => 2 } };
+    doc.update_object(&new, &value.clone().into()).unwrap();
+    assert_eq!(
+        doc.hydrate(&new, None).unwrap(),
+        automerge::hydrate::Value::from(value.clone())
+    );
+
+    // a new ordered map made in one go
+    let made = doc
+        .batch_create_object(ROOT, "made", &value.into(), false)
+        .unwrap();
+    let order = id(&doc, &made, "order");
+    assert_eq!(keys(&doc, &order), vec!["z", "y"]);
+    assert_eq!(
+        doc.batch_create_object(
+            ROOT,
+            "bad",
+            &hydrate_ordered_map! { "\u{0}z" => 1 }.into(),
+            false
+        ),
+        Err(AutomergeError::ReservedKey("\u{0}z".to_string()))
+    );
+}
@@ -0,0 +1,209 @@
+use automerge::{
+    hydrate_list, hydrate_map, transaction::Transactable, ActorId, AutoCommit, Automerge,
+    AutomergeError, ObjId, ObjType, ReadDoc, RestorePolicy, ROOT,
+};
+
+/// A document with a map at `card` which has a title
+fn doc_with_card() -> (AutoCommit, ObjId) {
+    let mut doc = AutoCommit::new().with_actor(ActorId::from([1]));
+    let card = doc.put_object(ROOT, "card", ObjType::Map).unwrap();
+    doc.put(&card, "title", "write tests").unwrap();
+    doc.commit();
+    (doc, card)
+}
+
+/// Edit `card` in `doc`, returning the ids of the three edits
+fn edit_card(doc: &mut AutoCommit, card: &ObjId) -> Vec<ObjId> {
+    doc.put(card, "title", "write more tests").unwrap();
+    let tags = doc.put_object(card, "tags", ObjType::List).unwrap();
+    doc.insert(&tags, 0, "urgent").unwrap();
+    vec![
+        doc.get(card, "title").unwrap().unwrap().1,
+        tags.clone(),
+        doc.get(&tags, 0).unwrap().unwrap().1,
+    ]
+}
+
+#[test]
+fn edits_to_an_object_deleted_concurrently_are_orphaned() {
+    let (mut doc, card) = doc_with_card();
+    let mut other = doc.fork().with_actor(ActorId::from([2]));
+
+    doc.delete(ROOT, "card").unwrap();
+    let mut edits = edit_card(&mut other, &card);
+    doc.merge(&mut other).unwrap();
+
+    assert_eq!(doc.get(ROOT, "card").unwrap(), None);
+    assert_eq!(doc.parents(&card).unwrap().visible_path(), None);
+    let orphans = doc.orphaned_edits();
+    assert_eq!(orphans.len(), 1);
+    let orphan = &orphans[0];
+    assert_eq!(orphan.obj, card);
+    assert_eq!(orphan.parent, ROOT);
+    assert_eq!(orphan.prop, "card".into());
+    assert_eq!(orphan.removed_by.len(), 1);
+    let mut orphaned = orphan.edits.clone();
+    orphaned.sort_by_key(|id| id.to_string());
+    edits.sort_by_key(|id| id.to_string());
+    assert_eq!(orphaned, edits);
+
+    // the other side of the merge sees the same thing
+    other.merge(&mut doc).unwrap();
+    assert_eq!(other.orphaned_edits(), orphans);
+
+    // by default nothing is put back
+    assert_eq!(doc.restore_policy(ROOT), RestorePolicy::Leave);
+    assert_eq!(doc.restore_orphans().unwrap(), Vec::<ObjId>::new());
+    assert_eq!(doc.get(ROOT, "card").unwrap(), None);
+}
+
+#[test]
+fn edits_the_delete_saw_are_not_orphaned() {
+    let (mut doc, card) = doc_with_card();
+    edit_card(&mut doc, &card);
+    doc.commit();
+    doc.delete(ROOT, "card").unwrap();
+    assert_eq!(doc.orphaned_edits(), vec![]);
+
+    // nor are objects which lost a conflict
+    let (mut doc, _) = doc_with_card();
+    let mut other = doc.fork().with_actor(ActorId::from([2]));
+    let theirs = other.put_object(ROOT, "card", ObjType::Map).unwrap();
+    other.put(&theirs, "title", "theirs").unwrap();
+    let ours = doc.put_object(ROOT, "card", ObjType::Map).unwrap();
+    doc.put(&ours, "title", "ours").unwrap();
+    doc.merge(&mut other).unwrap();
+    assert_eq!(doc.get_all(ROOT, "card").unwrap().len(), 2);
+    assert_eq!(doc.orphaned_edits(), vec![]);
+}
+
+#[test]
+fn orphaned_edits_since_only_looks_at_the_new_changes() {
+    let (mut doc, card) = doc_with_card();
+    let mut other = doc.fork().with_actor(ActorId::from([2]));
+
+    doc.delete(ROOT, "card").unwrap();
+    edit_card(&mut other, &card);
+    let ours = doc.get_heads();
+    let theirs = other.get_heads();
+    doc.merge(&mut other).unwrap();
+    other.merge(&mut doc).unwrap();
+
+    // whether the new changes made the edits or removed the object
+    let orphans = doc.orphaned_edits();
+    assert_eq!(orphans.len(), 1);
+    assert_eq!(doc.orphaned_edits_since(&ours), orphans);
+    assert_eq!(other.orphaned_edits_since(&theirs), orphans);
+    assert_eq!(doc.orphaned_edits_since(&[]), orphans);
+
+    // but not changes which have nothing to do with them
+    let heads = doc.get_heads();
+    doc.put(ROOT, "unrelated", 1).unwrap();
+    assert!(doc.orphaned_edits_since(&heads).is_empty());
+    let now = doc.get_heads();
+    assert!(doc.orphaned_edits_since(&now).is_empty());
+
+    doc.set_restore_policy(ROOT, RestorePolicy::Restore)
+        .unwrap();
+    assert!(doc.restore_orphans_since(&heads).unwrap().is_empty());
+    assert_eq!(doc.restore_orphans_since(&ours).unwrap(), vec![card]);
+}
+
+#[test]
+fn the_restore_policy_puts_deleted_objects_back() {
+    let (mut doc, card) = doc_with_card();
+    let list = doc.put_object(ROOT, "list", ObjType::List).unwrap();
+    let item = doc.insert_object(&list, 0, ObjType::Map).unwrap();
+    doc.commit();
+    let mut other = doc.fork().with_actor(ActorId::from([2]));
+
+    doc.delete(ROOT, "card").unwrap();
+    doc.delete(&list, 0).unwrap();
+    edit_card(&mut other, &card);
+    other.put(&item, "done", true).unwrap();
+    doc.merge(&mut other).unwrap();
+
+    // only the maps with the restore policy get their objects back
+    let orphans = doc.orphaned_edits();
+    assert_eq!(orphans.len(), 2);
+    assert!(orphans.iter().any(|o| o.obj == item && o.prop == 0.into()));
+    doc.set_restore_policy(ROOT, RestorePolicy::Restore)
+        .unwrap();
+    assert_eq!(doc.restore_policy(ROOT), RestorePolicy::Restore);
+    assert_eq!(doc.restore_orphans().unwrap(), vec![card.clone()]);
+    doc.commit();
+
+    assert_eq!(doc.get(ROOT, "card").unwrap().unwrap().1, card);
+    assert_eq!(
+        doc.hydrate(&card, None).unwrap(),
+        hydrate_map! {
+            "title" => "write more tests",
+            "tags" => hydrate_list!["urgent"],
+        }
+        .into()
+    );
+    assert_eq!(doc.orphaned_edits().len(), 1);
+
+    // the restore is an ordinary change which other peers receive
+    other.merge(&mut doc).unwrap();
+    assert_eq!(other.get(ROOT, "card").unwrap().unwrap().1, card);
+    assert_eq!(
+        other.hydrate(ROOT, None).unwrap(),
+        doc.hydrate(ROOT, None).unwrap()
+    );
+}
+
+#[test]
+fn objects_whose_key_was_overwritten_are_not_put_back() {
+    let (mut doc, card) = doc_with_card();
+    let mut other = doc.fork().with_actor(ActorId::from([2]));
+
+    doc.put(ROOT, "card", "archived").unwrap();
+    edit_card(&mut other, &card);
+    doc.merge(&mut other).unwrap();
+
+    let mut doc = Automerge::load(&doc.save()).unwrap();
+    assert_eq!(doc.orphaned_edits().len(), 1);
+    doc.set_restore_policy(ROOT, RestorePolicy::Restore)
+        .unwrap();
+    let heads = doc.get_heads();
+    assert_eq!(doc.restore_orphans().unwrap(), Vec::<ObjId>::new());
+    assert_eq!(doc.get_heads(), heads);
+    assert_eq!(
+        doc.get(ROOT, "card").unwrap().unwrap().0,
+        automerge::Value::from("archived")
+    );
+}
+
+#[test]
+fn restore_orphans_makes_a_change() {
+    let (mut doc, card) = doc_with_card();
+    let mut other = doc.fork().with_actor(ActorId::from([2]));
+    doc.delete(ROOT, "card").unwrap();
+    edit_card(&mut other, &card);
+    doc.merge(&mut other).unwrap();
+
+    let mut doc = Automerge::load(&doc.save()).unwrap();
+    doc.set_restore_policy(ROOT, RestorePolicy::Restore)
+        .unwrap();
+    let changes = doc.get_changes(&[]).len();
+    assert_eq!(doc.restore_orphans().unwrap(), vec![card.clone()]);
+    assert_eq!(doc.get_changes(&[]).len(), changes + 1);
+    assert_eq!(doc.get(ROOT, "card").unwrap().unwrap().1, card);
+    assert_eq!(doc.orphaned_edits(), vec![]);
+}
+
+#[test]
+fn restore_policy_errors() {
+    let (mut doc, card) = doc_with_card();
+    let list = doc.put_object(&card, "list", ObjType::List).unwrap();
+
+    assert_eq!(
+        doc.set_restore_policy(&list, RestorePolicy::Restore),
+        Err(AutomergeError::InvalidOp(ObjType::List))
+    );
+    doc.set_restore_policy(&card, RestorePolicy::Restore)
+        .unwrap();
+    doc.set_restore_policy(&card, RestorePolicy::Leave).unwrap();
+    assert_eq!(doc.restore_policy(&card), RestorePolicy::Leave);
+}
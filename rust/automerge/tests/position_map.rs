@@ -0,0 +1,59 @@
+use automerge::transaction::Transactable;
+use automerge::{ActorId, AutoCommit, MoveCursor, ObjType, PositionMap, ReadDoc, ROOT};
+
+#[test]
+fn map_selection_through_local_and_merged_edits() {
+    let mut doc = AutoCommit::new().with_actor(ActorId::from(b"aaaa"));
+    let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+    doc.splice_text(&text, 0, 0, "the quick brown fox jumps")
+        .unwrap();
+    doc.commit();
+    let mut other = doc.fork().with_actor(ActorId::from(b"bbbb"));
+    doc.update_diff_cursor();
+
+    other.splice_text(&text, 4, 6, "").unwrap();
+    other.splice_text(&text, 0, 0, "see ").unwrap();
+    other.commit();
+    doc.splice_text(&text, 25, 0, " high").unwrap();
+    doc.commit();
+    let local = doc.diff_incremental();
+    doc.merge(&mut other).unwrap();
+    let remote = doc.diff_incremental();
+    assert_eq!(doc.text(&text).unwrap(), "see the brown fox jumps high");
+
+    let mut map = PositionMap::from_patches(&text, &local);
+    map.append(PositionMap::from_patches(&text, &remote));
+
+    // the start of "brown" follows the text
+    assert_eq!(map.map(10, MoveCursor::After), 8);
+    // a selection of "quick " collapses to where it was
+    let start = map.map_result(4, MoveCursor::After);
+    let end = map.map_result(10, MoveCursor::Before);
+    assert_eq!((start.pos, end.pos), (8, 8));
+    assert!(map.map_result(6, MoveCursor::Before).deleted);
+    // the bias decides which side of text inserted at a position it ends up on
+    assert_eq!(map.map(25, MoveCursor::Before), 23);
+    assert_eq!(map.map(25, MoveCursor::After), 28);
+}
+
+#[test]
+fn map_through_block_markers_and_other_objects() {
+    let mut doc = AutoCommit::new();
+    let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+    let other = doc.put_object(ROOT, "other", ObjType::Text).unwrap();
+    doc.splice_text(&text, 0, 0, "one two").unwrap();
+    doc.commit();
+    doc.update_diff_cursor();
+
+    doc.split_block(&text, 4).unwrap();
+    doc.splice_text(&other, 0, 0, "unrelated").unwrap();
+    doc.put(&ROOT, "key", "value").unwrap();
+    doc.commit();
+
+    let map = PositionMap::from_patches(&text, &doc.diff_incremental());
+    assert_eq!(map.map(2, MoveCursor::After), 2);
+    assert_eq!(map.map(4, MoveCursor::Before), 4);
+    assert_eq!(map.map(4, MoveCursor::After), 5);
+    assert_eq!(map.map(7, MoveCursor::Before), 8);
+    assert!(PositionMap::from_patches(&ROOT, &[]).is_empty());
+}
@@ -0,0 +1,265 @@
+use automerge::{
+    transaction::{FailedPrecondition, PreconditionConflict, Transactable},
+    ActorId, AutoCommit, Automerge, AutomergeError, ObjId, ObjType, ReadDoc, ROOT,
+};
+
+/// A document with `status` set to "todo", and the id of that value
+fn doc_with_status() -> (Automerge, ObjId) {
+    let mut doc = Automerge::new().with_actor(ActorId::from([1]));
+    let mut tx = doc.transaction();
+    tx.put(ROOT, "status", "todo").unwrap();
+    tx.commit();
+    let id = doc.get(ROOT, "status").unwrap().unwrap().1;
+    (doc, id)
+}
+
+fn status(doc: &Automerge) -> String {
+    doc.get(ROOT, "status")
+        .unwrap()
+        .unwrap()
+        .0
+        .into_string()
+        .unwrap()
+}
+
+#[test]
+fn a_transaction_commits_if_its_preconditions_hold() {
+    let (mut doc, todo) = doc_with_status();
+
+    let mut tx = doc.transaction();
+    tx.expect_winner(ROOT, "status", Some(todo)).unwrap();
+    tx.expect_winner(ROOT, "owner", None).unwrap();
+    tx.put(ROOT, "status", "done").unwrap();
+    tx.put(ROOT, "owner", "alice").unwrap();
+    // the transaction's own ops don't count
+    assert_eq!(tx.check_preconditions(), Ok(()));
+    let (hash, _) = tx.commit();
+
+    assert!(hash.is_some());
+    assert_eq!(status(&doc), "done");
+    assert!(doc.take_precondition_conflicts().is_empty());
+}
+
+#[test]
+fn a_transaction_is_rolled_back_if_a_precondition_fails() {
+    let (mut doc, todo) = doc_with_status();
+    let heads = doc.get_heads();
+
+    let mut tx = doc.transaction();
+    tx.put(ROOT, "status", "doing").unwrap();
+    tx.commit();
+    let doing = doc.get(ROOT, "status").unwrap().unwrap().1;
+
+    // a transaction based on the old heads reads "todo", but "doing" was put since
+    let mut tx = doc
+        .transaction_at(automerge::PatchLog::inactive(), &heads)
+        .unwrap();
+    assert_eq!(tx.get(ROOT, "status").unwrap().unwrap().1, todo);
+    tx.expect_winner(ROOT, "status", Some(todo.clone()))
+        .unwrap();
+    tx.put(ROOT, "status", "done").unwrap();
+    assert_eq!(
+        tx.check_preconditions(),
+        Err(AutomergeError::PreconditionFailed(Box::new(
+            FailedPrecondition {
+                obj: ROOT,
+                prop: "status".into(),
+                expected: Some(todo.clone()),
+                actual: Some(doing),
+            }
+        )))
+    );
+    let (hash, _) = tx.commit();
+
+    assert_eq!(hash, None);
+    assert_eq!(status(&doc), "doing");
+    assert_eq!(doc.get_changes(&[]).len(), 2);
+
+    // try_commit says which precondition failed
+    let mut tx = doc
+        .transaction_at(automerge::PatchLog::inactive(), &heads)
+        .unwrap();
+    tx.expect_winner(ROOT, "status", Some(todo.clone()))
+        .unwrap();
+    tx.put(ROOT, "status", "done").unwrap();
+    let (result, _) = tx.try_commit();
+    assert!(matches!(
+        result,
+        Err(AutomergeError::PreconditionFailed(failed)) if failed.expected == Some(todo)
+    ));
+    assert_eq!(status(&doc), "doing");
+    assert_eq!(doc.get_changes(&[]).len(), 2);
+}
+
+#[test]
+fn precondition_errors() {
+    let (mut doc, todo) = doc_with_status();
+
+    let mut tx = doc.transaction();
+    tx.expect_winner(ROOT, "status", None).unwrap();
+    match tx.check_preconditions() {
+        Err(AutomergeError::PreconditionFailed(failed)) => {
+            assert_eq!(failed.expected, None);
+            assert_eq!(failed.actual, Some(todo.clone()));
+        }
+        other => panic!("unexpected result {:?}", other),
+    }
+    let list = tx.put_object(ROOT, "list", ObjType::List).unwrap();
+    assert!(tx.expect_winner(&list, 0, None).is_ok());
+    assert!(matches!(
+        tx.expect_winner(&todo, "key", None),
+        Err(AutomergeError::NotAnObject)
+    ));
+    tx.rollback();
+
+    // the owned transaction behaves the same way
+    let mut tx = doc.into_transaction(None, None).unwrap();
+    tx.expect_winner(ROOT, "status", None).unwrap();
+    tx.put(ROOT, "status", "done").unwrap();
+    let (doc, hash, _) = tx.commit();
+    assert_eq!(hash, None);
+    assert_eq!(status(&doc), "todo");
+    let mut tx = doc.into_transaction(None, None).unwrap();
+    tx.expect_winner(ROOT, "status", None).unwrap();
+    let (_, result, _) = tx.try_commit();
+    assert!(matches!(result, Err(AutomergeError::PreconditionFailed(_))));
+}
+
+#[test]
+fn autocommit_preconditions() {
+    let mut doc = AutoCommit::new().with_actor(ActorId::from([1]));
+    doc.put(ROOT, "status", "todo").unwrap();
+    doc.commit();
+    let todo = doc.get(ROOT, "status").unwrap().unwrap().1;
+
+    doc.expect_winner(ROOT, "status", Some(todo.clone()))
+        .unwrap();
+    doc.put(ROOT, "status", "done").unwrap();
+    assert!(doc.try_commit().unwrap().is_some());
+    let done = doc.get(ROOT, "status").unwrap().unwrap().1;
+
+    // a stale read fails and the operations are rolled back
+    doc.expect_winner(ROOT, "status", Some(todo)).unwrap();
+    doc.put(ROOT, "status", "doing").unwrap();
+    assert!(matches!(
+        doc.try_commit(),
+        Err(AutomergeError::PreconditionFailed(failed)) if failed.actual == Some(done.clone())
+    ));
+    assert_eq!(doc.get(ROOT, "status").unwrap().unwrap().1, done);
+
+    assert!(doc.take_failed_preconditions().is_empty());
+
+    // committing implicitly rolls back too, and keeps the failure
+    doc.expect_winner(ROOT, "status", None).unwrap();
+    doc.put(ROOT, "status", "doing").unwrap();
+    doc.save();
+    assert_eq!(doc.get(ROOT, "status").unwrap().unwrap().1, done);
+    assert_eq!(doc.get_changes(&[]).len(), 2);
+    doc.expect_winner(ROOT, "status", None).unwrap();
+    assert_eq!(doc.commit(), None);
+    assert_eq!(
+        doc.take_failed_preconditions(),
+        vec![
+            FailedPrecondition {
+                obj: ROOT,
+                prop: "status".into(),
+                expected: None,
+                actual: Some(done.clone()),
+            };
+            2
+        ]
+    );
+    assert!(doc.take_failed_preconditions().is_empty());
+
+    // and guarded writes are reported
+    let mut other = doc.fork().with_actor(ActorId::from([2]));
+    doc.expect_winner(ROOT, "status", Some(done)).unwrap();
+    doc.put(ROOT, "status", "archived").unwrap();
+    doc.commit();
+    other.put(ROOT, "status", "reopened").unwrap();
+    doc.merge(&mut other).unwrap();
+    assert_eq!(doc.take_precondition_conflicts().len(), 1);
+}
+
+#[test]
+fn concurrent_values_which_beat_a_guarded_write_are_reported() {
+    let (mut doc, todo) = doc_with_status();
+    let mut other = doc.fork().with_actor(ActorId::from([2]));
+    let mut loser = doc.fork().with_actor(ActorId::from([0]));
+
+    let mut tx = doc.transaction();
+    tx.expect_winner(ROOT, "status", Some(todo)).unwrap();
+    tx.put(ROOT, "status", "done").unwrap();
+    tx.commit();
+    let done = doc.get(ROOT, "status").unwrap().unwrap().1;
+
+    // a concurrent value which loses is not a conflict
+    let mut tx = loser.transaction();
+    tx.put(ROOT, "status", "blocked").unwrap();
+    tx.commit();
+    doc.merge(&mut loser).unwrap();
+    assert!(doc.take_precondition_conflicts().is_empty());
+
+    // but one which wins is, once
+    let mut tx = other.transaction();
+    tx.put(ROOT, "status", "won't do").unwrap();
+    tx.commit();
+    doc.merge(&mut other).unwrap();
+    let wont_do = doc.get(ROOT, "status").unwrap().unwrap().1;
+    assert_eq!(
+        doc.take_precondition_conflicts(),
+        vec![PreconditionConflict {
+            obj: ROOT,
+            prop: "status".into(),
+            write: done,
+            winner: wont_do,
+        }]
+    );
+    assert!(doc.take_precondition_conflicts().is_empty());
+    assert_eq!(doc.get_all(ROOT, "status").unwrap().len(), 3);
+}
+
+#[test]
+fn later_overwrites_of_a_guarded_write_are_not_reported() {
+    let (mut doc, todo) = doc_with_status();
+
+    let mut tx = doc.transaction();
+    tx.expect_winner(ROOT, "status", Some(todo)).unwrap();
+    tx.put(ROOT, "status", "done").unwrap();
+    tx.commit();
+
+    // a change which saw the guarded write overwrites it
+    let mut other = doc.fork().with_actor(ActorId::from([2]));
+    let mut tx = other.transaction();
+    tx.put(ROOT, "status", "reopened").unwrap();
+    tx.commit();
+    doc.merge(&mut other).unwrap();
+
+    assert_eq!(status(&doc), "reopened");
+    assert!(doc.take_precondition_conflicts().is_empty());
+}
+
+#[test]
+fn only_the_most_recent_guarded_writes_are_tracked() {
+    let count = Automerge::MAX_GUARDED_WRITES + 5;
+    let mut doc = Automerge::new().with_actor(ActorId::from([1]));
+    let mut other = doc.fork().with_actor(ActorId::from([2]));
+
+    for i in 0..count {
+        let mut tx = doc.transaction();
+        tx.expect_winner(ROOT, format!("k{}", i), None).unwrap();
+        tx.put(ROOT, format!("k{}", i), i as i64).unwrap();
+        tx.commit();
+    }
+    // the same keys with the same counters, which win because their actor is greater
+    let mut tx = other.transaction();
+    for i in 0..count {
+        tx.put(ROOT, format!("k{}", i), "other").unwrap();
+    }
+    tx.commit();
+    doc.merge(&mut other).unwrap();
+
+    let conflicts = doc.take_precondition_conflicts();
+    assert_eq!(conflicts.len(), Automerge::MAX_GUARDED_WRITES);
+    assert_eq!(conflicts[0].prop, "k5".into());
+}
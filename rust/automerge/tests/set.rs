@@ -0,0 +1,241 @@
+use automerge::{
+    hydrate_map, hydrate_set, transaction::Transactable, AutoCommit, Automerge, AutomergeError,
+    ObjId, ObjType, PatchAction, ReadDoc, ScalarValue, ROOT,
+};
+
+mod common;
+use common::{assert_diff_matches, doc_with, fork, merge_with_patches};
+
+/// A document with an empty set at `tags`
+fn doc_with_set() -> (AutoCommit, ObjId) {
+    doc_with("tags", ObjType::Set)
+}
+
+fn values(doc: &AutoCommit, set: &ObjId) -> Vec<ScalarValue> {
+    doc.set_values(set).collect()
+}
+
+#[test]
+fn add_and_remove_values() {
+    let (mut doc, tags) = doc_with_set();
+
+    doc.set_add(&tags, "a").unwrap();
+    doc.set_add(&tags, "b").unwrap();
+    doc.set_add(&tags, 1).unwrap();
+    doc.set_add(&tags, "a").unwrap();
+
+    assert!(doc.set_contains(&tags, &"a".into()).unwrap());
+    assert!(doc.set_contains(&tags, &1.into()).unwrap());
+    assert!(!doc.set_contains(&tags, &"c".into()).unwrap());
+    // values of different types are different members
+    assert!(!doc.set_contains(&tags, &1.0.into()).unwrap());
+    assert!(!doc.set_contains(&tags, &"1".into()).unwrap());
+    assert_eq!(doc.length(&tags), 3);
+
+    doc.set_remove(&tags, "a").unwrap();
+    doc.set_remove(&tags, "c").unwrap();
+
+    assert!(!doc.set_contains(&tags, &"a".into()).unwrap());
+    assert_eq!(values(&doc, &tags), vec![ScalarValue::Int(1), "b".into()]);
+    assert_eq!(
+        doc.hydrate(ROOT, None).unwrap(),
+        automerge::hydrate::Value::from(hydrate_map! {
+            "tags" => hydrate_set![1, "b"],
+        })
+    );
+}
+
+#[test]
+fn set_operations_on_other_objects_are_errors() {
+    let (mut doc, tags) = doc_with_set();
+    let map = doc.put_object(ROOT, "map", ObjType::Map).unwrap();
+
+    assert_eq!(
+        doc.set_add(&map, "a"),
+        Err(AutomergeError::InvalidOp(ObjType::Map))
+    );
+    assert_eq!(
+        doc.set_contains(&map, &"a".into()),
+        Err(AutomergeError::InvalidOp(ObjType::Map))
+    );
+    assert_eq!(doc.set_values(&map).count(), 0);
+    assert!(matches!(
+        doc.set_add(&tags, ScalarValue::counter(1)),
+        Err(AutomergeError::InvalidValueType { .. })
+    ));
+    assert_eq!(
+        doc.put(&tags, "a", 1),
+        Err(AutomergeError::InvalidOp(ObjType::Set))
+    );
+    assert_eq!(
+        doc.get(&tags, "a"),
+        Err(AutomergeError::InvalidOp(ObjType::Set))
+    );
+}
+
+#[test]
+fn concurrent_add_wins_over_remove() {
+    let (mut doc, tags) = doc_with_set();
+    doc.set_add(&tags, "a").unwrap();
+    let mut other = fork(&mut doc, 2);
+
+    doc.set_remove(&tags, "a").unwrap();
+    other.set_add(&tags, "a").unwrap();
+
+    let mut merged = doc.fork();
+    merge_with_patches(&mut merged, &mut other);
+    assert_eq!(values(&merged, &tags), vec![ScalarValue::from("a")]);
+
+    merge_with_patches(&mut other, &mut doc);
+    assert_eq!(values(&other, &tags), vec![ScalarValue::from("a")]);
+}
+
+#[test]
+fn remove_of_an_observed_add_wins() {
+    let (mut doc, tags) = doc_with_set();
+    doc.set_add(&tags, "a").unwrap();
+    let mut other = fork(&mut doc, 2);
+
+    doc.set_remove(&tags, "a").unwrap();
+    other.set_add(&tags, "b").unwrap();
+
+    merge_with_patches(&mut doc, &mut other);
+    assert_eq!(values(&doc, &tags), vec![ScalarValue::from("b")]);
+}
+
+#[test]
+fn concurrent_adds_of_a_value_are_one_member() {
+    let (mut doc, tags) = doc_with_set();
+    let mut other = fork(&mut doc, 2);
+
+    doc.set_add(&tags, "a").unwrap();
+    other.set_add(&tags, "a").unwrap();
+
+    merge_with_patches(&mut doc, &mut other);
+    assert_eq!(values(&doc, &tags), vec![ScalarValue::from("a")]);
+    assert_eq!(doc.length(&tags), 1);
+
+    // a remove which has seen both adds removes the value
+    doc.set_remove(&tags, "a").unwrap();
+    assert!(!doc.set_contains(&tags, &"a".into()).unwrap());
+}
+
+#[test]
+fn sets_round_trip_through_save_and_load() {
+    let (mut doc, tags) = doc_with_set();
+    let all = [
+        ScalarValue::Str("a".into()),
+        ScalarValue::Int(-1),
+        ScalarValue::Uint(2),
+        ScalarValue::F64(0.5),
+        ScalarValue::Bytes(vec![1, 2, 3]),
+        ScalarValue::Timestamp(10),
+        ScalarValue::Boolean(true),
+        ScalarValue::Null,
+    ];
+    for value in &all {
+        doc.set_add(&tags, value.clone()).unwrap();
+    }
+    doc.set_remove(&tags, 2_u64).unwrap();
+
+    let loaded = Automerge::load(&doc.save()).unwrap();
+    let (value, id) = loaded.get(ROOT, "tags").unwrap().unwrap();
+    assert_eq!(value, automerge::Value::Object(ObjType::Set));
+    assert_eq!(id, tags);
+    assert_eq!(loaded.set_values(&tags).count(), all.len() - 1);
+    for value in &all {
+        assert_eq!(
+            loaded.set_contains(&tags, value).unwrap(),
+            value != &ScalarValue::Uint(2)
+        );
+    }
+    assert_eq!(loaded.hydrate(None), doc.hydrate(ROOT, None).unwrap());
+
+    let mut incremental = Automerge::new();
+    incremental
+        .load_incremental(&doc.save_nocompress())
+        .unwrap();
+    assert_eq!(incremental.hydrate(None), doc.hydrate(ROOT, None).unwrap());
+}
+
+#[test]
+fn sets_sync_through_changes() {
+    let (mut doc, tags) = doc_with_set();
+    doc.set_add(&tags, "a").unwrap();
+    doc.set_add(&tags, 1).unwrap();
+    doc.set_remove(&tags, "a").unwrap();
+
+    let mut other = Automerge::new();
+    other.apply_changes(doc.get_changes(&[])).unwrap();
+    assert_eq!(
+        other.set_values(&tags).collect::<Vec<_>>(),
+        vec![ScalarValue::Int(1)]
+    );
+}
+
+#[test]
+fn read_a_set_at_old_heads() {
+    let (mut doc, tags) = doc_with_set();
+    doc.set_add(&tags, "a").unwrap();
+    let heads = doc.get_heads();
+    doc.set_remove(&tags, "a").unwrap();
+    doc.set_add(&tags, "b").unwrap();
+
+    assert!(doc.set_contains_at(&tags, &"a".into(), &heads).unwrap());
+    assert!(!doc.set_contains_at(&tags, &"b".into(), &heads).unwrap());
+    assert_eq!(
+        doc.set_values_at(&tags, &heads).collect::<Vec<_>>(),
+        vec![ScalarValue::from("a")]
+    );
+
+    assert_diff_matches(&mut doc, &heads);
+}
+
+#[test]
+fn set_patches() {
+    let (mut doc, tags) = doc_with_set();
+    doc.update_diff_cursor();
+
+    doc.set_add(&tags, "a").unwrap();
+    doc.set_add(&tags, 1).unwrap();
+    doc.set_remove(&tags, "a").unwrap();
+
+    let actions = doc
+        .diff_incremental()
+        .into_iter()
+        .map(|p| {
+            assert_eq!(p.obj, tags);
+            p.action
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        actions,
+        vec![
+            PatchAction::SetAdd { value: "a".into() },
+            PatchAction::SetAdd { value: 1.into() },
+            PatchAction::SetRemove { value: "a".into() },
+        ]
+    );
+}
+
+#[test]
+fn update_object_with_a_hydrated_set() {
+    let (mut doc, tags) = doc_with_set();
+    doc.set_add(&tags, "a").unwrap();
+    doc.set_add(&tags, "b").unwrap();
+
+    doc.update_object(&tags, &hydrate_set!["b", "c"].into())
+        .unwrap();
+    assert_eq!(
+        values(&doc, &tags),
+        vec![ScalarValue::from("b"), ScalarValue::from("c")]
+    );
+
+    let new = doc.put_object(ROOT, "new", ObjType::Map).unwrap();
+    doc.update_object(&new, &hydrate_map! { "set" => hydrate_set![true] }.into())
+        .unwrap();
+    assert_eq!(
+        doc.hydrate(&new, None).unwrap(),
+        automerge::hydrate::Value::from(hydrate_map! { "set" => hydrate_set![true] })
+    );
+}
@@ -0,0 +1,285 @@
+use automerge::sync::{State, SyncDoc};
+use automerge::transaction::{CommitOptions, Transactable};
+use automerge::{ActorId, AutoCommit, Automerge, AutomergeError, ObjType, ReadDoc, ROOT};
+
+/// A document edited by two actors with a text object, returns the doc and the heads in the
+/// middle of its history
+fn doc_with_history() -> (AutoCommit, Vec<automerge::ChangeHash>) {
+    let mut doc = AutoCommit::new().with_actor(ActorId::from(b"aaaa"));
+    let text = doc.put_object(&ROOT, "text", ObjType::Text).unwrap();
+    doc.splice_text(&text, 0, 0, "hello").unwrap();
+    doc.put(&ROOT, "count", 1).unwrap();
+    doc.commit();
+
+    let mut other = doc.fork().with_actor(ActorId::from(b"bbbb"));
+    other.splice_text(&text, 5, 0, " world").unwrap();
+    other.commit();
+    doc.put(&ROOT, "count", 2).unwrap();
+    doc.commit();
+    doc.merge(&mut other).unwrap();
+
+    let base = doc.get_heads();
+
+    doc.splice_text(&text, 0, 1, "H").unwrap();
+    doc.put(&ROOT, "count", 3).unwrap();
+    doc.commit();
+    (doc, base)
+}
+
+fn sync(a: &mut Automerge, b: &mut Automerge) -> Result<(), AutomergeError> {
+    let mut a_state = State::new();
+    let mut b_state = State::new();
+    for _ in 0..10 {
+        let a_msg = a.generate_sync_message(&mut a_state);
+        if let Some(msg) = &a_msg {
+            b.receive_sync_message(&mut b_state, msg.clone())?;
+        }
+        let b_msg = b.generate_sync_message(&mut b_state);
+        if let Some(msg) = &b_msg {
+            a.receive_sync_message(&mut a_state, msg.clone())?;
+        }
+        if a_msg.is_none() && b_msg.is_none() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn shallow_doc_keeps_state_and_drops_history() {
+    let (mut doc, base) = doc_with_history();
+    let mut shallow = doc.shallow(&base).unwrap();
+
+    assert_eq!(shallow.shallow_base(), Some(base.as_slice()));
+    assert_eq!(doc.shallow_base(), None);
+    assert_eq!(shallow.get_heads(), doc.get_heads());
+    assert_eq!(
+        shallow.hydrate(&ROOT, None).unwrap(),
+        doc.hydrate(&ROOT, None).unwrap()
+    );
+
+    // only the change after the base is left
+    assert_eq!(shallow.get_changes(&[]).len(), 1);
+    for hash in &base {
+        assert!(shallow.get_change_by_hash(hash).is_none());
+    }
+    assert!(matches!(
+        shallow.fork_at(&base),
+        Err(AutomergeError::TruncatedHistory(_))
+    ));
+
+    let saved = shallow.save();
+    let mut loaded = AutoCommit::load(&saved).unwrap();
+    assert_eq!(loaded.shallow_base(), Some(base.as_slice()));
+    assert_eq!(loaded.get_heads(), doc.get_heads());
+    assert_eq!(
+        loaded.hydrate(&ROOT, None).unwrap(),
+        doc.hydrate(&ROOT, None).unwrap()
+    );
+    assert_eq!(loaded.save(), saved);
+}
+
+#[test]
+fn shallow_doc_continues_editing_and_merges_with_full_peer() {
+    let (mut doc, base) = doc_with_history();
+    let mut shallow = doc
+        .shallow(&base)
+        .unwrap()
+        .with_actor(ActorId::from(b"cccc"));
+    let mut loaded = AutoCommit::load(&shallow.save()).unwrap();
+
+    shallow.put(&ROOT, "count", 4).unwrap();
+    shallow.commit();
+    loaded.put(&ROOT, "other", "x").unwrap();
+    loaded.commit();
+    doc.put(&ROOT, "full", true).unwrap();
+    doc.commit();
+
+    // the full peer merges changes from the shallow ones
+    doc.merge(&mut shallow).unwrap();
+    doc.merge(&mut loaded).unwrap();
+
+    // the shallow peers ignore the truncated history of the full peer
+    shallow.merge(&mut doc).unwrap();
+    loaded.merge(&mut shallow).unwrap();
+
+    let expected = doc.hydrate(&ROOT, None).unwrap();
+    assert_eq!(shallow.hydrate(&ROOT, None).unwrap(), expected);
+    assert_eq!(loaded.hydrate(&ROOT, None).unwrap(), expected);
+    assert_eq!(shallow.get_heads(), doc.get_heads());
+    assert_eq!(shallow.shallow_base(), Some(base.as_slice()));
+
+    // saves of the shallow doc can be loaded into the full doc
+    let mut full = doc.fork();
+    full.load_incremental(&shallow.save()).unwrap();
+    assert_eq!(full.get_heads(), doc.get_heads());
+}
+
+#[test]
+fn merging_shallow_doc_into_doc_without_base_fails() {
+    let (mut doc, base) = doc_with_history();
+    let mut shallow = doc.shallow(&base).unwrap();
+
+    let mut unrelated = AutoCommit::new();
+    unrelated.put(&ROOT, "key", "value").unwrap();
+    assert!(matches!(
+        unrelated.merge(&mut shallow),
+        Err(AutomergeError::TruncatedHistory(_))
+    ));
+
+    // a concurrent change on top of the truncated history can't be applied to the shallow doc
+    let mut old = doc.fork_at(&base).unwrap();
+    let first = doc.get_changes(&[])[0].hash();
+    let mut concurrent = doc.fork_at(&[first]).unwrap();
+    concurrent.put(&ROOT, "concurrent", 1).unwrap();
+    concurrent.commit();
+    old.merge(&mut concurrent).unwrap();
+    assert!(matches!(
+        shallow.merge(&mut old),
+        Err(AutomergeError::TruncatedHistory(_))
+    ));
+}
+
+#[test]
+fn shallow_doc_syncs_with_peers_which_have_the_base() {
+    let (mut doc, base) = doc_with_history();
+    let mut full = doc.document().clone();
+    let mut shallow = doc
+        .shallow(&base)
+        .unwrap()
+        .with_actor(ActorId::from(b"cccc"))
+        .document()
+        .clone();
+
+    shallow
+        .transact::<_, _, AutomergeError>(|tx| {
+            tx.put(ROOT, "shallow", 1)?;
+            Ok(())
+        })
+        .unwrap();
+    full.transact::<_, _, AutomergeError>(|tx| {
+        tx.put(ROOT, "full", 1)?;
+        Ok(())
+    })
+    .unwrap();
+
+    sync(&mut shallow, &mut full).unwrap();
+    assert_eq!(shallow.get_heads(), full.get_heads());
+    assert_eq!(shallow.hydrate(None), full.hydrate(None));
+
+    // an empty peer is sent the whole shallow document
+    let mut empty = Automerge::new();
+    sync(&mut shallow, &mut empty).unwrap();
+    assert_eq!(empty.get_heads(), shallow.get_heads());
+    assert_eq!(empty.shallow_base(), Some(base.as_slice()));
+    assert_eq!(empty.hydrate(None), shallow.hydrate(None));
+}
+
+#[test]
+fn syncing_with_peer_which_needs_truncated_history_fails() {
+    let (mut doc, base) = doc_with_history();
+    let mut shallow = doc.shallow(&base).unwrap().document().clone();
+
+    let first = doc.get_changes(&[])[0].hash();
+    let mut old = doc.fork_at(&[first]).unwrap().document().clone();
+
+    assert!(matches!(
+        sync(&mut shallow, &mut old),
+        Err(AutomergeError::TruncatedHistory(_))
+    ));
+}
+
+#[test]
+fn a_sync_message_which_fails_on_truncated_history_changes_nothing() {
+    let (mut doc, base) = doc_with_history();
+    let mut shallow = doc.shallow(&base).unwrap().document().clone();
+
+    let first = doc.get_changes(&[])[0].hash();
+    let mut old = doc.fork_at(&[first]).unwrap();
+    old.put(&ROOT, "old", 1).unwrap();
+    old.commit();
+    let mut old = old.document().clone();
+
+    let mut shallow_state = State::new();
+    let mut old_state = State::new();
+    let mut failed = false;
+    for _ in 0..10 {
+        if let Some(msg) = shallow.generate_sync_message(&mut shallow_state) {
+            old.receive_sync_message(&mut old_state, msg).unwrap();
+        }
+        let Some(msg) = old.generate_sync_message(&mut old_state) else {
+            break;
+        };
+        let heads = shallow.get_heads();
+        let state = shallow_state.clone();
+        if let Err(e) = shallow.receive_sync_message(&mut shallow_state, msg) {
+            assert!(matches!(e, AutomergeError::TruncatedHistory(_)));
+            assert_eq!(shallow.get_heads(), heads);
+            assert_eq!(shallow_state, state);
+            failed = true;
+            break;
+        }
+    }
+    assert!(failed);
+    assert_eq!(shallow.get(ROOT, "old").unwrap(), None);
+}
+
+#[test]
+fn shallow_docs_can_be_truncated_further() {
+    let (mut doc, base) = doc_with_history();
+    let mut shallow = doc.shallow(&base).unwrap();
+    shallow.put(&ROOT, "count", 4).unwrap();
+    shallow.commit();
+
+    let later = doc.get_heads();
+    let mut shallower = shallow.shallow(&later).unwrap();
+    assert_eq!(shallower.shallow_base(), Some(later.as_slice()));
+    assert_eq!(shallower.get_changes(&[]).len(), 1);
+    assert_eq!(
+        shallower.hydrate(&ROOT, None).unwrap(),
+        shallow.hydrate(&ROOT, None).unwrap()
+    );
+    let mut loaded = AutoCommit::load(&shallower.save()).unwrap();
+    assert_eq!(loaded.get_heads(), shallow.get_heads());
+
+    // the base can't move backwards
+    let first = doc.get_changes(&[])[0].hash();
+    assert!(shallower.shallow(&[first]).is_err());
+}
+
+#[test]
+fn shallow_doc_with_many_actors() {
+    let mut doc = AutoCommit::new();
+    let mut actors = Vec::new();
+    for i in 0..50 {
+        let mut peer = doc.fork();
+        actors.push(peer.get_actor().clone());
+        peer.put(&ROOT, "count", i).unwrap();
+        peer.commit_with(CommitOptions::default().with_message(format!("change {}", i)));
+        doc.merge(&mut peer).unwrap();
+    }
+    let heads = doc.get_heads();
+
+    let mut shallow = doc.shallow(&heads).unwrap();
+    assert_eq!(shallow.get_changes(&[]).len(), 0);
+    let mut loaded = AutoCommit::load(&shallow.save()).unwrap();
+    assert_eq!(loaded.get_heads(), heads);
+    assert_eq!(
+        loaded.hydrate(&ROOT, None).unwrap(),
+        doc.hydrate(&ROOT, None).unwrap()
+    );
+
+    // every truncated actor can keep editing
+    for actor in actors {
+        let mut peer = loaded.fork().with_actor(actor);
+        peer.put(&ROOT, "count", 100).unwrap();
+        peer.commit();
+        doc.merge(&mut peer).unwrap();
+        loaded.merge(&mut peer).unwrap();
+    }
+    assert_eq!(loaded.get_heads(), doc.get_heads());
+    assert_eq!(
+        loaded.hydrate(&ROOT, None).unwrap(),
+        doc.hydrate(&ROOT, None).unwrap()
+    );
+}
@@ -3119,6 +3119,71 @@ fn stats_smoke_test() {
     assert_eq!(stats.num_ops, 2);
 }
 
+#[test]
+fn detailed_stats_breaks_down_objects_actors_and_columns() {
+    let mut doc = AutoCommit::new().with_actor(ActorId::from(b"aaaa"));
+    let text = doc
+        .put_object(&automerge::ROOT, "text", ObjType::Text)
+        .unwrap();
+    doc.splice_text(&text, 0, 0, "hello world").unwrap();
+    doc.mark(
+        &text,
+        Mark::new("bold".to_string(), true, 0, 5),
+        ExpandMark::After,
+    )
+    .unwrap();
+    doc.put(&automerge::ROOT, "key", "one").unwrap();
+    doc.commit();
+    let mut other = doc.fork().with_actor(ActorId::from(b"bbbb"));
+    other.splice_text(&text, 5, 6, "").unwrap();
+    other.put(&automerge::ROOT, "key", "two").unwrap();
+    other.commit();
+    doc.merge(&mut other).unwrap();
+
+    let stats = doc.detailed_stats();
+    assert_eq!(stats.stats, doc.stats());
+
+    let largest = &stats.largest_objects(1)[0];
+    assert_eq!(largest.obj, text);
+    assert_eq!(largest.obj_type, ObjType::Text);
+    assert_eq!(largest.num_ops, 13);
+    assert_eq!(largest.live_ops, 5);
+    assert_eq!(largest.tombstones, 6);
+    assert_eq!(largest.marks, 1);
+    assert_eq!(largest.length, 5);
+    let root = &stats.objects[1];
+    assert_eq!(root.obj, automerge::ROOT);
+    assert_eq!((root.num_ops, root.live_ops, root.tombstones), (3, 2, 1));
+    assert_eq!(root.length, 2);
+
+    assert_eq!(stats.actors.len(), 2);
+    assert_eq!(stats.actors[0].actor, ActorId::from(b"aaaa"));
+    assert_eq!(stats.actors[0].num_changes, 1);
+    assert_eq!(stats.actors[0].num_ops, 15);
+    assert_eq!(stats.actors[1].num_ops, 1);
+    assert_eq!(stats.changes.len(), 2);
+    assert_eq!(stats.changes[1].num_ops, 7);
+    assert_eq!(
+        stats.change_bytes(),
+        stats.actors.iter().map(|a| a.change_bytes).sum::<usize>()
+    );
+    assert_eq!(
+        stats.changes[0].bytes,
+        doc.get_changes(&[])[0].raw_bytes().len()
+    );
+    // loaded and shallow documents know the sizes of their changes too
+    let loaded = AutoCommit::load(&doc.save()).unwrap();
+    assert_eq!(loaded.detailed_stats().changes, stats.changes);
+    let shallow = doc.shallow(&[stats.changes[0].hash]).unwrap();
+    assert_eq!(shallow.detailed_stats().changes, stats.changes[1..]);
+
+    assert!(stats
+        .columns
+        .iter()
+        .any(|c| c.name == "value" && c.bytes >= 11));
+    assert!(stats.column_bytes() > 0);
+}
+
 #[test]
 fn invalid_index() {
     let mut doc = AutoCommit::new();
@@ -4414,3 +4479,40 @@ fn patches_expose_surviving_conflict_after_deleting_other_branch_from_fuzz_trace
         .unwrap();
     assert_eq!(actual, expected);
 }
+
+#[test]
+fn save_and_load_with_each_compression_codec() {
+    use automerge::{Codec, ColumnGroup, Compression, SaveOptions};
+
+    let mut doc = AutoCommit::new();
+    let text = doc.put_object(&ROOT, "text", ObjType::Text).unwrap();
+    for i in 0..500 {
+        doc.splice_text(&text, i, 0, "a").unwrap();
+    }
+    doc.put(&ROOT, "key", "value").unwrap();
+    let expected = doc.hydrate(&ROOT, None).unwrap();
+
+    let uncompressed = doc.save_nocompress();
+    for compression in [
+        Compression::none(),
+        Compression::deflate(),
+        Compression::deflate().level(9),
+        Compression::zstd(),
+        Compression::zstd().min_size(0),
+        Compression::zstd().column_min_size(ColumnGroup::Ops, 1, usize::MAX),
+    ] {
+        let codec = compression.codec();
+        let bytes = doc.save_with_options(SaveOptions {
+            compression,
+            ..Default::default()
+        });
+        if codec == Codec::None {
+            assert_eq!(bytes, uncompressed);
+        } else {
+            assert!(bytes.len() < uncompressed.len());
+        }
+        let mut loaded = AutoCommit::load(&bytes).unwrap();
+        assert_eq!(loaded.hydrate(&ROOT, None).unwrap(), expected);
+        assert_eq!(loaded.get_heads(), doc.get_heads());
+    }
+}
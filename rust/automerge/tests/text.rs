@@ -4,7 +4,7 @@ use automerge::{
     hydrate_list, hydrate_map,
     iter::Span,
     marks::{ExpandMark, Mark, UpdateSpansConfig},
-    transaction::Transactable,
+    transaction::{TextDiffGranularity, Transactable, UpdateTextConfig},
     ActorId, AutoCommit, ConcreteTextValue, ObjType, Patch, PatchAction, ReadDoc, ScalarValue,
     TextEncoding, ROOT,
 };
@@ -1020,3 +1020,224 @@ fn splicing_into_multibyte_characters() {
     // deleting in the middle of a multi-byte character will delete after
     assert_eq!(doc3.text(&text).unwrap(), "ABBBBBC");
 }
+
+#[test]
+fn grapheme_range_snaps_to_grapheme_boundaries() {
+    // a thumbs up with a skin tone modifier is two code points and four UTF-16 code units
+    let mut doc = AutoCommit::new_with_encoding(TextEncoding::UnicodeCodePoint);
+    let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+    doc.splice_text(&text, 0, 0, "a👍🏽b").unwrap();
+    assert_eq!(doc.grapheme_range(&text, 2..2, None).unwrap(), 1..3);
+    assert_eq!(doc.grapheme_range(&text, 0..2, None).unwrap(), 0..3);
+    assert_eq!(doc.grapheme_range(&text, 1..3, None).unwrap(), 1..3);
+    assert_eq!(doc.grapheme_range(&text, 3..4, None).unwrap(), 3..4);
+    assert_eq!(doc.grapheme_range(&text, 4..4, None).unwrap(), 4..4);
+    assert!(doc.grapheme_range(&text, 2..5, None).is_err());
+
+    let heads = doc.get_heads();
+    doc.splice_text(&text, 0, 1, "").unwrap();
+    assert_eq!(doc.grapheme_range(&text, 1..1, None).unwrap(), 0..2);
+    assert_eq!(doc.grapheme_range(&text, 2..2, Some(&heads)).unwrap(), 1..3);
+
+    let mut doc = AutoCommit::new_with_encoding(TextEncoding::Utf16CodeUnit);
+    let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+    doc.splice_text(&text, 0, 0, "a👍🏽b").unwrap();
+    assert_eq!(doc.grapheme_range(&text, 2..3, None).unwrap(), 1..5);
+    assert_eq!(doc.grapheme_range(&text, 5..6, None).unwrap(), 5..6);
+}
+
+#[test]
+fn splice_graphemes_does_not_split_graphemes() {
+    let mut doc = AutoCommit::new_with_encoding(TextEncoding::Utf16CodeUnit);
+    let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+    doc.splice_text(&text, 0, 0, "a👍🏽b").unwrap();
+
+    // deleting half of a surrogate pair deletes the whole grapheme
+    assert_eq!(doc.splice_graphemes(&text, 2, 1, "").unwrap(), 1..5);
+    assert_eq!(doc.text(&text).unwrap(), "ab");
+
+    // inserting inside a grapheme inserts after it
+    doc.splice_text(&text, 1, 0, "🇳🇿").unwrap();
+    assert_eq!(doc.splice_graphemes(&text, 2, 0, "!").unwrap(), 5..5);
+    assert_eq!(doc.text(&text).unwrap(), "a🇳🇿!b");
+
+    // a negative delete counts backwards from `pos`
+    assert_eq!(doc.splice_graphemes(&text, 4, -2, "x").unwrap(), 1..5);
+    assert_eq!(doc.text(&text).unwrap(), "ax!b");
+    assert!(doc.splice_graphemes(&text, 1, -2, "").is_err());
+}
+
+fn update_text_patches(old: &str, new: &str, granularity: TextDiffGranularity) -> Vec<PatchAction> {
+    update_text_patches_with(
+        old,
+        new,
+        UpdateTextConfig::default().with_granularity(granularity),
+    )
+}
+
+fn update_text_patches_with(old: &str, new: &str, config: UpdateTextConfig) -> Vec<PatchAction> {
+    let mut doc = AutoCommit::new();
+    let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+    doc.splice_text(&text, 0, 0, old).unwrap();
+    doc.update_diff_cursor();
+    doc.update_text_with(&text, new, config).unwrap();
+    assert_eq!(doc.text(&text).unwrap(), new);
+    doc.diff_incremental()
+        .into_iter()
+        .map(|patch| patch.action)
+        .collect()
+}
+
+fn splice(index: usize, text: &str) -> PatchAction {
+    PatchAction::SpliceText {
+        index,
+        value: ConcreteTextValue::new(text, TextEncoding::platform_default()),
+        marks: None,
+    }
+}
+
+#[test]
+fn update_text_by_word_and_line() {
+    let old = "the quick fox\njumps over\nthe dog";
+    let new = "the quack fox\njumps over\nthe lazy dog";
+    assert_eq!(
+        update_text_patches(old, new, TextDiffGranularity::Grapheme),
+        vec![
+            splice(6, "a"),
+            PatchAction::DeleteSeq {
+                index: 7,
+                length: 1
+            },
+            splice(28, " lazy"),
+        ]
+    );
+    // changed words and lines are replaced as a whole
+    assert_eq!(
+        update_text_patches(old, new, TextDiffGranularity::Word),
+        vec![
+            splice(4, "quack"),
+            PatchAction::DeleteSeq {
+                index: 9,
+                length: 5
+            },
+            splice(28, " lazy"),
+        ]
+    );
+    assert_eq!(
+        update_text_patches(old, new, TextDiffGranularity::Line),
+        vec![
+            splice(0, "the quack fox\n"),
+            PatchAction::DeleteSeq {
+                index: 14,
+                length: 14
+            },
+            splice(25, "the lazy dog"),
+            PatchAction::DeleteSeq {
+                index: 37,
+                length: 7
+            },
+        ]
+    );
+}
+
+#[test]
+fn concurrent_word_updates_do_not_interleave() {
+    let mut doc1 = AutoCommit::new().with_actor(ActorId::from(b"aaaa"));
+    let text = doc1.put_object(ROOT, "text", ObjType::Text).unwrap();
+    doc1.splice_text(&text, 0, 0, "a colour").unwrap();
+    let mut doc2 = doc1.fork().with_actor(ActorId::from(b"bbbb"));
+
+    let by_word = UpdateTextConfig::default().with_granularity(TextDiffGranularity::Word);
+    doc1.update_text_with(&text, "a color", by_word.clone())
+        .unwrap();
+    doc2.update_text_with(&text, "a hue", by_word).unwrap();
+    doc1.merge(&mut doc2).unwrap();
+
+    // both edits replace the whole word, so the result contains each new word intact
+    let merged = doc1.text(&text).unwrap();
+    assert!(
+        merged == "a colorhue" || merged == "a huecolor",
+        "{}",
+        merged
+    );
+}
+
+#[test]
+fn update_text_refines_changed_lines() {
+    let old = "the quick fox\njumps over\nthe dog";
+    let new = "the quack fox\njumps over\nthe lazy dog";
+    // lines are matched first and the changed lines are then diffed by grapheme
+    let by_line = UpdateTextConfig::default()
+        .with_granularity(TextDiffGranularity::Line)
+        .with_refine(true);
+    assert_eq!(
+        update_text_patches_with(old, new, by_line),
+        vec![
+            splice(6, "a"),
+            PatchAction::DeleteSeq {
+                index: 7,
+                length: 1
+            },
+            splice(29, "lazy "),
+        ]
+    );
+}
+
+#[test]
+fn update_text_falls_back_when_out_of_time() {
+    let old = "abcdefghij klmnopqrst uvwxyz";
+    let new = "zyxwvu tsrqponmlk jihgfedcba";
+    for granularity in [
+        TextDiffGranularity::Grapheme,
+        TextDiffGranularity::Word,
+        TextDiffGranularity::Line,
+    ] {
+        let config = UpdateTextConfig::default()
+            .with_granularity(granularity)
+            .with_refine(true)
+            .with_timeout(std::time::Duration::ZERO);
+        // a diff which runs out of time is less minimal but still produces the new text
+        assert!(!update_text_patches_with(old, new, config).is_empty());
+    }
+}
+
+#[test]
+fn update_spans_by_word() {
+    let mut doc = AutoCommit::new_with_encoding(TextEncoding::UnicodeCodePoint);
+    let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+    doc.splice_text(&text, 0, 0, "a colour").unwrap();
+    doc.update_diff_cursor();
+
+    let config = UpdateSpansConfig::default()
+        .with_text_diff(UpdateTextConfig::default().with_granularity(TextDiffGranularity::Word));
+    doc.update_spans(
+        &text,
+        config,
+        [Span::Text {
+            text: "a color".into(),
+            marks: Default::default(),
+        }],
+    )
+    .unwrap();
+    assert_eq!(doc.text(&text).unwrap(), "a color");
+    let patches = doc
+        .diff_incremental()
+        .into_iter()
+        .map(|patch| patch.action)
+        .collect::<Vec<_>>();
+    // the changed word is replaced as a whole
+    assert_eq!(
+        patches,
+        vec![
+            PatchAction::SpliceText {
+                index: 2,
+                value: ConcreteTextValue::new("color", TextEncoding::UnicodeCodePoint),
+                marks: None,
+            },
+            PatchAction::DeleteSeq {
+                index: 7,
+                length: 6
+            },
+        ]
+    );
+}
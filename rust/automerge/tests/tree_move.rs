@@ -0,0 +1,370 @@
+use automerge::{
+    hydrate_list, hydrate_map, transaction::Transactable, ActorId, AutoCommit, AutomergeError,
+    ObjId, ObjType, PatchAction, Prop, ReadDoc, TextEncoding, ROOT,
+};
+
+mod common;
+use common::{assert_diff_matches, fork, merge_with_patches};
+
+fn path(doc: &AutoCommit, obj: &ObjId) -> Vec<Prop> {
+    doc.parents(obj)
+        .unwrap()
+        .path()
+        .into_iter()
+        .map(|(_, prop)| prop)
+        .collect()
+}
+
+/// A document with two maps, `a` and `b`, in the root and a map `x` with some content in `a`
+fn doc_with_tree() -> (AutoCommit, ObjId, ObjId, ObjId) {
+    let mut doc = AutoCommit::new().with_actor(ActorId::from([1]));
+    let a = doc.put_object(ROOT, "a", ObjType::Map).unwrap();
+    let b = doc.put_object(ROOT, "b", ObjType::Map).unwrap();
+    let x = doc.put_object(&a, "x", ObjType::Map).unwrap();
+    doc.put(&x, "name", "x").unwrap();
+    let list = doc.put_object(&x, "list", ObjType::List).unwrap();
+    doc.insert(&list, 0, 1).unwrap();
+    (doc, a, b, x)
+}
+
+#[test]
+fn move_a_map_into_another_map() {
+    let (mut doc, a, b, x) = doc_with_tree();
+
+    doc.move_object(&x, &b, "y").unwrap();
+
+    assert_eq!(doc.get(&a, "x").unwrap(), None);
+    assert_eq!(doc.length(&a), 0);
+    let (value, id) = doc.get(&b, "y").unwrap().unwrap();
+    assert_eq!(id, x);
+    assert_eq!(value, automerge::Value::Object(ObjType::Map));
+    assert_eq!(doc.get(&x, "name").unwrap().unwrap().0.to_str(), Some("x"));
+    assert_eq!(path(&doc, &x), vec![Prop::from("b"), Prop::from("y")]);
+    assert_eq!(doc.keys(&b).collect::<Vec<_>>(), vec!["y"]);
+    assert_eq!(
+        doc.hydrate(ROOT, None).unwrap(),
+        automerge::hydrate::Value::from(hydrate_map! {
+            "a" => hydrate_map! {},
+            "b" => hydrate_map! {
+                "y" => hydrate_map! {
+                    "name" => "x",
+                    "list" => hydrate_list![1],
+                },
+            },
+        })
+    );
+
+    // the moved object can be edited and moved again
+    doc.put(&x, "more", true).unwrap();
+    doc.move_object(&x, ROOT, "x").unwrap();
+    assert_eq!(doc.get(&b, "y").unwrap(), None);
+    assert_eq!(doc.get(ROOT, "x").unwrap().unwrap().1, x);
+    assert_eq!(path(&doc, &x), vec![Prop::from("x")]);
+    assert_eq!(doc.length(&x), 3);
+}
+
+#[test]
+fn move_objects_between_maps_and_lists() {
+    let (mut doc, a, _, x) = doc_with_tree();
+    let list = doc.put_object(ROOT, "items", ObjType::List).unwrap();
+    doc.insert(&list, 0, "first").unwrap();
+    doc.insert(&list, 1, "last").unwrap();
+
+    doc.move_object(&x, &list, 1).unwrap();
+    assert_eq!(doc.length(&list), 3);
+    assert_eq!(doc.get(&list, 1).unwrap().unwrap().1, x);
+    assert_eq!(doc.get(&list, 2).unwrap().unwrap().0.to_str(), Some("last"));
+    assert_eq!(path(&doc, &x), vec![Prop::from("items"), Prop::from(1)]);
+    assert_eq!(doc.get(&a, "x").unwrap(), None);
+
+    // edits to the list around the moved object
+    doc.insert(&list, 0, "new").unwrap();
+    assert_eq!(path(&doc, &x), vec![Prop::from("items"), Prop::from(2)]);
+    doc.delete(&list, 2).unwrap();
+    assert_eq!(doc.length(&list), 3);
+    assert_eq!(doc.get(&x, "name").unwrap().unwrap().0.to_str(), Some("x"));
+    assert!(doc.parents(&x).unwrap().visible_path().is_none());
+
+    let inner = doc.insert_object(&list, 0, ObjType::List).unwrap();
+    doc.move_object(&inner, &a, "inner").unwrap();
+    assert_eq!(doc.get(&a, "inner").unwrap().unwrap().1, inner);
+    assert_eq!(doc.length(&list), 3);
+}
+
+#[test]
+fn invalid_moves_are_rejected() {
+    let (mut doc, a, b, x) = doc_with_tree();
+    let text = doc.put_object(ROOT, "text", ObjType::Text).unwrap();
+    let list = doc.put_object(&b, "list", ObjType::List).unwrap();
+
+    assert!(matches!(
+        doc.move_object(&a, &x, "a"),
+        Err(AutomergeError::MoveIntoDescendant)
+    ));
+    assert!(matches!(
+        doc.move_object(&a, &a, "a"),
+        Err(AutomergeError::MoveIntoDescendant)
+    ));
+    assert!(matches!(
+        doc.move_object(&x, &text, 0),
+        Err(AutomergeError::InvalidOp(ObjType::Text))
+    ));
+    assert!(matches!(
+        doc.move_object(&x, &list, "key"),
+        Err(AutomergeError::InvalidOp(ObjType::List))
+    ));
+    assert!(matches!(
+        doc.move_object(&x, &b, 0),
+        Err(AutomergeError::InvalidOp(ObjType::Map))
+    ));
+    assert!(matches!(
+        doc.move_object(&x, &list, 1),
+        Err(AutomergeError::InvalidIndex(1))
+    ));
+    assert_eq!(doc.get(&a, "x").unwrap().unwrap().1, x);
+
+    // once moved the descendants change
+    doc.move_object(&x, &b, "x").unwrap();
+    doc.move_object(&a, &x, "a").unwrap();
+    assert!(matches!(
+        doc.move_object(&b, &a, "b"),
+        Err(AutomergeError::MoveIntoDescendant)
+    ));
+    assert_eq!(
+        path(&doc, &a),
+        vec![Prop::from("b"), Prop::from("x"), Prop::from("a")]
+    );
+}
+
+#[test]
+fn concurrent_moves_of_one_object_converge() {
+    let (mut doc1, a, b, x) = doc_with_tree();
+    let mut doc2 = fork(&mut doc1, 2);
+
+    doc1.move_object(&x, &b, "from1").unwrap();
+    doc2.move_object(&x, ROOT, "from2").unwrap();
+
+    doc1.merge(&mut doc2).unwrap();
+    doc2.merge(&mut doc1).unwrap();
+
+    assert_eq!(
+        doc1.hydrate(ROOT, None).unwrap(),
+        doc2.hydrate(ROOT, None).unwrap()
+    );
+    // the move by the greater actor wins and the object only appears once
+    assert_eq!(doc1.get(ROOT, "from2").unwrap().unwrap().1, x);
+    assert_eq!(doc1.get(&b, "from1").unwrap(), None);
+    assert_eq!(doc1.get(&a, "x").unwrap(), None);
+}
+
+#[test]
+fn concurrent_moves_into_each_other_do_not_make_a_cycle() {
+    let (mut doc1, a, b, _) = doc_with_tree();
+    let mut doc2 = fork(&mut doc1, 2);
+
+    doc1.move_object(&a, &b, "a").unwrap();
+    doc2.move_object(&b, &a, "b").unwrap();
+
+    doc1.merge(&mut doc2).unwrap();
+    doc2.merge(&mut doc1).unwrap();
+
+    assert_eq!(
+        doc1.hydrate(ROOT, None).unwrap(),
+        doc2.hydrate(ROOT, None).unwrap()
+    );
+    // the first move is applied and the second, which would make a cycle, is skipped
+    assert_eq!(path(&doc1, &a), vec![Prop::from("b"), Prop::from("a")]);
+    assert_eq!(path(&doc1, &b), vec![Prop::from("b")]);
+    assert_eq!(doc1.get(&a, "b").unwrap(), None);
+}
+
+#[test]
+fn concurrent_edits_of_a_moved_object_are_kept() {
+    let (mut doc1, _, b, x) = doc_with_tree();
+    let mut doc2 = fork(&mut doc1, 2);
+
+    doc1.move_object(&x, &b, "x").unwrap();
+    doc2.put(&x, "name", "changed").unwrap();
+
+    doc1.merge(&mut doc2).unwrap();
+    assert_eq!(
+        doc1.get(&x, "name").unwrap().unwrap().0.to_str(),
+        Some("changed")
+    );
+    assert_eq!(path(&doc1, &x), vec![Prop::from("b"), Prop::from("x")]);
+}
+
+#[test]
+fn moves_survive_save_and_load() {
+    let (mut doc, a, b, x) = doc_with_tree();
+    doc.move_object(&x, &b, "x").unwrap();
+    doc.move_object(&a, &x, "a").unwrap();
+    let expected = doc.hydrate(ROOT, None).unwrap();
+
+    let loaded = AutoCommit::load(&doc.save()).unwrap();
+    assert_eq!(loaded.hydrate(ROOT, None).unwrap(), expected);
+    assert_eq!(
+        path(&loaded, &a),
+        vec![Prop::from("b"), Prop::from("x"), Prop::from("a")]
+    );
+
+    let mut incremental = AutoCommit::new();
+    incremental.load_incremental(&doc.save()).unwrap();
+    assert_eq!(incremental.hydrate(ROOT, None).unwrap(), expected);
+}
+
+#[test]
+fn the_moved_object_is_stored_with_the_actors_of_the_change() {
+    let (mut doc, _, _, x) = doc_with_tree();
+    let mut other = fork(&mut doc, 2);
+    let c = other.put_object(ROOT, "c", ObjType::Map).unwrap();
+    other.move_object(&x, &c, "x").unwrap();
+
+    // the moved object is the only thing in the change made by actor 1
+    let change = other.get_last_local_change().unwrap();
+    assert_eq!(change.other_actor_ids(), &[ActorId::from([1])]);
+    let expanded = change.decode();
+    assert_eq!(automerge::Change::from(expanded).hash(), change.hash());
+
+    // an actor which sorts first shifts the index of every other actor in the document
+    let mut first = AutoCommit::new().with_actor(ActorId::from([0]));
+    first.put(ROOT, "first", true).unwrap();
+    merge_with_patches(&mut first, &mut doc);
+    merge_with_patches(&mut first, &mut other);
+    assert_eq!(path(&first, &x), vec![Prop::from("c"), Prop::from("x")]);
+
+    let loaded = AutoCommit::load(&first.save()).unwrap();
+    assert_eq!(path(&loaded, &x), vec![Prop::from("c"), Prop::from("x")]);
+    assert_eq!(
+        loaded.hydrate(ROOT, None).unwrap(),
+        first.hydrate(ROOT, None).unwrap()
+    );
+
+    let hashes = other.get_changes(&[]).into_iter().map(|c| c.hash());
+    let bundle = other.bundle(hashes).unwrap();
+    let mut from_bundle = AutoCommit::new();
+    from_bundle.load_incremental(bundle.bytes()).unwrap();
+    assert_eq!(
+        path(&from_bundle, &x),
+        vec![Prop::from("c"), Prop::from("x")]
+    );
+}
+
+#[test]
+fn moves_are_visible_at_historical_heads() {
+    let (mut doc, a, b, x) = doc_with_tree();
+    let before = doc.get_heads();
+    doc.move_object(&x, &b, "x").unwrap();
+    let after = doc.get_heads();
+    doc.move_object(&x, ROOT, "x").unwrap();
+
+    assert_eq!(doc.get_at(&a, "x", &before).unwrap().unwrap().1, x);
+    assert_eq!(doc.get_at(&b, "x", &before).unwrap(), None);
+    assert_eq!(doc.get_at(&b, "x", &after).unwrap().unwrap().1, x);
+    assert_eq!(doc.get_at(&a, "x", &after).unwrap(), None);
+    assert_eq!(doc.get(ROOT, "x").unwrap().unwrap().1, x);
+    assert_eq!(
+        doc.parents_at(&x, &before)
+            .unwrap()
+            .path()
+            .into_iter()
+            .map(|(_, prop)| prop)
+            .collect::<Vec<_>>(),
+        vec![Prop::from("a"), Prop::from("x")]
+    );
+    assert_eq!(
+        doc.parents_at(&x, &after)
+            .unwrap()
+            .path()
+            .into_iter()
+            .map(|(_, prop)| prop)
+            .collect::<Vec<_>>(),
+        vec![Prop::from("b"), Prop::from("x")]
+    );
+}
+
+#[test]
+fn local_moves_emit_detach_and_attach_patches() {
+    let (mut doc, _, b, x) = doc_with_tree();
+    doc.update_diff_cursor();
+    let mut hydrated = doc.hydrate(ROOT, None).unwrap();
+
+    doc.move_object(&x, &b, "y").unwrap();
+    let patches = doc.diff_incremental();
+    assert_eq!(
+        patches.iter().map(|p| &p.action).collect::<Vec<_>>(),
+        vec![
+            &PatchAction::Detach {
+                prop: Prop::from("x"),
+                moved: x.clone(),
+            },
+            &PatchAction::Attach {
+                prop: Prop::from("y"),
+                moved: x.clone(),
+                conflict: false,
+            },
+        ]
+    );
+
+    hydrated
+        .apply_patches(TextEncoding::UnicodeCodePoint, patches)
+        .unwrap();
+    assert_eq!(hydrated, doc.hydrate(ROOT, None).unwrap());
+}
+
+#[test]
+fn merged_moves_produce_patches_matching_the_document() {
+    let (mut doc1, a, b, x) = doc_with_tree();
+    let list = doc1.put_object(ROOT, "list", ObjType::List).unwrap();
+    doc1.insert(&list, 0, "item").unwrap();
+    let mut doc2 = fork(&mut doc1, 2);
+
+    doc1.move_object(&x, &b, "x").unwrap();
+    doc1.put(&x, "by", 1).unwrap();
+    doc2.move_object(&x, &list, 0).unwrap();
+    doc2.move_object(&a, &b, "a").unwrap();
+    doc2.put(&x, "by", 2).unwrap();
+
+    merge_with_patches(&mut doc1, &mut doc2);
+    merge_with_patches(&mut doc2, &mut doc1);
+    assert_eq!(
+        doc1.hydrate(ROOT, None).unwrap(),
+        doc2.hydrate(ROOT, None).unwrap()
+    );
+}
+
+#[test]
+fn diffs_across_moves_produce_matching_patches() {
+    let (mut doc, a, b, x) = doc_with_tree();
+    let start = doc.get_heads();
+    doc.move_object(&x, &b, "x").unwrap();
+    doc.move_object(&a, &x, "a").unwrap();
+    doc.put(&a, "inside", true).unwrap();
+    let middle = doc.get_heads();
+    doc.move_object(&x, ROOT, "x").unwrap();
+    doc.put(&b, "x", "gone").unwrap();
+
+    assert_diff_matches(&mut doc, &start);
+    assert_diff_matches(&mut doc, &middle);
+}
+
+#[test]
+fn merging_concurrent_cycles_produces_patches_matching_the_document() {
+    let (mut doc1, a, b, x) = doc_with_tree();
+    let items = doc1.put_object(&b, "items", ObjType::List).unwrap();
+    doc1.insert(&items, 0, "item").unwrap();
+    let mut doc2 = fork(&mut doc1, 2);
+
+    doc1.move_object(&a, &items, 1).unwrap();
+    doc1.put(&x, "by", 1).unwrap();
+    doc2.move_object(&b, &x, "b").unwrap();
+    doc2.insert(&items, 0, "first").unwrap();
+
+    let mut doc3 = doc2.fork();
+    merge_with_patches(&mut doc1, &mut doc2);
+    merge_with_patches(&mut doc3, &mut doc1);
+    assert_eq!(
+        doc1.hydrate(ROOT, None).unwrap(),
+        doc3.hydrate(ROOT, None).unwrap()
+    );
+}
@@ -1256,6 +1256,11 @@ where
         self.slabs.len()
     }
 
+    /// The number of bytes of encoded data held by the column
+    pub fn byte_len(&self) -> usize {
+        self.slabs.iter().map(|s| s.data.len()).sum()
+    }
+
     pub fn get(&self, index: usize) -> Option<T::Get<'_>> {
         self.iter().nth(index)
     }
@@ -2060,6 +2065,17 @@ mod tests {
         assert_eq!(v2.save(), base.save());
     }
 
+    #[test]
+    fn byte_len_counts_encoded_bytes() {
+        let empty: Column<u64> = Column::new();
+        assert_eq!(empty.byte_len(), 0);
+        let small: Column<u64> = Column::from_values(vec![42u64; 100]);
+        assert_eq!(small.byte_len(), small.save().len());
+        let large: Column<u64> = Column::from_values((0u64..5_000).collect());
+        assert!(large.slab_count() > 1);
+        assert!(large.byte_len() >= large.save().len());
+    }
+
     #[test]
     fn parity_u64_sequential() {
         parity_column((0u64..50).collect());
@@ -754,6 +754,11 @@ impl<T: DeltaValue, C: Codec> DeltaColumn<T, C> {
         self.col.slab_count()
     }
 
+    /// The number of bytes of encoded data held by the column
+    pub fn byte_len(&self) -> usize {
+        self.col.byte_len()
+    }
+
     pub fn save(&self) -> Vec<u8> {
         self.col.save()
     }
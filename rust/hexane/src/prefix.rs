@@ -479,6 +479,11 @@ impl<T: PrefixValue, C: Codec> PrefixColumn<T, C> {
         self.col.slab_count()
     }
 
+    /// The number of bytes of encoded data held by the column
+    pub fn byte_len(&self) -> usize {
+        self.col.byte_len()
+    }
+
     // ── Mutations ───────────────────────────────────────────────────────
 
     pub fn insert(&mut self, index: usize, value: impl crate::AsColumnRef<T>) {